@@ -0,0 +1,277 @@
+// Playback backends - what AudioPlayer drives to actually make sound
+// RodioBackend is the real one; NullBackend just keeps score, for tests and machines without audio
+
+use super::player::{PlaybackState, PlayerEvent};
+use super::Track;
+use anyhow::Result;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Plays one track at a time and reports what happens through `PlayerEvent`s
+///
+/// Implementations are expected to send the same events for the same calls: `TrackStarted`
+/// from `play`, `TrackStopped` from `stop`, `TrackFinished` once a track plays out, and so on.
+/// `PositionChanged` follows a seek or a pause and keeps coming while a track plays.
+pub trait PlaybackBackend {
+    fn set_event_sender(&mut self, sender: mpsc::UnboundedSender<PlayerEvent>);
+
+    /// Stop whatever is playing and start `track`
+    fn play(&self, track: Track) -> Result<()>;
+
+    /// Start `track` the moment the current one ends, without a gap
+    ///
+    /// Only one follow-up is held at a time; further calls are ignored until it starts.
+    fn queue_next(&self, track: Track) -> Result<()>;
+
+    /// Drop the follow-up from `queue_next`, so the current track is the last one to play
+    fn clear_queued_next(&self);
+
+    fn pause(&self) -> Result<()>;
+    fn resume(&self) -> Result<()>;
+    fn stop(&self) -> Result<()>;
+    fn seek(&self, position: Duration) -> Result<()>;
+
+    /// `volume` is already clamped to 0.0..=1.0
+    fn set_volume(&mut self, volume: f32) -> Result<()>;
+    fn volume(&self) -> f32;
+
+    /// Play at `level` of the volume without changing it, until `set_volume` or the next track
+    fn set_fade_level(&self, level: f32);
+
+    /// Position in the current track, `None` when nothing is loaded
+    ///
+    /// Track time rather than wall-clock time, the two drift apart at any speed but 1.0.
+    fn position(&self) -> Option<Duration>;
+
+    /// `speed` is already clamped to `MIN_SPEED..=MAX_SPEED` and carries over to later tracks
+    fn set_speed(&self, speed: f32);
+    fn speed(&self) -> f32;
+
+    /// True once the current track has played to its end (or nothing is loaded)
+    fn is_finished(&self) -> bool;
+
+    fn state(&self) -> PlaybackState;
+    fn current_track(&self) -> Option<Track>;
+
+    /// Fade tracks in on start and out on stop; on unless turned off, e.g. for spoken word
+    fn set_fades(&self, _enabled: bool) {}
+
+    /// Switch output device, `None` for the system default
+    fn set_output_device(&mut self, _name: Option<&str>) -> Result<()> {
+        Err(anyhow::anyhow!("This audio backend has no output devices"))
+    }
+}
+
+#[derive(Debug)]
+struct NullState {
+    state: PlaybackState,
+    current_track: Option<Track>,
+    queued_next: Option<Track>,
+    loaded: bool,
+    finished: bool,
+    position: Duration,
+    volume: f32,
+    fade_level: f32,
+    speed: f32,
+    sender: Option<mpsc::UnboundedSender<PlayerEvent>>,
+}
+
+impl NullState {
+    fn send(&self, event: PlayerEvent) {
+        if let Some(sender) = &self.sender {
+            let _ = sender.send(event);
+        }
+    }
+}
+
+/// A backend that plays nothing but behaves like one that does
+///
+/// Clones share state, so a test can keep one to drive playback (`advance`, `finish_track`)
+/// while `AudioPlayer` owns the other.
+#[derive(Debug, Clone)]
+pub struct NullBackend {
+    inner: Arc<Mutex<NullState>>,
+}
+
+impl NullBackend {
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(NullState {
+                state: PlaybackState::Stopped,
+                current_track: None,
+                queued_next: None,
+                loaded: false,
+                finished: false,
+                position: Duration::ZERO,
+                volume: 1.0,
+                fade_level: 1.0,
+                speed: 1.0,
+                sender: None,
+            })),
+        }
+    }
+
+    /// Let `by` of wall-clock time pass, moving the playing track on at the current speed
+    ///
+    /// Reports the new position once, standing in for the periodic reports of a real backend.
+    pub fn advance(&self, by: Duration) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.state == PlaybackState::Playing {
+            let heard = by.mul_f32(inner.speed);
+            inner.position += heard;
+            inner.send(PlayerEvent::PositionChanged(inner.position));
+        }
+    }
+
+    /// Reach the end of the current track, starting the queued follow-up if there is one
+    pub fn finish_track(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        if !inner.loaded || inner.finished {
+            return;
+        }
+        if let Some(track) = inner.current_track.clone() {
+            inner.send(PlayerEvent::TrackFinished(track));
+        }
+
+        match inner.queued_next.take() {
+            Some(next) => {
+                inner.current_track = Some(next.clone());
+                inner.position = Duration::ZERO;
+                inner.send(PlayerEvent::TrackStarted(next));
+            }
+            None => {
+                inner.finished = true;
+                inner.state = PlaybackState::Stopped;
+            }
+        }
+    }
+
+    pub fn fade_level(&self) -> f32 {
+        self.inner.lock().unwrap().fade_level
+    }
+
+    pub fn queued_next(&self) -> Option<Track> {
+        self.inner.lock().unwrap().queued_next.clone()
+    }
+}
+
+impl Default for NullBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PlaybackBackend for NullBackend {
+    fn set_event_sender(&mut self, sender: mpsc::UnboundedSender<PlayerEvent>) {
+        self.inner.lock().unwrap().sender = Some(sender);
+    }
+
+    fn play(&self, track: Track) -> Result<()> {
+        self.stop()?;
+
+        let mut inner = self.inner.lock().unwrap();
+        inner.current_track = Some(track.clone());
+        inner.loaded = true;
+        inner.finished = false;
+        inner.position = Duration::ZERO;
+        inner.fade_level = 1.0;
+        inner.state = PlaybackState::Playing;
+        inner.send(PlayerEvent::TrackStarted(track));
+        Ok(())
+    }
+
+    fn queue_next(&self, track: Track) -> Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.loaded && !inner.finished && inner.queued_next.is_none() {
+            inner.queued_next = Some(track);
+        }
+        Ok(())
+    }
+
+    fn clear_queued_next(&self) {
+        self.inner.lock().unwrap().queued_next = None;
+    }
+
+    fn pause(&self) -> Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.loaded && inner.state == PlaybackState::Playing {
+            inner.state = PlaybackState::Paused;
+            inner.send(PlayerEvent::TrackPaused);
+            inner.send(PlayerEvent::PositionChanged(inner.position));
+        }
+        Ok(())
+    }
+
+    fn resume(&self) -> Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.loaded && inner.state == PlaybackState::Paused {
+            inner.state = PlaybackState::Playing;
+            inner.send(PlayerEvent::TrackResumed);
+        }
+        Ok(())
+    }
+
+    fn stop(&self) -> Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.queued_next = None;
+        inner.loaded = false;
+        inner.state = PlaybackState::Stopped;
+        inner.send(PlayerEvent::TrackStopped);
+        Ok(())
+    }
+
+    fn seek(&self, position: Duration) -> Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.loaded {
+            inner.position = position;
+            inner.send(PlayerEvent::PositionChanged(position));
+        }
+        Ok(())
+    }
+
+    fn set_volume(&mut self, volume: f32) -> Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.volume = volume;
+        inner.fade_level = 1.0;
+        inner.send(PlayerEvent::VolumeChanged(volume));
+        Ok(())
+    }
+
+    fn volume(&self) -> f32 {
+        self.inner.lock().unwrap().volume
+    }
+
+    fn set_fade_level(&self, level: f32) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.loaded {
+            inner.fade_level = level.clamp(0.0, 1.0);
+        }
+    }
+
+    fn position(&self) -> Option<Duration> {
+        let inner = self.inner.lock().unwrap();
+        inner.loaded.then_some(inner.position)
+    }
+
+    fn set_speed(&self, speed: f32) {
+        self.inner.lock().unwrap().speed = speed;
+    }
+
+    fn speed(&self) -> f32 {
+        self.inner.lock().unwrap().speed
+    }
+
+    fn is_finished(&self) -> bool {
+        let inner = self.inner.lock().unwrap();
+        !inner.loaded || inner.finished
+    }
+
+    fn state(&self) -> PlaybackState {
+        self.inner.lock().unwrap().state.clone()
+    }
+
+    fn current_track(&self) -> Option<Track> {
+        self.inner.lock().unwrap().current_track.clone()
+    }
+}
@@ -0,0 +1,262 @@
+// Channel mix - stereo as is, folded down to mono, or with crossfeed for headphones
+// Sits right after the decoder, so the equalizer and visualizer see what actually reaches the ears
+
+#[cfg(feature = "audio")]
+use rodio::{source::SeekError, Source};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "audio")]
+use std::f32::consts::{FRAC_1_SQRT_2, PI};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+#[cfg(feature = "audio")]
+use std::time::Duration;
+
+#[cfg(feature = "audio")]
+const CROSSFEED_DELAY: Duration = Duration::from_micros(300); // roughly the extra path around the head
+#[cfg(feature = "audio")]
+const CROSSFEED_CUTOFF: f32 = 700.0; // Hz; the head shadows the highs from the far speaker
+#[cfg(feature = "audio")]
+const CROSSFEED_LEVEL: f32 = 0.5; // -6dB of the opposite channel
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChannelMode {
+    #[default]
+    Stereo,
+    Mono,      // both channels carry L+R at -3dB
+    Crossfeed, // each channel gets a delayed, darker, quieter copy of the other
+}
+
+impl ChannelMode {
+    /// Modes in the order the Settings tab cycles through them
+    pub const ALL: [ChannelMode; 3] = [ChannelMode::Stereo, ChannelMode::Mono, ChannelMode::Crossfeed];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChannelMode::Stereo => "Stereo",
+            ChannelMode::Mono => "Mono",
+            ChannelMode::Crossfeed => "Crossfeed",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        let idx = Self::ALL.iter().position(|m| *m == self).unwrap_or(0);
+        Self::ALL[(idx + 1) % Self::ALL.len()]
+    }
+
+    fn from_index(idx: u8) -> Self {
+        Self::ALL.get(idx as usize).copied().unwrap_or_default()
+    }
+}
+
+/// Shared channel mode; every source made by `apply` switches on its next frame
+#[derive(Debug, Clone, Default)]
+pub struct ChannelMixer {
+    mode: Arc<AtomicU8>, // index into `ChannelMode::ALL`
+}
+
+impl ChannelMixer {
+    pub fn new(mode: ChannelMode) -> Self {
+        let mixer = Self::default();
+        mixer.set_mode(mode);
+        mixer
+    }
+
+    pub fn mode(&self) -> ChannelMode {
+        ChannelMode::from_index(self.mode.load(Ordering::Relaxed))
+    }
+
+    pub fn set_mode(&self, mode: ChannelMode) {
+        let idx = ChannelMode::ALL.iter().position(|m| *m == mode).unwrap_or(0);
+        self.mode.store(idx as u8, Ordering::Relaxed);
+    }
+
+    /// Run `input` through the channel mix; anything but stereo passes through untouched
+    #[cfg(feature = "audio")]
+    pub fn apply<S: Source<Item = f32>>(&self, input: S) -> ChannelMixSource<S> {
+        ChannelMixSource {
+            input,
+            mixer: self.clone(),
+            pending: None,
+            mid_frame: false,
+            crossfeed: None,
+        }
+    }
+}
+
+/// A `Source` remixed by the shared channel mode
+#[cfg(feature = "audio")]
+pub struct ChannelMixSource<S> {
+    input: S,
+    mixer: ChannelMixer,
+    pending: Option<f32>,        // right sample of the frame whose left one was just returned
+    mid_frame: bool,             // a stereo frame is being passed through, its right sample is next
+    crossfeed: Option<Crossfeed>, // `None` unless crossfeeding, rebuilt when the sample rate changes
+}
+
+#[cfg(feature = "audio")]
+impl<S: Source<Item = f32>> Iterator for ChannelMixSource<S> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if let Some(right) = self.pending.take() {
+            return Some(right);
+        }
+
+        let left = self.input.next()?;
+        if self.mid_frame {
+            self.mid_frame = false;
+            return Some(left);
+        }
+        let mode = self.mixer.mode();
+        if mode == ChannelMode::Stereo || self.input.channels() != 2 {
+            self.mid_frame = self.input.channels() == 2;
+            self.crossfeed = None;
+            return Some(left);
+        }
+        let Some(right) = self.input.next() else {
+            return Some(left);
+        };
+
+        let (left, right) = match mode {
+            ChannelMode::Mono => {
+                self.crossfeed = None;
+                let mono = (left + right) * FRAC_1_SQRT_2;
+                (mono, mono)
+            }
+            _ => {
+                let sample_rate = self.input.sample_rate();
+                let crossfeed = match &mut self.crossfeed {
+                    Some(crossfeed) if crossfeed.sample_rate == sample_rate => crossfeed,
+                    slot => slot.insert(Crossfeed::new(sample_rate)),
+                };
+                crossfeed.process(left, right)
+            }
+        };
+        self.pending = Some(right);
+        Some(left)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+#[cfg(feature = "audio")]
+impl<S: Source<Item = f32>> Source for ChannelMixSource<S> {
+    fn current_frame_len(&self) -> Option<usize> {
+        self.input.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)?;
+
+        // The delay line holds audio we jumped away from
+        self.pending = None;
+        self.mid_frame = false;
+        self.crossfeed = None;
+        Ok(())
+    }
+}
+
+/// Delay lines and one-pole lowpasses feeding each channel into the other
+#[cfg(feature = "audio")]
+struct Crossfeed {
+    sample_rate: u32,
+    delayed: Vec<(f32, f32)>, // ring of filtered (left, right) frames
+    next: usize,
+    lowpass: (f32, f32),
+    smoothing: f32, // one-pole coefficient for CROSSFEED_CUTOFF
+}
+
+#[cfg(feature = "audio")]
+impl Crossfeed {
+    fn new(sample_rate: u32) -> Self {
+        let delay = (CROSSFEED_DELAY.as_secs_f32() * sample_rate as f32).round().max(1.0) as usize;
+        Self {
+            sample_rate,
+            delayed: vec![(0.0, 0.0); delay],
+            next: 0,
+            lowpass: (0.0, 0.0),
+            smoothing: 1.0 - (-2.0 * PI * CROSSFEED_CUTOFF / sample_rate.max(1) as f32).exp(),
+        }
+    }
+
+    fn process(&mut self, left: f32, right: f32) -> (f32, f32) {
+        self.lowpass.0 += self.smoothing * (left - self.lowpass.0);
+        self.lowpass.1 += self.smoothing * (right - self.lowpass.1);
+        let (far_left, far_right) = std::mem::replace(&mut self.delayed[self.next], self.lowpass);
+        self.next = (self.next + 1) % self.delayed.len();
+
+        // Scaled so a full-scale signal in both channels can't clip
+        let gain = 1.0 / (1.0 + CROSSFEED_LEVEL);
+        ((left + CROSSFEED_LEVEL * far_right) * gain, (right + CROSSFEED_LEVEL * far_left) * gain)
+    }
+}
+
+#[cfg(all(test, feature = "audio"))]
+mod tests {
+    use super::*;
+    use rodio::buffer::SamplesBuffer;
+
+    /// A 200Hz tone in the left channel only
+    fn left_only(sample_rate: u32, frames: usize) -> Vec<f32> {
+        (0..frames)
+            .flat_map(|n| [0.5 * (2.0 * PI * 200.0 * n as f32 / sample_rate as f32).sin(), 0.0])
+            .collect()
+    }
+
+    fn peaks(samples: &[f32]) -> (f32, f32) {
+        samples.chunks(2).fold((0.0f32, 0.0f32), |(l, r), frame| (l.max(frame[0].abs()), r.max(frame[1].abs())))
+    }
+
+    #[test]
+    fn test_modes() {
+        let input = left_only(44_100, 4_410);
+        let mixer = ChannelMixer::new(ChannelMode::Stereo);
+        let output: Vec<f32> = mixer.apply(SamplesBuffer::new(2, 44_100, input.clone())).collect();
+        assert_eq!(output, input);
+
+        mixer.set_mode(ChannelMode::Mono);
+        let output: Vec<f32> = mixer.apply(SamplesBuffer::new(2, 44_100, input.clone())).collect();
+        assert!(output.chunks(2).all(|frame| frame[0] == frame[1]));
+        assert!((peaks(&output).0 - 0.5 * FRAC_1_SQRT_2).abs() < 0.01);
+
+        mixer.set_mode(ChannelMode::Crossfeed);
+        let output: Vec<f32> = mixer.apply(SamplesBuffer::new(2, 44_100, input.clone())).collect();
+        assert_eq!(output.len(), input.len());
+        let (left, right) = peaks(&output);
+        assert!(right > 0.1 && right < left * 0.6, "left {} right {}", left, right);
+        assert_eq!(output[1], 0.0); // the opposite channel arrives late
+
+        // Mono sources have nothing to mix
+        let mono: Vec<f32> = mixer.apply(SamplesBuffer::new(1, 44_100, vec![0.1, 0.2, 0.3])).collect();
+        assert_eq!(mono, vec![0.1, 0.2, 0.3]);
+    }
+
+    #[test]
+    fn test_mode_changes_apply_mid_stream() {
+        let mixer = ChannelMixer::new(ChannelMode::Stereo);
+        let mut source = mixer.apply(SamplesBuffer::new(2, 44_100, left_only(44_100, 2_000)));
+
+        let before: Vec<f32> = source.by_ref().take(1_001).collect(); // ends on a left sample
+        mixer.set_mode(ChannelMode::Mono);
+        let after: Vec<f32> = source.collect();
+
+        assert_eq!(peaks(&before[..1_000]).1, 0.0);
+        assert_eq!(after[0], 0.0); // the rest of the frame already under way stays stereo
+        assert!(after[1..].chunks(2).all(|frame| frame[0] == frame[1]));
+    }
+}
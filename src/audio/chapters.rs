@@ -0,0 +1,255 @@
+// Chapter markers - MP4 Nero `chpl` boxes and Ogg CHAPTERxxx comments
+// Only headers are read: MP4 boxes are walked by seeking past the audio, Ogg stops after the comment packet
+
+use anyhow::Result;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::time::Duration;
+
+/// Boxes bigger than this are skipped rather than read into memory
+const MAX_CHPL_BOX: u64 = 1 << 20;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chapter {
+    pub start: Duration,
+    pub title: String,
+}
+
+/// Chapters in `path`, earliest first; empty for formats without chapter support
+pub fn read_chapters(path: &Path) -> Result<Vec<Chapter>> {
+    let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or("").to_lowercase();
+    let reader = || -> Result<BufReader<File>> { Ok(BufReader::new(File::open(path)?)) };
+    let mut chapters = match extension.as_str() {
+        "mp4" | "m4a" | "m4b" => read_mp4_chapters(reader()?)?,
+        "ogg" | "oga" | "opus" => read_ogg_chapters(reader()?)?,
+        _ => Vec::new(),
+    };
+    chapters.sort_by_key(|chapter| chapter.start);
+    Ok(chapters)
+}
+
+/// The chapter playing at `position`, as an index into `chapters`
+pub fn chapter_at(chapters: &[Chapter], position: Duration) -> Option<usize> {
+    chapters.iter().rposition(|chapter| chapter.start <= position)
+}
+
+/// Chapters from the `moov/udta/chpl` box, whose starts are counted in 100ns units
+pub fn read_mp4_chapters<R: Read + Seek>(mut reader: R) -> Result<Vec<Chapter>> {
+    let file_len = reader.seek(SeekFrom::End(0))?;
+    let mut range = (0, file_len);
+    for name in [b"moov", b"udta", b"chpl"] {
+        match find_box(&mut reader, range, name)? {
+            Some(found) => range = found,
+            None => return Ok(Vec::new()),
+        }
+    }
+
+    let (start, end) = range;
+    if end - start > MAX_CHPL_BOX {
+        return Ok(Vec::new());
+    }
+    let mut body = vec![0u8; (end - start) as usize];
+    reader.seek(SeekFrom::Start(start))?;
+    reader.read_exact(&mut body)?;
+
+    // Version and flags, four reserved bytes from version 1 on, then a one-byte count
+    let mut pos = if body.first().is_some_and(|&version| version > 0) { 8 } else { 4 };
+    let count = *body.get(pos).ok_or_else(|| anyhow::anyhow!("Truncated chpl box"))?;
+    pos += 1;
+
+    let mut chapters = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let Some(header) = body.get(pos..pos + 9) else {
+            break;
+        };
+        let start = u64::from_be_bytes(header[..8].try_into()?);
+        let title_len = header[8] as usize;
+        let Some(title) = body.get(pos + 9..pos + 9 + title_len) else {
+            break;
+        };
+        chapters.push(Chapter {
+            start: Duration::from_nanos(start.saturating_mul(100)),
+            title: String::from_utf8_lossy(title).trim().to_string(),
+        });
+        pos += 9 + title_len;
+    }
+    Ok(chapters)
+}
+
+/// The body of the first `name` box between `start` and `end`, as a byte range
+fn find_box<R: Read + Seek>(reader: &mut R, (mut start, end): (u64, u64), name: &[u8; 4]) -> Result<Option<(u64, u64)>> {
+    while start + 8 <= end {
+        reader.seek(SeekFrom::Start(start))?;
+        let mut header = [0u8; 8];
+        reader.read_exact(&mut header)?;
+        let mut header_len = 8;
+        let size = match u32::from_be_bytes(header[..4].try_into()?) {
+            0 => end - start, // runs to the end of its parent
+            1 => {
+                let mut large = [0u8; 8];
+                reader.read_exact(&mut large)?;
+                header_len = 16;
+                u64::from_be_bytes(large)
+            }
+            size => size as u64,
+        };
+        // A 64-bit size can claim more than the file, or more than fits in a u64 at all
+        let box_end = match start.checked_add(size) {
+            Some(box_end) if size >= header_len && box_end <= end => box_end,
+            _ => return Ok(None),
+        };
+        if &header[4..8] == name {
+            return Ok(Some((start + header_len, box_end)));
+        }
+        start = box_end;
+    }
+    Ok(None)
+}
+
+/// Chapters from the Vorbis or Opus comment header of an Ogg file
+pub fn read_ogg_chapters<R: Read + Seek>(reader: R) -> Result<Vec<Chapter>> {
+    let mut packets = ogg::PacketReader::new(reader);
+    // The identification header comes first, the comments straight after it
+    let (Some(_), Some(comments)) = (packets.read_packet()?, packets.read_packet()?) else {
+        return Ok(Vec::new());
+    };
+    let data = &comments.data;
+    let fields = if let Some(rest) = data.strip_prefix(b"\x03vorbis") {
+        rest
+    } else if let Some(rest) = data.strip_prefix(b"OpusTags") {
+        rest
+    } else {
+        return Ok(Vec::new());
+    };
+    Ok(chapters_from_comments(&parse_comments(fields)))
+}
+
+/// `KEY=value` pairs after the vendor string, keys upper-cased
+fn parse_comments(data: &[u8]) -> Vec<(String, String)> {
+    let mut comments = Vec::new();
+    let read_u32 = |pos: usize| data.get(pos..pos + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize);
+    let Some(vendor_len) = read_u32(0) else {
+        return comments;
+    };
+    let mut pos = 4 + vendor_len;
+    let Some(count) = read_u32(pos) else {
+        return comments;
+    };
+    pos += 4;
+    for _ in 0..count {
+        let Some(len) = read_u32(pos) else {
+            break;
+        };
+        let Some(comment) = data.get(pos + 4..pos + 4 + len) else {
+            break;
+        };
+        if let Some((key, value)) = String::from_utf8_lossy(comment).split_once('=') {
+            comments.push((key.to_uppercase(), value.to_string()));
+        }
+        pos += 4 + len;
+    }
+    comments
+}
+
+/// `CHAPTER001=00:01:02.500` starts with their `CHAPTER001NAME` titles
+fn chapters_from_comments(comments: &[(String, String)]) -> Vec<Chapter> {
+    comments
+        .iter()
+        .filter(|(key, _)| key.strip_prefix("CHAPTER").is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit())))
+        .filter_map(|(key, value)| {
+            let start = parse_timestamp(value)?;
+            let name_key = format!("{}NAME", key);
+            let title = comments.iter().find(|(k, _)| *k == name_key).map(|(_, v)| v.trim().to_string());
+            Some(Chapter { start, title: title.unwrap_or_else(|| format!("Chapter {}", key.trim_start_matches("CHAPTER").trim_start_matches('0'))) })
+        })
+        .collect()
+}
+
+/// "HH:MM:SS.mmm", where the hours and fraction may be missing
+fn parse_timestamp(text: &str) -> Option<Duration> {
+    let (clock, fraction) = text.trim().split_once('.').unwrap_or((text.trim(), ""));
+    let mut seconds = 0u64;
+    for part in clock.split(':') {
+        seconds = seconds.checked_mul(60)?.checked_add(part.parse().ok()?)?;
+    }
+    let fraction = if fraction.is_empty() { 0.0 } else { format!("0.{}", fraction).parse::<f64>().ok()? };
+    Some(Duration::from_secs(seconds) + Duration::from_secs_f64(fraction))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn mp4_box(name: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut bytes = ((body.len() + 8) as u32).to_be_bytes().to_vec();
+        bytes.extend(name);
+        bytes.extend(body);
+        bytes
+    }
+
+    #[test]
+    fn test_mp4_chpl_chapters() {
+        let mut chpl = vec![1, 0, 0, 0, 0, 0, 0, 0, 2];
+        for (start_100ns, title) in [(0u64, "Opening Credits"), (754_000_000, "Chapter 1")] {
+            chpl.extend(start_100ns.to_be_bytes());
+            chpl.push(title.len() as u8);
+            chpl.extend(title.as_bytes());
+        }
+        let mut file = mp4_box(b"ftyp", b"M4B \0\0\0\0");
+        file.extend(mp4_box(b"mdat", &[0u8; 64]));
+        file.extend(mp4_box(b"moov", &[mp4_box(b"mvhd", &[0u8; 16]), mp4_box(b"udta", &mp4_box(b"chpl", &chpl))].concat()));
+
+        let chapters = read_mp4_chapters(Cursor::new(file)).unwrap();
+        assert_eq!(chapters, vec![
+            Chapter { start: Duration::ZERO, title: "Opening Credits".to_string() },
+            Chapter { start: Duration::from_millis(75_400), title: "Chapter 1".to_string() },
+        ]);
+        assert_eq!(chapter_at(&chapters, Duration::from_secs(80)), Some(1));
+        assert_eq!(chapter_at(&chapters, Duration::from_secs(10)), Some(0));
+
+        // No udta at all
+        let bare = [mp4_box(b"ftyp", b"M4A \0\0\0\0"), mp4_box(b"moov", &mp4_box(b"mvhd", &[0u8; 16]))].concat();
+        assert!(read_mp4_chapters(Cursor::new(bare)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_mp4_oversized_box() {
+        // Extended-size boxes claiming more than the file holds, right up to wrapping the offset
+        for size in [u64::MAX, u64::MAX - 8, 1 << 40] {
+            let mut file = mp4_box(b"ftyp", b"M4B \0\0\0\0");
+            file.extend(1u32.to_be_bytes());
+            file.extend(b"mdat");
+            file.extend(size.to_be_bytes());
+            file.extend([0u8; 32]);
+            file.extend(mp4_box(b"moov", &mp4_box(b"udta", &mp4_box(b"chpl", &[1, 0, 0, 0, 0, 0, 0, 0, 0]))));
+
+            assert!(read_mp4_chapters(Cursor::new(file)).unwrap().is_empty());
+        }
+    }
+
+    #[test]
+    fn test_ogg_comment_chapters() {
+        let mut comments = b"\x03vorbis".to_vec();
+        comments.extend(4u32.to_le_bytes());
+        comments.extend(b"test");
+        let fields = ["TITLE=Book", "CHAPTER001=00:00:00.000", "CHAPTER001NAME=Prologue", "CHAPTER002=01:02:03.5", "chapter002name=Part Two"];
+        comments.extend((fields.len() as u32).to_le_bytes());
+        for field in fields {
+            comments.extend((field.len() as u32).to_le_bytes());
+            comments.extend(field.as_bytes());
+        }
+
+        let mut bytes = Vec::new();
+        let mut writer = ogg::PacketWriter::new(&mut bytes);
+        writer.write_packet(b"\x01vorbis-identification".to_vec().into_boxed_slice(), 7, ogg::PacketWriteEndInfo::EndPage, 0).unwrap();
+        writer.write_packet(comments.into_boxed_slice(), 7, ogg::PacketWriteEndInfo::EndStream, 0).unwrap();
+
+        let chapters = read_ogg_chapters(Cursor::new(bytes)).unwrap();
+        assert_eq!(chapters, vec![
+            Chapter { start: Duration::ZERO, title: "Prologue".to_string() },
+            Chapter { start: Duration::from_millis(3_723_500), title: "Part Two".to_string() },
+        ]);
+    }
+}
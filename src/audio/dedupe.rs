@@ -0,0 +1,101 @@
+// Duplicate cleanup - keep one copy from a duplicate group and hide or delete the others
+// Groups come from the scan report; a dry run only describes what would happen
+
+use super::scan_report::DuplicateGroup;
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateAction {
+    Hide,   // drop the extra copies from the library, the files stay on disk
+    Delete, // remove the extra copies from disk
+}
+
+/// What to do with one duplicate group
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateResolution {
+    pub keep: PathBuf,
+    pub remove: Vec<PathBuf>,
+    pub action: DuplicateAction,
+}
+
+/// What `DuplicateResolution::apply` managed to do
+#[derive(Debug, Clone, Default)]
+pub struct DuplicateOutcome {
+    pub removed: Vec<PathBuf>,          // gone from the library (and from disk when deleting)
+    pub failed: Vec<(PathBuf, String)>, // files that couldn't be deleted, still in the library
+}
+
+impl DuplicateResolution {
+    /// Keep `group.paths[keep]` and act on every other copy
+    pub fn new(group: &DuplicateGroup, keep: usize, action: DuplicateAction) -> Self {
+        let keep = keep.min(group.paths.len().saturating_sub(1));
+        Self {
+            keep: group.paths[keep].clone(),
+            remove: group.paths.iter()
+                .enumerate()
+                .filter(|(idx, _)| *idx != keep)
+                .map(|(_, path)| path.clone())
+                .collect(),
+            action,
+        }
+    }
+
+    /// One line for the status bar, used for dry runs and confirmation prompts
+    pub fn describe(&self) -> String {
+        let verb = match self.action {
+            DuplicateAction::Hide => "hide",
+            DuplicateAction::Delete => "delete",
+        };
+        let copies = if self.remove.len() == 1 { "copy" } else { "copies" };
+        let keep = self.keep.file_name().unwrap_or(self.keep.as_os_str()).to_string_lossy();
+        format!("Keep {} and {} {} other {}", keep, verb, self.remove.len(), copies)
+    }
+
+    /// Hide or delete the extra copies; a failed delete leaves that copy in the library
+    pub fn apply(&self) -> DuplicateOutcome {
+        let mut outcome = DuplicateOutcome::default();
+        for path in &self.remove {
+            if self.action == DuplicateAction::Delete {
+                if let Err(e) = fs::remove_file(path) {
+                    outcome.failed.push((path.clone(), e.to_string()));
+                    continue;
+                }
+            }
+            outcome.removed.push(path.clone());
+        }
+        outcome
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn group(paths: &[PathBuf]) -> DuplicateGroup {
+        DuplicateGroup { content_hash: 7, paths: paths.to_vec() }
+    }
+
+    #[test]
+    fn test_hide_keeps_files_and_delete_removes_them() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths: Vec<PathBuf> = ["a.mp3", "b.mp3", "c.mp3"].iter().map(|name| dir.path().join(name)).collect();
+        for path in &paths {
+            fs::write(path, "same audio").unwrap();
+        }
+
+        let hide = DuplicateResolution::new(&group(&paths), 1, DuplicateAction::Hide);
+        assert_eq!(hide.keep, paths[1]);
+        assert_eq!(hide.describe(), "Keep b.mp3 and hide 2 other copies");
+        assert_eq!(hide.apply().removed, vec![paths[0].clone(), paths[2].clone()]);
+        assert!(paths.iter().all(|path| path.exists()));
+
+        fs::remove_file(&paths[2]).unwrap(); // already gone, so deleting it fails
+        let delete = DuplicateResolution::new(&group(&paths), 1, DuplicateAction::Delete);
+        let outcome = delete.apply();
+        assert_eq!(outcome.removed, vec![paths[0].clone()]);
+        assert_eq!(outcome.failed.len(), 1);
+        assert!(!paths[0].exists());
+        assert!(paths[1].exists());
+    }
+}
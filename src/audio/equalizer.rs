@@ -0,0 +1,338 @@
+// Equalizer - ten peaking filters sitting between the decoder and the sink
+// Gains live behind a shared handle so the Settings tab can retune a track while it plays
+
+#[cfg(feature = "audio")]
+use rodio::{source::SeekError, Source};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "audio")]
+use std::f32::consts::PI;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "audio")]
+use std::time::Duration;
+
+pub const BAND_COUNT: usize = 10;
+
+/// Centre frequency of each band in Hz, lowest first
+pub const BAND_FREQUENCIES: [f32; BAND_COUNT] = [
+    31.0, 62.0, 125.0, 250.0, 500.0, 1_000.0, 2_000.0, 4_000.0, 8_000.0, 16_000.0,
+];
+
+/// Gains are limited to +/- this many dB
+pub const MAX_GAIN_DB: f32 = 12.0;
+
+#[cfg(feature = "audio")]
+const BAND_Q: f32 = 1.41; // about one octave wide, so neighbouring bands overlap smoothly
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EqPreset {
+    #[default]
+    Flat,
+    BassBoost,
+    Vocal,
+    Treble,
+    Custom, // the user's own per-band gains
+}
+
+impl EqPreset {
+    /// Presets in the order the Settings tab cycles through them
+    pub const ALL: [EqPreset; 5] = [
+        EqPreset::Flat,
+        EqPreset::BassBoost,
+        EqPreset::Vocal,
+        EqPreset::Treble,
+        EqPreset::Custom,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            EqPreset::Flat => "Flat",
+            EqPreset::BassBoost => "Bass Boost",
+            EqPreset::Vocal => "Vocal",
+            EqPreset::Treble => "Treble",
+            EqPreset::Custom => "Custom",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        let idx = Self::ALL.iter().position(|p| *p == self).unwrap_or(0);
+        Self::ALL[(idx + 1) % Self::ALL.len()]
+    }
+
+    /// Band gains in dB, `None` for Custom since those come from the config
+    pub fn gains(&self) -> Option<[f32; BAND_COUNT]> {
+        match self {
+            EqPreset::Flat => Some([0.0; BAND_COUNT]),
+            EqPreset::BassBoost => Some([6.0, 5.0, 4.0, 2.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]),
+            EqPreset::Vocal => Some([-2.0, -2.0, -1.0, 0.0, 2.0, 4.0, 4.0, 2.0, 0.0, -1.0]),
+            EqPreset::Treble => Some([0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 2.0, 4.0, 5.0, 6.0]),
+            EqPreset::Custom => None,
+        }
+    }
+}
+
+/// Shared band gains; every source made by `apply` picks up changes on its next frame
+#[derive(Debug, Clone, Default)]
+pub struct Equalizer {
+    gains: Arc<Mutex<[f32; BAND_COUNT]>>,
+    version: Arc<AtomicU64>, // bumped on every change so sources know to recompute
+}
+
+impl Equalizer {
+    pub fn new(gains: [f32; BAND_COUNT]) -> Self {
+        let equalizer = Self::default();
+        equalizer.set_gains(gains);
+        equalizer
+    }
+
+    pub fn gains(&self) -> [f32; BAND_COUNT] {
+        *self.gains.lock().unwrap()
+    }
+
+    pub fn set_gains(&self, gains: [f32; BAND_COUNT]) {
+        *self.gains.lock().unwrap() = gains.map(clamp_gain);
+        self.version.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Run `input` through the equalizer
+    #[cfg(feature = "audio")]
+    pub fn apply<S: Source<Item = f32>>(&self, input: S) -> EqualizerSource<S> {
+        EqualizerSource {
+            input,
+            equalizer: self.clone(),
+            version: None,
+            sample_rate: 0,
+            channels: 0,
+            filters: Vec::new(),
+            channel: 0,
+        }
+    }
+}
+
+/// Keep a hand-edited gain inside +/- `MAX_GAIN_DB`
+pub fn clamp_gain(gain: f32) -> f32 {
+    if gain.is_finite() { gain.clamp(-MAX_GAIN_DB, MAX_GAIN_DB) } else { 0.0 }
+}
+
+/// Short band name for display, e.g. "62Hz" or "16kHz"
+pub fn band_label(freq: f32) -> String {
+    if freq >= 1_000.0 {
+        format!("{}kHz", freq / 1_000.0)
+    } else {
+        format!("{}Hz", freq)
+    }
+}
+
+/// A `Source` filtered by the shared equalizer gains
+#[cfg(feature = "audio")]
+pub struct EqualizerSource<S> {
+    input: S,
+    equalizer: Equalizer,
+    version: Option<u64>, // gains the filters were built from, `None` before the first sample
+    sample_rate: u32,
+    channels: u16,
+    filters: Vec<[Biquad; BAND_COUNT]>, // one bank per channel, empty while the gains are flat
+    channel: usize,                     // channel the next sample belongs to
+}
+
+#[cfg(feature = "audio")]
+impl<S: Source<Item = f32>> EqualizerSource<S> {
+    /// Recompute coefficients if the gains or the stream format changed
+    ///
+    /// Only called on frame boundaries; filter state is kept so retuning doesn't click.
+    fn refresh(&mut self) {
+        let version = self.equalizer.version.load(Ordering::SeqCst);
+        let sample_rate = self.input.sample_rate();
+        let channels = self.input.channels().max(1);
+        if self.version == Some(version) && self.sample_rate == sample_rate && self.channels == channels {
+            return;
+        }
+
+        self.version = Some(version);
+        self.sample_rate = sample_rate;
+        self.channels = channels;
+
+        let gains = self.equalizer.gains();
+        if gains.iter().all(|gain| *gain == 0.0) {
+            self.filters.clear();
+            return;
+        }
+
+        self.filters.resize(channels as usize, [Biquad::default(); BAND_COUNT]);
+        for bank in &mut self.filters {
+            for (band, filter) in bank.iter_mut().enumerate() {
+                filter.set_peaking(BAND_FREQUENCIES[band], gains[band], sample_rate);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "audio")]
+impl<S: Source<Item = f32>> Iterator for EqualizerSource<S> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let sample = self.input.next()?;
+
+        if self.channel == 0 {
+            self.refresh();
+        }
+        let channel = self.channel;
+        self.channel = (self.channel + 1) % self.channels as usize;
+
+        match self.filters.get_mut(channel) {
+            Some(bank) => Some(bank.iter_mut().fold(sample, |x, filter| filter.process(x))),
+            None => Some(sample),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+#[cfg(feature = "audio")]
+impl<S: Source<Item = f32>> Source for EqualizerSource<S> {
+    fn current_frame_len(&self) -> Option<usize> {
+        self.input.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)?;
+
+        // Old filter history belongs to audio we jumped away from
+        for filter in self.filters.iter_mut().flatten() {
+            filter.reset();
+        }
+        self.channel = 0;
+        Ok(())
+    }
+}
+
+/// Peaking filter from the RBJ Audio EQ Cookbook, transposed direct form II
+#[cfg(feature = "audio")]
+#[derive(Debug, Clone, Copy)]
+struct Biquad {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+    z1: f32,
+    z2: f32,
+}
+
+#[cfg(feature = "audio")]
+impl Default for Biquad {
+    // Passes samples through untouched
+    fn default() -> Self {
+        Self { b0: 1.0, b1: 0.0, b2: 0.0, a1: 0.0, a2: 0.0, z1: 0.0, z2: 0.0 }
+    }
+}
+
+#[cfg(feature = "audio")]
+impl Biquad {
+    fn set_peaking(&mut self, freq: f32, gain_db: f32, sample_rate: u32) {
+        // A flat band, or one at or above Nyquist for this stream, is left as a pass-through
+        let nyquist = sample_rate as f32 / 2.0;
+        if gain_db == 0.0 || freq >= nyquist * 0.9 {
+            *self = Self { z1: self.z1, z2: self.z2, ..Self::default() };
+            return;
+        }
+
+        let a = 10f32.powf(gain_db / 40.0);
+        let w0 = 2.0 * PI * freq / sample_rate as f32;
+        let alpha = w0.sin() / (2.0 * BAND_Q);
+        let cos_w0 = w0.cos();
+
+        let a0 = 1.0 + alpha / a;
+        self.b0 = (1.0 + alpha * a) / a0;
+        self.b1 = (-2.0 * cos_w0) / a0;
+        self.b2 = (1.0 - alpha * a) / a0;
+        self.a1 = (-2.0 * cos_w0) / a0;
+        self.a2 = (1.0 - alpha / a) / a0;
+    }
+
+    fn process(&mut self, x: f32) -> f32 {
+        let y = self.b0 * x + self.z1;
+        self.z1 = self.b1 * x - self.a1 * y + self.z2;
+        self.z2 = self.b2 * x - self.a2 * y;
+        y
+    }
+
+    fn reset(&mut self) {
+        self.z1 = 0.0;
+        self.z2 = 0.0;
+    }
+}
+
+#[cfg(all(test, feature = "audio"))]
+mod tests {
+    use super::*;
+    use rodio::buffer::SamplesBuffer;
+
+    fn sine(freq: f32, sample_rate: u32, seconds: f32) -> SamplesBuffer<f32> {
+        let samples = (sample_rate as f32 * seconds) as usize;
+        let data = (0..samples)
+            .map(|n| 0.25 * (2.0 * PI * freq * n as f32 / sample_rate as f32).sin())
+            .collect::<Vec<_>>();
+        SamplesBuffer::new(1, sample_rate, data)
+    }
+
+    /// Peak level over the second half, once the filters have settled
+    fn settled_peak(samples: &[f32]) -> f32 {
+        samples[samples.len() / 2..].iter().fold(0.0f32, |peak, s| peak.max(s.abs()))
+    }
+
+    #[test]
+    fn test_flat_passes_samples_through() {
+        let equalizer = Equalizer::new([0.0; BAND_COUNT]);
+        let input: Vec<f32> = sine(440.0, 44_100, 0.1).collect();
+        let output: Vec<f32> = equalizer.apply(sine(440.0, 44_100, 0.1)).collect();
+        assert_eq!(input, output);
+    }
+
+    #[test]
+    fn test_band_boost_at_different_sample_rates() {
+        let mut gains = [0.0; BAND_COUNT];
+        gains[5] = 12.0; // 1kHz
+
+        for sample_rate in [22_050, 44_100, 48_000, 96_000] {
+            let equalizer = Equalizer::new(gains);
+            let output: Vec<f32> = equalizer.apply(sine(1_000.0, sample_rate, 0.5)).collect();
+
+            // +12dB is roughly 4x; the 16kHz band must not blow up near Nyquist at 22.05kHz
+            let ratio = settled_peak(&output) / 0.25;
+            assert!((3.5..4.5).contains(&ratio), "{}Hz: boost ratio {}", sample_rate, ratio);
+            assert!(output.iter().all(|s| s.is_finite()));
+        }
+    }
+
+    #[test]
+    fn test_gain_changes_apply_mid_stream() {
+        let equalizer = Equalizer::new([0.0; BAND_COUNT]);
+        let mut source = equalizer.apply(sine(1_000.0, 44_100, 1.0));
+
+        let before: Vec<f32> = source.by_ref().take(22_050).collect();
+        let mut gains = [0.0; BAND_COUNT];
+        gains[5] = -12.0;
+        equalizer.set_gains(gains);
+        let after: Vec<f32> = source.collect();
+
+        assert!(settled_peak(&before) > 0.24);
+        assert!(settled_peak(&after) < 0.08);
+    }
+}
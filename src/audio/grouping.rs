@@ -0,0 +1,275 @@
+// Library grouping - the artist and album trees behind the Library tab's browse views
+// Works on indices into the track list, like the rest of the Library tab
+
+use super::Track;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
+pub const UNKNOWN_ARTIST: &str = "Unknown Artist";
+pub const UNKNOWN_ALBUM: &str = "Unknown Album";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GroupBy {
+    #[default]
+    None, // the flat track list
+    Artist, // Artist → Albums → Tracks
+    Album,  // Album → Tracks
+}
+
+impl GroupBy {
+    pub fn next(self) -> Self {
+        match self {
+            GroupBy::None => GroupBy::Artist,
+            GroupBy::Artist => GroupBy::Album,
+            GroupBy::Album => GroupBy::None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            GroupBy::None => "tracks",
+            GroupBy::Artist => "artists",
+            GroupBy::Album => "albums",
+        }
+    }
+}
+
+/// One visible row of a grouped view
+#[derive(Debug, Clone, PartialEq)]
+pub enum TreeRow {
+    Group {
+        id: String, // stable across rebuilds, used to remember expansion
+        label: String,
+        depth: usize,
+        track_count: usize,
+        expanded: bool,
+    },
+    Track {
+        index: usize, // into the track list
+        depth: usize,
+    },
+}
+
+fn artist_name(track: &Track) -> Option<&str> {
+    track.metadata.artist.as_deref().map(str::trim).filter(|s| !s.is_empty())
+}
+
+fn album_name(track: &Track) -> Option<&str> {
+    track.metadata.album.as_deref().map(str::trim).filter(|s| !s.is_empty())
+}
+
+fn album_artist(track: &Track) -> Option<&str> {
+    track.metadata.album_artist.as_deref().map(str::trim).filter(|s| !s.is_empty()).or_else(|| artist_name(track))
+}
+
+/// Named groups alphabetically, then the Unknown group
+fn compare_names(a: Option<&str>, b: Option<&str>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => a.to_lowercase().cmp(&b.to_lowercase()),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// Where a track sits on its album: disc (1 when untagged), then numbered tracks before unnumbered ones
+pub fn disc_track_key(track: &Track) -> (u32, bool, Option<u32>) {
+    (track.metadata.disc_number.unwrap_or(1), track.metadata.track_number.is_none(), track.metadata.track_number)
+}
+
+/// "2.03" for disc 2 track 3, "03" without a disc tag; `None` for an unnumbered track
+pub fn disc_track_label(track: &Track) -> Option<String> {
+    let number = track.metadata.track_number?;
+    Some(match track.metadata.disc_number {
+        Some(disc) => format!("{}.{:02}", disc, number),
+        None => format!("{:02}", number),
+    })
+}
+
+/// Reorder `indices` so each group's tracks sit together, in tree order
+///
+/// The sort is stable, so tracks keep their existing relative order inside a group.
+pub fn order_for_tree(tracks: &[Track], indices: &mut [usize], group_by: GroupBy) {
+    match group_by {
+        GroupBy::None => {}
+        GroupBy::Artist => indices.sort_by(|&a, &b| {
+            let (a, b) = (&tracks[a], &tracks[b]);
+            compare_names(artist_name(a), artist_name(b))
+                .then_with(|| compare_names(album_name(a), album_name(b)))
+        }),
+        GroupBy::Album => indices.sort_by(|&a, &b| compare_names(album_name(&tracks[a]), album_name(&tracks[b]))),
+    }
+}
+
+/// The album `track_idx` is on, in disc then track-number order
+///
+/// An album is the tracks sharing its name and album artist (or artist, when untagged), compared
+/// case-insensitively. Unnumbered tracks follow the numbered ones of their disc, by file name.
+/// A track without an album tag is an album of one.
+pub fn album_order(tracks: &[Track], track_idx: usize) -> Vec<usize> {
+    let Some(album) = tracks.get(track_idx).and_then(album_name) else {
+        return if track_idx < tracks.len() { vec![track_idx] } else { Vec::new() };
+    };
+    let artist = album_artist(&tracks[track_idx]);
+    let same_name = |a: Option<&str>, b: Option<&str>| compare_names(a, b) == Ordering::Equal;
+
+    let mut order: Vec<usize> = (0..tracks.len())
+        .filter(|&idx| same_name(album_name(&tracks[idx]), Some(album)) && same_name(album_artist(&tracks[idx]), artist))
+        .collect();
+    order.sort_by_key(|&idx| {
+        let track = &tracks[idx];
+        (disc_track_key(track), track.file_path.file_name().map(|name| name.to_os_string()))
+    });
+    order
+}
+
+/// Flatten the grouped view into rows, descending only into expanded groups
+///
+/// `indices` must already be in tree order (see `order_for_tree`).
+pub fn tree_rows(tracks: &[Track], indices: &[usize], group_by: GroupBy, expanded: &HashSet<String>) -> Vec<TreeRow> {
+    let mut rows = Vec::new();
+    match group_by {
+        GroupBy::None => rows.extend(indices.iter().map(|&index| TreeRow::Track { index, depth: 0 })),
+        GroupBy::Artist => {
+            for artist in group_runs(indices, |idx| artist_name(&tracks[idx])) {
+                let id = format!("artist:{}", artist.name.unwrap_or(""));
+                let is_expanded = expanded.contains(&id);
+                rows.push(group_row(&id, artist.name.unwrap_or(UNKNOWN_ARTIST), 0, artist.members.len(), is_expanded));
+                if !is_expanded {
+                    continue;
+                }
+
+                for album in group_runs(artist.members, |idx| album_name(&tracks[idx])) {
+                    let album_id = format!("{}/album:{}", id, album.name.unwrap_or(""));
+                    let album_expanded = expanded.contains(&album_id);
+                    rows.push(group_row(&album_id, album.name.unwrap_or(UNKNOWN_ALBUM), 1, album.members.len(), album_expanded));
+                    if album_expanded {
+                        rows.extend(album.members.iter().map(|&index| TreeRow::Track { index, depth: 2 }));
+                    }
+                }
+            }
+        }
+        GroupBy::Album => {
+            for album in group_runs(indices, |idx| album_name(&tracks[idx])) {
+                let id = format!("album:{}", album.name.unwrap_or(""));
+                let is_expanded = expanded.contains(&id);
+                rows.push(group_row(&id, album.name.unwrap_or(UNKNOWN_ALBUM), 0, album.members.len(), is_expanded));
+                if is_expanded {
+                    rows.extend(album.members.iter().map(|&index| TreeRow::Track { index, depth: 1 }));
+                }
+            }
+        }
+    }
+    rows
+}
+
+fn group_row(id: &str, label: &str, depth: usize, track_count: usize, expanded: bool) -> TreeRow {
+    TreeRow::Group { id: id.to_string(), label: label.to_string(), depth, track_count, expanded }
+}
+
+struct Run<'a, 't> {
+    name: Option<&'t str>,
+    members: &'a [usize],
+}
+
+/// Split already-grouped `indices` into runs sharing a name (case-insensitively)
+fn group_runs<'a, 't>(indices: &'a [usize], name_of: impl Fn(usize) -> Option<&'t str>) -> Vec<Run<'a, 't>> {
+    let mut runs = Vec::new();
+    let mut start = 0;
+    for end in 1..=indices.len() {
+        let boundary = end == indices.len()
+            || compare_names(name_of(indices[start]), name_of(indices[end])) != Ordering::Equal;
+        if boundary {
+            runs.push(Run { name: name_of(indices[start]), members: &indices[start..end] });
+            start = end;
+        }
+    }
+    runs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_artist_tree_groups_and_expands() {
+        let tracks = vec![
+            Track::test_fixture("/m/1.mp3").with_artist("Boards of Canada").with_album("Geogaddi"),
+            Track::test_fixture("/m/2.mp3"),
+            Track::test_fixture("/m/3.mp3").with_artist("Aphex Twin").with_album("Drukqs"),
+            Track::test_fixture("/m/4.mp3").with_artist("boards of canada").with_album("Geogaddi"),
+            Track::test_fixture("/m/5.mp3").with_artist("Boards of Canada"),
+        ];
+        let mut indices: Vec<usize> = (0..tracks.len()).collect();
+        order_for_tree(&tracks, &mut indices, GroupBy::Artist);
+        assert_eq!(indices, vec![2, 0, 3, 4, 1]);
+
+        // Collapsed: one row per artist, Unknown last
+        let rows = tree_rows(&tracks, &indices, GroupBy::Artist, &HashSet::new());
+        let labels: Vec<_> = rows.iter().map(|row| match row {
+            TreeRow::Group { label, track_count, .. } => format!("{} ({})", label, track_count),
+            TreeRow::Track { index, .. } => index.to_string(),
+        }).collect();
+        assert_eq!(labels, vec!["Aphex Twin (1)", "Boards of Canada (3)", "Unknown Artist (1)"]);
+
+        let mut expanded = HashSet::new();
+        expanded.insert("artist:Boards of Canada".to_string());
+        expanded.insert("artist:Boards of Canada/album:Geogaddi".to_string());
+        let rows = tree_rows(&tracks, &indices, GroupBy::Artist, &expanded);
+        assert_eq!(rows.len(), 3 + 2 + 2);
+        assert_eq!(rows[2], TreeRow::Group {
+            id: "artist:Boards of Canada/album:Geogaddi".to_string(),
+            label: "Geogaddi".to_string(),
+            depth: 1,
+            track_count: 2,
+            expanded: true,
+        });
+        assert_eq!(rows[3], TreeRow::Track { index: 0, depth: 2 });
+        assert_eq!(rows[4], TreeRow::Track { index: 3, depth: 2 });
+        assert!(matches!(&rows[5], TreeRow::Group { label, .. } if label == UNKNOWN_ALBUM));
+    }
+
+    #[test]
+    fn test_album_order() {
+        let numbered = |path: &str, album_artist: Option<&str>, disc: Option<u32>, number: Option<u32>| {
+            let mut track = Track::test_fixture(path).with_artist("Someone").with_album("Album");
+            track.metadata.album_artist = album_artist.map(str::to_string);
+            track.metadata.disc_number = disc;
+            track.metadata.track_number = number;
+            track
+        };
+        let tracks = vec![
+            numbered("/m/z.mp3", None, Some(2), Some(1)),
+            numbered("/m/b.mp3", None, None, None),
+            numbered("/m/y.mp3", None, Some(1), Some(2)),
+            numbered("/m/x.mp3", Some("someone"), None, Some(1)),
+            numbered("/m/a.mp3", None, None, None),
+            numbered("/m/other.mp3", Some("Someone Else"), Some(1), Some(1)),
+            Track::test_fixture("/m/loose.mp3").with_artist("Someone"),
+        ];
+        assert_eq!(album_order(&tracks, 1), vec![3, 2, 4, 1, 0]);
+        assert_eq!(album_order(&tracks, 5), vec![5]);
+        assert_eq!(album_order(&tracks, 6), vec![6]);
+
+        let labels: Vec<_> = [0, 1, 3].iter().map(|&idx| disc_track_label(&tracks[idx])).collect();
+        assert_eq!(labels, vec![Some("2.01".to_string()), None, Some("01".to_string())]);
+    }
+
+    #[test]
+    fn test_album_tree_keeps_track_order_within_groups() {
+        let tracks = vec![
+            Track::test_fixture("/m/b2.mp3").with_artist("X").with_album("Beta"),
+            Track::test_fixture("/m/a1.mp3").with_artist("Y").with_album("Alpha"),
+            Track::test_fixture("/m/b1.mp3").with_artist("X").with_album("Beta"),
+        ];
+        let mut indices = vec![2, 0, 1];
+        order_for_tree(&tracks, &mut indices, GroupBy::Album);
+        assert_eq!(indices, vec![1, 2, 0]);
+
+        let expanded: HashSet<String> = ["album:Beta".to_string()].into_iter().collect();
+        let rows = tree_rows(&tracks, &indices, GroupBy::Album, &expanded);
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[2], TreeRow::Track { index: 2, depth: 1 });
+        assert_eq!(rows[3], TreeRow::Track { index: 0, depth: 1 });
+    }
+}
@@ -0,0 +1,351 @@
+// Library cache - the last scan's tracks on disk so a warm start doesn't re-walk the tree
+// Only trusted while the scan settings match; directory and file mtimes say what needs another look
+
+use super::scan_cache::file_modified_secs;
+use super::scan_report::UnreadableFile;
+use super::{MusicScanner, Track};
+use crate::config::Config;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use uuid::Uuid;
+use walkdir::WalkDir;
+
+/// How track ids are handed out; caches from an older scheme carry ids the behavior data no longer uses
+const TRACK_ID_SCHEME: u32 = 1; // 1: derived from the content hash
+
+/// Which formats the scanner reads tags from; a cache from before a new reader has those files filename-only
+const TAG_READERS: u32 = 1; // 1: WAV headers
+
+/// Whether read failures are kept with the tracks; older caches would make a warm start look clean
+const UNREADABLE_FILES: u32 = 1; // 1: kept and refreshed with the tracks
+
+/// The settings a cached library was scanned with - any change means a full rescan
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LibraryCacheKey {
+    music_directories: Vec<PathBuf>,
+    supported_extensions: Vec<String>,
+    include_patterns: Vec<String>,
+    exclude_patterns: Vec<String>,
+    #[serde(default)]
+    track_ids: u32, // TRACK_ID_SCHEME the tracks were scanned under
+    #[serde(default)]
+    tag_readers: u32, // TAG_READERS the tracks were scanned with
+    #[serde(default)]
+    unreadable_files: u32, // UNREADABLE_FILES the cache was written with
+}
+
+impl LibraryCacheKey {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            track_ids: TRACK_ID_SCHEME,
+            tag_readers: TAG_READERS,
+            unreadable_files: UNREADABLE_FILES,
+            music_directories: config.music_directories.clone(),
+            supported_extensions: config.library.supported_extensions.clone(),
+            include_patterns: config.library.include_patterns.clone(),
+            exclude_patterns: config.library.exclude_patterns.clone(),
+        }
+    }
+}
+
+/// What `LibraryCache::refresh` changed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CacheRefresh {
+    pub added: usize,
+    pub removed: usize,
+    pub updated: usize, // files modified since they were cached
+    pub rehashed: Vec<RehashedTrack>, // updated files whose history should follow them to a new id
+}
+
+/// A cached file whose contents changed, so its id moved with the new content hash
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RehashedTrack {
+    pub old_id: Uuid,
+    pub old_hash: Option<u64>,
+    pub new_id: Uuid,
+    pub new_hash: u64,
+}
+
+impl CacheRefresh {
+    pub fn is_empty(&self) -> bool {
+        self.added == 0 && self.removed == 0 && self.updated == 0
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LibraryCache {
+    key: LibraryCacheKey,
+    directories: HashMap<PathBuf, SystemTime>, // every directory under the music roots and its mtime
+    pub tracks: Vec<Track>,
+    #[serde(default)]
+    pub unreadable: Vec<UnreadableFile>, // what the scan report lists as unreadable, kept for warm starts
+}
+
+impl LibraryCache {
+    /// Cache the result of a complete scan, recording the directory tree as it is now
+    pub fn new(key: LibraryCacheKey, tracks: Vec<Track>) -> Self {
+        let mut directories = HashMap::new();
+        for root in &key.music_directories {
+            record_directories(root, &mut directories);
+        }
+        Self { key, directories, tracks, unreadable: Vec::new() }
+    }
+
+    /// Keep the scan's read failures so a warm start can still report them
+    pub fn with_unreadable(mut self, unreadable: Vec<UnreadableFile>) -> Self {
+        self.unreadable = unreadable;
+        self
+    }
+
+    /// The cache at `path`, or `None` if there isn't one or it was scanned with other settings
+    pub fn load(path: &Path, key: &LibraryCacheKey) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content = fs::read_to_string(path)?;
+        let cache: LibraryCache = serde_json::from_str(&content)?;
+        Ok((cache.key == *key).then_some(cache))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        // Compact on purpose, a big library is megabytes of JSON
+        fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Bring the cached tracks up to date without walking the whole tree
+    ///
+    /// Known files are only stat'ed; directory listings are re-read just for directories whose
+    /// mtime moved, which is where files were added, removed or renamed.
+    pub fn refresh(&mut self, scanner: &MusicScanner) -> CacheRefresh {
+        let mut refresh = CacheRefresh::default();
+        self.unreadable.retain(|file| file.path.exists());
+
+        let mut tracks = Vec::with_capacity(self.tracks.len());
+        for track in std::mem::take(&mut self.tracks) {
+            let Ok(metadata) = fs::metadata(&track.file_path) else {
+                refresh.removed += 1;
+                continue;
+            };
+            if metadata.len() == track.file_size && file_modified_secs(&metadata) == track.modified {
+                tracks.push(track);
+                continue;
+            }
+
+            // The id follows the new contents; without a hash there is nothing to derive one from
+            match scan_reporting(scanner, &track.file_path, &mut self.unreadable) {
+                Some(mut updated) => {
+                    match updated.content_hash {
+                        Some(new_hash) if updated.id != track.id => refresh.rehashed.push(RehashedTrack {
+                            old_id: track.id,
+                            old_hash: track.content_hash,
+                            new_id: updated.id,
+                            new_hash,
+                        }),
+                        Some(_) => {}
+                        None => updated.id = track.id,
+                    }
+                    tracks.push(updated);
+                    refresh.updated += 1;
+                }
+                None => refresh.removed += 1,
+            }
+        }
+
+        let changed_directories: Vec<PathBuf> = self.directories.iter()
+            .filter(|(dir, &modified)| directory_modified(dir) != Some(modified))
+            .map(|(dir, _)| dir.clone())
+            .collect();
+
+        let mut known: HashSet<PathBuf> = tracks.iter().map(|track| track.file_path.clone()).collect();
+        for dir in changed_directories {
+            self.directories.remove(&dir);
+            let (Some(modified), Ok(entries)) = (directory_modified(&dir), fs::read_dir(&dir)) else {
+                continue; // gone, its tracks were dropped above
+            };
+            self.directories.insert(dir.clone(), modified);
+
+            let Some(root) = self.key.music_directories.iter().find(|root| dir.starts_with(root)) else {
+                continue;
+            };
+
+            let mut new_files = Vec::new();
+            for path in entries.filter_map(Result::ok).map(|entry| entry.path()) {
+                if path.is_dir() {
+                    if !self.directories.contains_key(&path) {
+                        record_directories(&path, &mut self.directories);
+                        new_files.extend(scanner.collect_candidate_files_under(root, &path));
+                    }
+                } else if !known.contains(&path) && scanner.is_candidate_file(root, &path) {
+                    new_files.push(path);
+                }
+            }
+
+            for path in new_files {
+                if let Some(track) = scan_reporting(scanner, &path, &mut self.unreadable) {
+                    known.insert(path);
+                    tracks.push(track);
+                    refresh.added += 1;
+                }
+            }
+        }
+
+        // Identical copies share an id, and the ones left untouched still need their history
+        refresh.rehashed.retain(|rehashed| tracks.iter().all(|track| track.id != rehashed.old_id));
+
+        tracks.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+        self.tracks = tracks;
+        self.unreadable.sort_by(|a, b| a.path.cmp(&b.path));
+        refresh
+    }
+}
+
+/// Scan one file, replacing whatever the unreadable list said about it before
+fn scan_reporting(scanner: &MusicScanner, path: &Path, unreadable: &mut Vec<UnreadableFile>) -> Option<Track> {
+    unreadable.retain(|file| file.path != path);
+    match scanner.scan_file_with_problems(path) {
+        Ok((track, problems)) => {
+            unreadable.extend(problems.into_iter().map(|error| UnreadableFile { path: path.to_path_buf(), error }));
+            Some(track)
+        }
+        Err(e) => {
+            unreadable.push(UnreadableFile { path: path.to_path_buf(), error: e.to_string() });
+            None
+        }
+    }
+}
+
+fn directory_modified(dir: &Path) -> Option<SystemTime> {
+    fs::metadata(dir).ok().filter(|m| m.is_dir())?.modified().ok()
+}
+
+/// Record `dir` and every directory below it
+fn record_directories(dir: &Path, directories: &mut HashMap<PathBuf, SystemTime>) {
+    for entry in WalkDir::new(dir).follow_links(true).into_iter().filter_map(Result::ok) {
+        if entry.file_type().is_dir() {
+            if let Some(modified) = directory_modified(entry.path()) {
+                directories.insert(entry.into_path(), modified);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::test_support::touch;
+
+    fn key_for(root: &Path) -> LibraryCacheKey {
+        LibraryCacheKey { music_directories: vec![root.to_path_buf()], ..LibraryCacheKey::default() }
+    }
+
+    #[test]
+    fn test_load_requires_matching_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("library_cache.json");
+        let key = key_for(dir.path());
+
+        assert!(LibraryCache::load(&path, &key).unwrap().is_none());
+
+        LibraryCache::new(key.clone(), vec![Track::new(dir.path().join("a.mp3"))]).save(&path).unwrap();
+        assert_eq!(LibraryCache::load(&path, &key).unwrap().unwrap().tracks.len(), 1);
+
+        let other = LibraryCacheKey { supported_extensions: vec!["opus".to_string()], ..key };
+        assert!(LibraryCache::load(&path, &other).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_refresh_picks_up_added_and_removed_files() {
+        let dir = tempfile::tempdir().unwrap();
+        touch(dir.path(), "Album/a.mp3");
+        touch(dir.path(), "Album/b.mp3");
+
+        let scanner = MusicScanner::new();
+        let mut cache = LibraryCache::new(key_for(dir.path()), scanner.scan_directory(dir.path()).unwrap());
+        let kept_id = cache.tracks[0].id;
+        assert!(cache.refresh(&scanner).is_empty());
+
+        fs::remove_file(dir.path().join("Album/b.mp3")).unwrap();
+        touch(dir.path(), "Album/c.mp3");
+        touch(dir.path(), "New Album/d.mp3");
+        touch(dir.path(), "New Album/cover.jpg");
+
+        let refresh = cache.refresh(&scanner);
+        assert_eq!(refresh, CacheRefresh { added: 2, removed: 1, ..CacheRefresh::default() });
+
+        let names: Vec<_> = cache.tracks.iter()
+            .map(|t| t.file_path.strip_prefix(dir.path()).unwrap().to_string_lossy().replace('\\', "/"))
+            .collect();
+        assert_eq!(names, vec!["Album/a.mp3", "Album/c.mp3", "New Album/d.mp3"]);
+        assert_eq!(cache.tracks[0].id, kept_id);
+
+        // The new directory is tracked now, so nothing changes the second time
+        assert!(cache.refresh(&scanner).is_empty());
+    }
+
+    #[test]
+    fn test_unreadable_files_survive_a_warm_start() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("library_cache.json");
+        touch(dir.path(), "a.mp3");
+        touch(dir.path(), "broken.m4a");
+
+        let unreadable = |name: &str, error: &str| UnreadableFile { path: dir.path().join(name), error: error.to_string() };
+        let scanner = MusicScanner::new();
+        LibraryCache::new(key_for(dir.path()), scanner.scan_directory(dir.path()).unwrap())
+            .with_unreadable(vec![unreadable("broken.m4a", "MP4 tag unreadable"), unreadable("gone.flac", "truncated")])
+            .save(&path)
+            .unwrap();
+
+        let mut cache = LibraryCache::load(&path, &key_for(dir.path())).unwrap().unwrap();
+        assert_eq!(cache.unreadable.len(), 2);
+
+        // New files get their problems listed, deleted ones drop off, untouched ones stay as they were
+        touch(dir.path(), "Album/new.m4a");
+        cache.refresh(&scanner);
+        let paths: Vec<_> = cache.unreadable.iter().map(|file| file.path.clone()).collect();
+        assert_eq!(paths, vec![dir.path().join("Album/new.m4a"), dir.path().join("broken.m4a")]);
+        assert_eq!(cache.unreadable[1], unreadable("broken.m4a", "MP4 tag unreadable"));
+    }
+
+    #[test]
+    fn test_refresh_moves_ids_of_changed_files() {
+        let dir = tempfile::tempdir().unwrap();
+        touch(dir.path(), "a.mp3");
+        touch(dir.path(), "b.mp3"); // identical copy of a.mp3
+        fs::write(dir.path().join("c.mp3"), b"other audio").unwrap();
+
+        let scanner = MusicScanner::new();
+        let mut cache = LibraryCache::new(key_for(dir.path()), scanner.scan_directory(dir.path()).unwrap());
+        let (shared_id, c) = (cache.tracks[0].id, cache.tracks[2].clone());
+        assert_eq!(cache.tracks[1].id, shared_id);
+
+        fs::write(dir.path().join("a.mp3"), b"retagged audio bytes").unwrap();
+        fs::write(dir.path().join("c.mp3"), b"other audio, retagged").unwrap();
+
+        let refresh = cache.refresh(&scanner);
+        assert_eq!(refresh.updated, 2);
+
+        for track in &cache.tracks {
+            assert_eq!(track.id, Track::content_id(track.content_hash.unwrap()));
+        }
+        assert_eq!(cache.tracks[1].id, shared_id);
+
+        // b.mp3 still uses the old id of a.mp3, so only c.mp3's history moves
+        assert_eq!(refresh.rehashed, vec![RehashedTrack {
+            old_id: c.id,
+            old_hash: c.content_hash,
+            new_id: cache.tracks[2].id,
+            new_hash: cache.tracks[2].content_hash.unwrap(),
+        }]);
+    }
+}
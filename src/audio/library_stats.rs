@@ -0,0 +1,72 @@
+// Library stats - what's in the collection and how well it's tagged, from the tracks already in memory
+
+use super::{AudioFormat, Track};
+use std::time::Duration;
+
+#[derive(Debug, Clone, Default)]
+pub struct LibraryStats {
+    pub track_count: usize,
+    pub formats: Vec<(AudioFormat, usize)>, // most common first
+    pub total_size: u64, // bytes
+    pub total_duration: Duration, // of the tracks whose duration is known
+    pub missing_metadata: usize, // no title or no artist tag
+    pub missing_duration: usize,
+}
+
+impl LibraryStats {
+    pub fn from_tracks(tracks: &[Track]) -> Self {
+        let mut stats = Self { track_count: tracks.len(), ..Self::default() };
+
+        for track in tracks {
+            match stats.formats.iter_mut().find(|(format, _)| *format == track.format) {
+                Some((_, count)) => *count += 1,
+                None => stats.formats.push((track.format.clone(), 1)),
+            }
+            stats.total_size += track.file_size;
+
+            match track.duration.or(track.metadata.duration_ms.map(Duration::from_millis)) {
+                Some(duration) => stats.total_duration += duration,
+                None => stats.missing_duration += 1,
+            }
+            if track.metadata.title.is_none() || track.metadata.artist.is_none() {
+                stats.missing_metadata += 1;
+            }
+        }
+
+        stats.formats.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.name().cmp(b.0.name())));
+        stats
+    }
+
+    /// Mean length of the tracks whose duration is known
+    pub fn average_duration(&self) -> Option<Duration> {
+        let known = self.track_count - self.missing_duration;
+        (known > 0).then(|| self.total_duration / known as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_library_stats() {
+        let mut from_tags = Track::test_fixture("/m/d.mp3").with_file_size(1_000).with_title("Title").with_artist("Artist");
+        from_tags.metadata.duration_ms = Some(60_000);
+        let tracks = vec![
+            Track::test_fixture("/m/a.flac").with_file_size(30_000_000).with_duration_secs(240).with_title("Title").with_artist("Artist"),
+            Track::test_fixture("/m/b.mp3").with_file_size(5_000_000).with_duration_secs(180),
+            Track::test_fixture("/m/c.mp3").with_file_size(4_000_000).with_title("Title").with_artist("Artist"),
+            from_tags,
+        ];
+
+        let stats = LibraryStats::from_tracks(&tracks);
+        assert_eq!(stats.track_count, 4);
+        assert_eq!(stats.formats, vec![(AudioFormat::Mp3, 3), (AudioFormat::Flac, 1)]);
+        assert_eq!(stats.total_size, 39_001_000);
+        assert_eq!(stats.total_duration, Duration::from_secs(480));
+        assert_eq!(stats.average_duration(), Some(Duration::from_secs(160)));
+        assert_eq!((stats.missing_metadata, stats.missing_duration), (1, 1));
+
+        assert_eq!(LibraryStats::from_tracks(&[]).average_duration(), None);
+    }
+}
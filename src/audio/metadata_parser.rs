@@ -1,9 +1,13 @@
+use crate::config::MetadataConfig;
+use anyhow::{Context, Result};
 use regex::Regex;
 
 #[derive(Debug, Clone)]
 pub struct ParsedMetadata {
     pub suggested_title: String,
-    pub suggested_artist: String,
+    pub suggested_artist: String, // Primary artist only
+    pub featured_artists: Vec<String>, // From "feat."/"ft."/"featuring" and "&"/"," lists
+    pub track_number: Option<u32>, // Leading "03 - " number, unless it turned out to be part of the name
     pub confidence: f32, // 0.0 to 1.0
     pub pattern_used: String,
     pub normalization_applied: Vec<String>, // Track what normalizations were applied
@@ -16,9 +20,16 @@ pub struct DelimiterInfo {
     pub typical_pattern: String,
 }
 
+#[derive(Clone)]
 pub struct MetadataParser {
     patterns: Vec<ParsePattern>,
+    custom_patterns: Vec<ParsePattern>, // User regexes, tried before anything built in
     delimiter_cache: std::collections::HashMap<String, DelimiterInfo>, // Cache common delimiters
+    custom_delimiters: Vec<String>, // Keys into delimiter_cache, tried first in this order
+    featuring_bracketed: Regex, // "(feat. Guest)" / "[ft. Guest]" anywhere in the text
+    featuring_trailing: Regex,  // "Artist feat. Guest" up to the end of the text
+    version_suffix: Regex,      // Trailing "(Radio Edit)" style groups
+    track_prefix: Regex,        // "03 - ", "03. ", "03 " at the start of the filename
 }
 
 #[derive(Debug, Clone)]
@@ -74,7 +85,8 @@ impl MetadataParser {
         
         // Pattern 2: "Number - Artist - Title.ext"
         // Example: "21 - blink-182 - TAKE ME IN (Official Lyric Video).m4a"
-        if let Ok(regex) = Regex::new(r"^\d+\s*-\s*(.+?)\s*-\s*(.+?)(?:\s*\(.*?\))?\.") {
+        // Spaces around the separator are required so hyphenated artists stay intact
+        if let Ok(regex) = Regex::new(r"^\d+\s*-\s*(.+?)\s+-\s+(.+?)(?:\s*\(.*?\))?\.") {
             patterns.push(ParsePattern {
                 name: "Number - Artist - Title".to_string(),
                 regex,
@@ -118,18 +130,127 @@ impl MetadataParser {
             });
         }
         
-        Self { patterns, delimiter_cache }
+        Self {
+            patterns,
+            custom_patterns: Vec::new(),
+            delimiter_cache,
+            custom_delimiters: Vec::new(),
+            featuring_bracketed: Regex::new(r"(?i)\s*[\(\[](?:feat\.?|ft\.?|featuring)\s+([^\)\]]+)[\)\]]")
+                .expect("valid featuring regex"),
+            featuring_trailing: Regex::new(r"(?i)\s+(?:feat\.?|ft\.?|featuring)\s+(.+)$")
+                .expect("valid featuring regex"),
+            version_suffix: Regex::new(r"\s*([\(\[][^\)\]]*[\)\]])\s*$")
+                .expect("valid version regex"),
+            track_prefix: Regex::new(r"^(\d{1,3})(?:\s*[-._)]\s*|\s+)\S")
+                .expect("valid track number regex"),
+        }
+    }
+    
+    /// Load user delimiters and patterns from the `[metadata]` config section
+    ///
+    /// Invalid entries are skipped; the returned messages describe what was rejected.
+    pub fn apply_config(&mut self, config: &MetadataConfig) -> Vec<String> {
+        let mut errors = Vec::new();
+        
+        for delimiter in &config.custom_delimiters {
+            if delimiter.delimiter.trim().is_empty() {
+                errors.push(format!("Custom delimiter '{}' is blank", delimiter.name));
+                continue;
+            }
+            self.add_custom_delimiter(delimiter.delimiter.clone(), delimiter.confidence.clamp(0.0, 1.0), delimiter.name.clone());
+        }
+        
+        for pattern in &config.custom_patterns {
+            if let Err(e) = self.add_custom_pattern(&pattern.name, &pattern.regex, pattern.confidence) {
+                errors.push(format!("{:#}", e));
+            }
+        }
+        
+        errors
+    }
+    
+    /// Add a user regex with a named `title` group and optional `artist` group
+    pub fn add_custom_pattern(&mut self, name: &str, pattern: &str, confidence: f32) -> Result<()> {
+        let regex = Regex::new(pattern)
+            .with_context(|| format!("Custom pattern '{}' is not a valid regex", name))?;
+        
+        let group_index = |group: &str| regex.capture_names().position(|n| n == Some(group));
+        let title_group = group_index("title")
+            .ok_or_else(|| anyhow::anyhow!("Custom pattern '{}' needs a (?P<title>...) group", name))?;
+        let artist_group = group_index("artist").unwrap_or(0); // 0 falls back to "Unknown Artist"
+        
+        self.custom_patterns.push(ParsePattern {
+            name: name.to_string(),
+            regex,
+            title_group,
+            artist_group,
+            confidence: confidence.clamp(0.0, 1.0),
+        });
+        
+        Ok(())
     }
     
     pub fn parse_filename(&self, filename: &str) -> ParsedMetadata {
+        let mut parsed = self.parse_title_and_artist(filename);
+        parsed.track_number = self.track_number(filename, &parsed);
+        
+        // Fallbacks have nothing to split
+        if parsed.suggested_artist == "Unknown Artist" {
+            return parsed;
+        }
+        
+        let (title, mut featured) = self.extract_featuring(&parsed.suggested_title);
+        let (artist, version) = self.strip_version_suffix(&parsed.suggested_artist);
+        let (primary, artist_featured) = self.split_artists(&artist);
+        featured.extend(artist_featured);
+        
+        let mut unique = Vec::new();
+        for name in featured {
+            if name != primary && !unique.contains(&name) {
+                unique.push(name);
+            }
+        }
+        
+        // Remix/version info belongs with the title, never the artist
+        let title = match version {
+            Some(version) if !title.contains(&version) => format!("{} {}", title, version),
+            _ => title,
+        };
+        
+        if !unique.is_empty() || primary != parsed.suggested_artist || title != parsed.suggested_title {
+            parsed.normalization_applied.push("featured_artists".to_string());
+        }
+        
+        parsed.suggested_title = title;
+        parsed.suggested_artist = primary;
+        parsed.featured_artists = unique;
+        parsed
+    }
+    
+    /// The leading number the cleaners strip, when neither the title nor the artist kept it ("311 - Amber.mp3")
+    fn track_number(&self, filename: &str, parsed: &ParsedMetadata) -> Option<u32> {
+        let digits = self.track_prefix.captures(filename)?.get(1)?.as_str();
+        let kept = [&parsed.suggested_title, &parsed.suggested_artist].iter().any(|name| name.starts_with(digits));
+        digits.parse().ok().filter(|&number| number > 0 && !kept)
+    }
+    
+    fn parse_title_and_artist(&self, filename: &str) -> ParsedMetadata {
         let mut normalizations_applied = Vec::new();
         
+        // Phase 0: User-defined patterns know the library's naming best
+        if let Some(result) = self.match_patterns(&self.custom_patterns, filename) {
+            normalizations_applied.push("custom_pattern".to_string());
+            return ParsedMetadata { normalization_applied: normalizations_applied, ..result };
+        }
+        
         // Phase 1: Cheap delimiter normalization (O(1) hash lookups)
         if let Some(delimiter_result) = self.try_cheap_delimiter_parsing(filename) {
             normalizations_applied.push("cheap_delimiter".to_string());
             return ParsedMetadata {
                 suggested_title: delimiter_result.0,
                 suggested_artist: delimiter_result.1,
+                featured_artists: Vec::new(),
+                track_number: None,
                 confidence: delimiter_result.2,
                 pattern_used: delimiter_result.3,
                 normalization_applied: normalizations_applied,
@@ -138,7 +259,26 @@ impl MetadataParser {
         
         // Phase 2: Expensive regex patterns (only if cheap parsing failed)
         normalizations_applied.push("regex_patterns".to_string());
-        for pattern in &self.patterns {
+        if let Some(result) = self.match_patterns(&self.patterns, filename) {
+            return ParsedMetadata { normalization_applied: normalizations_applied, ..result };
+        }
+        
+        // Fallback if no patterns match
+        normalizations_applied.push("fallback".to_string());
+        ParsedMetadata {
+            suggested_title: filename.to_string(),
+            suggested_artist: "Unknown Artist".to_string(),
+            featured_artists: Vec::new(),
+            track_number: None,
+            confidence: 0.1,
+            pattern_used: "No pattern matched".to_string(),
+            normalization_applied: normalizations_applied,
+        }
+    }
+    
+    /// First pattern in `patterns` that matches, with cleaned title/artist
+    fn match_patterns(&self, patterns: &[ParsePattern], filename: &str) -> Option<ParsedMetadata> {
+        for pattern in patterns {
             if let Some(captures) = pattern.regex.captures(filename) {
                 let title = if pattern.title_group > 0 {
                     captures.get(pattern.title_group)
@@ -156,25 +296,19 @@ impl MetadataParser {
                     "Unknown Artist".to_string()
                 };
                 
-                return ParsedMetadata {
+                return Some(ParsedMetadata {
                     suggested_title: title,
                     suggested_artist: artist,
+                    featured_artists: Vec::new(),
+                    track_number: None,
                     confidence: pattern.confidence,
                     pattern_used: pattern.name.clone(),
-                    normalization_applied: normalizations_applied,
-                };
+                    normalization_applied: Vec::new(),
+                });
             }
         }
         
-        // Fallback if no patterns match
-        normalizations_applied.push("fallback".to_string());
-        ParsedMetadata {
-            suggested_title: filename.to_string(),
-            suggested_artist: "Unknown Artist".to_string(),
-            confidence: 0.1,
-            pattern_used: "No pattern matched".to_string(),
-            normalization_applied: normalizations_applied,
-        }
+        None
     }
     
     /// Fast O(1) delimiter-based parsing - checks common delimiters first
@@ -186,8 +320,18 @@ impl MetadataParser {
             filename
         };
         
-        // Try each cached delimiter for fast parsing
-        for (delimiter, info) in &self.delimiter_cache {
+        // Try each cached delimiter for fast parsing, user-defined ones first
+        let custom = self.custom_delimiters.iter()
+            .filter_map(|d| self.delimiter_cache.get_key_value(d));
+        let built_in = self.delimiter_cache.iter()
+            .filter(|(d, _)| !self.custom_delimiters.contains(d));
+        
+        for (delimiter, info) in custom.chain(built_in) {
+            // More than one delimiter is ambiguous ("18 - Title - Artist") - leave it to the regexes
+            if name_without_ext.matches(delimiter.as_str()).count() > 1 {
+                continue;
+            }
+            
             if let Some(split_pos) = name_without_ext.find(delimiter) {
                 let (left_part, right_part) = name_without_ext.split_at(split_pos);
                 let right_part = &right_part[delimiter.len()..]; // Skip delimiter
@@ -216,7 +360,7 @@ impl MetadataParser {
         let mut confidence = base_confidence;
         
         // Heuristic 1: Numbers at start usually indicate track number, so format is likely "Number - Artist - Title"
-        if left.chars().next().map_or(false, |c| c.is_numeric()) {
+        if left.chars().next().is_some_and(|c| c.is_numeric()) {
             // If left starts with number, it's probably track number, so right is likely artist
             confidence *= 0.9; // Slightly lower confidence due to ambiguity
             return (right.to_string(), left.to_string(), confidence);
@@ -239,6 +383,60 @@ impl MetadataParser {
         (right.to_string(), left.to_string(), confidence)
     }
 
+    /// Pull "feat. X" credits out of a title or artist string
+    fn extract_featuring(&self, text: &str) -> (String, Vec<String>) {
+        let mut featured = Vec::new();
+        
+        for captures in self.featuring_bracketed.captures_iter(text) {
+            featured.extend(self.split_artist_list(&captures[1]));
+        }
+        let mut remaining = self.featuring_bracketed.replace_all(text, "").to_string();
+        
+        if let Some(captures) = self.featuring_trailing.captures(&remaining) {
+            featured.extend(self.split_artist_list(&captures[1]));
+            remaining = self.featuring_trailing.replace(&remaining, "").to_string();
+        }
+        
+        (self.clean_text(&remaining), featured)
+    }
+    
+    /// Primary artist plus everyone credited after "feat.", "&" or ","
+    fn split_artists(&self, artist: &str) -> (String, Vec<String>) {
+        let (main, mut featured) = self.extract_featuring(artist);
+        let mut names = self.split_artist_list(&main);
+        
+        if names.is_empty() {
+            return (main, featured);
+        }
+        
+        let primary = names.remove(0);
+        names.append(&mut featured);
+        (primary, names)
+    }
+    
+    fn split_artist_list(&self, text: &str) -> Vec<String> {
+        text.split(['&', ','])
+            .map(|name| self.clean_text(name))
+            .filter(|name| !name.is_empty())
+            .collect()
+    }
+    
+    /// Separate a trailing "(Radio Edit)"-style group from an artist string
+    fn strip_version_suffix(&self, artist: &str) -> (String, Option<String>) {
+        match self.version_suffix.captures(artist) {
+            Some(captures) => {
+                let version = captures[1].to_string();
+                let rest = self.version_suffix.replace(artist, "").to_string();
+                if rest.trim().is_empty() {
+                    (artist.to_string(), None)
+                } else {
+                    (rest.trim().to_string(), Some(version))
+                }
+            }
+            None => (artist.to_string(), None),
+        }
+    }
+
     fn clean_text(&self, text: &str) -> String {
         let mut cleaned = text.to_string();
         
@@ -300,6 +498,9 @@ impl MetadataParser {
     
     /// Add custom delimiter patterns for extensibility
     pub fn add_custom_delimiter(&mut self, delimiter: String, confidence: f32, pattern_name: String) {
+        if !self.custom_delimiters.contains(&delimiter) {
+            self.custom_delimiters.push(delimiter.clone());
+        }
         self.delimiter_cache.insert(delimiter.clone(), DelimiterInfo {
             delimiter,
             confidence,
@@ -375,6 +576,134 @@ mod tests {
         let result = parser.parse_filename("The Black Keys - Beautiful People (Stay High) (Official Video).m4a");
         assert_eq!(result.suggested_title, "Beautiful People (Stay High)");
         assert_eq!(result.suggested_artist, "The Black Keys");
+        assert_eq!(result.track_number, None);
+    }
+    
+    #[test]
+    fn test_leading_track_number() {
+        let parser = MetadataParser::new();
+        
+        let result = parser.parse_filename("03 - Artist - Title.mp3");
+        assert_eq!(result.track_number, Some(3));
+        assert_eq!(result.suggested_artist, "Artist");
+        assert_eq!(result.suggested_title, "Title");
+        
+        assert_eq!(parser.parse_filename("18 - Heavy Is the Crown (Official Audio) - Linkin Park.m4a").track_number, Some(18));
+        assert_eq!(parser.parse_filename("07 Intro.flac").track_number, Some(7));
+        
+        // Numbers that belong to the name stay there
+        assert_eq!(parser.parse_filename("311 - Amber.mp3").track_number, None);
+        assert_eq!(parser.parse_filename("1999 - Prince.mp3").track_number, None);
+    }
+    
+    #[test]
+    fn test_hyphenated_names_and_repeated_delimiters() {
+        let parser = MetadataParser::new();
+
+        // A hyphen inside the artist name is not a separator
+        let result = parser.parse_filename("07 - Jay-Z - Empire State of Mind.mp3");
+        assert_eq!(result.suggested_artist, "Jay-Z");
+        assert_eq!(result.suggested_title, "Empire State of Mind");
+
+        // Two " - " separators must not be split at the first one by the delimiter fast path
+        let result = parser.parse_filename("04 - Numb (Official Video) - Linkin Park.mp3");
+        assert_eq!(result.suggested_title, "Numb");
+        assert_eq!(result.suggested_artist, "Linkin Park");
+    }
+
+    #[test]
+    fn test_feat_in_artist() {
+        let parser = MetadataParser::new();
+        
+        let result = parser.parse_filename("Artist feat. Guest - Title.mp3");
+        assert_eq!(result.suggested_artist, "Artist");
+        assert_eq!(result.featured_artists, vec!["Guest".to_string()]);
+        assert_eq!(result.suggested_title, "Title");
+        
+        let result = parser.parse_filename("Artist featuring Guest One & Guest Two - Title.mp3");
+        assert_eq!(result.suggested_artist, "Artist");
+        assert_eq!(result.featured_artists, vec!["Guest One".to_string(), "Guest Two".to_string()]);
+    }
+    
+    #[test]
+    fn test_ft_in_title_keeps_version_info() {
+        let parser = MetadataParser::new();
+        
+        let result = parser.parse_filename("Artist - Title (ft. Guest) (Radio Edit).mp3");
+        assert_eq!(result.suggested_artist, "Artist");
+        assert_eq!(result.featured_artists, vec!["Guest".to_string()]);
+        assert_eq!(result.suggested_title, "Title (Radio Edit)");
+        
+        let result = parser.parse_filename("Artist Ft Guest - Title.mp3");
+        assert_eq!(result.suggested_artist, "Artist");
+        assert_eq!(result.featured_artists, vec!["Guest".to_string()]);
+    }
+    
+    #[test]
+    fn test_multiple_artists() {
+        let parser = MetadataParser::new();
+        
+        let result = parser.parse_filename("Calvin Harris & Dua Lipa - One Kiss.mp3");
+        assert_eq!(result.suggested_artist, "Calvin Harris");
+        assert_eq!(result.featured_artists, vec!["Dua Lipa".to_string()]);
+        assert_eq!(result.suggested_title, "One Kiss");
+        
+        let result = parser.parse_filename("A, B & C - Song Title.mp3");
+        assert_eq!(result.suggested_artist, "A");
+        assert_eq!(result.featured_artists, vec!["B".to_string(), "C".to_string()]);
+        
+        // Words that merely contain "ft" are left alone
+        let result = parser.parse_filename("Daft Punk - Around the World.mp3");
+        assert_eq!(result.suggested_artist, "Daft Punk");
+        assert!(result.featured_artists.is_empty());
+    }
+    
+    #[test]
+    fn test_custom_pattern_from_config() {
+        let mut parser = MetadataParser::new();
+        let config = MetadataConfig {
+            custom_delimiters: Vec::new(),
+            custom_patterns: vec![crate::config::CustomPatternConfig {
+                name: "Artist — Title [Year]".to_string(),
+                regex: r"^(?P<artist>.+?) — (?P<title>.+?) \[\d{4}\]\.".to_string(),
+                confidence: 0.95,
+            }],
+        };
+        
+        assert!(parser.apply_config(&config).is_empty());
+        
+        let result = parser.parse_filename("Boards of Canada — Roygbiv [1998].flac");
+        assert_eq!(result.suggested_title, "Roygbiv");
+        assert_eq!(result.suggested_artist, "Boards of Canada");
+        assert_eq!(result.confidence, 0.95);
+        assert_eq!(result.pattern_used, "Artist — Title [Year]");
+        assert_eq!(result.normalization_applied, vec!["custom_pattern".to_string()]);
+    }
+    
+    #[test]
+    fn test_invalid_custom_patterns_are_reported() {
+        let mut parser = MetadataParser::new();
+        let config = MetadataConfig {
+            custom_delimiters: Vec::new(),
+            custom_patterns: vec![
+                crate::config::CustomPatternConfig {
+                    name: "broken".to_string(),
+                    regex: r"^(?P<title>.+".to_string(),
+                    confidence: 0.9,
+                },
+                crate::config::CustomPatternConfig {
+                    name: "no title group".to_string(),
+                    regex: r"^(?P<artist>.+)\.".to_string(),
+                    confidence: 0.9,
+                },
+            ],
+        };
+        
+        assert_eq!(parser.apply_config(&config).len(), 2);
+        
+        // Built-in parsing still works
+        let result = parser.parse_filename("The Black Keys - Lonely Boy.mp3");
+        assert_eq!(result.suggested_artist, "The Black Keys");
     }
     
     #[test]
@@ -3,12 +3,32 @@
 // Handles everything from file scanning to actual audio output
 
 pub mod player;          // core playback engine
+pub mod backend;         // swappable output: rodio, or a null backend for tests
+pub mod channel_mix;     // stereo, mono or crossfeed, ahead of the equalizer
+pub mod equalizer;       // ten-band EQ applied between decoder and sink
+pub mod visualizer;      // optional spectrum bars fed by a copy of the output
 pub mod track;           // track representation and metadata
 pub mod scanner;         // finds music files in directories
 pub mod metadata_parser; // extracts ID3 tags and such
+pub mod tag_writer;      // writes edited metadata back into MP3 and MP4 files
+pub mod wav;             // WAV duration and LIST/INFO or ID3 chunk tags
+pub mod silence;         // silent intros/outros, for skipping them during playback
+pub mod chapters;        // MP4 and Ogg chapter markers, for spoken word
 pub mod playlist;        // playlist management
+pub mod scan_cache;      // mtime/size cache so rescans skip unchanged files
+pub mod library_cache;   // whole-library snapshot so warm starts skip the walk
+pub mod scan_report;     // duplicates and unreadable files found by a scan
+pub mod library_stats;   // track counts, sizes and tag coverage for the Stats tab
+pub mod dedupe;          // hides or deletes the extra copies of duplicate tracks
+pub mod rename;          // renames files after their tags, with a preview first
+pub mod watcher;         // filesystem watcher for live library updates
+pub mod grouping;        // artist/album trees for the Library browse views
+pub mod search;          // precomputed fuzzy search over the library
+#[cfg(test)]
+mod test_support;        // temp-dir fixtures shared by the tests above
 
-pub use player::{AudioPlayer, PlaybackState};
+pub use backend::{NullBackend, PlaybackBackend};
+pub use player::{AudioPlayer, PlaybackState, RodioBackend};
 pub use track::{Track, TrackMetadata};
 pub use scanner::MusicScanner;
 
@@ -20,9 +40,15 @@ pub struct AudioConfig {
     pub crossfade_duration: u64, // milliseconds
     pub fade_in_duration: u64, // milliseconds for smooth track start
     pub fade_out_duration: u64, // milliseconds for smooth track stop
+    pub fade_on_pause: bool, // dip the volume out on pause and back in on resume
     pub buffer_size: usize,
     pub sample_rate: u32,
     pub channels: u16,
+    pub gapless: bool, // pre-queue the next track so albums play without gaps
+    pub speed: f32, // playback speed at startup, 1.0 is normal
+    pub equalizer_gains: [f32; equalizer::BAND_COUNT], // dB per band
+    pub channel_mode: channel_mix::ChannelMode, // applied to stereo sources only
+    pub visualizer: bool, // analyze the output for the spectrum pane
 }
 
 impl Default for AudioConfig {
@@ -32,18 +58,39 @@ impl Default for AudioConfig {
             crossfade_duration: 500,
             fade_in_duration: 300,  // 300ms smooth fade in
             fade_out_duration: 200, // 200ms smooth fade out
+            fade_on_pause: true,
             buffer_size: 65536, // Even larger buffer (16x) for ALSA underrun prevention
             sample_rate: 44100, // Standard CD quality
             channels: 2, // Stereo
+            gapless: false,
+            speed: 1.0,
+            equalizer_gains: [0.0; equalizer::BAND_COUNT],
+            channel_mode: channel_mix::ChannelMode::Stereo,
+            visualizer: false,
         }
     }
 }
 
 impl From<crate::config::Config> for AudioConfig {
-    fn from(_config: crate::config::Config) -> Self {
-        // For now, use default audio config
-        // Later we can add audio-specific config to the main Config
-        AudioConfig::default()
+    fn from(config: crate::config::Config) -> Self {
+        let mut audio = config.audio;
+        audio.clamp_to_ranges();
+        
+        // Buffer size and channel count stay at tuned defaults
+        AudioConfig {
+            volume: audio.volume,
+            crossfade_duration: audio.crossfade_duration,
+            fade_in_duration: audio.fade_in_duration,
+            fade_out_duration: audio.fade_out_duration,
+            fade_on_pause: audio.fade_on_pause,
+            sample_rate: audio.sample_rate,
+            gapless: audio.gapless,
+            speed: audio.playback_speed,
+            equalizer_gains: audio.equalizer.gains(),
+            channel_mode: audio.channel_mode,
+            visualizer: config.ui.visualizer,
+            ..AudioConfig::default()
+        }
     }
 }
 
@@ -63,7 +110,7 @@ impl AudioFormat {
             "mp3" => AudioFormat::Mp3,
             "flac" => AudioFormat::Flac,
             "ogg" | "oga" => AudioFormat::Ogg,
-            "mp4" | "m4a" | "aac" => AudioFormat::Mp4,
+            "mp4" | "m4a" | "m4b" | "aac" => AudioFormat::Mp4,
             "wav" => AudioFormat::Wav,
             _ => AudioFormat::Unknown,
         }
@@ -72,4 +119,15 @@ impl AudioFormat {
     pub fn is_supported(&self) -> bool {
         !matches!(self, AudioFormat::Unknown)
     }
+    
+    pub fn name(&self) -> &'static str {
+        match self {
+            AudioFormat::Mp3 => "MP3",
+            AudioFormat::Flac => "FLAC",
+            AudioFormat::Ogg => "Ogg",
+            AudioFormat::Mp4 => "AAC/M4A",
+            AudioFormat::Wav => "WAV",
+            AudioFormat::Unknown => "Other",
+        }
+    }
 }
@@ -1,12 +1,35 @@
+use super::backend::PlaybackBackend;
+use super::channel_mix::{ChannelMixSource, ChannelMixer};
+use super::equalizer::{Equalizer, EqualizerSource};
+use super::visualizer::{Visualizer, VisualizerTap};
 use super::{AudioConfig, Track};
 use anyhow::Result;
-use rodio::{Decoder, OutputStream, OutputStreamHandle, Sink};
+use rodio::source::{EmptyCallback, SamplesConverter};
+use rodio::{Decoder, OutputStream, OutputStreamHandle, Sample, Sink, Source};
+use rodio::cpal::traits::{DeviceTrait, HostTrait};
+use rodio::cpal::FromSample;
 use std::fs::File;
 use std::io::BufReader;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tokio::sync::mpsc;
 
+/// Playback speed range; rodio resamples, so pitch moves with the speed
+pub const MIN_SPEED: f32 = 0.5;
+pub const MAX_SPEED: f32 = 2.0;
+
+/// How often a playing track reports `PlayerEvent::PositionChanged`
+pub const POSITION_REPORT_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How often a queued follow-up checks whether it was cleared; it also checks before its first sample
+const SKIP_CHECK_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Keep a speed in range, falling back to normal speed for nonsense values
+pub fn clamp_speed(speed: f32) -> f32 {
+    if speed.is_finite() { speed.clamp(MIN_SPEED, MAX_SPEED) } else { 1.0 }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum PlaybackState {
     Stopped,
@@ -22,12 +45,183 @@ pub enum PlayerEvent {
     TrackStopped,
     TrackFinished(Track),
     DurationLearned(Track, Duration), // Track with learned duration from actual playback
-    PositionChanged(Duration),
+    PositionChanged(Duration), // after a seek or a pause, and every POSITION_REPORT_INTERVAL while playing
     VolumeChanged(f32),
     Error(String),
 }
 
+/// What the rest of the app plays through - delegates to a `PlaybackBackend`
+///
+/// Settings that don't depend on the backend, like gapless mode and the equalizer gains, live here.
 pub struct AudioPlayer {
+    backend: Box<dyn PlaybackBackend>,
+    gapless: bool,
+    channel_mixer: ChannelMixer, // Shared with the backend, so changes apply mid-track
+    equalizer: Equalizer, // Shared with the backend, so changes apply mid-track
+    visualizer: Visualizer, // Fed by the backend's sources while enabled
+}
+
+impl AudioPlayer {
+    /// Play through the system default output device
+    pub fn new(config: AudioConfig) -> Result<Self> {
+        let channel_mixer = ChannelMixer::new(config.channel_mode);
+        let equalizer = Equalizer::new(config.equalizer_gains);
+        let visualizer = Visualizer::new(config.visualizer);
+        let gapless = config.gapless;
+        let backend = RodioBackend::new(config, channel_mixer.clone(), equalizer.clone(), visualizer.clone())?;
+        Ok(Self { backend: Box::new(backend), gapless, channel_mixer, equalizer, visualizer })
+    }
+    
+    /// Play through the output device called `name`, as reported by `list_devices`
+    pub fn with_device(name: &str, config: AudioConfig) -> Result<Self> {
+        let channel_mixer = ChannelMixer::new(config.channel_mode);
+        let equalizer = Equalizer::new(config.equalizer_gains);
+        let visualizer = Visualizer::new(config.visualizer);
+        let gapless = config.gapless;
+        let backend = RodioBackend::with_device(name, config, channel_mixer.clone(), equalizer.clone(), visualizer.clone())?;
+        Ok(Self { backend: Box::new(backend), gapless, channel_mixer, equalizer, visualizer })
+    }
+    
+    /// Play through any backend, e.g. a `NullBackend` in tests; the backend brings its own volume and speed
+    pub fn with_backend(backend: Box<dyn PlaybackBackend>, config: &AudioConfig) -> Self {
+        Self {
+            backend,
+            gapless: config.gapless,
+            channel_mixer: ChannelMixer::new(config.channel_mode),
+            equalizer: Equalizer::new(config.equalizer_gains),
+            visualizer: Visualizer::new(config.visualizer),
+        }
+    }
+    
+    /// Names of the output devices on the default host
+    pub fn list_devices() -> Result<Vec<String>> {
+        RodioBackend::list_devices()
+    }
+    
+    /// Reopen the output stream on `name`, or the system default for `None`
+    ///
+    /// Playback stops; the old stream is kept if the new device can't be opened.
+    pub fn set_output_device(&mut self, name: Option<&str>) -> Result<()> {
+        self.backend.set_output_device(name)
+    }
+    
+    pub fn set_event_sender(&mut self, sender: mpsc::UnboundedSender<PlayerEvent>) {
+        self.backend.set_event_sender(sender);
+    }
+    
+    pub fn play_track(&self, track: Track) -> Result<()> {
+        self.backend.play(track)
+    }
+    
+    /// Gapless mode: append `track` behind the one playing so playback never runs dry
+    ///
+    /// Only one follow-up is held at a time. When the current track ends the player
+    /// sends `TrackFinished` for it and `TrackStarted` for this one, without any fades.
+    pub fn queue_next(&self, track: Track) -> Result<()> {
+        if !self.gapless {
+            return Ok(());
+        }
+        self.backend.queue_next(track)
+    }
+    
+    /// Take back the track handed to `queue_next`, e.g. once playback should stop after this one
+    pub fn clear_queued_next(&self) {
+        self.backend.clear_queued_next();
+    }
+    
+    pub fn is_gapless(&self) -> bool {
+        self.gapless
+    }
+    
+    /// Live channel mode - `set_mode` remixes the playing track without restarting it
+    pub fn channel_mixer(&self) -> &ChannelMixer {
+        &self.channel_mixer
+    }
+    
+    /// Live equalizer gains - `set_gains` retunes the playing track without restarting it
+    pub fn equalizer(&self) -> &Equalizer {
+        &self.equalizer
+    }
+    
+    /// Spectrum of what's playing - `set_enabled` switches the analysis on or off mid-track
+    pub fn visualizer(&self) -> &Visualizer {
+        &self.visualizer
+    }
+    
+    pub fn pause(&self) -> Result<()> {
+        self.backend.pause()
+    }
+    
+    pub fn resume(&self) -> Result<()> {
+        self.backend.resume()
+    }
+    
+    pub fn stop(&self) -> Result<()> {
+        self.backend.stop()
+    }
+    
+    pub fn set_volume(&mut self, volume: f32) -> Result<()> {
+        self.backend.set_volume(volume.clamp(0.0, 1.0))
+    }
+    
+    /// Play at `level` (0.0 to 1.0) of the configured volume without changing it
+    ///
+    /// For slow fades driven by the caller, like the sleep timer; `set_volume` or the next track resets it.
+    pub fn set_fade_level(&self, level: f32) {
+        self.backend.set_fade_level(level);
+    }
+    
+    /// Start and stop tracks with the configured fades, or cut straight in and out
+    pub fn set_fades(&self, enabled: bool) {
+        self.backend.set_fades(enabled);
+    }
+    
+    pub fn get_state(&self) -> PlaybackState {
+        self.backend.state()
+    }
+    
+    pub fn get_current_track(&self) -> Option<Track> {
+        self.backend.current_track()
+    }
+    
+    /// True once the current track has played to its end (or nothing is loaded)
+    pub fn is_finished(&self) -> bool {
+        self.backend.is_finished()
+    }
+    
+    pub fn get_volume(&self) -> f32 {
+        self.backend.volume()
+    }
+    
+    /// Jump to `position` in the current track
+    pub fn seek(&self, position: Duration) -> Result<()> {
+        self.backend.seek(position)
+    }
+    
+    /// Position in the current track, `None` when nothing is loaded
+    ///
+    /// Measured in track time, so at 2x it moves two seconds per second of wall clock.
+    pub fn get_position(&self) -> Option<Duration> {
+        self.backend.position()
+    }
+    
+    /// Play faster or slower, from `MIN_SPEED` to `MAX_SPEED`; applies to the playing track and every one after
+    ///
+    /// Pitch changes along with the speed - there's no time-stretching, so 2x sounds higher.
+    pub fn set_speed(&self, speed: f32) {
+        self.backend.set_speed(clamp_speed(speed));
+    }
+    
+    pub fn get_speed(&self) -> f32 {
+        self.backend.speed()
+    }
+}
+
+/// A track's decoder with position tracking, the channel mix, the equalizer and the visualizer tap on top, ready to append to a sink
+type DecodedTrack = VisualizerTap<EqualizerSource<ChannelMixSource<PositionSource<SamplesConverter<Decoder<BufReader<File>>, f32>>>>>;
+
+/// Plays through rodio on a cpal output device
+pub struct RodioBackend {
     _stream: OutputStream,
     stream_handle: OutputStreamHandle,
     sink: Arc<Mutex<Option<Sink>>>,
@@ -38,13 +232,43 @@ pub struct AudioPlayer {
     // Duration learning fields
     playback_start_time: Arc<Mutex<Option<std::time::Instant>>>,
     track_for_learning: Arc<Mutex<Option<Track>>>, // Track to learn duration for
+    // End-of-track detection
+    generation: Arc<AtomicU64>, // Bumped on every play/stop so stale callbacks stay quiet
+    finished: Arc<AtomicBool>,  // Set once the current track has genuinely played out
+    queued_next: Arc<Mutex<Option<Track>>>, // Gapless follow-up already appended to the sink
+    queued_skip: Mutex<Option<Arc<AtomicBool>>>, // Set to silence that follow-up; the sink can't drop it
+    channel_mixer: ChannelMixer, // Shared with every decoded source, ahead of the equalizer
+    equalizer: Equalizer, // Shared with every decoded source, so changes apply mid-track
+    visualizer: Visualizer, // Taps every decoded source after the equalizer
+    speed: Mutex<f32>,
+    position_ns: Arc<AtomicU64>, // written by the playing source - `sink.get_pos()` runs at wall-clock pace once sped up
+    fades: AtomicBool, // off cuts straight in and out, whatever the fade durations say
 }
 
-impl AudioPlayer {
-    pub fn new(config: AudioConfig) -> Result<Self> {
+impl RodioBackend {
+    pub fn new(config: AudioConfig, channel_mixer: ChannelMixer, equalizer: Equalizer, visualizer: Visualizer) -> Result<Self> {
         let (stream, stream_handle) = OutputStream::try_default()?;
+        Ok(Self::from_stream(stream, stream_handle, config, channel_mixer, equalizer, visualizer))
+    }
+    
+    /// Play through the output device called `name`, as reported by `list_devices`
+    pub fn with_device(name: &str, config: AudioConfig, channel_mixer: ChannelMixer, equalizer: Equalizer, visualizer: Visualizer) -> Result<Self> {
+        let (stream, stream_handle) = open_device(name)?;
+        Ok(Self::from_stream(stream, stream_handle, config, channel_mixer, equalizer, visualizer))
+    }
+    
+    /// Names of the output devices on the default host
+    pub fn list_devices() -> Result<Vec<String>> {
+        let host = rodio::cpal::default_host();
+        Ok(host.output_devices()?
+            .filter_map(|device| device.name().ok())
+            .collect())
+    }
+    
+    fn from_stream(stream: OutputStream, stream_handle: OutputStreamHandle, config: AudioConfig, channel_mixer: ChannelMixer, equalizer: Equalizer, visualizer: Visualizer) -> Self {
+        let speed = Mutex::new(clamp_speed(config.speed));
         
-        Ok(Self {
+        Self {
             _stream: stream,
             stream_handle,
             sink: Arc::new(Mutex::new(None)),
@@ -54,21 +278,21 @@ impl AudioPlayer {
             event_sender: None,
             playback_start_time: Arc::new(Mutex::new(None)),
             track_for_learning: Arc::new(Mutex::new(None)),
-        })
-    }
-    
-    pub fn set_event_sender(&mut self, sender: mpsc::UnboundedSender<PlayerEvent>) {
-        self.event_sender = Some(sender);
+            generation: Arc::new(AtomicU64::new(0)),
+            finished: Arc::new(AtomicBool::new(false)),
+            queued_next: Arc::new(Mutex::new(None)),
+            queued_skip: Mutex::new(None),
+            channel_mixer,
+            equalizer,
+            visualizer,
+            speed,
+            position_ns: Arc::new(AtomicU64::new(0)),
+            fades: AtomicBool::new(true),
+        }
     }
     
-    pub fn play_track(&self, track: Track) -> Result<()> {
-        // Stop current playback
-        self.stop()?;
-        
-        // Create new sink
-        let sink = Sink::try_new(&self.stream_handle)?;
-        sink.set_volume(self.config.volume);
-        
+    /// Open and decode `track` behind the channel mix, equalizer and visualizer, reporting failures as player errors
+    fn decode(&self, track: &Track) -> Result<DecodedTrack> {
         // Load and decode the audio file with error handling
         let file = match File::open(&track.file_path) {
             Ok(f) => f,
@@ -93,8 +317,190 @@ impl AudioPlayer {
             }
         };
         
-        // Start playback with fade in
-        sink.append(source);
+        let source = PositionSource::new(source.convert_samples(), Arc::clone(&self.position_ns));
+        Ok(self.visualizer.tap(self.equalizer.apply(self.channel_mixer.apply(source))))
+    }
+    
+    /// Callback run by the sink when playback reaches the end of `track`
+    ///
+    /// If a gapless follow-up was queued it is already playing, so it becomes the current track.
+    fn finish_callback(&self, generation: u64, track: Track) -> impl Fn() + Send + 'static {
+        let current_generation = Arc::clone(&self.generation);
+        let finished = Arc::clone(&self.finished);
+        let state = Arc::clone(&self.state);
+        let current_track = Arc::clone(&self.current_track);
+        let queued_next = Arc::clone(&self.queued_next);
+        let playback_start_time = Arc::clone(&self.playback_start_time);
+        let track_for_learning = Arc::clone(&self.track_for_learning);
+        let sender = self.event_sender.clone();
+        let fired = AtomicBool::new(false);
+        
+        move || {
+            // Stopped or replaced since this track started
+            if current_generation.load(Ordering::SeqCst) != generation {
+                return;
+            }
+            if fired.swap(true, Ordering::SeqCst) {
+                return;
+            }
+            
+            if let Some(sender) = &sender {
+                let _ = sender.send(PlayerEvent::TrackFinished(track.clone()));
+            }
+            
+            match queued_next.lock().unwrap().take() {
+                Some(next) => {
+                    *current_track.lock().unwrap() = Some(next.clone());
+                    start_duration_learning(&playback_start_time, &track_for_learning, &next);
+                    if let Some(sender) = &sender {
+                        let _ = sender.send(PlayerEvent::TrackStarted(next));
+                    }
+                }
+                None => {
+                    finished.store(true, Ordering::SeqCst);
+                    *state.lock().unwrap() = PlaybackState::Stopped;
+                }
+            }
+        }
+    }
+    
+    /// Report the real position every `POSITION_REPORT_INTERVAL` while `generation` plays
+    ///
+    /// The thread ends once the track is stopped or replaced, plays out, the sink is dropped
+    /// or nobody listens any more, so track changes don't leave threads behind.
+    fn spawn_position_monitor(&self, generation: u64) {
+        let Some(sender) = self.event_sender.clone() else {
+            return;
+        };
+        let sink = Arc::downgrade(&self.sink);
+        let current_generation = Arc::clone(&self.generation);
+        let finished = Arc::clone(&self.finished);
+        let state = Arc::clone(&self.state);
+        let position_ns = Arc::clone(&self.position_ns);
+        
+        std::thread::spawn(move || loop {
+            std::thread::sleep(POSITION_REPORT_INTERVAL);
+            
+            let Some(sink) = sink.upgrade() else {
+                return;
+            };
+            let replaced = current_generation.load(Ordering::SeqCst) != generation;
+            if replaced || finished.load(Ordering::SeqCst) || sink.lock().unwrap().is_none() {
+                return;
+            }
+            
+            if *state.lock().unwrap() == PlaybackState::Playing {
+                let position = Duration::from_nanos(position_ns.load(Ordering::SeqCst));
+                if sender.send(PlayerEvent::PositionChanged(position)).is_err() {
+                    return;
+                }
+            }
+        });
+    }
+    
+    /// Smooth fade in effect for professional track start
+    fn fade_in(&self, sink: &Sink) -> Result<()> {
+        let target_volume = self.config.volume;
+        let fade_duration = self.config.fade_in_duration;
+        
+        if fade_duration == 0 || !self.fades.load(Ordering::SeqCst) {
+            // No fade - set volume immediately
+            sink.set_volume(target_volume);
+            return Ok(());
+        }
+        
+        // Start from silence and perform immediate fade
+        sink.set_volume(0.0);
+        
+        let fade_steps = 10; // Fewer steps for immediate effect
+        let step_duration = fade_duration / fade_steps;
+        let volume_step = target_volume / fade_steps as f32;
+        
+        // Perform fade synchronously for immediate effect
+        for step in 1..=fade_steps {
+            let current_volume = volume_step * step as f32;
+            sink.set_volume(current_volume);
+            
+            // Small delay for smooth transition
+            std::thread::sleep(std::time::Duration::from_millis(step_duration));
+        }
+        
+        // Ensure final volume is exact
+        sink.set_volume(target_volume);
+        
+        Ok(())
+    }
+    
+    /// Smooth fade out effect for professional track stop
+    fn fade_out(&self, sink: &Sink) -> Result<()> {
+        let current_volume = self.config.volume;
+        let fade_duration = self.config.fade_out_duration;
+        
+        if fade_duration == 0 || !self.fades.load(Ordering::SeqCst) {
+            // No fade - stop immediately
+            return Ok(());
+        }
+        
+        let fade_steps = 15; // 15 steps for quick but smooth fade out
+        let step_duration = fade_duration / fade_steps;
+        let volume_step = current_volume / fade_steps as f32;
+        
+        // Perform fade out synchronously for immediate effect
+        for step in 1..=fade_steps {
+            let new_volume = current_volume - (volume_step * step as f32);
+            sink.set_volume(new_volume.max(0.0));
+            
+            std::thread::sleep(std::time::Duration::from_millis(step_duration));
+        }
+        
+        // Ensure final silence
+        sink.set_volume(0.0);
+        
+        Ok(())
+    }
+    
+    /// Quick fade out for pause transitions (shorter duration)
+    fn fade_out_quick(&self, sink: &Sink) -> Result<()> {
+        let current_volume = self.config.volume;
+        let fade_duration = 100; // Quick 100ms fade for pause
+        
+        let fade_steps = 10; // 10 steps for quick fade
+        let step_duration = fade_duration / fade_steps;
+        let volume_step = current_volume / fade_steps as f32;
+        
+        // Perform quick fade out synchronously
+        for step in 1..=fade_steps {
+            let new_volume = current_volume - (volume_step * step as f32);
+            sink.set_volume(new_volume.max(0.0));
+            
+            std::thread::sleep(std::time::Duration::from_millis(step_duration));
+        }
+        
+        Ok(())
+    }
+}
+
+impl PlaybackBackend for RodioBackend {
+    fn set_event_sender(&mut self, sender: mpsc::UnboundedSender<PlayerEvent>) {
+        self.event_sender = Some(sender);
+    }
+    
+    fn play(&self, track: Track) -> Result<()> {
+        // Stop current playback
+        self.stop()?;
+        
+        // Create new sink
+        let sink = Sink::try_new(&self.stream_handle)?;
+        sink.set_volume(self.config.volume);
+        sink.set_speed(*self.speed.lock().unwrap());
+        self.position_ns.store(0, Ordering::SeqCst);
+        
+        let source = self.decode(&track)?;
+        
+        // Start playback with fade in; the callback queued behind the track is our end-of-track signal
+        let generation = self.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        self.finished.store(false, Ordering::SeqCst);
+        append_with_finish_callback(&sink, source, self.finish_callback(generation, track.clone()));
         
         // Apply fade in effect for smooth start
         self.fade_in(&sink)?;
@@ -116,16 +522,10 @@ impl AudioPlayer {
         }
         
         // Start duration learning if track has no duration
-        if track.duration.is_none() {
-            let mut start_time_guard = self.playback_start_time.lock().unwrap();
-            *start_time_guard = Some(std::time::Instant::now());
-            
-            let mut learning_track_guard = self.track_for_learning.lock().unwrap();
-            *learning_track_guard = Some(track.clone());
-        }
+        start_duration_learning(&self.playback_start_time, &self.track_for_learning, &track);
         
-        // NOTE: Removed problematic completion monitor that was interfering with playback
-        // Duration learning will be handled by the existing UI-based completion detection
+        // Completion is reported by the finish callback queued behind the source
+        self.spawn_position_monitor(generation);
         
         // Send success event
         if let Some(sender) = &self.event_sender {
@@ -135,33 +535,85 @@ impl AudioPlayer {
         Ok(())
     }
     
-    pub fn pause(&self) -> Result<()> {
+    /// Appends `track` to the sink, so it never runs dry and nothing fades in
+    fn queue_next(&self, track: Track) -> Result<()> {
+        if self.queued_next.lock().unwrap().is_some() {
+            return Ok(());
+        }
+        
+        let source = self.decode(&track)?;
+        
+        let sink_guard = self.sink.lock().unwrap();
+        let Some(sink) = sink_guard.as_ref() else {
+            return Ok(());
+        };
+        
+        let skip = Arc::new(AtomicBool::new(false));
+        let source = skippable(source, Arc::clone(&skip));
+        let on_finish = {
+            let skip = Arc::clone(&skip);
+            let finish = self.finish_callback(self.generation.load(Ordering::SeqCst), track.clone());
+            move || {
+                if !skip.load(Ordering::SeqCst) {
+                    finish();
+                }
+            }
+        };
+        
+        // Same generation as the current track - a stop or new play invalidates both
+        *self.queued_skip.lock().unwrap() = Some(skip);
+        *self.queued_next.lock().unwrap() = Some(track);
+        append_with_finish_callback(sink, source, on_finish);
+        
+        Ok(())
+    }
+    
+    fn clear_queued_next(&self) {
+        // Once the follow-up has started it is the current track and stays
+        if self.queued_next.lock().unwrap().take().is_some() {
+            if let Some(skip) = self.queued_skip.lock().unwrap().take() {
+                skip.store(true, Ordering::SeqCst);
+            }
+        }
+    }
+    
+    fn pause(&self) -> Result<()> {
         if let Some(sink) = self.sink.lock().unwrap().as_ref() {
-            // Apply quick fade out before pausing for smooth transition
-            let _ = self.fade_out_quick(sink);
+            // Only a playing track can pause, so a repeated pause doesn't report again
+            if *self.state.lock().unwrap() != PlaybackState::Playing {
+                return Ok(());
+            }
             
+            if self.config.fade_on_pause {
+                let _ = self.fade_out_quick(sink);
+            }
             sink.pause();
-            
-            let mut state_guard = self.state.lock().unwrap();
-            *state_guard = PlaybackState::Paused;
+            *self.state.lock().unwrap() = PlaybackState::Paused;
             
             if let Some(sender) = &self.event_sender {
                 let _ = sender.send(PlayerEvent::TrackPaused);
+                // Where it really stopped; the last periodic report can be up to an interval behind
+                let position = Duration::from_nanos(self.position_ns.load(Ordering::SeqCst));
+                let _ = sender.send(PlayerEvent::PositionChanged(position));
             }
         }
         
         Ok(())
     }
     
-    pub fn resume(&self) -> Result<()> {
+    fn resume(&self) -> Result<()> {
         if let Some(sink) = self.sink.lock().unwrap().as_ref() {
-            sink.play();
-            
-            // Apply fade in effect when resuming for smooth transition
-            let _ = self.fade_in(sink);
+            if *self.state.lock().unwrap() != PlaybackState::Paused {
+                return Ok(());
+            }
             
-            let mut state_guard = self.state.lock().unwrap();
-            *state_guard = PlaybackState::Playing;
+            sink.play();
+            if self.config.fade_on_pause {
+                let _ = self.fade_in(sink);
+            } else {
+                sink.set_volume(self.config.volume);
+            }
+            *self.state.lock().unwrap() = PlaybackState::Playing;
             
             if let Some(sender) = &self.event_sender {
                 let _ = sender.send(PlayerEvent::TrackResumed);
@@ -171,7 +623,11 @@ impl AudioPlayer {
         Ok(())
     }
     
-    pub fn stop(&self) -> Result<()> {
+    fn stop(&self) -> Result<()> {
+        // Anything still queued behind the old track must not report it as finished
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        self.queued_next.lock().unwrap().take();
+        
         {
             let mut sink_guard = self.sink.lock().unwrap();
             if let Some(sink) = sink_guard.as_ref() {
@@ -197,119 +653,382 @@ impl AudioPlayer {
         Ok(())
     }
     
-    pub fn set_volume(&mut self, volume: f32) -> Result<()> {
-        let clamped_volume = volume.clamp(0.0, 1.0);
-        self.config.volume = clamped_volume;
+    fn set_volume(&mut self, volume: f32) -> Result<()> {
+        self.config.volume = volume;
         
         if let Some(sink) = self.sink.lock().unwrap().as_ref() {
-            sink.set_volume(clamped_volume);
+            sink.set_volume(volume);
         }
         
         if let Some(sender) = &self.event_sender {
-            let _ = sender.send(PlayerEvent::VolumeChanged(clamped_volume));
+            let _ = sender.send(PlayerEvent::VolumeChanged(volume));
         }
         
         Ok(())
     }
     
-    pub fn get_state(&self) -> PlaybackState {
+    fn volume(&self) -> f32 {
+        self.config.volume
+    }
+    
+    fn set_fade_level(&self, level: f32) {
+        if let Some(sink) = self.sink.lock().unwrap().as_ref() {
+            sink.set_volume(self.config.volume * level.clamp(0.0, 1.0));
+        }
+    }
+    
+    fn set_fades(&self, enabled: bool) {
+        self.fades.store(enabled, Ordering::SeqCst);
+    }
+    
+    fn state(&self) -> PlaybackState {
         self.state.lock().unwrap().clone()
     }
     
-    pub fn get_current_track(&self) -> Option<Track> {
+    fn current_track(&self) -> Option<Track> {
         self.current_track.lock().unwrap().clone()
     }
     
-    pub fn is_finished(&self) -> bool {
+    /// `sink.empty()` can't be used for this - it reports true right after `append`,
+    /// before the decoder has produced anything.
+    fn is_finished(&self) -> bool {
+        self.sink.lock().unwrap().is_none() || self.finished.load(Ordering::SeqCst)
+    }
+    
+    fn seek(&self, position: Duration) -> Result<()> {
+        if let Some(sink) = self.sink.lock().unwrap().as_ref() {
+            sink.try_seek(position)
+                .map_err(|e| anyhow::anyhow!("Failed to seek: {}", e))?;
+            
+            if let Some(sender) = &self.event_sender {
+                let _ = sender.send(PlayerEvent::PositionChanged(position));
+            }
+        }
+        
+        Ok(())
+    }
+    
+    fn position(&self) -> Option<Duration> {
         self.sink.lock().unwrap()
             .as_ref()
-            .map(|sink| sink.empty())
-            .unwrap_or(true)
+            .map(|_| Duration::from_nanos(self.position_ns.load(Ordering::SeqCst)))
     }
     
-    pub fn get_volume(&self) -> f32 {
-        self.config.volume
+    fn set_speed(&self, speed: f32) {
+        *self.speed.lock().unwrap() = speed;
+        if let Some(sink) = self.sink.lock().unwrap().as_ref() {
+            sink.set_speed(speed);
+        }
+    }
+    
+    fn speed(&self) -> f32 {
+        *self.speed.lock().unwrap()
     }
+    
+    /// Playback stops; the old stream is kept if the new device can't be opened.
+    fn set_output_device(&mut self, name: Option<&str>) -> Result<()> {
+        let (stream, stream_handle) = match name {
+            Some(name) => open_device(name)?,
+            None => OutputStream::try_default()?,
+        };
+        
+        self.stop()?;
+        self._stream = stream;
+        self.stream_handle = stream_handle;
+        Ok(())
+    }
+}
 
-    /// Smooth fade in effect for professional track start
-    fn fade_in(&self, sink: &Sink) -> Result<()> {
-        let target_volume = self.config.volume;
-        let fade_duration = self.config.fade_in_duration;
+/// Open an output stream on the device called `name`
+fn open_device(name: &str) -> Result<(OutputStream, OutputStreamHandle)> {
+    let device = rodio::cpal::default_host()
+        .output_devices()?
+        .find(|device| device.name().is_ok_and(|n| n == name))
+        .ok_or_else(|| anyhow::anyhow!("Audio output device '{}' not found", name))?;
+    
+    Ok(OutputStream::try_from_device(&device)?)
+}
+
+/// Time playback of `track` if it has no duration yet, so the real length can be learned
+fn start_duration_learning(
+    start_time: &Mutex<Option<std::time::Instant>>,
+    learning_track: &Mutex<Option<Track>>,
+    track: &Track,
+) {
+    if track.duration.is_none() {
+        *start_time.lock().unwrap() = Some(std::time::Instant::now());
+        *learning_track.lock().unwrap() = Some(track.clone());
+    }
+}
+
+/// Passes samples through while publishing how far into the track they are
+///
+/// Sits before rodio's speed control, so the position is in track time at any speed.
+/// A gapless follow-up shares the counter and takes it over when it starts producing.
+struct PositionSource<S> {
+    input: S,
+    samples: u64,
+    position_ns: Arc<AtomicU64>,
+}
+
+impl<S: Source<Item = f32>> PositionSource<S> {
+    fn new(input: S, position_ns: Arc<AtomicU64>) -> Self {
+        Self { input, samples: 0, position_ns }
+    }
+    
+    fn samples_per_second(&self) -> u64 {
+        self.input.sample_rate() as u64 * self.input.channels() as u64
+    }
+}
+
+impl<S: Source<Item = f32>> Iterator for PositionSource<S> {
+    type Item = f32;
+    
+    fn next(&mut self) -> Option<f32> {
+        let sample = self.input.next()?;
+        self.samples += 1;
         
-        if fade_duration == 0 {
-            // No fade - set volume immediately
-            sink.set_volume(target_volume);
-            return Ok(());
+        let per_second = self.samples_per_second();
+        if per_second > 0 {
+            let nanos = self.samples as u128 * 1_000_000_000 / per_second as u128;
+            self.position_ns.store(nanos as u64, Ordering::Relaxed);
         }
+        Some(sample)
+    }
+}
+
+impl<S: Source<Item = f32>> Source for PositionSource<S> {
+    fn current_frame_len(&self) -> Option<usize> {
+        self.input.current_frame_len()
+    }
+    
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+    
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+    
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+    
+    fn try_seek(&mut self, pos: Duration) -> Result<(), rodio::source::SeekError> {
+        self.input.try_seek(pos)?;
+        self.samples = (pos.as_secs_f64() * self.samples_per_second() as f64) as u64;
+        self.position_ns.store(pos.as_nanos() as u64, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+/// `source`, ending before its next sample once `skip` is set
+///
+/// A sink can't drop what was appended to it, so this is how a cleared follow-up is taken back.
+fn skippable<S>(source: S, skip: Arc<AtomicBool>) -> impl Source<Item = S::Item>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    source.stoppable().periodic_access(SKIP_CHECK_INTERVAL, move |source| {
+        if skip.load(Ordering::SeqCst) {
+            source.stop();
+        }
+    })
+}
+
+/// Queue `source` followed by a silent marker that runs `on_finish` once the source is exhausted
+fn append_with_finish_callback<S>(sink: &Sink, source: S, on_finish: impl Fn() + Send + 'static)
+where
+    S: Source + Send + 'static,
+    f32: FromSample<S::Item>,
+    S::Item: Sample + Send,
+{
+    sink.append(source);
+    sink.append::<EmptyCallback<f32>>(EmptyCallback::new(Box::new(on_finish)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::NullBackend;
+    use rodio::buffer::SamplesBuffer;
+    use std::path::PathBuf;
+    use std::sync::atomic::AtomicUsize;
+    
+    fn null_player(gapless: bool) -> (AudioPlayer, NullBackend, mpsc::UnboundedReceiver<PlayerEvent>) {
+        let backend = NullBackend::new();
+        let config = AudioConfig { gapless, ..AudioConfig::default() };
+        let mut player = AudioPlayer::with_backend(Box::new(backend.clone()), &config);
+        let (event_tx, event_rx) = mpsc::unbounded_channel();
+        player.set_event_sender(event_tx);
+        (player, backend, event_rx)
+    }
+    
+    fn drain(events: &mut mpsc::UnboundedReceiver<PlayerEvent>) -> Vec<String> {
+        std::iter::from_fn(|| events.try_recv().ok())
+            .map(|event| match event {
+                PlayerEvent::TrackStarted(track) => format!("started {}", track.file_path.display()),
+                PlayerEvent::TrackFinished(track) => format!("finished {}", track.file_path.display()),
+                PlayerEvent::PositionChanged(position) => format!("position {}", position.as_secs()),
+                PlayerEvent::VolumeChanged(volume) => format!("volume {}", volume),
+                other => format!("{:?}", other),
+            })
+            .collect()
+    }
+    
+    #[test]
+    fn test_null_backend_reports_playback() {
+        let (mut player, backend, mut events) = null_player(false);
+        assert!(player.is_finished());
+        assert_eq!(player.get_position(), None);
         
-        // Start from silence and perform immediate fade
-        sink.set_volume(0.0);
-        
-        let fade_steps = 10; // Fewer steps for immediate effect
-        let step_duration = fade_duration / fade_steps;
-        let volume_step = target_volume / fade_steps as f32;
+        player.play_track(Track::new(PathBuf::from("a.mp3"))).unwrap();
+        backend.advance(Duration::from_secs(5));
+        player.pause().unwrap();
+        backend.advance(Duration::from_secs(5)); // paused, so no progress
+        assert_eq!(player.get_position(), Some(Duration::from_secs(5)));
+        assert_eq!(player.get_state(), PlaybackState::Paused);
         
-        // Perform fade synchronously for immediate effect
-        for step in 1..=fade_steps {
-            let current_volume = volume_step * step as f32;
-            sink.set_volume(current_volume);
-            
-            // Small delay for smooth transition
-            std::thread::sleep(std::time::Duration::from_millis(step_duration));
-        }
+        player.resume().unwrap();
+        player.seek(Duration::from_secs(42)).unwrap();
+        player.set_volume(1.5).unwrap();
+        assert_eq!(player.get_volume(), 1.0);
         
-        // Ensure final volume is exact
-        sink.set_volume(target_volume);
+        // Position is track time, so it runs ahead of the wall clock when sped up
+        player.set_speed(5.0);
+        assert_eq!(player.get_speed(), MAX_SPEED);
+        backend.advance(Duration::from_secs(10));
+        assert_eq!(player.get_position(), Some(Duration::from_secs(62)));
+        player.stop().unwrap();
         
-        Ok(())
+        assert_eq!(drain(&mut events), vec![
+            "TrackStopped", "started a.mp3", "position 5", "TrackPaused", "position 5", "TrackResumed", "position 42", "volume 1",
+            "position 62", "TrackStopped",
+        ]);
+        assert!(player.is_finished());
+        assert_eq!(player.get_state(), PlaybackState::Stopped);
     }
     
-    /// Smooth fade out effect for professional track stop
-    fn fade_out(&self, sink: &Sink) -> Result<()> {
-        let current_volume = self.config.volume;
-        let fade_duration = self.config.fade_out_duration;
+    #[test]
+    fn test_pause_and_resume_report_once() {
+        let (player, _backend, mut events) = null_player(false);
+        player.resume().unwrap(); // nothing loaded yet
+        player.play_track(Track::new(PathBuf::from("a.mp3"))).unwrap();
+        player.resume().unwrap(); // already playing
+        player.pause().unwrap();
+        player.pause().unwrap();
+        player.resume().unwrap();
+        player.resume().unwrap();
         
-        if fade_duration == 0 {
-            // No fade - stop immediately
-            return Ok(());
-        }
+        assert_eq!(drain(&mut events), vec!["TrackStopped", "started a.mp3", "TrackPaused", "position 0", "TrackResumed"]);
+        assert_eq!(player.get_state(), PlaybackState::Playing);
+    }
+    
+    #[test]
+    fn test_gapless_follow_up_starts_when_track_ends() {
+        // Without gapless mode nothing is queued and the track just ends
+        let (player, backend, mut events) = null_player(false);
+        player.play_track(Track::new(PathBuf::from("a.mp3"))).unwrap();
+        player.queue_next(Track::new(PathBuf::from("b.mp3"))).unwrap();
+        assert!(backend.queued_next().is_none());
+        backend.finish_track();
+        assert!(player.is_finished());
+        assert_eq!(drain(&mut events), vec!["TrackStopped", "started a.mp3", "finished a.mp3"]);
         
-        let fade_steps = 15; // 15 steps for quick but smooth fade out
-        let step_duration = fade_duration / fade_steps;
-        let volume_step = current_volume / fade_steps as f32;
+        let (player, backend, mut events) = null_player(true);
+        player.play_track(Track::new(PathBuf::from("a.mp3"))).unwrap();
+        player.queue_next(Track::new(PathBuf::from("b.mp3"))).unwrap();
+        player.queue_next(Track::new(PathBuf::from("c.mp3"))).unwrap(); // one follow-up at a time
+        backend.finish_track();
         
-        // Perform fade out synchronously for immediate effect
-        for step in 1..=fade_steps {
-            let new_volume = current_volume - (volume_step * step as f32);
-            sink.set_volume(new_volume.max(0.0));
-            
-            std::thread::sleep(std::time::Duration::from_millis(step_duration));
-        }
+        assert!(!player.is_finished());
+        assert_eq!(player.get_current_track().unwrap().file_path, PathBuf::from("b.mp3"));
+        assert_eq!(drain(&mut events), vec!["TrackStopped", "started a.mp3", "finished a.mp3", "started b.mp3"]);
+    }
+    
+    #[test]
+    fn test_position_is_in_track_time() {
+        let position_ns = Arc::new(AtomicU64::new(0));
+        let buffer = SamplesBuffer::new(2, 1_000, vec![0.0f32; 8_000]); // 4 seconds of stereo
+        let mut source = PositionSource::new(buffer, Arc::clone(&position_ns));
         
-        // Ensure final silence
-        sink.set_volume(0.0);
+        // Sped up through rodio, one second of track is still 2000 samples here
+        source.by_ref().take(2_000).for_each(drop);
+        assert_eq!(Duration::from_nanos(position_ns.load(Ordering::SeqCst)), Duration::from_secs(1));
         
-        Ok(())
+        source.try_seek(Duration::from_secs(3)).unwrap();
+        assert_eq!(Duration::from_nanos(position_ns.load(Ordering::SeqCst)), Duration::from_secs(3));
+        source.by_ref().take(1_000).for_each(drop);
+        assert_eq!(Duration::from_nanos(position_ns.load(Ordering::SeqCst)), Duration::from_millis(3_500));
     }
     
-    /// Quick fade out for pause transitions (shorter duration)
-    fn fade_out_quick(&self, sink: &Sink) -> Result<()> {
-        let current_volume = self.config.volume;
-        let fade_duration = 100; // Quick 100ms fade for pause
+    #[test]
+    fn test_finish_callback_fires_once_at_end() {
+        let (sink, mut output) = Sink::new_idle();
+        let fired = Arc::new(AtomicUsize::new(0));
         
-        let fade_steps = 10; // 10 steps for quick fade
-        let step_duration = fade_duration / fade_steps;
-        let volume_step = current_volume / fade_steps as f32;
+        let counter = Arc::clone(&fired);
+        let source = SamplesBuffer::new(1, 44_100, vec![0.25f32; 2_000]);
+        append_with_finish_callback(&sink, source, move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
         
-        // Perform quick fade out synchronously
-        for step in 1..=fade_steps {
-            let new_volume = current_volume - (volume_step * step as f32);
-            sink.set_volume(new_volume.max(0.0));
-            
-            std::thread::sleep(std::time::Duration::from_millis(step_duration));
+        // Nothing fires at the start, or partway through
+        assert_eq!(fired.load(Ordering::SeqCst), 0);
+        output.by_ref().take(1_500).for_each(drop);
+        assert_eq!(fired.load(Ordering::SeqCst), 0);
+        
+        // Playing past the end fires exactly once, even as the idle queue keeps producing silence
+        output.by_ref().take(1_000).for_each(drop);
+        assert_eq!(fired.load(Ordering::SeqCst), 1);
+        output.by_ref().take(10_000).for_each(drop);
+        assert_eq!(fired.load(Ordering::SeqCst), 1);
+    }
+    
+    #[test]
+    fn test_queued_tracks_report_each_boundary() {
+        let (sink, mut output) = Sink::new_idle();
+        let boundaries = Arc::new(Mutex::new(Vec::new()));
+        
+        for (name, samples) in [("first", 1_000), ("second", 1_000)] {
+            let log = Arc::clone(&boundaries);
+            let source = SamplesBuffer::new(1, 44_100, vec![0.25f32; samples]);
+            append_with_finish_callback(&sink, source, move || log.lock().unwrap().push(name));
         }
         
-        Ok(())
+        // The first boundary fires while the second track is already queued behind it
+        output.by_ref().take(1_200).for_each(drop);
+        assert_eq!(*boundaries.lock().unwrap(), vec!["first"]);
+        assert!(!sink.empty());
+        
+        output.by_ref().take(1_200).for_each(drop);
+        assert_eq!(*boundaries.lock().unwrap(), vec!["first", "second"]);
+    }
+    
+    #[test]
+    fn test_skipped_follow_up_never_plays() {
+        let (sink, mut output) = Sink::new_idle();
+        let skip = Arc::new(AtomicBool::new(false));
+        sink.append(SamplesBuffer::new(1, 44_100, vec![0.25f32; 1_000]));
+        sink.append(skippable(SamplesBuffer::new(1, 44_100, vec![0.5f32; 1_000]), Arc::clone(&skip)));
+        
+        skip.store(true, Ordering::SeqCst);
+        let heard: Vec<f32> = output.by_ref().take(2_000).collect();
+        assert!(heard[..1_000].iter().all(|&sample| sample == 0.25));
+        assert!(heard[1_000..].iter().all(|&sample| sample != 0.5));
+        assert!(sink.empty());
+    }
+    
+    #[test]
+    fn test_cleared_follow_up_does_not_start() {
+        let (player, backend, mut events) = null_player(true);
+        player.play_track(Track::new(PathBuf::from("a.mp3"))).unwrap();
+        player.queue_next(Track::new(PathBuf::from("b.mp3"))).unwrap();
+        player.clear_queued_next();
+        backend.finish_track();
+        
+        assert!(player.is_finished());
+        assert_eq!(drain(&mut events), vec!["TrackStopped", "started a.mp3", "finished a.mp3"]);
     }
-
 }
@@ -1,11 +1,15 @@
 use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 use serde::{Deserialize, Serialize};
 use tracing::{info, warn};
 use uuid::Uuid;
 
+use super::rename::sanitize_file_name;
 use super::track::Track;
+use crate::export::{ExportFormat, ExportManager, PathStyle, PlaylistExport};
 
 /// Represents a single playlist with metadata and track references
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,7 +21,22 @@ pub struct Playlist {
     pub modified_at: chrono::DateTime<chrono::Utc>,
     pub track_paths: Vec<PathBuf>,  // Store file paths instead of full Track objects
     pub track_count: usize,
-    pub total_duration: Option<u64>, // Total duration in seconds
+    pub total_duration: Option<u64>, // Total duration in milliseconds, None while any track's is unknown
+    #[serde(default)]
+    track_durations: HashMap<PathBuf, u64>, // milliseconds, for the tracks whose duration was known when added
+    #[serde(default)]
+    pub shuffle: Option<bool>, // None follows the app's shuffle toggle
+    #[serde(default)]
+    pub repeat: Option<PlaylistRepeat>, // None follows the app's repeat mode
+}
+
+/// Repeat mode a playlist can insist on while it's the one playing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlaylistRepeat {
+    Off, // stop after the last track
+    All, // start over from the first
+    One, // play the same track again
 }
 
 impl Playlist {
@@ -32,14 +51,20 @@ impl Playlist {
             modified_at: now,
             track_paths: Vec::new(),
             track_count: 0,
-            total_duration: None,
+            total_duration: Some(0),
+            track_durations: HashMap::new(),
+            shuffle: None,
+            repeat: None,
         }
     }
 
-    /// Add a track to the playlist
-    pub fn add_track(&mut self, track_path: PathBuf) {
+    /// Add a track to the playlist, with its duration if the library knows it
+    pub fn add_track(&mut self, track_path: PathBuf, duration: Option<Duration>) {
         if !self.track_paths.contains(&track_path) {
             info!("Added track '{}' to playlist '{}'", track_path.display(), self.name);
+            if let Some(duration) = duration {
+                self.track_durations.insert(track_path.clone(), duration.as_millis() as u64);
+            }
             self.track_paths.push(track_path);
             self.track_count = self.track_paths.len();
             self.modified_at = chrono::Utc::now();
@@ -51,6 +76,7 @@ impl Playlist {
     pub fn remove_track(&mut self, track_path: &Path) -> bool {
         if let Some(pos) = self.track_paths.iter().position(|p| p == track_path) {
             self.track_paths.remove(pos);
+            self.track_durations.remove(track_path);
             self.track_count = self.track_paths.len();
             self.modified_at = chrono::Utc::now();
             self.update_total_duration();
@@ -61,12 +87,26 @@ impl Playlist {
         }
     }
 
+    /// Point entries for a file that was renamed or moved at its new path
+    pub fn replace_track_path(&mut self, old: &Path, new: &Path) -> bool {
+        let Some(pos) = self.track_paths.iter().position(|p| p == old) else {
+            return false;
+        };
+        self.track_paths[pos] = new.to_path_buf();
+        if let Some(duration) = self.track_durations.remove(old) {
+            self.track_durations.insert(new.to_path_buf(), duration);
+        }
+        self.modified_at = chrono::Utc::now();
+        true
+    }
+
     /// Move a track to a different position in the playlist
     pub fn move_track(&mut self, from_index: usize, to_index: usize) -> bool {
         if from_index < self.track_paths.len() && to_index < self.track_paths.len() {
             let track = self.track_paths.remove(from_index);
             self.track_paths.insert(to_index, track);
             self.modified_at = chrono::Utc::now();
+            self.update_total_duration();
             info!("Moved track from position {} to {} in playlist '{}'", from_index, to_index, self.name);
             true
         } else {
@@ -90,11 +130,20 @@ impl Playlist {
             .collect()
     }
 
-    /// Update total duration based on available tracks
+    /// Recompute the stored total from the durations recorded at edit time
+    ///
+    /// Stays `None` while any track's duration is unknown (or the playlist predates recording
+    /// them), leaving `calculate_duration` to work it out against the library.
     fn update_total_duration(&mut self) {
-        // Note: This sets duration to None since we don't have track metadata here.
-        // The actual duration will be calculated in get_playlist_stats() using calculate_duration()
-        self.total_duration = None;
+        self.total_duration = self.track_paths
+            .iter()
+            .map(|path| self.track_durations.get(path))
+            .sum::<Option<u64>>();
+    }
+
+    /// The stored total if it's complete, otherwise calculated from `all_tracks`
+    pub fn total_duration(&self, all_tracks: &[Track]) -> Option<u64> {
+        self.total_duration.or_else(|| self.calculate_duration(all_tracks))
     }
 
     /// Calculate total duration from available tracks
@@ -120,7 +169,8 @@ impl Playlist {
 
     /// Get formatted duration string
     pub fn duration_string(&self, all_tracks: &[Track]) -> String {
-        if let Some(duration) = self.calculate_duration(all_tracks) {
+        if let Some(duration) = self.total_duration(all_tracks) {
+            let duration = duration / 1000;
             let hours = duration / 3600;
             let minutes = (duration % 3600) / 60;
             let seconds = duration % 60;
@@ -137,7 +187,7 @@ impl Playlist {
 }
 
 /// Manages all playlists - creation, loading, saving, deletion
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct PlaylistManager {
     playlists: HashMap<String, Playlist>,
     playlists_dir: PathBuf,
@@ -287,8 +337,20 @@ impl PlaylistManager {
         }
     }
 
+    /// Store a playlist's own shuffle and repeat, None to follow the app-wide settings
+    pub fn set_playback_modes(&mut self, playlist_id: &str, shuffle: Option<bool>, repeat: Option<PlaylistRepeat>) -> anyhow::Result<()> {
+        let playlist = self.playlists.get_mut(playlist_id)
+            .ok_or_else(|| anyhow::anyhow!("Playlist not found: {}", playlist_id))?;
+        playlist.shuffle = shuffle;
+        playlist.repeat = repeat;
+        playlist.modified_at = chrono::Utc::now();
+        
+        let playlist_clone = playlist.clone();
+        self.save_playlist(&playlist_clone)
+    }
+
     /// Add a track to a playlist
-    pub fn add_track_to_playlist(&mut self, playlist_id: &str, track_path: &Path) -> anyhow::Result<()> {
+    pub fn add_track_to_playlist(&mut self, playlist_id: &str, track_path: &Path, duration: Option<Duration>) -> anyhow::Result<()> {
         // Check if playlist exists first
         if !self.playlists.contains_key(playlist_id) {
             return Err(anyhow::anyhow!("Playlist not found: {}", playlist_id));
@@ -296,7 +358,7 @@ impl PlaylistManager {
         
         // Update the playlist
         if let Some(playlist) = self.playlists.get_mut(playlist_id) {
-            playlist.add_track(track_path.to_path_buf());
+            playlist.add_track(track_path.to_path_buf(), duration);
             playlist.modified_at = chrono::Utc::now();
         }
         
@@ -310,26 +372,263 @@ impl PlaylistManager {
         Ok(())
     }
 
+    /// Follow renamed files in every playlist; returns how many playlists changed
+    pub fn update_track_paths(&mut self, renames: &[(PathBuf, PathBuf)]) -> anyhow::Result<usize> {
+        let mut changed = Vec::new();
+        for playlist in self.playlists.values_mut() {
+            let mut touched = false;
+            for (old, new) in renames {
+                touched |= playlist.replace_track_path(old, new);
+            }
+            if touched {
+                changed.push(playlist.id.clone());
+            }
+        }
+        for id in &changed {
+            self.save_playlist(&self.playlists[id])?;
+        }
+        Ok(changed.len())
+    }
+
     /// List all playlists
     pub fn list_playlists(&self) -> Vec<&Playlist> {
         self.playlists.values().collect()
     }
 
+    /// Write every playlist into `dir` as one file each, named after the playlist
+    ///
+    /// Entries are resolved against `tracks`; a playlist none of whose tracks are in the library is
+    /// reported as failed rather than written out empty.
+    pub async fn export_all(&self, dir: &Path, format: ExportFormat, tracks: &[Track]) -> anyhow::Result<PlaylistExportReport> {
+        self.export_all_with_progress(dir, format, tracks, |_, _| {}, &AtomicBool::new(false)).await
+    }
+
+    /// `export_all`, calling `progress(done, total)` as it goes; setting `cancel` stops it
+    /// before the next playlist, and the report is marked as cancelled
+    pub async fn export_all_with_progress(
+        &self,
+        dir: &Path,
+        format: ExportFormat,
+        tracks: &[Track],
+        mut progress: impl FnMut(usize, usize),
+        cancel: &AtomicBool,
+    ) -> anyhow::Result<PlaylistExportReport> {
+        fs::create_dir_all(dir)?;
+        let exporter = ExportManager::new();
+        let mut report = PlaylistExportReport::default();
+        let mut used_names = HashMap::new();
+
+        let mut playlists = self.list_playlists();
+        playlists.sort_by(|a, b| a.name.cmp(&b.name).then(a.created_at.cmp(&b.created_at)));
+        let total = playlists.len();
+        for (done, playlist) in playlists.into_iter().enumerate() {
+            if cancel.load(Ordering::Relaxed) {
+                report.cancelled = true;
+                break;
+            }
+            progress(done, total);
+            let entries: Vec<Track> = playlist.get_valid_tracks(tracks)
+                .into_iter()
+                .map(|idx| tracks[idx].clone())
+                .collect();
+            if entries.is_empty() {
+                let reason = if playlist.is_empty() { "playlist is empty" } else { "none of its tracks are in the library" };
+                report.failed.push((playlist.name.clone(), reason.to_string()));
+                continue;
+            }
+
+            // Playlists whose names sanitize the same get " (2)", " (3)", ...
+            let stem = export_file_stem(&playlist.name);
+            let count = used_names.entry(stem.to_lowercase()).or_insert(0);
+            *count += 1;
+            let file_name = match *count {
+                1 => format!("{}.{}", stem, format.extension()),
+                n => format!("{} ({}).{}", stem, n, format.extension()),
+            };
+            let path = dir.join(file_name);
+
+            let written = match format {
+                ExportFormat::M3u => exporter.export_to_m3u(&entries, &path, PathStyle::Absolute).await,
+                ExportFormat::Pls => exporter.export_to_pls(&entries, &path, PathStyle::Absolute).await,
+                ExportFormat::Json => {
+                    let export = PlaylistExport {
+                        name: playlist.name.clone(),
+                        tracks: entries.iter().map(|track| track.id).collect(),
+                        track_paths: entries.iter().map(|track| track.file_path.clone()).collect(),
+                        created_at: playlist.created_at,
+                        behavior_data: None,
+                    };
+                    exporter.export_to_json(&export, &path).await
+                }
+            };
+            match written {
+                Ok(()) => report.exported.push(path),
+                Err(e) => {
+                    warn!("Failed to export playlist '{}': {}", playlist.name, e);
+                    report.failed.push((playlist.name.clone(), e.to_string()));
+                }
+            }
+        }
+
+        if !report.cancelled {
+            progress(total, total);
+        }
+        info!("Exported {} playlists to {}", report.exported.len(), dir.display());
+        Ok(report)
+    }
+
     /// Get playlist statistics
     pub fn get_playlist_stats(&self, playlist_id: &str, all_tracks: &[Track]) -> Option<PlaylistStats> {
         self.playlists.get(playlist_id).map(|playlist| {
-            let calculated_duration = playlist.calculate_duration(all_tracks).unwrap_or(0);
             PlaylistStats {
                 track_count: playlist.track_count,
-                total_duration: calculated_duration,
+                total_duration: playlist.total_duration(all_tracks).unwrap_or(0),
             }
         })
     }
 }
 
+/// What `PlaylistManager::export_all` wrote
+#[derive(Debug, Clone, Default)]
+pub struct PlaylistExportReport {
+    pub exported: Vec<PathBuf>,
+    pub failed: Vec<(String, String)>, // playlist name, reason
+    pub cancelled: bool,               // stopped early, the rest weren't tried
+}
+
+/// A playlist name made safe to use as a file name on any platform
+fn export_file_stem(name: &str) -> String {
+    let cleaned = sanitize_file_name(name);
+    if cleaned.is_empty() { "playlist".to_string() } else { cleaned }
+}
+
 /// Statistics about playlists
 #[derive(Debug, Default)]
 pub struct PlaylistStats {
     pub track_count: usize,
     pub total_duration: u64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_total_duration_is_kept_up_to_date() {
+        let mut playlist = Playlist::new("Road Trip".to_string(), None);
+        assert_eq!(playlist.total_duration, Some(0));
+
+        playlist.add_track(PathBuf::from("/m/a.mp3"), Some(Duration::from_secs(180)));
+        playlist.add_track(PathBuf::from("/m/b.mp3"), Some(Duration::from_secs(240)));
+        assert_eq!(playlist.total_duration, Some(420_000));
+
+        playlist.move_track(1, 0);
+        assert_eq!(playlist.total_duration, Some(420_000));
+
+        assert!(playlist.remove_track(Path::new("/m/a.mp3")));
+        assert_eq!(playlist.total_duration, Some(240_000));
+        assert_eq!(playlist.duration_string(&[]), "4:00");
+
+        // Survives a save and load
+        let reloaded: Playlist = serde_json::from_str(&serde_json::to_string(&playlist).unwrap()).unwrap();
+        assert_eq!(reloaded.total_duration, Some(240_000));
+    }
+
+    #[test]
+    fn test_unknown_duration_falls_back_to_library() {
+        let mut playlist = Playlist::new("Road Trip".to_string(), None);
+        playlist.add_track(PathBuf::from("/m/a.mp3"), Some(Duration::from_secs(180)));
+        playlist.add_track(PathBuf::from("/m/b.mp3"), None);
+        assert_eq!(playlist.total_duration, None);
+
+        let library = vec![
+            Track::test_fixture("/m/a.mp3").with_duration_secs(180),
+            Track::test_fixture("/m/b.mp3").with_duration_secs(60),
+        ];
+        assert_eq!(playlist.total_duration(&library), Some(240_000));
+
+        // Dropping the unknown track makes the stored total complete again
+        playlist.remove_track(Path::new("/m/b.mp3"));
+        assert_eq!(playlist.total_duration, Some(180_000));
+    }
+
+    #[tokio::test]
+    async fn test_export_all() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut manager = PlaylistManager::new(dir.path().join("playlists")).unwrap();
+        let library = vec![Track::test_fixture("/m/a.mp3").with_duration_secs(180), Track::test_fixture("/m/b.mp3")];
+        for (name, paths) in [("Road/Trip", &["/m/a.mp3", "/m/b.mp3"][..]), ("Road:Trip", &["/m/b.mp3"]), ("Gone", &["/m/x.mp3"])] {
+            let id = manager.create_playlist(name.to_string(), None).unwrap();
+            for path in paths {
+                manager.add_track_to_playlist(&id, Path::new(path), None).unwrap();
+            }
+        }
+
+        let out = dir.path().join("export");
+        let report = manager.export_all(&out, ExportFormat::M3u, &library).await.unwrap();
+        assert_eq!(report.exported, vec![out.join("Road_Trip.m3u"), out.join("Road_Trip (2).m3u")]);
+        assert_eq!(report.failed, vec![("Gone".to_string(), "none of its tracks are in the library".to_string())]);
+        assert_eq!(fs::read_to_string(out.join("Road_Trip.m3u")).unwrap().matches("#EXTINF").count(), 2);
+
+        let report = manager.export_all(&out, ExportFormat::Json, &library).await.unwrap();
+        let json: PlaylistExport = serde_json::from_str(&fs::read_to_string(&report.exported[1]).unwrap()).unwrap();
+        assert_eq!(json.track_paths, vec![PathBuf::from("/m/b.mp3")]);
+        assert_eq!(json.tracks, vec![library[1].id]);
+    }
+
+    #[tokio::test]
+    async fn test_export_all_reports_progress_and_cancels() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut manager = PlaylistManager::new(dir.path().join("playlists")).unwrap();
+        let library = vec![Track::test_fixture("/m/a.mp3").with_duration_secs(180)];
+        for name in ["One", "Two", "Three"] {
+            let id = manager.create_playlist(name.to_string(), None).unwrap();
+            manager.add_track_to_playlist(&id, Path::new("/m/a.mp3"), None).unwrap();
+        }
+
+        let out = dir.path().join("export");
+        let mut updates = Vec::new();
+        let report = manager
+            .export_all_with_progress(&out, ExportFormat::M3u, &library, |done, total| updates.push((done, total)), &AtomicBool::new(false))
+            .await
+            .unwrap();
+        assert_eq!(updates, vec![(0, 3), (1, 3), (2, 3), (3, 3)]);
+        assert_eq!(report.exported.len(), 3);
+        assert!(!report.cancelled);
+
+        // Cancelled from the progress callback, so the playlist it reports on is the last one written
+        let cancel = AtomicBool::new(false);
+        let report = manager
+            .export_all_with_progress(&out, ExportFormat::M3u, &library, |done, _| if done == 1 { cancel.store(true, Ordering::Relaxed) }, &cancel)
+            .await
+            .unwrap();
+        assert_eq!(report.exported.len(), 2);
+        assert!(report.cancelled);
+    }
+
+    #[test]
+    fn test_playback_modes_are_saved() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut manager = PlaylistManager::new(dir.path().to_path_buf()).unwrap();
+        let id = manager.create_playlist("Album Run".to_string(), None).unwrap();
+        let playlist = manager.get_playlist(&id).unwrap();
+        assert_eq!((playlist.shuffle, playlist.repeat), (None, None));
+
+        manager.set_playback_modes(&id, Some(false), Some(PlaylistRepeat::One)).unwrap();
+        let reloaded = PlaylistManager::new(dir.path().to_path_buf()).unwrap();
+        let playlist = reloaded.get_playlist(&id).unwrap();
+        assert_eq!((playlist.shuffle, playlist.repeat), (Some(false), Some(PlaylistRepeat::One)));
+
+        // Playlists saved before these settings existed follow the app-wide ones
+        let mut json: serde_json::Value = serde_json::to_value(playlist).unwrap();
+        json.as_object_mut().unwrap().retain(|key, _| key != "shuffle" && key != "repeat");
+        let old: Playlist = serde_json::from_value(json).unwrap();
+        assert_eq!((old.shuffle, old.repeat), (None, None));
+    }
+
+    #[test]
+    fn test_export_file_stem() {
+        assert_eq!(export_file_stem("AC/DC: Best *of*"), "AC_DC_ Best _of_");
+        assert_eq!(export_file_stem(" ..."), "playlist");
+    }
+}
@@ -0,0 +1,148 @@
+// Batch rename - file names rebuilt from tags, e.g. "{track:02} - {artist} - {title}.{ext}"
+// A plan lists every old → new name up front; nothing on disk changes until it's applied
+
+use super::Track;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// One file to rename, within its own folder
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileRename {
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+/// Every rename a pattern calls for
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RenamePlan {
+    pub renames: Vec<FileRename>,
+    pub unchanged: usize, // files already named after the pattern
+}
+
+/// What `RenamePlan::apply` managed to do
+#[derive(Debug, Clone, Default)]
+pub struct RenameOutcome {
+    pub renamed: Vec<FileRename>,
+    pub failed: Vec<(PathBuf, String)>, // files left under their old name
+}
+
+impl RenamePlan {
+    /// Work out the new names; a name that's taken on disk or by an earlier track gets " (2)", " (3)", ...
+    pub fn new(tracks: &[Track], pattern: &str) -> Self {
+        let mut plan = Self::default();
+        let mut claimed = HashSet::new();
+
+        for track in tracks {
+            let from = &track.file_path;
+            let dir = from.parent().unwrap_or(Path::new(""));
+            let name = track.suggested_filename(pattern);
+            let mut to = dir.join(&name);
+
+            let mut counter = 1;
+            while to != *from && (claimed.contains(&claim_key(&to)) || (to.exists() && !same_ignoring_case(&to, from))) {
+                counter += 1;
+                to = dir.join(numbered(&name, counter));
+            }
+            claimed.insert(claim_key(&to));
+
+            if to == *from {
+                plan.unchanged += 1;
+            } else {
+                plan.renames.push(FileRename { from: from.clone(), to });
+            }
+        }
+        plan
+    }
+
+    /// One line for the status bar, used for dry runs and the confirmation prompt
+    pub fn describe(&self) -> String {
+        let files = if self.renames.len() == 1 { "file" } else { "files" };
+        format!("Rename {} {} ({} already match)", self.renames.len(), files, self.unchanged)
+    }
+
+    /// Rename on disk, never over an existing file
+    pub fn apply(&self) -> RenameOutcome {
+        let mut outcome = RenameOutcome::default();
+        for rename in &self.renames {
+            if rename.to.exists() && !same_ignoring_case(&rename.to, &rename.from) {
+                outcome.failed.push((rename.from.clone(), format!("{} already exists", rename.to.display())));
+                continue;
+            }
+            match fs::rename(&rename.from, &rename.to) {
+                Ok(()) => outcome.renamed.push(rename.clone()),
+                Err(e) => outcome.failed.push((rename.from.clone(), e.to_string())),
+            }
+        }
+        outcome
+    }
+}
+
+/// `name` with characters no filesystem accepts replaced, and without leading/trailing dots or spaces
+pub fn sanitize_file_name(name: &str) -> String {
+    let cleaned: String = name.chars()
+        .map(|c| if c.is_control() || matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') { '_' } else { c })
+        .collect();
+    cleaned.trim().trim_matches('.').trim().to_string()
+}
+
+/// "a.mp3" → "a (2).mp3"
+fn numbered(name: &str, counter: usize) -> String {
+    match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => format!("{} ({}).{}", stem, counter, ext),
+        _ => format!("{} ({})", name, counter),
+    }
+}
+
+/// Case-insensitive filesystems treat these as one file, so a case-only rename isn't a collision
+fn same_ignoring_case(a: &Path, b: &Path) -> bool {
+    claim_key(a) == claim_key(b)
+}
+
+fn claim_key(path: &Path) -> String {
+    path.to_string_lossy().to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_and_apply() {
+        let dir = tempfile::tempdir().unwrap();
+        let pattern = "{track:02} - {artist} - {title}.{ext}";
+        let names = ["x.mp3", "y.mp3", "01 - AC_DC - Thunder.mp3", "z.flac"];
+        for name in names {
+            fs::write(dir.path().join(name), "audio").unwrap();
+        }
+        let tracks = vec![
+            Track::test_fixture(dir.path().join("x.mp3")).with_track_number(2).with_artist("Low").with_title("Words"),
+            Track::test_fixture(dir.path().join("y.mp3")).with_track_number(2).with_artist("Low").with_title("Words"), // same tags as x
+            Track::test_fixture(dir.path().join(names[2])).with_track_number(1).with_artist("AC/DC").with_title("Thunder"), // already right
+            Track::test_fixture(dir.path().join("z.flac")).with_artist("Björk").with_title("Jóga?"),
+        ];
+
+        let plan = RenamePlan::new(&tracks, pattern);
+        assert_eq!(plan.unchanged, 1);
+        let targets: Vec<_> = plan.renames.iter().map(|r| r.to.file_name().unwrap().to_string_lossy().into_owned()).collect();
+        assert_eq!(targets, ["02 - Low - Words.mp3", "02 - Low - Words (2).mp3", "Björk - Jóga.flac"]);
+        assert_eq!(plan.describe(), "Rename 3 files (1 already match)");
+
+        // Taken since the plan was made: that one stays put
+        fs::write(&plan.renames[2].to, "someone else").unwrap();
+        let outcome = plan.apply();
+        assert_eq!(outcome.renamed.len(), 2);
+        assert_eq!(outcome.failed.len(), 1);
+        assert!(dir.path().join("02 - Low - Words (2).mp3").exists());
+        assert!(!dir.path().join("x.mp3").exists());
+        assert!(dir.path().join("z.flac").exists());
+    }
+
+    #[test]
+    fn test_sanitize_file_name() {
+        assert_eq!(sanitize_file_name("AC/DC: Best *of*"), "AC_DC_ Best _of_");
+        assert_eq!(sanitize_file_name(" ..hidden. "), "hidden");
+        assert_eq!(numbered("a.b.mp3", 3), "a.b (3).mp3");
+        assert_eq!(numbered(".mp3", 2), ".mp3 (2)");
+    }
+}
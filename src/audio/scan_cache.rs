@@ -0,0 +1,57 @@
+// Scan cache - lets a rescan skip tag reading and hashing for unchanged files
+// Entries are keyed by path and only trusted while size + mtime still match
+
+use super::Track;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+#[derive(Debug, Clone)]
+pub struct CachedTrack {
+    pub track: Track,
+    pub file_size: u64,
+    pub modified: u64, // seconds since the Unix epoch
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ScanCache {
+    entries: HashMap<PathBuf, CachedTrack>,
+}
+
+impl ScanCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    pub fn insert(&mut self, entry: CachedTrack) {
+        self.entries.insert(entry.track.file_path.clone(), entry);
+    }
+    
+    /// Cached track for `path` if the file hasn't changed since it was cached
+    pub fn lookup(&self, path: &Path, metadata: &fs::Metadata) -> Option<Track> {
+        let entry = self.entries.get(path)?;
+        let modified = file_modified_secs(metadata)?;
+        
+        if entry.file_size == metadata.len() && entry.modified == modified {
+            Some(entry.track.clone())
+        } else {
+            None
+        }
+    }
+    
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// File mtime in whole seconds, the granularity the cache compares at
+pub fn file_modified_secs(metadata: &fs::Metadata) -> Option<u64> {
+    metadata.modified().ok()?
+        .duration_since(UNIX_EPOCH).ok()
+        .map(|d| d.as_secs())
+}
@@ -0,0 +1,61 @@
+// Post-scan report - likely duplicates and files we couldn't read properly
+// Meant for library cleanup, nothing here stops a track from being playable
+
+use super::Track;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Files sharing a content hash - almost certainly the same audio
+#[derive(Debug, Clone)]
+pub struct DuplicateGroup {
+    pub content_hash: u64,
+    pub paths: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UnreadableFile {
+    pub path: PathBuf,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ScanReport {
+    pub duplicate_groups: Vec<DuplicateGroup>,
+    pub unreadable: Vec<UnreadableFile>,
+}
+
+impl ScanReport {
+    /// Group scanned tracks by content hash and attach the read failures collected during the scan
+    pub fn build(tracks: &[Track], mut unreadable: Vec<UnreadableFile>) -> Self {
+        let mut by_hash: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+        for track in tracks {
+            if let Some(hash) = track.content_hash {
+                by_hash.entry(hash).or_default().push(track.file_path.clone());
+            }
+        }
+        
+        let mut duplicate_groups: Vec<DuplicateGroup> = by_hash
+            .into_iter()
+            .filter(|(_, paths)| paths.len() > 1)
+            .map(|(content_hash, mut paths)| {
+                paths.sort();
+                DuplicateGroup { content_hash, paths }
+            })
+            .collect();
+        duplicate_groups.sort_by(|a, b| a.paths[0].cmp(&b.paths[0]));
+        
+        unreadable.sort_by(|a, b| a.path.cmp(&b.path));
+        
+        Self { duplicate_groups, unreadable }
+    }
+    
+    /// Number of files that could be removed without losing any unique audio
+    pub fn redundant_file_count(&self) -> usize {
+        self.duplicate_groups.iter().map(|g| g.paths.len() - 1).sum()
+    }
+    
+    pub fn is_clean(&self) -> bool {
+        self.duplicate_groups.is_empty() && self.unreadable.is_empty()
+    }
+}
@@ -1,100 +1,161 @@
-use super::{AudioFormat, Track, TrackMetadata};
-use anyhow::Result;
+use super::scan_cache::{file_modified_secs, ScanCache};
+use super::scan_report::{ScanReport, UnreadableFile};
+use super::{wav, AudioFormat, Track, TrackMetadata};
+use anyhow::{Context, Result};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use rayon::prelude::*;
+use std::collections::HashSet;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use tokio::sync::mpsc;
+use tracing::warn;
 use walkdir::WalkDir;
 
+/// Extensions scanned when the config doesn't list its own
+pub const DEFAULT_EXTENSIONS: &[&str] = &["mp3", "flac", "ogg", "oga", "mp4", "m4a", "m4b", "aac", "wav"];
+
 #[derive(Clone)]
 pub struct MusicScanner {
     supported_extensions: Vec<String>,
+    cache: Option<Arc<ScanCache>>,
+    include_patterns: GlobSet,
+    exclude_patterns: GlobSet,
+}
+
+/// What a scan makes of one file, for the `scan_report` diagnostic
+#[derive(Debug, Clone)]
+pub struct FileDiagnosis {
+    pub path: PathBuf,
+    pub format: AudioFormat,
+    pub skipped: Option<&'static str>, // why a scan passes over the file, if it does
+    pub track: Option<Track>,          // what a scan would list, None when the file couldn't be read
+    pub problems: Vec<String>,         // non-fatal read problems, or why reading failed
 }
 
 #[derive(Debug, Clone)]
 pub enum ScanProgress {
     Started { total_directories: usize },
     DirectoryStarted { path: PathBuf },
-    TrackFound { track: Track, progress: usize, total: Option<usize> },
+    TrackFound { track: Box<Track>, progress: usize, total: Option<usize> },
     DirectoryCompleted { path: PathBuf, tracks_found: usize },
     Completed { total_tracks: usize },
+    Cancelled { total_tracks: usize }, // stopped early, the scan returns what it found so far
     Error { path: PathBuf, error: String },
 }
 
 impl MusicScanner {
     pub fn new() -> Self {
         Self {
-            supported_extensions: vec![
-                "mp3".to_string(),
-                "flac".to_string(),
-                "ogg".to_string(),
-                "oga".to_string(),
-                "mp4".to_string(),
-                "m4a".to_string(),
-                "aac".to_string(),
-                "wav".to_string(),
-            ],
+            supported_extensions: DEFAULT_EXTENSIONS.iter().map(|ext| ext.to_string()).collect(),
+            cache: None,
+            include_patterns: GlobSet::empty(),
+            exclude_patterns: GlobSet::empty(),
         }
     }
 
-    pub fn scan_directory<P: AsRef<Path>>(&self, path: P) -> Result<Vec<Track>> {
-        let mut tracks = Vec::new();
+    /// Scanner for a custom extension list, e.g. `library.supported_extensions` from the config
+    pub fn with_extensions(extensions: Vec<String>) -> Self {
+        let supported_extensions: Vec<String> = extensions
+            .into_iter()
+            .map(|ext| ext.trim().trim_start_matches('.').to_ascii_lowercase())
+            .filter(|ext| !ext.is_empty())
+            .collect();
         
-        for entry in WalkDir::new(path).follow_links(true).into_iter().filter_map(Result::ok) {
-            let path = entry.path();
-            
-            if entry.file_type().is_file() {
-                // Skip hidden files (dotfiles)
-                if path.file_name()
-                    .and_then(|n| n.to_str())
-                    .map_or(false, |n| n.starts_with('.')) {
-                    continue;
-                }
-                
-                // Check file size to skip absurd files
-                if let Ok(metadata) = fs::metadata(path) {
-                    if metadata.len() == 0 || metadata.len() > 1_000_000_000 {
-                        // Skip empty files or files > 1GB
-                        continue;
-                    }
-                }
-                
-                if self.is_supported_file(path) {
-                    if let Ok(track) = self.create_track_from_file(path) {
-                        tracks.push(track);
-                    }
-                }
+        // AudioFormat decides what we can actually play - scanning anything else just lists dead tracks
+        for ext in &supported_extensions {
+            if !AudioFormat::from_extension(ext).is_supported() {
+                warn!("Configured extension '.{}' has no known audio format and won't decode", ext);
             }
         }
         
+        Self {
+            supported_extensions,
+            ..Self::new()
+        }
+    }
+
+    /// Skip files matching `exclude` globs unless an `include` glob matches too
+    ///
+    /// Patterns are matched against the path relative to the scanned root, e.g. `**/Podcasts/**` or `*.m4b`.
+    pub fn with_patterns(mut self, include: &[String], exclude: &[String]) -> Result<Self> {
+        self.include_patterns = Self::build_globset(include)?;
+        self.exclude_patterns = Self::build_globset(exclude)?;
+        Ok(self)
+    }
+
+    fn build_globset(patterns: &[String]) -> Result<GlobSet> {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in patterns {
+            let glob = Glob::new(pattern).with_context(|| format!("Invalid scan pattern '{}'", pattern))?;
+            builder.add(glob);
+        }
+        Ok(builder.build()?)
+    }
+
+    /// Whether include/exclude patterns filter out `path`, relative to `root`
+    pub fn is_excluded(&self, root: &Path, path: &Path) -> bool {
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        self.exclude_patterns.is_match(relative) && !self.include_patterns.is_match(relative)
+    }
+
+    /// Reuse cached tags/hashes for files whose size and mtime haven't changed
+    pub fn with_cache(mut self, cache: ScanCache) -> Self {
+        self.cache = Some(Arc::new(cache));
+        self
+    }
+
+    pub fn scan_directory<P: AsRef<Path>>(&self, path: P) -> Result<Vec<Track>> {
+        let files = self.collect_candidate_files(path.as_ref());
+        
+        // Tag reading and hashing are independent per file, so spread them over the rayon pool
+        let mut tracks: Vec<Track> = files
+            .par_iter()
+            .filter_map(|file| self.create_track_from_file(file).ok())
+            .collect();
+        
+        tracks.sort_by(|a, b| a.file_path.cmp(&b.file_path));
         Ok(tracks)
     }
 
     pub fn scan_directories(&self, paths: &[PathBuf]) -> Result<Vec<Track>> {
         let mut all_tracks = Vec::new();
         
-        for path in paths {
+        for path in scan_roots(paths) {
             if path.exists() {
                 let mut tracks = self.scan_directory(path)?;
                 all_tracks.append(&mut tracks);
             }
         }
         
+        all_tracks.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+        dedup_by_canonical_path(&mut all_tracks);
         Ok(all_tracks)
     }
 
     /// Incremental scan with progress updates via channel for non-blocking UI updates
+    ///
+    /// Returns the tracks along with a report of likely duplicates and files that couldn't be read.
+    /// Setting `cancel` stops the scan between files; the tracks found so far are still returned.
     pub async fn scan_directories_incremental(
         &self,
         paths: &[PathBuf],
         progress_tx: mpsc::Sender<ScanProgress>,
-    ) -> Result<Vec<Track>> {
+        cancel: Arc<AtomicBool>,
+    ) -> Result<(Vec<Track>, ScanReport)> {
         let mut all_tracks = Vec::new();
+        let mut unreadable = Vec::new();
+        let paths = scan_roots(paths);
         let total_directories = paths.len();
         
         // Send initial progress
         let _ = progress_tx.send(ScanProgress::Started { total_directories }).await;
         
-        for path in paths {
+        for path in &paths {
+            if cancel.load(Ordering::Relaxed) {
+                break;
+            }
             if !path.exists() {
                 let _ = progress_tx.send(ScanProgress::Error {
                     path: path.clone(),
@@ -109,53 +170,63 @@ impl MusicScanner {
             let mut directory_tracks = 0;
             let mut progress_count = all_tracks.len();
             
-            for entry in WalkDir::new(path).follow_links(true).into_iter().filter_map(Result::ok) {
-                let entry_path = entry.path();
-                
-                if entry.file_type().is_file() {
-                    // Skip hidden files (dotfiles)
-                    if entry_path.file_name()
-                        .and_then(|n| n.to_str())
-                        .map_or(false, |n| n.starts_with('.')) {
-                        continue;
+            // Walking is cheap, do it up front so the expensive part can run in parallel
+            let scanner = self.clone();
+            let root = path.clone();
+            let files = tokio::task::spawn_blocking(move || scanner.collect_candidate_files(&root)).await?;
+            
+            // Process files on the rayon pool and stream results back as they finish
+            let (result_tx, mut result_rx) = mpsc::unbounded_channel();
+            let scanner = self.clone();
+            let worker_cancel = Arc::clone(&cancel);
+            let worker = tokio::task::spawn_blocking(move || {
+                files.par_iter().for_each_with(result_tx, |tx, file| {
+                    if worker_cancel.load(Ordering::Relaxed) {
+                        return;
                     }
-                    
-                    // Check file size to skip absurd files
-                    if let Ok(metadata) = fs::metadata(entry_path) {
-                        if metadata.len() == 0 || metadata.len() > 1_000_000_000 {
-                            continue;
+                    let _ = tx.send((file.clone(), scanner.create_track_with_problems(file)));
+                });
+            });
+            
+            while let Some((file, result)) = result_rx.recv().await {
+                match result {
+                    Ok((track, problems)) => {
+                        // Still a usable track, but worth listing in the report
+                        for problem in problems {
+                            unreadable.push(UnreadableFile { path: file.clone(), error: problem });
                         }
+                        
+                        progress_count += 1;
+                        directory_tracks += 1;
+                        
+                        // Send track found progress
+                        let _ = progress_tx.send(ScanProgress::TrackFound {
+                            track: Box::new(track.clone()),
+                            progress: progress_count,
+                            total: None, // We don't know total until complete
+                        }).await;
+                        
+                        all_tracks.push(track);
                     }
-                    
-                    if self.is_supported_file(entry_path) {
-                        match self.create_track_from_file(entry_path) {
-                            Ok(track) => {
-                                progress_count += 1;
-                                directory_tracks += 1;
-                                
-                                // Send track found progress
-                                let _ = progress_tx.send(ScanProgress::TrackFound {
-                                    track: track.clone(),
-                                    progress: progress_count,
-                                    total: None, // We don't know total until complete
-                                }).await;
-                                
-                                all_tracks.push(track);
-                                
-                                // Yield control periodically for UI responsiveness
-                                if progress_count % 10 == 0 {
-                                    tokio::task::yield_now().await;
-                                }
-                            }
-                            Err(e) => {
-                                let _ = progress_tx.send(ScanProgress::Error {
-                                    path: entry_path.to_path_buf(),
-                                    error: e.to_string(),
-                                }).await;
-                            }
-                        }
+                    Err(e) => {
+                        unreadable.push(UnreadableFile { path: file.clone(), error: e.to_string() });
+                        let _ = progress_tx.send(ScanProgress::Error {
+                            path: file,
+                            error: e.to_string(),
+                        }).await;
                     }
                 }
+                
+                if cancel.load(Ordering::Relaxed) {
+                    break;
+                }
+            }
+            // Files still queued on the worker see the flag and are skipped
+            drop(result_rx);
+            worker.await?;
+            
+            if cancel.load(Ordering::Relaxed) {
+                break;
             }
             
             // Send directory completion progress
@@ -165,12 +236,136 @@ impl MusicScanner {
             }).await;
         }
         
+        // Results arrive in completion order - sort so the library order is deterministic
+        all_tracks.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+        dedup_by_canonical_path(&mut all_tracks);
+        
+        let report = ScanReport::build(&all_tracks, unreadable);
+        
         // Send final completion progress
-        let _ = progress_tx.send(ScanProgress::Completed {
-            total_tracks: all_tracks.len(),
-        }).await;
+        let total_tracks = all_tracks.len();
+        let finished = if cancel.load(Ordering::Relaxed) {
+            ScanProgress::Cancelled { total_tracks }
+        } else {
+            ScanProgress::Completed { total_tracks }
+        };
+        let _ = progress_tx.send(finished).await;
         
-        Ok(all_tracks)
+        Ok((all_tracks, report))
+    }
+
+    /// Scan a single file, e.g. one reported by the library watcher
+    pub fn scan_file(&self, path: &Path) -> Result<Track> {
+        self.scan_file_with_problems(path).map(|(track, _)| track)
+    }
+
+    /// Like `scan_file`, but also returns the non-fatal problems a scan report lists
+    pub(crate) fn scan_file_with_problems(&self, path: &Path) -> Result<(Track, Vec<String>)> {
+        self.check_single_file(path)?;
+        self.create_track_with_problems(path)
+    }
+
+    /// Re-read one file from disk, ignoring the scan cache so tags, duration and hash are all fresh
+    pub fn rescan_file(&self, path: &Path) -> Result<Track> {
+        self.check_single_file(path)?;
+        self.read_track_with_problems(path, &fs::metadata(path)?).map(|(track, _)| track)
+    }
+
+    fn check_single_file(&self, path: &Path) -> Result<()> {
+        let is_hidden = path.file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.starts_with('.'));
+        
+        if is_hidden || !path.is_file() || !self.is_supported_file(path) {
+            return Err(anyhow::anyhow!("Not a supported audio file: {}", path.display()));
+        }
+        Ok(())
+    }
+
+    /// Walk a directory and return the supported, non-hidden, sanely sized audio files in it
+    fn collect_candidate_files(&self, path: &Path) -> Vec<PathBuf> {
+        self.collect_candidate_files_under(path, path)
+    }
+
+    /// Like `collect_candidate_files` for a subdirectory of the music directory `root`
+    pub(crate) fn collect_candidate_files_under(&self, root: &Path, dir: &Path) -> Vec<PathBuf> {
+        WalkDir::new(dir)
+            .follow_links(true)
+            .into_iter()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_type().is_file() && self.is_candidate_file(root, entry.path()))
+            .map(|entry| entry.into_path())
+            .collect()
+    }
+
+    /// Whether a file under the music directory `root` is one a scan would pick up
+    pub(crate) fn is_candidate_file(&self, root: &Path, path: &Path) -> bool {
+        self.skip_reason(root, path).is_none()
+    }
+
+    /// Why a scan would pass over a file under the music directory `root`, if it would
+    fn skip_reason(&self, root: &Path, path: &Path) -> Option<&'static str> {
+        // Skip hidden files (dotfiles)
+        if path.file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.starts_with('.')) {
+            return Some("hidden file");
+        }
+        
+        // Check file size to skip absurd files
+        if let Ok(metadata) = fs::metadata(path) {
+            if metadata.len() == 0 {
+                return Some("empty file");
+            }
+            if metadata.len() > 1_000_000_000 {
+                return Some("larger than 1 GB");
+            }
+        }
+        
+        if !self.is_supported_file(path) {
+            return Some("extension not scanned");
+        }
+        if self.is_excluded(root, path) {
+            return Some("matches an exclude pattern");
+        }
+        None
+    }
+
+    /// Every file under `root`, scanned or not, with what a scan makes of it - sorted by path
+    ///
+    /// Files are read fresh, never from the scan cache.
+    pub fn diagnose_directory(&self, root: &Path) -> Vec<FileDiagnosis> {
+        let files: Vec<PathBuf> = WalkDir::new(root)
+            .follow_links(true)
+            .into_iter()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_type().is_file())
+            .map(|entry| entry.into_path())
+            .collect();
+        
+        let mut diagnoses: Vec<FileDiagnosis> = files
+            .into_par_iter()
+            .map(|path| {
+                let format = path.extension()
+                    .and_then(|ext| ext.to_str())
+                    .map(AudioFormat::from_extension)
+                    .unwrap_or(AudioFormat::Unknown);
+                let skipped = self.skip_reason(root, &path);
+                let (track, problems) = match skipped {
+                    Some(_) => (None, Vec::new()),
+                    None => match fs::metadata(&path).map_err(anyhow::Error::from)
+                        .and_then(|metadata| self.read_track_with_problems(&path, &metadata))
+                    {
+                        Ok((track, problems)) => (Some(track), problems),
+                        Err(e) => (None, vec![e.to_string()]),
+                    },
+                };
+                FileDiagnosis { path, format, skipped, track, problems }
+            })
+            .collect();
+        
+        diagnoses.sort_by(|a, b| a.path.cmp(&b.path));
+        diagnoses
     }
 
     fn is_supported_file(&self, path: &Path) -> bool {
@@ -184,8 +379,29 @@ impl MusicScanner {
     }
 
     fn create_track_from_file(&self, path: &Path) -> Result<Track> {
+        self.create_track_with_problems(path).map(|(track, _)| track)
+    }
+
+    /// Like `create_track_from_file`, but also returns non-fatal read problems (broken tags, hash failures)
+    fn create_track_with_problems(&self, path: &Path) -> Result<(Track, Vec<String>)> {
         let metadata = fs::metadata(path)?;
+        
+        // Unchanged since last scan - skip tag extraction and hashing entirely
+        // (WAVs cached before their headers were read have no duration, so they get another look)
+        let cached = self.cache.as_ref()
+            .and_then(|cache| cache.lookup(path, &metadata))
+            .filter(|track| !matches!(track.format, AudioFormat::Wav) || track.duration.is_some());
+        if let Some(cached) = cached {
+            return Ok((cached, Vec::new()));
+        }
+        
+        self.read_track_with_problems(path, &metadata)
+    }
+
+    /// Extract tags, hash and duration straight from the file
+    fn read_track_with_problems(&self, path: &Path, metadata: &fs::Metadata) -> Result<(Track, Vec<String>)> {
         let file_size = metadata.len();
+        let mut problems = Vec::new();
         
         let format = path
             .extension()
@@ -196,17 +412,22 @@ impl MusicScanner {
         let mut track = Track::new(path.to_path_buf());
         track.file_size = file_size;
         track.format = format;
+        track.modified = file_modified_secs(metadata);
 
         // Extract metadata based on file type
         match &track.format {
             AudioFormat::Mp3 => {
-                if let Ok(tag_metadata) = self.extract_id3_metadata(path) {
-                    track = track.with_metadata(tag_metadata);
+                match self.extract_id3_metadata(path) {
+                    Ok(tag_metadata) => track = track.with_metadata(tag_metadata),
+                    // Untagged files are normal, only broken tags are worth reporting
+                    Err(e) if e.downcast_ref::<id3::Error>().is_some_and(|e| matches!(e.kind, id3::ErrorKind::NoTag)) => {}
+                    Err(e) => problems.push(format!("ID3 tag unreadable: {}", e)),
                 }
             }
             AudioFormat::Mp4 => {
-                if let Ok(tag_metadata) = self.extract_mp4_metadata(path) {
-                    track = track.with_metadata(tag_metadata);
+                match self.extract_mp4_metadata(path) {
+                    Ok(tag_metadata) => track = track.with_metadata(tag_metadata),
+                    Err(e) => problems.push(format!("MP4 tag unreadable: {}", e)),
                 }
             }
             AudioFormat::Flac => {
@@ -214,6 +435,12 @@ impl MusicScanner {
                     track = track.with_metadata(tag_metadata);
                 }
             }
+            AudioFormat::Wav => {
+                match wav::read_wav_info(path) {
+                    Ok(info) => track = track.with_metadata(info.metadata),
+                    Err(e) => problems.push(format!("WAV header unreadable: {}", e)),
+                }
+            }
             _ => {
                 // For unsupported metadata formats, use filename
                 track.metadata.title = path
@@ -227,6 +454,12 @@ impl MusicScanner {
         if let Err(e) = track.compute_content_hash() {
             // Log error but don't fail the entire track creation
             eprintln!("Warning: Failed to compute content hash for {}: {}", path.display(), e);
+            problems.push(format!("Content hash failed: {}", e));
+        }
+
+        // Identity follows the content, so a moved or renamed file keeps its history
+        if let Some(hash) = track.content_hash {
+            track.id = Track::content_id(hash);
         }
 
         // Feature-gated duration probing using symphonia
@@ -240,7 +473,7 @@ impl MusicScanner {
             }
         }
 
-        Ok(track)
+        Ok((track, problems))
     }
 
     fn extract_id3_metadata(&self, path: &Path) -> Result<TrackMetadata> {
@@ -272,7 +505,7 @@ impl MusicScanner {
 
     /// Feature-gated duration probing using symphonia codec
     #[cfg(feature = "probe")]
-    fn probe_duration_with_symphonia(&self, path: &Path) -> Result<std::time::Duration> {
+    pub fn probe_duration_with_symphonia(&self, path: &Path) -> Result<std::time::Duration> {
         use symphonia::core::formats::FormatOptions;
         use symphonia::core::io::MediaSourceStream;
         use symphonia::core::meta::MetadataOptions;
@@ -303,9 +536,8 @@ impl MusicScanner {
 
         // Calculate duration from time base and frame count
         if let (Some(time_base), Some(n_frames)) = (track.codec_params.time_base, track.codec_params.n_frames) {
-            let duration_secs = time_base.calc_time(n_frames).seconds as f64 
-                + (time_base.calc_time(n_frames).frac as f64 / time_base.denom as f64);
-            return Ok(Duration::from_secs_f64(duration_secs));
+            let time = time_base.calc_time(n_frames);
+            return Ok(Duration::from_secs_f64(time.seconds as f64 + time.frac));
         }
 
         // Fallback: try to get duration from format metadata
@@ -328,3 +560,188 @@ impl Default for MusicScanner {
         Self::new()
     }
 }
+
+/// The configured directories worth walking, in their configured order
+///
+/// Repeats and directories inside another configured one are dropped, comparing canonical paths so
+/// `~/Music/../Music` or a symlink still count. Missing directories stay for the scan to report.
+pub fn scan_roots(paths: &[PathBuf]) -> Vec<PathBuf> {
+    let canonical: Vec<PathBuf> = paths.iter()
+        .map(|path| fs::canonicalize(path).unwrap_or_else(|_| path.clone()))
+        .collect();
+    
+    paths.iter()
+        .zip(&canonical)
+        .enumerate()
+        .filter(|(i, (_, path))| {
+            !canonical.iter().enumerate().any(|(j, other)| {
+                // Of two equal paths the first one listed stays
+                j != *i && path.starts_with(other) && (*path != other || j < *i)
+            })
+        })
+        .map(|(_, (path, _))| path.clone())
+        .collect()
+}
+
+/// Keep the first of any tracks whose files resolve to the same place, e.g. through a symlinked folder
+fn dedup_by_canonical_path(tracks: &mut Vec<Track>) {
+    let mut seen = HashSet::new();
+    tracks.retain(|track| seen.insert(fs::canonicalize(&track.file_path).unwrap_or_else(|_| track.file_path.clone())));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::scan_cache::CachedTrack;
+    use crate::audio::test_support::touch;
+
+    fn scanned_names(scanner: &MusicScanner, root: &Path) -> Vec<String> {
+        scanner.scan_directory(root).unwrap()
+            .iter()
+            .map(|t| t.file_path.strip_prefix(root).unwrap().to_string_lossy().replace('\\', "/"))
+            .collect()
+    }
+
+    #[test]
+    fn test_exclude_by_directory_glob() {
+        let dir = tempfile::tempdir().unwrap();
+        touch(dir.path(), "Music/song.mp3");
+        touch(dir.path(), "Podcasts/episode1.mp3");
+        touch(dir.path(), "Other/Podcasts/episode2.mp3");
+
+        let scanner = MusicScanner::new()
+            .with_patterns(&[], &["**/Podcasts/**".to_string()])
+            .unwrap();
+
+        assert_eq!(scanned_names(&scanner, dir.path()), vec!["Music/song.mp3"]);
+    }
+
+    #[test]
+    fn test_include_overrides_broad_exclude() {
+        let dir = tempfile::tempdir().unwrap();
+        touch(dir.path(), "Audiobooks/chapter1.mp3");
+        touch(dir.path(), "Audiobooks/keep-this-one.mp3");
+        touch(dir.path(), "Music/song.mp3");
+
+        let scanner = MusicScanner::new()
+            .with_patterns(&["**/keep-*.mp3".to_string()], &["Audiobooks/**".to_string()])
+            .unwrap();
+
+        assert_eq!(
+            scanned_names(&scanner, dir.path()),
+            vec!["Audiobooks/keep-this-one.mp3", "Music/song.mp3"]
+        );
+    }
+
+    async fn scan_incremental(root: &Path, cancel: bool) -> (usize, Vec<ScanProgress>) {
+        let (progress_tx, mut progress_rx) = mpsc::channel(128);
+        let (tracks, _) = MusicScanner::new()
+            .scan_directories_incremental(&[root.to_path_buf()], progress_tx, Arc::new(AtomicBool::new(cancel)))
+            .await
+            .unwrap();
+        
+        let mut progress = Vec::new();
+        while let Some(update) = progress_rx.recv().await {
+            progress.push(update);
+        }
+        (tracks.len(), progress)
+    }
+
+    #[tokio::test]
+    async fn test_incremental_scan_cancellation() {
+        let dir = tempfile::tempdir().unwrap();
+        touch(dir.path(), "a.mp3");
+        touch(dir.path(), "b.mp3");
+
+        let (found, progress) = scan_incremental(dir.path(), false).await;
+        assert_eq!(found, 2);
+        assert!(matches!(progress.last(), Some(ScanProgress::Completed { total_tracks: 2 })));
+
+        // The channel still ends with a final message, so readers waiting on it aren't left hanging
+        let (found, progress) = scan_incremental(dir.path(), true).await;
+        assert_eq!(found, 0);
+        assert!(matches!(progress.last(), Some(ScanProgress::Cancelled { total_tracks: 0 })));
+    }
+
+    #[tokio::test]
+    async fn test_nested_directories_scan_once() {
+        let dir = tempfile::tempdir().unwrap();
+        touch(dir.path(), "Music/a.mp3");
+        touch(dir.path(), "Music/Rock/b.mp3");
+        touch(dir.path(), "Other/c.mp3");
+        let music = dir.path().join("Music");
+        let paths = vec![
+            music.join("Rock"),
+            music.clone(),
+            dir.path().join("Other/../Music"),
+            dir.path().join("Other"),
+            dir.path().join("Missing"),
+        ];
+        assert_eq!(scan_roots(&paths), vec![music.clone(), dir.path().join("Other"), dir.path().join("Missing")]);
+
+        let scanner = MusicScanner::new();
+        assert_eq!(scanner.scan_directories(&paths).unwrap().len(), 3);
+        let (progress_tx, _progress_rx) = mpsc::channel(128);
+        let (tracks, _) = scanner
+            .scan_directories_incremental(&paths, progress_tx, Arc::new(AtomicBool::new(false)))
+            .await
+            .unwrap();
+        let names: Vec<_> = tracks.iter().filter_map(|t| t.file_path.file_name()).collect();
+        assert_eq!(names, ["b.mp3", "a.mp3", "c.mp3"]); // by path, and "Rock/" sorts before "a.mp3"
+    }
+
+    #[test]
+    fn test_invalid_pattern_is_an_error() {
+        assert!(MusicScanner::new().with_patterns(&[], &["[unclosed".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_rescan_file_ignores_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        touch(dir.path(), "song.wav");
+        let path = dir.path().join("song.wav");
+        let metadata = fs::metadata(&path).unwrap();
+
+        // A cache entry that still matches size and mtime, left over from before a tag edit
+        let mut stale = Track::new(path.clone());
+        stale.metadata.title = Some("Old Title".to_string());
+        stale.duration = Some(std::time::Duration::from_secs(1));
+        let mut cache = ScanCache::new();
+        cache.insert(CachedTrack {
+            track: stale,
+            file_size: metadata.len(),
+            modified: file_modified_secs(&metadata).unwrap(),
+        });
+        let scanner = MusicScanner::new().with_cache(cache);
+
+        assert_eq!(scanner.scan_file(&path).unwrap().metadata.title.as_deref(), Some("Old Title"));
+        let fresh = scanner.rescan_file(&path).unwrap();
+        assert_ne!(fresh.metadata.title.as_deref(), Some("Old Title"));
+        assert!(fresh.content_hash.is_some());
+    }
+
+    #[test]
+    fn test_diagnose_directory_explains_skipped_files() {
+        let dir = tempfile::tempdir().unwrap();
+        touch(dir.path(), "song.mp3");
+        touch(dir.path(), "cover.jpg");
+        touch(dir.path(), ".hidden.mp3");
+        touch(dir.path(), "Podcasts/episode.mp3");
+        fs::write(dir.path().join("empty.flac"), b"").unwrap();
+
+        let scanner = MusicScanner::new().with_patterns(&[], &["Podcasts/**".to_string()]).unwrap();
+        let diagnoses = scanner.diagnose_directory(dir.path());
+        let outcome: Vec<_> = diagnoses.iter()
+            .map(|d| (d.path.file_name().unwrap().to_string_lossy().into_owned(), d.skipped, d.track.is_some()))
+            .collect();
+        assert_eq!(outcome, vec![
+            (".hidden.mp3".to_string(), Some("hidden file"), false),
+            ("episode.mp3".to_string(), Some("matches an exclude pattern"), false),
+            ("cover.jpg".to_string(), Some("extension not scanned"), false),
+            ("empty.flac".to_string(), Some("empty file"), false),
+            ("song.mp3".to_string(), None, true),
+        ]);
+        assert_eq!(diagnoses[4].format, AudioFormat::Mp3);
+        assert_eq!(diagnoses[2].format, AudioFormat::Unknown);
+    }
+}
@@ -0,0 +1,194 @@
+// Library search - fuzzy matching over strings prepared once per library load
+// Lowercasing every title, artist and filename on each keystroke was most of the cost
+
+use super::Track;
+use fuzzy_matcher::{clangd::ClangdMatcher, FuzzyMatcher};
+
+/// Which fields a search matches against
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SearchScope {
+    #[default]
+    All,    // title, artist, album and filename
+    Title,  // display title only
+    Artist,
+    Album,
+}
+
+impl SearchScope {
+    pub fn next(self) -> Self {
+        match self {
+            SearchScope::All => SearchScope::Title,
+            SearchScope::Title => SearchScope::Artist,
+            SearchScope::Artist => SearchScope::Album,
+            SearchScope::Album => SearchScope::All,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SearchScope::All => "All",
+            SearchScope::Title => "Title",
+            SearchScope::Artist => "Artist",
+            SearchScope::Album => "Album",
+        }
+    }
+}
+
+/// The searchable fields of one track, already lowercased
+#[derive(Debug, Clone, Default)]
+struct SearchEntry {
+    title: String, // display title, so untagged files match on their stem
+    artist: Option<String>,
+    album: Option<String>,
+    filename: String,
+}
+
+impl SearchEntry {
+    fn fields(&self, scope: SearchScope) -> Vec<&str> {
+        match scope {
+            SearchScope::All => std::iter::once(self.title.as_str())
+                .chain(self.artist.as_deref())
+                .chain(self.album.as_deref())
+                .chain(std::iter::once(self.filename.as_str()))
+                .collect(),
+            SearchScope::Title => vec![self.title.as_str()],
+            SearchScope::Artist => self.artist.as_deref().into_iter().collect(),
+            SearchScope::Album => self.album.as_deref().into_iter().collect(),
+        }
+    }
+}
+
+pub struct SearchIndex {
+    entries: Vec<SearchEntry>,
+    matcher: ClangdMatcher,
+}
+
+impl SearchIndex {
+    /// Index `tracks`; rebuild whenever the track list changes so indices line up
+    pub fn build(tracks: &[Track]) -> Self {
+        let entries = tracks.iter().map(|track| SearchEntry {
+            title: track.display_title().to_lowercase(),
+            artist: track.metadata.artist.as_deref().map(str::to_lowercase),
+            album: track.metadata.album.as_deref().map(str::to_lowercase),
+            filename: track.file_path.file_name()
+                .map(|name| name.to_string_lossy().to_lowercase())
+                .unwrap_or_default(),
+        }).collect();
+
+        // Everything is lowercased up front, so the matcher can skip case folding
+        Self { entries, matcher: ClangdMatcher::default().respect_case() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Score a single piece of text, e.g. a playlist name, the same way tracks are scored
+    pub fn score_text(&self, text: &str, query: &str) -> Option<i64> {
+        self.matcher.fuzzy_match(&query.to_lowercase(), &text.to_lowercase())
+            .filter(|&score| score > 0)
+    }
+
+    /// Score every track against `query` within `scope`, best match first
+    ///
+    /// Returns `(track index, score)`; tracks with equal scores keep library order.
+    pub fn search(&self, query: &str, scope: SearchScope) -> Vec<(usize, i64)> {
+        let query = query.to_lowercase();
+        let mut results: Vec<(usize, i64)> = self.entries.iter().enumerate()
+            .filter_map(|(idx, entry)| {
+                // ClangdMatcher's signature is fuzzy_match(choice, pattern); passing the query as the
+                // choice is deliberate - it's what lets "the ouytside" still find "The Outside"
+                entry.fields(scope)
+                    .into_iter()
+                    .filter_map(|field| self.matcher.fuzzy_match(&query, field))
+                    .max()
+                    .filter(|&score| score > 0)
+                    .map(|score| (idx, score))
+            })
+            .collect();
+        results.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hits(index: &SearchIndex, query: &str, scope: SearchScope) -> Vec<usize> {
+        index.search(query, scope).into_iter().map(|(idx, _)| idx).collect()
+    }
+
+    #[test]
+    fn test_matcher_argument_order() {
+        // The cases that used to be logged on every search keystroke
+        let matcher = ClangdMatcher::default();
+        assert!(matcher.fuzzy_match("the outside", "the outside").is_some());
+        assert!(matcher.fuzzy_match("the outside", "outside").is_some());
+        assert!(matcher.fuzzy_match("the outside", "the out").is_some());
+        assert!(matcher.fuzzy_match("the out", "the outside").is_none());
+
+        // A typo only matches with the query in the choice position
+        assert_eq!(matcher.fuzzy_match("the ouytside", "the outside"), Some(290));
+        assert!(matcher.fuzzy_match("the outside", "the ouytside").is_none());
+    }
+
+    #[test]
+    fn test_search_matches_title_artist_and_filename() {
+        let tracks = vec![
+            Track::test_fixture("/m/01.mp3").with_title("The Outside").with_artist("David Bowie"),
+            Track::test_fixture("/m/warszawa.flac"),
+            Track::test_fixture("/m/heroes.mp3").with_title("Heroes").with_artist("David Bowie"),
+            Track::test_fixture("/m/sound and vision.mp3").with_title("Untitled"),
+        ];
+        let index = SearchIndex::build(&tracks);
+        assert_eq!(index.len(), 4);
+
+        let all = |query: &str| hits(&index, query, SearchScope::All);
+        assert_eq!(all("THE OUYTSIDE"), vec![0]);
+        assert_eq!(all("warszawa"), vec![1]);
+        assert_eq!(all("sound and vision.mp3"), vec![3]);
+
+        let bowie = all("david bowie");
+        assert_eq!(bowie.len(), 2);
+        assert!(bowie.contains(&0) && bowie.contains(&2));
+        assert!(all("zzzz").is_empty());
+    }
+
+    #[test]
+    fn test_search_ranks_best_match_first() {
+        let tracks = vec![
+            Track::test_fixture("/m/a.mp3").with_title("Hello"),
+            Track::test_fixture("/m/b.mp3").with_title("Hello Goodbye"),
+        ];
+        let results = SearchIndex::build(&tracks).search("hello goodbye", SearchScope::All);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].0, 1);
+        assert!(results[0].1 > results[1].1);
+    }
+
+    #[test]
+    fn test_scope_restricts_fields() {
+        let mut low = Track::test_fixture("/m/low.mp3").with_title("Warszawa").with_artist("David Bowie");
+        low.metadata.album = Some("Low".to_string());
+        let tracks = vec![
+            low,
+            Track::test_fixture("/m/bowie.mp3").with_title("David Bowie").with_artist("Someone Else"),
+        ];
+        let index = SearchIndex::build(&tracks);
+
+        assert_eq!(hits(&index, "david bowie", SearchScope::All).len(), 2);
+        assert_eq!(hits(&index, "david bowie", SearchScope::Title), vec![1]);
+        assert_eq!(hits(&index, "david bowie", SearchScope::Artist), vec![0]);
+        assert_eq!(hits(&index, "low", SearchScope::Album), vec![0]);
+        assert_eq!(hits(&index, "low", SearchScope::All), vec![0]);
+        assert!(hits(&index, "low", SearchScope::Title).is_empty());
+
+        assert!(index.score_text("Road Trip", "ROAD TRIP").is_some());
+        assert!(index.score_text("Road Trip", "gym").is_none());
+    }
+}
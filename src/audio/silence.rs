@@ -0,0 +1,89 @@
+// Silence detection - where a track's silent intro ends and its silent outro begins
+// Finding it means decoding the whole file, so results are kept by content hash and worked out once
+
+use std::time::Duration;
+
+/// Quieter than this counts as silence (about -60 dBFS)
+const SILENCE_THRESHOLD: f32 = 0.001;
+
+/// Shorter silent stretches are left alone - they're part of the song
+const MIN_SILENCE: Duration = Duration::from_secs(1);
+
+/// Kept either side of the sound, so a soft first note or a reverb tail isn't clipped
+const MARGIN: Duration = Duration::from_millis(100);
+
+/// Where a track's audible part starts and ends
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SilenceBounds {
+    pub start: Duration,       // zero without a silent intro
+    pub end: Option<Duration>, // `None` without a silent outro
+}
+
+impl SilenceBounds {
+    pub fn is_empty(&self) -> bool {
+        self.start.is_zero() && self.end.is_none()
+    }
+}
+
+/// Bounds from interleaved samples; a track that is silent throughout is left untouched
+pub fn find_bounds(samples: impl IntoIterator<Item = f32>, channels: u16, sample_rate: u32) -> SilenceBounds {
+    let channels = channels.max(1) as u64;
+    let mut total = 0u64;
+    let mut first_loud = None;
+    let mut last_loud = 0u64;
+    for sample in samples {
+        if sample.abs() > SILENCE_THRESHOLD {
+            first_loud.get_or_insert(total);
+            last_loud = total;
+        }
+        total += 1;
+    }
+    let Some(first_loud) = first_loud else {
+        return SilenceBounds::default();
+    };
+
+    let at = |sample: u64| Duration::from_secs_f64((sample / channels) as f64 / sample_rate.max(1) as f64);
+    let (sound_starts, sound_ends, length) = (at(first_loud), at(last_loud + 1), at(total));
+    SilenceBounds {
+        start: if sound_starts >= MIN_SILENCE { sound_starts - MARGIN } else { Duration::ZERO },
+        end: (length.saturating_sub(sound_ends) >= MIN_SILENCE).then(|| sound_ends + MARGIN),
+    }
+}
+
+/// Decode `path` and find its bounds; takes a while for a long track, so run it off the UI thread
+#[cfg(feature = "silence")]
+pub fn detect_silence(path: &std::path::Path) -> anyhow::Result<SilenceBounds> {
+    use rodio::Source;
+
+    let file = std::io::BufReader::new(std::fs::File::open(path)?);
+    let decoder = rodio::Decoder::new(file)?;
+    let (channels, sample_rate) = (decoder.channels(), decoder.sample_rate());
+    Ok(find_bounds(decoder.convert_samples::<f32>(), channels, sample_rate))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Mono at 1kHz: `silent` seconds, `loud` seconds of signal, then `silent_after` seconds
+    fn track(silent: f64, loud: f64, silent_after: f64) -> Vec<f32> {
+        let samples = |secs: f64, value: f32| std::iter::repeat_n(value, (secs * 1000.0) as usize);
+        samples(silent, 0.0004).chain(samples(loud, 0.3)).chain(samples(silent_after, 0.0)).collect()
+    }
+
+    #[test]
+    fn test_find_bounds() {
+        let bounds = find_bounds(track(4.0, 10.0, 3.0), 1, 1000);
+        assert_eq!(bounds.start, Duration::from_millis(3900));
+        assert_eq!(bounds.end, Some(Duration::from_millis(14_100)));
+
+        // Stereo frames count once
+        let stereo: Vec<f32> = track(2.0, 1.0, 0.0).into_iter().flat_map(|s| [s, s]).collect();
+        assert_eq!(find_bounds(stereo, 2, 1000), SilenceBounds { start: Duration::from_millis(1900), end: None });
+
+        // A breath before the first note, all silence, nothing at all
+        assert!(find_bounds(track(0.5, 5.0, 0.5), 1, 1000).is_empty());
+        assert!(find_bounds(track(6.0, 0.0, 0.0), 1, 1000).is_empty());
+        assert!(find_bounds(Vec::new(), 2, 44_100).is_empty());
+    }
+}
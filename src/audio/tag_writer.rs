@@ -0,0 +1,158 @@
+// Tag writing - puts metadata edited in the TUI back into the files it came from
+// MP3 (ID3v2.4) and MP4 are written; the other formats stay read-only for now
+
+use super::{AudioFormat, TrackMetadata};
+use anyhow::Result;
+use std::path::Path;
+
+/// Whether `write_tags` can handle files with this extension
+pub fn can_write_tags(path: &Path) -> bool {
+    matches!(format_of(path), AudioFormat::Mp3 | AudioFormat::Mp4)
+}
+
+/// Write the fields the editor knows about into the file, leaving any other frames alone
+///
+/// Empty fields are removed from the file rather than written blank.
+pub fn write_tags(path: &Path, metadata: &TrackMetadata) -> Result<()> {
+    match format_of(path) {
+        AudioFormat::Mp3 => write_id3(path, metadata),
+        AudioFormat::Mp4 => write_mp4(path, metadata),
+        format => Err(anyhow::anyhow!("Writing {} tags isn't supported yet", format.name())),
+    }
+}
+
+fn format_of(path: &Path) -> AudioFormat {
+    AudioFormat::from_extension(&path.extension().unwrap_or_default().to_string_lossy())
+}
+
+fn write_id3(path: &Path, metadata: &TrackMetadata) -> Result<()> {
+    use id3::TagLike;
+
+    let mut tag = match id3::Tag::read_from_path(path) {
+        Ok(tag) => tag,
+        Err(e) if matches!(e.kind, id3::ErrorKind::NoTag) => id3::Tag::new(),
+        Err(e) => return Err(e.into()),
+    };
+
+    match &metadata.title {
+        Some(title) => tag.set_title(title.as_str()),
+        None => tag.remove_title(),
+    }
+    match &metadata.artist {
+        Some(artist) => tag.set_artist(artist.as_str()),
+        None => tag.remove_artist(),
+    }
+    match &metadata.album {
+        Some(album) => tag.set_album(album.as_str()),
+        None => tag.remove_album(),
+    }
+    match &metadata.album_artist {
+        Some(album_artist) => tag.set_album_artist(album_artist.as_str()),
+        None => tag.remove_album_artist(),
+    }
+    match &metadata.genre {
+        Some(genre) => tag.set_genre(genre.as_str()),
+        None => tag.remove_genre(),
+    }
+    match metadata.year {
+        Some(year) => tag.set_year(year as i32),
+        None => tag.remove_year(),
+    }
+    match metadata.track_number {
+        Some(track) => tag.set_track(track),
+        None => tag.remove_track(),
+    }
+    match metadata.disc_number {
+        Some(disc) => tag.set_disc(disc),
+        None => tag.remove_disc(),
+    }
+
+    tag.write_to_path(path, id3::Version::Id3v24)?;
+    Ok(())
+}
+
+fn write_mp4(path: &Path, metadata: &TrackMetadata) -> Result<()> {
+    let mut tag = mp4ameta::Tag::read_from_path(path)?;
+
+    match &metadata.title {
+        Some(title) => tag.set_title(title.as_str()),
+        None => tag.remove_title(),
+    }
+    match &metadata.artist {
+        Some(artist) => tag.set_artist(artist.as_str()),
+        None => tag.remove_artists(),
+    }
+    match &metadata.album {
+        Some(album) => tag.set_album(album.as_str()),
+        None => tag.remove_album(),
+    }
+    match &metadata.album_artist {
+        Some(album_artist) => tag.set_album_artist(album_artist.as_str()),
+        None => tag.remove_album_artists(),
+    }
+    match &metadata.genre {
+        Some(genre) => tag.set_genre(genre.as_str()),
+        None => tag.remove_genres(),
+    }
+    match metadata.year {
+        Some(year) => tag.set_year(year.to_string()),
+        None => tag.remove_year(),
+    }
+    // MP4 keeps these as 16-bit numbers
+    match metadata.track_number.and_then(|n| u16::try_from(n).ok()) {
+        Some(track) => tag.set_track_number(track),
+        None => tag.remove_track_number(),
+    }
+    match metadata.disc_number.and_then(|n| u16::try_from(n).ok()) {
+        Some(disc) => tag.set_disc_number(disc),
+        None => tag.remove_disc_number(),
+    }
+
+    tag.write_to_path(path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_id3_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("song.mp3");
+        std::fs::write(&path, b"not really audio, but ID3 only prepends").unwrap();
+
+        let metadata = TrackMetadata {
+            title: Some("Hyperballad".to_string()),
+            artist: Some("Björk".to_string()),
+            album: Some("Post".to_string()),
+            track_number: Some(6),
+            year: Some(1995),
+            genre: Some("Electronic".to_string()),
+            ..Default::default()
+        };
+        write_tags(&path, &metadata).unwrap();
+        let read_back = TrackMetadata::from_id3_tag(&id3::Tag::read_from_path(&path).unwrap());
+        assert_eq!(read_back.title.as_deref(), Some("Hyperballad"));
+        assert_eq!(read_back.artist.as_deref(), Some("Björk"));
+        assert_eq!(read_back.track_number, Some(6));
+        assert_eq!(read_back.year, Some(1995));
+
+        // Clearing a field takes it out of the file
+        let cleared = TrackMetadata { genre: None, ..metadata };
+        write_tags(&path, &cleared).unwrap();
+        let read_back = TrackMetadata::from_id3_tag(&id3::Tag::read_from_path(&path).unwrap());
+        assert_eq!(read_back.genre, None);
+        assert_eq!(read_back.album.as_deref(), Some("Post"));
+    }
+
+    #[test]
+    fn test_unsupported_formats_are_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("song.flac");
+        std::fs::write(&path, b"fLaC").unwrap();
+        assert!(!can_write_tags(&path));
+        assert!(write_tags(&path, &TrackMetadata::default()).is_err());
+        assert!(can_write_tags(Path::new("book.m4b")));
+    }
+}
@@ -0,0 +1,11 @@
+//! Helpers shared by the audio test modules
+
+use std::fs;
+use std::path::Path;
+
+/// Creates a placeholder file (and its directories) under `root` - enough for the scanner to find, not to decode
+pub(crate) fn touch(root: &Path, relative: &str) {
+    let path = root.join(relative);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, b"not really audio").unwrap();
+}
@@ -1,3 +1,4 @@
+use super::rename::sanitize_file_name;
 use super::AudioFormat;
 use anyhow::Result;
 use id3::TagLike;
@@ -18,9 +19,11 @@ pub struct Track {
     pub file_size: u64,
     pub duration: Option<Duration>,
     pub content_hash: Option<u64>, // xxhash64 for deduplication and move detection
+    #[serde(default)]
+    pub modified: Option<u64>, // file mtime in seconds since the Unix epoch, drives "recently added"
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct TrackMetadata {
     pub title: Option<String>,
     pub artist: Option<String>,
@@ -49,6 +52,7 @@ impl Track {
             file_size: 0,
             duration: None,
             content_hash: None,
+            modified: None,
         }
     }
 
@@ -60,6 +64,17 @@ impl Track {
         self
     }
 
+    /// Track id for a content hash, so a file keeps its identity (and listening history) across
+    /// re-scans, moves and renames; identical copies share it
+    pub fn content_id(hash: u64) -> Uuid {
+        // The hash goes around bytes 6-9, where the version and variant bits are written
+        let hash = hash.to_be_bytes();
+        let mut bytes = *b"\0\0\0\0\0\0\0\0\0\0\0\0btun";
+        bytes[..6].copy_from_slice(&hash[..6]);
+        bytes[10..12].copy_from_slice(&hash[6..]);
+        uuid::Builder::from_custom_bytes(bytes).into_uuid()
+    }
+
     /// Compute xxhash64 of file content for deduplication and move detection
     pub fn compute_content_hash(&mut self) -> Result<u64> {
         if let Some(hash) = self.content_hash {
@@ -89,11 +104,7 @@ impl Track {
             }
             Some(existing) => {
                 // Update if the difference is more than 2 seconds (accounts for fade-outs, etc.)
-                let diff = if actual_duration > existing {
-                    actual_duration - existing
-                } else {
-                    existing - actual_duration
-                };
+                let diff = actual_duration.abs_diff(existing);
                 
                 if diff > Duration::from_secs(2) {
                     self.duration = Some(actual_duration);
@@ -143,6 +154,61 @@ impl Track {
             .unwrap_or_else(|| "Unknown Album".to_string())
     }
 
+    /// File name for this track from `pattern`, e.g. `{track:02} - {artist} - {title}.{ext}`
+    ///
+    /// Placeholders: `{title}`, `{artist}`, `{album}`, `{album_artist}`, `{genre}`, `{year}`,
+    /// `{track}`, `{disc}` and `{ext}`; `{track:02}` pads with zeros. Missing numbers, genres and
+    /// years leave nothing, and separators left dangling at either end are dropped.
+    pub fn suggested_filename(&self, pattern: &str) -> String {
+        let number = |value: Option<u32>, spec: Option<&str>| {
+            let width = spec.and_then(|spec| spec.parse::<usize>().ok()).unwrap_or(0);
+            value.map(|n| format!("{:0width$}", n, width = width)).unwrap_or_default()
+        };
+        let extension = self.file_path.extension().map(|ext| ext.to_string_lossy().into_owned()).unwrap_or_default();
+
+        let mut name = String::new();
+        let mut rest = pattern;
+        while let Some(start) = rest.find('{') {
+            name.push_str(&rest[..start]);
+            let Some(len) = rest[start..].find('}') else {
+                rest = &rest[start..];
+                break;
+            };
+            let placeholder = &rest[start + 1..start + len];
+            let (key, spec) = match placeholder.split_once(':') {
+                Some((key, spec)) => (key, Some(spec)),
+                None => (placeholder, None),
+            };
+            let value = match key {
+                "title" => self.display_title(),
+                "artist" => self.display_artist(),
+                "album" => self.display_album(),
+                "album_artist" => self.metadata.album_artist.clone().unwrap_or_else(|| self.display_artist()),
+                "genre" => self.metadata.genre.clone().unwrap_or_default(),
+                "year" => number(self.metadata.year, spec),
+                "track" => number(self.metadata.track_number, spec),
+                "disc" => number(self.metadata.disc_number, spec),
+                "ext" => extension.clone(),
+                _ => format!("{{{}}}", placeholder), // not ours, keep it as written
+            };
+            name.push_str(&sanitize_file_name(&value));
+            rest = &rest[start + len + 1..];
+        }
+        name.push_str(rest);
+
+        let dotted_extension = format!(".{}", extension);
+        let (stem, ext) = match name.strip_suffix(&dotted_extension) {
+            Some(stem) if !extension.is_empty() => (stem, dotted_extension.as_str()),
+            _ => (name.as_str(), ""),
+        };
+        let stem = sanitize_file_name(stem);
+        let stem = stem.trim_matches(|c: char| c.is_whitespace() || matches!(c, '-' | '_' | '.'));
+        match stem {
+            "" => format!("{}{}", self.file_path.file_stem().unwrap_or_default().to_string_lossy(), ext),
+            stem => format!("{}{}", stem, ext),
+        }
+    }
+
     pub fn duration_seconds(&self) -> Option<u64> {
         self.duration.map(|d| d.as_secs())
     }
@@ -152,19 +218,51 @@ impl Track {
     }
 }
 
-impl Default for TrackMetadata {
-    fn default() -> Self {
-        Self {
-            title: None,
-            artist: None,
-            album: None,
-            album_artist: None,
-            track_number: None,
-            disc_number: None,
-            year: None,
-            genre: None,
-            duration_ms: None,
-        }
+/// Test tracks that were never read from disk - chain the setters for whatever a test looks at
+#[cfg(test)]
+impl Track {
+    pub(crate) fn test_fixture(path: impl Into<PathBuf>) -> Self {
+        Self::new(path.into())
+    }
+
+    pub(crate) fn with_title(mut self, title: &str) -> Self {
+        self.metadata.title = Some(title.to_string());
+        self
+    }
+
+    pub(crate) fn with_artist(mut self, artist: &str) -> Self {
+        self.metadata.artist = Some(artist.to_string());
+        self
+    }
+
+    pub(crate) fn with_album(mut self, album: &str) -> Self {
+        self.metadata.album = Some(album.to_string());
+        self
+    }
+
+    pub(crate) fn with_genre(mut self, genre: &str) -> Self {
+        self.metadata.genre = Some(genre.to_string());
+        self
+    }
+
+    pub(crate) fn with_year(mut self, year: u32) -> Self {
+        self.metadata.year = Some(year);
+        self
+    }
+
+    pub(crate) fn with_track_number(mut self, number: u32) -> Self {
+        self.metadata.track_number = Some(number);
+        self
+    }
+
+    pub(crate) fn with_duration_secs(mut self, secs: u64) -> Self {
+        self.duration = Some(Duration::from_secs(secs));
+        self
+    }
+
+    pub(crate) fn with_file_size(mut self, size: u64) -> Self {
+        self.file_size = size;
+        self
     }
 }
 
@@ -183,3 +281,37 @@ impl TrackMetadata {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_suggested_filename() {
+        let mut track = Track::new(PathBuf::from("/music/old name.flac"));
+        track.metadata.title = Some("What's Up?".to_string());
+        track.metadata.artist = Some("4 Non Blondes".to_string());
+        track.metadata.track_number = Some(3);
+        track.metadata.year = Some(1992);
+
+        assert_eq!(track.suggested_filename("{track:02} - {artist} - {title}.{ext}"), "03 - 4 Non Blondes - What's Up.flac");
+        assert_eq!(track.suggested_filename("{year}/{album} {unknown}.{ext}"), "1992_Unknown Album {unknown}.flac");
+
+        // No track number: no dangling separator, and never an empty name
+        track.metadata.track_number = None;
+        assert_eq!(track.suggested_filename("{track:02} - {title}.{ext}"), "What's Up.flac");
+        assert_eq!(track.suggested_filename("{genre}.{ext}"), "old name.flac");
+    }
+
+    #[test]
+    fn test_content_id_is_stable_per_hash() {
+        let hash = 0xdead_beef_cafe_f00d_u64;
+        assert_eq!(Track::content_id(hash), Track::content_id(hash));
+        assert_eq!(Track::content_id(hash).get_version_num(), 8);
+
+        // Every bit of the hash counts, including the ones next to the version and variant
+        let ids: std::collections::HashSet<Uuid> = (0..64).map(|bit| Track::content_id(hash ^ (1 << bit))).collect();
+        assert_eq!(ids.len(), 64);
+        assert!(!ids.contains(&Track::content_id(hash)));
+    }
+}
@@ -0,0 +1,343 @@
+// Visualizer - a copy of what goes to the sink, boiled down to a row of spectrum bars
+// Pays nothing while off: the tap checks one flag per sample and the ring buffer only exists while enabled
+
+#[cfg(feature = "audio")]
+use rodio::{source::SeekError, Source};
+use std::f32::consts::PI;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::thread;
+use std::time::Duration;
+
+pub const BAR_COUNT: usize = 16;
+
+const WINDOW: usize = 1024; // mono samples per analysis, a power of two for the FFT
+#[cfg(feature = "audio")]
+const CHUNK: usize = 256; // samples a tap collects before handing them over, so it rarely touches the lock
+const ANALYSIS_INTERVAL: Duration = Duration::from_millis(50); // about 20 updates a second
+const LOWEST_FREQ: f32 = 40.0;
+const HIGHEST_FREQ: f32 = 16_000.0;
+const FLOOR_DB: f32 = -60.0; // an empty bar
+const RELEASE: f32 = 0.8; // share of last update's height a falling bar keeps
+
+/// Shared switch and bar heights; every source made by `tap` feeds the same analysis
+#[derive(Debug, Clone, Default)]
+pub struct Visualizer {
+    shared: Arc<Shared>,
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    enabled: AtomicBool,
+    analyzing: AtomicBool, // the analysis thread is running
+    sample_rate: AtomicU32, // of the samples in the ring
+    ring: Mutex<Option<RingBuffer>>, // `None` while off
+    levels: Mutex<[f32; BAR_COUNT]>,
+}
+
+impl Visualizer {
+    pub fn new(enabled: bool) -> Self {
+        let visualizer = Self::default();
+        visualizer.set_enabled(enabled);
+        visualizer
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.shared.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Turning it on allocates the ring buffer and starts the analysis thread, turning it off frees both
+    pub fn set_enabled(&self, enabled: bool) {
+        if enabled {
+            self.shared.ring.lock().unwrap().get_or_insert_with(|| RingBuffer::new(WINDOW));
+            self.shared.enabled.store(true, Ordering::SeqCst);
+            if self.shared.analyzing.compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst).is_ok() {
+                let shared = Arc::downgrade(&self.shared);
+                thread::spawn(move || analysis_loop(shared));
+            }
+        } else {
+            self.shared.enabled.store(false, Ordering::SeqCst);
+            *self.shared.ring.lock().unwrap() = None;
+            *self.shared.levels.lock().unwrap() = [0.0; BAR_COUNT];
+        }
+    }
+
+    /// Bar heights from 0.0 to 1.0, lowest frequencies first; all zero while off
+    pub fn levels(&self) -> [f32; BAR_COUNT] {
+        *self.shared.levels.lock().unwrap()
+    }
+
+    /// Copy the samples of `input` to the visualizer as they play; the samples themselves pass through untouched
+    #[cfg(feature = "audio")]
+    pub fn tap<S: Source<Item = f32>>(&self, input: S) -> VisualizerTap<S> {
+        VisualizerTap {
+            input,
+            visualizer: self.clone(),
+            chunk: Vec::new(),
+            frame_sum: 0.0,
+            channel: 0,
+        }
+    }
+}
+
+/// Analyze the ring about 20 times a second until the visualizer is turned off or dropped
+fn analysis_loop(shared: Weak<Shared>) {
+    let mut window = vec![0.0; WINDOW];
+    loop {
+        let Some(state) = shared.upgrade() else {
+            return;
+        };
+        if !state.enabled.load(Ordering::SeqCst) {
+            state.analyzing.store(false, Ordering::SeqCst);
+            // Turned back on before we noticed and nobody else picked it up
+            if !state.enabled.load(Ordering::SeqCst)
+                || state.analyzing.compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst).is_err()
+            {
+                return;
+            }
+        }
+
+        let filled = match state.ring.lock().unwrap().as_mut() {
+            Some(ring) => ring.drain_into(&mut window),
+            None => false,
+        };
+        let bars = if filled {
+            analyze(&window, state.sample_rate.load(Ordering::Relaxed))
+        } else {
+            [0.0; BAR_COUNT] // nothing played since the last update, let the bars fall
+        };
+        {
+            let mut levels = state.levels.lock().unwrap();
+            for (level, bar) in levels.iter_mut().zip(bars) {
+                *level = bar.max(*level * RELEASE);
+            }
+        }
+
+        drop(state);
+        thread::sleep(ANALYSIS_INTERVAL);
+    }
+}
+
+/// Bar heights for one window of mono samples
+fn analyze(samples: &[f32], sample_rate: u32) -> [f32; BAR_COUNT] {
+    let n = samples.len();
+    let mut re: Vec<f32> = samples.iter().enumerate()
+        .map(|(i, s)| s * 0.5 * (1.0 - (2.0 * PI * i as f32 / n as f32).cos())) // Hann window
+        .collect();
+    let mut im = vec![0.0; n];
+    fft(&mut re, &mut im);
+
+    let bin_width = sample_rate.max(1) as f32 / n as f32;
+    let mut bars = [0.0; BAR_COUNT];
+    for (bar, level) in bars.iter_mut().enumerate() {
+        let (low, high) = band_edges(bar, sample_rate);
+        let first = ((low / bin_width) as usize).max(1);
+        let last = ((high / bin_width) as usize).clamp(first, n / 2 - 1);
+        let peak = (first..=last).map(|bin| re[bin].hypot(im[bin])).fold(0.0f32, f32::max);
+
+        // A full-scale sine through the Hann window peaks at n/4
+        let db = 20.0 * (peak / (n as f32 / 4.0)).max(1e-9).log10();
+        *level = ((db - FLOOR_DB) / -FLOOR_DB).clamp(0.0, 1.0);
+    }
+    bars
+}
+
+/// Frequency range of `bar` in Hz, spaced evenly on a log scale up to the stream's Nyquist limit
+fn band_edges(bar: usize, sample_rate: u32) -> (f32, f32) {
+    let highest = HIGHEST_FREQ.min(sample_rate as f32 / 2.0).max(LOWEST_FREQ * 2.0);
+    let ratio = (highest / LOWEST_FREQ).powf(1.0 / BAR_COUNT as f32);
+    (LOWEST_FREQ * ratio.powi(bar as i32), LOWEST_FREQ * ratio.powi(bar as i32 + 1))
+}
+
+/// In-place radix-2 FFT; the length must be a power of two
+fn fft(re: &mut [f32], im: &mut [f32]) {
+    let n = re.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * PI / len as f32;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (sin, cos) = (angle * k as f32).sin_cos();
+                let (a, b) = (start + k, start + k + len / 2);
+                let tr = re[b] * cos - im[b] * sin;
+                let ti = re[b] * sin + im[b] * cos;
+                re[b] = re[a] - tr;
+                im[b] = im[a] - ti;
+                re[a] += tr;
+                im[a] += ti;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+/// The latest `capacity` mono samples
+#[derive(Debug)]
+struct RingBuffer {
+    samples: Vec<f32>,
+    next: usize,  // where the next sample goes, also the oldest one once full
+    fresh: usize, // samples written since the last drain
+}
+
+impl RingBuffer {
+    fn new(capacity: usize) -> Self {
+        Self { samples: vec![0.0; capacity], next: 0, fresh: 0 }
+    }
+
+    #[cfg(feature = "audio")]
+    fn extend(&mut self, samples: &[f32]) {
+        for &sample in samples {
+            self.samples[self.next] = sample;
+            self.next = (self.next + 1) % self.samples.len();
+        }
+        self.fresh += samples.len();
+    }
+
+    /// Copy the samples out oldest first; false if nothing new arrived since the last call
+    fn drain_into(&mut self, out: &mut [f32]) -> bool {
+        if self.fresh == 0 {
+            return false;
+        }
+        let (newer, older) = self.samples.split_at(self.next);
+        out[..older.len()].copy_from_slice(older);
+        out[older.len()..].copy_from_slice(newer);
+        self.fresh = 0;
+        true
+    }
+}
+
+/// A `Source` whose samples are also copied to a `Visualizer`
+#[cfg(feature = "audio")]
+pub struct VisualizerTap<S> {
+    input: S,
+    visualizer: Visualizer,
+    chunk: Vec<f32>, // mono samples not handed over yet, only grows while enabled
+    frame_sum: f32,  // channels of the current frame added up so far
+    channel: usize,  // channel the next sample belongs to
+}
+
+#[cfg(feature = "audio")]
+impl<S: Source<Item = f32>> VisualizerTap<S> {
+    /// Hand the chunk to the ring, or drop it if the analysis holds the lock - the audio thread never waits
+    fn flush(&mut self) {
+        let shared = &self.visualizer.shared;
+        if let Ok(mut ring) = shared.ring.try_lock() {
+            if let Some(ring) = ring.as_mut() {
+                ring.extend(&self.chunk);
+                shared.sample_rate.store(self.input.sample_rate(), Ordering::Relaxed);
+            }
+        }
+        self.chunk.clear();
+    }
+}
+
+#[cfg(feature = "audio")]
+impl<S: Source<Item = f32>> Iterator for VisualizerTap<S> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let sample = self.input.next()?;
+
+        if self.visualizer.is_enabled() {
+            let channels = self.input.channels().max(1) as usize;
+            self.frame_sum += sample;
+            self.channel += 1;
+            if self.channel >= channels {
+                self.chunk.push(self.frame_sum / channels as f32);
+                self.frame_sum = 0.0;
+                self.channel = 0;
+                if self.chunk.len() >= CHUNK {
+                    self.flush();
+                }
+            }
+        }
+
+        Some(sample)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+#[cfg(feature = "audio")]
+impl<S: Source<Item = f32>> Source for VisualizerTap<S> {
+    fn current_frame_len(&self) -> Option<usize> {
+        self.input.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)?;
+        self.chunk.clear();
+        self.frame_sum = 0.0;
+        self.channel = 0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "audio")]
+    use rodio::buffer::SamplesBuffer;
+
+    fn sine(freq: f32, sample_rate: u32, samples: usize) -> Vec<f32> {
+        (0..samples)
+            .map(|n| 0.5 * (2.0 * PI * freq * n as f32 / sample_rate as f32).sin())
+            .collect()
+    }
+
+    #[test]
+    #[cfg(feature = "audio")]
+    fn test_tap_leaves_samples_untouched() {
+        let input = sine(440.0, 44_100, 4_410);
+        let stereo: Vec<f32> = input.iter().flat_map(|s| [*s, -*s]).collect();
+
+        for enabled in [false, true] {
+            let visualizer = Visualizer::new(enabled);
+            let output: Vec<f32> = visualizer.tap(SamplesBuffer::new(2, 44_100, stereo.clone())).collect();
+            assert_eq!(output, stereo);
+            assert_eq!(visualizer.shared.ring.lock().unwrap().is_some(), enabled);
+        }
+    }
+
+    #[test]
+    fn test_tone_lights_up_its_band() {
+        for sample_rate in [22_050, 44_100, 48_000] {
+            let bars = analyze(&sine(1_000.0, sample_rate, WINDOW), sample_rate);
+            let loudest = (0..BAR_COUNT).max_by(|a, b| bars[*a].total_cmp(&bars[*b])).unwrap();
+            let (low, high) = band_edges(loudest, sample_rate);
+
+            assert!((low..high).contains(&1_000.0), "{}Hz: loudest bar {}..{}", sample_rate, low, high);
+            assert!(bars[loudest] > 0.8);
+            assert!(bars[BAR_COUNT - 1] < 0.3);
+        }
+        assert_eq!(analyze(&[0.0; WINDOW], 44_100), [0.0; BAR_COUNT]);
+    }
+}
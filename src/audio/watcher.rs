@@ -0,0 +1,76 @@
+// Library watcher - notices files being added/removed while the player runs
+// Raw filesystem events are noisy, so they're batched until things go quiet
+
+use anyhow::Result;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use std::sync::mpsc as std_mpsc;
+use std::thread;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+/// A debounced batch of paths that were created, modified or removed
+#[derive(Debug, Clone)]
+pub struct LibraryChange {
+    pub paths: Vec<PathBuf>,
+}
+
+/// Keeps the underlying watcher alive - dropping it stops watching
+pub struct LibraryWatcher {
+    _watcher: RecommendedWatcher,
+}
+
+impl LibraryWatcher {
+    /// Watch `directories` recursively, sending a `LibraryChange` once events stop for `debounce`
+    pub fn start(
+        directories: &[PathBuf],
+        debounce: Duration,
+    ) -> Result<(Self, mpsc::UnboundedReceiver<LibraryChange>)> {
+        let (raw_tx, raw_rx) = std_mpsc::channel::<Vec<PathBuf>>();
+        let (change_tx, change_rx) = mpsc::unbounded_channel();
+        
+        let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+            match res {
+                Ok(event) if !event.kind.is_access() => {
+                    let _ = raw_tx.send(event.paths);
+                }
+                Ok(_) => {}
+                Err(e) => warn!("Library watcher error: {}", e),
+            }
+        })?;
+        
+        for directory in directories.iter().filter(|d| d.exists()) {
+            watcher.watch(directory, RecursiveMode::Recursive)?;
+            debug!("Watching {:?} for library changes", directory);
+        }
+        
+        thread::spawn(move || Self::debounce_loop(raw_rx, change_tx, debounce));
+        
+        Ok((Self { _watcher: watcher }, change_rx))
+    }
+    
+    fn debounce_loop(
+        raw_rx: std_mpsc::Receiver<Vec<PathBuf>>,
+        change_tx: mpsc::UnboundedSender<LibraryChange>,
+        debounce: Duration,
+    ) {
+        // Block for the first event of a burst, then keep collecting until it goes quiet
+        while let Ok(paths) = raw_rx.recv() {
+            let mut pending: BTreeSet<PathBuf> = paths.into_iter().collect();
+            
+            loop {
+                match raw_rx.recv_timeout(debounce) {
+                    Ok(paths) => pending.extend(paths),
+                    Err(std_mpsc::RecvTimeoutError::Timeout) => break,
+                    Err(std_mpsc::RecvTimeoutError::Disconnected) => return,
+                }
+            }
+            
+            if change_tx.send(LibraryChange { paths: pending.into_iter().collect() }).is_err() {
+                return; // App is gone
+            }
+        }
+    }
+}
@@ -0,0 +1,214 @@
+// WAV headers - duration from the fmt and data chunks, tags from LIST/INFO or an embedded ID3 chunk
+// Only chunk headers and tag chunks are read; the audio itself is seeked past
+
+use super::TrackMetadata;
+use anyhow::Result;
+use std::fs::File;
+use std::io::{BufReader, Cursor, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::time::Duration;
+
+/// Tag chunks bigger than this are skipped rather than read into memory
+const MAX_TAG_CHUNK: u32 = 1 << 20;
+
+/// What the header of a WAV file says about it
+#[derive(Debug, Clone, Default)]
+pub struct WavInfo {
+    pub duration: Option<Duration>, // data length over byte rate; `None` without both chunks
+    pub metadata: TrackMetadata,    // ID3 wins over LIST/INFO where both have a field
+}
+
+pub fn read_wav_info(path: &Path) -> Result<WavInfo> {
+    read_wav(BufReader::new(File::open(path)?))
+}
+
+pub fn read_wav<R: Read + Seek>(mut reader: R) -> Result<WavInfo> {
+    let file_len = reader.seek(SeekFrom::End(0))?;
+    reader.seek(SeekFrom::Start(0))?;
+
+    let mut header = [0u8; 12];
+    reader.read_exact(&mut header)?;
+    if &header[0..4] != b"RIFF" || &header[8..12] != b"WAVE" {
+        return Err(anyhow::anyhow!("Not a RIFF/WAVE file"));
+    }
+
+    let mut byte_rate = None;
+    let mut data_len = None;
+    let mut info = TrackMetadata::default();
+    let mut id3 = None;
+    let mut chunk_header = [0u8; 8];
+    // A truncated final chunk just ends the walk; whatever was found before it still counts
+    while reader.read_exact(&mut chunk_header).is_ok() {
+        let id = [chunk_header[0], chunk_header[1], chunk_header[2], chunk_header[3]];
+        let size = u32::from_le_bytes([chunk_header[4], chunk_header[5], chunk_header[6], chunk_header[7]]);
+        let start = reader.stream_position()?;
+        let remaining = file_len.saturating_sub(start);
+
+        match &id {
+            b"fmt " if size >= 16 => {
+                let mut fmt = [0u8; 16];
+                reader.read_exact(&mut fmt)?;
+                byte_rate = Some(u32::from_le_bytes([fmt[8], fmt[9], fmt[10], fmt[11]])).filter(|&rate| rate > 0);
+            }
+            // Recorders that never went back to fill in the size leave 0 or 0xFFFFFFFF; the rest of the file is audio then
+            b"data" => data_len = Some(if size == 0 || size as u64 > remaining { remaining } else { size as u64 }),
+            b"LIST" | b"id3 " | b"ID3 " if size <= MAX_TAG_CHUNK && size as u64 <= remaining => {
+                let mut body = vec![0u8; size as usize];
+                reader.read_exact(&mut body)?;
+                if &id == b"LIST" {
+                    if let Some(fields) = body.strip_prefix(b"INFO") {
+                        parse_info(fields, &mut info);
+                    }
+                } else {
+                    id3 = id3::Tag::read_from2(Cursor::new(body)).ok();
+                }
+            }
+            _ => {}
+        }
+
+        // Chunks are padded to an even length
+        let next = start + size as u64 + (size & 1) as u64;
+        if next >= file_len {
+            break;
+        }
+        reader.seek(SeekFrom::Start(next))?;
+    }
+
+    let mut metadata = match &id3 {
+        Some(tag) => merge(TrackMetadata::from_id3_tag(tag), info),
+        None => info,
+    };
+    let duration = byte_rate.zip(data_len).map(|(rate, len)| Duration::from_secs_f64(len as f64 / rate as f64));
+    if let Some(duration) = duration {
+        metadata.duration_ms = Some(duration.as_millis() as u64);
+    }
+    Ok(WavInfo { duration, metadata })
+}
+
+/// The sub-chunks of a LIST/INFO chunk, after its "INFO" marker
+fn parse_info(mut fields: &[u8], metadata: &mut TrackMetadata) {
+    while fields.len() >= 8 {
+        let id = &fields[0..4];
+        let size = u32::from_le_bytes([fields[4], fields[5], fields[6], fields[7]]) as usize;
+        let Some(value) = fields.get(8..8 + size) else {
+            break;
+        };
+        let text = info_text(value);
+        let number = || text.chars().take_while(char::is_ascii_digit).collect::<String>().parse().ok();
+        match id {
+            b"INAM" => metadata.title = Some(text.clone()),
+            b"IART" => metadata.artist = Some(text.clone()),
+            b"IPRD" => metadata.album = Some(text.clone()),
+            b"IGNR" => metadata.genre = Some(text.clone()),
+            b"ICRD" => metadata.year = number(),
+            b"ITRK" | b"IPRT" => metadata.track_number = number(),
+            _ => {}
+        }
+        fields = fields.get(8 + size + (size & 1)..).unwrap_or_default();
+    }
+    for field in [&mut metadata.title, &mut metadata.artist, &mut metadata.album, &mut metadata.genre] {
+        if field.as_deref() == Some("") {
+            *field = None;
+        }
+    }
+}
+
+/// NUL-terminated text, UTF-8 when it is valid and Latin-1 otherwise, as older tools wrote it
+fn info_text(value: &[u8]) -> String {
+    let value = value.split(|&b| b == 0).next().unwrap_or_default();
+    match std::str::from_utf8(value) {
+        Ok(text) => text.trim().to_string(),
+        Err(_) => value.iter().map(|&b| b as char).collect::<String>().trim().to_string(),
+    }
+}
+
+fn merge(preferred: TrackMetadata, fallback: TrackMetadata) -> TrackMetadata {
+    TrackMetadata {
+        title: preferred.title.or(fallback.title),
+        artist: preferred.artist.or(fallback.artist),
+        album: preferred.album.or(fallback.album),
+        album_artist: preferred.album_artist.or(fallback.album_artist),
+        track_number: preferred.track_number.or(fallback.track_number),
+        disc_number: preferred.disc_number.or(fallback.disc_number),
+        year: preferred.year.or(fallback.year),
+        genre: preferred.genre.or(fallback.genre),
+        duration_ms: preferred.duration_ms.or(fallback.duration_ms),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use id3::TagLike;
+
+    fn chunk(id: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut bytes = id.to_vec();
+        bytes.extend((body.len() as u32).to_le_bytes());
+        bytes.extend(body);
+        if body.len() % 2 == 1 {
+            bytes.push(0);
+        }
+        bytes
+    }
+
+    /// 16-bit stereo at 44.1kHz: 176400 bytes a second
+    fn wav(data_len: usize, extra: &[Vec<u8>]) -> Vec<u8> {
+        let mut fmt = Vec::new();
+        fmt.extend(1u16.to_le_bytes()); // PCM
+        fmt.extend(2u16.to_le_bytes());
+        fmt.extend(44_100u32.to_le_bytes());
+        fmt.extend(176_400u32.to_le_bytes());
+        fmt.extend(4u16.to_le_bytes());
+        fmt.extend(16u16.to_le_bytes());
+
+        let mut body = b"WAVE".to_vec();
+        body.extend(chunk(b"fmt ", &fmt));
+        body.extend(chunk(b"data", &vec![0u8; data_len]));
+        for extra in extra {
+            body.extend(extra);
+        }
+        chunk(b"RIFF", &body)
+    }
+
+    #[test]
+    fn test_duration_and_info_tags() {
+        let mut info = b"INFO".to_vec();
+        info.extend(chunk(b"INAM", b"Loop 04\0"));
+        info.extend(chunk(b"IART", b"Caf\xe9 Tacvba\0")); // Latin-1
+        info.extend(chunk(b"ICRD", b"1999-05-01\0"));
+        info.extend(chunk(b"ITRK", b"7/12\0"));
+        let bytes = wav(176_400 * 2 + 88_200, &[chunk(b"LIST", &info)]);
+
+        let info = read_wav(Cursor::new(bytes)).unwrap();
+        assert_eq!(info.duration, Some(Duration::from_millis(2500)));
+        assert_eq!(info.metadata.duration_ms, Some(2500));
+        assert_eq!(info.metadata.title.as_deref(), Some("Loop 04"));
+        assert_eq!(info.metadata.artist.as_deref(), Some("Café Tacvba"));
+        assert_eq!((info.metadata.year, info.metadata.track_number), (Some(1999), Some(7)));
+
+        // Nothing but audio: a duration and no tags
+        let bare = read_wav(Cursor::new(wav(176_400, &[]))).unwrap();
+        assert_eq!(bare.duration, Some(Duration::from_secs(1)));
+        assert_eq!(bare.metadata.title, None);
+        assert!(read_wav(Cursor::new(b"not a wav file at all".to_vec())).is_err());
+    }
+
+    #[test]
+    fn test_id3_chunk_wins_over_info() {
+        let mut tag = id3::Tag::new();
+        tag.set_title("Tagged Title");
+        tag.set_album("Samples Vol. 2");
+        let mut tag_bytes = Vec::new();
+        tag.write_to(&mut tag_bytes, id3::Version::Id3v24).unwrap();
+
+        let mut info = b"INFO".to_vec();
+        info.extend(chunk(b"INAM", b"Info Title\0"));
+        info.extend(chunk(b"IART", b"Info Artist\0"));
+        let bytes = wav(1_000, &[chunk(b"LIST", &info), chunk(b"id3 ", &tag_bytes)]);
+
+        let metadata = read_wav(Cursor::new(bytes)).unwrap().metadata;
+        assert_eq!(metadata.title.as_deref(), Some("Tagged Title"));
+        assert_eq!(metadata.album.as_deref(), Some("Samples Vol. 2"));
+        assert_eq!(metadata.artist.as_deref(), Some("Info Artist"));
+    }
+}
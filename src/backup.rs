@@ -0,0 +1,160 @@
+// Backup and restore - the behavior database and playlists are the data a rescan can't bring back
+// A backup is a timestamped folder: the database copied with SQLite's online backup API, plus the playlist files
+
+use anyhow::Result;
+use rusqlite::{backup::Backup, Connection, OpenFlags};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const DATABASE_FILE: &str = "panpipe.db";
+const PLAYLISTS_DIR: &str = "playlists";
+
+/// What a backup folder holds
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackupSummary {
+    pub path: PathBuf, // the backup folder
+    pub playlists: usize,
+}
+
+/// Back up `database` and the playlist files in `playlists_dir` into a new timestamped folder in `target_dir`
+///
+/// Safe while the player has the database open; a missing playlists folder just means no playlists.
+pub fn create_backup(database: &Path, playlists_dir: &Path, target_dir: &Path) -> Result<BackupSummary> {
+    if !database.exists() {
+        return Err(anyhow::anyhow!("No behavior database at {}", database.display()));
+    }
+    let name = format!("bangtunes-backup-{}", chrono::Local::now().format("%Y%m%d-%H%M%S"));
+    let path = target_dir.join(name);
+    if path.exists() {
+        return Err(anyhow::anyhow!("{} already exists", path.display()));
+    }
+    fs::create_dir_all(path.join(PLAYLISTS_DIR))?;
+
+    let source = Connection::open_with_flags(database, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    copy_database(&source, &path.join(DATABASE_FILE))?;
+    let playlists = copy_playlists(playlists_dir, &path.join(PLAYLISTS_DIR))?;
+    Ok(BackupSummary { path, playlists })
+}
+
+/// Check a backup folder before restoring it: the database must pass SQLite's integrity check
+pub fn inspect_backup(backup: &Path) -> Result<BackupSummary> {
+    let database = backup.join(DATABASE_FILE);
+    if !database.is_file() {
+        return Err(anyhow::anyhow!("{} is not a backup, it has no {}", backup.display(), DATABASE_FILE));
+    }
+    let connection = Connection::open_with_flags(&database, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let check: String = connection.query_row("PRAGMA quick_check", [], |row| row.get(0))
+        .map_err(|e| anyhow::anyhow!("The backed up database is unreadable: {}", e))?;
+    if check != "ok" {
+        return Err(anyhow::anyhow!("The backed up database failed its integrity check: {}", check));
+    }
+
+    let playlists = playlist_files(&backup.join(PLAYLISTS_DIR))?.len();
+    Ok(BackupSummary { path: backup.to_path_buf(), playlists })
+}
+
+/// Replace `database` with the backed up one and put the backed up playlists back in `playlists_dir`
+///
+/// Nothing is touched unless the backup passes `inspect_backup`. Playlists with the same file
+/// name are overwritten; ones created since the backup are kept.
+pub fn restore_backup(backup: &Path, database: &Path, playlists_dir: &Path) -> Result<BackupSummary> {
+    let summary = inspect_backup(backup)?;
+
+    let source = Connection::open_with_flags(backup.join(DATABASE_FILE), OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    if let Some(parent) = database.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    copy_database(&source, database)?;
+
+    fs::create_dir_all(playlists_dir)?;
+    copy_playlists(&backup.join(PLAYLISTS_DIR), playlists_dir)?;
+    Ok(summary)
+}
+
+/// Copy page by page with the online backup API, so a database in use is copied consistently
+fn copy_database(source: &Connection, target: &Path) -> Result<()> {
+    let mut target = Connection::open(target)?;
+    let backup = Backup::new(source, &mut target)?;
+    backup.run_to_completion(256, Duration::from_millis(10), None)?;
+    Ok(())
+}
+
+/// Copy every playlist file from `from` into `to`; returns how many
+fn copy_playlists(from: &Path, to: &Path) -> Result<usize> {
+    let files = playlist_files(from)?;
+    for file in &files {
+        if let Some(name) = file.file_name() {
+            fs::copy(file, to.join(name))?;
+        }
+    }
+    Ok(files.len())
+}
+
+fn playlist_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() && path.extension().and_then(|ext| ext.to_str()) == Some("json") {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plays(database: &Path) -> i64 {
+        Connection::open(database).unwrap()
+            .query_row("SELECT plays FROM stats", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_backup_and_restore_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = dir.path().join("panpipe.db");
+        let playlists = dir.path().join("playlists");
+        fs::create_dir_all(&playlists).unwrap();
+        fs::write(playlists.join("a.json"), "{}").unwrap();
+        fs::write(playlists.join("notes.txt"), "not a playlist").unwrap();
+
+        let live = Connection::open(&database).unwrap();
+        live.execute_batch("CREATE TABLE stats (plays INTEGER); INSERT INTO stats VALUES (42);").unwrap();
+
+        // Backed up while the connection is still open
+        let backup = create_backup(&database, &playlists, &dir.path().join("backups")).unwrap();
+        assert_eq!(backup.playlists, 1);
+        assert_eq!(inspect_backup(&backup.path).unwrap(), backup);
+
+        live.execute("UPDATE stats SET plays = 0", []).unwrap();
+        fs::remove_file(playlists.join("a.json")).unwrap();
+        drop(live);
+
+        restore_backup(&backup.path, &database, &playlists).unwrap();
+        assert_eq!(plays(&database), 42);
+        assert!(playlists.join("a.json").exists());
+    }
+
+    #[test]
+    fn test_corrupt_backup_is_not_restored() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = dir.path().join("panpipe.db");
+        Connection::open(&database).unwrap()
+            .execute_batch("CREATE TABLE stats (plays INTEGER); INSERT INTO stats VALUES (7);")
+            .unwrap();
+
+        let backup = dir.path().join("bangtunes-backup-broken");
+        fs::create_dir_all(&backup).unwrap();
+        fs::write(backup.join(DATABASE_FILE), "definitely not sqlite").unwrap();
+
+        assert!(restore_backup(&backup, &database, &dir.path().join("playlists")).is_err());
+        assert_eq!(plays(&database), 7);
+        assert!(inspect_backup(&dir.path().join("playlists")).is_err()); // no database at all
+    }
+}
@@ -1,9 +1,20 @@
-use super::{PlaySession, TrackBehavior};
+use super::{weighting::PlayHours, Bookmark, PlaySession, RecentPlay, TrackBehavior};
+use crate::audio::scan_cache::{file_modified_secs, CachedTrack, ScanCache};
+use crate::audio::silence::SilenceBounds;
+use crate::audio::{Track, TrackMetadata};
 use anyhow::Result;
+use chrono::{DateTime, Local, Timelike, Utc};
 use rusqlite::{params, Connection, OptionalExtension, Row};
-use std::path::Path;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use uuid::Uuid;
 
+/// Bumped by each one-time data migration, stored in `PRAGMA user_version`
+const SCHEMA_VERSION: i64 = 1; // 1: track ids derived from the content hash
+
 pub struct BehaviorDatabase {
     conn: Connection,
 }
@@ -13,6 +24,7 @@ impl BehaviorDatabase {
         let conn = Connection::open(db_path)?;
         let db = Self { conn };
         db.initialize_tables()?;
+        db.migrate()?;
         Ok(db)
     }
     
@@ -67,7 +79,62 @@ impl BehaviorDatabase {
             [],
         )?;
         
+        // Bookmarks inside long tracks - keyed by content hash so they survive moves
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS bookmarks (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                content_hash INTEGER NOT NULL,
+                position_ms INTEGER NOT NULL,
+                label TEXT NOT NULL,
+                created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )",
+            [],
+        )?;
+        
+        // Silent intro/outro of each track, found by decoding it once - keyed by content hash like bookmarks
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS silence_bounds (
+                content_hash INTEGER PRIMARY KEY,
+                start_ms INTEGER NOT NULL,
+                end_ms INTEGER, -- NULL when the track doesn't end in silence
+                analysed_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )",
+            [],
+        )?;
+        
+        // Where each spoken-word file was left - one position per file, unlike the session's single resume point
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS resume_positions (
+                content_hash INTEGER PRIMARY KEY,
+                position_ms INTEGER NOT NULL,
+                updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )",
+            [],
+        )?;
+        
+        self.ensure_column("track_behaviors", "shuffle_excluded", "INTEGER NOT NULL DEFAULT 0")?;
+        self.ensure_column("track_behaviors", "early_skips", "INTEGER NOT NULL DEFAULT 0")?;
+        
+        // Scan cache columns, added to track_metadata after the fact
+        for (column, column_type) in [
+            ("album_artist", "TEXT"),
+            ("track_number", "INTEGER"),
+            ("disc_number", "INTEGER"),
+            ("year", "INTEGER"),
+            ("genre", "TEXT"),
+            ("duration_ms", "INTEGER"),
+            ("content_hash", "INTEGER"),
+            ("file_mtime", "INTEGER"), // seconds since epoch, unlike last_modified
+        ] {
+            self.ensure_column("track_metadata", column, column_type)?;
+        }
+        
         // Create indexes for performance
+        self.conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_track_metadata_file_path ON track_metadata(file_path)",
+            [],
+        )?;
+        
         self.conn.execute(
             "CREATE INDEX IF NOT EXISTS idx_sessions_track_id ON play_sessions(track_id)",
             [],
@@ -78,10 +145,92 @@ impl BehaviorDatabase {
             [],
         )?;
         
+        self.conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_bookmarks_content_hash ON bookmarks(content_hash)",
+            [],
+        )?;
+        
+        Ok(())
+    }
+    
+    fn migrate(&self) -> Result<()> {
+        let version: i64 = self.conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version < 1 {
+            let tx = self.conn.unchecked_transaction()?;
+            self.migrate_to_content_ids()?;
+            tx.execute_batch(&format!("PRAGMA user_version = {}", SCHEMA_VERSION))?;
+            tx.commit()?;
+        }
+        Ok(())
+    }
+    
+    /// Track ids used to be random per scan; move the history of files that are still around onto
+    /// their content-derived id. Rows for files that are gone keep their old id.
+    fn migrate_to_content_ids(&self) -> Result<()> {
+        let rows = {
+            let mut stmt = self.conn.prepare(
+                "SELECT track_id, file_path, content_hash FROM track_metadata
+                 WHERE content_hash IS NOT NULL AND file_path IS NOT NULL"
+            )?;
+            let rows = stmt.query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, i64>(2)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+            rows
+        };
+        
+        for (track_id_str, file_path, content_hash) in rows {
+            let Ok(old_id) = Uuid::parse_str(&track_id_str) else {
+                continue;
+            };
+            let new_id = Track::content_id(content_hash as u64);
+            if old_id == new_id || !Path::new(&file_path).exists() {
+                continue;
+            }
+            
+            self.merge_behavior(old_id, new_id)?;
+            
+            // Duplicate copies all map to one id, and the first of them already claimed the row
+            let taken: bool = self.conn.query_row(
+                "SELECT EXISTS(SELECT 1 FROM track_metadata WHERE track_id = ?1)",
+                params![new_id.to_string()],
+                |row| row.get(0),
+            )?;
+            if taken {
+                self.conn.execute("DELETE FROM track_metadata WHERE track_id = ?1", params![track_id_str])?;
+            } else {
+                self.conn.execute(
+                    "UPDATE track_metadata SET track_id = ?2 WHERE track_id = ?1",
+                    params![track_id_str, new_id.to_string()],
+                )?;
+            }
+        }
+        
+        Ok(())
+    }
+    
+    fn ensure_column(&self, table: &str, column: &str, column_type: &str) -> Result<()> {
+        let mut stmt = self.conn.prepare(&format!("PRAGMA table_info({})", table))?;
+        let exists = stmt
+            .query_map([], |row| row.get::<_, String>(1))?
+            .filter_map(|name| name.ok())
+            .any(|name| name == column);
+        
+        if !exists {
+            self.conn.execute(
+                &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, column_type),
+                [],
+            )?;
+        }
+        
         Ok(())
     }
     
     pub async fn save_track_behavior(&self, behavior: &TrackBehavior) -> Result<()> {
+        self.store_behavior(behavior)
+    }
+    
+    fn store_behavior(&self, behavior: &TrackBehavior) -> Result<()> {
         let skip_positions_json = serde_json::to_string(&behavior.skip_positions)?;
         let tags_json = serde_json::to_string(&behavior.tags)?;
         let last_played = behavior.last_played.map(|dt| dt.to_rfc3339());
@@ -89,8 +238,8 @@ impl BehaviorDatabase {
         self.conn.execute(
             "INSERT OR REPLACE INTO track_behaviors 
              (track_id, total_plays, total_skips, total_play_time, last_played, 
-              skip_positions, completion_rate, weight, tags, updated_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, CURRENT_TIMESTAMP)",
+              skip_positions, completion_rate, weight, tags, shuffle_excluded, early_skips, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, CURRENT_TIMESTAMP)",
             params![
                 behavior.track_id.to_string(),
                 behavior.total_plays,
@@ -101,16 +250,29 @@ impl BehaviorDatabase {
                 behavior.completion_rate,
                 behavior.weight,
                 tags_json,
+                behavior.shuffle_excluded,
+                behavior.early_skips,
             ],
         )?;
         
         Ok(())
     }
     
+    /// Keep a track out of (or let it back into) shuffle, creating its behavior row if need be
+    pub async fn set_shuffle_excluded(&self, track_id: Uuid, excluded: bool) -> Result<()> {
+        let mut behavior = self.load_behavior(track_id)?.unwrap_or_else(|| TrackBehavior::new(track_id));
+        behavior.shuffle_excluded = excluded;
+        self.store_behavior(&behavior)
+    }
+    
     pub async fn get_track_behavior(&self, track_id: Uuid) -> Result<Option<TrackBehavior>> {
+        self.load_behavior(track_id)
+    }
+    
+    fn load_behavior(&self, track_id: Uuid) -> Result<Option<TrackBehavior>> {
         let mut stmt = self.conn.prepare(
             "SELECT track_id, total_plays, total_skips, total_play_time, last_played,
-                    skip_positions, completion_rate, weight, tags
+                    skip_positions, completion_rate, weight, tags, shuffle_excluded, early_skips
              FROM track_behaviors WHERE track_id = ?1"
         )?;
         
@@ -124,7 +286,7 @@ impl BehaviorDatabase {
     pub async fn get_all_track_behaviors(&self) -> Result<Vec<TrackBehavior>> {
         let mut stmt = self.conn.prepare(
             "SELECT track_id, total_plays, total_skips, total_play_time, last_played,
-                    skip_positions, completion_rate, weight, tags
+                    skip_positions, completion_rate, weight, tags, shuffle_excluded, early_skips
              FROM track_behaviors ORDER BY weight DESC"
         )?;
         
@@ -136,6 +298,52 @@ impl BehaviorDatabase {
         Ok(behaviors)
     }
     
+    /// Forget one track's behavior; returns whether there was any. Its play sessions are kept.
+    pub async fn delete_track_behavior(&self, track_id: Uuid) -> Result<bool> {
+        let deleted = self.conn.execute(
+            "DELETE FROM track_behaviors WHERE track_id = ?1",
+            params![track_id.to_string()],
+        )?;
+        Ok(deleted > 0)
+    }
+    
+    /// Move `from`'s play sessions and behavior onto `into`, for duplicates merged into one copy
+    ///
+    /// Returns whether `from` had any behavior to merge. Copies that already share an id are left alone.
+    pub async fn merge_track_behavior(&self, from: Uuid, into: Uuid) -> Result<bool> {
+        self.merge_behavior(from, into)
+    }
+    
+    fn merge_behavior(&self, from: Uuid, into: Uuid) -> Result<bool> {
+        if from == into {
+            return Ok(false);
+        }
+        
+        self.conn.execute(
+            "UPDATE play_sessions SET track_id = ?2 WHERE track_id = ?1",
+            params![from.to_string(), into.to_string()],
+        )?;
+        
+        let Some(from_behavior) = self.load_behavior(from)? else {
+            return Ok(false);
+        };
+        let merged = match self.load_behavior(into)? {
+            Some(mut behavior) => {
+                behavior.absorb(&from_behavior);
+                behavior
+            }
+            None => TrackBehavior { track_id: into, ..from_behavior },
+        };
+        self.store_behavior(&merged)?;
+        self.conn.execute("DELETE FROM track_behaviors WHERE track_id = ?1", params![from.to_string()])?;
+        Ok(true)
+    }
+    
+    /// Forget every track's behavior; returns how many rows went. Play sessions are kept.
+    pub async fn clear_all_behaviors(&self) -> Result<usize> {
+        Ok(self.conn.execute("DELETE FROM track_behaviors", [])?)
+    }
+    
     pub async fn save_session(&self, session: &PlaySession) -> Result<()> {
         let skip_reason_str = session.skip_reason.as_ref()
             .map(|r| serde_json::to_string(r).unwrap_or_default());
@@ -161,6 +369,23 @@ impl BehaviorDatabase {
         Ok(())
     }
     
+    /// Every track's recorded plays bucketed by the local hour they started in
+    pub async fn get_play_hours(&self) -> Result<HashMap<Uuid, PlayHours>> {
+        let mut stmt = self.conn.prepare("SELECT track_id, started_at FROM play_sessions")?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+        
+        let mut hours: HashMap<Uuid, PlayHours> = HashMap::new();
+        for row in rows {
+            let (track_id, started_at) = row?;
+            let (Ok(track_id), Ok(started_at)) = (Uuid::parse_str(&track_id), DateTime::parse_from_rfc3339(&started_at)) else {
+                continue;
+            };
+            let hour = started_at.with_timezone(&Local).hour() as usize;
+            hours.entry(track_id).or_insert([0; 24])[hour] += 1;
+        }
+        Ok(hours)
+    }
+    
     pub async fn get_track_duration(&self, track_id: Uuid) -> Result<Option<u64>> {
         let mut stmt = self.conn.prepare(
             "SELECT duration FROM track_metadata WHERE track_id = ?1"
@@ -173,6 +398,7 @@ impl BehaviorDatabase {
         Ok(duration)
     }
     
+    #[allow(clippy::too_many_arguments)]
     pub async fn save_track_metadata(
         &self,
         track_id: Uuid,
@@ -201,6 +427,330 @@ impl BehaviorDatabase {
         Ok(())
     }
     
+    /// Load the scan cache from track_metadata rows that have a recorded file mtime
+    pub fn load_scan_cache(&self) -> Result<ScanCache> {
+        let mut stmt = self.conn.prepare(
+            "SELECT track_id, file_path, title, artist, album, album_artist, track_number,
+                    disc_number, year, genre, duration_ms, file_size, file_mtime, content_hash
+             FROM track_metadata WHERE file_mtime IS NOT NULL AND file_path IS NOT NULL"
+        )?;
+        
+        let entries = stmt.query_map([], |row| {
+            let track_id_str: String = row.get(0)?;
+            let file_path: String = row.get(1)?;
+            let duration_ms: Option<i64> = row.get(10)?;
+            let file_size: Option<i64> = row.get(11)?;
+            let file_mtime: i64 = row.get(12)?;
+            let content_hash: Option<i64> = row.get(13)?;
+            
+            let metadata = TrackMetadata {
+                title: row.get(2)?,
+                artist: row.get(3)?,
+                album: row.get(4)?,
+                album_artist: row.get(5)?,
+                track_number: row.get(6)?,
+                disc_number: row.get(7)?,
+                year: row.get(8)?,
+                genre: row.get(9)?,
+                duration_ms: duration_ms.map(|d| d as u64),
+            };
+            
+            Ok((track_id_str, PathBuf::from(file_path), metadata, file_size, file_mtime, content_hash))
+        })?;
+        
+        let mut cache = ScanCache::new();
+        for entry in entries {
+            let (track_id_str, file_path, metadata, file_size, file_mtime, content_hash) = entry?;
+            let Ok(track_id) = Uuid::parse_str(&track_id_str) else {
+                continue;
+            };
+            
+            let mut track = Track::new(file_path).with_metadata(metadata);
+            track.id = track_id;
+            track.file_size = file_size.unwrap_or(0) as u64;
+            track.content_hash = content_hash.map(|h| h as u64);
+            track.modified = Some(file_mtime as u64);
+            
+            cache.insert(CachedTrack {
+                file_size: track.file_size,
+                modified: file_mtime as u64,
+                track,
+            });
+        }
+        
+        Ok(cache)
+    }
+    
+    /// Record scanned tracks so the next scan can skip unchanged files
+    pub fn save_scan_cache(&self, tracks: &[Track]) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        
+        for track in tracks {
+            let Some(modified) = std::fs::metadata(&track.file_path).ok().and_then(|m| file_modified_secs(&m)) else {
+                continue;
+            };
+            let file_path = track.file_path.to_string_lossy();
+            let duration_ms = track.duration.map(|d| d.as_millis() as u64).or(track.metadata.duration_ms);
+            
+            // One row per path - drop entries left over from a previous id
+            tx.execute(
+                "DELETE FROM track_metadata WHERE file_path = ?1 AND track_id != ?2",
+                params![file_path, track.id.to_string()],
+            )?;
+            
+            tx.execute(
+                "INSERT OR REPLACE INTO track_metadata
+                 (track_id, file_path, title, artist, album, album_artist, track_number, disc_number,
+                  year, genre, duration, duration_ms, file_size, file_mtime, content_hash, last_modified)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, CURRENT_TIMESTAMP)",
+                params![
+                    track.id.to_string(),
+                    file_path,
+                    track.metadata.title,
+                    track.metadata.artist,
+                    track.metadata.album,
+                    track.metadata.album_artist,
+                    track.metadata.track_number,
+                    track.metadata.disc_number,
+                    track.metadata.year,
+                    track.metadata.genre,
+                    duration_ms.map(|ms| Duration::from_millis(ms).as_secs() as i64),
+                    duration_ms.map(|ms| ms as i64),
+                    track.file_size as i64,
+                    modified as i64,
+                    track.content_hash.map(|h| h as i64),
+                ],
+            )?;
+        }
+        
+        tx.commit()?;
+        Ok(())
+    }
+    
+    /// Store a bookmark at `position` in the track with this content hash; returns its id
+    pub fn add_bookmark(&self, content_hash: u64, position: Duration, label: &str) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO bookmarks (content_hash, position_ms, label) VALUES (?1, ?2, ?3)",
+            params![content_hash as i64, position.as_millis() as i64, label],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+    
+    /// Bookmarks for the track with this content hash, earliest position first
+    pub fn get_bookmarks(&self, content_hash: u64) -> Result<Vec<Bookmark>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, position_ms, label FROM bookmarks
+             WHERE content_hash = ?1 ORDER BY position_ms, id"
+        )?;
+        
+        let bookmarks = stmt.query_map(params![content_hash as i64], |row| {
+            Ok(Bookmark {
+                id: row.get(0)?,
+                content_hash,
+                position_ms: row.get::<_, i64>(1)?.max(0) as u64,
+                label: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+        
+        Ok(bookmarks)
+    }
+    
+    pub fn delete_bookmark(&self, id: i64) -> Result<()> {
+        self.conn.execute("DELETE FROM bookmarks WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+    
+    /// Silence bounds worked out earlier for the track with this content hash
+    pub fn get_silence_bounds(&self, content_hash: u64) -> Result<Option<SilenceBounds>> {
+        let bounds = self.conn.query_row(
+            "SELECT start_ms, end_ms FROM silence_bounds WHERE content_hash = ?1",
+            params![content_hash as i64],
+            |row| Ok(SilenceBounds {
+                start: Duration::from_millis(row.get::<_, i64>(0)?.max(0) as u64),
+                end: row.get::<_, Option<i64>>(1)?.map(|ms| Duration::from_millis(ms.max(0) as u64)),
+            }),
+        ).optional()?;
+        Ok(bounds)
+    }
+    
+    pub fn save_silence_bounds(&self, content_hash: u64, bounds: SilenceBounds) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO silence_bounds (content_hash, start_ms, end_ms) VALUES (?1, ?2, ?3)",
+            params![
+                content_hash as i64,
+                bounds.start.as_millis() as i64,
+                bounds.end.map(|end| end.as_millis() as i64),
+            ],
+        )?;
+        Ok(())
+    }
+    
+    /// Where the file with this content hash was left, if it was
+    pub fn get_resume_position(&self, content_hash: u64) -> Result<Option<Duration>> {
+        let position = self.conn.query_row(
+            "SELECT position_ms FROM resume_positions WHERE content_hash = ?1",
+            params![content_hash as i64],
+            |row| Ok(Duration::from_millis(row.get::<_, i64>(0)?.max(0) as u64)),
+        ).optional()?;
+        Ok(position)
+    }
+    
+    pub fn save_resume_position(&self, content_hash: u64, position: Duration) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO resume_positions (content_hash, position_ms, updated_at)
+             VALUES (?1, ?2, CURRENT_TIMESTAMP)",
+            params![content_hash as i64, position.as_millis() as i64],
+        )?;
+        Ok(())
+    }
+    
+    /// Forget the position, e.g. once the file has been listened to the end
+    pub fn clear_resume_position(&self, content_hash: u64) -> Result<()> {
+        self.conn.execute("DELETE FROM resume_positions WHERE content_hash = ?1", params![content_hash as i64])?;
+        Ok(())
+    }
+    
+    /// Carry bookmarks, silence bounds and the resume position over to a file's new hash, e.g.
+    /// after its tags were rewritten. Rows already stored under `to` win.
+    pub fn move_content_hash(&self, from: u64, to: u64) -> Result<()> {
+        if from == to {
+            return Ok(());
+        }
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute("UPDATE bookmarks SET content_hash = ?2 WHERE content_hash = ?1", params![from as i64, to as i64])?;
+        for table in ["silence_bounds", "resume_positions"] {
+            tx.execute(
+                &format!("UPDATE OR IGNORE {} SET content_hash = ?2 WHERE content_hash = ?1", table),
+                params![from as i64, to as i64],
+            )?;
+            tx.execute(&format!("DELETE FROM {} WHERE content_hash = ?1", table), params![from as i64])?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+    
+    /// The last `limit` entries of the listening history, newest first
+    ///
+    /// Back-to-back plays of the same track fold into one entry.
+    pub fn recent_plays(&self, limit: usize) -> Result<Vec<RecentPlay>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT s.track_id, s.started_at, m.file_path, m.title, m.artist
+             FROM play_sessions s
+             LEFT JOIN track_metadata m ON m.track_id = s.track_id
+             ORDER BY s.started_at DESC"
+        )?;
+        
+        let mut plays: Vec<RecentPlay> = Vec::new();
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            let track_id = Uuid::parse_str(&row.get::<_, String>(0)?);
+            let started_at = DateTime::parse_from_rfc3339(&row.get::<_, String>(1)?);
+            let (Ok(track_id), Ok(started_at)) = (track_id, started_at) else {
+                continue;
+            };
+            if let Some(last) = plays.last_mut().filter(|last| last.track_id == track_id) {
+                last.plays += 1;
+                continue;
+            }
+            if plays.len() == limit {
+                break;
+            }
+            plays.push(RecentPlay {
+                track_id,
+                started_at: started_at.with_timezone(&Utc),
+                plays: 1,
+                file_path: row.get::<_, Option<String>>(2)?.map(PathBuf::from),
+                title: row.get(3)?,
+                artist: row.get(4)?,
+            });
+        }
+        Ok(plays)
+    }
+    
+    /// Write every play session to `path` as CSV, oldest first; returns the row count
+    pub fn export_sessions_csv<P: AsRef<Path>>(&self, path: P) -> Result<usize> {
+        let mut stmt = self.conn.prepare(
+            "SELECT track_id, started_at, ended_at, play_duration, track_duration,
+                    completion_percentage, skip_reason
+             FROM play_sessions ORDER BY started_at"
+        )?;
+        
+        let mut writer = BufWriter::new(File::create(path)?);
+        write_csv_row(&mut writer, &[
+            "track_id", "started_at", "ended_at", "play_duration",
+            "track_duration", "completion_percentage", "skip_reason",
+        ])?;
+        
+        let mut rows = stmt.query([])?;
+        let mut count = 0;
+        while let Some(row) = rows.next()? {
+            let skip_reason: Option<String> = row.get(6)?;
+            // Stored as JSON ("\"NextTrack\""), export the bare variant name
+            let skip_reason = skip_reason
+                .map(|json| serde_json::from_str::<String>(&json).unwrap_or(json))
+                .unwrap_or_default();
+            
+            write_csv_row(&mut writer, &[
+                &row.get::<_, String>(0)?,
+                &row.get::<_, String>(1)?,
+                &row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+                &row.get::<_, i64>(3)?.to_string(),
+                &row.get::<_, i64>(4)?.to_string(),
+                &format!("{:.1}", row.get::<_, f64>(5)?),
+                &skip_reason,
+            ])?;
+            count += 1;
+        }
+        
+        writer.flush()?;
+        Ok(count)
+    }
+    
+    /// Write per-track behavior, with title/artist from track_metadata, to `path` as CSV
+    pub fn export_behaviors_csv<P: AsRef<Path>>(&self, path: P) -> Result<usize> {
+        let mut stmt = self.conn.prepare(
+            "SELECT b.track_id, m.title, m.artist, m.album, b.total_plays, b.total_skips,
+                    b.total_play_time, b.last_played, b.completion_rate, b.weight, b.tags
+             FROM track_behaviors b
+             LEFT JOIN track_metadata m ON m.track_id = b.track_id
+             ORDER BY b.total_plays DESC"
+        )?;
+        
+        let mut writer = BufWriter::new(File::create(path)?);
+        write_csv_row(&mut writer, &[
+            "track_id", "title", "artist", "album", "total_plays", "total_skips",
+            "total_play_time", "last_played", "completion_rate", "weight", "tags",
+        ])?;
+        
+        let mut rows = stmt.query([])?;
+        let mut count = 0;
+        while let Some(row) = rows.next()? {
+            let tags_json: Option<String> = row.get(10)?;
+            let tags: Vec<String> = tags_json
+                .and_then(|json| serde_json::from_str(&json).ok())
+                .unwrap_or_default();
+            
+            write_csv_row(&mut writer, &[
+                &row.get::<_, String>(0)?,
+                &row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+                &row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+                &row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+                &row.get::<_, i64>(4)?.to_string(),
+                &row.get::<_, i64>(5)?.to_string(),
+                &row.get::<_, i64>(6)?.to_string(),
+                &row.get::<_, Option<String>>(7)?.unwrap_or_default(),
+                &format!("{:.1}", row.get::<_, f64>(8)?),
+                &format!("{:.3}", row.get::<_, f64>(9)?),
+                &tags.join(";"),
+            ])?;
+            count += 1;
+        }
+        
+        writer.flush()?;
+        Ok(count)
+    }
+    
     fn row_to_track_behavior(&self, row: &Row) -> rusqlite::Result<TrackBehavior> {
         let track_id_str: String = row.get(0)?;
         let track_id = Uuid::parse_str(&track_id_str)
@@ -221,12 +771,376 @@ impl BehaviorDatabase {
             track_id,
             total_plays: row.get(1)?,
             total_skips: row.get(2)?,
+            early_skips: row.get(10)?,
             total_play_time: row.get(3)?,
             last_played,
             skip_positions,
             completion_rate: row.get(6)?,
             weight: row.get(7)?,
             tags,
+            shuffle_excluded: row.get(9)?,
         })
     }
 }
+
+/// One CSV record, quoting fields that contain commas, quotes or newlines
+fn write_csv_row<W: Write>(writer: &mut W, fields: &[&str]) -> std::io::Result<()> {
+    let line = fields.iter()
+        .map(|field| {
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    writeln!(writer, "{}", line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::behavior::SkipReason;
+    use chrono::Utc;
+    
+    #[tokio::test]
+    async fn test_export_csv() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = BehaviorDatabase::new(dir.path().join("behavior.db")).unwrap();
+        
+        let track_id = Uuid::new_v4();
+        db.save_session(&PlaySession {
+            session_id: Uuid::new_v4(),
+            track_id,
+            started_at: Utc::now(),
+            ended_at: Some(Utc::now()),
+            play_duration: 42,
+            track_duration: 200,
+            skip_reason: Some(SkipReason::NextTrack),
+            completion_percentage: 21.0,
+        }).await.unwrap();
+        
+        let mut behavior = TrackBehavior::new(track_id);
+        behavior.total_plays = 3;
+        behavior.tags = vec!["favorite".to_string(), "low_skip_rate".to_string()];
+        db.save_track_behavior(&behavior).await.unwrap();
+        db.save_track_metadata(track_id, "/music/a.mp3", Some("Hello, World"), Some("Artist"), None, Some(200), None)
+            .await.unwrap();
+        
+        let sessions_path = dir.path().join("sessions.csv");
+        assert_eq!(db.export_sessions_csv(&sessions_path).unwrap(), 1);
+        let sessions = std::fs::read_to_string(&sessions_path).unwrap();
+        let lines: Vec<_> = sessions.lines().collect();
+        assert_eq!(lines[0], "track_id,started_at,ended_at,play_duration,track_duration,completion_percentage,skip_reason");
+        assert!(lines[1].starts_with(&track_id.to_string()));
+        assert!(lines[1].ends_with(",42,200,21.0,NextTrack"));
+        
+        let behaviors_path = dir.path().join("behaviors.csv");
+        assert_eq!(db.export_behaviors_csv(&behaviors_path).unwrap(), 1);
+        let behaviors = std::fs::read_to_string(&behaviors_path).unwrap();
+        let row = behaviors.lines().nth(1).unwrap();
+        assert!(row.contains(",\"Hello, World\",Artist,,3,"));
+        assert!(row.ends_with(",favorite;low_skip_rate"));
+    }
+    
+    #[tokio::test]
+    async fn test_play_hours() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = BehaviorDatabase::new(dir.path().join("behavior.db")).unwrap();
+        let track_id = Uuid::new_v4();
+        let started_at = Utc::now();
+        for _ in 0..3 {
+            db.save_session(&PlaySession {
+                session_id: Uuid::new_v4(),
+                track_id,
+                started_at,
+                ended_at: None,
+                play_duration: 100,
+                track_duration: 200,
+                skip_reason: None,
+                completion_percentage: 50.0,
+            }).await.unwrap();
+        }
+        
+        let hours = db.get_play_hours().await.unwrap();
+        let hour = started_at.with_timezone(&Local).hour() as usize;
+        assert_eq!(hours[&track_id][hour], 3);
+        assert_eq!(hours[&track_id].iter().sum::<u32>(), 3);
+    }
+    
+    #[tokio::test]
+    async fn test_delete_and_clear_behaviors() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = BehaviorDatabase::new(dir.path().join("behavior.db")).unwrap();
+        let (first, second) = (Uuid::new_v4(), Uuid::new_v4());
+        for id in [first, second] {
+            let mut behavior = TrackBehavior::new(id);
+            behavior.total_plays = 12;
+            behavior.weight = 3.5;
+            db.save_track_behavior(&behavior).await.unwrap();
+        }
+        
+        assert!(db.delete_track_behavior(first).await.unwrap());
+        assert!(db.get_track_behavior(first).await.unwrap().is_none());
+        assert!(!db.delete_track_behavior(first).await.unwrap());
+        assert_eq!(db.get_track_behavior(second).await.unwrap().unwrap().total_plays, 12);
+        
+        assert_eq!(db.clear_all_behaviors().await.unwrap(), 1);
+        assert!(db.get_track_behavior(second).await.unwrap().is_none());
+        assert!(db.get_all_track_behaviors().await.unwrap().is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_merge_track_behavior() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = BehaviorDatabase::new(dir.path().join("behavior.db")).unwrap();
+        let (kept, duplicate, untouched) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        for (id, plays, completion) in [(kept, 2, 100.0), (duplicate, 6, 20.0)] {
+            let mut behavior = TrackBehavior::new(id);
+            behavior.total_plays = plays;
+            behavior.total_skips = plays / 2;
+            behavior.completion_rate = completion;
+            db.save_track_behavior(&behavior).await.unwrap();
+        }
+        db.save_session(&PlaySession {
+            session_id: Uuid::new_v4(),
+            track_id: duplicate,
+            started_at: Utc::now(),
+            ended_at: None,
+            play_duration: 30,
+            track_duration: 200,
+            skip_reason: Some(SkipReason::UserSkip),
+            completion_percentage: 15.0,
+        }).await.unwrap();
+        
+        assert!(db.merge_track_behavior(duplicate, kept).await.unwrap());
+        let merged = db.get_track_behavior(kept).await.unwrap().unwrap();
+        assert_eq!((merged.total_plays, merged.total_skips), (8, 4));
+        assert_eq!(merged.completion_rate, 40.0);
+        assert!(db.get_track_behavior(duplicate).await.unwrap().is_none());
+        assert_eq!(db.get_play_hours().await.unwrap()[&kept].iter().sum::<u32>(), 1);
+        
+        // Nothing recorded yet for the kept copy: the duplicate's history moves over as is
+        assert!(db.merge_track_behavior(kept, untouched).await.unwrap());
+        assert_eq!(db.get_track_behavior(untouched).await.unwrap().unwrap().total_plays, 8);
+        assert!(!db.merge_track_behavior(duplicate, untouched).await.unwrap());
+        assert!(!db.merge_track_behavior(untouched, untouched).await.unwrap());
+        assert_eq!(db.get_track_behavior(untouched).await.unwrap().unwrap().total_plays, 8);
+    }
+    
+    #[tokio::test]
+    async fn test_migrate_to_content_ids() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("behavior.db");
+        let hash = 0xdead_beef_cafe_f00d_u64;
+        let paths: Vec<PathBuf> = ["a.mp3", "b.mp3", "gone.mp3"].iter().map(|name| dir.path().join(name)).collect();
+        let old_ids: Vec<Uuid> = paths.iter().map(|_| Uuid::new_v4()).collect();
+        
+        {
+            // A database from before content ids: random ids, two copies of one file and a file since removed
+            let db = BehaviorDatabase::new(&db_path).unwrap();
+            db.conn.execute_batch("PRAGMA user_version = 0").unwrap();
+            for (idx, (path, id)) in paths.iter().zip(&old_ids).enumerate() {
+                std::fs::write(path, "same audio").unwrap();
+                let mut track = Track::new(path.clone());
+                track.id = *id;
+                track.content_hash = Some(if idx < 2 { hash } else { 7 });
+                db.save_scan_cache(&[track]).unwrap();
+                
+                let mut behavior = TrackBehavior::new(*id);
+                behavior.total_plays = 2;
+                db.save_track_behavior(&behavior).await.unwrap();
+            }
+            std::fs::remove_file(&paths[2]).unwrap();
+        }
+        
+        let db = BehaviorDatabase::new(&db_path).unwrap();
+        let content_id = Track::content_id(hash);
+        assert_eq!(db.get_track_behavior(content_id).await.unwrap().unwrap().total_plays, 4);
+        assert!(db.get_track_behavior(old_ids[0]).await.unwrap().is_none());
+        assert_eq!(db.get_track_behavior(old_ids[2]).await.unwrap().unwrap().total_plays, 2);
+        let cache = db.load_scan_cache().unwrap();
+        assert_eq!(cache.len(), 2);
+        
+        // Runs once: reopening leaves everything as it is
+        drop(db);
+        let db = BehaviorDatabase::new(&db_path).unwrap();
+        assert_eq!(db.get_track_behavior(content_id).await.unwrap().unwrap().total_plays, 4);
+    }
+    
+    #[tokio::test]
+    async fn test_recent_plays() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = BehaviorDatabase::new(dir.path().join("behavior.db")).unwrap();
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        db.save_track_metadata(a, "/music/a.mp3", Some("Song A"), Some("Artist"), None, None, None).await.unwrap();
+        
+        let start = Utc::now() - chrono::Duration::hours(1);
+        for (minutes, track_id) in [(0, a), (5, b), (10, a), (15, a), (20, b)] {
+            db.save_session(&PlaySession {
+                session_id: Uuid::new_v4(),
+                track_id,
+                started_at: start + chrono::Duration::minutes(minutes),
+                ended_at: None,
+                play_duration: 60,
+                track_duration: 200,
+                skip_reason: None,
+                completion_percentage: 30.0,
+            }).await.unwrap();
+        }
+        
+        let plays = db.recent_plays(10).unwrap();
+        let runs: Vec<_> = plays.iter().map(|p| (p.track_id, p.plays)).collect();
+        assert_eq!(runs, vec![(b, 1), (a, 2), (b, 1), (a, 1)]);
+        assert_eq!(plays[1].started_at, start + chrono::Duration::minutes(15));
+        assert_eq!(plays[1].title.as_deref(), Some("Song A"));
+        assert_eq!(plays[1].file_path, Some(PathBuf::from("/music/a.mp3")));
+        assert_eq!(plays[0].title, None);
+        
+        assert_eq!(db.recent_plays(2).unwrap().len(), 2);
+    }
+    
+    #[test]
+    fn test_bookmarks_follow_content_hash() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("behavior.db");
+        let hash = 0xdead_beef_cafe_f00d_u64; // top bit set, so it round-trips through a signed column
+        
+        {
+            let db = BehaviorDatabase::new(&db_path).unwrap();
+            db.add_bookmark(hash, Duration::from_secs(1800), "Second half").unwrap();
+            db.add_bookmark(hash, Duration::from_millis(95_500), "Drop").unwrap();
+            db.add_bookmark(42, Duration::from_secs(10), "Other track").unwrap();
+        }
+        
+        // Reopened, as after a restart and re-scan that handed out new track ids
+        let db = BehaviorDatabase::new(&db_path).unwrap();
+        let bookmarks = db.get_bookmarks(hash).unwrap();
+        let labels: Vec<_> = bookmarks.iter().map(|b| b.label.as_str()).collect();
+        assert_eq!(labels, vec!["Drop", "Second half"]);
+        assert_eq!(bookmarks[0].position(), Duration::from_millis(95_500));
+        assert_eq!(bookmarks[0].content_hash, hash);
+        
+        db.delete_bookmark(bookmarks[0].id).unwrap();
+        assert_eq!(db.get_bookmarks(hash).unwrap().len(), 1);
+        assert_eq!(db.get_bookmarks(42).unwrap().len(), 1);
+    }
+    
+    #[test]
+    fn test_silence_bounds() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = BehaviorDatabase::new(dir.path().join("behavior.db")).unwrap();
+        let hash = 0xdead_beef_cafe_f00d_u64;
+        assert_eq!(db.get_silence_bounds(hash).unwrap(), None);
+        
+        let bounds = SilenceBounds { start: Duration::from_millis(3900), end: Some(Duration::from_secs(240)) };
+        db.save_silence_bounds(hash, bounds).unwrap();
+        db.save_silence_bounds(7, SilenceBounds::default()).unwrap();
+        assert_eq!(db.get_silence_bounds(hash).unwrap(), Some(bounds));
+        assert_eq!(db.get_silence_bounds(7).unwrap(), Some(SilenceBounds::default())); // analysed, nothing to skip
+    }
+    
+    #[test]
+    fn test_resume_positions_per_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = BehaviorDatabase::new(dir.path().join("behavior.db")).unwrap();
+        assert_eq!(db.get_resume_position(1).unwrap(), None);
+        
+        db.save_resume_position(1, Duration::from_secs(600)).unwrap();
+        db.save_resume_position(2, Duration::from_secs(30)).unwrap();
+        db.save_resume_position(1, Duration::from_millis(1_250_500)).unwrap();
+        assert_eq!(db.get_resume_position(1).unwrap(), Some(Duration::from_millis(1_250_500)));
+        assert_eq!(db.get_resume_position(2).unwrap(), Some(Duration::from_secs(30)));
+        
+        db.clear_resume_position(1).unwrap();
+        assert_eq!(db.get_resume_position(1).unwrap(), None);
+        assert_eq!(db.get_resume_position(2).unwrap(), Some(Duration::from_secs(30)));
+    }
+    
+    #[test]
+    fn test_move_content_hash() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = BehaviorDatabase::new(dir.path().join("behavior.db")).unwrap();
+        db.add_bookmark(1, Duration::from_secs(90), "Chorus").unwrap();
+        db.save_resume_position(1, Duration::from_secs(600)).unwrap();
+        db.save_silence_bounds(1, SilenceBounds::default()).unwrap();
+        db.save_silence_bounds(2, SilenceBounds { start: Duration::from_secs(2), end: None }).unwrap();
+        
+        db.move_content_hash(1, 2).unwrap();
+        assert!(db.get_bookmarks(1).unwrap().is_empty());
+        assert_eq!(db.get_bookmarks(2).unwrap()[0].label, "Chorus");
+        assert_eq!(db.get_resume_position(1).unwrap(), None);
+        assert_eq!(db.get_resume_position(2).unwrap(), Some(Duration::from_secs(600)));
+        // Already analysed under the new hash, so that result stays
+        assert_eq!(db.get_silence_bounds(1).unwrap(), None);
+        assert_eq!(db.get_silence_bounds(2).unwrap().unwrap().start, Duration::from_secs(2));
+    }
+    
+    #[test]
+    fn test_scan_cache_keeps_file_mtime() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = BehaviorDatabase::new(dir.path().join("behavior.db")).unwrap();
+        let music_path = dir.path().join("new.mp3");
+        std::fs::write(&music_path, b"not really audio").unwrap();
+        let file_metadata = std::fs::metadata(&music_path).unwrap();
+        
+        let mut track = Track::new(music_path.clone());
+        track.file_size = file_metadata.len();
+        track.modified = file_modified_secs(&file_metadata);
+        db.save_scan_cache(&[track.clone()]).unwrap();
+        
+        let cached = db.load_scan_cache().unwrap().lookup(&music_path, &file_metadata).unwrap();
+        assert_eq!(cached.id, track.id);
+        assert!(cached.modified.is_some());
+        assert_eq!(cached.modified, track.modified);
+    }
+    
+    #[test]
+    fn test_scan_cache_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = BehaviorDatabase::new(dir.path().join("behavior.db")).unwrap();
+        
+        let mut tracks = Vec::new();
+        for (name, title) in [("a.mp3", "First"), ("b.flac", "Second")] {
+            let path = dir.path().join(name);
+            std::fs::write(&path, title.as_bytes()).unwrap();
+            let file_metadata = std::fs::metadata(&path).unwrap();
+            
+            let mut track = Track::new(path);
+            track.metadata.title = Some(title.to_string());
+            track.file_size = file_metadata.len();
+            track.modified = file_modified_secs(&file_metadata);
+            track.content_hash = Some(title.len() as u64);
+            tracks.push(track);
+        }
+        db.save_scan_cache(&tracks).unwrap();
+        
+        let cache = db.load_scan_cache().unwrap();
+        assert_eq!(cache.len(), 2);
+        for track in &tracks {
+            let file_metadata = std::fs::metadata(&track.file_path).unwrap();
+            let cached = cache.lookup(&track.file_path, &file_metadata).unwrap();
+            assert_eq!(cached.file_path, track.file_path);
+            assert_eq!(cached.file_size, track.file_size);
+            assert_eq!(cached.modified, track.modified);
+            assert_eq!(cached.id, track.id);
+            assert_eq!(cached.content_hash, track.content_hash);
+            assert_eq!(cached.metadata.title, track.metadata.title);
+        }
+        
+        // A newer mtime misses the cache
+        let touched = &tracks[0].file_path;
+        let original_mtime = std::fs::metadata(touched).unwrap().modified().unwrap();
+        let file = std::fs::File::options().write(true).open(touched).unwrap();
+        file.set_modified(original_mtime + std::time::Duration::from_secs(60)).unwrap();
+        assert!(cache.lookup(touched, &std::fs::metadata(touched).unwrap()).is_none());
+        
+        // So does a different size, even with the mtime put back
+        let resized = &tracks[1].file_path;
+        let original_mtime = std::fs::metadata(resized).unwrap().modified().unwrap();
+        std::fs::write(resized, b"Second, re-encoded").unwrap();
+        std::fs::File::options().write(true).open(resized).unwrap().set_modified(original_mtime).unwrap();
+        assert!(cache.lookup(resized, &std::fs::metadata(resized).unwrap()).is_none());
+    }
+}
@@ -4,8 +4,11 @@
 pub mod database;  // SQLite storage for behavior data
 pub mod tracker;   // tracks play sessions and skip patterns
 pub mod weighting; // calculates shuffle weights based on behavior
+pub mod stats;     // aggregates behavior into listening stats for display
+pub mod similar;   // "more like this": tracks that share tags and get listened to alike
 
 pub use database::BehaviorDatabase;
+pub use stats::ListeningStats;
 pub use tracker::{BehaviorTracker, PlaybackEvent, SkipReason};
 
 use chrono::{DateTime, Utc};
@@ -17,12 +20,16 @@ pub struct TrackBehavior {
     pub track_id: Uuid,
     pub total_plays: u64,
     pub total_skips: u64,
+    #[serde(default)]
+    pub early_skips: u64, // skips within skip_threshold_seconds - "not this one", not "heard enough"
     pub total_play_time: u64, // in seconds
     pub last_played: Option<DateTime<Utc>>,
     pub skip_positions: Vec<u64>, // positions where skips occurred (in seconds)
     pub completion_rate: f64, // percentage of track typically played
     pub weight: f64, // current shuffle weight
     pub tags: Vec<String>, // behavior-based tags
+    #[serde(default)]
+    pub shuffle_excluded: bool, // never picked by shuffle, still playable on request
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -37,28 +44,60 @@ pub struct PlaySession {
     pub completion_percentage: f64,
 }
 
+/// A named spot inside a track, keyed by content hash so it survives re-scans
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub id: i64,
+    pub content_hash: u64,
+    pub position_ms: u64,
+    pub label: String,
+}
+
+/// One entry of the listening history, with what track_metadata knows about the track
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecentPlay {
+    pub track_id: Uuid,
+    pub started_at: DateTime<Utc>, // the latest play, when it was played several times running
+    pub plays: u32,                // back-to-back plays folded into this entry
+    pub file_path: Option<std::path::PathBuf>,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+}
+
+impl Bookmark {
+    pub fn position(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.position_ms)
+    }
+}
+
 impl TrackBehavior {
     pub fn new(track_id: Uuid) -> Self {
         Self {
             track_id,
             total_plays: 0,
             total_skips: 0,
+            early_skips: 0,
             total_play_time: 0,
             last_played: None,
             skip_positions: Vec::new(),
             completion_rate: 0.0,
             weight: 1.0, // neutral starting weight
             tags: Vec::new(),
+            shuffle_excluded: false,
         }
     }
     
-    pub fn update_from_session(&mut self, session: &PlaySession) {
+    /// Count a finished session; a skip before `skip_threshold` seconds counts as an early one too
+    pub fn update_from_session(&mut self, session: &PlaySession, skip_threshold: u64) {
         self.total_plays += 1;
         self.total_play_time += session.play_duration;
         self.last_played = Some(session.started_at);
         
         if session.skip_reason.is_some() {
             self.total_skips += 1;
+            if session.play_duration < skip_threshold {
+                self.early_skips += 1;
+            }
             // Record skip position as percentage of track
             let skip_position = (session.play_duration as f64 / session.track_duration as f64 * 100.0) as u64;
             self.skip_positions.push(skip_position);
@@ -77,6 +116,23 @@ impl TrackBehavior {
         self.update_tags();
     }
     
+    /// Fold another copy's history into this one, e.g. when duplicates of a track are merged
+    pub fn absorb(&mut self, other: &TrackBehavior) {
+        let plays = self.total_plays + other.total_plays;
+        if plays > 0 {
+            self.completion_rate = (self.completion_rate * self.total_plays as f64
+                + other.completion_rate * other.total_plays as f64) / plays as f64;
+        }
+        self.total_plays = plays;
+        self.total_skips += other.total_skips;
+        self.early_skips += other.early_skips;
+        self.total_play_time += other.total_play_time;
+        self.last_played = self.last_played.max(other.last_played);
+        self.skip_positions.extend(&other.skip_positions);
+        self.shuffle_excluded |= other.shuffle_excluded;
+        self.update_tags();
+    }
+    
     fn update_tags(&mut self) {
         self.tags.clear();
         
@@ -111,31 +167,4 @@ impl TrackBehavior {
             self.tags.push("low_skip_rate".to_string());
         }
     }
-    
-    pub fn calculate_shuffle_weight(&self, days_since_last_play: Option<u64>) -> f64 {
-        let mut weight = 1.0;
-        
-        // Boost favorites
-        if self.tags.contains(&"favorite".to_string()) {
-            weight *= 1.5;
-        }
-        
-        // Reduce weight for often skipped tracks
-        if self.tags.contains(&"often_skipped".to_string()) {
-            weight *= 0.3;
-        }
-        
-        // Boost tracks that haven't been played recently
-        if let Some(days) = days_since_last_play {
-            if days > 7 {
-                weight *= 1.0 + (days as f64 * 0.1).min(2.0); // Cap at 3x boost
-            }
-        }
-        
-        // Reduce weight for high skip rate tracks
-        let skip_ratio = self.total_skips as f64 / self.total_plays.max(1) as f64;
-        weight *= (1.0 - skip_ratio * 0.5).max(0.1); // Never go below 0.1
-        
-        weight.max(0.1).min(5.0) // Clamp between 0.1 and 5.0
-    }
 }
@@ -0,0 +1,177 @@
+// Similar tracks - "more like this" for a track, from its tags and how it gets listened to
+// Metadata decides what counts as similar; behavior only reorders those and adds liked tracks in common
+
+use super::weighting::genres;
+use super::TrackBehavior;
+use crate::audio::{Track, TrackMetadata};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Behavior tags that say a track is liked; the skip ones aren't a taste two tracks share
+const LIKED_TAGS: &[&str] = &["favorite", "frequently_played", "low_skip_rate"];
+
+/// A library track that resembles the one asked about
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimilarTrack {
+    pub index: usize, // into the library slice
+    pub score: f64,
+    pub reasons: Vec<&'static str>, // what they have in common: "artist", "album", "genre", "era", "liked", "listened alike"
+}
+
+/// The `n` library tracks most like `track`, best first
+///
+/// Shared artist, album and genre count most, then a close year. Liked-tags in common add to
+/// that, as does a similar completion rate, and tracks usually skipped sink. Tracks with
+/// nothing in common but listening habits aren't suggested.
+pub fn recommend_similar(
+    track: &Track,
+    library: &[Track],
+    behaviors: &HashMap<Uuid, TrackBehavior>,
+    n: usize,
+) -> Vec<SimilarTrack> {
+    let seed_behavior = behaviors.get(&track.id);
+    let mut similar: Vec<SimilarTrack> = library.iter()
+        .enumerate()
+        // Other copies of the same file aren't recommendations
+        .filter(|(_, candidate)| {
+            candidate.id != track.id
+                && candidate.file_path != track.file_path
+                && (candidate.content_hash.is_none() || candidate.content_hash != track.content_hash)
+        })
+        .filter_map(|(index, candidate)| {
+            let (score, reasons) = score(&track.metadata, seed_behavior, &candidate.metadata, behaviors.get(&candidate.id));
+            (score > 0.0).then_some(SimilarTrack { index, score, reasons })
+        })
+        .collect();
+    
+    similar.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.index.cmp(&b.index)));
+    similar.truncate(n);
+    similar
+}
+
+fn score(
+    seed: &TrackMetadata,
+    seed_behavior: Option<&TrackBehavior>,
+    candidate: &TrackMetadata,
+    candidate_behavior: Option<&TrackBehavior>,
+) -> (f64, Vec<&'static str>) {
+    let mut score = 0.0;
+    let mut reasons = Vec::new();
+    
+    let artist = |metadata: &TrackMetadata| normalized(metadata.album_artist.as_deref().or(metadata.artist.as_deref()));
+    let same_artist = same(normalized(seed.artist.as_deref()), normalized(candidate.artist.as_deref()))
+        || same(artist(seed), artist(candidate));
+    if same_artist {
+        score += 3.0;
+        reasons.push("artist");
+    }
+    // "Greatest Hits" from two artists isn't the same album
+    if same_artist && same(normalized(seed.album.as_deref()), normalized(candidate.album.as_deref())) {
+        score += 2.0;
+        reasons.push("album");
+    }
+    let seed_genres = genres(seed);
+    if genres(candidate).iter().any(|genre| seed_genres.contains(genre)) {
+        score += 2.0;
+        reasons.push("genre");
+    }
+    if let Some(gap) = seed.year.zip(candidate.year).map(|(a, b)| a.abs_diff(b)).filter(|gap| *gap < 10) {
+        score += 1.0 - gap as f64 / 10.0;
+        if gap <= 5 {
+            reasons.push("era");
+        }
+    }
+    
+    if let (Some(seed_behavior), Some(candidate_behavior)) = (seed_behavior, candidate_behavior) {
+        let has = |behavior: &TrackBehavior, tag: &str| behavior.tags.iter().any(|t| t == tag);
+        let shared = LIKED_TAGS.iter()
+            .filter(|tag| has(seed_behavior, tag) && has(candidate_behavior, tag))
+            .count();
+        if shared > 0 {
+            score += 0.75 * shared as f64;
+            reasons.push("liked");
+        }
+    }
+    // Listening habits only reorder tracks that already have something in common
+    if score == 0.0 {
+        return (0.0, Vec::new());
+    }
+    
+    if let (Some(seed_behavior), Some(candidate_behavior)) = (seed_behavior, candidate_behavior) {
+        if seed_behavior.total_plays > 0 && candidate_behavior.total_plays > 0 {
+            let closeness = 1.0 - (seed_behavior.completion_rate - candidate_behavior.completion_rate).abs() / 100.0;
+            score += closeness.clamp(0.0, 1.0);
+            if closeness >= 0.8 {
+                reasons.push("listened alike");
+            }
+        }
+    }
+    if let Some(behavior) = candidate_behavior.filter(|behavior| behavior.total_plays > 0) {
+        let skip_ratio = behavior.total_skips as f64 / behavior.total_plays as f64;
+        score *= 1.0 - skip_ratio.min(1.0) * 0.5;
+    }
+    (score, reasons)
+}
+
+fn normalized(value: Option<&str>) -> Option<String> {
+    value.map(|value| value.trim().to_lowercase()).filter(|value| !value.is_empty())
+}
+
+fn same(a: Option<String>, b: Option<String>) -> bool {
+    a.is_some() && a == b
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recommend_similar_ranks_shared_tags() {
+        let library = vec![
+            Track::test_fixture("/m/seed.mp3").with_artist("Boards of Canada").with_album("Geogaddi").with_genre("Electronic").with_year(2002),
+            Track::test_fixture("/m/same_album.mp3").with_artist("Boards of Canada").with_album("Geogaddi").with_genre("Electronic").with_year(2002),
+            Track::test_fixture("/m/same_artist.mp3").with_artist("boards of canada ").with_album("Music Has the Right to Children").with_genre("Electronic").with_year(1998),
+            Track::test_fixture("/m/same_genre.mp3").with_artist("Aphex Twin").with_album("Drukqs").with_genre("IDM; Electronic").with_year(2001),
+            Track::test_fixture("/m/unrelated.mp3").with_artist("Miles Davis").with_album("Kind of Blue").with_genre("Jazz").with_year(1959),
+        ];
+        let similar = recommend_similar(&library[0], &library, &HashMap::new(), 10);
+        let order: Vec<usize> = similar.iter().map(|s| s.index).collect();
+        assert_eq!(order, vec![1, 2, 3]);
+        assert_eq!(similar[0].reasons, vec!["artist", "album", "genre", "era"]);
+        assert_eq!(similar[2].reasons, vec!["genre", "era"]);
+
+        assert_eq!(recommend_similar(&library[0], &library, &HashMap::new(), 2).len(), 2);
+    }
+
+    #[test]
+    fn test_recommend_similar_uses_behavior() {
+        let library = vec![
+            Track::test_fixture("/m/seed.mp3").with_artist("A").with_album("One").with_genre("Ambient").with_year(2000),
+            Track::test_fixture("/m/loved.mp3").with_artist("B").with_album("Two").with_genre("Ambient").with_year(2000),
+            Track::test_fixture("/m/skipped.mp3").with_artist("C").with_album("Three").with_genre("Ambient").with_year(2000),
+            Track::test_fixture("/m/liked_only.mp3").with_artist("D").with_album("Four").with_genre("Polka").with_year(1970),
+        ];
+        let behavior = |track: &Track, plays, skips, completion, tags: &[&str]| {
+            let mut behavior = TrackBehavior::new(track.id);
+            behavior.total_plays = plays;
+            behavior.total_skips = skips;
+            behavior.completion_rate = completion;
+            behavior.tags = tags.iter().map(|tag| tag.to_string()).collect();
+            (track.id, behavior)
+        };
+        let behaviors: HashMap<_, _> = [
+            behavior(&library[0], 10, 0, 95.0, &["favorite"]),
+            behavior(&library[1], 8, 0, 90.0, &["favorite"]),
+            behavior(&library[2], 8, 6, 20.0, &["high_skip_rate"]),
+            behavior(&library[3], 5, 0, 95.0, &["favorite"]),
+        ].into_iter().collect();
+
+        let similar = recommend_similar(&library[0], &library, &behaviors, 10);
+        let order: Vec<usize> = similar.iter().map(|s| s.index).collect();
+        // Sharing only a liked tag still counts, sharing only a skip tag doesn't
+        assert_eq!(order, vec![1, 2, 3]);
+        assert!(similar[0].reasons.contains(&"liked"));
+        assert!(similar[0].reasons.contains(&"listened alike"));
+        assert!(similar[0].score > similar[1].score);
+    }
+}
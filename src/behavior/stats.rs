@@ -0,0 +1,95 @@
+// Listening stats - turns the raw behavior rows into numbers worth looking at
+
+use super::TrackBehavior;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// How many tracks the top-played / top-skipped lists keep
+pub const TOP_TRACKS: usize = 10;
+
+#[derive(Debug, Clone, Default)]
+pub struct ListeningStats {
+    pub tracks_with_history: usize,
+    pub total_plays: u64,
+    pub total_skips: u64,
+    pub total_listening_secs: u64,
+    pub most_played: Vec<(Uuid, u64)>,  // (track, plays), highest first
+    pub most_skipped: Vec<(Uuid, u64)>, // (track, skips), highest first
+    pub tag_counts: Vec<(String, usize)>, // most common tag first
+}
+
+impl ListeningStats {
+    pub fn from_behaviors(behaviors: &[TrackBehavior]) -> Self {
+        let mut most_played: Vec<_> = behaviors.iter()
+            .filter(|b| b.total_plays > 0)
+            .map(|b| (b.track_id, b.total_plays))
+            .collect();
+        most_played.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        most_played.truncate(TOP_TRACKS);
+
+        let mut most_skipped: Vec<_> = behaviors.iter()
+            .filter(|b| b.total_skips > 0)
+            .map(|b| (b.track_id, b.total_skips))
+            .collect();
+        most_skipped.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        most_skipped.truncate(TOP_TRACKS);
+
+        let mut tags: HashMap<&str, usize> = HashMap::new();
+        for tag in behaviors.iter().flat_map(|b| &b.tags) {
+            *tags.entry(tag.as_str()).or_insert(0) += 1;
+        }
+        let mut tag_counts: Vec<_> = tags.into_iter()
+            .map(|(tag, count)| (tag.to_string(), count))
+            .collect();
+        tag_counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        Self {
+            tracks_with_history: behaviors.len(),
+            total_plays: behaviors.iter().map(|b| b.total_plays).sum(),
+            total_skips: behaviors.iter().map(|b| b.total_skips).sum(),
+            total_listening_secs: behaviors.iter().map(|b| b.total_play_time).sum(),
+            most_played,
+            most_skipped,
+            tag_counts,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total_plays == 0 && self.total_skips == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn behavior(plays: u64, skips: u64, secs: u64, tags: &[&str]) -> TrackBehavior {
+        let mut behavior = TrackBehavior::new(Uuid::new_v4());
+        behavior.total_plays = plays;
+        behavior.total_skips = skips;
+        behavior.total_play_time = secs;
+        behavior.tags = tags.iter().map(|t| t.to_string()).collect();
+        behavior
+    }
+
+    #[test]
+    fn test_stats_totals_and_rankings() {
+        let behaviors = vec![
+            behavior(12, 1, 2400, &["favorite", "frequently_played"]),
+            behavior(3, 3, 90, &["often_skipped"]),
+            behavior(0, 0, 0, &[]),
+            behavior(7, 0, 1500, &["favorite"]),
+        ];
+
+        let stats = ListeningStats::from_behaviors(&behaviors);
+        assert_eq!(stats.tracks_with_history, 4);
+        assert_eq!(stats.total_plays, 22);
+        assert_eq!(stats.total_skips, 4);
+        assert_eq!(stats.total_listening_secs, 3990);
+
+        let played: Vec<u64> = stats.most_played.iter().map(|(_, plays)| *plays).collect();
+        assert_eq!(played, vec![12, 7, 3]);
+        assert_eq!(stats.most_skipped[0], (behaviors[1].track_id, 3));
+        assert_eq!(stats.tag_counts[0], ("favorite".to_string(), 2));
+    }
+}
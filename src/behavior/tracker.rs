@@ -1,7 +1,10 @@
-use super::{BehaviorDatabase, PlaySession, TrackBehavior};
+use super::{weighting::{PlayHours, ShuffleWeighting, WeightCalculator}, BehaviorDatabase, Bookmark, PlaySession, RecentPlay, TrackBehavior};
+use crate::audio::{silence::SilenceBounds, Track};
+use crate::config::BehaviorConfig;
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use uuid::Uuid;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -44,7 +47,10 @@ pub enum PlaybackEvent {
 pub struct BehaviorTracker {
     database: BehaviorDatabase,
     current_session: Option<ActiveSession>,
+    finished_session: Option<PlaySession>, // the last session to end, until taken
     min_play_time: u64, // minimum seconds to count as a "play"
+    skip_threshold: u64, // skips before this many seconds count as early ones
+    weights: WeightCalculator, // for the weight stored with each recorded session
 }
 
 #[derive(Debug)]
@@ -52,17 +58,45 @@ struct ActiveSession {
     session: PlaySession,
     actual_play_time: u64, // time actually spent playing (excluding pauses)
     pause_start: Option<DateTime<Utc>>,
+    paused_secs: u64, // total length of finished pauses
+}
+
+impl ActiveSession {
+    /// Seconds of the track heard by `now`, for sessions that end without a final position
+    fn played_secs(&self, now: DateTime<Utc>) -> u64 {
+        if self.pause_start.is_some() {
+            return self.actual_play_time;
+        }
+        let elapsed = (now - self.session.started_at).num_seconds().max(0) as u64;
+        elapsed.saturating_sub(self.paused_secs).min(self.session.track_duration)
+    }
 }
 
 impl BehaviorTracker {
     pub fn new(database: BehaviorDatabase, min_play_time: u64) -> Self {
+        let defaults = BehaviorConfig::default();
         Self {
             database,
             current_session: None,
+            finished_session: None,
             min_play_time,
+            skip_threshold: defaults.skip_threshold_seconds,
+            weights: WeightCalculator::new(defaults.weight_decay_days),
         }
     }
     
+    /// Skips before `seconds` weigh more heavily against a track than later ones
+    pub fn with_skip_threshold(mut self, seconds: u64) -> Self {
+        self.skip_threshold = seconds;
+        self
+    }
+    
+    /// Decay stored weights over `days`, the same as `ShuffleWeighting` does at startup
+    pub fn with_decay_days(mut self, days: u64) -> Self {
+        self.weights = WeightCalculator::new(days);
+        self
+    }
+    
     pub async fn handle_event(&mut self, event: PlaybackEvent) -> Result<()> {
         match event {
             PlaybackEvent::TrackStarted { track_id, timestamp } => {
@@ -116,6 +150,7 @@ impl BehaviorTracker {
             session,
             actual_play_time: 0,
             pause_start: None,
+            paused_secs: 0,
         });
         
         Ok(())
@@ -131,10 +166,12 @@ impl BehaviorTracker {
         Ok(())
     }
     
-    fn resume_session(&mut self, track_id: Uuid, _position: u64, _timestamp: DateTime<Utc>) -> Result<()> {
+    fn resume_session(&mut self, track_id: Uuid, _position: u64, timestamp: DateTime<Utc>) -> Result<()> {
         if let Some(active) = &mut self.current_session {
             if active.session.track_id == track_id {
-                active.pause_start = None;
+                if let Some(paused_at) = active.pause_start.take() {
+                    active.paused_secs += (timestamp - paused_at).num_seconds().max(0) as u64;
+                }
                 // Note: We don't update position here as we track actual play time separately
             }
         }
@@ -156,6 +193,7 @@ impl BehaviorTracker {
                 active.session.skip_reason = skip_reason;
                 active.session.completion_percentage = 
                     (active.session.play_duration as f64 / active.session.track_duration as f64 * 100.0).min(100.0);
+                self.finished_session = Some(active.session.clone());
                 
                 // Only record if played for minimum time
                 if active.session.play_duration >= self.min_play_time {
@@ -167,6 +205,40 @@ impl BehaviorTracker {
         Ok(())
     }
     
+    /// The session the last event ended, recorded or not - for listeners like scrobbling
+    pub fn take_finished_session(&mut self) -> Option<PlaySession> {
+        self.finished_session.take()
+    }
+    
+    /// End the session in progress, e.g. on quit, so a partial listen isn't lost
+    ///
+    /// Recorded like any other session if it reached `min_play_time`. Quitting isn't a
+    /// judgement on the track, so it's not counted as a skip.
+    pub async fn finalize(&mut self) -> Result<()> {
+        let Some(active) = &self.current_session else {
+            return Ok(());
+        };
+        let now = Utc::now();
+        let track_id = active.session.track_id;
+        let position = active.played_secs(now);
+        self.end_session(track_id, position, None, now).await
+    }
+    
+    /// Re-apply time decay to every stored behavior, so tracks that haven't been played
+    /// lately still drift back up; returns how many were updated
+    pub async fn recalculate_weights(&self, weighting: &ShuffleWeighting) -> Result<usize> {
+        let mut behaviors: HashMap<Uuid, TrackBehavior> = self.get_all_behaviors().await?
+            .into_iter()
+            .map(|behavior| (behavior.track_id, behavior))
+            .collect();
+        weighting.recalculate_all_weights(&mut behaviors);
+        
+        for behavior in behaviors.values() {
+            self.database.save_track_behavior(behavior).await?;
+        }
+        Ok(behaviors.len())
+    }
+    
     async fn record_session(&mut self, session: PlaySession) -> Result<()> {
         // Save session to database
         self.database.save_session(&session).await?;
@@ -175,23 +247,247 @@ impl BehaviorTracker {
         let mut behavior = self.database.get_track_behavior(session.track_id).await?
             .unwrap_or_else(|| TrackBehavior::new(session.track_id));
         
-        behavior.update_from_session(&session);
-        
-        // Recalculate weight
-        let days_since_last = behavior.last_played
-            .map(|last| (Utc::now() - last).num_days() as u64);
-        behavior.weight = behavior.calculate_shuffle_weight(days_since_last);
+        behavior.update_from_session(&session, self.skip_threshold);
+        behavior.weight = self.weights.calculate_weight(&behavior, Utc::now());
         
         self.database.save_track_behavior(&behavior).await?;
         
         Ok(())
     }
     
+    /// Plays per local hour for every track, for time-of-day weighting
+    pub async fn get_play_hours(&self) -> Result<HashMap<Uuid, PlayHours>> {
+        self.database.get_play_hours().await
+    }
+    
     pub async fn get_track_behavior(&self, track_id: Uuid) -> Result<Option<TrackBehavior>> {
         self.database.get_track_behavior(track_id).await
     }
     
+    /// Start a track over as if it had never been played, back at a neutral shuffle weight
+    pub async fn reset_track_behavior(&self, track_id: Uuid) -> Result<bool> {
+        self.database.delete_track_behavior(track_id).await
+    }
+    
+    /// Keep a track out of shuffle without touching its history
+    pub async fn set_shuffle_excluded(&self, track_id: Uuid, excluded: bool) -> Result<()> {
+        self.database.set_shuffle_excluded(track_id, excluded).await
+    }
+    
+    /// Hand a duplicate's plays, skips and sessions to the copy that's kept
+    pub async fn merge_track_behavior(&self, from: Uuid, into: Uuid) -> Result<bool> {
+        self.database.merge_track_behavior(from, into).await
+    }
+    
+    /// Move a track's history from its current id and hash onto `new_hash`, returning the new id
+    ///
+    /// For when the file's bytes changed under it - retagged here, or modified on disk.
+    pub async fn move_track_history(&self, tracks: &[Track], track_idx: usize, new_hash: u64) -> Result<Uuid> {
+        let (old_id, old_hash) = (tracks[track_idx].id, tracks[track_idx].content_hash);
+        let new_id = Track::content_id(new_hash);
+        
+        // Identical copies share an id, and the others still have the old bytes, so their history stays
+        let shared = tracks.iter().enumerate().any(|(i, other)| i != track_idx && other.id == old_id);
+        if !shared {
+            self.database.merge_track_behavior(old_id, new_id).await?;
+            if let Some(old_hash) = old_hash {
+                self.database.move_content_hash(old_hash, new_hash)?;
+            }
+        }
+        Ok(new_id)
+    }
+    
+    /// Start every track over; returns how many had behavior data
+    pub async fn reset_all_behaviors(&self) -> Result<usize> {
+        self.database.clear_all_behaviors().await
+    }
+    
     pub async fn get_all_behaviors(&self) -> Result<Vec<TrackBehavior>> {
         self.database.get_all_track_behaviors().await
     }
+    
+    pub fn add_bookmark(&self, content_hash: u64, position: std::time::Duration, label: &str) -> Result<i64> {
+        self.database.add_bookmark(content_hash, position, label)
+    }
+    
+    pub fn get_bookmarks(&self, content_hash: u64) -> Result<Vec<Bookmark>> {
+        self.database.get_bookmarks(content_hash)
+    }
+    
+    pub fn delete_bookmark(&self, id: i64) -> Result<()> {
+        self.database.delete_bookmark(id)
+    }
+    
+    pub fn get_silence_bounds(&self, content_hash: u64) -> Result<Option<SilenceBounds>> {
+        self.database.get_silence_bounds(content_hash)
+    }
+    
+    pub fn save_silence_bounds(&self, content_hash: u64, bounds: SilenceBounds) -> Result<()> {
+        self.database.save_silence_bounds(content_hash, bounds)
+    }
+    
+    pub fn get_resume_position(&self, content_hash: u64) -> Result<Option<std::time::Duration>> {
+        self.database.get_resume_position(content_hash)
+    }
+    
+    pub fn save_resume_position(&self, content_hash: u64, position: std::time::Duration) -> Result<()> {
+        self.database.save_resume_position(content_hash, position)
+    }
+    
+    pub fn clear_resume_position(&self, content_hash: u64) -> Result<()> {
+        self.database.clear_resume_position(content_hash)
+    }
+    
+    /// Keep bookmarks and positions with a file whose bytes changed, e.g. after a tag write
+    pub fn move_content_hash(&self, from: u64, to: u64) -> Result<()> {
+        self.database.move_content_hash(from, to)
+    }
+    
+    /// Record re-read tracks so the next scan doesn't serve their old tags
+    pub fn save_scan_cache(&self, tracks: &[Track]) -> Result<()> {
+        self.database.save_scan_cache(tracks)
+    }
+    
+    pub fn recent_plays(&self, limit: usize) -> Result<Vec<RecentPlay>> {
+        self.database.recent_plays(limit)
+    }
+    
+    pub fn export_sessions_csv(&self, path: &std::path::Path) -> Result<usize> {
+        self.database.export_sessions_csv(path)
+    }
+    
+    pub fn export_behaviors_csv(&self, path: &std::path::Path) -> Result<usize> {
+        self.database.export_behaviors_csv(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[tokio::test]
+    async fn test_finalize_records_partial_listen() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = BehaviorDatabase::new(dir.path().join("behavior.db")).unwrap();
+        let mut tracker = BehaviorTracker::new(db, 30);
+        let track_id = Uuid::new_v4();
+        let started = Utc::now() - Duration::seconds(100);
+
+        tracker.handle_event(PlaybackEvent::TrackStarted { track_id, timestamp: started }).await.unwrap();
+        tracker.handle_event(PlaybackEvent::TrackPaused {
+            track_id,
+            position: 20,
+            timestamp: started + Duration::seconds(20),
+        }).await.unwrap();
+        tracker.handle_event(PlaybackEvent::TrackResumed {
+            track_id,
+            position: 20,
+            timestamp: started + Duration::seconds(60),
+        }).await.unwrap();
+        tracker.finalize().await.unwrap();
+
+        // 100s since start, 40s of it paused
+        let behavior = tracker.get_track_behavior(track_id).await.unwrap().unwrap();
+        assert_eq!(behavior.total_plays, 1);
+        assert_eq!(behavior.total_skips, 0);
+        assert!((59..=61).contains(&behavior.total_play_time));
+
+        // Nothing left to flush
+        tracker.finalize().await.unwrap();
+        assert_eq!(tracker.get_track_behavior(track_id).await.unwrap().unwrap().total_plays, 1);
+    }
+
+    #[tokio::test]
+    async fn test_finalize_drops_short_listen() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = BehaviorDatabase::new(dir.path().join("behavior.db")).unwrap();
+        let mut tracker = BehaviorTracker::new(db, 30);
+        let track_id = Uuid::new_v4();
+
+        tracker.handle_event(PlaybackEvent::TrackStarted { track_id, timestamp: Utc::now() }).await.unwrap();
+        tracker.finalize().await.unwrap();
+        assert!(tracker.get_track_behavior(track_id).await.unwrap().is_none());
+        // Still handed out, a listener may have its own threshold
+        assert_eq!(tracker.take_finished_session().unwrap().track_id, track_id);
+        assert!(tracker.take_finished_session().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_modified_file_takes_its_history_to_the_new_id() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = BehaviorDatabase::new(dir.path().join("behavior.db")).unwrap();
+        let mut tracker = BehaviorTracker::new(db, 30);
+        let scanner = crate::audio::MusicScanner::new();
+        let path = dir.path().join("song.mp3");
+        std::fs::write(&path, b"not really audio").unwrap();
+        let tracks = vec![scanner.scan_file(&path).unwrap()];
+        let (old_id, old_hash) = (tracks[0].id, tracks[0].content_hash.unwrap());
+
+        tracker.handle_event(PlaybackEvent::TrackStarted { track_id: old_id, timestamp: Utc::now() - Duration::seconds(100) }).await.unwrap();
+        tracker.finalize().await.unwrap();
+        tracker.add_bookmark(old_hash, std::time::Duration::from_secs(42), "chorus").unwrap();
+
+        // Retagged by another program, as the watcher sees it
+        std::fs::write(&path, b"not really audio, retagged").unwrap();
+        let rescanned = scanner.scan_file(&path).unwrap();
+        let new_hash = rescanned.content_hash.unwrap();
+        let new_id = tracker.move_track_history(&tracks, 0, new_hash).await.unwrap();
+
+        assert_ne!(new_id, old_id);
+        assert_eq!(new_id, rescanned.id);
+        assert_eq!(tracker.get_track_behavior(new_id).await.unwrap().unwrap().total_plays, 1);
+        assert!(tracker.get_track_behavior(old_id).await.unwrap().is_none());
+        assert_eq!(tracker.get_bookmarks(new_hash).unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_shared_id_keeps_its_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = BehaviorDatabase::new(dir.path().join("behavior.db")).unwrap();
+        let mut tracker = BehaviorTracker::new(db, 30);
+        let mut copy = Track::test_fixture("/music/a.mp3");
+        copy.id = Track::content_id(1);
+        copy.content_hash = Some(1);
+        let tracks = vec![copy.clone(), Track { file_path: "/music/b.mp3".into(), ..copy }];
+
+        tracker.handle_event(PlaybackEvent::TrackStarted { track_id: tracks[0].id, timestamp: Utc::now() - Duration::seconds(100) }).await.unwrap();
+        tracker.finalize().await.unwrap();
+
+        // The untouched copy still has the old bytes, so it keeps the plays
+        let new_id = tracker.move_track_history(&tracks, 0, 2).await.unwrap();
+        assert_eq!(new_id, Track::content_id(2));
+        assert_eq!(tracker.get_track_behavior(tracks[1].id).await.unwrap().unwrap().total_plays, 1);
+        assert!(tracker.get_track_behavior(new_id).await.unwrap().is_none());
+    }
+
+    /// One session of a fresh track, skipped (or finished, with `None`) `played` seconds in
+    async fn weight_after(tracker: &mut BehaviorTracker, played: u64, reason: Option<SkipReason>) -> TrackBehavior {
+        let track_id = Uuid::new_v4();
+        let started = Utc::now() - Duration::seconds(played as i64);
+        tracker.handle_event(PlaybackEvent::TrackStarted { track_id, timestamp: started }).await.unwrap();
+        let timestamp = Utc::now();
+        match reason {
+            Some(reason) => tracker.handle_event(PlaybackEvent::TrackSkipped { track_id, position: played, reason, timestamp }).await.unwrap(),
+            None => tracker.handle_event(PlaybackEvent::TrackCompleted { track_id, timestamp }).await.unwrap(),
+        }
+        tracker.get_track_behavior(track_id).await.unwrap().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_early_skip_costs_more_than_late_skip() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = BehaviorDatabase::new(dir.path().join("behavior.db")).unwrap();
+        let mut tracker = BehaviorTracker::new(db, 5).with_skip_threshold(30).with_decay_days(30);
+
+        // Unknown tracks are taken to be 180s long
+        let early = weight_after(&mut tracker, 12, Some(SkipReason::UserSkip)).await;
+        let late = weight_after(&mut tracker, 170, Some(SkipReason::UserSkip)).await;
+        let completed = weight_after(&mut tracker, 180, None).await;
+        assert_eq!((early.total_skips, early.early_skips), (1, 1));
+        assert_eq!((late.total_skips, late.early_skips), (1, 0));
+        assert_eq!(completed.total_skips, 0);
+        assert!(early.weight < late.weight, "{} vs {}", early.weight, late.weight);
+        assert!(late.weight < completed.weight, "{} vs {}", late.weight, completed.weight);
+    }
 }
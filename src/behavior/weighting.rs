@@ -1,13 +1,18 @@
 use super::TrackBehavior;
-use chrono::{DateTime, Utc};
+use crate::audio::TrackMetadata;
+use chrono::{DateTime, Local, Timelike, Utc};
 use rand::prelude::*;
 use std::collections::HashMap;
 use uuid::Uuid;
 
+/// Plays of one track per local hour of the day, midnight first
+pub type PlayHours = [u32; 24];
+
 pub struct WeightCalculator {
     decay_days: u64,
     boost_factor: f64,
     penalty_factor: f64,
+    play_hours: HashMap<Uuid, PlayHours>, // empty unless time-of-day weighting is on
 }
 
 impl WeightCalculator {
@@ -16,9 +21,35 @@ impl WeightCalculator {
             decay_days,
             boost_factor: 1.5,
             penalty_factor: 0.3,
+            play_hours: HashMap::new(),
         }
     }
     
+    /// Favor tracks usually played around the current hour, from each track's play history
+    pub fn with_play_hours(mut self, play_hours: HashMap<Uuid, PlayHours>) -> Self {
+        self.play_hours = play_hours;
+        self
+    }
+    
+    /// Up to 2x for a track mostly played within an hour of `current_time` (local), 1.0 otherwise
+    ///
+    /// Needs at least 3 plays to go on; playing at other times is never a penalty.
+    pub fn time_of_day_boost(&self, track_id: Uuid, current_time: DateTime<Utc>) -> f64 {
+        let Some(hours) = self.play_hours.get(&track_id) else {
+            return 1.0;
+        };
+        let total: u32 = hours.iter().sum();
+        if total < 3 {
+            return 1.0;
+        }
+        
+        let hour = current_time.with_timezone(&Local).hour() as usize;
+        let nearby: u32 = [hour + 23, hour, hour + 1].iter().map(|h| hours[h % 24]).sum();
+        // How much more often than an even spread over the day (3 of 24 hours)
+        let ratio = (nearby as f64 / total as f64) / (3.0 / 24.0);
+        (1.0 + 0.25 * (ratio - 1.0)).clamp(1.0, 2.0)
+    }
+    
     pub fn calculate_weight(&self, behavior: &TrackBehavior, current_time: DateTime<Utc>) -> f64 {
         let mut weight = 1.0;
         
@@ -50,6 +81,10 @@ impl WeightCalculator {
         if behavior.total_plays > 0 {
             let skip_ratio = behavior.total_skips as f64 / behavior.total_plays as f64;
             weight *= (1.0 - skip_ratio * 0.6).max(0.2);
+            
+            // Skipped within the threshold: on top of the above, so it costs more than a late skip
+            let early_skip_ratio = behavior.early_skips as f64 / behavior.total_plays as f64;
+            weight *= (1.0 - early_skip_ratio * 0.5).max(0.3);
         }
         
         // Tag-based adjustments
@@ -68,14 +103,43 @@ impl WeightCalculator {
             }
         }
         
+        weight *= self.time_of_day_boost(behavior.track_id, current_time);
+        
         // Ensure weight stays within reasonable bounds
-        weight.max(0.05).min(5.0)
+        weight.clamp(0.05, 5.0)
     }
 }
 
+/// How alike two tracks are by genre and year, 0.0 to 1.0; `None` when neither can be compared
+///
+/// Genres match if they share any entry ("Rock; Indie" vs "indie"), years fade out over a decade.
+pub fn metadata_similarity(a: &TrackMetadata, b: &TrackMetadata) -> Option<f64> {
+    let (genres_a, genres_b) = (genres(a), genres(b));
+    let genre = (!genres_a.is_empty() && !genres_b.is_empty())
+        .then(|| if genres_a.iter().any(|g| genres_b.contains(g)) { 1.0 } else { 0.0 });
+    let year = a.year.zip(b.year)
+        .map(|(x, y)| (1.0 - x.abs_diff(y) as f64 / 10.0).max(0.0));
+    
+    match (genre, year) {
+        (Some(genre), Some(year)) => Some((genre + year) / 2.0),
+        (genre, year) => genre.or(year),
+    }
+}
+
+/// The genre tag split into its entries, lowercased: "Rock; Indie" is ["rock", "indie"]
+pub(crate) fn genres(metadata: &TrackMetadata) -> Vec<String> {
+    metadata.genre.as_deref().unwrap_or_default()
+        .split([';', ',', '/'])
+        .map(|genre| genre.trim().to_lowercase())
+        .filter(|genre| !genre.is_empty())
+        .collect()
+}
+
 pub struct ShuffleWeighting {
     calculator: WeightCalculator,
     rng: ThreadRng,
+    coherence: f64, // 0.0 = behavior only, 1.0 = strongly follow the last track's genre/year
+    metadata: HashMap<Uuid, TrackMetadata>,
 }
 
 impl ShuffleWeighting {
@@ -83,10 +147,45 @@ impl ShuffleWeighting {
         Self {
             calculator: WeightCalculator::new(decay_days),
             rng: thread_rng(),
+            coherence: 0.0,
+            metadata: HashMap::new(),
+        }
+    }
+    
+    /// Favor tracks usually played around the current hour, see `WeightCalculator::time_of_day_boost`
+    pub fn with_play_hours(mut self, play_hours: HashMap<Uuid, PlayHours>) -> Self {
+        self.calculator = self.calculator.with_play_hours(play_hours);
+        self
+    }
+    
+    /// Session coherence: favor tracks whose genre/year are close to the one just played
+    ///
+    /// `strength` (0.0 to 1.0) blends between pure behavior weighting and genre-coherent flow.
+    pub fn with_coherence(mut self, strength: f64, metadata: HashMap<Uuid, TrackMetadata>) -> Self {
+        self.coherence = if strength.is_finite() { strength.clamp(0.0, 1.0) } else { 0.0 };
+        self.metadata = metadata;
+        self
+    }
+    
+    /// Multiplier for `candidate` following `previous`: 1.0 with coherence off or nothing to compare,
+    /// otherwise from 0.1 for a clash up to 3.0 for a close match at full strength
+    fn coherence_factor(&self, previous: Option<Uuid>, candidate: Uuid) -> f64 {
+        if self.coherence == 0.0 {
+            return 1.0;
+        }
+        let similarity = previous
+            .and_then(|previous| self.metadata.get(&previous))
+            .zip(self.metadata.get(&candidate))
+            .and_then(|(previous, candidate)| metadata_similarity(previous, candidate));
+        match similarity {
+            Some(similarity) => (1.0 - self.coherence) + self.coherence * (0.1 + 2.9 * similarity),
+            None => 1.0,
         }
     }
     
     /// Select next track using weighted random selection
+    ///
+    /// The last entry of `recently_played` is the track just played, which session coherence follows.
     pub fn select_next_track(
         &mut self,
         available_tracks: &[Uuid],
@@ -97,10 +196,20 @@ impl ShuffleWeighting {
             return None;
         }
         
+        // Excluded tracks are out entirely, not merely unlikely
+        let available_tracks: Vec<Uuid> = available_tracks.iter()
+            .copied()
+            .filter(|id| !behaviors.get(id).is_some_and(|b| b.shuffle_excluded))
+            .collect();
+        if available_tracks.is_empty() {
+            return None;
+        }
+        
         let current_time = Utc::now();
+        let previous = recently_played.last().copied();
         let mut weighted_tracks = Vec::new();
         
-        for &track_id in available_tracks {
+        for &track_id in &available_tracks {
             // Skip recently played tracks unless it's the only option
             if recently_played.contains(&track_id) && available_tracks.len() > recently_played.len() {
                 continue;
@@ -113,7 +222,7 @@ impl ShuffleWeighting {
                 1.2
             };
             
-            weighted_tracks.push((track_id, weight));
+            weighted_tracks.push((track_id, weight * self.coherence_factor(previous, track_id)));
         }
         
         if weighted_tracks.is_empty() {
@@ -212,3 +321,112 @@ impl ShuffleWeighting {
         weighted_tracks
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(genre: &str, year: u32) -> TrackMetadata {
+        TrackMetadata { genre: Some(genre.to_string()), year: Some(year), ..TrackMetadata::default() }
+    }
+
+    #[test]
+    fn test_early_skips_weigh_more_than_near_complete_plays() {
+        let calculator = WeightCalculator::new(30);
+        let now = Utc::now();
+        let mut near_complete = TrackBehavior::new(Uuid::new_v4());
+        near_complete.total_plays = 4;
+        near_complete.total_skips = 1;
+        near_complete.completion_rate = 95.0;
+        near_complete.last_played = Some(now - chrono::Duration::days(2));
+
+        // Same number of skips, but made within the threshold
+        let early = TrackBehavior { track_id: Uuid::new_v4(), early_skips: 1, ..near_complete.clone() };
+        assert!(calculator.calculate_weight(&early, now) < calculator.calculate_weight(&near_complete, now));
+
+        let all_early = TrackBehavior { early_skips: 4, total_skips: 4, completion_rate: 10.0, ..early.clone() };
+        assert!(calculator.calculate_weight(&all_early, now) < calculator.calculate_weight(&early, now));
+    }
+
+    #[test]
+    fn test_metadata_similarity() {
+        assert_eq!(metadata_similarity(&metadata("Ambient", 1994), &metadata("ambient", 1994)), Some(1.0));
+        assert_eq!(metadata_similarity(&metadata("Rock; Indie", 2000), &metadata("Indie", 2005)), Some(0.75));
+        assert_eq!(metadata_similarity(&metadata("Metal", 1980), &metadata("Jazz", 2020)), Some(0.0));
+        assert_eq!(metadata_similarity(&TrackMetadata::default(), &metadata("Jazz", 2020)), None);
+    }
+
+    #[test]
+    fn test_time_of_day_boost() {
+        let now = Utc::now();
+        let hour = now.with_timezone(&Local).hour() as usize;
+        let (morning, evening, rare) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let mut usual = [0; 24];
+        usual[hour] = 6;
+        usual[(hour + 1) % 24] = 2;
+        let mut elsewhere = [0; 24];
+        elsewhere[(hour + 12) % 24] = 8;
+        let mut once = [0; 24];
+        once[hour] = 1;
+        let calculator = WeightCalculator::new(30)
+            .with_play_hours(HashMap::from([(morning, usual), (evening, elsewhere), (rare, once)]));
+
+        assert_eq!(calculator.time_of_day_boost(morning, now), 2.0);
+        assert_eq!(calculator.time_of_day_boost(evening, now), 1.0);
+        assert_eq!(calculator.time_of_day_boost(rare, now), 1.0); // too few plays to tell
+        assert_eq!(calculator.time_of_day_boost(Uuid::new_v4(), now), 1.0);
+
+        let mut behavior = TrackBehavior::new(morning);
+        behavior.completion_rate = 50.0;
+        let plain = WeightCalculator::new(30).calculate_weight(&behavior, now);
+        assert_eq!(calculator.calculate_weight(&behavior, now), plain * 2.0);
+    }
+
+    #[test]
+    fn test_excluded_tracks_never_shuffled() {
+        let tracks: Vec<Uuid> = (0..6).map(|_| Uuid::new_v4()).collect();
+        let behaviors: HashMap<Uuid, TrackBehavior> = tracks[..4].iter()
+            .map(|&id| {
+                let mut behavior = TrackBehavior::new(id);
+                behavior.shuffle_excluded = true;
+                (id, behavior)
+            })
+            .collect();
+        let mut weighting = ShuffleWeighting::new(30);
+
+        let playlist = weighting.generate_shuffled_playlist(&tracks, &behaviors, 10);
+        assert_eq!(playlist.len(), 2);
+        assert!(playlist.iter().all(|id| !behaviors.contains_key(id)));
+
+        // Even with nothing else left to pick
+        assert_eq!(weighting.select_next_track(&tracks[..4], &behaviors, &[]), None);
+        assert_eq!(weighting.select_next_track(&tracks, &behaviors, &tracks[4..]).map(|id| tracks[4..].contains(&id)), Some(true));
+    }
+
+    #[test]
+    fn test_high_coherence_prefers_same_genre() {
+        let previous = Uuid::new_v4();
+        let ambient: Vec<Uuid> = (0..10).map(|_| Uuid::new_v4()).collect();
+        let metal: Vec<Uuid> = (0..10).map(|_| Uuid::new_v4()).collect();
+        let mut tags = HashMap::from([(previous, metadata("Ambient", 1994))]);
+        tags.extend(ambient.iter().map(|&id| (id, metadata("Ambient", 1995))));
+        tags.extend(metal.iter().map(|&id| (id, metadata("Metal", 2015))));
+        let candidates: Vec<Uuid> = ambient.iter().chain(&metal).copied().collect();
+
+        let ambient_picks = |weighting: &mut ShuffleWeighting| {
+            (0..400)
+                .filter_map(|_| weighting.select_next_track(&candidates, &HashMap::new(), &[previous]))
+                .filter(|id| ambient.contains(id))
+                .count()
+        };
+
+        let mut coherent = ShuffleWeighting::new(30).with_coherence(1.0, tags.clone());
+        assert!(ambient_picks(&mut coherent) > 320); // ~96% expected
+
+        // Off by default: genre plays no part
+        let mut plain = ShuffleWeighting::new(30);
+        assert_eq!(plain.coherence_factor(Some(previous), metal[0]), 1.0);
+        let picks = ambient_picks(&mut plain);
+        assert!((120..=280).contains(&picks), "{}", picks);
+    }
+}
@@ -1,16 +1,18 @@
 use anyhow::Result;
-use tracing::{debug, info, error};
+use tracing::{debug, info, error, warn};
 use clap::Parser;
 use crossterm::{
-    event::{self, Event, KeyCode, KeyEvent, KeyEventKind},
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, MouseButton, MouseEvent, MouseEventKind},
 };
-use fuzzy_matcher::{clangd::ClangdMatcher, FuzzyMatcher};
 use panpipe::{
-    audio::{AudioPlayer, MusicScanner, metadata_parser::MetadataParser, scanner::ScanProgress, playlist::PlaylistManager, player::PlayerEvent},
-    behavior::{BehaviorDatabase, BehaviorTracker, PlaybackEvent, SkipReason},
-    config::Config,
-    ui::TerminalManager,
+    backup,
+    audio::{AudioPlayer, MusicScanner, chapters::{self, Chapter}, equalizer::{band_label, clamp_gain, EqPreset, BAND_COUNT, BAND_FREQUENCIES}, visualizer::BAR_COUNT, grouping::{self, GroupBy, TreeRow}, search::{SearchIndex, SearchScope}, metadata_parser::{MetadataParser, ParsedMetadata}, tag_writer, scan_cache::ScanCache, library_cache::{LibraryCache, LibraryCacheKey}, scan_report::{DuplicateGroup, ScanReport}, library_stats::LibraryStats, dedupe::{DuplicateAction, DuplicateResolution}, rename::RenamePlan, scanner::ScanProgress, watcher::{LibraryChange, LibraryWatcher}, playlist::{Playlist, PlaylistExportReport, PlaylistManager, PlaylistRepeat}, player::PlayerEvent},
+    behavior::{BehaviorDatabase, BehaviorTracker, Bookmark, ListeningStats, PlaybackEvent, RecentPlay, SkipReason, TrackBehavior, similar::{self, SimilarTrack}, weighting::{ShuffleWeighting, WeightCalculator}},
+    export::ExportFormat,
+    config::{AudioSettings, Config, ResumePoint, SessionState},
+    ui::{keybindings::{Action, KeyCombo, KeyScope, Keymap, ACTIONS}, restore_terminal, ErrorLog, NotificationCenter, NotificationLevel, TerminalManager, Theme},
 };
+use rand::seq::SliceRandom;
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Margin, Rect},
     style::{Color, Modifier, Style},
@@ -19,7 +21,9 @@ use ratatui::{
     Frame,
 };
 use std::{
-    path::PathBuf,
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::{atomic::{AtomicBool, Ordering}, Arc},
     time::{Duration, Instant},
 };
 use tokio::{
@@ -28,6 +32,17 @@ use tokio::{
 };
 
 use tracing_subscriber::EnvFilter;
+#[cfg(feature = "mpris")]
+use panpipe::{audio::PlaybackState, mpris::{MprisCommand, MprisHandle}};
+use panpipe::control::{ControlCommand, ControlServer, ControlStatus};
+#[cfg(feature = "scrobble")]
+use panpipe::scrobble::{LastfmClient, Scrobbler};
+#[cfg(feature = "musicbrainz")]
+use panpipe::musicbrainz::{MetadataCandidate, MusicBrainzClient};
+#[cfg(feature = "lyrics")]
+use panpipe::lyrics::{Lyrics, LyricsClient};
+#[cfg(feature = "silence")]
+use panpipe::audio::silence::{self, SilenceBounds};
 
 #[derive(Parser)]
 #[command(name = "panpipe_interactive")]
@@ -36,6 +51,55 @@ struct Args {
     /// Enable developer logging (stderr + debug output)
     #[arg(long)]
     dev: bool,
+    
+    /// Ignore the library and scan caches and re-read tags/hashes for every file
+    #[arg(long)]
+    rescan: bool,
+    
+    /// Config file to load and save instead of the default (also settable with BANGTUNES_CONFIG)
+    #[arg(long = "config", value_name = "PATH", global = true)]
+    config_path: Option<PathBuf>,
+    
+    /// Run a single command without the TUI
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(clap::Subcommand)]
+enum Command {
+    /// Play one audio file (space = pause, n = next, q = quit)
+    Play { path: PathBuf },
+    /// Play a saved playlist by name (space = pause, n = next, q = quit)
+    PlayPlaylist { name: String },
+    /// Scan the library, print every track and exit
+    Scan,
+    /// Manage the music directories without editing config.toml
+    #[command(subcommand)]
+    Config(ConfigCommand),
+    /// Back up the listening history and playlists into a new timestamped folder in DIR
+    Backup { dir: PathBuf },
+    /// Restore the listening history and playlists from a backup folder
+    Restore {
+        backup: PathBuf,
+        /// Don't ask before overwriting
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Authorize scrobbling to last.fm in the browser
+    #[cfg(feature = "scrobble")]
+    LastfmAuth,
+}
+
+#[derive(clap::Subcommand)]
+enum ConfigCommand {
+    /// Add a folder to scan for music
+    AddDir { path: PathBuf },
+    /// Stop scanning a folder
+    RemoveDir { path: PathBuf },
+    /// List the folders scanned for music
+    ListDirs,
+    /// Print where the config file lives
+    Path,
 }
 
 fn init_logging(dev: bool) -> Result<()> {
@@ -80,11 +144,19 @@ async fn main() -> Result<()> {
     
     // Initialize logging system
     init_logging(args.dev)?;
+    install_panic_hook();
     
     info!("🎵 PanPipe Interactive starting up");
     
     // Only redirect stderr if NOT in dev mode (dev mode needs stderr for debug output)
-    let _stderr_redirect = if !args.dev {
+    // Config commands never touch audio, and their errors need to reach the user
+    let is_config_command = match &args.command {
+        Some(Command::Config(_) | Command::Backup { .. } | Command::Restore { .. }) => true,
+        #[cfg(feature = "scrobble")]
+        Some(Command::LastfmAuth) => true,
+        _ => false,
+    };
+    let _stderr_redirect = if !args.dev && !is_config_command {
         debug!("Redirecting stderr to suppress ALSA errors");
         Some(redirect_stderr_to_null())
     } else {
@@ -93,95 +165,591 @@ async fn main() -> Result<()> {
     };
     
     // Initialize configuration
+    if let Some(path) = args.config_path {
+        Config::set_path_override(path)?;
+    }
     let config = Config::load()?;
     
+    if let Some(command) = args.command {
+        return run_headless(command, config, args.rescan).await;
+    }
+    
     // Print startup banner
     println!("🎵 BangTunes - Terminal Music Player");
     println!("===================================");
     println!("Loading your music library...");
     
+    let (mut all_tracks, mut scan_report) = load_library(&config, args.rescan).await?;
+    
+    // Copies hidden by Find Duplicates stay on disk but out of the library
+    if !config.library.hidden_paths.is_empty() {
+        all_tracks.retain(|track| !config.library.is_hidden(&track.file_path));
+        scan_report.duplicate_groups = ScanReport::build(&all_tracks, Vec::new()).duplicate_groups;
+    }
+    
+    if all_tracks.is_empty() {
+        eprintln!("❌ No music files found in configured directories!");
+        eprintln!("Please check your music directories in the config.");
+        return Ok(());
+    }
+    
+    println!("✅ Loaded {} tracks total", all_tracks.len());
+    if !scan_report.is_clean() {
+        println!(
+            "🧹 {} duplicate groups, {} unreadable files (see Settings tab)",
+            scan_report.duplicate_groups.len(),
+            scan_report.unreadable.len()
+        );
+    }
+    println!("🚀 Starting BangTunes...\n");
+    
+    // Small delay to let user see the loading info
+    sleep(Duration::from_millis(1500)).await;
+    
+    // Initialize the interactive app
+    let mut app = InteractiveApp::new(config, all_tracks, scan_report).await?;
+    
+    // Run the interactive interface
+    app.run().await?;
+    
+    println!("\n👋 Thanks for using BangTunes!");
+    Ok(())
+}
+
+/// Load the library from the library cache, or scan the configured music directories, printing progress
+///
+/// Either way the scan cache is refreshed, and a complete scan replaces the library cache.
+async fn load_library(config: &Config, rescan: bool) -> Result<(Vec<panpipe::Track>, ScanReport)> {
+    // Load the scan cache so unchanged files skip tag reading and hashing
+    let cache_db = BehaviorDatabase::new(&config.database_path)?;
+    let scan_cache = if rescan {
+        info!("--rescan given, ignoring scan cache");
+        ScanCache::new()
+    } else {
+        cache_db.load_scan_cache().unwrap_or_else(|e| {
+            warn!("Could not load scan cache, doing a full scan: {}", e);
+            ScanCache::new()
+        })
+    };
+    debug!("Scan cache has {} entries", scan_cache.len());
+    
     // Initialize music scanner with incremental loading
-    let scanner = MusicScanner::new();
+    let scanner = config.library.build_scanner()?.with_cache(scan_cache);
+    
+    // Warm start: only look at what changed since the cached scan
+    let library_cache_path = config.library_cache_path();
+    let library_cache_key = LibraryCacheKey::new(config);
+    if !rescan {
+        match LibraryCache::load(&library_cache_path, &library_cache_key) {
+            Ok(Some(mut library_cache)) => {
+                let refresh = library_cache.refresh(&scanner);
+                say(&format!(
+                    "⚡ Loaded {} tracks from the library cache (+{} -{} ~{})",
+                    library_cache.tracks.len(), refresh.added, refresh.removed, refresh.updated
+                ));
+                
+                // Files changed on disk got new ids; their history goes with them
+                for rehashed in &refresh.rehashed {
+                    let moved = cache_db.merge_track_behavior(rehashed.old_id, rehashed.new_id).await
+                        .and_then(|_| match rehashed.old_hash {
+                            Some(old_hash) => cache_db.move_content_hash(old_hash, rehashed.new_hash),
+                            None => Ok(()),
+                        });
+                    if let Err(e) = moved {
+                        warn!("Failed to move behavior to the new id of a changed file: {}", e);
+                    }
+                }
+                
+                if !refresh.is_empty() {
+                    if let Err(e) = cache_db.save_scan_cache(&library_cache.tracks) {
+                        warn!("Failed to update scan cache: {}", e);
+                    }
+                    if let Err(e) = library_cache.save(&library_cache_path) {
+                        warn!("Failed to update library cache: {}", e);
+                    }
+                }
+                
+                let scan_report = ScanReport::build(&library_cache.tracks, library_cache.unreadable.clone());
+                return Ok((library_cache.tracks, scan_report));
+            }
+            Ok(None) => debug!("No library cache for the current settings, scanning"),
+            Err(e) => warn!("Could not read library cache, scanning: {}", e),
+        }
+    }
+    
     let (progress_tx, mut progress_rx) = mpsc::channel(128); // Bounded channel per analysis
     
-    println!("📁 Scanning music directories...");
+    say("📁 Scanning music directories...");
+    
+    // A key press stops the scan early, e.g. when pointed at a huge network mount by mistake
+    let cancel = Arc::new(AtomicBool::new(false));
+    let cancel_listener = ScanCancelListener::spawn(Arc::clone(&cancel));
+    if cancel_listener.is_some() {
+        say("   (press Esc to stop and start with the tracks found so far)");
+    }
     
     // Start incremental scanning in background
     let scanner_task = {
         let scanner = scanner.clone();
         let directories = config.music_directories.clone();
         tokio::spawn(async move {
-            scanner.scan_directories_incremental(&directories, progress_tx).await
+            scanner.scan_directories_incremental(&directories, progress_tx, cancel).await
         })
     };
     
     // Process scan progress with live updates
     let mut all_tracks = Vec::new();
+    let mut scan_report = ScanReport::default();
+    let mut scan_complete = false; // partial results must not end up in the library cache
     
+    // Lines end in \r\n since the cancel listener may have the terminal in raw mode
     while let Some(progress) = progress_rx.recv().await {
         match progress {
             ScanProgress::Started { total_directories } => {
-                println!("🔍 Starting scan of {} directories", total_directories);
+                say(&format!("🔍 Starting scan of {} directories", total_directories));
             }
             ScanProgress::DirectoryStarted { path } => {
-                println!("📂 Scanning: {:?}", path);
+                say(&format!("📂 Scanning: {:?}", path));
             }
             ScanProgress::TrackFound { track, progress, .. } => {
-                all_tracks.push(track);
+                all_tracks.push(*track);
                 
                 // Update progress every 50 tracks for smooth feedback
                 if progress % 50 == 0 {
-                    println!("   📀 Found {} tracks so far...", progress);
+                    say(&format!("   📀 Found {} tracks so far...", progress));
                 }
             }
             ScanProgress::DirectoryCompleted { path, tracks_found } => {
-                println!("   ✅ {:?}: {} tracks", path, tracks_found);
+                say(&format!("   ✅ {:?}: {} tracks", path, tracks_found));
             }
             ScanProgress::Completed { total_tracks } => {
-                println!("🎵 Scan complete: {} tracks total", total_tracks);
+                say(&format!("🎵 Scan complete: {} tracks total", total_tracks));
+                scan_complete = true;
+                break;
+            }
+            ScanProgress::Cancelled { total_tracks } => {
+                say(&format!("⏹️  Scan stopped: continuing with {} tracks", total_tracks));
                 break;
             }
             ScanProgress::Error { path, error } => {
-                eprintln!("   ⚠️  Error scanning {:?}: {}", path, error);
+                eprint!("   ⚠️  Error scanning {:?}: {}\r\n", path, error);
             }
         }
     }
     
     // Wait for scanner task to complete and get final results
     match scanner_task.await {
-        Ok(Ok(final_tracks)) => {
+        Ok(Ok((final_tracks, report))) => {
             all_tracks = final_tracks; // Use final results to ensure consistency
+            scan_report = report;
         }
         Ok(Err(e)) => {
-            eprintln!("❌ Scanner error: {}", e);
+            scan_complete = false;
+            eprint!("❌ Scanner error: {}\r\n", e);
         }
         Err(e) => {
-            eprintln!("❌ Scanner task error: {}", e);
+            scan_complete = false;
+            eprint!("❌ Scanner task error: {}\r\n", e);
         }
     }
+    drop(cancel_listener);
     
-    if all_tracks.is_empty() {
-        eprintln!("❌ No music files found in configured directories!");
-        eprintln!("Please check your music directories in the config.");
-        return Ok(());
+    if let Err(e) = cache_db.save_scan_cache(&all_tracks) {
+        warn!("Failed to update scan cache: {}", e);
+    }
+    if scan_complete {
+        let library_cache = LibraryCache::new(library_cache_key, all_tracks.clone())
+            .with_unreadable(scan_report.unreadable.clone());
+        if let Err(e) = library_cache.save(&library_cache_path) {
+            warn!("Failed to write library cache: {}", e);
+        }
     }
     
-    println!("✅ Loaded {} tracks total", all_tracks.len());
-    println!("🚀 Starting BangTunes...\n");
+    Ok((all_tracks, scan_report))
+}
+
+/// Keys understood while playing headlessly
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum HeadlessInput {
+    TogglePause,
+    Next,
+    Quit,
+}
+
+/// Leaves raw mode when headless playback ends, however it ends
+struct RawModeGuard;
+
+impl Drop for RawModeGuard {
+    fn drop(&mut self) {
+        let _ = crossterm::terminal::disable_raw_mode();
+    }
+}
+
+/// Watches for Esc/q/Ctrl+C while the library scans and sets the cancel flag
+///
+/// Dropping it stops the watcher thread and leaves raw mode, so nothing is left
+/// reading the terminal once the UI takes over.
+struct ScanCancelListener {
+    done: Arc<AtomicBool>,
+    thread: Option<std::thread::JoinHandle<()>>,
+    _raw_mode: RawModeGuard,
+}
+
+impl ScanCancelListener {
+    /// `None` when stdin isn't a terminal, e.g. `scan` piped into another command
+    fn spawn(cancel: Arc<AtomicBool>) -> Option<Self> {
+        use crossterm::event::KeyModifiers;
+        use std::io::IsTerminal;
+        
+        if !std::io::stdin().is_terminal() || crossterm::terminal::enable_raw_mode().is_err() {
+            return None;
+        }
+        
+        let done = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let done = Arc::clone(&done);
+            std::thread::spawn(move || {
+                // Poll rather than block on read so the thread notices when the scan finishes
+                while !done.load(Ordering::Relaxed) {
+                    if !event::poll(Duration::from_millis(100)).unwrap_or(false) {
+                        continue;
+                    }
+                    let Ok(Event::Key(key)) = event::read() else { continue };
+                    if key.kind == KeyEventKind::Press
+                        && matches!(
+                            (key.code, key.modifiers),
+                            (KeyCode::Esc, _) | (KeyCode::Char('q'), _) | (KeyCode::Char('c'), KeyModifiers::CONTROL)
+                        )
+                    {
+                        cancel.store(true, Ordering::Relaxed);
+                        break;
+                    }
+                }
+            })
+        };
+        
+        Some(Self { done, thread: Some(thread), _raw_mode: RawModeGuard })
+    }
+}
+
+impl Drop for ScanCancelListener {
+    fn drop(&mut self) {
+        self.done.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Print a line that looks right whether or not the terminal is in raw mode
+fn say(message: &str) {
+    use std::io::Write;
+    print!("{}\r\n", message);
+    let _ = std::io::stdout().flush();
+}
+
+async fn run_headless(command: Command, config: Config, rescan: bool) -> Result<()> {
+    match command {
+        Command::Config(command) => run_config_command(command, config),
+        Command::Backup { dir } => {
+            let summary = backup::create_backup(&config.database_path, &config.playlists_dir(), &dir)?;
+            println!("💾 Backed up listening history and {} playlists to {}", summary.playlists, summary.path.display());
+            Ok(())
+        }
+        Command::Restore { backup: path, yes } => {
+            let summary = backup::inspect_backup(&path)?;
+            println!(
+                "Restoring {} replaces the listening history in {} and {} playlists.",
+                summary.path.display(),
+                config.database_path.display(),
+                summary.playlists
+            );
+            if !yes && !confirm("Continue? [y/N] ")? {
+                println!("Nothing restored");
+                return Ok(());
+            }
+            backup::restore_backup(&path, &config.database_path, &config.playlists_dir())?;
+            println!("♻️ Restored");
+            Ok(())
+        }
+        #[cfg(feature = "scrobble")]
+        Command::LastfmAuth => run_lastfm_auth(config).await,
+        Command::Scan => {
+            let (tracks, report) = load_library(&config, rescan).await?;
+            for track in &tracks {
+                let duration = track.duration_seconds()
+                    .map(|secs| format!("{}:{:02}", secs / 60, secs % 60))
+                    .unwrap_or_else(|| "--:--".to_string());
+                println!("{} - {} [{}] {}", track.display_artist(), track.display_title(), duration, track.file_path.display());
+            }
+            println!("{} tracks", tracks.len());
+            if !report.is_clean() {
+                println!(
+                    "{} duplicate groups, {} unreadable files",
+                    report.duplicate_groups.len(),
+                    report.unreadable.len()
+                );
+            }
+            Ok(())
+        }
+        Command::Play { path } => {
+            let path = std::fs::canonicalize(&path).unwrap_or(path);
+            
+            // Go through the scan cache so the track keeps a stable id for behavior tracking
+            let cache_db = BehaviorDatabase::new(&config.database_path)?;
+            let scan_cache = cache_db.load_scan_cache().unwrap_or_else(|e| {
+                warn!("Could not load scan cache: {}", e);
+                ScanCache::new()
+            });
+            let track = config.library.build_scanner()?.with_cache(scan_cache).scan_file(&path)?;
+            if let Err(e) = cache_db.save_scan_cache(std::slice::from_ref(&track)) {
+                warn!("Failed to update scan cache: {}", e);
+            }
+            drop(cache_db);
+            
+            play_headless(&config, vec![track]).await
+        }
+        Command::PlayPlaylist { name } => {
+            let (tracks, _) = load_library(&config, rescan).await?;
+            let playlist_manager = PlaylistManager::new(config.playlists_dir()).map_err(|e| anyhow::anyhow!("{}", e))?;
+            let playlist = playlist_manager.list_playlists()
+                .into_iter()
+                .find(|p| p.name.eq_ignore_ascii_case(&name))
+                .ok_or_else(|| anyhow::anyhow!("No playlist named '{}'", name))?;
+            
+            let queue: Vec<_> = playlist.get_valid_tracks(&tracks)
+                .into_iter()
+                .map(|idx| tracks[idx].clone())
+                .collect();
+            if queue.is_empty() {
+                return Err(anyhow::anyhow!("Playlist '{}' has no playable tracks", playlist.name));
+            }
+            
+            println!("🎵 Playing '{}' ({} tracks)", playlist.name, queue.len());
+            play_headless(&config, queue).await
+        }
+    }
+}
+
+/// `config` subcommands: edit the music directories and save straight away
+fn run_config_command(command: ConfigCommand, mut config: Config) -> Result<()> {
+    match command {
+        ConfigCommand::AddDir { path } => {
+            if config.add_music_directory(&path)? {
+                config.save()?;
+                println!("➕ Added {}", config.music_directories.last().unwrap().display());
+            } else {
+                println!("{} is already a music directory", path.display());
+            }
+        }
+        ConfigCommand::RemoveDir { path } => {
+            if config.remove_music_directory(&path) {
+                config.save()?;
+                println!("➖ Removed {}", path.display());
+            } else {
+                return Err(anyhow::anyhow!("{} is not a music directory", path.display()));
+            }
+        }
+        ConfigCommand::ListDirs => {
+            if config.music_directories.is_empty() {
+                println!("No music directories - add one with `config add-dir <path>`");
+            }
+            for dir in &config.music_directories {
+                let missing = if dir.is_dir() { "" } else { "  (missing)" };
+                println!("{}{}", dir.display(), missing);
+            }
+        }
+        ConfigCommand::Path => println!("{}", Config::config_path()?.display()),
+    }
+    Ok(())
+}
+
+/// last.fm's web auth flow: approve a token in the browser, then trade it for a session key
+#[cfg(feature = "scrobble")]
+async fn run_lastfm_auth(mut config: Config) -> Result<()> {
+    let client = LastfmClient::new(&config.lastfm)?;
+    let token = client.get_token().await?;
+    println!("Open this link and allow access:\n\n  {}\n", client.auth_url(&token));
+    println!("Then press Enter here.");
+    let mut line = String::new();
+    std::io::stdin().read_line(&mut line)?;
     
-    // Small delay to let user see the loading info
-    sleep(Duration::from_millis(1500)).await;
+    let (username, session_key) = client.get_session(&token).await?;
+    config.lastfm.username = Some(username.clone());
+    config.lastfm.session_key = Some(session_key);
+    config.lastfm.enabled = true;
+    config.save()?;
+    println!("📻 Scrobbling to last.fm as {}", username);
+    Ok(())
+}
+
+/// Ask a yes/no question on the terminal, defaulting to no
+fn confirm(question: &str) -> Result<bool> {
+    use std::io::Write;
+    print!("{}", question);
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+/// m:ss for status messages
+fn format_position(position: Duration) -> String {
+    let secs = position.as_secs();
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+/// Open the configured output device, falling back to the system default if it has gone away
+///
+/// The second value is a warning for the user when the fallback was needed.
+fn open_audio_player(config: &Config) -> Result<(AudioPlayer, Option<String>)> {
+    let Some(device) = &config.audio.output_device else {
+        return Ok((AudioPlayer::new(config.clone().into())?, None));
+    };
     
-    // Initialize the interactive app
-    let mut app = InteractiveApp::new(config, all_tracks).await?;
+    match AudioPlayer::with_device(device, config.clone().into()) {
+        Ok(player) => Ok((player, None)),
+        Err(e) => {
+            warn!("Output device '{}' unavailable, using the default: {}", device, e);
+            let player = AudioPlayer::new(config.clone().into())?;
+            Ok((player, Some(format!("Output device '{}' is unavailable - using the system default", device))))
+        }
+    }
+}
+
+/// Play `queue` in order with a minimal keyboard loop, recording behavior like the TUI does
+async fn play_headless(config: &Config, queue: Vec<panpipe::Track>) -> Result<()> {
+    let (mut audio_player, device_warning) = open_audio_player(config)?;
+    if let Some(warning) = device_warning {
+        say(&format!("⚠️ {}", warning));
+    }
+    let (audio_event_tx, mut audio_event_rx) = mpsc::unbounded_channel();
+    audio_player.set_event_sender(audio_event_tx);
     
-    // Run the interactive interface
-    app.run().await?;
+    let mut behavior_tracker = BehaviorTracker::new(
+        BehaviorDatabase::new(&config.database_path)?,
+        config.behavior.min_play_time_for_tracking,
+    )
+    .with_skip_threshold(config.behavior.skip_threshold_seconds)
+    .with_decay_days(config.behavior.weight_decay_days);
     
-    println!("\n👋 Thanks for using BangTunes!");
+    let (_raw_mode, mut input_rx) = spawn_headless_input();
+    let position_secs = |player: &AudioPlayer| player.get_position().map(|p| p.as_secs()).unwrap_or(0);
+    
+    'queue: for (number, track) in queue.iter().enumerate() {
+        if let Err(e) = audio_player.play_track(track.clone()) {
+            say(&format!("❌ {}", e));
+            continue;
+        }
+        let _ = behavior_tracker.handle_event(PlaybackEvent::TrackStarted {
+            track_id: track.id,
+            timestamp: chrono::Utc::now(),
+        }).await;
+        say(&format!("▶️ [{}/{}] {} - {}", number + 1, queue.len(), track.display_artist(), track.display_title()));
+        
+        let mut paused = false;
+        loop {
+            tokio::select! {
+                Some(event) = audio_event_rx.recv() => match event {
+                    PlayerEvent::TrackFinished(finished) if finished.id == track.id => {
+                        let _ = behavior_tracker.handle_event(PlaybackEvent::TrackCompleted {
+                            track_id: track.id,
+                            timestamp: chrono::Utc::now(),
+                        }).await;
+                        continue 'queue;
+                    }
+                    PlayerEvent::Error(error) => say(&format!("❌ {}", error)),
+                    _ => {}
+                },
+                Some(input) = input_rx.recv() => match input {
+                    HeadlessInput::TogglePause => {
+                        let position = position_secs(&audio_player);
+                        if paused {
+                            audio_player.resume()?;
+                            let _ = behavior_tracker.handle_event(PlaybackEvent::TrackResumed {
+                                track_id: track.id,
+                                position,
+                                timestamp: chrono::Utc::now(),
+                            }).await;
+                            say("▶️ Resumed");
+                        } else {
+                            audio_player.pause()?;
+                            let _ = behavior_tracker.handle_event(PlaybackEvent::TrackPaused {
+                                track_id: track.id,
+                                position,
+                                timestamp: chrono::Utc::now(),
+                            }).await;
+                            say("⏸️ Paused");
+                        }
+                        paused = !paused;
+                    }
+                    HeadlessInput::Next | HeadlessInput::Quit => {
+                        let _ = behavior_tracker.handle_event(PlaybackEvent::TrackSkipped {
+                            track_id: track.id,
+                            position: position_secs(&audio_player),
+                            reason: if input == HeadlessInput::Next { SkipReason::NextTrack } else { SkipReason::UserSkip },
+                            timestamp: chrono::Utc::now(),
+                        }).await;
+                        if input == HeadlessInput::Quit {
+                            break 'queue;
+                        }
+                        continue 'queue;
+                    }
+                },
+            }
+        }
+    }
+    
+    let _ = behavior_tracker.finalize().await;
+    audio_player.stop()?;
+    say("👋 Done");
     Ok(())
 }
 
+/// Read control keys from the terminal, or whole lines when stdin is a pipe
+fn spawn_headless_input() -> (Option<RawModeGuard>, mpsc::UnboundedReceiver<HeadlessInput>) {
+    use crossterm::event::KeyModifiers;
+    use std::io::IsTerminal;
+    
+    let (input_tx, input_rx) = mpsc::unbounded_channel();
+    
+    if std::io::stdin().is_terminal() && crossterm::terminal::enable_raw_mode().is_ok() {
+        std::thread::spawn(move || {
+            while let Ok(Event::Key(key)) = event::read() {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                let input = match (key.code, key.modifiers) {
+                    (KeyCode::Char(' '), _) => HeadlessInput::TogglePause,
+                    (KeyCode::Char('n'), _) => HeadlessInput::Next,
+                    (KeyCode::Char('q'), _) | (KeyCode::Char('c'), KeyModifiers::CONTROL) => HeadlessInput::Quit,
+                    _ => continue,
+                };
+                if input_tx.send(input).is_err() || input == HeadlessInput::Quit {
+                    break;
+                }
+            }
+        });
+        return (Some(RawModeGuard), input_rx);
+    }
+    
+    // Scripted use: "p"/"pause", "n"/"next", "q"/"quit", one per line
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lines().map_while(|line| line.ok()) {
+            let input = match line.trim() {
+                "" | "p" | "pause" => HeadlessInput::TogglePause,
+                "n" | "next" => HeadlessInput::Next,
+                "q" | "quit" => HeadlessInput::Quit,
+                _ => continue,
+            };
+            if input_tx.send(input).is_err() {
+                break;
+            }
+        }
+    });
+    (None, input_rx)
+}
+
 struct InteractiveApp {
     #[allow(dead_code)] // Used in initialization and throughout app lifecycle
     config: Config,
@@ -192,25 +760,95 @@ struct InteractiveApp {
     // Music library
     tracks: Vec<panpipe::Track>,
     filtered_tracks: Vec<usize>, // indices into tracks
+    scanner: MusicScanner,
+    scan_report: ScanReport,
+    show_scan_report: bool,
+    scan_report_scroll: u16,
+    eq_band: usize, // Settings tab: equalizer band the gain keys adjust
+    show_device_picker: bool,
+    pending_g: bool, // first half of a vim-style `gg`
+    list_page_rows: u16, // rows of list visible in the content area at the last render
+    type_jump: String,          // Library tab: prefix typed so far
+    type_jump_at: Instant,      // when the last character was typed
+    sort_field: SortField,
+    sort_descending: bool,
+    play_counts: HashMap<uuid::Uuid, u64>, // loaded from behavior data when sorting by plays
+    tag_filter: Option<TagFilter>,
+    behavior_tags: HashMap<uuid::Uuid, Vec<String>>, // loaded from behavior data when a tag filter is picked
+    sleep_timer: Option<SleepTimer>,
+    stop_after_current: bool, // halt instead of autoplaying when the current track ends
+    ab_loop: Option<AbLoop>, // cleared whenever a different track starts
+    progress_bar_area: Option<Rect>, // where the last frame drew the gauge, `None` while the length is unknown
+    muted_volume: Option<f32>, // volume to restore while muted
+    pending_resume: Option<(usize, Duration)>, // (track index, position) from the last quit, until played or declined
+    output_devices: Vec<String>, // snapshot taken when the picker opens
+    device_picker_state: ListState, // row 0 is the system default
+    bookmarks: Vec<Bookmark>, // the current track's, earliest first
+    show_bookmark_picker: bool,
+    history: Option<Vec<RecentPlay>>, // 'H' overlay, newest first
+    history_state: ListState,
+    shuffle_excluded: HashSet<uuid::Uuid>, // tracks shuffle never picks, loaded with the listening stats
+    show_excluded_tracks: bool,            // Settings tab: 'E' overlay to let excluded tracks back in
+    excluded_tracks_state: ListState,
+    pending_confirm: Option<ConfirmAction>, // y/n overlay; blocks every other key while open
+    behavior_detail: Option<BehaviorDetail>, // 'i' overlay for the selected Library track
+    more_like_this: Option<MoreLikeThis>,    // 'L' overlay for the selected Library track
+    more_like_this_state: ListState,
+    duplicate_review: Option<DuplicateReview>, // Settings tab: Find Duplicates overlay
+    rename_preview: Option<RenamePreview>,     // Metadata tab: old → new names before renaming files
+    background_task: Option<BackgroundTask>,   // bulk apply or playlist export in progress, one at a time
+    marked_tracks: HashSet<usize>, // Library tab: track indices picked for a bulk playlist add
+    library_view: GroupBy,           // Library tab: flat list or an artist/album tree
+    expanded_groups: HashSet<String>, // ids of the open groups in the tree views
+    library_rows: Vec<TreeRow>,      // visible tree rows, rebuilt with `filtered_tracks`
+    library_tree_state: ListState,
+    bookmark_picker_state: ListState,
+    listening_stats: ListeningStats,
+    library_stats: LibraryStats,
+    stats_scroll: u16,
+    _library_watcher: Option<LibraryWatcher>, // kept alive while the app runs
+    library_change_rx: Option<mpsc::UnboundedReceiver<LibraryChange>>,
+    #[cfg(feature = "mpris")]
+    mpris: Option<MprisBridge>, // None when the session bus isn't reachable
+    control: Option<(ControlServer, mpsc::UnboundedReceiver<ControlCommand>)>, // None unless [control] has a socket path
+    #[cfg(feature = "scrobble")]
+    scrobbler: Option<Scrobbler>, // None unless [lastfm] is enabled and authorized
+    #[cfg(feature = "musicbrainz")]
+    musicbrainz: Option<Arc<MusicBrainzClient>>, // None if the HTTP client couldn't be set up
+    #[cfg(feature = "musicbrainz")]
+    metadata_matches: Option<MetadataMatches>, // Metadata tab: MusicBrainz candidates for a track
+    #[cfg(feature = "lyrics")]
+    lyrics_client: Option<Arc<LyricsClient>>, // None if the HTTP client couldn't be set up
+    #[cfg(feature = "lyrics")]
+    lyrics: Option<(PathBuf, LyricsState)>, // the pane's track, fetched while the pane is open
+    #[cfg(feature = "silence")]
+    silence: Option<SilenceSkip>, // for the playing track, reset by every play
+    spoken_word: Option<SpokenWord>, // the playing file, when it's under one of `library.audiobook_directories`
+    show_chapter_list: bool,
+    chapter_list_state: ListState,
     
     // UI state
     list_state: ListState,
     current_track_index: Option<usize>,
+    gapless_next: Option<(usize, usize)>, // (position in the playback context, track index) queued on the player
+    playback_context: PlaybackContext,
     should_quit: bool,
+    // Edits held in memory until Save All: retagged tracks by path, playlists whose save failed by id
+    dirty_tracks: HashSet<PathBuf>,
+    dirty_playlists: HashSet<String>,
     current_tab: AppTab,
+    keymap: Keymap, // from the [keybindings] config section
     
     // Playback state
-    volume: f32,
     is_playing: bool,
     is_shuffled: bool,
     repeat_mode: RepeatMode,
+    theme: Theme,
     
     // Time tracking
     current_position: Duration,
     total_duration: Option<Duration>,
-    last_position_update: Instant,
-    
-    // Visualizer removed for performance optimization
+    position_reports_stale: bool, // play_track ran, reports still queued from the old track don't count
     
     // Metadata editor state
     metadata_parser: MetadataParser,
@@ -226,7 +864,12 @@ struct InteractiveApp {
     audio_event_rx: mpsc::UnboundedReceiver<PlayerEvent>,
     
     // Status messages
-    status_message: Option<(String, Instant)>,
+    status_message: Option<(String, NotificationLevel, Instant)>,
+    notifications: NotificationCenter,
+    error_log: ErrorLog,
+    show_error_log: bool,
+    error_log_state: ListState,
+    needs_redraw: bool, // something on screen changed since the last frame
     
     // Help overlay
     show_help: bool,
@@ -234,13 +877,15 @@ struct InteractiveApp {
     // Search functionality
     search_mode: bool,
     search_query: String,
-    fuzzy_matcher: ClangdMatcher,
+    search_index: SearchIndex,
+    search_scope: SearchScope,
+    search_pending_at: Option<Instant>, // last keystroke not yet searched for
+    playlist_search_matches: Option<HashSet<String>>, // Playlists tab: ids the search kept, None = all
+    playlist_search_tracks: bool, // Playlists tab: also match playlists by the tracks they hold
     
     // Playlist functionality
     playlist_manager: PlaylistManager,
     playlist_list_state: ListState,
-    current_playlist_id: Option<String>,
-    playlist_tracks: Vec<usize>, // indices into tracks for current playlist
     playlist_creation_mode: bool,
     playlist_name_input: String,
     expanded_playlists: std::collections::HashSet<String>, // Track which playlists are expanded
@@ -249,7 +894,7 @@ struct InteractiveApp {
     // Playlist selector overlay (for Library tab 'a' key)
     show_playlist_selector: bool,
     playlist_selector_state: ListState,
-    selected_track_for_playlist: Option<usize>, // Track index to add to selected playlist
+    tracks_for_playlist: Vec<usize>, // Track indices to add to the chosen playlist
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -258,8 +903,102 @@ enum AppTab {
     Playlists,
     MetadataEditor,
     Settings,
+    Stats,
+}
+
+impl AppTab {
+    /// Which tab-specific keybindings apply
+    fn key_scope(&self) -> KeyScope {
+        match self {
+            AppTab::Library => KeyScope::Library,
+            AppTab::Playlists => KeyScope::Playlists,
+            AppTab::MetadataEditor => KeyScope::Metadata,
+            AppTab::Settings => KeyScope::Settings,
+            AppTab::Stats => KeyScope::Stats,
+        }
+    }
+    
+    /// Stable name used in the session file
+    fn session_key(&self) -> &'static str {
+        match self {
+            AppTab::Library => "library",
+            AppTab::Playlists => "playlists",
+            AppTab::MetadataEditor => "metadata_editor",
+            AppTab::Settings => "settings",
+            AppTab::Stats => "stats",
+        }
+    }
+    
+    fn from_session_key(key: &str) -> Option<Self> {
+        match key {
+            "library" => Some(AppTab::Library),
+            "playlists" => Some(AppTab::Playlists),
+            "metadata_editor" => Some(AppTab::MetadataEditor),
+            "settings" => Some(AppTab::Settings),
+            "stats" => Some(AppTab::Stats),
+            _ => None,
+        }
+    }
 }
 
+/// The sleep timer fades playback out over this final stretch
+const SLEEP_FADE: Duration = Duration::from_secs(30);
+
+/// How often a spoken-word file's position is written down while it plays
+const RESUME_SAVE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// A spoken-word file stopped this close to its end counts as finished and starts over next time
+const RESUME_END_MARGIN: Duration = Duration::from_secs(15);
+
+/// "Previous chapter" restarts the current one until this far into it
+const CHAPTER_RESTART_WINDOW: Duration = Duration::from_secs(3);
+
+/// Library type-to-jump forgets what was typed after this long without a key
+const TYPE_JUMP_TIMEOUT: Duration = Duration::from_millis(800);
+
+/// Entries the history overlay lists, after folding repeats
+const HISTORY_LENGTH: usize = 100;
+
+/// Warnings and errors the error log keeps, after folding repeats
+const ERROR_LOG_LENGTH: usize = 200;
+
+/// Suggestions the More like this overlay lists
+const SIMILAR_COUNT: usize = 20;
+
+/// Files a bulk apply parses between progress reports and cancel checks
+const BULK_APPLY_BATCH: usize = 100;
+
+/// Floor for `ui.min_width`/`ui.min_height`: header, a few rows of content and the one-line controls
+const MIN_TERMINAL_WIDTH: u16 = 20;
+const MIN_TERMINAL_HEIGHT: u16 = 8;
+
+/// Below this many rows the controls and status bar shrink to a line each and the visualizer goes
+const COMPACT_HEIGHT: u16 = 24;
+
+/// Overlays never shrink below this, so their borders and hint rows stay drawable
+const MIN_POPUP_WIDTH: u16 = 24;
+const MIN_POPUP_HEIGHT: u16 = 5;
+
+/// Search waits this long after the last keystroke before matching the library
+const SEARCH_DEBOUNCE: Duration = Duration::from_millis(150);
+
+/// How long a status bar message stays up before it goes back to "Ready"
+const STATUS_DURATION: Duration = Duration::from_secs(3);
+
+/// Redraw pace while the progress bar moves (~4 FPS); keys still redraw right away
+const PLAYING_FRAME: Duration = Duration::from_millis(250);
+/// The spectrum looks choppy any slower, so playing with it on screen redraws at ~10 FPS
+const VISUALIZER_FRAME: Duration = Duration::from_millis(100);
+/// Paused or stopped with nothing open: wake this rarely for the watcher, sockets and fetches
+const IDLE_POLL: Duration = Duration::from_millis(500);
+
+/// Rows of the visualizer pane, borders included
+const VISUALIZER_HEIGHT: u16 = 6;
+
+/// How far one press of < or > moves the playback speed
+const SPEED_STEP: f32 = 0.25;
+
+
 #[derive(Debug, Clone, PartialEq)]
 enum EditMode {
     None,
@@ -267,6 +1006,18 @@ enum EditMode {
     Artist,
 }
 
+/// Modes stacked over the tab being browsed, in the order they're left by Esc (last first)
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ModeLayer {
+    Marks,     // tracks marked in the library
+    TagFilter, // the library narrowed to one genre, artist or year
+    Edit,      // typing a new title or artist
+    Search,
+    Help,
+    Popup,   // pickers, reports and the other overlays, which take their own keys
+    Confirm, // a yes/no question
+}
+
 #[derive(Debug, Clone, PartialEq)]
 enum RepeatMode {
     Off,
@@ -274,60 +1025,467 @@ enum RepeatMode {
     One,
 }
 
-// Visualizer enum removed for performance optimization
-
-impl InteractiveApp {
-    async fn new(config: Config, tracks: Vec<panpipe::Track>) -> Result<Self> {
-        let terminal = TerminalManager::new()?;
-        let mut audio_player = AudioPlayer::new(config.clone().into())?;
-        
-        // Initialize behavior database and tracker
-        let behavior_db = BehaviorDatabase::new(&config.database_path)?;
-        let behavior_tracker = BehaviorTracker::new(
-            behavior_db,
-            config.behavior.min_play_time_for_tracking,
-        );
-        
-        // Create event channel (revert to unbounded for stability)
-        let (event_tx, event_rx) = mpsc::unbounded_channel();
-        
-        // Create audio event channel for duration learning
-        let (audio_event_tx, audio_event_rx) = mpsc::unbounded_channel();
-        audio_player.set_event_sender(audio_event_tx);
-        
-        // Initialize filtered tracks (show all initially)
-        let filtered_tracks: Vec<usize> = (0..tracks.len()).collect();
-        
-        let mut list_state = ListState::default();
-        if !filtered_tracks.is_empty() {
-            list_state.select(Some(0));
+impl RepeatMode {
+    fn session_key(&self) -> &'static str {
+        match self {
+            RepeatMode::Off => "off",
+            RepeatMode::All => "all",
+            RepeatMode::One => "one",
         }
-        
-        let mut metadata_list_state = ListState::default();
-        if !tracks.is_empty() {
-            metadata_list_state.select(Some(0));
+    }
+    
+    fn from_session_key(key: &str) -> Option<Self> {
+        match key {
+            "off" => Some(RepeatMode::Off),
+            "all" => Some(RepeatMode::All),
+            "one" => Some(RepeatMode::One),
+            _ => None,
         }
-        
-        Ok(Self {
-            config,
-            terminal,
-            audio_player,
-            behavior_tracker,
-            tracks,
-            filtered_tracks,
+    }
+}
+
+impl From<PlaylistRepeat> for RepeatMode {
+    fn from(repeat: PlaylistRepeat) -> Self {
+        match repeat {
+            PlaylistRepeat::Off => RepeatMode::Off,
+            PlaylistRepeat::All => RepeatMode::All,
+            PlaylistRepeat::One => RepeatMode::One,
+        }
+    }
+}
+
+/// Library ordering, applied to `filtered_tracks` on top of scan order or search relevance
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SortField {
+    Unsorted, // scan order, or best match first while searching
+    Artist,
+    Title,
+    Album,
+    Duration,
+    Plays,
+    Added, // newest file first, limited to `library.recently_added_days`
+}
+
+impl SortField {
+    fn next(self) -> Self {
+        match self {
+            SortField::Unsorted => SortField::Artist,
+            SortField::Artist => SortField::Title,
+            SortField::Title => SortField::Album,
+            SortField::Album => SortField::Duration,
+            SortField::Duration => SortField::Plays,
+            SortField::Plays => SortField::Added,
+            SortField::Added => SortField::Unsorted,
+        }
+    }
+    
+    fn label(&self) -> &'static str {
+        match self {
+            SortField::Unsorted => "unsorted",
+            SortField::Artist => "artist",
+            SortField::Title => "title",
+            SortField::Album => "album",
+            SortField::Duration => "duration",
+            SortField::Plays => "plays",
+            SortField::Added => "recently added",
+        }
+    }
+}
+
+/// Library filter on behavior data, applied on top of the search
+#[derive(Debug, Clone, PartialEq)]
+enum TagFilter {
+    Tag(String), // tracks whose behavior carries this tag, e.g. "favorite"
+    NeverPlayed, // tracks with no behavior row at all
+}
+
+impl TagFilter {
+    fn matches(&self, tags: Option<&Vec<String>>) -> bool {
+        match self {
+            TagFilter::Tag(tag) => tags.is_some_and(|tags| tags.contains(tag)),
+            TagFilter::NeverPlayed => tags.is_none(),
+        }
+    }
+    
+    fn label(&self) -> String {
+        match self {
+            TagFilter::Tag(tag) if tag == "favorite" => "favorites".to_string(),
+            TagFilter::Tag(tag) => tag.replace('_', " "),
+            TagFilter::NeverPlayed => "never played".to_string(),
+        }
+    }
+}
+
+/// What next/previous and autoplay step through, fixed when a track is started from a list
+///
+/// Orders are snapshots, so searching or re-sorting the library doesn't pull the rug out from
+/// under whatever is playing.
+#[derive(Debug, Clone, Default, PartialEq)]
+enum PlaybackContext {
+    Library { filtered: Vec<usize> },           // the filtered, sorted library as it was when play started
+    Playlist { id: String, order: Vec<usize> }, // the playlist's tracks that are in the library, in order
+    Album { order: Vec<usize> },                // one album by disc and track number
+    Similar { order: Vec<usize> },              // More like this suggestions, after the track they came from
+    #[default]
+    Queue, // a lone track, e.g. resumed from the last session - nothing follows it
+}
+
+impl PlaybackContext {
+    /// Track indices in play order
+    fn order(&self) -> &[usize] {
+        match self {
+            PlaybackContext::Library { filtered } => filtered,
+            PlaybackContext::Playlist { order, .. } | PlaybackContext::Album { order } | PlaybackContext::Similar { order } => order,
+            PlaybackContext::Queue => &[],
+        }
+    }
+    
+    fn playlist_id(&self) -> Option<&str> {
+        match self {
+            PlaybackContext::Playlist { id, .. } => Some(id),
+            _ => None,
+        }
+    }
+    
+    /// The entry `offset` places away from `track_idx`, wrapping at either end, as (position, track index)
+    fn step(&self, track_idx: usize, offset: isize) -> Option<(usize, usize)> {
+        let order = self.order();
+        let current = order.iter().position(|&i| i == track_idx)?;
+        let next = (current as isize + offset).rem_euclid(order.len() as isize) as usize;
+        Some((next, order[next]))
+    }
+}
+
+/// Bedtime stop, cycled with Shift+T
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SleepTimer {
+    After { minutes: u64, deadline: Instant },
+    EndOfTrack, // let the current track finish, then stop
+}
+
+/// Library tab overlay: what the behavior model knows about one track
+struct BehaviorDetail {
+    title: String,
+    behavior: Option<TrackBehavior>, // None until the track has a recorded play
+    weight: Option<f64>,             // as WeightCalculator sees it right now
+}
+
+/// Library tab overlay: tracks like the selected one, best match first
+struct MoreLikeThis {
+    title: String, // the track they're like
+    similar: Vec<SimilarTrack>,
+}
+
+/// Find Duplicates: one group at a time, with the copy that stays picked out
+struct DuplicateReview {
+    groups: Vec<DuplicateGroup>,
+    selected: usize, // group on screen
+    keep: usize,     // index into that group's paths
+    dry_run: bool,   // hide/delete only report what they would do
+}
+
+/// Rename files: the whole plan on screen before anything is renamed
+struct RenamePreview {
+    plan: RenamePlan,
+    scroll: u16,
+    dry_run: bool, // renaming only logs the plan
+}
+
+/// What the lyrics pane has for the track it's showing
+#[cfg(feature = "lyrics")]
+enum LyricsState {
+    Loading,
+    Found(Lyrics),
+    NotFound, // also when the lookup failed, which only goes to the log
+}
+
+/// The playing track's silent intro and outro, while `audio.skip_silence` is on
+#[cfg(feature = "silence")]
+struct SilenceSkip {
+    content_hash: u64,
+    bounds: Option<SilenceBounds>, // `None` while the track is being analysed
+    intro_skipped: bool,           // once per play, so seeking back into the intro sticks
+}
+
+/// Spoken word: the playing file's chapters, and when its place was last written down
+struct SpokenWord {
+    content_hash: u64, // positions are kept per file, by content
+    chapters: Vec<Chapter>,
+    saved_at: Instant,
+}
+
+/// A long job running off the UI loop, shown as a progress overlay until it finishes or is cancelled
+struct BackgroundTask {
+    label: &'static str,
+    done: usize,
+    total: usize,
+    results: usize,                      // what the job has produced so far, e.g. tracks retagged
+    results_label: Option<&'static str>, // shown as "<label> results / total" when the count means something
+    cancel: Arc<AtomicBool>, // the worker checks it between items
+}
+
+/// What a background task reports once it stops, whether it ran out of work or was cancelled
+#[derive(Debug, Clone)]
+enum TaskResult {
+    BulkApply { total: usize },
+    PlaylistsExported { dir: PathBuf, report: Result<PlaylistExportReport, String> },
+}
+
+/// MusicBrainz lookup: candidate tags for one track, applied on Enter
+#[cfg(feature = "musicbrainz")]
+struct MetadataMatches {
+    path: PathBuf, // the track looked up; the selection may have moved on since
+    candidates: Vec<MetadataCandidate>,
+    selected: usize,
+}
+
+/// A-B repeat: once B is set, reaching it seeks back to A
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct AbLoop {
+    a: Duration,
+    b: Option<Duration>,
+}
+
+/// Destructive action held back until the user answers the y/n overlay
+#[derive(Debug, Clone, PartialEq)]
+enum ConfirmAction {
+    DeletePlaylist { id: String, name: String },
+    ResetMetadata { path: PathBuf, title: String }, // by path, the watcher can shift indices while it's open
+    ClearMetadata { path: PathBuf, title: String },
+    ResetTrackBehavior { track_id: uuid::Uuid, title: String },
+    ResetAllBehavior,
+    DeleteDuplicates(DuplicateResolution),
+    RenameFiles(RenamePlan),
+    QuitUnsaved { count: usize },
+}
+
+impl ConfirmAction {
+    fn prompt(&self) -> String {
+        match self {
+            ConfirmAction::DeletePlaylist { name, .. } => format!("Delete playlist '{}'?", name),
+            ConfirmAction::ResetMetadata { title, .. } => format!("Reset '{}' to its original tags?", title),
+            ConfirmAction::ClearMetadata { title, .. } => format!("Clear the title and artist of '{}'?", title),
+            ConfirmAction::ResetTrackBehavior { title, .. } => format!("Forget plays, skips and tags for '{}'?", title),
+            ConfirmAction::ResetAllBehavior => "Forget listening behavior for every track?".to_string(),
+            ConfirmAction::DeleteDuplicates(resolution) => format!("{} from disk?", resolution.describe()),
+            ConfirmAction::RenameFiles(plan) => format!("{}?", plan.describe()),
+            ConfirmAction::QuitUnsaved { count } => format!("{} unsaved change(s) - save them before quitting?", count),
+        }
+    }
+    
+    fn hint(&self) -> &'static str {
+        match self {
+            ConfirmAction::QuitUnsaved { .. } => "y/Enter: Save and quit | n: Quit anyway | Esc: Stay",
+            _ => "y/Enter: Confirm | n/Esc: Cancel",
+        }
+    }
+}
+
+impl InteractiveApp {
+    async fn new(config: Config, tracks: Vec<panpipe::Track>, scan_report: ScanReport) -> Result<Self> {
+        let terminal = TerminalManager::new()?;
+        let (mut audio_player, device_warning) = open_audio_player(&config)?;
+        
+        // Initialize behavior database and tracker
+        let behavior_db = BehaviorDatabase::new(&config.database_path)?;
+        let behavior_tracker = BehaviorTracker::new(
+            behavior_db,
+            config.behavior.min_play_time_for_tracking,
+        )
+        .with_skip_threshold(config.behavior.skip_threshold_seconds)
+        .with_decay_days(config.behavior.weight_decay_days);
+        
+        // Time decay only runs when a track is played, so catch up everything else once per start
+        match behavior_tracker.recalculate_weights(&ShuffleWeighting::new(config.behavior.weight_decay_days)).await {
+            Ok(count) => debug!("Recalculated shuffle weights for {} tracks", count),
+            Err(e) => warn!("Failed to recalculate shuffle weights: {}", e),
+        }
+        
+        // Create event channel (revert to unbounded for stability)
+        let (event_tx, event_rx) = mpsc::unbounded_channel();
+        spawn_signal_listener(event_tx.clone());
+        
+        // Create audio event channel for duration learning
+        let (audio_event_tx, audio_event_rx) = mpsc::unbounded_channel();
+        audio_player.set_event_sender(audio_event_tx);
+        
+        // Initialize filtered tracks (show all initially)
+        let filtered_tracks: Vec<usize> = (0..tracks.len()).collect();
+        let search_index = SearchIndex::build(&tracks);
+        
+        let mut list_state = ListState::default();
+        if !filtered_tracks.is_empty() {
+            list_state.select(Some(0));
+        }
+        
+        let mut metadata_list_state = ListState::default();
+        if !tracks.is_empty() {
+            metadata_list_state.select(Some(0));
+        }
+        
+        let theme = Theme::from_name(&config.ui.theme);
+        let keymap = config.keymap()?;
+        let playlist_manager = PlaylistManager::new(config.playlists_dir()).map_err(|e| anyhow::anyhow!("{}", e))?;
+        let scanner = config.library.build_scanner()?;
+        
+        // User naming conventions go ahead of the built-in filename patterns
+        let mut metadata_parser = MetadataParser::new();
+        let metadata_config_errors = metadata_parser.apply_config(&config.metadata);
+        for error in &metadata_config_errors {
+            warn!("Ignoring metadata config entry: {}", error);
+        }
+        
+        // Optional live library updates - off unless enabled in config
+        let (library_watcher, library_change_rx) = if config.library.watch_for_changes {
+            match LibraryWatcher::start(&config.music_directories, Duration::from_millis(config.library.watch_debounce_ms)) {
+                Ok((watcher, rx)) => (Some(watcher), Some(rx)),
+                Err(e) => {
+                    warn!("Could not start library watcher: {}", e);
+                    (None, None)
+                }
+            }
+        } else {
+            (None, None)
+        };
+        let notification_duration_ms = config.ui.notification_duration_ms;
+        
+        #[cfg(feature = "mpris")]
+        let mpris = match MprisHandle::start().await {
+            Ok((handle, commands)) => Some(MprisBridge::new(handle, commands)),
+            Err(e) => {
+                warn!("MPRIS unavailable, media keys won't work: {}", e);
+                None
+            }
+        };
+        
+        let control = config.control.socket_path.as_deref().and_then(|path| match ControlServer::start(path) {
+            Ok(control) => {
+                info!("Control socket listening on {}", path.display());
+                Some(control)
+            }
+            Err(e) => {
+                warn!("Control socket unavailable: {}", e);
+                None
+            }
+        });
+        
+        #[cfg(feature = "scrobble")]
+        let scrobbler = match Scrobbler::start(&config.lastfm, &config.scrobble_queue_path()) {
+            Ok(scrobbler) => scrobbler,
+            Err(e) => {
+                warn!("Not scrobbling: {}", e);
+                None
+            }
+        };
+        
+        #[cfg(feature = "musicbrainz")]
+        let musicbrainz = match MusicBrainzClient::new(&config.musicbrainz, &config.musicbrainz_cache_path()) {
+            Ok(client) => Some(Arc::new(client)),
+            Err(e) => {
+                warn!("MusicBrainz lookups unavailable: {}", e);
+                None
+            }
+        };
+        
+        #[cfg(feature = "lyrics")]
+        let lyrics_client = match LyricsClient::new(&config.lyrics_cache_dir()) {
+            Ok(client) => Some(Arc::new(client)),
+            Err(e) => {
+                warn!("Lyrics unavailable: {}", e);
+                None
+            }
+        };
+        
+        let mut app = Self {
+            config,
+            terminal,
+            audio_player,
+            behavior_tracker,
+            tracks,
+            filtered_tracks,
+            scanner,
+            scan_report,
+            show_scan_report: false,
+            eq_band: 0,
+            show_device_picker: false,
+            pending_g: false,
+            list_page_rows: 10,
+            type_jump: String::new(),
+            type_jump_at: Instant::now(),
+            sort_field: SortField::Unsorted,
+            sort_descending: false,
+            play_counts: HashMap::new(),
+            tag_filter: None,
+            behavior_tags: HashMap::new(),
+            sleep_timer: None,
+            stop_after_current: false,
+            pending_resume: None,
+            ab_loop: None,
+            progress_bar_area: None,
+            muted_volume: None,
+            output_devices: Vec::new(),
+            device_picker_state: ListState::default(),
+            bookmarks: Vec::new(),
+            show_bookmark_picker: false,
+            history: None,
+            history_state: ListState::default(),
+            shuffle_excluded: HashSet::new(),
+            show_excluded_tracks: false,
+            excluded_tracks_state: ListState::default(),
+            pending_confirm: None,
+            behavior_detail: None,
+            more_like_this: None,
+            more_like_this_state: ListState::default(),
+            background_task: None,
+            duplicate_review: None,
+            rename_preview: None,
+            marked_tracks: HashSet::new(),
+            library_view: GroupBy::None,
+            expanded_groups: HashSet::new(),
+            library_rows: Vec::new(),
+            library_tree_state: ListState::default(),
+            bookmark_picker_state: ListState::default(),
+            scan_report_scroll: 0,
+            listening_stats: ListeningStats::default(),
+            library_stats: LibraryStats::default(),
+            stats_scroll: 0,
+            _library_watcher: library_watcher,
+            library_change_rx,
+            #[cfg(feature = "mpris")]
+            mpris,
+            control,
+            #[cfg(feature = "scrobble")]
+            scrobbler,
+            #[cfg(feature = "musicbrainz")]
+            musicbrainz,
+            #[cfg(feature = "musicbrainz")]
+            metadata_matches: None,
+            #[cfg(feature = "lyrics")]
+            lyrics_client,
+            #[cfg(feature = "lyrics")]
+            lyrics: None,
+            #[cfg(feature = "silence")]
+            silence: None,
+            spoken_word: None,
+            show_chapter_list: false,
+            chapter_list_state: ListState::default(),
             list_state,
             current_track_index: None,
+            gapless_next: None,
+            playback_context: PlaybackContext::Queue,
             should_quit: false,
+            dirty_tracks: HashSet::new(),
+            dirty_playlists: HashSet::new(),
             current_tab: AppTab::Library,
-            volume: 0.7,
+            keymap,
             is_playing: false,
             is_shuffled: false,
             repeat_mode: RepeatMode::Off,
+            theme,
             current_position: Duration::from_secs(0),
             total_duration: None,
-            last_position_update: Instant::now(),
-            // Visualizer initialization removed
-            metadata_parser: MetadataParser::new(),
+            position_reports_stale: false,
+            metadata_parser,
             metadata_list_state,
             editing_track_index: None,
             edit_title: String::new(),
@@ -337,16 +1495,23 @@ impl InteractiveApp {
             _event_tx: event_tx,
             audio_event_rx,
             status_message: None,
+            notifications: NotificationCenter::new(Duration::from_millis(notification_duration_ms)),
+            error_log: ErrorLog::new(ERROR_LOG_LENGTH),
+            show_error_log: false,
+            error_log_state: ListState::default(),
+            needs_redraw: true,
             show_help: false,
             search_mode: false,
             search_query: String::new(),
-            fuzzy_matcher: ClangdMatcher::default(),
+            search_index,
+            search_scope: SearchScope::default(),
+            search_pending_at: None,
+            playlist_search_matches: None,
+            playlist_search_tracks: false,
             
             // Initialize playlist functionality
-            playlist_manager: PlaylistManager::new("playlists".into()).map_err(|e| anyhow::anyhow!("{}", e))?,
+            playlist_manager,
             playlist_list_state: ListState::default(),
-            current_playlist_id: None,
-            playlist_tracks: Vec::new(),
             playlist_creation_mode: false,
             playlist_name_input: String::new(),
             expanded_playlists: std::collections::HashSet::new(),
@@ -355,1109 +1520,3526 @@ impl InteractiveApp {
             // Initialize playlist selector overlay
             show_playlist_selector: false,
             playlist_selector_state: ListState::default(),
-            selected_track_for_playlist: None,
-        })
+            tracks_for_playlist: Vec::new(),
+        };
+        
+        // Library is scanned by now, so saved paths can be resolved to indices
+        app.restore_session()?;
+        app.refresh_stats().await;
+        
+        for error in metadata_config_errors {
+            app.push_notification(NotificationLevel::Warning, &format!("⚠️ {}", error));
+        }
+        if let Some(warning) = device_warning {
+            app.push_notification(NotificationLevel::Warning, &format!("⚠️ {}", warning));
+        }
+        #[cfg(not(feature = "silence"))]
+        if app.config.audio.skip_silence {
+            app.push_notification(NotificationLevel::Warning, "⚠️ audio.skip_silence needs a build with the silence feature");
+        }
+        
+        app.start_on_launch().await?;
+        if let Some((track_idx, position)) = app.pending_resume {
+            let secs = position.as_secs();
+            let message = format!(
+                "⏯️ Press Shift+R to resume {} at {}:{:02}",
+                app.format_track_title(&app.tracks[track_idx]),
+                secs / 60,
+                secs % 60
+            );
+            app.push_notification(NotificationLevel::Info, &message);
+        }
+        
+        Ok(app)
     }
     
-    async fn run(&mut self) -> Result<()> {
-        // SYNCHRONOUS event handling - no separate async tasks for terminal I/O
-        // This prevents race conditions that cause "Error: end of stream"
+    /// Apply the last saved session (tab, selection, shuffle/repeat, playlist)
+    fn restore_session(&mut self) -> Result<()> {
+        let session = SessionState::load().unwrap_or_else(|e| {
+            warn!("Could not read session file, starting fresh: {}", e);
+            SessionState::default()
+        });
         
-        let _last_update = Instant::now();
+        self.is_shuffled = session.shuffle;
+        self.repeat_mode = RepeatMode::from_session_key(&session.repeat_mode).unwrap_or(RepeatMode::Off);
+        self.current_tab = AppTab::from_session_key(&session.last_tab).unwrap_or(AppTab::Library);
         
-        while !self.should_quit {
-            // Handle input events with balanced polling for responsive UI
-            if event::poll(Duration::from_millis(50)).unwrap_or(false) {
-                if let Ok(event) = event::read() {
-                    if let Event::Key(key) = event {
-                        if key.kind == KeyEventKind::Press {
-                            let app_event = if self.search_mode {
-                                Self::key_to_search_event(key)
-                            } else if self.playlist_creation_mode {
-                                Self::key_to_playlist_event(key)
-                            } else if self.show_playlist_selector {
-                                Self::key_to_playlist_selector_event(key)
-                            } else {
-                                self.key_to_app_event_basic(key)
-                            };
-                            
-                            if let Some(app_event) = app_event {
-                                self.handle_event(app_event).await?;
-                            }
-                        }
-                    }
+        // Missing tracks just fall back to the default selection
+        if let Some(path) = &session.selected_track_path {
+            if let Some(track_idx) = self.tracks.iter().position(|t| &t.file_path == path) {
+                if let Some(pos) = self.filtered_tracks.iter().position(|&i| i == track_idx) {
+                    self.list_state.select(Some(pos));
                 }
+                self.metadata_list_state.select(Some(track_idx));
+            } else {
+                debug!("Saved track {:?} no longer in library", path);
             }
+        }
+        
+        // Resuming into the playlist that was playing carries on through it
+        if let Some(playlist) = session.current_playlist_id.and_then(|id| self.playlist_manager.get_playlist(&id)) {
+            self.playback_context = PlaybackContext::Playlist {
+                id: playlist.id.clone(),
+                order: self.playlist_order(playlist, None),
+            };
+        }
+        
+        // Offer to resume only if the track was properly underway and isn't over; a missing file is skipped quietly
+        if let Some(resume) = &session.resume {
+            let position = Duration::from_millis(resume.position_ms);
+            let min_play_time = Duration::from_secs(self.config.behavior.min_play_time_for_tracking);
+            let track_idx = self.tracks.iter().position(|t| t.file_path == resume.track_path && t.file_path.exists());
             
-            // Handle audio events (duration learning, track finished, etc.)
-            while let Ok(audio_event) = self.audio_event_rx.try_recv() {
-                self.handle_audio_event(audio_event).await?;
-            }
-            
-            // Handle internal events (including Tick events for time tracking)
-            while let Ok(internal_event) = self.event_rx.try_recv() {
-                self.handle_event(internal_event).await?;
+            if let Some(track_idx) = track_idx {
+                let before_end = self.tracks[track_idx].duration.is_none_or(|total| position < total);
+                if position > min_play_time && before_end {
+                    self.pending_resume = Some((track_idx, position));
+                }
             }
-            
-            // Generate a Tick event for time tracking updates
-            let _ = self._event_tx.send(InteractiveEvent::Tick);
-            
-            // Render UI
-            self.render()?;
-            
-            // Balanced delay for smooth UI with good audio performance
-            sleep(Duration::from_millis(100)).await; // ~10 FPS (balanced UI/audio)
         }
         
+        debug!("Restored session: tab={:?}", self.current_tab);
         Ok(())
     }
     
-    fn key_to_search_event(key: KeyEvent) -> Option<InteractiveEvent> {
-        use crossterm::event::KeyModifiers;
+    /// Startup playback as `ui.auto_resume`, `ui.continue_last_playlist` and `ui.autoplay_on_start` ask
+    ///
+    /// A resumable track goes first, so together they pick up exactly where the last session quit.
+    async fn start_on_launch(&mut self) -> Result<()> {
+        let ui = self.config.ui.clone();
+        let playlist_id = self.playback_context.playlist_id().map(str::to_string);
+        let resume_in_playlist = playlist_id.is_some()
+            && self.pending_resume.is_some_and(|(track_idx, _)| self.playback_context.order().contains(&track_idx));
         
-        match (key.code, key.modifiers) {
-            // Exit search mode
-            (KeyCode::Esc, _) => Some(InteractiveEvent::ExitSearch),
-            (KeyCode::Enter, _) => Some(InteractiveEvent::ExitSearch),
-            
-            // Search input handling
-            (KeyCode::Backspace, _) => Some(InteractiveEvent::SearchBackspace),
-            (KeyCode::Char(c), KeyModifiers::NONE) if !c.is_control() => Some(InteractiveEvent::SearchInput(c)),
-            
-            // Allow navigation in search results
-            (KeyCode::Up, _) => Some(InteractiveEvent::Up),
-            (KeyCode::Down, _) => Some(InteractiveEvent::Down),
-            
-            // Global quit still works
-            (KeyCode::Char('q'), KeyModifiers::NONE) => Some(InteractiveEvent::Quit),
-            (KeyCode::Char('c'), KeyModifiers::CONTROL) => Some(InteractiveEvent::Quit),
-            
-            _ => None,
+        if self.pending_resume.is_some() && (ui.auto_resume || ui.autoplay_on_start || (ui.continue_last_playlist && resume_in_playlist)) {
+            return self.resume_last_track().await;
         }
+        if let Some(playlist_id) = playlist_id.filter(|_| ui.continue_last_playlist) {
+            return self.play_playlist(&playlist_id, 0).await;
+        }
+        if ui.autoplay_on_start {
+            if let Some(&track_idx) = self.filtered_tracks.first() {
+                self.playback_context = PlaybackContext::Library { filtered: self.filtered_tracks.clone() };
+                self.play_track(track_idx).await?;
+            }
+        }
+        Ok(())
     }
     
-    fn key_to_playlist_event(key: KeyEvent) -> Option<InteractiveEvent> {
-        use crossterm::event::KeyModifiers;
+    /// Current track and position, for picking up where we left off next launch
+    fn resume_point(&self) -> Option<ResumePoint> {
+        let track = self.tracks.get(self.current_track_index?)?;
+        Some(ResumePoint {
+            track_path: track.file_path.clone(),
+            position_ms: self.current_position.as_millis() as u64,
+        })
+    }
+    
+    /// Play the track from the last session and seek back to where it was
+    async fn resume_last_track(&mut self) -> Result<()> {
+        let Some((track_idx, position)) = self.pending_resume.take() else {
+            return Ok(());
+        };
         
-        match (key.code, key.modifiers) {
-            // Confirm playlist creation
-            (KeyCode::Enter, _) => Some(InteractiveEvent::ConfirmPlaylistCreation),
-            // Cancel playlist creation
-            (KeyCode::Esc, _) => Some(InteractiveEvent::CancelPlaylistCreation),
-            
-            // Playlist name input handling
-            (KeyCode::Backspace, _) => Some(InteractiveEvent::PlaylistBackspace),
-            (KeyCode::Char(c), KeyModifiers::NONE) if !c.is_control() => Some(InteractiveEvent::PlaylistInput(c)),
-            
-            // Global quit still works
-            (KeyCode::Char('q'), KeyModifiers::NONE) => Some(InteractiveEvent::Quit),
-            (KeyCode::Char('c'), KeyModifiers::CONTROL) => Some(InteractiveEvent::Quit),
-            
-            _ => None,
+        if !self.playback_context.order().contains(&track_idx) {
+            self.playback_context = if self.filtered_tracks.contains(&track_idx) {
+                PlaybackContext::Library { filtered: self.filtered_tracks.clone() }
+            } else {
+                PlaybackContext::Queue
+            };
+        }
+        if let Some((position, _)) = self.playback_context.step(track_idx, 0) {
+            self.select_in_playing_list(position);
         }
+        self.play_track(track_idx).await?;
+        if self.current_track_index == Some(track_idx) {
+            self.seek_to(position);
+        }
+        Ok(())
     }
     
-    fn key_to_playlist_selector_event(key: KeyEvent) -> Option<InteractiveEvent> {
-        use crossterm::event::KeyModifiers;
+    /// Step the volume and remember it in the config; the player is the single source of truth
+    ///
+    /// While muted the step starts from the muted-away volume, and unmutes.
+    fn change_volume(&mut self, delta: f32) -> Result<()> {
+        let current = self.muted_volume.unwrap_or_else(|| self.audio_player.get_volume());
+        // Round to whole steps so repeated presses don't accumulate float drift
+        let volume = ((current + delta) * 10.0).round() / 10.0;
+        self.set_volume(volume)
+    }
+    
+    /// Apply an absolute volume and remember it in the config
+    fn set_volume(&mut self, volume: f32) -> Result<()> {
+        let previous = self.muted_volume.take().unwrap_or_else(|| self.audio_player.get_volume());
+        self.audio_player.set_volume(volume)?;
+        let volume = self.audio_player.get_volume();
         
-        match (key.code, key.modifiers) {
-            // Navigation in playlist selector
-            (KeyCode::Up, _) => Some(InteractiveEvent::Up),
-            (KeyCode::Down, _) => Some(InteractiveEvent::Down),
-            
-            // Select playlist or create new one
-            (KeyCode::Enter, _) => Some(InteractiveEvent::SelectPlaylistFromSelector),
-            
-            // Cancel playlist selection
-            (KeyCode::Esc, _) => Some(InteractiveEvent::CancelPlaylistSelector),
-            
-            // Global quit still works
-            (KeyCode::Char('q'), KeyModifiers::NONE) => Some(InteractiveEvent::Quit),
-            (KeyCode::Char('c'), KeyModifiers::CONTROL) => Some(InteractiveEvent::Quit),
-            
-            _ => None,
+        let icon = if volume >= previous { "🔊" } else { "🔉" };
+        self.set_status(&format!("{} Volume: {}%", icon, (volume * 100.0).round() as u32));
+        
+        self.config.audio.volume = volume;
+        if let Err(e) = self.config.save() {
+            warn!("Failed to save volume to config: {}", e);
         }
+        Ok(())
     }
     
-    fn key_to_app_event_basic(&self, key: KeyEvent) -> Option<InteractiveEvent> {
-        use crossterm::event::KeyModifiers;
-        
-        match (key.code, key.modifiers) {
-            // Ctrl combinations for ergonomic shortcuts
-            (KeyCode::Char('s'), KeyModifiers::CONTROL) => Some(InteractiveEvent::SaveMetadata),
-            (KeyCode::Char('c'), KeyModifiers::CONTROL) => Some(InteractiveEvent::Quit), // Ctrl+C
-            
-            // Regular key mappings
-            (KeyCode::Char('q'), KeyModifiers::NONE) => Some(InteractiveEvent::Quit),
-            (KeyCode::Char('1'), KeyModifiers::NONE) => Some(InteractiveEvent::SwitchToLibrary),
-            (KeyCode::Char('2'), KeyModifiers::NONE) => Some(InteractiveEvent::SwitchToPlaylists),
-            (KeyCode::Char('3'), KeyModifiers::NONE) => Some(InteractiveEvent::SwitchToMetadataEditor),
-            (KeyCode::Char('4'), KeyModifiers::NONE) => Some(InteractiveEvent::SwitchToSettings),
-            (KeyCode::Char(' '), KeyModifiers::NONE) => Some(InteractiveEvent::TogglePlayPause),
-            (KeyCode::Char('n'), KeyModifiers::NONE) => Some(InteractiveEvent::NextTrack),
-            (KeyCode::Char('p'), KeyModifiers::NONE) => Some(InteractiveEvent::PreviousTrack),
-            (KeyCode::Char('s'), KeyModifiers::NONE) => Some(InteractiveEvent::Stop),
-            (KeyCode::Char('+'), KeyModifiers::NONE) | (KeyCode::Char('='), KeyModifiers::NONE) => Some(InteractiveEvent::VolumeUp),
-            (KeyCode::Char('-'), KeyModifiers::NONE) => Some(InteractiveEvent::VolumeDown),
-            (KeyCode::Char('z'), KeyModifiers::NONE) => Some(InteractiveEvent::ToggleShuffle),
-
-            (KeyCode::Up, _) => Some(InteractiveEvent::Up),
-            (KeyCode::Down, _) => Some(InteractiveEvent::Down),
-            (KeyCode::Esc, _) => Some(InteractiveEvent::CancelEdit),
-            (KeyCode::Backspace, _) => Some(InteractiveEvent::Backspace),
-            // Context-sensitive key bindings based on current tab
-            (KeyCode::Char('c'), KeyModifiers::NONE) => {
-                match self.current_tab {
-                    AppTab::MetadataEditor => Some(InteractiveEvent::ClearMetadata),
-                    _ => None,
-                }
-            }
-            (KeyCode::Char('a'), KeyModifiers::NONE) => {
-                match self.current_tab {
-                    AppTab::Library => Some(InteractiveEvent::AddToPlaylist),
-                    AppTab::MetadataEditor => Some(InteractiveEvent::EditArtist),
-                    _ => None,
-                }
-            }
-            (KeyCode::Char('l'), KeyModifiers::NONE) => {
-                match self.current_tab {
-                    AppTab::Playlists => Some(InteractiveEvent::LoadPlaylist),
-                    _ => None,
-                }
+    /// Silence the player without touching the configured volume, or bring the volume back
+    fn toggle_mute(&mut self) -> Result<()> {
+        match self.muted_volume.take() {
+            Some(volume) => {
+                self.audio_player.set_volume(volume)?;
+                self.set_status(&format!("🔊 Volume: {}%", (volume * 100.0).round() as u32));
             }
-            (KeyCode::Char('r'), KeyModifiers::NONE) => {
-                match self.current_tab {
-                    AppTab::Playlists => Some(InteractiveEvent::RenamePlaylist),
-                    _ => Some(InteractiveEvent::ToggleRepeat), // Default behavior for other tabs
-                }
+            None => {
+                self.muted_volume = Some(self.audio_player.get_volume());
+                self.audio_player.set_volume(0.0)?;
+                self.set_status("🔇 Muted");
             }
-            (KeyCode::Char('x'), KeyModifiers::NONE) => {
-                match self.current_tab {
-                    AppTab::Playlists => Some(InteractiveEvent::RemoveFromPlaylist),
-                    _ => None,
-                }
+        }
+        Ok(())
+    }
+    
+    /// Change playback speed for this session; `audio.playback_speed` in the config sets the startup speed
+    fn set_speed(&mut self, speed: f32) {
+        self.audio_player.set_speed(speed);
+        let speed = self.audio_player.get_speed();
+        let icon = if speed < 1.0 { "⏪" } else { "⏩" };
+        self.set_status(&format!("{} Speed: {}x", icon, speed));
+    }
+    
+    /// Badge for the player controls while not playing at normal speed
+    fn speed_label(&self) -> Option<String> {
+        let speed = self.audio_player.get_speed();
+        ((speed - 1.0).abs() > f32::EPSILON).then(|| format!("⏩ {}x", speed))
+    }
+    
+    /// Push the configured equalizer gains to the player and remember them in the config
+    fn apply_equalizer(&mut self) {
+        self.audio_player.equalizer().set_gains(self.config.audio.equalizer.gains());
+        if let Err(e) = self.config.save() {
+            warn!("Failed to save equalizer to config: {}", e);
+        }
+    }
+    
+    /// Nudge the selected band; editing a preset turns it into a custom curve starting from it
+    fn adjust_eq_gain(&mut self, delta: f32) {
+        let equalizer = &mut self.config.audio.equalizer;
+        if equalizer.preset != EqPreset::Custom {
+            equalizer.custom_gains = equalizer.gains();
+            equalizer.preset = EqPreset::Custom;
+        }
+        
+        let gain = &mut equalizer.custom_gains[self.eq_band];
+        *gain = clamp_gain(*gain + delta);
+        let message = format!("🎚️ {} {:+.0} dB", band_label(BAND_FREQUENCIES[self.eq_band]), *gain);
+        
+        self.apply_equalizer();
+        self.set_status(&message);
+    }
+    
+    /// List the output devices and open the picker on the one in use
+    fn open_device_picker(&mut self) {
+        self.output_devices = match AudioPlayer::list_devices() {
+            Ok(devices) => devices,
+            Err(e) => {
+                self.push_notification(NotificationLevel::Error, &format!("❌ Could not list audio devices: {}", e));
+                return;
             }
-            (KeyCode::Enter, KeyModifiers::NONE) => {
-                match self.current_tab {
-                    AppTab::Playlists => Some(InteractiveEvent::TogglePlaylistExpansion),
-                    _ => Some(InteractiveEvent::Play), // Default behavior for other tabs
-                }
+        };
+        
+        let current_row = self.config.audio.output_device.as_ref()
+            .and_then(|name| self.output_devices.iter().position(|d| d == name))
+            .map_or(0, |pos| pos + 1);
+        self.device_picker_state.select(Some(current_row));
+        self.show_device_picker = true;
+    }
+    
+    fn step_device_picker(&mut self, delta: i32) {
+        let rows = self.output_devices.len() as i32 + 1; // + the system default
+        let current = self.device_picker_state.selected().unwrap_or(0) as i32;
+        self.device_picker_state.select(Some((current + delta).rem_euclid(rows) as usize));
+    }
+    
+    /// Content hash of the playing track, hashing the file now if the scan didn't
+    fn current_content_hash(&mut self) -> Option<u64> {
+        let track = self.tracks.get_mut(self.current_track_index?)?;
+        match track.compute_content_hash() {
+            Ok(hash) => Some(hash),
+            Err(e) => {
+                debug!("Could not hash {}: {}", track.file_path.display(), e);
+                None
             }
-            
-            // Metadata editor specific keys (only work in metadata editor tab)
-            (KeyCode::Char('t'), KeyModifiers::NONE) => {
-                if self.current_tab == AppTab::MetadataEditor {
-                    Some(InteractiveEvent::EditTitle)
-                } else {
-                    None
-                }
+        }
+    }
+    
+    /// Reload the playing track's bookmarks, e.g. after a track change
+    fn load_bookmarks(&mut self) {
+        self.bookmarks = match self.current_content_hash() {
+            Some(hash) => self.behavior_tracker.get_bookmarks(hash).unwrap_or_else(|e| {
+                warn!("Failed to load bookmarks: {}", e);
+                Vec::new()
+            }),
+            None => Vec::new(),
+        };
+    }
+    
+    /// Look up the playing track's silence, or work it out in the background the first time it plays
+    #[cfg(feature = "silence")]
+    fn load_silence(&mut self) {
+        if !self.config.audio.skip_silence {
+            self.silence = None;
+            return;
+        }
+        let Some(content_hash) = self.current_content_hash() else {
+            self.silence = None;
+            return;
+        };
+        // Played again before the first analysis finished: that one will still answer
+        if let Some(skip) = self.silence.as_mut().filter(|skip| skip.content_hash == content_hash && skip.bounds.is_none()) {
+            skip.intro_skipped = false;
+            return;
+        }
+        
+        let bounds = self.behavior_tracker.get_silence_bounds(content_hash).unwrap_or_else(|e| {
+            warn!("Failed to load silence bounds: {}", e);
+            None
+        });
+        if bounds.is_none() {
+            let path = self.tracks[self.current_track_index.unwrap_or_default()].file_path.clone();
+            let events = self._event_tx.clone();
+            tokio::task::spawn_blocking(move || {
+                let bounds = silence::detect_silence(&path).map_err(|e| e.to_string());
+                let _ = events.send(InteractiveEvent::SilenceDetected(content_hash, bounds));
+            });
+        }
+        self.silence = Some(SilenceSkip { content_hash, bounds, intro_skipped: false });
+    }
+    
+    /// Jump past a silent intro once per play, and end the track where its silent outro begins
+    #[cfg(feature = "silence")]
+    async fn skip_silence(&mut self) -> Result<()> {
+        let Some(skip) = self.silence.as_mut() else {
+            return Ok(());
+        };
+        let (Some(bounds), true) = (skip.bounds, self.is_playing) else {
+            return Ok(());
+        };
+        if !skip.intro_skipped {
+            skip.intro_skipped = true;
+            if self.current_position < bounds.start {
+                self.seek_to(bounds.start);
             }
-            (KeyCode::Tab, KeyModifiers::NONE) => {
-                if self.current_tab == AppTab::MetadataEditor {
-                    Some(InteractiveEvent::ApplySuggestion)
-                } else {
-                    None
-                }
+        }
+        
+        if bounds.end.is_some_and(|end| self.current_position >= end) {
+            self.silence = None;
+            let Some(track) = self.current_track_index.and_then(|idx| self.tracks.get(idx)).cloned() else {
+                return Ok(());
+            };
+            // Whatever was queued gaplessly would only start after the silence - start the next track now
+            self.gapless_next = None;
+            self.finish_track(track).await?;
+        }
+        Ok(())
+    }
+    
+    /// Under one of `library.audiobook_directories`: played in order without fades, each file resumed where it was left
+    fn is_spoken_word(&self, track_idx: usize) -> bool {
+        self.tracks.get(track_idx).is_some_and(|track| self.config.library.is_spoken_word(&track.file_path))
+    }
+    
+    /// Read the chapters of a spoken-word track that just started, and seek back to where it was left
+    fn load_spoken_word(&mut self) {
+        let spoken = self.current_track_index.is_some_and(|idx| self.is_spoken_word(idx));
+        let Some(content_hash) = spoken.then(|| self.current_content_hash()).flatten() else {
+            self.spoken_word = None;
+            return;
+        };
+        let path = &self.tracks[self.current_track_index.unwrap_or_default()].file_path;
+        let chapters = chapters::read_chapters(path).unwrap_or_else(|e| {
+            debug!("No chapters in {}: {}", path.display(), e);
+            Vec::new()
+        });
+        self.spoken_word = Some(SpokenWord { content_hash, chapters, saved_at: Instant::now() });
+        
+        match self.behavior_tracker.get_resume_position(content_hash) {
+            Ok(Some(position)) => {
+                self.seek_to(position);
+                self.set_status(&format!("🎧 Resuming at {}", format_position(position)));
             }
-            (KeyCode::Char('b'), KeyModifiers::NONE) => {
-                if self.current_tab == AppTab::MetadataEditor {
-                    Some(InteractiveEvent::BulkApplySuggestions)
-                } else {
-                    None
-                }
+            Ok(None) => {}
+            Err(e) => warn!("Failed to load the resume position: {}", e),
+        }
+    }
+    
+    /// Write down where the spoken-word file is; one heard to its last seconds starts over next time
+    fn save_spoken_word_position(&mut self) {
+        let near_end = self.total_duration.is_some_and(|total| total.saturating_sub(self.current_position) < RESUME_END_MARGIN);
+        let Some(spoken) = self.spoken_word.as_mut() else {
+            return;
+        };
+        spoken.saved_at = Instant::now();
+        let result = if near_end {
+            self.behavior_tracker.clear_resume_position(spoken.content_hash)
+        } else if !self.current_position.is_zero() {
+            self.behavior_tracker.save_resume_position(spoken.content_hash, self.current_position)
+        } else {
+            Ok(())
+        };
+        if let Err(e) = result {
+            warn!("Failed to save the resume position: {}", e);
+        }
+    }
+    
+    /// Save the spoken-word position before playback moves away from the file
+    fn leave_spoken_word(&mut self) {
+        self.save_spoken_word_position();
+        self.spoken_word = None;
+        self.show_chapter_list = false;
+    }
+    
+    /// The playing spoken-word file's chapters, empty for anything else
+    fn chapters(&self) -> &[Chapter] {
+        self.spoken_word.as_ref().map_or(&[], |spoken| spoken.chapters.as_slice())
+    }
+    
+    /// Next or previous chapter; going back early in a chapter goes to the one before, otherwise to its start
+    fn step_chapter(&mut self, delta: isize) {
+        let chapters = self.chapters();
+        if chapters.is_empty() {
+            self.set_status("📖 No chapters in this track");
+            return;
+        }
+        let current = chapters::chapter_at(chapters, self.current_position);
+        let target = match (current, delta > 0) {
+            (None, true) => Some(0),
+            (None, false) => None,
+            (Some(idx), true) => Some(idx + 1).filter(|&next| next < chapters.len()),
+            (Some(idx), false) if self.current_position.saturating_sub(chapters[idx].start) > CHAPTER_RESTART_WINDOW => Some(idx),
+            (Some(idx), false) => Some(idx.saturating_sub(1)),
+        };
+        match target {
+            Some(idx) => self.jump_to_chapter(idx),
+            None => self.set_status("📖 No more chapters"),
+        }
+    }
+    
+    fn jump_to_chapter(&mut self, idx: usize) {
+        let Some(chapter) = self.chapters().get(idx).cloned() else {
+            return;
+        };
+        self.seek_to(chapter.start);
+        self.set_status(&format!("📖 {} ({})", chapter.title, format_position(chapter.start)));
+    }
+    
+    fn open_chapter_list(&mut self) {
+        if self.chapters().is_empty() {
+            self.set_status("📖 No chapters in this track");
+            return;
+        }
+        let row = chapters::chapter_at(self.chapters(), self.current_position).unwrap_or(0);
+        self.chapter_list_state.select(Some(row));
+        self.show_chapter_list = true;
+    }
+    
+    fn step_chapter_list(&mut self, delta: i32) {
+        let rows = self.chapters().len().max(1) as i32;
+        let current = self.chapter_list_state.selected().unwrap_or(0) as i32;
+        self.chapter_list_state.select(Some((current + delta).rem_euclid(rows) as usize));
+    }
+    
+    /// The spoken-word badge for the player controls: the chapter playing, if the file has any
+    fn spoken_word_label(&self) -> Option<String> {
+        let spoken = self.spoken_word.as_ref()?;
+        Some(match chapters::chapter_at(&spoken.chapters, self.current_position) {
+            Some(idx) => format!("📖 {}/{} {}", idx + 1, spoken.chapters.len(), spoken.chapters[idx].title),
+            None => "🎧 spoken word".to_string(),
+        })
+    }
+    
+    /// Drop a bookmark at the current position of the playing track
+    fn add_bookmark(&mut self) {
+        let Some(hash) = self.current_content_hash() else {
+            self.set_status_level(NotificationLevel::Warning, "⚠️ Nothing playing to bookmark");
+            return;
+        };
+        
+        let position = self.current_position;
+        let label = format!("Bookmark {}", self.bookmarks.len() + 1);
+        match self.behavior_tracker.add_bookmark(hash, position, &label) {
+            Ok(_) => {
+                self.load_bookmarks();
+                self.set_status(&format!("🔖 {} at {} (' to jump)", label, format_position(position)));
             }
-            
-            // Global keys that work everywhere
-            (KeyCode::Delete, KeyModifiers::NONE) => {
-                if self.current_tab == AppTab::Playlists {
-                    Some(InteractiveEvent::DeletePlaylist)
-                } else {
-                    None
-                }
+            Err(e) => self.push_notification(NotificationLevel::Error, &format!("❌ Could not save bookmark: {}", e)),
+        }
+    }
+    
+    fn open_bookmark_picker(&mut self) {
+        if self.bookmarks.is_empty() {
+            self.set_status("🔖 No bookmarks in this track (Shift+B adds one)");
+            return;
+        }
+        
+        // Start on the last bookmark we've already passed
+        let row = self.bookmarks.iter()
+            .rposition(|b| b.position() <= self.current_position)
+            .unwrap_or(0);
+        self.bookmark_picker_state.select(Some(row));
+        self.show_bookmark_picker = true;
+    }
+    
+    fn step_bookmark_picker(&mut self, delta: i32) {
+        let rows = self.bookmarks.len().max(1) as i32;
+        let current = self.bookmark_picker_state.selected().unwrap_or(0) as i32;
+        self.bookmark_picker_state.select(Some((current + delta).rem_euclid(rows) as usize));
+    }
+    
+    fn open_history(&mut self) {
+        match self.behavior_tracker.recent_plays(HISTORY_LENGTH) {
+            Ok(plays) if plays.is_empty() => self.set_status("🕘 Nothing played yet"),
+            Ok(plays) => {
+                self.history_state.select(Some(0));
+                self.history = Some(plays);
             }
-            
-            // Search mode - forward slash to enter search
-            (KeyCode::Char('/'), KeyModifiers::NONE) => Some(InteractiveEvent::EnterSearch),
-            
-            // Help overlay - handle ? key (Shift+/ produces '?' character)
-            (KeyCode::Char('?'), KeyModifiers::NONE) => Some(InteractiveEvent::ShowHelp),
-            (KeyCode::Char('?'), KeyModifiers::SHIFT) => Some(InteractiveEvent::ShowHelp),
-            
-            // Catch-all for text input (exclude ? to avoid conflict with help)
-            (KeyCode::Char(c), KeyModifiers::NONE) if !c.is_control() && c != '?' => Some(InteractiveEvent::Input(c)),
-            _ => None,
+            Err(e) => self.push_notification(NotificationLevel::Error, &format!("❌ Could not load the history: {}", e)),
         }
     }
     
-    async fn handle_event(&mut self, event: InteractiveEvent) -> Result<()> {
-        // Context-aware event filtering
-        let should_process = match (&event, &self.current_tab, &self.edit_mode) {
-            // Global events always process
-            (InteractiveEvent::Quit, _, _) => true,
-            (InteractiveEvent::SwitchToLibrary, _, _) => true,
-            (InteractiveEvent::SwitchToPlaylists, _, _) => true,
-            (InteractiveEvent::SwitchToMetadataEditor, _, _) => true,
-            (InteractiveEvent::SwitchToSettings, _, _) => true,
-            (InteractiveEvent::Up, _, _) => true,
-            (InteractiveEvent::Down, _, _) => true,
-            (InteractiveEvent::Tick, _, _) => true,
-            (InteractiveEvent::ShowHelp, _, _) => true, // Help overlay should work globally
-            
-            // Search events - should work globally
-            (InteractiveEvent::EnterSearch, _, _) => true,
-            (InteractiveEvent::ExitSearch, _, _) => true,
-            (InteractiveEvent::SearchInput(_), _, _) => true,
-            (InteractiveEvent::SearchBackspace, _, _) => true,
-            
-            // Playlist creation input events - should work when in playlist creation mode
-            (InteractiveEvent::PlaylistInput(_), _, _) => true,
-            (InteractiveEvent::PlaylistBackspace, _, _) => true,
-            (InteractiveEvent::ConfirmPlaylistCreation, _, _) => true,
-            (InteractiveEvent::CancelPlaylistCreation, _, _) => true,
-            
-            // Playlist selector overlay events - should work when overlay is shown
-            (InteractiveEvent::SelectPlaylistFromSelector, _, _) => true,
-            (InteractiveEvent::CancelPlaylistSelector, _, _) => true,
-            
-            // Editing mode events (highest priority)
-            (InteractiveEvent::SaveMetadata, _, EditMode::Title | EditMode::Artist) => true,
-            (InteractiveEvent::CancelEdit, _, EditMode::Title | EditMode::Artist) => true,
-            (InteractiveEvent::Backspace, _, EditMode::Title | EditMode::Artist) => true,
-            (InteractiveEvent::Input(_), _, EditMode::Title | EditMode::Artist) => true,
-            
-            // Metadata editor events (when not editing)
-            (InteractiveEvent::EditTitle, AppTab::MetadataEditor, EditMode::None) => true,
-            (InteractiveEvent::EditArtist, AppTab::MetadataEditor, EditMode::None) => true,
-            (InteractiveEvent::ApplySuggestion, AppTab::MetadataEditor, EditMode::None) => true,
-            (InteractiveEvent::ResetToOriginal, AppTab::MetadataEditor, EditMode::None) => true,
-            (InteractiveEvent::BulkApplySuggestions, AppTab::MetadataEditor, EditMode::None) => true,
-            (InteractiveEvent::ClearMetadata, AppTab::MetadataEditor, EditMode::None) => true,
-            
-            // Playlist events (when not editing)
-            (InteractiveEvent::LoadPlaylist, AppTab::Playlists, EditMode::None) => true,
-            (InteractiveEvent::TogglePlaylistExpansion, AppTab::Playlists, EditMode::None) => true,
-            (InteractiveEvent::DeletePlaylist, AppTab::Playlists, EditMode::None) => true,
-            (InteractiveEvent::AddToPlaylist, AppTab::Library, EditMode::None) => true,
-            
-            // 'r' key context-sensitive handling
-            (InteractiveEvent::ToggleRepeat, AppTab::Library, EditMode::None) => true,
-            (InteractiveEvent::ToggleRepeat, AppTab::MetadataEditor, EditMode::None) => false, // Block in metadata editor
-            
-            // Playback controls work in both tabs when not editing
-            (InteractiveEvent::TogglePlayPause, _, EditMode::None) => true,
-            (InteractiveEvent::Play, _, EditMode::None) => true,
-            (InteractiveEvent::NextTrack, _, EditMode::None) => true,
-            (InteractiveEvent::PreviousTrack, _, EditMode::None) => true,
-            (InteractiveEvent::Stop, _, EditMode::None) => true,
-            (InteractiveEvent::ToggleShuffle, _, EditMode::None) => true,
-            (InteractiveEvent::VolumeUp, _, EditMode::None) => true,
-            (InteractiveEvent::VolumeDown, _, EditMode::None) => true,
-            
-            // Visualizer event filtering removed
-            
-            // Block other events when editing or in wrong context
-            _ => false,
+    /// The track under the cursor on the tabs that list tracks, else the one playing
+    fn reveal_track_file(&mut self) {
+        let selected = match self.current_tab {
+            AppTab::Library => self.selected_library_track(),
+            AppTab::MetadataEditor => self.metadata_list_state.selected(),
+            AppTab::Playlists => self.get_selected_playlist_track().and_then(|(playlist_id, position)| {
+                let playlist = self.playlist_manager.get_playlist(&playlist_id)?;
+                playlist.get_valid_tracks(&self.tracks).get(position).copied()
+            }),
+            _ => None,
+        };
+        let Some(path) = selected.or(self.current_track_index).and_then(|idx| self.tracks.get(idx)).map(|t| t.file_path.clone()) else {
+            self.set_status("📂 No track selected");
+            return;
         };
         
-        if !should_process {
+        match panpipe::reveal::reveal(&path) {
+            Ok(()) => self.set_status(&format!("📂 Showing {} in the file manager", path.display())),
+            Err(e) => self.set_status_level(NotificationLevel::Error, &format!("❌ Can't show the file: {}", e)),
+        }
+    }
+    
+    fn open_error_log(&mut self) {
+        if self.error_log.is_empty() {
+            self.set_status_level(NotificationLevel::Success, "✅ No warnings or errors this session");
+            return;
+        }
+        self.error_log_state.select(Some(0));
+        self.show_error_log = true;
+    }
+    
+    fn step_error_log(&mut self, delta: i32) {
+        let rows = self.error_log.len().max(1) as i32;
+        let current = self.error_log_state.selected().unwrap_or(0) as i32;
+        self.error_log_state.select(Some((current + delta).rem_euclid(rows) as usize));
+    }
+    
+    fn step_history(&mut self, delta: i32) {
+        let rows = self.history.as_ref().map_or(0, Vec::len).max(1) as i32;
+        let current = self.history_state.selected().unwrap_or(0) as i32;
+        self.history_state.select(Some((current + delta).rem_euclid(rows) as usize));
+    }
+    
+    /// Play the selected history entry again, looked up by id and then by its last known path
+    async fn play_from_history(&mut self) -> Result<()> {
+        let Some(play) = self.history_state.selected().and_then(|row| self.history.as_ref()?.get(row)).cloned() else {
+            return Ok(());
+        };
+        let track_idx = self.tracks.iter().position(|t| t.id == play.track_id)
+            .or_else(|| self.tracks.iter().position(|t| Some(&t.file_path) == play.file_path.as_ref()));
+        let Some(track_idx) = track_idx else {
+            self.set_status_level(NotificationLevel::Warning, "⚠️ That track is no longer in the library");
             return Ok(());
+        };
+        
+        self.history = None;
+        // Carry on through the library when the track is in the list on screen, like a resume does
+        self.playback_context = if self.filtered_tracks.contains(&track_idx) {
+            PlaybackContext::Library { filtered: self.filtered_tracks.clone() }
+        } else {
+            PlaybackContext::Queue
+        };
+        self.play_track(track_idx).await
+    }
+    
+    /// Library tracks kept out of shuffle, in library order
+    fn excluded_track_indices(&self) -> Vec<usize> {
+        (0..self.tracks.len()).filter(|&idx| self.shuffle_excluded.contains(&self.tracks[idx].id)).collect()
+    }
+    
+    fn open_excluded_tracks(&mut self) {
+        if self.excluded_track_indices().is_empty() {
+            self.set_status(&format!("🔀 No tracks are kept out of shuffle ({} in the Library excludes one)", self.keymap.keys_label(Action::ExcludeFromShuffle)));
+            return;
+        }
+        self.excluded_tracks_state.select(Some(0));
+        self.show_excluded_tracks = true;
+    }
+    
+    fn step_excluded_tracks(&mut self, delta: i32) {
+        let rows = self.excluded_track_indices().len().max(1) as i32;
+        let current = self.excluded_tracks_state.selected().unwrap_or(0) as i32;
+        self.excluded_tracks_state.select(Some((current + delta).rem_euclid(rows) as usize));
+    }
+    
+    /// Keep a track out of shuffle or let it back in; it can still be played by hand either way
+    async fn set_shuffle_excluded(&mut self, track_idx: usize, excluded: bool) {
+        let track = &self.tracks[track_idx];
+        let (track_id, title) = (track.id, track.display_title());
+        if let Err(e) = self.behavior_tracker.set_shuffle_excluded(track_id, excluded).await {
+            self.push_notification(NotificationLevel::Error, &format!("❌ Could not update the shuffle exclusion: {}", e));
+            return;
+        }
+        if excluded {
+            self.shuffle_excluded.insert(track_id);
+            self.set_status(&format!("⊘ {} won't come up in shuffle", title));
+        } else {
+            self.shuffle_excluded.remove(&track_id);
+            self.set_status(&format!("🔀 {} is back in shuffle", title));
+        }
+    }
+    
+    /// Let the selected track in the excluded overlay back into shuffle, closing it once none are left
+    async fn include_selected_in_shuffle(&mut self) {
+        let excluded = self.excluded_track_indices();
+        let Some(&track_idx) = self.excluded_tracks_state.selected().and_then(|row| excluded.get(row)) else {
+            return;
+        };
+        self.set_shuffle_excluded(track_idx, false).await;
+        let remaining = self.excluded_track_indices().len();
+        if remaining == 0 {
+            self.show_excluded_tracks = false;
+        } else {
+            self.excluded_tracks_state.select(self.excluded_tracks_state.selected().map(|row| row.min(remaining - 1)));
         }
+    }
+    
+    /// Load the selected Library track's behavior and score it for the detail overlay
+    async fn open_behavior_detail(&mut self) {
+        let Some(track) = self.selected_library_track().map(|idx| &self.tracks[idx]) else {
+            return;
+        };
+        let (track_id, title) = (track.id, track.display_title());
         
-        match event {
-            InteractiveEvent::Quit => {
-                self.should_quit = true;
-            }
-            InteractiveEvent::Up => {
-                self.move_selection(-1);
+        match self.behavior_tracker.get_track_behavior(track_id).await {
+            Ok(behavior) => {
+                let mut calculator = WeightCalculator::new(self.config.behavior.weight_decay_days);
+                if self.config.behavior.time_of_day_weighting {
+                    calculator = calculator.with_play_hours(self.behavior_tracker.get_play_hours().await.unwrap_or_default());
+                }
+                let weight = behavior.as_ref().map(|b| calculator.calculate_weight(b, chrono::Utc::now()));
+                self.behavior_detail = Some(BehaviorDetail { title, behavior, weight });
             }
-            InteractiveEvent::Down => {
-                self.move_selection(1);
+            Err(e) => {
+                self.push_notification(NotificationLevel::Error, &format!("❌ Could not load behavior: {}", e));
             }
-            InteractiveEvent::Play => {
-                // Check if we're in playlist context first
-                if let Some((playlist_id, track_idx_in_playlist)) = self.get_playlist_selection_context() {
-                    // Playing from playlist - get the actual track index
-                    debug!("🎵 Playlist context detected: playlist={}, track_idx={}", playlist_id, track_idx_in_playlist);
-                    if let Some(playlist) = self.playlist_manager.get_playlist(&playlist_id) {
-                        let valid_tracks = playlist.get_valid_tracks(&self.tracks);
-                        debug!("🎵 Valid tracks in playlist: {:?}", valid_tracks);
-                        if let Some(&actual_track_idx) = valid_tracks.get(track_idx_in_playlist) {
-                            debug!("🎵 Playing track {} from playlist", actual_track_idx);
-                            self.play_track(actual_track_idx).await?;
-                        } else {
-                            debug!("❌ Track index {} not found in valid tracks", track_idx_in_playlist);
+        }
+    }
+    
+    /// Score the library against the selected Library track for the More like this overlay
+    async fn open_more_like_this(&mut self) {
+        let Some(track_idx) = self.selected_library_track() else {
+            return;
+        };
+        let behaviors: HashMap<_, _> = match self.behavior_tracker.get_all_behaviors().await {
+            Ok(behaviors) => behaviors.into_iter().map(|behavior| (behavior.track_id, behavior)).collect(),
+            Err(e) => {
+                self.push_notification(NotificationLevel::Error, &format!("❌ Could not load behavior: {}", e));
+                return;
+            }
+        };
+        
+        let track = &self.tracks[track_idx];
+        let similar = similar::recommend_similar(track, &self.tracks, &behaviors, SIMILAR_COUNT);
+        if similar.is_empty() {
+            self.set_status(&format!("🔍 Nothing shares an artist, album, genre or era with '{}'", track.display_title()));
+            return;
+        }
+        self.more_like_this_state.select(Some(0));
+        self.more_like_this = Some(MoreLikeThis { title: track.display_title(), similar });
+    }
+    
+    fn step_more_like_this(&mut self, delta: i32) {
+        let rows = self.more_like_this.as_ref().map_or(0, |view| view.similar.len()).max(1) as i32;
+        let current = self.more_like_this_state.selected().unwrap_or(0) as i32;
+        self.more_like_this_state.select(Some((current + delta).rem_euclid(rows) as usize));
+    }
+    
+    /// Play the highlighted suggestion, then on through the ones below it
+    async fn play_more_like_this(&mut self) -> Result<()> {
+        let Some(view) = self.more_like_this.take() else {
+            return Ok(());
+        };
+        let row = self.more_like_this_state.selected().unwrap_or(0).min(view.similar.len() - 1);
+        let order: Vec<usize> = view.similar[row..].iter().map(|s| s.index).collect();
+        let track_idx = order[0];
+        self.playback_context = PlaybackContext::Similar { order };
+        self.play_track(track_idx).await
+    }
+    
+    /// Line every suggestion up after the track playing, or start on them when nothing is
+    async fn queue_more_like_this(&mut self) -> Result<()> {
+        let Some(view) = self.more_like_this.take() else {
+            return Ok(());
+        };
+        let mut order: Vec<usize> = view.similar.iter().map(|s| s.index).collect();
+        let Some(current) = self.current_track_index else {
+            self.set_status(&format!("▶️ Playing {} tracks like '{}'", order.len(), view.title));
+            let track_idx = order[0];
+            self.playback_context = PlaybackContext::Similar { order };
+            return self.play_track(track_idx).await;
+        };
+        
+        // A gapless successor is already on the player, so it stays next
+        let queued = self.gapless_next.map(|(_, track_idx)| track_idx);
+        order.retain(|&idx| idx != current && Some(idx) != queued);
+        let count = order.len();
+        order.splice(0..0, std::iter::once(current).chain(queued));
+        self.playback_context = PlaybackContext::Similar { order };
+        match queued {
+            Some(track_idx) => self.gapless_next = Some((1, track_idx)),
+            None => self.queue_gapless_next(),
+        }
+        self.set_status(&format!("➕ Queued {} tracks like '{}' after this one", count, view.title));
+        Ok(())
+    }
+    
+    fn delete_selected_bookmark(&mut self) {
+        let Some(bookmark) = self.bookmark_picker_state.selected().and_then(|row| self.bookmarks.get(row)).cloned() else {
+            return;
+        };
+        
+        if let Err(e) = self.behavior_tracker.delete_bookmark(bookmark.id) {
+            self.push_notification(NotificationLevel::Error, &format!("❌ Could not delete bookmark: {}", e));
+            return;
+        }
+        self.load_bookmarks();
+        self.set_status(&format!("🔖 Removed {}", bookmark.label));
+        
+        if self.bookmarks.is_empty() {
+            self.show_bookmark_picker = false;
+        } else {
+            self.step_bookmark_picker(0);
+        }
+    }
+    
+    /// Rebuild the output stream on `device` (`None` for the system default) and carry on playing
+    async fn switch_output_device(&mut self, device: Option<String>) {
+        let position = self.audio_player.get_position();
+        
+        if let Err(e) = self.audio_player.set_output_device(device.as_deref()) {
+            self.push_notification(NotificationLevel::Error, &format!("❌ Could not open output device: {}", e));
+            return;
+        }
+        
+        let name = device.clone().unwrap_or_else(|| "system default".to_string());
+        self.config.audio.output_device = device;
+        if let Err(e) = self.config.save() {
+            warn!("Failed to save output device to config: {}", e);
+        }
+        self.push_notification(NotificationLevel::Success, &format!("🔈 Output device: {}", name));
+        
+        // The old stream took the track with it - pick it up where it was on the new device
+        self.gapless_next = None;
+        let Some(track) = self.current_track_index.and_then(|idx| self.tracks.get(idx)).cloned() else {
+            return;
+        };
+        if let Err(e) = self.audio_player.play_track(track) {
+            self.push_notification(NotificationLevel::Error, &format!("❌ Could not resume on the new device: {}", e));
+            self.is_playing = false;
+            self.current_track_index = None;
+            return;
+        }
+        if let Some(position) = position {
+            let _ = self.audio_player.seek(position);
+        }
+        if !self.is_playing {
+            let _ = self.audio_player.pause();
+        }
+        self.queue_gapless_next();
+    }
+    
+    /// Write the current session to disk - failures are logged, never fatal
+    fn save_session(&self) {
+        self.write_session(None);
+    }
+    
+    /// Save the session; `resume` is only passed on quit, so a crash never offers a stale position
+    fn write_session(&self, resume: Option<ResumePoint>) {
+        let selected_track_path = self.list_state.selected()
+            .and_then(|pos| self.filtered_tracks.get(pos))
+            .and_then(|&idx| self.tracks.get(idx))
+            .map(|track| track.file_path.clone());
+        
+        let session = SessionState {
+            last_tab: self.current_tab.session_key().to_string(),
+            selected_track_path,
+            shuffle: self.is_shuffled,
+            repeat_mode: self.repeat_mode.session_key().to_string(),
+            current_playlist_id: self.playback_context.playlist_id().map(str::to_string),
+            resume,
+        };
+        
+        if let Err(e) = session.save() {
+            warn!("Failed to save session: {}", e);
+        }
+    }
+    
+    async fn run(&mut self) -> Result<()> {
+        // SYNCHRONOUS event handling - no separate async tasks for terminal I/O
+        // This prevents race conditions that cause "Error: end of stream"
+        
+        while !self.should_quit {
+            // Waiting for input is the loop's only pause, so a key is handled and drawn right away
+            if event::poll(self.poll_interval()).unwrap_or(false) {
+                match event::read() {
+                    Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                        let app_event = if self.pending_confirm.is_some() {
+                            Self::key_to_confirm_event(key)
+                        } else if self.background_task.is_some() {
+                            Self::key_to_task_event(key)
+                        } else if self.search_mode {
+                            Self::key_to_search_event(key)
+                        } else if self.playlist_creation_mode {
+                            Self::key_to_playlist_event(key)
+                        } else if self.show_playlist_selector {
+                            Self::key_to_playlist_selector_event(key)
+                        } else if self.show_scan_report {
+                            Self::key_to_scan_report_event(key)
+                        } else if self.show_device_picker {
+                            Self::key_to_device_picker_event(key)
+                        } else if self.show_bookmark_picker {
+                            Self::key_to_bookmark_picker_event(key)
+                        } else if self.show_chapter_list {
+                            Self::key_to_chapter_list_event(key)
+                        } else if self.history.is_some() {
+                            Self::key_to_history_event(key)
+                        } else if self.show_error_log {
+                            Self::key_to_error_log_event(key)
+                        } else if self.show_excluded_tracks {
+                            Self::key_to_excluded_tracks_event(key)
+                        } else if self.behavior_detail.is_some() {
+                            Self::key_to_behavior_detail_event(key)
+                        } else if self.more_like_this.is_some() {
+                            Self::key_to_more_like_this_event(key)
+                        } else if self.duplicate_review.is_some() {
+                            Self::key_to_duplicates_event(key)
+                        } else if self.rename_preview.is_some() {
+                            Self::key_to_rename_preview_event(key)
+                        } else if self.metadata_matches_open() {
+                            Self::key_to_metadata_matches_event(key)
+                        } else {
+                            self.key_to_app_event_basic(key)
+                        };
+                        
+                        // Any other key abandons a half-typed `gg`
+                        if !matches!(app_event, Some(InteractiveEvent::GotoPrefix)) {
+                            self.pending_g = false;
+                        }
+                        
+                        if let Some(app_event) = app_event {
+                            self.handle_event(app_event).await?;
+                        }
+                    }
+                    Ok(Event::Mouse(mouse)) if self.pending_confirm.is_none() => {
+                        if let Some(app_event) = self.mouse_to_app_event(mouse) {
+                            self.handle_event(app_event).await?;
+                        }
+                    }
+                    // Layout comes from f.area() every frame, so redrawing is all a resize needs
+                    Ok(Event::Resize(_, _)) => self.needs_redraw = true,
+                    _ => {}
+                }
+            }
+            
+            // Handle audio events (duration learning, track finished, etc.)
+            while let Ok(audio_event) = self.audio_event_rx.try_recv() {
+                self.handle_audio_event(audio_event).await?;
+            }
+            
+            // Media keys and desktop widgets
+            #[cfg(feature = "mpris")]
+            self.process_mpris().await?;
+            
+            // Scripts talking to the control socket
+            self.process_control().await?;
+            
+            // Handle debounced library changes from the filesystem watcher
+            let library_changes: Vec<LibraryChange> = match self.library_change_rx.as_mut() {
+                Some(rx) => std::iter::from_fn(|| rx.try_recv().ok()).collect(),
+                None => Vec::new(),
+            };
+            for change in library_changes {
+                self.handle_event(InteractiveEvent::LibraryChanged(change.paths)).await?;
+            }
+            
+            // Handle internal events (including Tick events for time tracking)
+            while let Ok(internal_event) = self.event_rx.try_recv() {
+                self.handle_event(internal_event).await?;
+            }
+            
+            // Generate a Tick event for time tracking updates
+            let _ = self._event_tx.send(InteractiveEvent::Tick);
+            
+            // Only draw when something changed; a static screen costs nothing but the poll
+            if self.needs_redraw {
+                self.render()?;
+                self.needs_redraw = false;
+            }
+        }
+        
+        // Count a track quit mid-way; the terminal is restored once the app is dropped
+        if let Err(e) = self.behavior_tracker.finalize().await {
+            warn!("Failed to save the last listening session: {}", e);
+        }
+        #[cfg(feature = "scrobble")]
+        {
+            self.scrobble_finished_session();
+            if let Some(scrobbler) = self.scrobbler.take() {
+                scrobbler.shutdown().await;
+            }
+        }
+        
+        Ok(())
+    }
+    
+    fn key_to_search_event(key: KeyEvent) -> Option<InteractiveEvent> {
+        use crossterm::event::KeyModifiers;
+        
+        match (key.code, key.modifiers) {
+            // Exit search mode
+            (KeyCode::Esc, _) => Some(InteractiveEvent::ExitSearch),
+            (KeyCode::Enter, _) => Some(InteractiveEvent::ExitSearch),
+            
+            // Search input handling
+            (KeyCode::Backspace, _) => Some(InteractiveEvent::SearchBackspace),
+            (KeyCode::Tab, _) => Some(InteractiveEvent::CycleSearchScope),
+            (KeyCode::Char(c), KeyModifiers::NONE) if !c.is_control() => Some(InteractiveEvent::SearchInput(c)),
+            
+            // Allow navigation in search results
+            (KeyCode::Up, _) => Some(InteractiveEvent::Up),
+            (KeyCode::Down, _) => Some(InteractiveEvent::Down),
+            
+            // Global quit still works
+            (KeyCode::Char('q'), KeyModifiers::NONE) => Some(InteractiveEvent::Quit),
+            (KeyCode::Char('c'), KeyModifiers::CONTROL) => Some(InteractiveEvent::Quit),
+            
+            _ => None,
+        }
+    }
+    
+    fn key_to_confirm_event(key: KeyEvent) -> Option<InteractiveEvent> {
+        use crossterm::event::KeyModifiers;
+        
+        match (key.code, key.modifiers) {
+            (KeyCode::Char('y' | 'Y'), KeyModifiers::NONE | KeyModifiers::SHIFT) | (KeyCode::Enter, _) => {
+                Some(InteractiveEvent::AcceptConfirm)
+            }
+            (KeyCode::Char('n' | 'N'), KeyModifiers::NONE | KeyModifiers::SHIFT) => Some(InteractiveEvent::DeclineConfirm),
+            (KeyCode::Esc, _) => Some(InteractiveEvent::DismissConfirm),
+            (KeyCode::Char('c'), KeyModifiers::CONTROL) => Some(InteractiveEvent::Quit),
+            _ => None,
+        }
+    }
+    
+    /// A running task takes every key: Esc cancels it, quitting still works
+    fn key_to_task_event(key: KeyEvent) -> Option<InteractiveEvent> {
+        use crossterm::event::KeyModifiers;
+        
+        match (key.code, key.modifiers) {
+            (KeyCode::Esc, _) | (KeyCode::Char('c'), KeyModifiers::NONE) => Some(InteractiveEvent::CancelTask),
+            (KeyCode::Char('q'), KeyModifiers::NONE) | (KeyCode::Char('c'), KeyModifiers::CONTROL) => Some(InteractiveEvent::Quit),
+            _ => None,
+        }
+    }
+    
+    fn key_to_playlist_event(key: KeyEvent) -> Option<InteractiveEvent> {
+        use crossterm::event::KeyModifiers;
+        
+        match (key.code, key.modifiers) {
+            // Confirm playlist creation
+            (KeyCode::Enter, _) => Some(InteractiveEvent::ConfirmPlaylistCreation),
+            // Cancel playlist creation
+            (KeyCode::Esc, _) => Some(InteractiveEvent::CancelPlaylistCreation),
+            
+            // Playlist name input handling
+            (KeyCode::Backspace, _) => Some(InteractiveEvent::PlaylistBackspace),
+            (KeyCode::Char(c), KeyModifiers::NONE) if !c.is_control() => Some(InteractiveEvent::PlaylistInput(c)),
+            
+            // Global quit still works
+            (KeyCode::Char('q'), KeyModifiers::NONE) => Some(InteractiveEvent::Quit),
+            (KeyCode::Char('c'), KeyModifiers::CONTROL) => Some(InteractiveEvent::Quit),
+            
+            _ => None,
+        }
+    }
+    
+    fn key_to_playlist_selector_event(key: KeyEvent) -> Option<InteractiveEvent> {
+        use crossterm::event::KeyModifiers;
+        
+        match (key.code, key.modifiers) {
+            // Navigation in playlist selector
+            (KeyCode::Up, _) => Some(InteractiveEvent::Up),
+            (KeyCode::Down, _) => Some(InteractiveEvent::Down),
+            
+            // Select playlist or create new one
+            (KeyCode::Enter, _) => Some(InteractiveEvent::SelectPlaylistFromSelector),
+            
+            // Cancel playlist selection
+            (KeyCode::Esc, _) => Some(InteractiveEvent::CancelPlaylistSelector),
+            
+            // Global quit still works
+            (KeyCode::Char('q'), KeyModifiers::NONE) => Some(InteractiveEvent::Quit),
+            (KeyCode::Char('c'), KeyModifiers::CONTROL) => Some(InteractiveEvent::Quit),
+            
+            _ => None,
+        }
+    }
+    
+    fn key_to_chapter_list_event(key: KeyEvent) -> Option<InteractiveEvent> {
+        use crossterm::event::KeyModifiers;
+        
+        match (key.code, key.modifiers) {
+            (KeyCode::Up, _) | (KeyCode::Char('k'), KeyModifiers::NONE) => Some(InteractiveEvent::Up),
+            (KeyCode::Down, _) | (KeyCode::Char('j'), KeyModifiers::NONE) => Some(InteractiveEvent::Down),
+            (KeyCode::Enter, _) => Some(InteractiveEvent::JumpToChapter),
+            (KeyCode::Esc, _) | (KeyCode::Char('K'), _) => Some(InteractiveEvent::CloseChapterList),
+            
+            // Global quit still works
+            (KeyCode::Char('q'), KeyModifiers::NONE) => Some(InteractiveEvent::Quit),
+            (KeyCode::Char('c'), KeyModifiers::CONTROL) => Some(InteractiveEvent::Quit),
+            
+            _ => None,
+        }
+    }
+    
+    fn key_to_error_log_event(key: KeyEvent) -> Option<InteractiveEvent> {
+        use crossterm::event::KeyModifiers;
+        
+        match (key.code, key.modifiers) {
+            (KeyCode::Up, _) | (KeyCode::Char('k'), KeyModifiers::NONE) => Some(InteractiveEvent::Up),
+            (KeyCode::Down, _) | (KeyCode::Char('j'), KeyModifiers::NONE) => Some(InteractiveEvent::Down),
+            (KeyCode::Esc, _) | (KeyCode::Enter, _) | (KeyCode::Char('!'), _) => Some(InteractiveEvent::CloseErrorLog),
+            
+            // Global quit still works
+            (KeyCode::Char('q'), KeyModifiers::NONE) => Some(InteractiveEvent::Quit),
+            (KeyCode::Char('c'), KeyModifiers::CONTROL) => Some(InteractiveEvent::Quit),
+            
+            _ => None,
+        }
+    }
+    
+    fn key_to_bookmark_picker_event(key: KeyEvent) -> Option<InteractiveEvent> {
+        use crossterm::event::KeyModifiers;
+        
+        match (key.code, key.modifiers) {
+            (KeyCode::Up, _) | (KeyCode::Char('k'), KeyModifiers::NONE) => Some(InteractiveEvent::Up),
+            (KeyCode::Down, _) | (KeyCode::Char('j'), KeyModifiers::NONE) => Some(InteractiveEvent::Down),
+            (KeyCode::Enter, _) => Some(InteractiveEvent::JumpToBookmark),
+            (KeyCode::Char('x'), KeyModifiers::NONE) | (KeyCode::Delete, _) => Some(InteractiveEvent::DeleteBookmark),
+            (KeyCode::Esc, _) | (KeyCode::Char('\''), KeyModifiers::NONE) => Some(InteractiveEvent::CloseBookmarkPicker),
+            
+            // Global quit still works
+            (KeyCode::Char('q'), KeyModifiers::NONE) => Some(InteractiveEvent::Quit),
+            (KeyCode::Char('c'), KeyModifiers::CONTROL) => Some(InteractiveEvent::Quit),
+            
+            _ => None,
+        }
+    }
+    
+    fn key_to_excluded_tracks_event(key: KeyEvent) -> Option<InteractiveEvent> {
+        use crossterm::event::KeyModifiers;
+        
+        match (key.code, key.modifiers) {
+            (KeyCode::Up, _) | (KeyCode::Char('k'), KeyModifiers::NONE) => Some(InteractiveEvent::Up),
+            (KeyCode::Down, _) | (KeyCode::Char('j'), KeyModifiers::NONE) => Some(InteractiveEvent::Down),
+            (KeyCode::Enter | KeyCode::Delete, _) | (KeyCode::Char('x'), KeyModifiers::NONE) => Some(InteractiveEvent::IncludeInShuffle),
+            (KeyCode::Esc, _) | (KeyCode::Char('E'), KeyModifiers::SHIFT | KeyModifiers::NONE) => Some(InteractiveEvent::CloseExcludedTracks),
+            
+            // Global quit still works
+            (KeyCode::Char('q'), KeyModifiers::NONE) => Some(InteractiveEvent::Quit),
+            (KeyCode::Char('c'), KeyModifiers::CONTROL) => Some(InteractiveEvent::Quit),
+            
+            _ => None,
+        }
+    }
+    
+    fn key_to_history_event(key: KeyEvent) -> Option<InteractiveEvent> {
+        use crossterm::event::KeyModifiers;
+        
+        match (key.code, key.modifiers) {
+            (KeyCode::Up, _) | (KeyCode::Char('k'), KeyModifiers::NONE) => Some(InteractiveEvent::Up),
+            (KeyCode::Down, _) | (KeyCode::Char('j'), KeyModifiers::NONE) => Some(InteractiveEvent::Down),
+            (KeyCode::Enter, _) => Some(InteractiveEvent::PlayFromHistory),
+            (KeyCode::Esc, _) | (KeyCode::Char('H'), KeyModifiers::SHIFT | KeyModifiers::NONE) => Some(InteractiveEvent::CloseHistory),
+            
+            // Global quit still works
+            (KeyCode::Char('q'), KeyModifiers::NONE) => Some(InteractiveEvent::Quit),
+            (KeyCode::Char('c'), KeyModifiers::CONTROL) => Some(InteractiveEvent::Quit),
+            
+            _ => None,
+        }
+    }
+    
+    fn key_to_more_like_this_event(key: KeyEvent) -> Option<InteractiveEvent> {
+        use crossterm::event::KeyModifiers;
+        
+        match (key.code, key.modifiers) {
+            (KeyCode::Up, _) | (KeyCode::Char('k'), KeyModifiers::NONE) => Some(InteractiveEvent::Up),
+            (KeyCode::Down, _) | (KeyCode::Char('j'), KeyModifiers::NONE) => Some(InteractiveEvent::Down),
+            (KeyCode::Enter, _) => Some(InteractiveEvent::PlayMoreLikeThis),
+            (KeyCode::Char('a'), KeyModifiers::NONE) => Some(InteractiveEvent::QueueMoreLikeThis),
+            (KeyCode::Esc, _) | (KeyCode::Char('L'), KeyModifiers::SHIFT | KeyModifiers::NONE) => Some(InteractiveEvent::CloseMoreLikeThis),
+            
+            // Global quit still works
+            (KeyCode::Char('q'), KeyModifiers::NONE) => Some(InteractiveEvent::Quit),
+            (KeyCode::Char('c'), KeyModifiers::CONTROL) => Some(InteractiveEvent::Quit),
+            
+            _ => None,
+        }
+    }
+    
+    fn key_to_behavior_detail_event(key: KeyEvent) -> Option<InteractiveEvent> {
+        use crossterm::event::KeyModifiers;
+        
+        match (key.code, key.modifiers) {
+            (KeyCode::Esc, _) | (KeyCode::Char('i'), KeyModifiers::NONE) => Some(InteractiveEvent::CloseBehaviorDetail),
+            
+            // Global quit still works
+            (KeyCode::Char('q'), KeyModifiers::NONE) => Some(InteractiveEvent::Quit),
+            (KeyCode::Char('c'), KeyModifiers::CONTROL) => Some(InteractiveEvent::Quit),
+            
+            _ => None,
+        }
+    }
+    
+    fn key_to_duplicates_event(key: KeyEvent) -> Option<InteractiveEvent> {
+        use crossterm::event::KeyModifiers;
+        
+        match (key.code, key.modifiers) {
+            (KeyCode::Up, _) | (KeyCode::Char('k'), KeyModifiers::NONE) => Some(InteractiveEvent::StepDuplicateGroup(-1)),
+            (KeyCode::Down, _) | (KeyCode::Char('j'), KeyModifiers::NONE) => Some(InteractiveEvent::StepDuplicateGroup(1)),
+            (KeyCode::Left, _) => Some(InteractiveEvent::StepDuplicateKeep(-1)),
+            (KeyCode::Right, _) => Some(InteractiveEvent::StepDuplicateKeep(1)),
+            (KeyCode::Char('h'), KeyModifiers::NONE) => Some(InteractiveEvent::ResolveDuplicates(DuplicateAction::Hide)),
+            (KeyCode::Char('x'), KeyModifiers::NONE) => Some(InteractiveEvent::ResolveDuplicates(DuplicateAction::Delete)),
+            (KeyCode::Char('r'), KeyModifiers::NONE) => Some(InteractiveEvent::ToggleDuplicatesDryRun),
+            (KeyCode::Esc, _) => Some(InteractiveEvent::CloseDuplicates),
+            
+            // Global quit still works
+            (KeyCode::Char('q'), KeyModifiers::NONE) => Some(InteractiveEvent::Quit),
+            (KeyCode::Char('c'), KeyModifiers::CONTROL) => Some(InteractiveEvent::Quit),
+            
+            _ => None,
+        }
+    }
+    
+    fn key_to_rename_preview_event(key: KeyEvent) -> Option<InteractiveEvent> {
+        use crossterm::event::KeyModifiers;
+        
+        match (key.code, key.modifiers) {
+            (KeyCode::Up, _) | (KeyCode::Char('k'), KeyModifiers::NONE) => Some(InteractiveEvent::ScrollRenamePreview(-1)),
+            (KeyCode::Down, _) | (KeyCode::Char('j'), KeyModifiers::NONE) => Some(InteractiveEvent::ScrollRenamePreview(1)),
+            (KeyCode::PageUp, _) => Some(InteractiveEvent::ScrollRenamePreview(-10)),
+            (KeyCode::PageDown, _) => Some(InteractiveEvent::ScrollRenamePreview(10)),
+            (KeyCode::Char('r'), KeyModifiers::NONE) => Some(InteractiveEvent::ToggleRenameDryRun),
+            (KeyCode::Enter, _) => Some(InteractiveEvent::ApplyRenames),
+            (KeyCode::Esc, _) => Some(InteractiveEvent::CloseRenamePreview),
+            
+            // Global quit still works
+            (KeyCode::Char('q'), KeyModifiers::NONE) => Some(InteractiveEvent::Quit),
+            (KeyCode::Char('c'), KeyModifiers::CONTROL) => Some(InteractiveEvent::Quit),
+            
+            _ => None,
+        }
+    }
+    
+    fn key_to_metadata_matches_event(key: KeyEvent) -> Option<InteractiveEvent> {
+        use crossterm::event::KeyModifiers;
+        
+        match (key.code, key.modifiers) {
+            (KeyCode::Up, _) | (KeyCode::Char('k'), KeyModifiers::NONE) => Some(InteractiveEvent::StepMetadataMatch(-1)),
+            (KeyCode::Down, _) | (KeyCode::Char('j'), KeyModifiers::NONE) => Some(InteractiveEvent::StepMetadataMatch(1)),
+            (KeyCode::Enter, _) => Some(InteractiveEvent::ApplyMetadataMatch),
+            (KeyCode::Esc, _) => Some(InteractiveEvent::CloseMetadataMatches),
+            
+            // Global quit still works
+            (KeyCode::Char('q'), KeyModifiers::NONE) => Some(InteractiveEvent::Quit),
+            (KeyCode::Char('c'), KeyModifiers::CONTROL) => Some(InteractiveEvent::Quit),
+            
+            _ => None,
+        }
+    }
+    
+    fn key_to_device_picker_event(key: KeyEvent) -> Option<InteractiveEvent> {
+        use crossterm::event::KeyModifiers;
+        
+        match (key.code, key.modifiers) {
+            (KeyCode::Up, _) => Some(InteractiveEvent::Up),
+            (KeyCode::Down, _) => Some(InteractiveEvent::Down),
+            (KeyCode::Enter, _) => Some(InteractiveEvent::SelectOutputDevice),
+            (KeyCode::Esc, _) => Some(InteractiveEvent::CancelDevicePicker),
+            
+            // Global quit still works
+            (KeyCode::Char('q'), KeyModifiers::NONE) => Some(InteractiveEvent::Quit),
+            (KeyCode::Char('c'), KeyModifiers::CONTROL) => Some(InteractiveEvent::Quit),
+            
+            _ => None,
+        }
+    }
+    
+    fn key_to_scan_report_event(key: KeyEvent) -> Option<InteractiveEvent> {
+        use crossterm::event::KeyModifiers;
+        
+        match (key.code, key.modifiers) {
+            // Scroll the report
+            (KeyCode::Up, _) => Some(InteractiveEvent::Up),
+            (KeyCode::Down, _) => Some(InteractiveEvent::Down),
+            
+            // Close the report
+            (KeyCode::Esc, _) | (KeyCode::Char('d'), KeyModifiers::NONE) => Some(InteractiveEvent::ToggleScanReport),
+            
+            // Global quit still works
+            (KeyCode::Char('q'), KeyModifiers::NONE) => Some(InteractiveEvent::Quit),
+            (KeyCode::Char('c'), KeyModifiers::CONTROL) => Some(InteractiveEvent::Quit),
+            
+            _ => None,
+        }
+    }
+    
+    fn key_to_app_event_basic(&self, key: KeyEvent) -> Option<InteractiveEvent> {
+        use crossterm::event::KeyModifiers;
+        
+        // Mid-jump, letters (and spaces, for "the b...") keep extending the prefix instead of running commands
+        if let KeyCode::Char(c) = key.code {
+            let plain = matches!(key.modifiers, KeyModifiers::NONE | KeyModifiers::SHIFT);
+            if plain && self.type_jump_active() && (c.is_alphanumeric() || c == ' ') {
+                return Some(InteractiveEvent::TypeJump(c));
+            }
+        }
+        
+        // While editing, plain characters are text - only keys with Ctrl/Alt or no character run commands
+        let typing = self.edit_mode != EditMode::None
+            && matches!(key.code, KeyCode::Char(_))
+            && !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+        
+        if !typing {
+            let bound = self.keymap.lookup(KeyCombo::from_event(&key), self.current_tab.key_scope());
+            // Resume only means something while a resume is on offer; otherwise the key falls through
+            let bound = bound.filter(|&action| action != Action::ResumeLastTrack || self.pending_resume.is_some());
+            if let Some(action) = bound {
+                return Some(Self::action_event(action));
+            }
+        }
+        
+        match (key.code, key.modifiers) {
+            (KeyCode::Esc, _) => Some(InteractiveEvent::Escape),
+            (KeyCode::Backspace, _) => Some(InteractiveEvent::Backspace),
+            
+            // Letters with no command of their own start a type-to-jump in the flat library list
+            (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT)
+                if c.is_alphanumeric()
+                    && self.current_tab == AppTab::Library
+                    && self.library_view == GroupBy::None
+                    && self.edit_mode == EditMode::None =>
+            {
+                Some(InteractiveEvent::TypeJump(c))
+            }
+            
+            // Catch-all for text input
+            (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) if !c.is_control() => Some(InteractiveEvent::Input(c)),
+            _ => None,
+        }
+    }
+    
+    /// The event a bound key sends
+    fn action_event(action: Action) -> InteractiveEvent {
+        match action {
+            Action::Quit => InteractiveEvent::Quit,
+            Action::ShowHelp => InteractiveEvent::ShowHelp,
+            Action::Search => InteractiveEvent::EnterSearch,
+            Action::TabLibrary => InteractiveEvent::SwitchToLibrary,
+            Action::TabPlaylists => InteractiveEvent::SwitchToPlaylists,
+            Action::TabMetadata => InteractiveEvent::SwitchToMetadataEditor,
+            Action::TabSettings => InteractiveEvent::SwitchToSettings,
+            Action::TabStats => InteractiveEvent::SwitchToStats,
+            Action::Up => InteractiveEvent::Up,
+            Action::Down => InteractiveEvent::Down,
+            Action::GotoTop => InteractiveEvent::GotoPrefix,
+            Action::JumpToTop => InteractiveEvent::JumpToTop,
+            Action::JumpToBottom => InteractiveEvent::JumpToBottom,
+            Action::HalfPageDown => InteractiveEvent::HalfPage(1),
+            Action::HalfPageUp => InteractiveEvent::HalfPage(-1),
+            Action::PageDown => InteractiveEvent::Page(1),
+            Action::PageUp => InteractiveEvent::Page(-1),
+            Action::Play => InteractiveEvent::Play,
+            Action::PlayPause => InteractiveEvent::TogglePlayPause,
+            Action::NextTrack => InteractiveEvent::NextTrack,
+            Action::PreviousTrack => InteractiveEvent::PreviousTrack,
+            Action::Stop => InteractiveEvent::Stop,
+            Action::VolumeUp => InteractiveEvent::VolumeUp,
+            Action::VolumeDown => InteractiveEvent::VolumeDown,
+            Action::Mute => InteractiveEvent::ToggleMute,
+            Action::Shuffle => InteractiveEvent::ToggleShuffle,
+            Action::Repeat => InteractiveEvent::ToggleRepeat,
+            Action::SpeedDown => InteractiveEvent::ChangeSpeed(-SPEED_STEP),
+            Action::SpeedUp => InteractiveEvent::ChangeSpeed(SPEED_STEP),
+            Action::SpeedReset => InteractiveEvent::ResetSpeed,
+            Action::RemainingTime => InteractiveEvent::ToggleRemainingTime,
+            Action::Lyrics => InteractiveEvent::ToggleLyrics,
+            Action::LoopStart => InteractiveEvent::SetLoopStart,
+            Action::LoopEnd => InteractiveEvent::SetLoopEnd,
+            Action::LoopClear => InteractiveEvent::ClearLoop,
+            Action::SleepTimer => InteractiveEvent::CycleSleepTimer,
+            Action::SleepTimerCancel => InteractiveEvent::CancelSleepTimer,
+            Action::StopAfterCurrent => InteractiveEvent::ToggleStopAfterCurrent,
+            Action::AddBookmark => InteractiveEvent::AddBookmark,
+            Action::Bookmarks => InteractiveEvent::OpenBookmarkPicker,
+            Action::Chapters => InteractiveEvent::OpenChapterList,
+            Action::NextChapter => InteractiveEvent::StepChapter(1),
+            Action::PreviousChapter => InteractiveEvent::StepChapter(-1),
+            Action::History => InteractiveEvent::OpenHistory,
+            Action::ErrorLog => InteractiveEvent::OpenErrorLog,
+            Action::RevealFile => InteractiveEvent::RevealFile,
+            Action::SaveAll => InteractiveEvent::SaveAll,
+            Action::ResumeLastTrack => InteractiveEvent::ResumeLastTrack,
+            Action::AddToPlaylist => InteractiveEvent::AddToPlaylist,
+            Action::ToggleMark => InteractiveEvent::ToggleMark,
+            Action::MarkAll => InteractiveEvent::MarkAllFiltered,
+            Action::CycleSort => InteractiveEvent::CycleSort,
+            Action::SortDirection => InteractiveEvent::ToggleSortDirection,
+            Action::CycleTagFilter => InteractiveEvent::CycleTagFilter,
+            Action::ClearTagFilter => InteractiveEvent::ClearTagFilter,
+            Action::LibraryView => InteractiveEvent::CycleLibraryView,
+            Action::BehaviorDetail => InteractiveEvent::ShowBehaviorDetail,
+            Action::ResetTrackBehavior => InteractiveEvent::ResetTrackBehavior,
+            Action::PlayAlbum => InteractiveEvent::PlayAlbum,
+            Action::ReloadTrack => InteractiveEvent::ReloadTrack,
+            Action::ExcludeFromShuffle => InteractiveEvent::ToggleShuffleExclusion,
+            Action::MoreLikeThis => InteractiveEvent::OpenMoreLikeThis,
+            Action::SmartMix => InteractiveEvent::GenerateSmartMix,
+            Action::TogglePlaylist => InteractiveEvent::TogglePlaylistExpansion,
+            Action::LoadPlaylist => InteractiveEvent::LoadPlaylist,
+            Action::RenamePlaylist => InteractiveEvent::RenamePlaylist,
+            Action::RemoveFromPlaylist => InteractiveEvent::RemoveFromPlaylist,
+            Action::DeletePlaylist => InteractiveEvent::DeletePlaylist,
+            Action::MoveTrackUp => InteractiveEvent::MoveTrackUp,
+            Action::MoveTrackDown => InteractiveEvent::MoveTrackDown,
+            Action::PlaylistShuffle => InteractiveEvent::CyclePlaylistShuffle,
+            Action::PlaylistRepeat => InteractiveEvent::CyclePlaylistRepeat,
+            Action::EditTitle => InteractiveEvent::EditTitle,
+            Action::EditArtist => InteractiveEvent::EditArtist,
+            Action::SaveMetadata => InteractiveEvent::SaveMetadata,
+            Action::ApplySuggestion => InteractiveEvent::ApplySuggestion,
+            Action::BulkApplySuggestions => InteractiveEvent::BulkApplySuggestions,
+            Action::ClearMetadata => InteractiveEvent::ClearMetadata,
+            Action::RenameFiles => InteractiveEvent::PreviewRenames,
+            Action::LookupMetadata => InteractiveEvent::LookupMetadata,
+            Action::EqBandPrevious => InteractiveEvent::SelectEqBand(-1),
+            Action::EqBandNext => InteractiveEvent::SelectEqBand(1),
+            Action::EqGainDown => InteractiveEvent::AdjustEqGain(-1.0),
+            Action::EqGainUp => InteractiveEvent::AdjustEqGain(1.0),
+            Action::EqPreset => InteractiveEvent::CycleEqPreset,
+            Action::ChannelMode => InteractiveEvent::CycleChannelMode,
+            Action::OutputDevice => InteractiveEvent::OpenDevicePicker,
+            Action::Theme => InteractiveEvent::CycleTheme,
+            Action::ScanReport => InteractiveEvent::ToggleScanReport,
+            Action::FindDuplicates => InteractiveEvent::FindDuplicates,
+            Action::ExportPlaylists => InteractiveEvent::ExportPlaylists,
+            Action::Visualizer => InteractiveEvent::ToggleVisualizer,
+            Action::ResetAllBehavior => InteractiveEvent::ResetAllBehavior,
+            Action::ExcludedTracks => InteractiveEvent::OpenExcludedTracks,
+            Action::ExportHistory => InteractiveEvent::ExportListeningHistory,
+        }
+    }
+    
+    async fn handle_event(&mut self, event: InteractiveEvent) -> Result<()> {
+        // Context-aware event filtering
+        let should_process = match (&event, &self.current_tab, &self.edit_mode) {
+            // An open confirmation only takes its own answer; playback housekeeping carries on underneath
+            (event, _, _) if self.pending_confirm.is_some() => matches!(
+                event,
+                InteractiveEvent::AcceptConfirm
+                    | InteractiveEvent::DeclineConfirm
+                    | InteractiveEvent::DismissConfirm
+                    | InteractiveEvent::Quit
+                    | InteractiveEvent::QuitNow
+                    | InteractiveEvent::Tick
+                    | InteractiveEvent::LibraryChanged(_)
+                    | InteractiveEvent::TaskProgress { .. }
+                    | InteractiveEvent::SuggestionsParsed(_)
+                    | InteractiveEvent::TaskFinished(_)
+            ),
+            // An analysis that finished while a confirmation was up is still worth keeping
+            #[cfg(feature = "silence")]
+            (InteractiveEvent::SilenceDetected(..), _, _) => true,
+            // Background task reports, and cancelling from its overlay
+            (InteractiveEvent::TaskProgress { .. }, _, _) => true,
+            (InteractiveEvent::SuggestionsParsed(_), _, _) => true,
+            (InteractiveEvent::TaskFinished(_), _, _) => true,
+            (InteractiveEvent::CancelTask, _, _) => true,
+            // Global events always process
+            (InteractiveEvent::Quit, _, _) => true,
+            (InteractiveEvent::QuitNow, _, _) => true,
+            (InteractiveEvent::SwitchToLibrary, _, _) => true,
+            (InteractiveEvent::SwitchToPlaylists, _, _) => true,
+            (InteractiveEvent::SwitchToMetadataEditor, _, _) => true,
+            (InteractiveEvent::SwitchToSettings, _, _) => true,
+            (InteractiveEvent::SwitchToStats, _, _) => true,
+            (InteractiveEvent::Up, _, _) => true,
+            (InteractiveEvent::Down, _, _) => true,
+            (InteractiveEvent::GotoPrefix, _, EditMode::None) => true,
+            (InteractiveEvent::JumpToBottom, _, EditMode::None) => true,
+            (InteractiveEvent::HalfPage(_), _, EditMode::None) => true,
+            (InteractiveEvent::Page(_), _, EditMode::None) => true,
+            (InteractiveEvent::JumpToTop, _, EditMode::None) => true,
+            (InteractiveEvent::TypeJump(_), AppTab::Library, EditMode::None) => true,
+            (InteractiveEvent::CycleSort, AppTab::Library, EditMode::None) => true,
+            (InteractiveEvent::CycleSleepTimer, _, EditMode::None) => true,
+            (InteractiveEvent::CancelSleepTimer, _, EditMode::None) => true,
+            (InteractiveEvent::ToggleStopAfterCurrent, _, EditMode::None) => true,
+            (InteractiveEvent::ResumeLastTrack, _, EditMode::None) => true,
+            (InteractiveEvent::SetLoopStart, _, EditMode::None) => true,
+            (InteractiveEvent::SetLoopEnd, _, EditMode::None) => true,
+            (InteractiveEvent::ClearLoop, _, EditMode::None) => true,
+            (InteractiveEvent::ChangeSpeed(_), _, EditMode::None) => true,
+            (InteractiveEvent::ResetSpeed, _, EditMode::None) => true,
+            (InteractiveEvent::ToggleRemainingTime, _, EditMode::None) => true,
+            (InteractiveEvent::ToggleLyrics, _, EditMode::None) => true,
+            #[cfg(feature = "lyrics")]
+            (InteractiveEvent::LyricsLoaded(..), _, _) => true,
+            (InteractiveEvent::SeekToFraction(_), _, _) => true,
+            (InteractiveEvent::AddBookmark, _, EditMode::None) => true,
+            (InteractiveEvent::OpenBookmarkPicker, _, EditMode::None) => true,
+            (InteractiveEvent::OpenChapterList, _, EditMode::None) => true,
+            (InteractiveEvent::StepChapter(_), _, EditMode::None) => true,
+            (InteractiveEvent::OpenHistory, _, EditMode::None) => true,
+            (InteractiveEvent::OpenErrorLog, _, EditMode::None) => true,
+            (InteractiveEvent::RevealFile, _, EditMode::None) => true,
+            (InteractiveEvent::SaveAll, _, EditMode::None) => true,
+            (InteractiveEvent::ShowBehaviorDetail, AppTab::Library, EditMode::None) => true,
+            (InteractiveEvent::OpenMoreLikeThis, AppTab::Library, EditMode::None) => true,
+            (InteractiveEvent::ToggleSortDirection, AppTab::Library, EditMode::None) => true,
+            (InteractiveEvent::ToggleMark, AppTab::Library, EditMode::None) => true,
+            (InteractiveEvent::MarkAllFiltered, AppTab::Library, EditMode::None) => true,
+            (InteractiveEvent::CycleLibraryView, AppTab::Library, EditMode::None) => true,
+            (InteractiveEvent::CycleTagFilter, AppTab::Library, EditMode::None) => true,
+            (InteractiveEvent::ClearTagFilter, AppTab::Library, EditMode::None) => true,
+            (InteractiveEvent::Tick, _, _) => true,
+            (InteractiveEvent::LibraryChanged(_), _, _) => true,
+            (InteractiveEvent::ShowHelp, _, _) => true, // Help overlay should work globally
+            (InteractiveEvent::Escape, _, _) => !self.mode_stack().is_empty(), // never quits, only leaves modes
+            
+            // Search events - should work globally
+            (InteractiveEvent::EnterSearch, _, _) => true,
+            (InteractiveEvent::ExitSearch, _, _) => true,
+            (InteractiveEvent::SearchInput(_), _, _) => true,
+            (InteractiveEvent::SearchBackspace, _, _) => true,
+            (InteractiveEvent::CycleSearchScope, _, _) => true,
+            
+            // Playlist creation input events - should work when in playlist creation mode
+            (InteractiveEvent::PlaylistInput(_), _, _) => true,
+            (InteractiveEvent::PlaylistBackspace, _, _) => true,
+            (InteractiveEvent::ConfirmPlaylistCreation, _, _) => true,
+            (InteractiveEvent::CancelPlaylistCreation, _, _) => true,
+            
+            // Playlist selector overlay events - should work when overlay is shown
+            (InteractiveEvent::SelectPlaylistFromSelector, _, _) => true,
+            (InteractiveEvent::CancelPlaylistSelector, _, _) => true,
+            
+            // Output device picker overlay
+            (InteractiveEvent::SelectOutputDevice, _, _) => true,
+            (InteractiveEvent::CancelDevicePicker, _, _) => true,
+            
+            // Bookmark picker overlay
+            (InteractiveEvent::JumpToBookmark, _, _) => true,
+            (InteractiveEvent::DeleteBookmark, _, _) => true,
+            (InteractiveEvent::CloseBookmarkPicker, _, _) => true,
+            (InteractiveEvent::JumpToChapter, _, _) => true,
+            (InteractiveEvent::CloseChapterList, _, _) => true,
+            (InteractiveEvent::PlayFromHistory, _, _) => true,
+            (InteractiveEvent::CloseHistory, _, _) => true,
+            (InteractiveEvent::CloseErrorLog, _, _) => true,
+            (InteractiveEvent::IncludeInShuffle, _, _) => true,
+            (InteractiveEvent::CloseExcludedTracks, _, _) => true,
+            (InteractiveEvent::CloseBehaviorDetail, _, _) => true,
+            (InteractiveEvent::PlayMoreLikeThis, _, _) => true,
+            (InteractiveEvent::QueueMoreLikeThis, _, _) => true,
+            (InteractiveEvent::CloseMoreLikeThis, _, _) => true,
+            (InteractiveEvent::CloseDuplicates, _, _) => true,
+            (InteractiveEvent::StepDuplicateGroup(_), _, _) => true,
+            (InteractiveEvent::StepDuplicateKeep(_), _, _) => true,
+            (InteractiveEvent::ToggleDuplicatesDryRun, _, _) => true,
+            (InteractiveEvent::ResolveDuplicates(_), _, _) => true,
+            (InteractiveEvent::CloseRenamePreview, _, _) => true,
+            (InteractiveEvent::ScrollRenamePreview(_), _, _) => true,
+            (InteractiveEvent::ToggleRenameDryRun, _, _) => true,
+            (InteractiveEvent::ApplyRenames, _, _) => true,
+            #[cfg(feature = "musicbrainz")]
+            (InteractiveEvent::MetadataMatchesFound(..), _, _) => true,
+            (InteractiveEvent::CloseMetadataMatches, _, _) => true,
+            (InteractiveEvent::StepMetadataMatch(_), _, _) => true,
+            (InteractiveEvent::ApplyMetadataMatch, _, _) => true,
+            
+            // Editing mode events (highest priority)
+            (InteractiveEvent::SaveMetadata, _, EditMode::Title | EditMode::Artist) => true,
+            (InteractiveEvent::Backspace, _, EditMode::Title | EditMode::Artist) => true,
+            (InteractiveEvent::Input(_), _, EditMode::Title | EditMode::Artist) => true,
+            
+            // Metadata editor events (when not editing)
+            (InteractiveEvent::EditTitle, AppTab::MetadataEditor, EditMode::None) => true,
+            (InteractiveEvent::EditArtist, AppTab::MetadataEditor, EditMode::None) => true,
+            (InteractiveEvent::ApplySuggestion, AppTab::MetadataEditor, EditMode::None) => true,
+            (InteractiveEvent::ResetToOriginal, AppTab::MetadataEditor, EditMode::None) => true,
+            (InteractiveEvent::BulkApplySuggestions, AppTab::MetadataEditor, EditMode::None) => true,
+            (InteractiveEvent::ClearMetadata, AppTab::MetadataEditor, EditMode::None) => true,
+            (InteractiveEvent::PreviewRenames, AppTab::MetadataEditor, EditMode::None) => true,
+            (InteractiveEvent::LookupMetadata, AppTab::MetadataEditor, EditMode::None) => true,
+            (InteractiveEvent::ResetTrackBehavior, AppTab::Library, EditMode::None) => true,
+            (InteractiveEvent::PlayAlbum, AppTab::Library, EditMode::None) => true,
+            (InteractiveEvent::ReloadTrack, AppTab::Library, EditMode::None) => true,
+            (InteractiveEvent::ToggleShuffleExclusion, AppTab::Library, EditMode::None) => true,
+            (InteractiveEvent::ResetAllBehavior, AppTab::Settings, EditMode::None) => true,
+            (InteractiveEvent::OpenExcludedTracks, AppTab::Settings, EditMode::None) => true,
+            
+            // Playlist events (when not editing)
+            (InteractiveEvent::LoadPlaylist, AppTab::Playlists, EditMode::None) => true,
+            (InteractiveEvent::TogglePlaylistExpansion, AppTab::Playlists, EditMode::None) => true,
+            (InteractiveEvent::DeletePlaylist, AppTab::Playlists, EditMode::None) => true,
+            (InteractiveEvent::MoveTrackUp, AppTab::Playlists, EditMode::None) => true,
+            (InteractiveEvent::MoveTrackDown, AppTab::Playlists, EditMode::None) => true,
+            (InteractiveEvent::GenerateSmartMix, AppTab::Library | AppTab::Playlists, EditMode::None) => true,
+            (InteractiveEvent::ExportListeningHistory, AppTab::Stats, EditMode::None) => true,
+            (InteractiveEvent::CycleTheme, AppTab::Settings, EditMode::None) => true,
+            (InteractiveEvent::ToggleScanReport, AppTab::Settings, EditMode::None) => true,
+            (InteractiveEvent::FindDuplicates, AppTab::Settings, EditMode::None) => true,
+            (InteractiveEvent::ExportPlaylists, AppTab::Settings, EditMode::None) => true,
+            (InteractiveEvent::ToggleVisualizer, AppTab::Settings, EditMode::None) => true,
+            (InteractiveEvent::CycleEqPreset, AppTab::Settings, EditMode::None) => true,
+            (InteractiveEvent::CycleChannelMode, AppTab::Settings, EditMode::None) => true,
+            (InteractiveEvent::SelectEqBand(_), AppTab::Settings, EditMode::None) => true,
+            (InteractiveEvent::AdjustEqGain(_), AppTab::Settings, EditMode::None) => true,
+            (InteractiveEvent::OpenDevicePicker, AppTab::Settings, EditMode::None) => true,
+            (InteractiveEvent::CyclePlaylistShuffle, AppTab::Playlists, EditMode::None) => true,
+            (InteractiveEvent::CyclePlaylistRepeat, AppTab::Playlists, EditMode::None) => true,
+            (InteractiveEvent::AddToPlaylist, AppTab::Library, EditMode::None) => true,
+            
+            // 'r' key context-sensitive handling
+            (InteractiveEvent::ToggleRepeat, AppTab::Library, EditMode::None) => true,
+            (InteractiveEvent::ToggleRepeat, AppTab::MetadataEditor, EditMode::None) => false, // Block in metadata editor
+            
+            // Playback controls work in both tabs when not editing
+            (InteractiveEvent::TogglePlayPause, _, EditMode::None) => true,
+            (InteractiveEvent::Play, _, EditMode::None) => true,
+            (InteractiveEvent::NextTrack, _, EditMode::None) => true,
+            (InteractiveEvent::PreviousTrack, _, EditMode::None) => true,
+            (InteractiveEvent::Stop, _, EditMode::None) => true,
+            (InteractiveEvent::ToggleShuffle, _, EditMode::None) => true,
+            (InteractiveEvent::VolumeUp, _, EditMode::None) => true,
+            (InteractiveEvent::VolumeDown, _, EditMode::None) => true,
+            (InteractiveEvent::ToggleMute, _, EditMode::None) => true,
+            
+            // Block other events when editing or in wrong context
+            _ => false,
+        };
+        
+        if !should_process {
+            return Ok(());
+        }
+        // Ticks decide for themselves whether anything visible moved
+        if !matches!(event, InteractiveEvent::Tick) {
+            self.needs_redraw = true;
+        }
+        
+        match event {
+            InteractiveEvent::Quit => {
+                let count = self.unsaved_count();
+                // Asked once; quitting again at the prompt (Ctrl+C) means it
+                if count > 0 && !matches!(self.pending_confirm, Some(ConfirmAction::QuitUnsaved { .. })) {
+                    self.pending_confirm = Some(ConfirmAction::QuitUnsaved { count });
+                } else {
+                    self.quit();
+                }
+            }
+            InteractiveEvent::QuitNow => {
+                self.quit();
+            }
+            InteractiveEvent::SaveAll => {
+                self.save_all().await;
+            }
+            InteractiveEvent::Up => {
+                if self.show_scan_report {
+                    self.scan_report_scroll = self.scan_report_scroll.saturating_sub(1);
+                } else if self.show_device_picker {
+                    self.step_device_picker(-1);
+                } else if self.show_bookmark_picker {
+                    self.step_bookmark_picker(-1);
+                } else if self.show_chapter_list {
+                    self.step_chapter_list(-1);
+                } else if self.history.is_some() {
+                    self.step_history(-1);
+                } else if self.show_error_log {
+                    self.step_error_log(-1);
+                } else if self.show_excluded_tracks {
+                    self.step_excluded_tracks(-1);
+                } else if self.more_like_this.is_some() {
+                    self.step_more_like_this(-1);
+                } else {
+                    self.move_selection(-1);
+                }
+            }
+            InteractiveEvent::Down => {
+                if self.show_scan_report {
+                    self.scan_report_scroll = self.scan_report_scroll.saturating_add(1);
+                } else if self.show_device_picker {
+                    self.step_device_picker(1);
+                } else if self.show_bookmark_picker {
+                    self.step_bookmark_picker(1);
+                } else if self.show_chapter_list {
+                    self.step_chapter_list(1);
+                } else if self.history.is_some() {
+                    self.step_history(1);
+                } else if self.show_error_log {
+                    self.step_error_log(1);
+                } else if self.show_excluded_tracks {
+                    self.step_excluded_tracks(1);
+                } else if self.more_like_this.is_some() {
+                    self.step_more_like_this(1);
+                } else {
+                    self.move_selection(1);
+                }
+            }
+            InteractiveEvent::GotoPrefix => {
+                if self.pending_g {
+                    self.pending_g = false;
+                    self.move_selection_to(0);
+                } else {
+                    self.pending_g = true;
+                }
+            }
+            InteractiveEvent::TypeJump(c) => {
+                self.type_jump(c);
+            }
+            InteractiveEvent::CycleSort => {
+                self.sort_field = self.sort_field.next();
+                if self.sort_field == SortField::Plays {
+                    self.load_play_counts().await;
+                }
+                self.resort_library();
+            }
+            InteractiveEvent::CycleSleepTimer => {
+                self.cycle_sleep_timer();
+            }
+            InteractiveEvent::CancelSleepTimer => {
+                if self.sleep_timer.take().is_some() {
+                    self.audio_player.set_fade_level(1.0);
+                    self.set_status("💤 Sleep timer cancelled");
+                }
+            }
+            InteractiveEvent::SetLoopStart => {
+                if self.current_track_index.is_some() {
+                    self.ab_loop = Some(AbLoop { a: self.current_position, b: None });
+                    self.set_status(&format!("🔁 Loop A set at {}", format_position(self.current_position)));
+                }
+            }
+            InteractiveEvent::SetLoopEnd => {
+                match self.ab_loop {
+                    Some(AbLoop { a, .. }) if self.current_position > a => {
+                        self.ab_loop = Some(AbLoop { a, b: Some(self.current_position) });
+                        self.set_status(&format!(
+                            "🔁 Looping {} - {} (\\ to clear)",
+                            format_position(a),
+                            format_position(self.current_position)
+                        ));
+                    }
+                    Some(_) => self.set_status_level(NotificationLevel::Warning, "⚠️ Loop B must come after A"),
+                    None => self.set_status_level(NotificationLevel::Warning, "⚠️ Set loop start with [ first"),
+                }
+            }
+            InteractiveEvent::ClearLoop => {
+                if self.ab_loop.take().is_some() {
+                    self.set_status("🔁 Loop cleared");
+                }
+            }
+            InteractiveEvent::ChangeSpeed(delta) => {
+                // Round to whole steps so repeated presses don't accumulate float drift
+                let speed = ((self.audio_player.get_speed() + delta) / SPEED_STEP).round() * SPEED_STEP;
+                self.set_speed(speed);
+            }
+            InteractiveEvent::ResetSpeed => self.set_speed(1.0),
+            InteractiveEvent::ToggleRemainingTime => {
+                self.config.ui.show_remaining_time = !self.config.ui.show_remaining_time;
+                if let Err(e) = self.config.save() {
+                    warn!("Failed to save time display to config: {}", e);
+                }
+            }
+            InteractiveEvent::ToggleLyrics => self.toggle_lyrics(),
+            #[cfg(feature = "lyrics")]
+            InteractiveEvent::LyricsLoaded(path, lyrics) => {
+                // The pane may have moved on to another track while this one was fetched
+                if let Some((shown, state)) = self.lyrics.as_mut() {
+                    if *shown == path {
+                        *state = lyrics.map_or(LyricsState::NotFound, LyricsState::Found);
+                    }
+                }
+            }
+            #[cfg(feature = "silence")]
+            InteractiveEvent::SilenceDetected(content_hash, bounds) => match bounds {
+                Ok(bounds) => {
+                    if let Err(e) = self.behavior_tracker.save_silence_bounds(content_hash, bounds) {
+                        warn!("Failed to save silence bounds: {}", e);
+                    }
+                    // The track may have changed while it was being decoded
+                    if let Some(skip) = self.silence.as_mut().filter(|skip| skip.content_hash == content_hash) {
+                        skip.bounds = Some(bounds);
+                    }
+                }
+                Err(e) => debug!("Could not look for silence: {}", e),
+            },
+            InteractiveEvent::SeekToFraction(fraction) => {
+                if let Some(total) = self.total_duration {
+                    self.seek_to(total.mul_f64(fraction.clamp(0.0, 1.0)));
+                }
+            }
+            InteractiveEvent::ResumeLastTrack => {
+                self.resume_last_track().await?;
+            }
+            InteractiveEvent::ToggleMark => {
+                if let Some(track_idx) = self.selected_library_track() {
+                    if !self.marked_tracks.remove(&track_idx) {
+                        self.marked_tracks.insert(track_idx);
+                    }
+                    self.move_selection(1);
+                }
+            }
+            InteractiveEvent::MarkAllFiltered => {
+                // A second press on a fully marked view clears the marks
+                if self.filtered_tracks.iter().all(|idx| self.marked_tracks.contains(idx)) {
+                    self.marked_tracks.clear();
+                    self.set_status("☐ Marks cleared");
+                } else {
+                    self.marked_tracks.extend(self.filtered_tracks.iter().copied());
+                    self.set_status(&format!("✓ {} tracks marked ('a' adds them to a playlist)", self.marked_tracks.len()));
+                }
+            }
+            InteractiveEvent::CycleLibraryView => {
+                self.cycle_library_view();
+            }
+            InteractiveEvent::CycleTagFilter => {
+                self.cycle_tag_filter().await;
+            }
+            InteractiveEvent::ClearTagFilter => {
+                self.clear_tag_filter();
+            }
+            InteractiveEvent::AddBookmark => {
+                self.add_bookmark();
+            }
+            InteractiveEvent::OpenBookmarkPicker => {
+                self.open_bookmark_picker();
+            }
+            InteractiveEvent::JumpToBookmark => {
+                self.show_bookmark_picker = false;
+                if let Some(bookmark) = self.bookmark_picker_state.selected().and_then(|row| self.bookmarks.get(row)).cloned() {
+                    self.seek_to(bookmark.position());
+                    self.set_status(&format!("🔖 {} ({})", bookmark.label, format_position(bookmark.position())));
+                }
+            }
+            InteractiveEvent::DeleteBookmark => {
+                self.delete_selected_bookmark();
+            }
+            InteractiveEvent::CloseBookmarkPicker => {
+                self.show_bookmark_picker = false;
+            }
+            InteractiveEvent::OpenChapterList => {
+                self.open_chapter_list();
+            }
+            InteractiveEvent::StepChapter(delta) => {
+                self.step_chapter(delta);
+            }
+            InteractiveEvent::JumpToChapter => {
+                self.show_chapter_list = false;
+                if let Some(idx) = self.chapter_list_state.selected() {
+                    self.jump_to_chapter(idx);
+                }
+            }
+            InteractiveEvent::CloseChapterList => {
+                self.show_chapter_list = false;
+            }
+            InteractiveEvent::OpenHistory => {
+                self.open_history();
+            }
+            InteractiveEvent::PlayFromHistory => {
+                self.play_from_history().await?;
+            }
+            InteractiveEvent::OpenExcludedTracks => {
+                self.open_excluded_tracks();
+            }
+            InteractiveEvent::IncludeInShuffle => {
+                self.include_selected_in_shuffle().await;
+            }
+            InteractiveEvent::CloseExcludedTracks => {
+                self.show_excluded_tracks = false;
+            }
+            InteractiveEvent::CloseHistory => {
+                self.history = None;
+            }
+            InteractiveEvent::RevealFile => {
+                self.reveal_track_file();
+            }
+            InteractiveEvent::OpenErrorLog => {
+                self.open_error_log();
+            }
+            InteractiveEvent::CloseErrorLog => {
+                self.show_error_log = false;
+            }
+            InteractiveEvent::ShowBehaviorDetail => {
+                self.open_behavior_detail().await;
+            }
+            InteractiveEvent::CloseBehaviorDetail => {
+                self.behavior_detail = None;
+            }
+            InteractiveEvent::OpenMoreLikeThis => {
+                self.open_more_like_this().await;
+            }
+            InteractiveEvent::PlayMoreLikeThis => {
+                self.play_more_like_this().await?;
+            }
+            InteractiveEvent::QueueMoreLikeThis => {
+                self.queue_more_like_this().await?;
+            }
+            InteractiveEvent::CloseMoreLikeThis => {
+                self.more_like_this = None;
+            }
+            InteractiveEvent::ToggleStopAfterCurrent => {
+                self.stop_after_current = !self.stop_after_current;
+                if self.stop_after_current {
+                    // A follow-up already handed to the player would otherwise start before the stop
+                    self.audio_player.clear_queued_next();
+                    self.gapless_next = None;
+                    self.set_status("⏹️ Stopping after the current track");
+                } else {
+                    // Autoplay is back on, so a gapless successor can be queued again
+                    self.queue_gapless_next();
+                    self.set_status("▶️ Autoplay resumed");
+                }
+            }
+            InteractiveEvent::ToggleSortDirection => {
+                self.sort_descending = !self.sort_descending;
+                self.resort_library();
+            }
+            InteractiveEvent::JumpToTop => {
+                self.move_selection_to(0);
+            }
+            InteractiveEvent::JumpToBottom => {
+                self.move_selection_to(i64::MAX);
+            }
+            InteractiveEvent::HalfPage(direction) => {
+                self.move_by_rows((self.list_page_rows / 2).max(1) as i64 * direction as i64);
+            }
+            InteractiveEvent::Page(direction) => {
+                self.move_by_rows(self.list_page_rows.max(1) as i64 * direction as i64);
+            }
+            InteractiveEvent::Play => {
+                self.play_selection().await?;
+            }
+            InteractiveEvent::TogglePlayPause => {
+                if self.is_playing {
+                    self.audio_player.pause()?;
+                    self.is_playing = false;
+                    self.save_spoken_word_position();
+                    self.set_status("⏸️ Paused");
+                } else {
+                    if self.current_track_index.is_some() {
+                        self.audio_player.resume()?;
+                        self.is_playing = true;
+                        self.set_status("▶️ Resumed");
+                    } else {
+                        self.play_selection().await?;
+                    }
+                }
+            }
+            InteractiveEvent::NextTrack => {
+                self.next_track().await?;
+            }
+            InteractiveEvent::PreviousTrack => {
+                self.previous_track().await?;
+            }
+            InteractiveEvent::Stop => {
+                self.leave_spoken_word();
+                self.audio_player.stop()?;
+                self.is_playing = false;
+                self.current_track_index = None;
+                self.set_status("⏹️ Stopped");
+            }
+            InteractiveEvent::VolumeUp => {
+                self.change_volume(0.1)?;
+            }
+            InteractiveEvent::VolumeDown => {
+                self.change_volume(-0.1)?;
+            }
+            InteractiveEvent::ToggleMute => {
+                self.toggle_mute()?;
+            }
+            InteractiveEvent::ToggleRepeat => {
+                self.repeat_mode = match self.repeat_mode {
+                    RepeatMode::Off => RepeatMode::All,
+                    RepeatMode::All => RepeatMode::One,
+                    RepeatMode::One => RepeatMode::Off,
+                };
+                let mode_str = match self.repeat_mode {
+                    RepeatMode::Off => "🔁 Repeat: Off",
+                    RepeatMode::All => "🔁 Repeat: All",
+                    RepeatMode::One => "🔂 Repeat: One",
+                };
+                self.set_status(mode_str);
+            }
+            InteractiveEvent::ToggleShuffle => {
+                self.is_shuffled = !self.is_shuffled;
+                if self.is_shuffled {
+                    self.set_status("🔀 Shuffle: On");
+                } else {
+                    self.set_status("🔀 Shuffle: Off");
+                }
+            }
+            InteractiveEvent::LibraryChanged(paths) => {
+                self.apply_library_change(paths).await;
+                // A metadata confirmation for a file that just went away has nothing left to act on
+                if let Some(ConfirmAction::ResetMetadata { path, title } | ConfirmAction::ClearMetadata { path, title }) = &self.pending_confirm {
+                    if !self.tracks.iter().any(|t| &t.file_path == path) {
+                        let message = format!("⚠️ '{}' is no longer in the library", title);
+                        self.pending_confirm = None;
+                        self.set_status_level(NotificationLevel::Warning, &message);
+                    }
+                }
+            }
+            InteractiveEvent::Tick => {
+                // Handle periodic updates; the position arrives as PlayerEvent::PositionChanged
+                self.expire_messages();
+                if self.is_playing {
+                    self.needs_redraw = true; // progress bar, lyrics and visualizer move
+                }
+                self.check_sleep_timer()?;
+                self.run_pending_search();
+                #[cfg(feature = "lyrics")]
+                self.sync_lyrics();
+                if let Some(AbLoop { a, b: Some(b) }) = self.ab_loop {
+                    if self.is_playing && self.current_position >= b {
+                        self.seek_to(a);
+                    }
+                }
+                #[cfg(feature = "silence")]
+                self.skip_silence().await?;
+                if self.is_playing && self.spoken_word.as_ref().is_some_and(|spoken| spoken.saved_at.elapsed() >= RESUME_SAVE_INTERVAL) {
+                    self.save_spoken_word_position();
+                }
+            }
+            InteractiveEvent::SwitchToLibrary => {
+                self.current_tab = AppTab::Library;
+                self.set_status("📚 Library Tab");
+                self.save_session();
+            }
+            InteractiveEvent::SwitchToPlaylists => {
+                self.current_tab = AppTab::Playlists;
+                self.set_status("🎵 Playlists Tab");
+                self.save_session();
+            }
+            InteractiveEvent::SwitchToMetadataEditor => {
+                self.current_tab = AppTab::MetadataEditor;
+                self.set_status("🏷️ Metadata Editor Tab");
+                self.save_session();
+            }
+            InteractiveEvent::SwitchToSettings => {
+                self.current_tab = AppTab::Settings;
+                self.set_status("⚙️ Settings Tab");
+                self.save_session();
+            }
+            InteractiveEvent::SwitchToStats => {
+                self.current_tab = AppTab::Stats;
+                self.refresh_stats().await;
+                self.set_status("📊 Stats Tab");
+                self.save_session();
+            }
+            InteractiveEvent::EditTitle => {
+                if self.current_tab == AppTab::MetadataEditor {
+                    if let Some(selected) = self.metadata_list_state.selected() {
+                        if selected < self.tracks.len() {
+                            self.editing_track_index = Some(selected);
+                            self.edit_mode = EditMode::Title;
+                            self.edit_title = self.tracks[selected].display_title();
+                            self.set_status("✏️ Editing title - Press Enter to save, Esc to cancel");
+                        }
+                    }
+                }
+            }
+            InteractiveEvent::EditArtist => {
+                if self.current_tab == AppTab::MetadataEditor {
+                    if let Some(selected) = self.metadata_list_state.selected() {
+                        if selected < self.tracks.len() {
+                            self.editing_track_index = Some(selected);
+                            self.edit_mode = EditMode::Artist;
+                            self.edit_artist = self.tracks[selected].display_artist();
+                            self.set_status("✏️ Editing artist - Press Enter to save, Esc to cancel");
+                        }
+                    }
+                }
+            }
+            InteractiveEvent::SaveMetadata => {
+                if self.edit_mode != EditMode::None {
+                    self.save_current_edit().await?;
+                }
+            }
+            InteractiveEvent::Escape => {
+                self.escape();
+            }
+            InteractiveEvent::ApplySuggestion => {
+                if self.current_tab == AppTab::MetadataEditor {
+                    if let Some(selected) = self.metadata_list_state.selected() {
+                        if selected < self.tracks.len() {
+                            self.apply_filename_suggestion(selected).await?;
+                        }
+                    }
+                }
+            }
+            InteractiveEvent::ResetToOriginal => {
+                if self.current_tab == AppTab::MetadataEditor {
+                    if let Some(selected) = self.metadata_list_state.selected() {
+                        if selected < self.tracks.len() {
+                            let track = &self.tracks[selected];
+                            let (path, title) = (track.file_path.clone(), track.display_title());
+                            self.pending_confirm = Some(ConfirmAction::ResetMetadata { path, title });
+                        }
+                    }
+                }
+            }
+            InteractiveEvent::BulkApplySuggestions => {
+                if self.current_tab == AppTab::MetadataEditor {
+                    self.bulk_apply_suggestions();
+                }
+            }
+            InteractiveEvent::TaskProgress { done, total } => {
+                if let Some(task) = &mut self.background_task {
+                    task.done = done;
+                    task.total = total;
+                }
+            }
+            InteractiveEvent::SuggestionsParsed(suggestions) => self.apply_parsed_suggestions(suggestions),
+            InteractiveEvent::TaskFinished(result) => self.finish_task(result),
+            InteractiveEvent::CancelTask => {
+                if let Some(task) = &self.background_task {
+                    task.cancel.store(true, Ordering::Relaxed);
+                    self.set_status(&format!("⏹️ Cancelling: {}", task.label));
+                }
+            }
+            InteractiveEvent::ClearMetadata => {
+                if self.current_tab == AppTab::MetadataEditor {
+                    if let Some(selected) = self.metadata_list_state.selected() {
+                        if selected < self.tracks.len() {
+                            let track = &self.tracks[selected];
+                            let (path, title) = (track.file_path.clone(), track.display_title());
+                            self.pending_confirm = Some(ConfirmAction::ClearMetadata { path, title });
+                        }
+                    }
+                }
+            }
+            InteractiveEvent::ResetTrackBehavior => {
+                if let Some(track) = self.selected_library_track().map(|idx| &self.tracks[idx]) {
+                    self.pending_confirm = Some(ConfirmAction::ResetTrackBehavior {
+                        track_id: track.id,
+                        title: track.display_title(),
+                    });
+                }
+            }
+            InteractiveEvent::ResetAllBehavior => {
+                self.pending_confirm = Some(ConfirmAction::ResetAllBehavior);
+            }
+            InteractiveEvent::Input(c) => {
+                match self.edit_mode {
+                    EditMode::Title => {
+                        self.edit_title.push(c);
+                    }
+                    EditMode::Artist => {
+                        self.edit_artist.push(c);
+                    }
+                    EditMode::None => {
+                        // No special input handling needed in non-edit mode
+                    }
+                }
+            }
+            InteractiveEvent::Backspace => {
+                match self.edit_mode {
+                    EditMode::Title => {
+                        self.edit_title.pop();
+                    }
+                    EditMode::Artist => {
+                        self.edit_artist.pop();
+                    }
+                    EditMode::None => {}
+                }
+            }
+            InteractiveEvent::ShowHelp => {
+                self.show_help = !self.show_help;
+                self.set_status("❓ Help overlay toggled");
+            }
+            InteractiveEvent::EnterSearch => {
+                self.search_mode = true;
+                self.search_query.clear();
+                self.search_pending_at = None;
+                // Titles may have been edited since the last search
+                self.search_index = SearchIndex::build(&self.tracks);
+                self.refresh_search();
+                debug!("🔍 Search mode activated");
+                self.set_status("🔍 Search mode - type to search, Esc to exit");
+            }
+            InteractiveEvent::ExitSearch => {
+                self.exit_search();
+            }
+            InteractiveEvent::SearchInput(c) => {
+                self.search_query.push(c);
+                self.search_pending_at = Some(Instant::now());
+                self.set_status(&format!("🔍 Searching: '{}'", self.search_query));
+            }
+            InteractiveEvent::SearchBackspace => {
+                self.search_query.pop();
+                self.search_pending_at = Some(Instant::now());
+                if self.search_query.is_empty() {
+                    self.set_status("🔍 Search mode - type to search, Esc to exit");
+                } else {
+                    self.set_status(&format!("🔍 Searching: '{}'", self.search_query));
+                }
+            }
+            InteractiveEvent::CycleSearchScope => {
+                if self.current_tab == AppTab::Playlists {
+                    self.playlist_search_tracks = !self.playlist_search_tracks;
+                } else {
+                    self.search_scope = self.search_scope.next();
+                }
+                self.search_pending_at = None;
+                self.refresh_search();
+                self.set_status(&format!("🔍 Searching in: {}", self.search_scope_label()));
+            }
+            // Playlist events
+
+            InteractiveEvent::DeletePlaylist => {
+                if self.current_tab == AppTab::Playlists {
+                    if let Some(selected) = self.playlist_list_state.selected() {
+                        let playlists = self.visible_playlists();
+                        if let Some(playlist) = playlists.get(selected) {
+                            self.pending_confirm = Some(ConfirmAction::DeletePlaylist {
+                                id: playlist.id.clone(),
+                                name: playlist.name.clone(),
+                            });
+                        }
+                    }
+                }
+            }
+            InteractiveEvent::AcceptConfirm => {
+                if let Some(action) = self.pending_confirm.take() {
+                    self.run_confirmed(action).await?;
+                }
+            }
+            InteractiveEvent::DeclineConfirm => match self.pending_confirm.take() {
+                Some(ConfirmAction::QuitUnsaved { .. }) => self.quit(),
+                Some(_) => self.set_status("Cancelled"),
+                None => {}
+            },
+            InteractiveEvent::DismissConfirm => {
+                if self.pending_confirm.take().is_some() {
+                    self.set_status("Cancelled");
+                }
+            }
+            InteractiveEvent::LoadPlaylist => {
+                if let Some((playlist_id, _)) = self.get_playlist_selection_context() {
+                    self.play_playlist(&playlist_id, 0).await?;
+                }
+            }
+            InteractiveEvent::TogglePlaylistExpansion => {
+                if self.current_tab == AppTab::Playlists {
+                    if let Some(selected) = self.playlist_list_state.selected() {
+                        let playlists = self.visible_playlists();
+                        if let Some(playlist) = playlists.get(selected) {
+                            let playlist_id = playlist.id.clone();
+                            let playlist_name = playlist.name.clone();
+                            let valid_tracks = playlist.get_valid_tracks(&self.tracks);
+                            
+                            // Single playlist expansion: only one playlist can be expanded at a time
+                            if self.expanded_playlists.contains(&playlist_id) {
+                                // Collapse the currently expanded playlist
+                                self.expanded_playlists.clear();
+                                self.playlist_track_states.clear();
+                                self.set_status(&format!("📁 Collapsed playlist: {}", playlist_name));
+                                debug!("🔍 Collapsed playlist: {}", playlist_name);
+                            } else {
+                                // Collapse any previously expanded playlists first
+                                self.expanded_playlists.clear();
+                                self.playlist_track_states.clear();
+                                
+                                // Expand only this playlist
+                                self.expanded_playlists.insert(playlist_id.clone());
+                                
+                                // Initialize track navigation state for this playlist
+                                let mut track_state = ListState::default();
+                                if !valid_tracks.is_empty() {
+                                    track_state.select(Some(0));
+                                }
+                                self.playlist_track_states.insert(playlist_id.clone(), track_state);
+                                
+                                self.set_status(&format!("📂 Expanded playlist: {} ({} tracks)", playlist_name, valid_tracks.len()));
+                                debug!("🔍 Expanded playlist: {} ({} tracks) - all others collapsed", playlist_name, valid_tracks.len());
+                            }
                         }
-                    } else {
-                        debug!("❌ Playlist {} not found", playlist_id);
                     }
-                } else {
-                    debug!("🎵 No playlist context, checking library selection");
-                    if let Some(selected) = self.list_state.selected() {
-                        // Playing from library
-                        if selected < self.filtered_tracks.len() {
-                            let track_idx = self.filtered_tracks[selected];
-                            debug!("🎵 Playing track {} from library", track_idx);
-                            self.play_track(track_idx).await?;
-                        }
+                }
+            }
+            InteractiveEvent::AddToPlaylist => {
+                if self.current_tab == AppTab::Library {
+                    // Marked rows win over the cursor row
+                    let targets: Vec<usize> = if self.marked_tracks.is_empty() {
+                        self.selected_library_track().into_iter().collect()
                     } else {
-                        debug!("❌ No selection found in library");
+                        let mut marked: Vec<usize> = self.marked_tracks.iter().copied().collect();
+                        marked.sort_unstable();
+                        marked
+                    };
+                    
+                    if !targets.is_empty() {
+                        // Show playlist selector overlay instead of auto-adding to first playlist
+                        self.show_playlist_selector = true;
+                        self.tracks_for_playlist = targets;
+                        
+                        // Initialize selector state
+                        let playlists = self.playlist_manager.list_playlists();
+                        let total_options = playlists.len() + 1; // +1 for "Create New Playlist" option
+                        
+                        if total_options > 0 {
+                            self.playlist_selector_state.select(Some(0));
+                        }
+                        
+                        let target_label = self.playlist_target_label();
+                        self.set_status(&format!("📋 Select playlist for {}", target_label));
+                        debug!("🎵 Showing playlist selector for {}", target_label);
                     }
                 }
             }
-            InteractiveEvent::TogglePlayPause => {
-                if self.is_playing {
-                    self.audio_player.pause()?;
-                    self.is_playing = false;
-                    self.set_status("⏸️ Paused");
-                } else {
-                    if self.current_track_index.is_some() {
-                        self.audio_player.resume()?;
-                        self.is_playing = true;
-                        self.set_status("▶️ Resumed");
-                    } else {
-                        // Check if we're in playlist context first
-                        if let Some((playlist_id, track_idx_in_playlist)) = self.get_playlist_selection_context() {
-                            // Playing from playlist - get the actual track index
-                            debug!("🎵 TogglePlayPause: Playlist context detected: playlist={}, track_idx={}", playlist_id, track_idx_in_playlist);
-                            if let Some(playlist) = self.playlist_manager.get_playlist(&playlist_id) {
-                                let valid_tracks = playlist.get_valid_tracks(&self.tracks);
-                                debug!("🎵 TogglePlayPause: Valid tracks in playlist: {:?}", valid_tracks);
-                                if let Some(&actual_track_idx) = valid_tracks.get(track_idx_in_playlist) {
-                                    debug!("🎵 TogglePlayPause: Playing track {} from playlist", actual_track_idx);
-                                    self.play_track(actual_track_idx).await?;
-                                } else {
-                                    debug!("❌ TogglePlayPause: Track index {} not found in valid tracks", track_idx_in_playlist);
-                                }
-                            } else {
-                                debug!("❌ TogglePlayPause: Playlist {} not found", playlist_id);
-                            }
-                        } else {
-                            debug!("🎵 TogglePlayPause: No playlist context, checking library selection");
-                            if let Some(selected) = self.list_state.selected() {
-                                // Playing from library
-                                if selected < self.filtered_tracks.len() {
-                                    let track_idx = self.filtered_tracks[selected];
-                                    debug!("🎵 TogglePlayPause: Playing track {} from library", track_idx);
-                                    self.play_track(track_idx).await?;
-                                }
-                            } else {
-                                debug!("❌ TogglePlayPause: No selection found in library");
-                            }
+            InteractiveEvent::PlaylistInput(c) => {
+                if self.playlist_creation_mode {
+                    self.playlist_name_input.push(c);
+                    self.set_status(&format!("🎵 Playlist name: {}", self.playlist_name_input));
+                }
+            }
+            InteractiveEvent::PlaylistBackspace => {
+                if self.playlist_creation_mode {
+                    self.playlist_name_input.pop();
+                    self.set_status(&format!("🎵 Playlist name: {}", self.playlist_name_input));
+                }
+            }
+            InteractiveEvent::ConfirmPlaylistCreation => {
+                if self.playlist_creation_mode && !self.playlist_name_input.is_empty() {
+                    match self.playlist_manager.create_playlist(self.playlist_name_input.clone(), None) {
+                        Ok(playlist_id) => {
+                            self.push_notification(NotificationLevel::Success, &format!("✅ Created playlist: {}", self.playlist_name_input));
+                            info!("Created playlist: {} (ID: {})", self.playlist_name_input, playlist_id);
+                        }
+                        Err(e) => {
+                            self.push_notification(NotificationLevel::Error, &format!("❌ Failed to create playlist: {}", e));
                         }
                     }
+                    self.playlist_creation_mode = false;
+                    self.playlist_name_input.clear();
                 }
             }
-            InteractiveEvent::NextTrack => {
-                self.next_track().await?;
+            InteractiveEvent::CancelPlaylistCreation => {
+                self.playlist_creation_mode = false;
+                self.playlist_name_input.clear();
+                self.set_status("❌ Playlist creation cancelled");
             }
-            InteractiveEvent::PreviousTrack => {
-                self.previous_track().await?;
+            InteractiveEvent::MoveTrackUp => {
+                self.move_playlist_track(-1);
             }
-            InteractiveEvent::Stop => {
-                self.audio_player.stop()?;
-                self.is_playing = false;
-                self.current_track_index = None;
-                self.set_status("⏹️ Stopped");
+            InteractiveEvent::MoveTrackDown => {
+                self.move_playlist_track(1);
             }
-            InteractiveEvent::VolumeUp => {
-                self.volume = (self.volume + 0.1).min(1.0);
-                self.audio_player.set_volume(self.volume)?;
-                self.set_status(&format!("🔊 Volume: {}%", (self.volume * 100.0) as u32));
+            InteractiveEvent::ExportListeningHistory => {
+                if let Err(e) = self.export_listening_history() {
+                    self.push_notification(NotificationLevel::Error, &format!("❌ Export failed: {}", e));
+                }
             }
-            InteractiveEvent::VolumeDown => {
-                self.volume = (self.volume - 0.1).max(0.0);
-                self.audio_player.set_volume(self.volume)?;
-                self.set_status(&format!("🔉 Volume: {}%", (self.volume * 100.0) as u32));
+            InteractiveEvent::ExportPlaylists => self.export_playlists(),
+            InteractiveEvent::PlayAlbum => {
+                self.play_album().await?;
             }
-            InteractiveEvent::ToggleRepeat => {
-                self.repeat_mode = match self.repeat_mode {
-                    RepeatMode::Off => RepeatMode::All,
-                    RepeatMode::All => RepeatMode::One,
-                    RepeatMode::One => RepeatMode::Off,
-                };
-                let mode_str = match self.repeat_mode {
-                    RepeatMode::Off => "🔁 Repeat: Off",
-                    RepeatMode::All => "🔁 Repeat: All",
-                    RepeatMode::One => "🔂 Repeat: One",
-                };
-                self.set_status(mode_str);
+            InteractiveEvent::ReloadTrack => {
+                if let Some(track_idx) = self.selected_library_track() {
+                    self.reload_track(track_idx).await;
+                }
             }
-            InteractiveEvent::ToggleShuffle => {
-                self.is_shuffled = !self.is_shuffled;
-                if self.is_shuffled {
-                    self.set_status("🔀 Shuffle: On");
-                } else {
-                    self.set_status("🔀 Shuffle: Off");
+            InteractiveEvent::ToggleShuffleExclusion => {
+                if let Some(track_idx) = self.selected_library_track() {
+                    let excluded = !self.shuffle_excluded.contains(&self.tracks[track_idx].id);
+                    self.set_shuffle_excluded(track_idx, excluded).await;
                 }
             }
-            InteractiveEvent::Tick => {
-                // Handle periodic updates
-                self.update_playback_status().await?;
+            InteractiveEvent::GenerateSmartMix => {
+                if let Err(e) = self.generate_smart_mix().await {
+                    self.push_notification(NotificationLevel::Error, &format!("❌ Failed to generate Smart Mix: {}", e));
+                }
             }
-            InteractiveEvent::SwitchToLibrary => {
-                self.current_tab = AppTab::Library;
-                self.set_status("📚 Library Tab");
+            InteractiveEvent::FindDuplicates => {
+                let groups = ScanReport::build(&self.tracks, Vec::new()).duplicate_groups;
+                if groups.is_empty() {
+                    self.set_status("✨ No duplicate tracks in the library");
+                } else {
+                    self.duplicate_review = Some(DuplicateReview { groups, selected: 0, keep: 0, dry_run: true });
+                }
             }
-            InteractiveEvent::SwitchToPlaylists => {
-                self.current_tab = AppTab::Playlists;
-                self.set_status("🎵 Playlists Tab");
+            InteractiveEvent::CloseDuplicates => {
+                self.duplicate_review = None;
             }
-            InteractiveEvent::SwitchToMetadataEditor => {
-                self.current_tab = AppTab::MetadataEditor;
-                self.set_status("🏷️ Metadata Editor Tab");
+            InteractiveEvent::StepDuplicateGroup(delta) => {
+                if let Some(review) = self.duplicate_review.as_mut() {
+                    review.selected = (review.selected as i32 + delta).clamp(0, review.groups.len() as i32 - 1) as usize;
+                    review.keep = 0;
+                }
             }
-            InteractiveEvent::SwitchToSettings => {
-                self.current_tab = AppTab::Settings;
-                self.set_status("⚙️ Settings Tab");
+            InteractiveEvent::StepDuplicateKeep(delta) => {
+                if let Some(review) = self.duplicate_review.as_mut() {
+                    let copies = review.groups[review.selected].paths.len() as i32;
+                    review.keep = (review.keep as i32 + delta).rem_euclid(copies) as usize;
+                }
             }
-            InteractiveEvent::EditTitle => {
-                if self.current_tab == AppTab::MetadataEditor {
-                    if let Some(selected) = self.metadata_list_state.selected() {
-                        if selected < self.tracks.len() {
-                            self.editing_track_index = Some(selected);
-                            self.edit_mode = EditMode::Title;
-                            self.edit_title = self.tracks[selected].display_title();
-                            self.set_status("✏️ Editing title - Press Enter to save, Esc to cancel");
-                        }
-                    }
+            InteractiveEvent::ToggleDuplicatesDryRun => {
+                if let Some(review) = self.duplicate_review.as_mut() {
+                    review.dry_run = !review.dry_run;
+                    let mode = if review.dry_run { "🔍 Dry run: nothing is changed" } else { "⚠️ Dry run off: hide and delete act for real" };
+                    self.set_status(mode);
                 }
             }
-            InteractiveEvent::EditArtist => {
-                if self.current_tab == AppTab::MetadataEditor {
-                    if let Some(selected) = self.metadata_list_state.selected() {
-                        if selected < self.tracks.len() {
-                            self.editing_track_index = Some(selected);
-                            self.edit_mode = EditMode::Artist;
-                            self.edit_artist = self.tracks[selected].display_artist();
-                            self.set_status("✏️ Editing artist - Press Enter to save, Esc to cancel");
-                        }
-                    }
+            InteractiveEvent::ResolveDuplicates(action) => {
+                self.resolve_duplicates(action).await;
+            }
+            InteractiveEvent::PreviewRenames => {
+                let plan = RenamePlan::new(&self.tracks, &self.config.library.rename_pattern);
+                if plan.renames.is_empty() {
+                    self.set_status(&format!("✨ All {} files already match the pattern", plan.unchanged));
+                } else {
+                    self.rename_preview = Some(RenamePreview { plan, scroll: 0, dry_run: true });
                 }
             }
-            InteractiveEvent::SaveMetadata => {
-                if self.edit_mode != EditMode::None {
-                    self.save_current_edit().await?;
+            InteractiveEvent::CloseRenamePreview => {
+                self.rename_preview = None;
+            }
+            InteractiveEvent::ScrollRenamePreview(delta) => {
+                if let Some(preview) = self.rename_preview.as_mut() {
+                    let max = preview.plan.renames.len().saturating_sub(1) as i32;
+                    preview.scroll = (preview.scroll as i32 + delta).clamp(0, max) as u16;
                 }
             }
-            InteractiveEvent::CancelEdit => {
-                self.edit_mode = EditMode::None;
-                self.editing_track_index = None;
-                self.edit_title.clear();
-                self.edit_artist.clear();
-                self.set_status("❌ Edit cancelled");
+            InteractiveEvent::ToggleRenameDryRun => {
+                if let Some(preview) = self.rename_preview.as_mut() {
+                    preview.dry_run = !preview.dry_run;
+                    let mode = if preview.dry_run { "🔍 Dry run: nothing is renamed" } else { "⚠️ Dry run off: Enter renames for real" };
+                    self.set_status(mode);
+                }
             }
-            InteractiveEvent::ApplySuggestion => {
-                if self.current_tab == AppTab::MetadataEditor {
-                    if let Some(selected) = self.metadata_list_state.selected() {
-                        if selected < self.tracks.len() {
-                            self.apply_filename_suggestion(selected).await?;
+            InteractiveEvent::ApplyRenames => {
+                if let Some(preview) = &self.rename_preview {
+                    if preview.dry_run {
+                        for rename in &preview.plan.renames {
+                            info!("Dry run rename: {} → {}", rename.from.display(), rename.to.display());
                         }
+                        self.push_notification(NotificationLevel::Info, &format!("🔍 Dry run: {} - plan written to the log", preview.plan.describe()));
+                    } else {
+                        self.pending_confirm = Some(ConfirmAction::RenameFiles(preview.plan.clone()));
                     }
                 }
             }
-            InteractiveEvent::ResetToOriginal => {
-                if self.current_tab == AppTab::MetadataEditor {
-                    if let Some(selected) = self.metadata_list_state.selected() {
-                        if selected < self.tracks.len() {
-                            self.reset_track_metadata(selected).await?;
-                        }
-                    }
+            InteractiveEvent::LookupMetadata => self.lookup_metadata(),
+            #[cfg(feature = "musicbrainz")]
+            InteractiveEvent::MetadataMatchesFound(path, result) => self.show_metadata_matches(path, result),
+            InteractiveEvent::CloseMetadataMatches | InteractiveEvent::StepMetadataMatch(_) | InteractiveEvent::ApplyMetadataMatch => {
+                #[cfg(feature = "musicbrainz")]
+                self.metadata_matches_event(event);
+            }
+            InteractiveEvent::ToggleScanReport => {
+                self.show_scan_report = !self.show_scan_report;
+                self.scan_report_scroll = 0;
+            }
+            InteractiveEvent::ToggleVisualizer => {
+                let enabled = !self.config.ui.visualizer;
+                self.audio_player.visualizer().set_enabled(enabled);
+                self.config.ui.visualizer = enabled;
+                if let Err(e) = self.config.save() {
+                    warn!("Failed to save visualizer setting to config: {}", e);
                 }
+                self.set_status(if enabled { "📊 Visualizer on" } else { "📊 Visualizer off" });
             }
-            InteractiveEvent::BulkApplySuggestions => {
-                if self.current_tab == AppTab::MetadataEditor {
-                    self.bulk_apply_suggestions().await?;
+            InteractiveEvent::CycleTheme => {
+                self.theme = self.theme.next();
+                self.config.ui.theme = self.theme.name.to_string();
+                if let Err(e) = self.config.save() {
+                    warn!("Failed to save theme to config: {}", e);
                 }
+                self.set_status(&format!("🎨 Theme: {}", self.theme.name));
             }
-            InteractiveEvent::ClearMetadata => {
-                if self.current_tab == AppTab::MetadataEditor {
-                    if let Some(selected) = self.metadata_list_state.selected() {
-                        if selected < self.tracks.len() {
-                            self.clear_track_metadata(selected).await?;
-                        }
-                    }
+            InteractiveEvent::CycleEqPreset => {
+                self.config.audio.equalizer.preset = self.config.audio.equalizer.preset.next();
+                self.apply_equalizer();
+                self.set_status(&format!("🎚️ Equalizer: {}", self.config.audio.equalizer.preset.name()));
+            }
+            InteractiveEvent::CycleChannelMode => {
+                let mode = self.config.audio.channel_mode.next();
+                self.audio_player.channel_mixer().set_mode(mode);
+                self.config.audio.channel_mode = mode;
+                if let Err(e) = self.config.save() {
+                    warn!("Failed to save channel mode to config: {}", e);
                 }
+                self.set_status(&format!("🎧 Channels: {}", mode.name()));
             }
-            // Visualizer event handling removed
-            InteractiveEvent::Input(c) => {
-                match self.edit_mode {
-                    EditMode::Title => {
-                        self.edit_title.push(c);
-                    }
-                    EditMode::Artist => {
-                        self.edit_artist.push(c);
-                    }
-                    EditMode::None => {
-                        // No special input handling needed in non-edit mode
+            InteractiveEvent::SelectEqBand(delta) => {
+                self.eq_band = (self.eq_band as i32 + delta).clamp(0, BAND_COUNT as i32 - 1) as usize;
+            }
+            InteractiveEvent::AdjustEqGain(delta) => {
+                self.adjust_eq_gain(delta);
+            }
+            InteractiveEvent::OpenDevicePicker => {
+                self.open_device_picker();
+            }
+            InteractiveEvent::SelectOutputDevice => {
+                self.show_device_picker = false;
+                // Row 0 is the system default, the rest follow `output_devices`
+                let device = match self.device_picker_state.selected() {
+                    Some(0) | None => None,
+                    Some(row) => self.output_devices.get(row - 1).cloned(),
+                };
+                self.switch_output_device(device).await;
+            }
+            InteractiveEvent::CancelDevicePicker => {
+                self.show_device_picker = false;
+            }
+            InteractiveEvent::CyclePlaylistShuffle => {
+                self.cycle_playlist_shuffle();
+            }
+            InteractiveEvent::CyclePlaylistRepeat => {
+                self.cycle_playlist_repeat();
+            }
+            // Placeholder events for future implementation
+            InteractiveEvent::RenamePlaylist => {
+                self.set_status("🚧 Rename playlist - not yet implemented");
+            }
+            InteractiveEvent::RemoveFromPlaylist => {
+                self.set_status("🚧 Remove from playlist - not yet implemented");
+            }
+            InteractiveEvent::SelectPlaylistFromSelector => {
+                if self.show_playlist_selector {
+                    if let Some(selected) = self.playlist_selector_state.selected() {
+                        if !self.tracks_for_playlist.is_empty() {
+                            let playlists = self.playlist_manager.list_playlists();
+                            
+                            if selected < playlists.len() {
+                                // Selected existing playlist
+                                let playlist_id = playlists[selected].id.clone();
+                                let playlist_name = playlists[selected].name.clone();
+                                drop(playlists); // Release the immutable borrow
+                                self.add_tracks_to_playlist(&playlist_id, &playlist_name);
+                            } else {
+                                // Selected "Create New Playlist" option
+                                drop(playlists); // Release the immutable borrow
+                                self.playlist_creation_mode = true;
+                                self.playlist_name_input.clear();
+                                self.set_status("📝 Enter new playlist name:");
+                                debug!("🎵 Starting playlist creation from selector");
+                            }
+                            
+                            // Close the selector overlay
+                            self.show_playlist_selector = false;
+                            self.tracks_for_playlist.clear();
+                        }
                     }
                 }
             }
-            InteractiveEvent::Backspace => {
-                match self.edit_mode {
-                    EditMode::Title => {
-                        self.edit_title.pop();
-                    }
-                    EditMode::Artist => {
-                        self.edit_artist.pop();
-                    }
-                    EditMode::None => {}
-                }
+            InteractiveEvent::CancelPlaylistSelector => {
+                self.show_playlist_selector = false;
+                self.tracks_for_playlist.clear();
+                self.set_status("❌ Playlist selection cancelled");
+                debug!("🎵 Playlist selector cancelled");
+            }
+        }
+        
+        Ok(())
+    }
+    
+    async fn play_track(&mut self, track_idx: usize) -> Result<()> {
+        if track_idx >= self.tracks.len() {
+            return Ok(());
+        }
+        
+        let track = self.tracks[track_idx].clone();
+        self.leave_spoken_word();
+        // A fresh play_track drops whatever was queued behind the old track
+        self.gapless_next = None;
+        // Starting something else declines the offer to resume
+        self.pending_resume = None;
+        self.ab_loop = None;
+        
+        // Record behavior tracking event
+        self.record_playback(PlaybackEvent::TrackStarted {
+            track_id: track.id,
+            timestamp: chrono::Utc::now(),
+        }).await;
+        
+        // Play the track with graceful error handling
+        self.set_status(&format!("🔄 Attempting to play: {}", track.display_title()));
+        
+        // Spoken word cuts straight in, so no words are lost to a fade
+        self.audio_player.set_fades(!self.is_spoken_word(track_idx));
+        match self.audio_player.play_track(track.clone()) {
+            Ok(()) => {
+                self.current_track_index = Some(track_idx);
+                self.is_playing = true;
+                self.load_bookmarks();
+                #[cfg(feature = "silence")]
+                self.load_silence();
+    
+                
+                // Reset time tracking
+                self.current_position = Duration::from_secs(0);
+                self.total_duration = track.duration;
+                self.position_reports_stale = true;
+                
+                self.set_status_level(NotificationLevel::Success, &format!("✅ SUCCESS: Playing {} | idx={} | is_playing={}", 
+                    track.display_title(), track_idx, self.is_playing));
+                self.load_spoken_word();
+                self.queue_gapless_next();
+            }
+            Err(e) => {
+                // Don't crash the TUI - just show error and continue
+                self.push_notification(NotificationLevel::Error, &format!("❌ AUDIO PLAYER FAILED: {} | Error: {}", track.display_title(), e));
+                self.is_playing = false;
+                self.current_track_index = None;
             }
-            InteractiveEvent::ShowHelp => {
-                self.show_help = !self.show_help;
-                self.set_status("❓ Help overlay toggled");
+        }
+        
+        Ok(())
+    }
+    
+    /// Get the current playlist selection context (playlist_id, track_index_in_playlist)
+    ///
+    /// A playlist header counts as its first track.
+    fn get_playlist_selection_context(&self) -> Option<(String, usize)> {
+        if self.current_tab != AppTab::Playlists {
+            return None;
+        }
+        
+        let selected = self.playlist_list_state.selected()?;
+        let mut current_index = 0;
+        for playlist in self.visible_playlists() {
+            if current_index == selected {
+                return Some((playlist.id.clone(), 0));
             }
-            InteractiveEvent::EnterSearch => {
-                self.search_mode = true;
-                self.search_query.clear();
-                self.update_search_results();
-                debug!("🔍 Search mode activated");
-                self.set_status("🔍 Search mode - type to search, Esc to exit");
+            current_index += 1;
+            
+            if self.expanded_playlists.contains(&playlist.id) {
+                let track_count = playlist.get_valid_tracks(&self.tracks).len();
+                if selected < current_index + track_count {
+                    return Some((playlist.id.clone(), selected - current_index));
+                }
+                current_index += track_count;
             }
-            InteractiveEvent::ExitSearch => {
-                self.search_mode = false;
-                self.search_query.clear();
-                self.reset_to_full_library();
-                debug!("🔍 Search mode exited");
-                self.set_status("🔍 Search exited");
+        }
+        
+        None
+    }
+    
+    /// Tree row of the track at `position` in a playlist, if the playlist is shown expanded
+    fn playlist_track_row(&self, playlist_id: &str, position: usize) -> Option<usize> {
+        let mut current_index = 0;
+        for playlist in self.visible_playlists() {
+            let expanded = self.expanded_playlists.contains(&playlist.id);
+            if playlist.id == playlist_id {
+                return expanded.then_some(current_index + 1 + position);
             }
-            InteractiveEvent::SearchInput(c) => {
-                debug!("🔍 Search input: '{}' (char code: {})", c, c as u32);
-                self.search_query.push(c);
-                debug!("🔍 Search query now: '{}' (len={})", self.search_query, self.search_query.len());
-                self.update_search_results();
-                self.set_status(&format!("🔍 Searching: '{}' ({} results)", self.search_query, self.filtered_tracks.len()));
+            current_index += 1;
+            if expanded {
+                current_index += playlist.get_valid_tracks(&self.tracks).len();
             }
-            InteractiveEvent::SearchBackspace => {
-                self.search_query.pop();
-                self.update_search_results();
-                if self.search_query.is_empty() {
-                    self.set_status("🔍 Search mode - type to search, Esc to exit");
-                } else {
-                    self.set_status(&format!("🔍 Searching: '{}'", self.search_query));
-                }
+        }
+        None
+    }
+    
+    /// Enter: play the selected track and everything after it in the same list
+    ///
+    /// On a playlist header that's the whole playlist, in order.
+    async fn play_selection(&mut self) -> Result<()> {
+        if let Some((playlist_id, position)) = self.get_playlist_selection_context() {
+            return self.play_playlist(&playlist_id, position).await;
+        }
+        if self.current_tab == AppTab::Library && self.library_view != GroupBy::None {
+            return self.activate_library_row().await;
+        }
+        if let Some(&track_idx) = self.list_state.selected().and_then(|pos| self.filtered_tracks.get(pos)) {
+            self.playback_context = PlaybackContext::Library { filtered: self.filtered_tracks.clone() };
+            self.play_track(track_idx).await?;
+        }
+        Ok(())
+    }
+    
+    /// Play the selected track's album from that track, then on in disc and track order
+    async fn play_album(&mut self) -> Result<()> {
+        let Some(track_idx) = self.selected_library_track() else {
+            return Ok(());
+        };
+        let order = grouping::album_order(&self.tracks, track_idx);
+        if order.len() < 2 {
+            self.set_status_level(NotificationLevel::Warning, "⚠️ No other tracks share this one's album tag");
+        } else {
+            let album = self.tracks[track_idx].metadata.album.clone().unwrap_or_default();
+            self.set_status(&format!("💿 Playing '{}' ({} tracks)", album.trim(), order.len()));
+        }
+        self.playback_context = PlaybackContext::Album { order };
+        self.play_track(track_idx).await
+    }
+    
+    /// Play a playlist from `position`, then on through the rest of it
+    async fn play_playlist(&mut self, playlist_id: &str, position: usize) -> Result<()> {
+        let Some(playlist) = self.playlist_manager.get_playlist(playlist_id) else {
+            return Ok(());
+        };
+        let Some(&track_idx) = playlist.get_valid_tracks(&self.tracks).get(position) else {
+            let name = playlist.name.clone();
+            self.set_status_level(NotificationLevel::Warning, &format!("⚠️ '{}' has no tracks in the library", name));
+            return Ok(());
+        };
+        let order = self.playlist_order(playlist, Some(track_idx));
+        
+        self.playback_context = PlaybackContext::Playlist { id: playlist_id.to_string(), order };
+        self.play_track(track_idx).await
+    }
+    
+    /// Pick up edits to the playlist that's playing, so next/previous follow its new order
+    fn refresh_playlist_context(&mut self, playlist_id: &str) {
+        if self.playback_context.playlist_id() != Some(playlist_id) {
+            return;
+        }
+        if let Some(playlist) = self.playlist_manager.get_playlist(playlist_id) {
+            self.playback_context = PlaybackContext::Playlist {
+                id: playlist_id.to_string(),
+                order: self.playlist_order(playlist, self.current_track_index),
+            };
+        }
+    }
+    
+    /// A playlist's tracks in play order; shuffled ones are dealt afresh with `first` leading
+    fn playlist_order(&self, playlist: &Playlist, first: Option<usize>) -> Vec<usize> {
+        let mut order = playlist.get_valid_tracks(&self.tracks);
+        if playlist.shuffle.unwrap_or(self.is_shuffled) {
+            order.shuffle(&mut rand::thread_rng());
+            if let Some(pos) = first.and_then(|first| order.iter().position(|&idx| idx == first)) {
+                let first = order.remove(pos);
+                order.insert(0, first);
             }
-            // Playlist events
+        }
+        order
+    }
+    
+    /// Shuffle and repeat for what's playing: the playlist's own where it has them, else the app's
+    fn playback_modes(&self) -> (bool, RepeatMode) {
+        let playlist = self.playback_context.playlist_id().and_then(|id| self.playlist_manager.get_playlist(id));
+        let shuffle = playlist.and_then(|p| p.shuffle).unwrap_or(self.is_shuffled);
+        let repeat = playlist.and_then(|p| p.repeat).map(RepeatMode::from).unwrap_or_else(|| self.repeat_mode.clone());
+        (shuffle, repeat)
+    }
+    
+    /// Follow the app → always → never, for the selected playlist's shuffle
+    fn cycle_playlist_shuffle(&mut self) {
+        let Some((playlist_id, _)) = self.get_playlist_selection_context() else {
+            return;
+        };
+        let Some(playlist) = self.playlist_manager.get_playlist(&playlist_id) else {
+            return;
+        };
+        let shuffle = match playlist.shuffle {
+            None => Some(true),
+            Some(true) => Some(false),
+            Some(false) => None,
+        };
+        let repeat = playlist.repeat;
+        self.save_playlist_modes(&playlist_id, shuffle, repeat);
+    }
+    
+    /// Follow the app → off → all → one, for the selected playlist's repeat
+    fn cycle_playlist_repeat(&mut self) {
+        let Some((playlist_id, _)) = self.get_playlist_selection_context() else {
+            return;
+        };
+        let Some(playlist) = self.playlist_manager.get_playlist(&playlist_id) else {
+            return;
+        };
+        let repeat = match playlist.repeat {
+            None => Some(PlaylistRepeat::Off),
+            Some(PlaylistRepeat::Off) => Some(PlaylistRepeat::All),
+            Some(PlaylistRepeat::All) => Some(PlaylistRepeat::One),
+            Some(PlaylistRepeat::One) => None,
+        };
+        let shuffle = playlist.shuffle;
+        self.save_playlist_modes(&playlist_id, shuffle, repeat);
+    }
+    
+    fn save_playlist_modes(&mut self, playlist_id: &str, shuffle: Option<bool>, repeat: Option<PlaylistRepeat>) {
+        if let Err(e) = self.playlist_manager.set_playback_modes(playlist_id, shuffle, repeat) {
+            self.dirty_playlists.insert(playlist_id.to_string());
+            self.push_notification(NotificationLevel::Error, &format!("❌ Failed to save playlist: {}", e));
+            return;
+        }
+        self.dirty_playlists.remove(playlist_id);
+        // Takes effect straight away when it's the playlist playing
+        self.refresh_playlist_context(playlist_id);
+        let Some(playlist) = self.playlist_manager.get_playlist(playlist_id) else {
+            return;
+        };
+        let modes = Self::playlist_modes_label(playlist).unwrap_or_else(|| "shuffle and repeat as the app".to_string());
+        let message = format!("🎛️ '{}': {}", playlist.name, modes);
+        self.set_status(&message);
+    }
+    
+    /// e.g. "in order · repeat all", None while the playlist follows the app-wide settings
+    fn playlist_modes_label(playlist: &Playlist) -> Option<String> {
+        let shuffle = playlist.shuffle.map(|shuffle| if shuffle { "shuffled" } else { "in order" });
+        let repeat = playlist.repeat.map(|repeat| match repeat {
+            PlaylistRepeat::Off => "no repeat",
+            PlaylistRepeat::All => "repeat all",
+            PlaylistRepeat::One => "repeat one",
+        });
+        let parts: Vec<&str> = shuffle.into_iter().chain(repeat).collect();
+        (!parts.is_empty()).then(|| parts.join(" · "))
+    }
 
-            InteractiveEvent::DeletePlaylist => {
-                if self.current_tab == AppTab::Playlists {
-                    if let Some(selected) = self.playlist_list_state.selected() {
-                        let playlists = self.playlist_manager.list_playlists();
-                        if let Some(playlist) = playlists.get(selected) {
-                            let playlist_id = playlist.id.clone();
-                            let playlist_count = playlists.len();
-                            drop(playlists); // Release the immutable borrow
-                            
-                            match self.playlist_manager.delete_playlist(&playlist_id) {
-                                Ok(deleted) => {
-                                    self.set_status("🗑️ Playlist deleted");
-                                    info!("Deleted playlist: {}", playlist_id);
-                                    if deleted {
-                                        // Reset selection if we deleted the last item
-                                        if selected >= playlist_count.saturating_sub(1) && selected > 0 {
-                                            self.playlist_list_state.select(Some(selected - 1));
-                                        }
-                                    }
-                                }
-                                Err(e) => {
-                                    self.set_status(&format!("❌ Failed to delete playlist: {}", e));
-                                    error!("Failed to delete playlist: {}", e);
-                                }
-                            }
-                        }
-                    }
-                }
+    /// Like `get_playlist_selection_context`, but only when a track row (not a header) is selected
+    fn get_selected_playlist_track(&self) -> Option<(String, usize)> {
+        let selected = self.playlist_list_state.selected()?;
+        let mut current_index = 0;
+        
+        for playlist in self.visible_playlists() {
+            if current_index == selected {
+                return None; // Header row
             }
-            InteractiveEvent::LoadPlaylist => {
-                if self.current_tab == AppTab::Playlists {
-                    if let Some(selected) = self.playlist_list_state.selected() {
-                        let playlists = self.playlist_manager.list_playlists();
-                        if let Some(playlist) = playlists.get(selected) {
-                            // Clone necessary data before making mutable borrows
-                            let playlist_id = playlist.id.clone();
-                            let playlist_name = playlist.name.clone();
-                            let valid_tracks = playlist.get_valid_tracks(&self.tracks);
-                            
-                            // Load playlist tracks
-                            self.playlist_tracks = valid_tracks;
-                            self.current_playlist_id = Some(playlist_id);
-                            
-                            // Update filtered tracks to show playlist content
-                            self.filtered_tracks = (0..self.playlist_tracks.len()).collect();
-                            if !self.filtered_tracks.is_empty() {
-                                self.list_state.select(Some(0));
-                            }
-                            
-                            self.set_status(&format!("🎵 Loaded playlist: {}", playlist_name));
-                            info!("Loaded playlist: {} ({} tracks)", playlist_name, self.filtered_tracks.len());
-                        }
-                    }
+            current_index += 1;
+            
+            if self.expanded_playlists.contains(&playlist.id) {
+                let track_count = playlist.get_valid_tracks(&self.tracks).len();
+                if selected < current_index + track_count {
+                    return Some((playlist.id.clone(), selected - current_index));
                 }
+                current_index += track_count;
             }
-            InteractiveEvent::TogglePlaylistExpansion => {
-                if self.current_tab == AppTab::Playlists {
-                    if let Some(selected) = self.playlist_list_state.selected() {
-                        let playlists = self.playlist_manager.list_playlists();
-                        if let Some(playlist) = playlists.get(selected) {
-                            let playlist_id = playlist.id.clone();
-                            let playlist_name = playlist.name.clone();
-                            
-                            // Single playlist expansion: only one playlist can be expanded at a time
-                            if self.expanded_playlists.contains(&playlist_id) {
-                                // Collapse the currently expanded playlist
-                                self.expanded_playlists.clear();
-                                self.playlist_track_states.clear();
-                                self.set_status(&format!("📁 Collapsed playlist: {}", playlist_name));
-                                debug!("🔍 Collapsed playlist: {}", playlist_name);
-                            } else {
-                                // Collapse any previously expanded playlists first
-                                self.expanded_playlists.clear();
-                                self.playlist_track_states.clear();
-                                
-                                // Expand only this playlist
-                                self.expanded_playlists.insert(playlist_id.clone());
-                                
-                                // Initialize track navigation state for this playlist
-                                let mut track_state = ListState::default();
-                                let valid_tracks = playlist.get_valid_tracks(&self.tracks);
-                                if !valid_tracks.is_empty() {
-                                    track_state.select(Some(0));
-                                }
-                                self.playlist_track_states.insert(playlist_id.clone(), track_state);
-                                
-                                self.set_status(&format!("📂 Expanded playlist: {} ({} tracks)", playlist_name, valid_tracks.len()));
-                                debug!("🔍 Expanded playlist: {} ({} tracks) - all others collapsed", playlist_name, valid_tracks.len());
-                            }
-                        }
-                    }
+        }
+        
+        None
+    }
+    
+    /// Move the selected track of the expanded playlist one slot up (-1) or down (+1)
+    fn move_playlist_track(&mut self, delta: i32) {
+        let Some((playlist_id, track_idx_in_playlist)) = self.get_selected_playlist_track() else {
+            self.set_status_level(NotificationLevel::Warning, "⚠️ Select a track inside an expanded playlist to reorder it");
+            return;
+        };
+        
+        let Some(playlist) = self.playlist_manager.get_playlist(&playlist_id) else {
+            return;
+        };
+        
+        // The tree only shows tracks present in the library, so map visible rows back to track_paths
+        let valid_tracks = playlist.get_valid_tracks(&self.tracks);
+        let target_idx_in_playlist = if delta < 0 {
+            match track_idx_in_playlist.checked_sub(1) {
+                Some(idx) => idx,
+                None => {
+                    self.set_status_level(NotificationLevel::Warning, "⚠️ Track is already at the top of the playlist");
+                    return;
                 }
             }
-            InteractiveEvent::AddToPlaylist => {
-                if self.current_tab == AppTab::Library {
-                    if let Some(selected) = self.list_state.selected() {
-                        if selected < self.filtered_tracks.len() {
-                            let track_idx = self.filtered_tracks[selected];
-                            
-                            // Show playlist selector overlay instead of auto-adding to first playlist
-                            self.show_playlist_selector = true;
-                            self.selected_track_for_playlist = Some(track_idx);
-                            
-                            // Initialize selector state
-                            let playlists = self.playlist_manager.list_playlists();
-                            let total_options = playlists.len() + 1; // +1 for "Create New Playlist" option
-                            
-                            if total_options > 0 {
-                                self.playlist_selector_state.select(Some(0));
-                            }
-                            
-                            let track_title = self.tracks[track_idx].display_title();
-                            self.set_status(&format!("📋 Select playlist for '{}'", track_title));
-                            debug!("🎵 Showing playlist selector for track: {}", track_title);
-                        }
-                    }
+        } else if track_idx_in_playlist + 1 < valid_tracks.len() {
+            track_idx_in_playlist + 1
+        } else {
+            self.set_status_level(NotificationLevel::Warning, "⚠️ Track is already at the bottom of the playlist");
+            return;
+        };
+        
+        let moved_path = &self.tracks[valid_tracks[track_idx_in_playlist]].file_path;
+        let target_path = &self.tracks[valid_tracks[target_idx_in_playlist]].file_path;
+        let from_index = playlist.track_paths.iter().position(|p| p == moved_path);
+        let to_index = playlist.track_paths.iter().position(|p| p == target_path);
+        let track_title = self.tracks[valid_tracks[track_idx_in_playlist]].display_title();
+        
+        let (Some(from_index), Some(to_index)) = (from_index, to_index) else {
+            return;
+        };
+        
+        let Some(playlist) = self.playlist_manager.get_playlist_mut(&playlist_id) else {
+            return;
+        };
+        if !playlist.move_track(from_index, to_index) {
+            return;
+        }
+        let playlist_clone = playlist.clone();
+        self.refresh_playlist_context(&playlist_id);
+        
+        match self.playlist_manager.save_playlist(&playlist_clone) {
+            Ok(()) => {
+                self.dirty_playlists.remove(&playlist_id);
+                // Keep the cursor on the track we just moved
+                if let Some(selected) = self.playlist_list_state.selected() {
+                    let new_selected = if delta < 0 { selected - 1 } else { selected + 1 };
+                    self.playlist_list_state.select(Some(new_selected));
                 }
-            }
-            InteractiveEvent::PlaylistInput(c) => {
-                if self.playlist_creation_mode {
-                    self.playlist_name_input.push(c);
-                    self.set_status(&format!("🎵 Playlist name: {}", self.playlist_name_input));
+                if let Some(track_state) = self.playlist_track_states.get_mut(&playlist_id) {
+                    track_state.select(Some(target_idx_in_playlist));
                 }
+                self.push_notification(NotificationLevel::Success, &format!("↕️ Moved '{}' to position {}", track_title, target_idx_in_playlist + 1));
             }
-            InteractiveEvent::PlaylistBackspace => {
-                if self.playlist_creation_mode {
-                    self.playlist_name_input.pop();
-                    self.set_status(&format!("🎵 Playlist name: {}", self.playlist_name_input));
-                }
+            Err(e) => {
+                self.dirty_playlists.insert(playlist_id.clone());
+                self.push_notification(NotificationLevel::Error, &format!("❌ Failed to save playlist: {}", e));
+                error!("Failed to save reordered playlist: {}", e);
             }
-            InteractiveEvent::ConfirmPlaylistCreation => {
-                if self.playlist_creation_mode && !self.playlist_name_input.is_empty() {
-                    match self.playlist_manager.create_playlist(self.playlist_name_input.clone(), None) {
-                        Ok(playlist_id) => {
-                            self.set_status(&format!("✅ Created playlist: {}", self.playlist_name_input));
-                            info!("Created playlist: {} (ID: {})", self.playlist_name_input, playlist_id);
-                        }
-                        Err(e) => {
-                            self.set_status(&format!("❌ Failed to create playlist: {}", e));
-                        }
-                    }
-                    self.playlist_creation_mode = false;
-                    self.playlist_name_input.clear();
+        }
+    }
+
+    async fn next_track(&mut self) -> Result<()> {
+        if let Some(current_idx) = self.current_track_index {
+            // Record skip event
+            let track = &self.tracks[current_idx];
+            self.record_playback(PlaybackEvent::TrackSkipped {
+                track_id: track.id,
+                position: self.current_position.as_secs(),
+                reason: SkipReason::NextTrack,
+                timestamp: chrono::Utc::now(),
+            }).await;
+        }
+        
+        self.advance_track().await
+    }
+    
+    /// Move to and play the next track without recording a skip
+    async fn advance_track(&mut self) -> Result<()> {
+        match self.upcoming_track() {
+            Some((position, track_idx)) => {
+                debug!("🎵 Next track {} at position {}", track_idx, position);
+                self.select_in_playing_list(position);
+                self.play_track(track_idx).await?;
+            }
+            None => debug!("❌ No next track in the current context"),
+        }
+        
+        Ok(())
+    }
+    
+    /// The track after the playing one in the playback context, as (position, track index)
+    fn upcoming_track(&self) -> Option<(usize, usize)> {
+        self.playback_context.step(self.current_track_index?, 1)
+    }
+    
+    /// What plays once the current track ends by itself; a playlist's repeat mode can end it there or go again
+    fn autoplay_track(&self) -> Option<(usize, usize)> {
+        let current = self.current_track_index?;
+        if self.playback_context.playlist_id().is_none() {
+            return self.upcoming_track();
+        }
+        let order = self.playback_context.order();
+        let position = order.iter().position(|&idx| idx == current)?;
+        match self.playback_modes().1 {
+            RepeatMode::One => Some((position, current)),
+            RepeatMode::Off if position + 1 == order.len() => None,
+            _ => self.upcoming_track(),
+        }
+    }
+    
+    /// Keep the cursor on the playing track, when the list it's playing from is on screen
+    fn select_in_playing_list(&mut self, position: usize) {
+        match &self.playback_context {
+            PlaybackContext::Library { filtered: order } | PlaybackContext::Album { order } | PlaybackContext::Similar { order } => {
+                let shown = order.get(position)
+                    .and_then(|idx| self.filtered_tracks.iter().position(|i| i == idx));
+                if let Some(pos) = shown {
+                    self.list_state.select(Some(pos));
                 }
             }
-            InteractiveEvent::CancelPlaylistCreation => {
-                self.playlist_creation_mode = false;
-                self.playlist_name_input.clear();
-                self.set_status("❌ Playlist creation cancelled");
-            }
-            // Placeholder events for future implementation
-            InteractiveEvent::RenamePlaylist => {
-                self.set_status("🚧 Rename playlist - not yet implemented");
+            PlaybackContext::Playlist { id, order } => {
+                // Rows follow the playlist's own order, which a shuffle doesn't change
+                let listed = order.get(position).and_then(|&track_idx| {
+                    let playlist = self.playlist_manager.get_playlist(id)?;
+                    playlist.get_valid_tracks(&self.tracks).iter().position(|&idx| idx == track_idx)
+                });
+                if let Some(row) = listed.and_then(|listed| self.playlist_track_row(id, listed)) {
+                    self.playlist_list_state.select(Some(row));
+                }
             }
-            InteractiveEvent::RemoveFromPlaylist => {
-                self.set_status("🚧 Remove from playlist - not yet implemented");
+            PlaybackContext::Queue => {}
+        }
+    }
+    
+    /// Step through the configured durations, then "end of track", then off
+    fn cycle_sleep_timer(&mut self) {
+        let presets = &self.config.audio.sleep_timer_minutes;
+        let next_minutes = match self.sleep_timer {
+            None => presets.first().copied(),
+            Some(SleepTimer::After { minutes, .. }) => presets.iter().copied().find(|m| *m > minutes),
+            Some(SleepTimer::EndOfTrack) => {
+                self.sleep_timer = None;
+                self.audio_player.set_fade_level(1.0);
+                self.set_status("💤 Sleep timer off");
+                return;
             }
-            InteractiveEvent::SelectPlaylistFromSelector => {
-                if self.show_playlist_selector {
-                    if let Some(selected) = self.playlist_selector_state.selected() {
-                        if let Some(track_idx) = self.selected_track_for_playlist {
-                            let playlists = self.playlist_manager.list_playlists();
-                            let track_path = self.tracks[track_idx].file_path.clone();
-                            let track_title = self.tracks[track_idx].display_title();
-                            
-                            if selected < playlists.len() {
-                                // Selected existing playlist
-                                let playlist_id = playlists[selected].id.clone();
-                                let playlist_name = playlists[selected].name.clone();
-                                drop(playlists); // Release the immutable borrow
-                                
-                                match self.playlist_manager.add_track_to_playlist(&playlist_id, &track_path) {
-                                    Ok(_) => {
-                                        self.set_status(&format!("➕ Added '{}' to '{}'", track_title, playlist_name));
-                                        debug!("🎵 Added track to existing playlist: {}", playlist_name);
-                                    }
-                                    Err(e) => {
-                                        self.set_status(&format!("❌ Failed to add track: {}", e));
-                                    }
-                                }
-                            } else {
-                                // Selected "Create New Playlist" option
-                                drop(playlists); // Release the immutable borrow
-                                self.playlist_creation_mode = true;
-                                self.playlist_name_input.clear();
-                                self.set_status("📝 Enter new playlist name:");
-                                debug!("🎵 Starting playlist creation from selector");
-                            }
-                            
-                            // Close the selector overlay
-                            self.show_playlist_selector = false;
-                            self.selected_track_for_playlist = None;
-                        }
-                    }
-                }
+        };
+        
+        // A new timer replaces the old one, including any fade it had started
+        self.audio_player.set_fade_level(1.0);
+        match next_minutes {
+            Some(minutes) => {
+                let deadline = Instant::now() + Duration::from_secs(minutes * 60);
+                self.sleep_timer = Some(SleepTimer::After { minutes, deadline });
+                self.set_status(&format!("💤 Sleep timer: stopping in {} min", minutes));
             }
-            InteractiveEvent::CancelPlaylistSelector => {
-                self.show_playlist_selector = false;
-                self.selected_track_for_playlist = None;
-                self.set_status("❌ Playlist selection cancelled");
-                debug!("🎵 Playlist selector cancelled");
+            None => {
+                self.sleep_timer = Some(SleepTimer::EndOfTrack);
+                self.set_status("💤 Sleep timer: stopping after this track");
             }
         }
+    }
+    
+    /// Fade toward the deadline and stop once it passes
+    fn check_sleep_timer(&mut self) -> Result<()> {
+        let Some(SleepTimer::After { deadline, .. }) = self.sleep_timer else {
+            return Ok(());
+        };
         
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return self.sleep_stop();
+        }
+        if self.config.audio.sleep_timer_fade && remaining < SLEEP_FADE && self.is_playing {
+            self.audio_player.set_fade_level(remaining.as_secs_f32() / SLEEP_FADE.as_secs_f32());
+        }
         Ok(())
     }
     
-    async fn play_track(&mut self, track_idx: usize) -> Result<()> {
-        if track_idx >= self.tracks.len() {
-            return Ok(());
+    fn sleep_stop(&mut self) -> Result<()> {
+        self.leave_spoken_word();
+        self.sleep_timer = None;
+        self.gapless_next = None;
+        self.audio_player.stop()?;
+        self.audio_player.set_fade_level(1.0);
+        self.is_playing = false;
+        self.current_track_index = None;
+        self.push_notification(NotificationLevel::Info, "💤 Sleep timer: playback stopped");
+        Ok(())
+    }
+    
+    /// The armed stop-after-current fired: halt and disarm
+    fn stop_after_track(&mut self) -> Result<()> {
+        self.stop_after_current = false;
+        self.gapless_next = None;
+        self.audio_player.stop()?;
+        self.is_playing = false;
+        self.current_track_index = None;
+        self.set_status("⏹️ Stopped after track");
+        Ok(())
+    }
+    
+    /// Remaining time for the player controls line
+    fn sleep_timer_label(&self) -> Option<String> {
+        match self.sleep_timer? {
+            SleepTimer::After { deadline, .. } => {
+                let secs = deadline.saturating_duration_since(Instant::now()).as_secs();
+                Some(format!("💤 {}:{:02}", secs / 60, secs % 60))
+            }
+            SleepTimer::EndOfTrack => Some("💤 end of track".to_string()),
+        }
+    }
+    
+    /// Gapless mode: hand the player the next track now so it can follow without a gap
+    fn queue_gapless_next(&mut self) {
+        if !self.audio_player.is_gapless() || self.stop_after_current || self.sleep_timer == Some(SleepTimer::EndOfTrack) {
+            return;
         }
         
-        let track = self.tracks[track_idx].clone();
+        let Some((position, track_idx)) = self.autoplay_track() else {
+            return;
+        };
+        if Some(track_idx) == self.current_track_index {
+            return; // repeat-one, or a one-track list, restarts through the normal end-of-track path
+        }
+        // A spoken-word file starts with a seek to where it was left, which a queued start can't do
+        if self.spoken_word.is_some() || self.is_spoken_word(track_idx) {
+            return;
+        }
         
-        // Record behavior tracking event
-        let _ = self.behavior_tracker.handle_event(PlaybackEvent::TrackStarted {
+        match self.audio_player.queue_next(self.tracks[track_idx].clone()) {
+            Ok(()) => self.gapless_next = Some((position, track_idx)),
+            Err(e) => debug!("❌ Could not pre-queue next track: {}", e),
+        }
+    }
+    
+    /// After the track list changed: point `gapless_next` at the queued track again, or queue the new successor
+    fn resolve_gapless_next(&mut self, queued: Option<(PathBuf, uuid::Uuid)>) {
+        let Some((queued_path, queued_id)) = queued else {
+            return;
+        };
+        let still_next = self.autoplay_track()
+            .filter(|&(_, idx)| self.tracks.get(idx).is_some_and(|t| t.file_path == queued_path && t.id == queued_id));
+        if still_next.is_some() {
+            self.gapless_next = still_next;
+            return;
+        }
+        
+        self.audio_player.clear_queued_next();
+        self.gapless_next = None;
+        self.queue_gapless_next();
+    }
+    
+    /// The player moved on to the pre-queued track by itself - catch the UI up
+    async fn adopt_gapless_track(&mut self, position: usize, track_idx: usize) {
+        let track = self.tracks[track_idx].clone();
+        self.record_playback(PlaybackEvent::TrackStarted {
             track_id: track.id,
             timestamp: chrono::Utc::now(),
         }).await;
         
-        // Play the track with graceful error handling
-        self.set_status(&format!("🔄 Attempting to play: {}", track.display_title()));
+        self.select_in_playing_list(position);
+        self.current_track_index = Some(track_idx);
+        self.is_playing = true;
+        self.ab_loop = None;
+        self.load_bookmarks();
+        #[cfg(feature = "silence")]
+        self.load_silence();
+        self.current_position = Duration::from_secs(0);
+        self.total_duration = track.duration;
         
-        match self.audio_player.play_track(track.clone()) {
-            Ok(()) => {
-                self.current_track_index = Some(track_idx);
-                self.is_playing = true;
+        self.gapless_next = None;
+        self.queue_gapless_next();
+    }
     
-                
-                // Reset time tracking
-                self.current_position = Duration::from_secs(0);
-                self.total_duration = track.duration;
-                self.last_position_update = Instant::now();
-                
-                self.set_status(&format!("✅ SUCCESS: Playing {} | idx={} | is_playing={}", 
-                    track.display_title(), track_idx, self.is_playing));
-            }
-            Err(e) => {
-                // Don't crash the TUI - just show error and continue
-                self.set_status(&format!("❌ AUDIO PLAYER FAILED: {} | Error: {}", track.display_title(), e));
-                self.is_playing = false;
-                self.current_track_index = None;
+    async fn previous_track(&mut self) -> Result<()> {
+        let previous = self.current_track_index.and_then(|idx| self.playback_context.step(idx, -1));
+        match previous {
+            Some((position, track_idx)) => {
+                self.select_in_playing_list(position);
+                self.play_track(track_idx).await?;
             }
+            None => debug!("❌ No previous track in the current context"),
         }
         
         Ok(())
     }
     
-    /// Get the current playlist selection context (playlist_id, track_index_in_playlist)
-    fn get_playlist_selection_context(&self) -> Option<(String, usize)> {
-        if self.current_tab != AppTab::Playlists {
-            debug!("🔍 Not in playlists tab, current_tab={:?}", self.current_tab);
-            return None;
-        }
+    /// Fold watcher-reported paths into the library, keeping selection and playback pointed at the same files
+    async fn apply_library_change(&mut self, paths: Vec<PathBuf>) {
+        let path_at = |tracks: &[panpipe::Track], idx: Option<usize>| {
+            idx.and_then(|i| tracks.get(i)).map(|t| t.file_path.clone())
+        };
+        let current_path = path_at(&self.tracks, self.current_track_index);
+        let editing_path = path_at(&self.tracks, self.editing_track_index);
+        let metadata_path = path_at(&self.tracks, self.metadata_list_state.selected());
+        let playlist_target_paths: Vec<PathBuf> = self.tracks_for_playlist.iter()
+            .filter_map(|&idx| path_at(&self.tracks, Some(idx)))
+            .collect();
+        let marked_paths: Vec<PathBuf> = self.marked_tracks.iter()
+            .filter_map(|&idx| path_at(&self.tracks, Some(idx)))
+            .collect();
+        let selected_path = path_at(
+            &self.tracks,
+            self.list_state.selected().and_then(|pos| self.filtered_tracks.get(pos).copied()),
+        );
+        let library_context_paths: Vec<PathBuf> = match &self.playback_context {
+            PlaybackContext::Library { filtered: order } | PlaybackContext::Album { order } | PlaybackContext::Similar { order } => order.iter()
+                .filter_map(|&idx| path_at(&self.tracks, Some(idx)))
+                .collect(),
+            _ => Vec::new(),
+        };
+        // Checked by id as well, the player holds the track as it was when it was queued
+        let gapless_queued = self.gapless_next
+            .and_then(|(_, idx)| self.tracks.get(idx))
+            .map(|t| (t.file_path.clone(), t.id));
         
-        if let Some(selected) = self.playlist_list_state.selected() {
-            debug!("🔍 Playlist selection detected: selected={}", selected);
-            let playlists = self.playlist_manager.list_playlists();
-            let mut current_index = 0;
-            
-            for playlist in playlists {
-                let is_expanded = self.expanded_playlists.contains(&playlist.id);
-                debug!("🔍 Checking playlist '{}': current_index={}, is_expanded={}", playlist.name, current_index, is_expanded);
-                
-                if current_index == selected {
-                    // Selected the playlist header itself
-                    debug!("🔍 Selected playlist header: {}", playlist.name);
-                    return Some((playlist.id.clone(), 0));
+        let mut added = 0;
+        let mut removed = 0;
+        
+        for path in &paths {
+            if path.is_dir() {
+                // New or moved-in directory - pick up anything we don't know yet
+                if let Ok(found) = self.scanner.scan_directory(path) {
+                    for track in found {
+                        let known = self.tracks.iter().any(|t| t.file_path == track.file_path);
+                        if !known && !self.is_excluded_from_library(&track.file_path) {
+                            self.tracks.push(track);
+                            added += 1;
+                        }
+                    }
                 }
-                current_index += 1;
-                
-                if is_expanded {
-                    let valid_tracks = playlist.get_valid_tracks(&self.tracks);
-                    debug!("🔍 Expanded playlist has {} valid tracks", valid_tracks.len());
-                    for (track_idx_in_playlist, _) in valid_tracks.iter().enumerate() {
-                        debug!("🔍 Checking track {}: current_index={}", track_idx_in_playlist, current_index);
-                        if current_index == selected {
-                            // Selected a track within the expanded playlist
-                            debug!("🔍 Selected track {} in playlist '{}'", track_idx_in_playlist, playlist.name);
-                            return Some((playlist.id.clone(), track_idx_in_playlist));
+            } else if path.exists() {
+                if self.is_excluded_from_library(path) {
+                    // Hidden since it was scanned, or a new file nobody wants in the library
+                    let before = self.tracks.len();
+                    self.tracks.retain(|t| &t.file_path != path);
+                    removed += before - self.tracks.len();
+                    continue;
+                }
+                if let Ok(mut track) = self.scanner.scan_file(path) {
+                    if let Some(idx) = self.tracks.iter().position(|t| &t.file_path == path) {
+                        // Modified in place - new bytes, new id, and the history goes with it
+                        match track.content_hash {
+                            Some(new_hash) => {
+                                if let Err(e) = self.behavior_tracker.move_track_history(&self.tracks, idx, new_hash).await {
+                                    warn!("Failed to move history of {} to its new id: {}", path.display(), e);
+                                }
+                            }
+                            None => track.id = self.tracks[idx].id,
                         }
-                        current_index += 1;
+                        self.tracks[idx] = track;
+                    } else {
+                        self.tracks.push(track);
+                        added += 1;
                     }
                 }
+            } else {
+                // Removed file or whole directory
+                let before = self.tracks.len();
+                self.tracks.retain(|t| !t.file_path.starts_with(path));
+                removed += before - self.tracks.len();
             }
-            debug!("🔍 No match found for selection {}", selected);
+        }
+        
+        self.tracks.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+        
+        // Indices may have shifted, resolve everything again by path
+        let index_of = |tracks: &[panpipe::Track], path: Option<PathBuf>| {
+            path.and_then(|p| tracks.iter().position(|t| t.file_path == p))
+        };
+        self.current_track_index = index_of(&self.tracks, current_path);
+        self.editing_track_index = index_of(&self.tracks, editing_path);
+        self.tracks_for_playlist = playlist_target_paths.into_iter()
+            .filter_map(|path| index_of(&self.tracks, Some(path)))
+            .collect();
+        self.marked_tracks = marked_paths.into_iter()
+            .filter_map(|path| index_of(&self.tracks, Some(path)))
+            .collect();
+        self.search_index = SearchIndex::build(&self.tracks);
+        // Its suggestions point at indices that may have moved
+        self.more_like_this = None;
+        
+        if self.search_query.is_empty() {
+            self.filtered_tracks = (0..self.tracks.len()).collect();
+            self.sort_filtered_tracks();
         } else {
-            debug!("🔍 No playlist selection found");
+            self.update_search_results();
         }
         
-        None
-    }
-
-    async fn next_track(&mut self) -> Result<()> {
-        if let Some(current_idx) = self.current_track_index {
-            // Record skip event
-            let track = &self.tracks[current_idx];
-            let _ = self.behavior_tracker.handle_event(PlaybackEvent::TrackSkipped {
-                track_id: track.id,
-                position: 0, // TODO: get actual position
-                reason: SkipReason::NextTrack,
-                timestamp: chrono::Utc::now(),
-            }).await;
+        let selected_index = index_of(&self.tracks, selected_path);
+        let selected_pos = selected_index
+            .and_then(|idx| self.filtered_tracks.iter().position(|&i| i == idx))
+            .or_else(|| (!self.filtered_tracks.is_empty()).then_some(0));
+        self.list_state.select(selected_pos);
+        
+        let metadata_index = index_of(&self.tracks, metadata_path)
+            .or_else(|| (!self.tracks.is_empty()).then_some(0));
+        self.metadata_list_state.select(metadata_index);
+        
+        // Library and album snapshots keep their order minus removed files; a playlist is simply re-read
+        match &self.playback_context {
+            PlaybackContext::Library { .. } | PlaybackContext::Album { .. } | PlaybackContext::Similar { .. } => {
+                let by_path: HashMap<&std::path::Path, usize> = self.tracks.iter().enumerate()
+                    .map(|(idx, track)| (track.file_path.as_path(), idx))
+                    .collect();
+                let order = library_context_paths.iter()
+                    .filter_map(|path| by_path.get(path.as_path()).copied())
+                    .collect();
+                self.playback_context = match self.playback_context {
+                    PlaybackContext::Album { .. } => PlaybackContext::Album { order },
+                    PlaybackContext::Similar { .. } => PlaybackContext::Similar { order },
+                    _ => PlaybackContext::Library { filtered: order },
+                };
+            }
+            PlaybackContext::Playlist { id, .. } => {
+                let id = id.clone();
+                self.refresh_playlist_context(&id);
+            }
+            PlaybackContext::Queue => {}
         }
+        self.resolve_gapless_next(gapless_queued);
         
-        // Check if we're in playlist context first
-        if self.current_tab == AppTab::Playlists && !self.expanded_playlists.is_empty() {
-            // Get the currently expanded playlist (only one can be expanded)
-            let expanded_playlist_id = self.expanded_playlists.iter().next().unwrap().clone();
-            debug!("🎵 Next track in playlist context: playlist={}", expanded_playlist_id);
-            
-            if let Some(playlist) = self.playlist_manager.get_playlist(&expanded_playlist_id) {
-                let valid_tracks = playlist.get_valid_tracks(&self.tracks);
-                
-                // Get current track state for this playlist
-                if let Some(track_state) = self.playlist_track_states.get_mut(&expanded_playlist_id) {
-                    let current_track_idx = track_state.selected().unwrap_or(0);
-                    let next_track_idx = (current_track_idx + 1) % valid_tracks.len();
-                    
-                    // Update playlist track selection
-                    track_state.select(Some(next_track_idx));
-                    
-                    if let Some(&actual_track_idx) = valid_tracks.get(next_track_idx) {
-                        debug!("🎵 Playing next track {} from playlist (track {} of {})", actual_track_idx, next_track_idx + 1, valid_tracks.len());
-                        self.play_track(actual_track_idx).await?;
-                    } else {
-                        debug!("❌ Next track index {} not found in playlist", next_track_idx);
-                    }
+        if added > 0 || removed > 0 {
+            info!("Library changed on disk: {} added, {} removed", added, removed);
+            self.push_notification(
+                NotificationLevel::Info,
+                &format!("📁 Library updated: +{} / -{} tracks", added, removed),
+            );
+        }
+    }
+    
+    /// Apply include/exclude patterns relative to whichever music directory holds `path`, and the hidden duplicates
+    fn is_excluded_from_library(&self, path: &std::path::Path) -> bool {
+        self.config.library.is_hidden(path) || self.config.music_directories.iter()
+            .find(|root| path.starts_with(root))
+            .is_some_and(|root| self.scanner.is_excluded(root, path))
+    }
+    
+    /// Run the search once typing has paused for `SEARCH_DEBOUNCE`
+    fn run_pending_search(&mut self) {
+        if self.search_pending_at.is_some_and(|at| at.elapsed() >= SEARCH_DEBOUNCE) {
+            self.search_pending_at = None;
+            self.refresh_search();
+            if !self.search_query.is_empty() {
+                let results = if self.current_tab == AppTab::Playlists {
+                    self.visible_playlists().len()
                 } else {
-                    debug!("❌ No track state found for expanded playlist");
-                }
+                    self.filtered_tracks.len()
+                };
+                self.set_status(&format!("🔍 Searching: '{}' ({} results)", self.search_query, results));
             }
+        }
+    }
+    
+    /// Re-run the search for whichever tab it's filtering
+    fn refresh_search(&mut self) {
+        if self.current_tab == AppTab::Playlists {
+            self.update_playlist_search();
         } else {
-            // Next track in library
-            debug!("🎵 Next track in library context");
-            if let Some(selected) = self.list_state.selected() {
-                let next_idx = (selected + 1) % self.filtered_tracks.len();
-                self.list_state.select(Some(next_idx));
-                
-                let track_idx = self.filtered_tracks[next_idx];
-                self.play_track(track_idx).await?;
+            self.update_search_results();
+        }
+    }
+    
+    fn search_scope_label(&self) -> String {
+        match (self.current_tab == AppTab::Playlists, self.playlist_search_tracks) {
+            (true, false) => "names".to_string(),
+            (true, true) => "names + tracks".to_string(),
+            (false, _) => self.search_scope.label().to_string(),
+        }
+    }
+    
+    /// Playlists shown in the tree view, narrowed by a Playlists-tab search
+    fn visible_playlists(&self) -> Vec<&Playlist> {
+        let mut playlists = self.playlist_manager.list_playlists();
+        if let Some(matches) = &self.playlist_search_matches {
+            playlists.retain(|playlist| matches.contains(&playlist.id));
+        }
+        playlists
+    }
+    
+    /// Narrow the playlist tree to playlists whose name - or, optionally, one of whose tracks - matches
+    ///
+    /// Expansion is left alone, so a matching playlist that was open stays open.
+    fn update_playlist_search(&mut self) {
+        if self.search_query.is_empty() {
+            self.playlist_search_matches = None;
+        } else {
+            let matching_tracks: HashSet<usize> = if self.playlist_search_tracks {
+                self.search_index.search(&self.search_query, SearchScope::All)
+                    .into_iter()
+                    .map(|(idx, _)| idx)
+                    .collect()
+            } else {
+                HashSet::new()
+            };
+            let matches = self.playlist_manager.list_playlists().into_iter()
+                .filter(|playlist| {
+                    self.search_index.score_text(&playlist.name, &self.search_query).is_some()
+                        || playlist.get_valid_tracks(&self.tracks).iter().any(|idx| matching_tracks.contains(idx))
+                })
+                .map(|playlist| playlist.id.clone())
+                .collect();
+            self.playlist_search_matches = Some(matches);
+        }
+        
+        let selected = (self.playlist_tree_len() > 0).then_some(0);
+        self.playlist_list_state.select(selected);
+    }
+    
+    /// Drop the playlist filter, keeping whichever playlist was highlighted selected in the full tree
+    fn clear_playlist_search(&mut self) {
+        if self.playlist_search_matches.is_none() {
+            return;
+        }
+        let selected_id = self.get_playlist_selection_context().map(|(id, _)| id);
+        self.playlist_search_matches = None;
+        
+        let mut row = 0;
+        for playlist in self.playlist_manager.list_playlists() {
+            if selected_id.as_deref() == Some(playlist.id.as_str()) {
+                self.playlist_list_state.select(Some(row));
+                return;
+            }
+            row += 1;
+            if self.expanded_playlists.contains(&playlist.id) {
+                row += playlist.get_valid_tracks(&self.tracks).len();
             }
         }
+        self.playlist_list_state.select((row > 0).then_some(0));
+    }
+    
+    fn update_search_results(&mut self) {
+        if self.search_query.is_empty() {
+            debug!("🔍 Empty search query, showing all {} tracks", self.tracks.len());
+            self.filtered_tracks = (0..self.tracks.len()).collect();
+            self.sort_filtered_tracks();
+        } else {
+            // Results come back best match first, unless the user picked a sort of their own
+            self.filtered_tracks = self.search_index.search(&self.search_query, self.search_scope)
+                .into_iter()
+                .map(|(idx, _)| idx)
+                .collect();
+            let match_count = self.filtered_tracks.len();
+            self.sort_filtered_tracks();
+            debug!("🔍 Search for '{}': {} matches out of {} tracks", self.search_query, match_count, self.tracks.len());
+        }
         
-        Ok(())
+        // Reset selection to first result
+        if !self.filtered_tracks.is_empty() {
+            self.list_state.select(Some(0));
+        } else {
+            self.list_state.select(None);
+        }
     }
     
-    async fn previous_track(&mut self) -> Result<()> {
-        // Check if we're in playlist context first
-        if self.current_tab == AppTab::Playlists && !self.expanded_playlists.is_empty() {
-            // Get the currently expanded playlist (only one can be expanded)
-            let expanded_playlist_id = self.expanded_playlists.iter().next().unwrap().clone();
-            debug!("🎵 Previous track in playlist context: playlist={}", expanded_playlist_id);
-            
-            if let Some(playlist) = self.playlist_manager.get_playlist(&expanded_playlist_id) {
-                let valid_tracks = playlist.get_valid_tracks(&self.tracks);
-                
-                // Get current track state for this playlist
-                if let Some(track_state) = self.playlist_track_states.get_mut(&expanded_playlist_id) {
-                    let current_track_idx = track_state.selected().unwrap_or(0);
-                    let prev_track_idx = if current_track_idx == 0 {
-                        valid_tracks.len() - 1
-                    } else {
-                        current_track_idx - 1
-                    };
-                    
-                    // Update playlist track selection
-                    track_state.select(Some(prev_track_idx));
-                    
-                    if let Some(&actual_track_idx) = valid_tracks.get(prev_track_idx) {
-                        debug!("🎵 Playing previous track {} from playlist (track {} of {})", actual_track_idx, prev_track_idx + 1, valid_tracks.len());
-                        self.play_track(actual_track_idx).await?;
-                    } else {
-                        debug!("❌ Previous track index {} not found in playlist", prev_track_idx);
-                    }
+    /// Order `filtered_tracks` by the chosen sort, dropping rows the tag filter excludes; `tracks` itself never moves
+    fn sort_filtered_tracks(&mut self) {
+        let tracks = &self.tracks;
+        let key = |idx: &usize| -> (String, String) {
+            let track = &tracks[*idx];
+            (track.display_artist().to_lowercase(), track.display_title().to_lowercase())
+        };
+        
+        let mut order: Vec<usize> = std::mem::take(&mut self.filtered_tracks);
+        if let Some(filter) = &self.tag_filter {
+            order.retain(|idx| filter.matches(self.behavior_tags.get(&tracks[*idx].id)));
+        }
+        
+        match self.sort_field {
+            SortField::Unsorted => {}
+            SortField::Artist => order.sort_by_cached_key(key),
+            SortField::Title => order.sort_by_cached_key(|idx| {
+                let (artist, title) = key(idx);
+                (title, artist)
+            }),
+            // An album's tracks in disc then track-number order
+            SortField::Album => order.sort_by_cached_key(|idx| {
+                (tracks[*idx].display_album().to_lowercase(), grouping::disc_track_key(&tracks[*idx]), key(idx))
+            }),
+            SortField::Duration => order.sort_by_key(|idx| tracks[*idx].duration),
+            SortField::Plays => order.sort_by_key(|idx| self.play_counts.get(&tracks[*idx].id).copied().unwrap_or(0)),
+            SortField::Added => {
+                // Doubles as a filter: only files modified within the cutoff stay in view
+                let cutoff_days = self.config.library.recently_added_days;
+                if cutoff_days > 0 {
+                    let cutoff = (chrono::Utc::now().timestamp() as u64).saturating_sub(cutoff_days * 24 * 60 * 60);
+                    order.retain(|idx| tracks[*idx].modified.is_some_and(|modified| modified >= cutoff));
+                }
+                order.sort_by_key(|idx| std::cmp::Reverse(tracks[*idx].modified));
+            }
+        }
+        if self.sort_descending && self.sort_field != SortField::Unsorted {
+            order.reverse();
+        }
+        
+        // Tree views keep each group together; the sort above still orders tracks within a group
+        grouping::order_for_tree(tracks, &mut order, self.library_view);
+        self.filtered_tracks = order;
+        self.rebuild_library_tree();
+    }
+    
+    /// Recompute the visible tree rows after the filter, sort or expansion changed
+    fn rebuild_library_tree(&mut self) {
+        self.library_rows = grouping::tree_rows(&self.tracks, &self.filtered_tracks, self.library_view, &self.expanded_groups);
+        let selected = match self.library_rows.len() {
+            0 => None,
+            len => Some(self.library_tree_state.selected().unwrap_or(0).min(len - 1)),
+        };
+        self.library_tree_state.select(selected);
+    }
+    
+    /// Re-read one file from disk in place; if its bytes changed, its behavior data moves to the new id
+    async fn reload_track(&mut self, track_idx: usize) {
+        let path = self.tracks[track_idx].file_path.clone();
+        let mut track = match self.scanner.rescan_file(&path) {
+            Ok(track) => track,
+            Err(e) => {
+                self.push_notification(NotificationLevel::Error, &format!("❌ Couldn't reload {}: {}", path.display(), e));
+                return;
+            }
+        };
+        // Changed bytes mean a new id, and the history goes with it
+        match track.content_hash {
+            Some(new_hash) => {
+                if let Err(e) = self.behavior_tracker.move_track_history(&self.tracks, track_idx, new_hash).await {
+                    warn!("Failed to move history of {} to its new id: {}", path.display(), e);
+                }
+            }
+            None => track.id = self.tracks[track_idx].id,
+        }
+        if let Err(e) = self.behavior_tracker.save_scan_cache(std::slice::from_ref(&track)) {
+            warn!("Failed to update scan cache for {}: {}", path.display(), e);
+        }
+        let title = track.display_title();
+        // Whatever was edited in memory is gone now
+        self.dirty_tracks.remove(&path);
+        if self.current_track_index == Some(track_idx) {
+            self.total_duration = track.duration;
+        }
+        self.tracks[track_idx] = track;
+        
+        // Tags may have moved it within the sort or in or out of the search results
+        self.search_index = SearchIndex::build(&self.tracks);
+        self.resort_library();
+        self.set_status(&format!("🔄 Reloaded '{}' from disk", title));
+    }
+    
+    /// Track under the Library cursor, whichever view is showing
+    fn selected_library_track(&self) -> Option<usize> {
+        if self.library_view == GroupBy::None {
+            return self.list_state.selected().and_then(|pos| self.filtered_tracks.get(pos).copied());
+        }
+        match self.library_tree_state.selected().and_then(|row| self.library_rows.get(row)) {
+            Some(TreeRow::Track { index, .. }) => Some(*index),
+            _ => None,
+        }
+    }
+    
+    /// Flat list → by artist → by album → flat list, keeping the highlighted track in view
+    fn cycle_library_view(&mut self) {
+        let selected_track = self.selected_library_track();
+        self.library_view = self.library_view.next();
+        self.library_tree_state.select(Some(0));
+        self.resort_library();
+        
+        if let Some(track_idx) = selected_track {
+            if let Some(pos) = self.filtered_tracks.iter().position(|&i| i == track_idx) {
+                self.list_state.select(Some(pos));
+            }
+            if let Some(row) = self.library_rows.iter().position(|r| matches!(r, TreeRow::Track { index, .. } if *index == track_idx)) {
+                self.library_tree_state.select(Some(row));
+            }
+        }
+        self.set_status(&format!("🗂️ Library view: {}", self.library_view.label()));
+    }
+    
+    /// Enter on a tree row: open or close a group, or play a track
+    async fn activate_library_row(&mut self) -> Result<()> {
+        let Some(row) = self.library_tree_state.selected().and_then(|row| self.library_rows.get(row)).cloned() else {
+            return Ok(());
+        };
+        
+        match row {
+            TreeRow::Group { id, expanded, .. } => {
+                if expanded {
+                    // Closing an artist closes its albums too
+                    let nested = format!("{}/", id);
+                    self.expanded_groups.retain(|open| open != &id && !open.starts_with(&nested));
                 } else {
-                    debug!("❌ No track state found for expanded playlist");
+                    self.expanded_groups.insert(id);
                 }
+                self.rebuild_library_tree();
             }
-        } else {
-            // Previous track in library
-            debug!("🎵 Previous track in library context");
-            if let Some(selected) = self.list_state.selected() {
-                let prev_idx = if selected == 0 {
-                    self.filtered_tracks.len() - 1
-                } else {
-                    selected - 1
-                };
-                self.list_state.select(Some(prev_idx));
-                
-                let track_idx = self.filtered_tracks[prev_idx];
-                self.play_track(track_idx).await?;
+            TreeRow::Track { index, .. } => {
+                // `filtered_tracks` is already in tree order
+                if let Some(pos) = self.filtered_tracks.iter().position(|&i| i == index) {
+                    self.list_state.select(Some(pos));
+                }
+                self.playback_context = PlaybackContext::Library { filtered: self.filtered_tracks.clone() };
+                self.play_track(index).await?;
             }
         }
-        
         Ok(())
     }
     
-    fn update_search_results(&mut self) {
-        if self.search_query.is_empty() {
-            debug!("🔍 Empty search query, showing all {} tracks", self.tracks.len());
-            self.filtered_tracks = (0..self.tracks.len()).collect();
+    /// Re-sort the visible list after the sort changed, keeping the highlighted track selected
+    fn resort_library(&mut self) {
+        let selected_track = self.list_state.selected().and_then(|pos| self.filtered_tracks.get(pos).copied());
+        
+        // Rebuild from the search (or whole library) rather than re-sorting what's shown:
+        // unsorted means scan order or search relevance, and "recently added" filters rows out
+        if !self.search_query.is_empty() {
+            self.update_search_results();
         } else {
-            debug!("🔍 Fuzzy searching for: '{}'", self.search_query);
-            
-            // CRITICAL: ClangdMatcher parameter order is fuzzy_match(pattern, choice) NOT (choice, pattern)!
-        // This was the root cause of typo tolerance failing - we had the parameters backwards.
-        // The search query is the "pattern" and the track field is the "choice".
-        // Test results: "the ouytside" vs "The Outside" works in reverse order (Some(290))
-        // but returns None in forward order. Always use fuzzy_match(search_query, track_field)!
-        
-        // Fuzzy matcher testing
-        let test_cases = [
-            ("the outside", "the outside"),     // Exact match
-            ("the outside", "the ouytside"),    // Original typo
-            ("the outside", "the outsyde"),     // Different typo
-            ("the outside", "outside"),         // Partial match
-            ("the outside", "the out"),         // Prefix match
-            ("the outside", "outside the"),     // Reversed
-        ];
+            self.filtered_tracks = (0..self.tracks.len()).collect();
+            self.sort_filtered_tracks();
+        }
         
-        for (query, target) in &test_cases {
-                let score1 = self.fuzzy_matcher.fuzzy_match(query, target);
-                let score2 = self.fuzzy_matcher.fuzzy_match(target, query);
-                
-                // Simple similarity test for comparison
-                let simple_similarity = if target.contains(query) || query.contains(target) {
-                    100
-                } else if target.to_lowercase().contains(&query.to_lowercase()) || query.to_lowercase().contains(&target.to_lowercase()) {
-                    50
-                } else {
-                    0
-                };
-                
-                debug!("🔍 Fuzzy test: '{}' vs '{}' = {:?} (forward)", target, query, score1);
-                debug!("🔍 Fuzzy test: '{}' vs '{}' = {:?} (reverse)", query, target, score2);
-                debug!("🔍 Simple similarity: '{}' vs '{}' = {}", target, query, simple_similarity);
+        let selected_pos = selected_track
+            .and_then(|idx| self.filtered_tracks.iter().position(|&i| i == idx))
+            .or_else(|| (!self.filtered_tracks.is_empty()).then_some(0));
+        self.list_state.select(selected_pos);
+        
+        self.set_status(&format!("↕️ Sort: {}", self.sort_label()));
+    }
+    
+    /// Current sort for the Library title bar, e.g. "artist ↑"
+    fn sort_label(&self) -> String {
+        let arrow = if self.sort_descending { "↓" } else { "↑" };
+        match self.sort_field {
+            SortField::Unsorted => SortField::Unsorted.label().to_string(),
+            SortField::Added if self.config.library.recently_added_days > 0 => {
+                format!("added in last {} days {}", self.config.library.recently_added_days, arrow)
             }
-            
-            let mut scored_results: Vec<(usize, i64)> = Vec::new();
-            let mut match_count = 0;
-            
-            for (idx, track) in self.tracks.iter().enumerate() {
-                let mut best_score = 0i64;
-                let mut match_field = "none";
-                
-                // Try matching against title
-                if let Some(title) = &track.metadata.title {
-                    if let Some(score) = self.fuzzy_matcher.fuzzy_match(&self.search_query, title) {
-                        if score > best_score {
-                            best_score = score;
-                            match_field = "title";
-                        }
-                    }
-                }
-                
-                // Try matching against display title
-                let display_title = track.display_title();
-                if let Some(score) = self.fuzzy_matcher.fuzzy_match(&self.search_query, &display_title) {
-                    if score > best_score {
-                        best_score = score;
-                        match_field = "display_title";
-                    }
-                }
-                
-                // Try matching against artist
-                if let Some(artist) = &track.metadata.artist {
-                    if let Some(score) = self.fuzzy_matcher.fuzzy_match(&self.search_query, artist) {
-                        if score > best_score {
-                            best_score = score;
-                            match_field = "artist";
-                        }
-                    }
-                }
-                
-                // Try matching against filename
-                if let Some(filename) = track.file_path.file_name() {
-                    let filename_str = filename.to_string_lossy();
-                    if let Some(score) = self.fuzzy_matcher.fuzzy_match(&self.search_query, &filename_str) {
-                        if score > best_score {
-                            best_score = score;
-                            match_field = "filename";
-                        }
-                    }
-                }
-                
-                // Log detailed info for first few matches
-                if idx < 3 {
-                    debug!("🔍 Track {}: '{}' -> score {} (via {})", idx, track.display_title(), best_score, match_field);
-                }
-                
-                if best_score > 0 {
-                    scored_results.push((idx, best_score));
-                    match_count += 1;
-                }
+            field => format!("{} {}", field.label(), arrow),
+        }
+    }
+    
+    /// Favorites → never played → every other tag in the behavior data → off
+    async fn cycle_tag_filter(&mut self) {
+        match self.behavior_tracker.get_all_behaviors().await {
+            Ok(behaviors) => {
+                self.behavior_tags = behaviors.into_iter().map(|b| (b.track_id, b.tags)).collect();
             }
-            
-            // Sort by score (highest first)
-            scored_results.sort_by(|a, b| b.1.cmp(&a.1));
-            self.filtered_tracks = scored_results.into_iter().map(|(idx, _)| idx).collect();
-            
-            debug!("🔍 Search complete: {} matches found out of {} tracks", match_count, self.tracks.len());
-            if !self.filtered_tracks.is_empty() {
-                let top_track = &self.tracks[self.filtered_tracks[0]];
-                debug!("🔍 Top match: '{}'", top_track.display_title());
+            Err(e) => {
+                self.push_notification(NotificationLevel::Error, &format!("❌ Could not load behavior tags: {}", e));
+                return;
             }
         }
         
-        // Reset selection to first result
-        if !self.filtered_tracks.is_empty() {
-            self.list_state.select(Some(0));
-        } else {
-            self.list_state.select(None);
+        let mut other_tags: Vec<&String> = self.behavior_tags.values().flatten().filter(|tag| *tag != "favorite").collect();
+        other_tags.sort();
+        other_tags.dedup();
+        let options: Vec<TagFilter> = [TagFilter::Tag("favorite".to_string()), TagFilter::NeverPlayed]
+            .into_iter()
+            .chain(other_tags.into_iter().map(|tag| TagFilter::Tag(tag.clone())))
+            .collect();
+        
+        self.tag_filter = match &self.tag_filter {
+            None => options.first().cloned(),
+            Some(current) => options.iter()
+                .position(|option| option == current)
+                .and_then(|pos| options.get(pos + 1))
+                .cloned(),
+        };
+        self.resort_library();
+        
+        match &self.tag_filter {
+            Some(filter) => self.set_status(&format!("🏷️ Filter: {} ({} tracks)", filter.label(), self.filtered_tracks.len())),
+            None => self.set_status("🏷️ Filter cleared"),
+        }
+    }
+    
+    /// Cache per-track play counts for the plays sort
+    async fn load_play_counts(&mut self) {
+        match self.behavior_tracker.get_all_behaviors().await {
+            Ok(behaviors) => {
+                self.play_counts = behaviors.iter().map(|b| (b.track_id, b.total_plays)).collect();
+            }
+            Err(e) => warn!("Failed to load play counts for sorting: {}", e),
         }
     }
     
     fn reset_to_full_library(&mut self) {
         // Reset to show all tracks
         self.filtered_tracks = (0..self.tracks.len()).collect();
+        self.sort_filtered_tracks();
         
         // Reset selection to first item
         if !self.filtered_tracks.is_empty() {
@@ -1494,6 +5076,15 @@ impl InteractiveApp {
         }
         
         match self.current_tab {
+            AppTab::Library if self.library_view != GroupBy::None => {
+                let rows = self.library_rows.len();
+                if rows == 0 {
+                    return;
+                }
+                
+                let current = self.library_tree_state.selected().unwrap_or(0) as i64;
+                self.library_tree_state.select(Some((current + delta as i64).rem_euclid(rows as i64) as usize));
+            }
             AppTab::Library => {
                 if self.filtered_tracks.is_empty() {
                     return;
@@ -1531,22 +5122,8 @@ impl InteractiveApp {
                 self.metadata_list_state.select(Some(new_index));
             }
             AppTab::Playlists => {
-                // Tree-view navigation: calculate total items (playlists + expanded tracks)
-                let playlists = self.playlist_manager.list_playlists();
-                if playlists.is_empty() {
-                    return;
-                }
-                
-                // Calculate total items in the tree view
-                let mut total_items = 0;
-                for playlist in &playlists {
-                    total_items += 1; // Playlist header
-                    if self.expanded_playlists.contains(&playlist.id) {
-                        let valid_tracks = playlist.get_valid_tracks(&self.tracks);
-                        total_items += valid_tracks.len(); // Expanded tracks
-                    }
-                }
-                
+                // Tree-view navigation over playlists plus expanded tracks
+                let total_items = self.playlist_tree_len();
                 if total_items == 0 {
                     return;
                 }
@@ -1568,6 +5145,98 @@ impl InteractiveApp {
             AppTab::Settings => {
                 // Settings tab has no navigable list - do nothing
             }
+            AppTab::Stats => {
+                self.stats_scroll = if delta > 0 {
+                    self.stats_scroll.saturating_add(delta as u16)
+                } else {
+                    self.stats_scroll.saturating_sub((-delta) as u16)
+                };
+            }
+        }
+    }
+    
+    /// True while a type-to-jump prefix is still being typed
+    fn type_jump_active(&self) -> bool {
+        self.current_tab == AppTab::Library
+            && self.edit_mode == EditMode::None
+            && !self.type_jump.is_empty()
+            && self.type_jump_at.elapsed() < TYPE_JUMP_TIMEOUT
+    }
+    
+    /// Add `c` to the jump prefix and select the next library row whose artist or title starts with it
+    ///
+    /// A one-letter prefix searches after the selection so repeating it cycles through matches;
+    /// longer prefixes refine in place.
+    fn type_jump(&mut self, c: char) {
+        if !self.type_jump_active() {
+            self.type_jump.clear();
+        }
+        self.type_jump.extend(c.to_lowercase());
+        self.type_jump_at = Instant::now();
+        
+        let len = self.filtered_tracks.len();
+        if len == 0 {
+            return;
+        }
+        let selected = self.list_state.selected().unwrap_or(0).min(len - 1);
+        let start = if self.type_jump.chars().count() == 1 { selected + 1 } else { selected };
+        
+        let prefix = self.type_jump.as_str();
+        let found = (start..len).chain(0..start).find(|&pos| {
+            let track = &self.tracks[self.filtered_tracks[pos]];
+            track.display_artist().to_lowercase().starts_with(prefix)
+                || track.display_title().to_lowercase().starts_with(prefix)
+        });
+        
+        match found {
+            Some(pos) => {
+                self.list_state.select(Some(pos));
+                self.set_status(&format!("🔎 Jump: {}", self.type_jump));
+            }
+            None => self.set_status(&format!("🔎 Jump: {} (no match)", self.type_jump)),
+        }
+    }
+    
+    /// Rows in the playlist tree view: one per playlist plus the tracks of expanded ones
+    fn playlist_tree_len(&self) -> usize {
+        self.visible_playlists().iter()
+            .map(|playlist| {
+                if self.expanded_playlists.contains(&playlist.id) {
+                    1 + playlist.get_valid_tracks(&self.tracks).len()
+                } else {
+                    1
+                }
+            })
+            .sum()
+    }
+    
+    /// (selected row, row count) of the list on the current tab, `None` without a list
+    fn selection_bounds(&self) -> Option<(usize, usize)> {
+        let (selected, len) = match self.current_tab {
+            AppTab::Library if self.library_view != GroupBy::None => (self.library_tree_state.selected(), self.library_rows.len()),
+            AppTab::Library => (self.list_state.selected(), self.filtered_tracks.len()),
+            AppTab::MetadataEditor => (self.metadata_list_state.selected(), self.tracks.len()),
+            AppTab::Playlists => (self.playlist_list_state.selected(), self.playlist_tree_len()),
+            AppTab::Settings | AppTab::Stats => return None,
+        };
+        (len > 0).then(|| (selected.unwrap_or(0).min(len - 1), len))
+    }
+    
+    /// Move `rows` up (negative) or down, stopping at either end of the list
+    fn move_by_rows(&mut self, rows: i64) {
+        match self.selection_bounds() {
+            Some((current, _)) => self.move_selection_to(current as i64 + rows),
+            None => self.move_selection(rows as i32), // Stats just scrolls
+        }
+    }
+    
+    /// Move to row `target`, clamped to the list, without move_selection's wrap-around
+    fn move_selection_to(&mut self, target: i64) {
+        if let Some((current, len)) = self.selection_bounds() {
+            let target = target.clamp(0, len as i64 - 1);
+            if target != current as i64 {
+                self.move_selection((target - current as i64) as i32);
+            }
         }
     }
     
@@ -1579,17 +5248,19 @@ impl InteractiveApp {
                 match self.edit_mode {
                     EditMode::Title => {
                         track.metadata.title = Some(self.edit_title.clone());
-                        self.set_status(&format!("✅ Title updated: {}", self.edit_title));
+                        self.set_status_level(NotificationLevel::Success, &format!("✅ Title updated: {}", self.edit_title));
                     }
                     EditMode::Artist => {
                         track.metadata.artist = Some(self.edit_artist.clone());
-                        self.set_status(&format!("✅ Artist updated: {}", self.edit_artist));
+                        self.set_status_level(NotificationLevel::Success, &format!("✅ Artist updated: {}", self.edit_artist));
                     }
                     EditMode::None => {}
                 }
                 
-                // TODO: Save to file tags and database
-                // For now, just update in memory
+                // Held in memory until Save All writes it into the file's tags
+                if self.edit_mode != EditMode::None {
+                    self.dirty_tracks.insert(self.tracks[track_idx].file_path.clone());
+                }
                 
                 self.edit_mode = EditMode::None;
                 self.editing_track_index = None;
@@ -1601,6 +5272,208 @@ impl InteractiveApp {
         Ok(())
     }
     
+    #[cfg(feature = "lyrics")]
+    fn toggle_lyrics(&mut self) {
+        let shown = !self.config.ui.show_lyrics;
+        self.config.ui.show_lyrics = shown;
+        if let Err(e) = self.config.save() {
+            warn!("Failed to save lyrics pane setting to config: {}", e);
+        }
+        self.sync_lyrics();
+        self.set_status(if shown { "🎤 Lyrics on" } else { "🎤 Lyrics off" });
+    }
+    
+    #[cfg(not(feature = "lyrics"))]
+    fn toggle_lyrics(&mut self) {
+        self.set_status("🎤 Lyrics need a build with the lyrics feature");
+    }
+    
+    /// Point the lyrics pane at the playing track, fetching its lyrics in the background the first time
+    #[cfg(feature = "lyrics")]
+    fn sync_lyrics(&mut self) {
+        if !self.config.ui.show_lyrics {
+            return;
+        }
+        let Some(track) = self.current_track_index.and_then(|idx| self.tracks.get(idx)) else {
+            self.lyrics = None;
+            return;
+        };
+        if self.lyrics.as_ref().is_some_and(|(path, _)| *path == track.file_path) {
+            return;
+        }
+        let Some(client) = self.lyrics_client.clone() else {
+            self.lyrics = Some((track.file_path.clone(), LyricsState::NotFound));
+            return;
+        };
+        
+        self.lyrics = Some((track.file_path.clone(), LyricsState::Loading));
+        let track = track.clone();
+        let events = self._event_tx.clone();
+        tokio::spawn(async move {
+            let lyrics = client.fetch(&track).await.unwrap_or_else(|e| {
+                debug!("🎤 No lyrics for {}: {}", track.file_path.display(), e);
+                None
+            });
+            let _ = events.send(InteractiveEvent::LyricsLoaded(track.file_path, lyrics));
+        });
+    }
+    
+    /// Ask MusicBrainz about the selected track in the background; the answer comes back as an event
+    #[cfg(feature = "musicbrainz")]
+    fn lookup_metadata(&mut self) {
+        let Some(track) = self.metadata_list_state.selected().and_then(|idx| self.tracks.get(idx)).cloned() else {
+            return;
+        };
+        let Some(client) = self.musicbrainz.clone() else {
+            self.push_notification(NotificationLevel::Error, "❌ MusicBrainz lookups are unavailable - see the log");
+            return;
+        };
+        
+        // Search by the current tags, or the filename parser's guess where they're missing
+        let known = |value: &Option<String>| value.as_deref().map(str::trim).filter(|v| !v.is_empty()).map(str::to_string);
+        let filename = track.file_path.file_name().and_then(|name| name.to_str()).unwrap_or("unknown");
+        let parsed = self.metadata_parser.parse_filename(filename);
+        let title = known(&track.metadata.title).unwrap_or(parsed.suggested_title);
+        let artist = known(&track.metadata.artist)
+            .or_else(|| (parsed.suggested_artist != "Unknown Artist").then_some(parsed.suggested_artist));
+        
+        self.set_status(&format!("🔎 Looking up '{}' on MusicBrainz...", track.display_title()));
+        let events = self._event_tx.clone();
+        tokio::spawn(async move {
+            let result = client.lookup(&track, artist.as_deref(), &title).await.map_err(|e| e.to_string());
+            let _ = events.send(InteractiveEvent::MetadataMatchesFound(track.file_path, result));
+        });
+    }
+    
+    #[cfg(not(feature = "musicbrainz"))]
+    fn lookup_metadata(&mut self) {
+        self.set_status("🔎 MusicBrainz lookups need a build with the musicbrainz feature");
+    }
+    
+    #[cfg(feature = "musicbrainz")]
+    fn show_metadata_matches(&mut self, path: PathBuf, result: Result<Vec<MetadataCandidate>, String>) {
+        match result {
+            Ok(candidates) if candidates.is_empty() => {
+                let name = path.file_name().unwrap_or(path.as_os_str()).to_string_lossy().into_owned();
+                self.set_status(&format!("🔎 No MusicBrainz matches for {}", name));
+            }
+            Ok(candidates) => self.metadata_matches = Some(MetadataMatches { path, candidates, selected: 0 }),
+            Err(e) => self.push_notification(NotificationLevel::Error, &format!("❌ MusicBrainz lookup failed: {}", e)),
+        }
+    }
+    
+    #[cfg(feature = "musicbrainz")]
+    fn metadata_matches_event(&mut self, event: InteractiveEvent) {
+        let Some(matches) = self.metadata_matches.as_mut() else {
+            return;
+        };
+        match event {
+            InteractiveEvent::StepMetadataMatch(delta) => {
+                let last = matches.candidates.len().saturating_sub(1) as i32;
+                matches.selected = (matches.selected as i32 + delta).clamp(0, last) as usize;
+            }
+            InteractiveEvent::ApplyMetadataMatch => {
+                let Some(matches) = self.metadata_matches.take() else {
+                    return;
+                };
+                let Some(candidate) = matches.candidates.get(matches.selected) else {
+                    return;
+                };
+                match self.tracks.iter_mut().find(|track| track.file_path == matches.path) {
+                    Some(track) => {
+                        candidate.apply_to(&mut track.metadata);
+                        self.dirty_tracks.insert(track.file_path.clone());
+                        self.set_status(&format!("🎼 Applied MusicBrainz tags: {}", candidate.display_name()));
+                    }
+                    None => self.push_notification(NotificationLevel::Warning, "⚠️ That track is no longer in the library"),
+                }
+            }
+            _ => self.metadata_matches = None,
+        }
+    }
+    
+    /// What's stacked over the current tab, outermost first
+    fn mode_stack(&self) -> Vec<ModeLayer> {
+        let popup_open = self.playlist_creation_mode
+            || self.show_playlist_selector
+            || self.show_scan_report
+            || self.show_device_picker
+            || self.show_bookmark_picker
+            || self.show_chapter_list
+            || self.history.is_some()
+            || self.show_error_log
+            || self.show_excluded_tracks
+            || self.behavior_detail.is_some()
+            || self.more_like_this.is_some()
+            || self.duplicate_review.is_some()
+            || self.rename_preview.is_some()
+            || self.background_task.is_some()
+            || self.metadata_matches_open();
+        // Marks and the tag filter only show in the library, so Esc elsewhere shouldn't silently drop them
+        let in_library = self.current_tab == AppTab::Library;
+        [
+            (ModeLayer::Marks, in_library && !self.marked_tracks.is_empty()),
+            (ModeLayer::TagFilter, in_library && self.tag_filter.is_some()),
+            (ModeLayer::Edit, self.edit_mode != EditMode::None),
+            (ModeLayer::Search, self.search_mode),
+            (ModeLayer::Help, self.show_help),
+            (ModeLayer::Popup, popup_open),
+            (ModeLayer::Confirm, self.pending_confirm.is_some()),
+        ]
+        .into_iter()
+        .filter_map(|(layer, active)| active.then_some(layer))
+        .collect()
+    }
+    
+    /// Leave the innermost mode: help, then an edit, then the tag filter, then the marks
+    fn escape(&mut self) {
+        match self.mode_stack().last() {
+            Some(ModeLayer::Help) => self.show_help = false,
+            Some(ModeLayer::Search) => self.exit_search(),
+            Some(ModeLayer::Edit) => {
+                self.edit_mode = EditMode::None;
+                self.editing_track_index = None;
+                self.edit_title.clear();
+                self.edit_artist.clear();
+                self.set_status("❌ Edit cancelled");
+            }
+            Some(ModeLayer::TagFilter) => self.clear_tag_filter(),
+            Some(ModeLayer::Marks) => {
+                self.marked_tracks.clear();
+                self.set_status("☐ Marks cleared");
+            }
+            // These map Esc to their own close event before it gets here
+            Some(ModeLayer::Popup | ModeLayer::Confirm) | None => {}
+        }
+    }
+    
+    fn exit_search(&mut self) {
+        self.search_mode = false;
+        self.search_query.clear();
+        self.search_pending_at = None;
+        self.reset_to_full_library();
+        self.clear_playlist_search();
+        debug!("🔍 Search mode exited");
+        self.set_status("🔍 Search exited");
+    }
+    
+    fn clear_tag_filter(&mut self) {
+        if self.tag_filter.take().is_some() {
+            self.resort_library();
+            self.set_status("🏷️ Filter cleared");
+        }
+    }
+    
+    #[cfg(feature = "musicbrainz")]
+    fn metadata_matches_open(&self) -> bool {
+        self.metadata_matches.is_some()
+    }
+    
+    #[cfg(not(feature = "musicbrainz"))]
+    fn metadata_matches_open(&self) -> bool {
+        false
+    }
+    
     async fn apply_filename_suggestion(&mut self, track_idx: usize) -> Result<()> {
         if track_idx < self.tracks.len() {
             let track = &self.tracks[track_idx];
@@ -1613,6 +5486,10 @@ impl InteractiveApp {
             // Update the track metadata with suggestions
             self.tracks[track_idx].metadata.title = Some(parsed.suggested_title.clone());
             self.tracks[track_idx].metadata.artist = Some(parsed.suggested_artist.clone());
+            if parsed.track_number.is_some() {
+                self.tracks[track_idx].metadata.track_number = parsed.track_number;
+            }
+            self.dirty_tracks.insert(self.tracks[track_idx].file_path.clone());
             
             self.set_status(&format!(
                 "🤖 Applied suggestion: {} - {} (confidence: {:.0}%)", 
@@ -1625,158 +5502,685 @@ impl InteractiveApp {
         Ok(())
     }
     
-    async fn reset_track_metadata(&mut self, track_idx: usize) -> Result<()> {
-        if track_idx < self.tracks.len() {
-            // Reset to original metadata from file tags
-            let track = &mut self.tracks[track_idx];
-            // For now, just clear the metadata - in a full implementation, 
-            // we'd reload from the original file tags
-            track.metadata.title = None;
-            track.metadata.artist = None;
-            
-            self.set_status("🔄 Reset to original metadata");
+    /// What the playlist selector is adding, for its title and status line
+    fn playlist_target_label(&self) -> String {
+        match self.tracks_for_playlist.as_slice() {
+            [track_idx] => self.tracks.get(*track_idx)
+                .map(|track| format!("'{}'", track.display_title()))
+                .unwrap_or_default(),
+            targets => format!("{} marked tracks", targets.len()),
+        }
+    }
+    
+    /// Append every pending track to a playlist, then drop the marks that picked them
+    fn add_tracks_to_playlist(&mut self, playlist_id: &str, playlist_name: &str) {
+        let targets = std::mem::take(&mut self.tracks_for_playlist);
+        let mut added = 0;
+        for &track_idx in &targets {
+            let track_path = self.tracks[track_idx].file_path.clone();
+            let duration = self.tracks[track_idx].duration;
+            match self.playlist_manager.add_track_to_playlist(playlist_id, &track_path, duration) {
+                Ok(_) => added += 1,
+                Err(e) => {
+                    // Added in memory, just not written out
+                    self.dirty_playlists.insert(playlist_id.to_string());
+                    self.push_notification(NotificationLevel::Error, &format!("❌ Failed to add track: {}", e));
+                    break;
+                }
+            }
+        }
+        self.refresh_playlist_context(playlist_id);
+        
+        if added == 1 && targets.len() == 1 {
+            let track_title = self.tracks[targets[0]].display_title();
+            self.push_notification(NotificationLevel::Success, &format!("➕ Added '{}' to '{}'", track_title, playlist_name));
+        } else if added > 0 {
+            self.push_notification(NotificationLevel::Success, &format!("➕ Added {} tracks to '{}'", added, playlist_name));
+        }
+        debug!("🎵 Added {} track(s) to existing playlist: {}", added, playlist_name);
+        self.marked_tracks.clear();
+    }
+    
+    /// Hide or delete the extra copies in the group on screen; deleting asks first, a dry run only reports
+    async fn resolve_duplicates(&mut self, action: DuplicateAction) {
+        let Some(review) = &self.duplicate_review else {
+            return;
+        };
+        let resolution = DuplicateResolution::new(&review.groups[review.selected], review.keep, action);
+        
+        if review.dry_run {
+            self.push_notification(NotificationLevel::Info, &format!("🔍 Dry run: {}", resolution.describe()));
+        } else if action == DuplicateAction::Delete {
+            self.pending_confirm = Some(ConfirmAction::DeleteDuplicates(resolution));
+        } else {
+            self.apply_duplicate_resolution(resolution).await;
+        }
+    }
+    
+    /// Drop the extra copies from the library; their plays and skips move to the copy that stays
+    async fn apply_duplicate_resolution(&mut self, resolution: DuplicateResolution) {
+        let outcome = resolution.apply();
+        
+        let id_of = |path: &PathBuf| self.tracks.iter().find(|t| &t.file_path == path).map(|t| t.id);
+        if let Some(keep_id) = id_of(&resolution.keep) {
+            let removed_ids: Vec<uuid::Uuid> = outcome.removed.iter().filter_map(id_of).collect();
+            for id in removed_ids {
+                if let Err(e) = self.behavior_tracker.merge_track_behavior(id, keep_id).await {
+                    warn!("Failed to merge behavior of a duplicate: {}", e);
+                }
+            }
+        }
+        
+        if resolution.action == DuplicateAction::Hide {
+            self.config.library.hidden_paths.extend(outcome.removed.iter().cloned());
+            if let Err(e) = self.config.save() {
+                warn!("Failed to save hidden duplicates to config: {}", e);
+            }
+        }
+        self.apply_library_change(outcome.removed.clone()).await;
+        self.scan_report.duplicate_groups = ScanReport::build(&self.tracks, Vec::new()).duplicate_groups;
+        
+        // Move on to the next group, or close once everything is resolved
+        if let Some(review) = self.duplicate_review.as_mut() {
+            review.groups = self.scan_report.duplicate_groups.clone();
+            review.selected = review.selected.min(review.groups.len().saturating_sub(1));
+            review.keep = 0;
+            if review.groups.is_empty() {
+                self.duplicate_review = None;
+            }
+        }
+        
+        let verb = if resolution.action == DuplicateAction::Hide { "Hid" } else { "Deleted" };
+        match outcome.failed.first() {
+            None => self.push_notification(NotificationLevel::Success, &format!("🧹 {} {} duplicate(s)", verb, outcome.removed.len())),
+            Some((path, error)) => self.push_notification(
+                NotificationLevel::Error,
+                &format!("❌ Could not delete {} file(s), e.g. {}: {}", outcome.failed.len(), path.display(), error),
+            ),
+        }
+        self.reload_behavior_views().await;
+    }
+    
+    /// Rename files on disk, then follow them in the library and in every playlist
+    async fn rename_files(&mut self, plan: RenamePlan) {
+        let outcome = plan.apply();
+        self.rename_preview = None;
+        
+        let renames: Vec<(PathBuf, PathBuf)> = outcome.renamed.iter()
+            .map(|rename| (rename.from.clone(), rename.to.clone()))
+            .collect();
+        let new_paths: HashMap<&PathBuf, &PathBuf> = renames.iter().map(|(from, to)| (from, to)).collect();
+        for track in &mut self.tracks {
+            if let Some(to) = new_paths.get(&track.file_path) {
+                track.file_path = (*to).clone();
+            }
+        }
+        self.dirty_tracks = self.dirty_tracks.drain()
+            .map(|path| new_paths.get(&path).map(|to| (*to).clone()).unwrap_or(path))
+            .collect();
+        if let Err(e) = self.playlist_manager.update_track_paths(&renames) {
+            warn!("Failed to save playlists after renaming files: {}", e);
+            let renamed: HashSet<&PathBuf> = new_paths.values().copied().collect();
+            for playlist in self.playlist_manager.list_playlists() {
+                if playlist.track_paths.iter().any(|path| renamed.contains(path)) {
+                    self.dirty_playlists.insert(playlist.id.clone());
+                }
+            }
+        }
+        // Nothing added or removed - this re-sorts and re-resolves every index by the new paths
+        self.apply_library_change(Vec::new()).await;
+        
+        match outcome.failed.first() {
+            None => self.push_notification(NotificationLevel::Success, &format!("✏️ Renamed {} file(s)", outcome.renamed.len())),
+            Some((path, error)) => self.push_notification(
+                NotificationLevel::Error,
+                &format!("❌ Renamed {}, {} failed, e.g. {}: {}", outcome.renamed.len(), outcome.failed.len(), path.display(), error),
+            ),
+        }
+    }
+    
+    /// Carry out an action the user just confirmed
+    async fn run_confirmed(&mut self, action: ConfirmAction) -> Result<()> {
+        match action {
+            ConfirmAction::DeletePlaylist { id, .. } => self.delete_playlist(&id),
+            ConfirmAction::ResetMetadata { path, .. } => self.reset_track_metadata(&path).await?,
+            ConfirmAction::ClearMetadata { path, .. } => self.clear_track_metadata(&path).await?,
+            ConfirmAction::ResetTrackBehavior { track_id, title } => {
+                match self.behavior_tracker.reset_track_behavior(track_id).await {
+                    Ok(true) => self.push_notification(NotificationLevel::Success, &format!("🧹 Reset stats for {}", title)),
+                    Ok(false) => self.set_status(&format!("{} had no stats to reset", title)),
+                    Err(e) => self.push_notification(NotificationLevel::Error, &format!("❌ Failed to reset stats: {}", e)),
+                }
+                self.reload_behavior_views().await;
+            }
+            ConfirmAction::DeleteDuplicates(resolution) => {
+                self.apply_duplicate_resolution(resolution).await;
+            }
+            ConfirmAction::RenameFiles(plan) => {
+                self.rename_files(plan).await;
+            }
+            ConfirmAction::QuitUnsaved { .. } => {
+                // Stay when something couldn't be saved, so the error can be read
+                if self.save_all().await {
+                    self.quit();
+                }
+            }
+            ConfirmAction::ResetAllBehavior => {
+                match self.behavior_tracker.reset_all_behaviors().await {
+                    Ok(count) => self.push_notification(NotificationLevel::Success, &format!("🧹 Reset behavior for {} tracks", count)),
+                    Err(e) => self.push_notification(NotificationLevel::Error, &format!("❌ Failed to reset behavior: {}", e)),
+                }
+                self.reload_behavior_views().await;
+            }
+        }
+        Ok(())
+    }
+    
+    fn delete_playlist(&mut self, playlist_id: &str) {
+        match self.playlist_manager.delete_playlist(playlist_id) {
+            Ok(deleted) => {
+                self.push_notification(NotificationLevel::Success, "🗑️ Playlist deleted");
+                info!("Deleted playlist: {}", playlist_id);
+                if deleted {
+                    // Keep the selection on the list if we deleted the last item
+                    let remaining = self.visible_playlists().len();
+                    if let Some(selected) = self.playlist_list_state.selected() {
+                        if selected >= remaining && selected > 0 {
+                            self.playlist_list_state.select(Some(selected - 1));
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                self.push_notification(NotificationLevel::Error, &format!("❌ Failed to delete playlist: {}", e));
+                error!("Failed to delete playlist: {}", e);
+            }
+        }
+    }
+    
+    /// Throw away unsaved edits by reading the file's tags again
+    async fn reset_track_metadata(&mut self, path: &Path) -> Result<()> {
+        if let Some(track_idx) = self.tracks.iter().position(|t| t.file_path == path) {
+            self.reload_track(track_idx).await;
         }
         Ok(())
     }
     
-    async fn bulk_apply_suggestions(&mut self) -> Result<()> {
-        let mut applied_count = 0;
-        let total_tracks = self.tracks.len();
+    /// Claim the task slot for a new job, or say what's still running. Returns its cancel flag.
+    fn start_task(&mut self, label: &'static str, total: usize, results_label: Option<&'static str>) -> Option<Arc<AtomicBool>> {
+        if let Some(task) = &self.background_task {
+            self.set_status(&format!("⏳ {} is still running - wait for it or press Esc to cancel", task.label));
+            return None;
+        }
+        let cancel = Arc::new(AtomicBool::new(false));
+        self.background_task = Some(BackgroundTask { label, done: 0, total, results: 0, results_label, cancel: cancel.clone() });
+        Some(cancel)
+    }
+    
+    /// Parse every filename on a worker, applying the confident suggestions batch by batch as they come back
+    fn bulk_apply_suggestions(&mut self) {
+        let paths: Vec<PathBuf> = self.tracks.iter().map(|t| t.file_path.clone()).collect();
+        let Some(cancel) = self.start_task("Applying filename suggestions", paths.len(), Some("applied")) else {
+            return;
+        };
+        let parser = self.metadata_parser.clone();
+        let events = self._event_tx.clone();
+        tokio::task::spawn_blocking(move || {
+            let total = paths.len();
+            let mut done = 0;
+            for batch in paths.chunks(BULK_APPLY_BATCH) {
+                if cancel.load(Ordering::Relaxed) {
+                    break;
+                }
+                let suggestions = batch.iter()
+                    .filter_map(|path| {
+                        let filename = path.file_name().and_then(|name| name.to_str()).unwrap_or("unknown");
+                        let parsed = parser.parse_filename(filename);
+                        // Only apply if confidence is reasonable (>50%)
+                        (parsed.confidence > 0.5).then(|| (path.clone(), parsed))
+                    })
+                    .collect();
+                done += batch.len();
+                let _ = events.send(InteractiveEvent::SuggestionsParsed(suggestions));
+                let _ = events.send(InteractiveEvent::TaskProgress { done, total });
+            }
+            let _ = events.send(InteractiveEvent::TaskFinished(TaskResult::BulkApply { total }));
+        });
+    }
+    
+    /// One batch from `bulk_apply_suggestions`, matched back to the library by path
+    fn apply_parsed_suggestions(&mut self, suggestions: Vec<(PathBuf, ParsedMetadata)>) {
+        // A batch already on its way when Esc was pressed isn't applied
+        let Some(task) = self.background_task.as_mut().filter(|task| !task.cancel.load(Ordering::Relaxed)) else {
+            return;
+        };
+        let indices: HashMap<&Path, usize> = self.tracks.iter().enumerate().map(|(idx, t)| (t.file_path.as_path(), idx)).collect();
+        let found: Vec<(usize, ParsedMetadata)> = suggestions.into_iter()
+            .filter_map(|(path, parsed)| indices.get(path.as_path()).map(|&idx| (idx, parsed)))
+            .collect();
         
-        for i in 0..total_tracks {
-            let track = &self.tracks[i];
-            let filename = track.file_path.file_name()
-                .and_then(|name| name.to_str())
-                .unwrap_or("unknown");
-            
-            let parsed = self.metadata_parser.parse_filename(filename);
-            
-            // Only apply if confidence is reasonable (>50%)
-            if parsed.confidence > 0.5 {
-                self.tracks[i].metadata.title = Some(parsed.suggested_title);
-                self.tracks[i].metadata.artist = Some(parsed.suggested_artist);
-                applied_count += 1;
+        for (idx, parsed) in found {
+            let track = &mut self.tracks[idx];
+            track.metadata.title = Some(parsed.suggested_title);
+            track.metadata.artist = Some(parsed.suggested_artist);
+            if parsed.track_number.is_some() {
+                track.metadata.track_number = parsed.track_number;
             }
+            self.dirty_tracks.insert(track.file_path.clone());
+            task.results += 1;
         }
-        
-        self.set_status(&format!(
-            "🚀 Bulk applied suggestions to {}/{} tracks (confidence >50%)", 
-            applied_count, 
-            total_tracks
-        ));
-        
-        Ok(())
     }
     
-    async fn clear_track_metadata(&mut self, track_idx: usize) -> Result<()> {
-        if track_idx < self.tracks.len() {
-            let track = &mut self.tracks[track_idx];
+    /// Close the progress overlay and report what the task got through
+    fn finish_task(&mut self, result: TaskResult) {
+        let Some(task) = self.background_task.take() else {
+            return;
+        };
+        let cancelled = task.cancel.load(Ordering::Relaxed);
+        match result {
+            TaskResult::BulkApply { total } => {
+                let message = format!("🚀 Bulk applied suggestions to {}/{} tracks (confidence >50%)", task.results, total);
+                if cancelled {
+                    self.set_status_level(NotificationLevel::Warning, &format!("{} - cancelled after {} files", message, task.done));
+                } else {
+                    self.set_status(&message);
+                }
+            }
+            TaskResult::PlaylistsExported { dir, report: Err(e) } => {
+                self.push_notification(NotificationLevel::Error, &format!("❌ Playlist export to {} failed: {}", dir.display(), e));
+            }
+            TaskResult::PlaylistsExported { dir, report: Ok(report) } => {
+                let mut message = format!("📤 Exported {} playlists to {}", report.exported.len(), dir.display());
+                if !report.failed.is_empty() {
+                    let names: Vec<&str> = report.failed.iter().map(|(name, _)| name.as_str()).collect();
+                    message.push_str(&format!(" - skipped {}: {}", names.len(), names.join(", ")));
+                }
+                if report.cancelled {
+                    message.push_str(" - cancelled before the rest");
+                }
+                let level = if report.exported.is_empty() || report.cancelled { NotificationLevel::Warning } else { NotificationLevel::Success };
+                self.push_notification(level, &message);
+            }
+        }
+    }
+    
+    async fn clear_track_metadata(&mut self, path: &Path) -> Result<()> {
+        if let Some(track) = self.tracks.iter_mut().find(|t| t.file_path == path) {
             track.metadata.title = None;
             track.metadata.artist = None;
+            self.dirty_tracks.insert(track.file_path.clone());
             
             self.set_status("🗑️ Cleared track metadata");
         }
         Ok(())
     }
     
-    // All visualizer methods removed for performance optimization
+    /// Edits that Save All would write: retagged tracks plus playlists that failed to save
+    fn unsaved_count(&self) -> usize {
+        self.dirty_tracks.len() + self.dirty_playlists.len()
+    }
+    
+    fn quit(&mut self) {
+        // Workers stop at their next check rather than holding up the exit
+        if let Some(task) = &self.background_task {
+            task.cancel.store(true, Ordering::Relaxed);
+        }
+        let unsaved = self.unsaved_count();
+        if unsaved > 0 {
+            warn!("Quitting with {} unsaved change(s)", unsaved);
+        }
+        self.save_spoken_word_position();
+        self.write_session(self.resume_point());
+        self.should_quit = true;
+    }
     
-    async fn update_playback_status(&mut self) -> Result<()> {
+    /// Write out everything held in memory: edited tags, playlists whose save failed, and the
+    /// session. Returns whether nothing is left unsaved.
+    async fn save_all(&mut self) -> bool {
+        let mut failures = Vec::new();
         
-
+        let mut tags_written = 0;
+        let mut paths: Vec<PathBuf> = self.dirty_tracks.iter().cloned().collect();
+        paths.sort();
+        for path in paths {
+            let Some(track_idx) = self.tracks.iter().position(|track| track.file_path == path) else {
+                // Deleted or dropped from the library since, so there's nothing to write to
+                self.dirty_tracks.remove(&path);
+                continue;
+            };
+            match self.write_track_tags(track_idx).await {
+                Ok(()) => {
+                    self.dirty_tracks.remove(&path);
+                    tags_written += 1;
+                }
+                Err(e) => {
+                    error!("Failed to write tags to {}: {}", path.display(), e);
+                    failures.push(format!("{}: {}", path.file_name().unwrap_or_default().to_string_lossy(), e));
+                }
+            }
+        }
+        
+        let mut playlists_saved = 0;
+        let ids: Vec<String> = self.dirty_playlists.iter().cloned().collect();
+        for id in ids {
+            let Some(playlist) = self.playlist_manager.get_playlist(&id).cloned() else {
+                self.dirty_playlists.remove(&id);
+                continue;
+            };
+            match self.playlist_manager.save_playlist(&playlist) {
+                Ok(()) => {
+                    self.dirty_playlists.remove(&id);
+                    playlists_saved += 1;
+                }
+                Err(e) => failures.push(format!("'{}': {}", playlist.name, e)),
+            }
+        }
         
-        // Update time tracking if playing
-        if self.is_playing {
-            let now = Instant::now();
-            let elapsed = now.duration_since(self.last_position_update);
-            self.current_position += elapsed;
-            self.last_position_update = now;
+        // Behavior is written as it happens; the session and audiobook position are the rest
+        self.save_spoken_word_position();
+        self.write_session(self.resume_point());
+        if tags_written > 0 {
+            // Retagged files hash to new ids, so the stats views need reloading
+            self.reload_behavior_views().await;
+            self.search_index = SearchIndex::build(&self.tracks);
+            self.resort_library();
         }
         
-        // Update visualizer data
-        // Visualizer removed for performance optimization
+        match failures.as_slice() {
+            [] if tags_written + playlists_saved == 0 => self.set_status("💾 Nothing unsaved"),
+            [] => self.push_notification(
+                NotificationLevel::Success,
+                &format!("💾 Saved tags of {} track(s) and {} playlist(s)", tags_written, playlists_saved),
+            ),
+            [first, rest @ ..] => self.push_notification(
+                NotificationLevel::Error,
+                &format!("❌ {} change(s) not saved, e.g. {}", rest.len() + 1, first),
+            ),
+        }
+        failures.is_empty()
+    }
+    
+    /// Write a track's tags into its file, then move its history onto the id the new bytes hash to
+    async fn write_track_tags(&mut self, track_idx: usize) -> Result<()> {
+        let track = &self.tracks[track_idx];
+        tag_writer::write_tags(&track.file_path, &track.metadata)?;
         
-        // NOTE: Removed problematic UI-based completion detection
-        // The is_finished() check was returning true immediately due to sink.empty()
-        // causing premature track advancement and state resets
-        // Track completion will be handled by PlayerEvent::TrackFinished events
+        let mut rewritten = track.clone();
+        rewritten.content_hash = None;
+        let new_hash = rewritten.compute_content_hash()?;
+        let new_id = self.behavior_tracker.move_track_history(&self.tracks, track_idx, new_hash).await?;
         
+        let track = &mut self.tracks[track_idx];
+        track.id = new_id;
+        track.content_hash = Some(new_hash);
         Ok(())
     }
     
     fn set_status(&mut self, message: &str) {
-        self.status_message = Some((message.to_string(), Instant::now()));
+        self.set_status_level(NotificationLevel::Info, message);
+    }
+    
+    /// Status bar message colored by level; warnings and errors are kept in the error log too
+    fn set_status_level(&mut self, level: NotificationLevel, message: &str) {
+        self.log_problem(level, message);
+        self.status_message = Some((message.to_string(), level, Instant::now()));
+        self.needs_redraw = true;
+    }
+    
+    fn log_problem(&mut self, level: NotificationLevel, message: &str) {
+        if matches!(level, NotificationLevel::Warning | NotificationLevel::Error) {
+            self.error_log.record(level, message);
+        }
+    }
+    
+    /// Drop a status message or toasts that have timed out, redrawing only if one went
+    fn expire_messages(&mut self) {
+        if self.status_message.as_ref().is_some_and(|(_, _, at)| at.elapsed() >= STATUS_DURATION) {
+            self.status_message = None;
+            self.needs_redraw = true;
+        }
+        let toasts = self.notifications.visible().count();
+        self.notifications.prune();
+        if self.notifications.visible().count() != toasts {
+            self.needs_redraw = true;
+        }
+    }
+    
+    /// How long to wait for input before the next pass over background work and ticks
+    ///
+    /// Anything that can change without a key - playback, an open overlay, a message about to
+    /// expire, a search waiting out its debounce - keeps the short interval; otherwise idle.
+    fn poll_interval(&self) -> Duration {
+        if self.search_pending_at.is_some() {
+            SEARCH_DEBOUNCE
+        } else if self.is_playing && self.config.ui.visualizer {
+            VISUALIZER_FRAME
+        } else if self.is_playing
+            || self.status_message.is_some()
+            || !self.notifications.is_empty()
+            || !self.mode_stack().is_empty()
+        {
+            PLAYING_FRAME
+        } else {
+            IDLE_POLL
+        }
+    }
+    
+    /// Longer-lived confirmations and errors go to toasts, or the status bar if toasts are disabled
+    fn push_notification(&mut self, level: NotificationLevel, message: &str) {
+        if self.config.ui.show_notifications {
+            self.log_problem(level, message);
+            self.notifications.push(level, message);
+        } else {
+            self.set_status_level(level, message);
+        }
     }
     
     fn render(&mut self) -> Result<()> {
         let current_track_index = self.current_track_index;
         let is_playing = self.is_playing;
-        let volume = self.volume;
-        let repeat_mode = self.repeat_mode.clone();
-        let is_shuffled = self.is_shuffled;
+        let volume = self.audio_player.get_volume();
+        let (is_shuffled, repeat_mode) = self.playback_modes();
         let status_message = self.status_message.clone();
+        let theme = self.theme.clone();
+        let sort_label = self.sort_label();
+        let filter_label = self.tag_filter.as_ref().map(TagFilter::label);
+        let badges: Vec<String> = self.stop_after_current.then(|| "⏹ after track".to_string())
+            .into_iter()
+            .chain(self.sleep_timer_label())
+            .chain(self.speed_label())
+            .chain(self.muted_volume.map(|_| "🔇 muted".to_string()))
+            .chain(self.spoken_word_label())
+            .collect();
+        let playlist_target = self.playlist_target_label();
+        let confirm_prompt = self.pending_confirm.as_ref().map(|action| (action.prompt(), action.hint()));
+        let unsaved = self.unsaved_count();
+        let bookmark_positions: Vec<Duration> = self.bookmarks.iter().map(Bookmark::position).collect();
+        let search_scope = self.search_scope_label();
+        let search_results = if self.current_tab == AppTab::Playlists {
+            self.visible_playlists().len()
+        } else {
+            self.filtered_tracks.len()
+        };
+        self.notifications.prune();
+        let spectrum = self.config.ui.visualizer.then(|| self.audio_player.visualizer().levels());
+        let show_remaining_time = self.config.ui.show_remaining_time;
+        let min_size = (
+            self.config.ui.min_width.max(MIN_TERMINAL_WIDTH),
+            self.config.ui.min_height.max(MIN_TERMINAL_HEIGHT),
+        );
+        #[cfg(feature = "lyrics")]
+        let show_lyrics = self.config.ui.show_lyrics;
+        let excluded_tracks = self.excluded_track_indices();
         
         // Attempt render with error recovery
         match self.terminal.draw(|f| {
             let size = f.area();
+            if size.width < min_size.0 || size.height < min_size.1 {
+                Self::render_too_small(f, size, &theme, min_size);
+                self.progress_bar_area = None;
+                return;
+            }
             
-            // Create main layout (visualizer removed)
+            // Create main layout; the visualizer pane only exists while it's enabled and there's room
+            let compact = size.height < COMPACT_HEIGHT;
+            let visualizer_height = if spectrum.is_some() && !compact { VISUALIZER_HEIGHT } else { 0 };
             let chunks = Layout::default()
                 .direction(Direction::Vertical)
                 .constraints([
                     Constraint::Length(3), // Header
-                    Constraint::Min(6),    // Content (reduced to make room)
-                    Constraint::Length(4), // Player controls
-                    Constraint::Length(3), // Status bar (increased for visibility)
+                    Constraint::Min(3),    // Content
+                    Constraint::Length(visualizer_height),
+                    Constraint::Length(if compact { 1 } else { 4 }), // Player controls
+                    Constraint::Length(if compact { 1 } else { 3 }), // Status bar
                 ])
                 .split(size);
             
             // Render header with tabs
-            Self::render_header_with_tabs(f, chunks[0], &self.current_tab);
+            Self::render_header_with_tabs(f, chunks[0], &theme, &self.current_tab, unsaved);
+            
+            // The lyrics pane takes the right side of the content area while it's open
+            #[cfg(feature = "lyrics")]
+            let (content_area, lyrics_area) = if show_lyrics {
+                let columns = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([Constraint::Percentage(62), Constraint::Percentage(38)])
+                    .split(chunks[1]);
+                (columns[0], Some(columns[1]))
+            } else {
+                (chunks[1], None)
+            };
+            #[cfg(not(feature = "lyrics"))]
+            let content_area = chunks[1];
+            
+            // Paging moves by what fits inside the list's borders
+            self.list_page_rows = content_area.height.saturating_sub(2);
             
             // Render content based on current tab
             match &self.current_tab {
+                AppTab::Library if self.library_view != GroupBy::None => {
+                    Self::render_library_tree(f, content_area, &theme, &self.library_rows, &self.tracks, &self.marked_tracks, &self.shuffle_excluded, self.library_view, current_track_index, is_playing, &mut self.library_tree_state, filter_label.as_deref());
+                }
                 AppTab::Library => {
-                    Self::render_track_list(f, chunks[1], &self.tracks, &self.filtered_tracks, current_track_index, is_playing, &mut self.list_state);
+                    Self::render_track_list(f, content_area, &theme, &self.tracks, &self.filtered_tracks, &self.marked_tracks, &self.shuffle_excluded, current_track_index, is_playing, &mut self.list_state, &sort_label, filter_label.as_deref());
                 }
                 AppTab::Playlists => {
-                    Self::render_playlists_tree_view(f, chunks[1], &self.playlist_manager, &mut self.playlist_list_state, &self.expanded_playlists, &self.tracks, &self.playlist_track_states, current_track_index, is_playing);
+                    Self::render_playlists_tree_view(f, content_area, &theme, &self.playlist_manager, self.playlist_search_matches.as_ref(), &mut self.playlist_list_state, &self.expanded_playlists, &self.tracks, &self.playlist_track_states, current_track_index, is_playing);
                 }
                 AppTab::MetadataEditor => {
-                    Self::render_metadata_editor(f, chunks[1], &self.tracks, &self.metadata_parser, &mut self.metadata_list_state, &self.edit_mode, &self.edit_title, &self.edit_artist, self.editing_track_index);
+                    Self::render_metadata_editor(f, content_area, &theme, &self.tracks, &self.metadata_parser, &mut self.metadata_list_state, &self.edit_mode, &self.edit_title, &self.edit_artist, self.editing_track_index);
                 }
                 AppTab::Settings => {
-                    Self::render_settings(f, chunks[1]);
+                    Self::render_settings(f, content_area, &theme, &self.scan_report, &self.config.audio, self.config.ui.visualizer, self.eq_band, excluded_tracks.len(), &self.keymap);
+                }
+                AppTab::Stats => {
+                    Self::render_stats(f, content_area, &theme, &self.listening_stats, &self.library_stats, &self.tracks, self.stats_scroll);
                 }
             }
             
-            // Render player controls (visualizer removed)
-            Self::render_player_controls(f, chunks[2], &self.tracks, current_track_index, is_playing, volume, repeat_mode, is_shuffled, self.current_position, self.total_duration);
+            #[cfg(feature = "lyrics")]
+            if let Some(area) = lyrics_area {
+                Self::render_lyrics(f, area, &theme, self.lyrics.as_ref().map(|(_, state)| state), self.current_position, self.total_duration);
+            }
+            
+            if let Some(levels) = spectrum.as_ref().filter(|_| visualizer_height > 0) {
+                Self::render_visualizer(f, chunks[2], &theme, levels, is_playing);
+            }
+            
+            // Render player controls
+            let progress_bar = Self::render_player_controls(f, chunks[3], &theme, &self.tracks, current_track_index, is_playing, volume, repeat_mode, is_shuffled, self.current_position, self.total_duration, show_remaining_time, &badges, self.ab_loop, &bookmark_positions);
+            // Clicks only seek when there's a known length to take a share of
+            self.progress_bar_area = self.total_duration.filter(|t| !t.is_zero()).map(|_| progress_bar);
             
             // Render status bar
-            Self::render_status_bar(f, chunks[3], status_message);
+            Self::render_status_bar(f, chunks[4], &theme, status_message);
             
             // Render search input if in search mode
             if self.search_mode {
-                Self::render_search_input(f, size, &self.search_query, self.filtered_tracks.len());
+                Self::render_search_input(f, size, &theme, &self.search_query, &search_scope, search_results);
             }
             
             // Render playlist creation input if in playlist creation mode
             if self.playlist_creation_mode {
-                Self::render_playlist_input(f, size, &self.playlist_name_input);
+                Self::render_playlist_input(f, size, &theme, &self.playlist_name_input);
             }
             
             // Render playlist selector overlay if active
-            if self.show_playlist_selector {
-                if let Some(track_idx) = self.selected_track_for_playlist {
-                    let track_title = self.tracks[track_idx].display_title();
-                    Self::render_playlist_selector_overlay(f, size, &self.playlist_manager, &mut self.playlist_selector_state, &track_title);
-                }
+            if self.show_playlist_selector && !self.tracks_for_playlist.is_empty() {
+                Self::render_playlist_selector_overlay(f, size, &theme, &self.playlist_manager, &mut self.playlist_selector_state, &playlist_target);
+            }
+            
+            // Render output device picker if active
+            if self.show_device_picker {
+                Self::render_device_picker_overlay(f, size, &theme, &self.output_devices, &mut self.device_picker_state);
+            }
+            
+            // Render bookmark picker if active
+            if self.show_bookmark_picker {
+                Self::render_bookmark_picker_overlay(f, size, &theme, &self.bookmarks, &mut self.bookmark_picker_state);
+            }
+            
+            if self.show_chapter_list {
+                let chapters = self.spoken_word.as_ref().map_or(&[][..], |spoken| spoken.chapters.as_slice());
+                Self::render_chapter_list_overlay(f, size, &theme, chapters, &mut self.chapter_list_state);
+            }
+            
+            if let Some(history) = &self.history {
+                Self::render_history_overlay(f, size, &theme, history, &mut self.history_state);
+            }
+            
+            if self.show_error_log {
+                Self::render_error_log_overlay(f, size, &theme, &self.error_log, &mut self.error_log_state);
+            }
+            
+            if self.show_excluded_tracks {
+                Self::render_excluded_tracks_overlay(f, size, &theme, &self.tracks, &excluded_tracks, &mut self.excluded_tracks_state);
+            }
+            
+            // Render behavior detail if open
+            if let Some(detail) = &self.behavior_detail {
+                Self::render_behavior_detail_overlay(f, size, &theme, detail);
+            }
+            
+            if let Some(view) = &self.more_like_this {
+                Self::render_more_like_this_overlay(f, size, &theme, view, &self.tracks, &mut self.more_like_this_state);
+            }
+            
+            // Render scan report overlay if active
+            if self.show_scan_report {
+                Self::render_scan_report_overlay(f, size, &theme, &self.scan_report, self.scan_report_scroll);
+            }
+            
+            if let Some(task) = &self.background_task {
+                Self::render_task_overlay(f, size, &theme, task);
+            }
+            
+            if let Some(review) = &self.duplicate_review {
+                Self::render_duplicates_overlay(f, size, &theme, review, &self.tracks);
+            }
+            
+            if let Some(preview) = &self.rename_preview {
+                Self::render_rename_preview_overlay(f, size, &theme, preview, &self.config.library.rename_pattern);
+            }
+            
+            #[cfg(feature = "musicbrainz")]
+            if let Some(matches) = &self.metadata_matches {
+                Self::render_metadata_matches_overlay(f, size, &theme, matches);
             }
             
             // Render help overlay if active
             if self.show_help {
-                Self::render_help_overlay(f, size);
+                Self::render_help_overlay(f, size, &theme, &self.keymap, &self.current_tab);
+            }
+            
+            // Confirmation is modal, so it goes above the other overlays
+            if let Some((prompt, hint)) = &confirm_prompt {
+                Self::render_confirm_overlay(f, size, &theme, prompt, hint);
+            }
+            
+            // Toasts sit on top of everything else
+            if !self.notifications.is_empty() {
+                Self::render_notifications(f, size, &theme, &self.notifications);
             }
         }) {
             Ok(_) => Ok(()),
@@ -1788,38 +6192,49 @@ impl InteractiveApp {
         }
     }
     
-    fn render_header_with_tabs(f: &mut Frame, area: Rect, current_tab: &AppTab) {
-        let tab_titles = vec![
+    fn render_header_with_tabs(f: &mut Frame, area: Rect, theme: &Theme, current_tab: &AppTab, unsaved: usize) {
+        let mut tab_titles = vec![
+            match current_tab {
+                AppTab::Library => Span::styled("1. 📚 Library", Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)),
+                _ => Span::styled("1. 📚 Library", Style::default().fg(theme.muted)),
+            },
+            Span::raw(" | "),
             match current_tab {
-                AppTab::Library => Span::styled("1. 📚 Library", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
-                _ => Span::styled("1. 📚 Library", Style::default().fg(Color::Gray)),
+                AppTab::Playlists => Span::styled("2. 🎵 Playlists", Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)),
+                _ => Span::styled("2. 🎵 Playlists", Style::default().fg(theme.muted)),
             },
             Span::raw(" | "),
             match current_tab {
-                AppTab::Playlists => Span::styled("2. 🎵 Playlists", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
-                _ => Span::styled("2. 🎵 Playlists", Style::default().fg(Color::Gray)),
+                AppTab::MetadataEditor => Span::styled("3. 🏷️ Metadata Editor", Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)),
+                _ => Span::styled("3. 🏷️ Metadata Editor", Style::default().fg(theme.muted)),
             },
             Span::raw(" | "),
             match current_tab {
-                AppTab::MetadataEditor => Span::styled("3. 🏷️ Metadata Editor", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
-                _ => Span::styled("3. 🏷️ Metadata Editor", Style::default().fg(Color::Gray)),
+                AppTab::Settings => Span::styled("4. ⚙️ Settings", Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)),
+                _ => Span::styled("4. ⚙️ Settings", Style::default().fg(theme.muted)),
             },
             Span::raw(" | "),
             match current_tab {
-                AppTab::Settings => Span::styled("4. ⚙️ Settings", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
-                _ => Span::styled("4. ⚙️ Settings", Style::default().fg(Color::Gray)),
+                AppTab::Stats => Span::styled("5. 📊 Stats", Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)),
+                _ => Span::styled("5. 📊 Stats", Style::default().fg(theme.muted)),
             },
         ];
+        if unsaved > 0 {
+            tab_titles.push(Span::raw("   "));
+            tab_titles.push(Span::styled(format!("● {} unsaved", unsaved), Style::default().fg(theme.warning).add_modifier(Modifier::BOLD)));
+        }
         
         let header = Paragraph::new(Line::from(tab_titles))
-            .style(Style::default().fg(Color::Cyan))
+            .style(Style::default().fg(theme.primary))
             .block(Block::default().borders(Borders::ALL).title("🎵 BangTunes"));
         f.render_widget(header, area);
     }
     
+    #[allow(clippy::too_many_arguments)]
     fn render_metadata_editor(
         f: &mut Frame,
         area: Rect,
+        theme: &Theme,
         tracks: &[panpipe::Track],
         metadata_parser: &MetadataParser,
         list_state: &mut ListState,
@@ -1833,11 +6248,14 @@ impl InteractiveApp {
             .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
             .split(area);
         
-        // Left side: Track list with metadata
-        let items: Vec<ListItem> = tracks
+        // Left side: Track list with metadata - parsing filenames is costly, so only the rows on screen
+        let (window, mut window_state) = Self::visible_window(list_state, tracks.len(), chunks[0]);
+        let first_row = window.start;
+        let items: Vec<ListItem> = tracks[window]
             .iter()
             .enumerate()
-            .map(|(i, track)| {
+            .map(|(row, track)| {
+                let i = first_row + row;
                 let filename = track.file_path.file_name()
                     .and_then(|name| name.to_str())
                     .unwrap_or("unknown");
@@ -1851,7 +6269,7 @@ impl InteractiveApp {
                 
                 let is_editing = editing_track_index == Some(i);
                 let style = if is_editing {
-                    Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+                    Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)
                 } else {
                     Style::default()
                 };
@@ -1875,29 +6293,29 @@ impl InteractiveApp {
                     .borders(Borders::ALL)
                     .title("Metadata Editor (🟢=Good 🟡=OK 🔴=Poor)")
             )
-            .highlight_style(Style::default().bg(Color::DarkGray))
+            .highlight_style(Style::default().bg(theme.highlight_bg))
             .highlight_symbol("→ ");
         
-        f.render_stateful_widget(list, chunks[0], list_state);
+        f.render_stateful_widget(list, chunks[0], &mut window_state);
         
         // Right side: Edit panel
         let edit_content = match edit_mode {
             EditMode::Title => {
                 vec![
-                    Line::from(vec![Span::styled("Editing Title:", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD))]),
+                    Line::from(vec![Span::styled("Editing Title:", Style::default().fg(theme.playing).add_modifier(Modifier::BOLD))]),
                     Line::from(vec![Span::raw("")]),
-                    Line::from(vec![Span::styled(edit_title, Style::default().fg(Color::White).add_modifier(Modifier::UNDERLINED))]),
+                    Line::from(vec![Span::styled(edit_title, Style::default().fg(theme.text).add_modifier(Modifier::UNDERLINED))]),
                     Line::from(vec![Span::raw("")]),
-                    Line::from(vec![Span::styled("Press Enter to save, Esc to cancel", Style::default().fg(Color::Gray))]),
+                    Line::from(vec![Span::styled("Press Enter to save, Esc to cancel", Style::default().fg(theme.muted))]),
                 ]
             }
             EditMode::Artist => {
                 vec![
-                    Line::from(vec![Span::styled("Editing Artist:", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD))]),
+                    Line::from(vec![Span::styled("Editing Artist:", Style::default().fg(theme.playing).add_modifier(Modifier::BOLD))]),
                     Line::from(vec![Span::raw("")]),
-                    Line::from(vec![Span::styled(edit_artist, Style::default().fg(Color::White).add_modifier(Modifier::UNDERLINED))]),
+                    Line::from(vec![Span::styled(edit_artist, Style::default().fg(theme.text).add_modifier(Modifier::UNDERLINED))]),
                     Line::from(vec![Span::raw("")]),
-                    Line::from(vec![Span::styled("Press Enter to save, Esc to cancel", Style::default().fg(Color::Gray))]),
+                    Line::from(vec![Span::styled("Press Enter to save, Esc to cancel", Style::default().fg(theme.muted))]),
                 ]
             }
             EditMode::None => {
@@ -1914,29 +6332,40 @@ impl InteractiveApp {
                         let current_artist = track.display_artist();
                         let suggested_title = parsed.suggested_title.clone();
                         let suggested_artist = parsed.suggested_artist.clone();
+                        let current_position = grouping::disc_track_label(track).unwrap_or_else(|| "-".to_string());
+                        let suggested_position = parsed.track_number.map(|n| format!("{:02}", n)).unwrap_or_else(|| "-".to_string());
+                        let featured_text = if parsed.featured_artists.is_empty() {
+                            "-".to_string()
+                        } else {
+                            parsed.featured_artists.join(", ")
+                        };
                         let confidence_text = format!("Confidence: {:.0}%", parsed.confidence * 100.0);
                         
                         vec![
-                            Line::from(vec![Span::styled("Current Track:", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))]),
+                            Line::from(vec![Span::styled("Current Track:", Style::default().fg(theme.primary).add_modifier(Modifier::BOLD))]),
                             Line::from(vec![Span::raw("")]),
-                            Line::from(vec![Span::styled("Title: ", Style::default().fg(Color::Gray)), Span::raw(current_title)]),
-                            Line::from(vec![Span::styled("Artist: ", Style::default().fg(Color::Gray)), Span::raw(current_artist)]),
+                            Line::from(vec![Span::styled("Title: ", Style::default().fg(theme.muted)), Span::raw(current_title)]),
+                            Line::from(vec![Span::styled("Artist: ", Style::default().fg(theme.muted)), Span::raw(current_artist)]),
+                            Line::from(vec![Span::styled("Track: ", Style::default().fg(theme.muted)), Span::raw(current_position)]),
                             Line::from(vec![Span::raw("")]),
-                            Line::from(vec![Span::styled("Suggested:", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD))]),
-                            Line::from(vec![Span::styled("Title: ", Style::default().fg(Color::Gray)), Span::raw(suggested_title)]),
-                            Line::from(vec![Span::styled("Artist: ", Style::default().fg(Color::Gray)), Span::raw(suggested_artist)]),
-                            Line::from(vec![Span::styled(confidence_text, Style::default().fg(Color::Yellow))]),
+                            Line::from(vec![Span::styled("Suggested:", Style::default().fg(theme.playing).add_modifier(Modifier::BOLD))]),
+                            Line::from(vec![Span::styled("Title: ", Style::default().fg(theme.muted)), Span::raw(suggested_title)]),
+                            Line::from(vec![Span::styled("Artist: ", Style::default().fg(theme.muted)), Span::raw(suggested_artist)]),
+                            Line::from(vec![Span::styled("Track: ", Style::default().fg(theme.muted)), Span::raw(suggested_position)]),
+                            Line::from(vec![Span::styled("Featuring: ", Style::default().fg(theme.muted)), Span::raw(featured_text)]),
+                            Line::from(vec![Span::styled(confidence_text, Style::default().fg(theme.accent))]),
                             Line::from(vec![Span::raw("")]),
-                            Line::from(vec![Span::styled("Controls:", Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD))]),
-                            Line::from(vec![Span::styled("t", Style::default().fg(Color::Yellow)), Span::raw(" = Edit Title")]),
-                            Line::from(vec![Span::styled("a", Style::default().fg(Color::Yellow)), Span::raw(" = Edit Artist")]),
-                            Line::from(vec![Span::styled("Tab", Style::default().fg(Color::Yellow)), Span::raw(" = Apply Suggestion")]),
-                            Line::from(vec![Span::styled("r", Style::default().fg(Color::Yellow)), Span::raw(" = Reset to Original")]),
-                            Line::from(vec![Span::styled("c", Style::default().fg(Color::Yellow)), Span::raw(" = Clear Metadata")]),
+                            Line::from(vec![Span::styled("Controls:", Style::default().fg(theme.accent).add_modifier(Modifier::BOLD))]),
+                            Line::from(vec![Span::styled("t", Style::default().fg(theme.accent)), Span::raw(" = Edit Title")]),
+                            Line::from(vec![Span::styled("a", Style::default().fg(theme.accent)), Span::raw(" = Edit Artist")]),
+                            Line::from(vec![Span::styled("Tab", Style::default().fg(theme.accent)), Span::raw(" = Apply Suggestion")]),
+                            Line::from(vec![Span::styled("r", Style::default().fg(theme.accent)), Span::raw(" = Reset to Original")]),
+                            Line::from(vec![Span::styled("c", Style::default().fg(theme.accent)), Span::raw(" = Clear Metadata")]),
+                            Line::from(vec![Span::styled("L", Style::default().fg(theme.accent)), Span::raw(" = Look Up on MusicBrainz")]),
                             Line::from(vec![Span::raw("")]),
-                            Line::from(vec![Span::styled("Bulk Operations:", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))]),
-                            Line::from(vec![Span::styled("b", Style::default().fg(Color::Green)), Span::raw(" = Bulk Apply Suggestions")]),
-                            Line::from(vec![Span::styled("S", Style::default().fg(Color::Green)), Span::raw(" = Save Changes")]),
+                            Line::from(vec![Span::styled("Bulk Operations:", Style::default().fg(theme.primary).add_modifier(Modifier::BOLD))]),
+                            Line::from(vec![Span::styled("b", Style::default().fg(theme.playing)), Span::raw(" = Bulk Apply Suggestions")]),
+                            Line::from(vec![Span::styled("S", Style::default().fg(theme.playing)), Span::raw(" = Save Changes")]),
                         ]
                     } else {
                         vec![Line::from(vec![Span::raw("No track selected")])]
@@ -1953,27 +6382,35 @@ impl InteractiveApp {
         
         f.render_widget(edit_panel, chunks[1]);
     }
+
     
-    // All visualizer rendering methods removed for performance optimization
-    
+    #[allow(clippy::too_many_arguments)]
     fn render_track_list(
         f: &mut Frame,
         area: Rect,
+        theme: &Theme,
         tracks: &[panpipe::Track],
         filtered_tracks: &[usize],
+        marked_tracks: &HashSet<usize>,
+        shuffle_excluded: &HashSet<uuid::Uuid>,
         current_track_index: Option<usize>,
         is_playing: bool,
-        list_state: &mut ListState
+        list_state: &mut ListState,
+        sort_label: &str,
+        filter_label: Option<&str>,
     ) {
-        let items: Vec<ListItem> = filtered_tracks
+        let (window, mut window_state) = Self::visible_window(list_state, filtered_tracks.len(), area);
+        let items: Vec<ListItem> = filtered_tracks[window]
             .iter()
-            .enumerate()
-            .map(|(_i, &track_idx)| {
+            .map(|&track_idx| {
                 let track = &tracks[track_idx];
                 let is_current = current_track_index == Some(track_idx);
+                let is_marked = marked_tracks.contains(&track_idx);
                 
                 let style = if is_current {
-                    Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+                    Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)
+                } else if is_marked {
+                    Style::default().fg(theme.primary)
                 } else {
                     Style::default()
                 };
@@ -1987,8 +6424,10 @@ impl InteractiveApp {
                 };
                 
                 let content = format!(
-                    "{}{} - {} - {}",
+                    "{}{}{}{} - {} - {}",
                     prefix,
+                    if is_marked { "✓ " } else { "" },
+                    if shuffle_excluded.contains(&track.id) { "⊘ " } else { "" },
                     track.display_artist(),
                     track.display_title(),
                     track.display_album()
@@ -1998,23 +6437,218 @@ impl InteractiveApp {
             })
             .collect();
         
+        let mut title = format!("Library ({} tracks) · sort: {}", filtered_tracks.len(), sort_label);
+        if let Some(filter) = filter_label {
+            title.push_str(&format!(" · filter: {}", filter));
+        }
+        if !marked_tracks.is_empty() {
+            title.push_str(&format!(" · {} marked", marked_tracks.len()));
+        }
+        
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(title)
+            )
+            .highlight_style(Style::default().bg(theme.highlight_bg))
+            .highlight_symbol("→ ");
+        
+        f.render_stateful_widget(list, area, &mut window_state);
+    }
+    
+    /// Rows of a `len`-row bordered list that fit in `area`, scrolled to keep the selection in view
+    ///
+    /// Callers build `ListItem`s for just this range and render with the returned state, whose
+    /// selection is relative to the window. `list_state`'s offset is moved like `List` would.
+    fn visible_window(list_state: &mut ListState, len: usize, area: Rect) -> (std::ops::Range<usize>, ListState) {
+        let height = (area.height.saturating_sub(2) as usize).max(1);
+        let mut offset = list_state.offset().min(len.saturating_sub(height));
+        // A selection left over from a longer list would otherwise scroll past the end
+        let selected = list_state.selected().map(|selected| selected.min(len.saturating_sub(1)));
+        if let Some(selected) = selected {
+            if selected < offset {
+                offset = selected;
+            } else if selected >= offset + height {
+                offset = selected + 1 - height;
+            }
+        }
+        *list_state.offset_mut() = offset;
+        
+        let window_state = ListState::default().with_selected(selected.map(|selected| selected - offset));
+        (offset..(offset + height).min(len), window_state)
+    }
+    
+    #[allow(clippy::too_many_arguments)]
+    fn render_library_tree(
+        f: &mut Frame,
+        area: Rect,
+        theme: &Theme,
+        rows: &[TreeRow],
+        tracks: &[panpipe::Track],
+        marked_tracks: &HashSet<usize>,
+        shuffle_excluded: &HashSet<uuid::Uuid>,
+        library_view: GroupBy,
+        current_track_index: Option<usize>,
+        is_playing: bool,
+        tree_state: &mut ListState,
+        filter_label: Option<&str>,
+    ) {
+        let (window, mut window_state) = Self::visible_window(tree_state, rows.len(), area);
+        let items: Vec<ListItem> = rows[window]
+            .iter()
+            .map(|row| match row {
+                TreeRow::Group { label, depth, track_count, expanded, .. } => {
+                    let expand_icon = if *expanded { "▼" } else { "▶" };
+                    let content = format!(
+                        "{}{} {} ({} tracks)",
+                        "  ".repeat(*depth),
+                        expand_icon,
+                        label,
+                        track_count
+                    );
+                    ListItem::new(content).style(Style::default().fg(theme.primary).add_modifier(Modifier::BOLD))
+                }
+                TreeRow::Track { index, depth } => {
+                    let track = &tracks[*index];
+                    let is_current = current_track_index == Some(*index);
+                    let is_marked = marked_tracks.contains(index);
+                    
+                    let prefix = if is_current && is_playing {
+                        "▶ "
+                    } else if is_current {
+                        "⏸ "
+                    } else {
+                        "  "
+                    };
+                    // Every track row sits under an album group, so its place on the album is worth showing
+                    let position = grouping::disc_track_label(track).map(|label| format!("{} ", label)).unwrap_or_default();
+                    let content = format!(
+                        "{}{}{}{}{}{}",
+                        "  ".repeat(*depth),
+                        prefix,
+                        if is_marked { "✓ " } else { "" },
+                        if shuffle_excluded.contains(&track.id) { "⊘ " } else { "" },
+                        position,
+                        track.display_title()
+                    );
+                    
+                    let style = if is_current {
+                        Style::default().fg(theme.playing).add_modifier(Modifier::BOLD)
+                    } else if is_marked {
+                        Style::default().fg(theme.primary)
+                    } else {
+                        Style::default().fg(theme.muted)
+                    };
+                    ListItem::new(content).style(style)
+                }
+            })
+            .collect();
+        
+        let group_count = rows.iter().filter(|row| matches!(row, TreeRow::Group { depth: 0, .. })).count();
+        let grouping = if library_view == GroupBy::Album { "album" } else { "artist" };
+        let mut title = format!("Library · by {} ({} groups)", grouping, group_count);
+        if let Some(filter) = filter_label {
+            title.push_str(&format!(" · filter: {}", filter));
+        }
+        title.push_str(" · Tab: change view");
+        
         let list = List::new(items)
             .block(
                 Block::default()
                     .borders(Borders::ALL)
-                    .title(format!("Library ({} tracks)", filtered_tracks.len()))
+                    .title(title)
             )
-            .highlight_style(Style::default().bg(Color::DarkGray))
+            .highlight_style(Style::default().bg(theme.highlight_bg))
             .highlight_symbol("→ ");
         
-        f.render_stateful_widget(list, area, list_state);
+        f.render_stateful_widget(list, area, &mut window_state);
+    }
+    
+    /// Spectrum bars spread across the pane, each column drawn with eighth-block characters for smooth heights
+    /// Timed lyrics keep the current line in the middle; plain ones scroll along with the track
+    #[cfg(feature = "lyrics")]
+    fn render_lyrics(f: &mut Frame, area: Rect, theme: &Theme, state: Option<&LyricsState>, position: Duration, total: Option<Duration>) {
+        let visible = area.height.saturating_sub(2) as usize;
+        let note = |text: &str| vec![Line::from(Span::styled(text.to_string(), Style::default().fg(theme.muted)))];
+        let (lines, scroll) = match state {
+            None => (note("Nothing playing"), 0),
+            Some(LyricsState::Loading) => (note("Looking for lyrics..."), 0),
+            Some(LyricsState::NotFound) => (note("No lyrics found"), 0),
+            Some(LyricsState::Found(Lyrics::Plain(text))) => {
+                let overflow = text.len().saturating_sub(visible);
+                let progress = match total {
+                    Some(total) if !total.is_zero() => (position.as_secs_f64() / total.as_secs_f64()).min(1.0),
+                    _ => 0.0,
+                };
+                let lines = text.iter().map(|line| Line::from(line.clone())).collect();
+                (lines, (overflow as f64 * progress) as usize)
+            }
+            Some(LyricsState::Found(lyrics @ Lyrics::Synced(timed))) => {
+                let current = lyrics.current_line(position);
+                let lines = timed.iter().enumerate().map(|(idx, line)| {
+                    let style = match current {
+                        Some(current) if idx == current => Style::default().fg(theme.accent).add_modifier(Modifier::BOLD),
+                        Some(current) if idx < current => Style::default().fg(theme.muted),
+                        _ => Style::default().fg(theme.text),
+                    };
+                    Line::from(Span::styled(line.text.clone(), style))
+                }).collect();
+                (lines, current.unwrap_or(0).saturating_sub(visible / 2))
+            }
+        };
+        
+        let paragraph = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("🎤 Lyrics")
+                    .border_style(Style::default().fg(theme.primary))
+            )
+            .alignment(Alignment::Center)
+            .scroll((scroll.min(u16::MAX as usize) as u16, 0));
+        f.render_widget(paragraph, area);
     }
     
-    // All remaining visualizer rendering methods removed for performance optimization
+    fn render_visualizer(f: &mut Frame, area: Rect, theme: &Theme, levels: &[f32; BAR_COUNT], is_playing: bool) {
+        const EIGHTHS: [char; 9] = [' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+        
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.primary))
+            .title("📊 Visualizer");
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+        if inner.width == 0 || inner.height == 0 {
+            return;
+        }
+        
+        // Bar heights in eighths of a row, one per column
+        let columns: Vec<usize> = (0..inner.width as usize)
+            .map(|x| {
+                let level = levels[x * BAR_COUNT / inner.width as usize];
+                (level * inner.height as f32 * 8.0).round() as usize
+            })
+            .collect();
+        let lines: Vec<Line> = (0..inner.height as usize)
+            .rev() // top row first
+            .map(|row| {
+                let text: String = columns.iter()
+                    .map(|height| EIGHTHS[height.saturating_sub(row * 8).min(8)])
+                    .collect();
+                Line::from(text)
+            })
+            .collect();
+        
+        let color = if is_playing { theme.playing } else { theme.paused };
+        f.render_widget(Paragraph::new(lines).style(Style::default().fg(color)), inner);
+    }
     
+    #[allow(clippy::too_many_arguments)]
     fn render_player_controls(
         f: &mut Frame, 
         area: Rect, 
+        theme: &Theme,
         tracks: &[panpipe::Track], 
         current_track_index: Option<usize>, 
         is_playing: bool, 
@@ -2022,8 +6656,12 @@ impl InteractiveApp {
         repeat_mode: RepeatMode, 
         is_shuffled: bool,
         current_position: Duration,
-        total_duration: Option<Duration>
-    ) {
+        total_duration: Option<Duration>,
+        show_remaining_time: bool, // "-3:12" left instead of the track length
+        badges: &[String], // armed modes like the sleep timer, shown after shuffle
+        ab_loop: Option<AbLoop>,
+        bookmarks: &[Duration], // tick positions in the current track
+    ) -> Rect {
         // Create layout for progress bar and controls
         let chunks = Layout::default()
             .direction(Direction::Vertical)
@@ -2033,6 +6671,15 @@ impl InteractiveApp {
             ])
             .split(area);
         
+        // Player info and controls
+        let current_track_info = if let Some(idx) = current_track_index {
+            let track = &tracks[idx];
+            format!("♪ {} - {}", track.display_artist(), track.display_title())
+        } else {
+            "No track selected".to_string()
+        };
+        let status_symbol = if is_playing { "▶" } else { "⏸" };
+        
         // Progress bar with time tracking
         let (progress_ratio, time_display) = if let Some(total) = total_duration {
             let total_secs = total.as_secs();
@@ -2040,7 +6687,12 @@ impl InteractiveApp {
             let ratio = if total_secs > 0 { current_secs as f64 / total_secs as f64 } else { 0.0 };
             
             let current_time = format!("{}:{:02}", current_secs / 60, current_secs % 60);
-            let total_time = format!("{}:{:02}", total_secs / 60, total_secs % 60);
+            let total_time = if show_remaining_time {
+                let remaining_secs = total_secs - current_secs;
+                format!("-{}:{:02}", remaining_secs / 60, remaining_secs % 60)
+            } else {
+                format!("{}:{:02}", total_secs / 60, total_secs % 60)
+            };
             let time_str = format!("{} / {}", current_time, total_time);
             
             (ratio, time_str)
@@ -2052,31 +6704,55 @@ impl InteractiveApp {
         
         // Animated progress bar with visual effects
         let progress_color = if is_playing {
-            Color::Green // Pulsing green when playing
+            theme.playing // Pulsing green when playing
         } else {
-            Color::Yellow // Yellow when paused
+            theme.paused // Yellow when paused
         };
         
         let progress_bar = Gauge::default()
             .block(Block::default().borders(Borders::NONE))
             .gauge_style(Style::default().fg(progress_color).add_modifier(Modifier::BOLD))
             .ratio(progress_ratio)
-            .label(time_display);
+            .label(if area.height < 2 {
+                // Compact layout: the gauge is all there is, so it carries the track too
+                format!("{} {}  {}", status_symbol, current_track_info, time_display)
+            } else {
+                time_display
+            });
         
         f.render_widget(progress_bar, chunks[0]);
         
-        // Player info and controls
-        let current_track_info = if let Some(idx) = current_track_index {
-            let track = &tracks[idx];
-            format!("♪ {} - {}", track.display_artist(), track.display_title())
-        } else {
-            "No track selected".to_string()
-        };
+        // Bookmark ticks go down first so the A/B markers win where they overlap
+        if let Some(total) = total_duration.filter(|t| !t.is_zero()) {
+            let bar = chunks[0];
+            for at in bookmarks {
+                let ratio = (at.as_secs_f64() / total.as_secs_f64()).min(1.0);
+                let x = bar.x + ((bar.width.saturating_sub(1)) as f64 * ratio) as u16;
+                let tick = Paragraph::new("▼").style(Style::default().fg(theme.primary).add_modifier(Modifier::BOLD));
+                f.render_widget(tick, Rect { x, y: bar.y, width: 1, height: 1 });
+            }
+        }
+        
+        // A/B loop markers drawn over the gauge at their share of the track
+        if let (Some(ab_loop), Some(total)) = (ab_loop, total_duration.filter(|t| !t.is_zero())) {
+            let bar = chunks[0];
+            let markers = std::iter::once(("A", ab_loop.a)).chain(ab_loop.b.map(|b| ("B", b)));
+            for (label, at) in markers {
+                let ratio = (at.as_secs_f64() / total.as_secs_f64()).min(1.0);
+                let x = bar.x + ((bar.width.saturating_sub(1)) as f64 * ratio) as u16;
+                let marker = Paragraph::new(label)
+                    .style(Style::default().fg(theme.background).bg(theme.accent).add_modifier(Modifier::BOLD));
+                f.render_widget(marker, Rect { x, y: bar.y, width: 1, height: 1 });
+            }
+        }
+        
+        if area.height < 2 {
+            return chunks[0];
+        }
         
         // Animated status with visual effects
-        let status_symbol = if is_playing { "▶" } else { "⏸" };
         let status_text = if is_playing { "Playing" } else { "Paused" };
-        let status_color = if is_playing { Color::Green } else { Color::Yellow };
+        let status_color = if is_playing { theme.playing } else { theme.paused };
         
         let volume_bar = "█".repeat((volume * 10.0) as usize);
         let volume_empty = "░".repeat(10 - (volume * 10.0) as usize);
@@ -2087,35 +6763,42 @@ impl InteractiveApp {
             RepeatMode::One => "🔂",
         };
         
-        let shuffle_symbol = if is_shuffled { "🔀" } else { "🔀" };
+        let shuffle_symbol = "🔀";
+        let shuffle_color = if is_shuffled { theme.primary } else { theme.muted };
+        
+        let mut status_spans = vec![
+            Span::styled(status_symbol, Style::default().fg(status_color).add_modifier(Modifier::BOLD)),
+            Span::raw(" "),
+            Span::styled(status_text, Style::default().fg(status_color)),
+            Span::raw(" | "),
+            Span::styled("Vol: ", Style::default().fg(theme.muted)),
+            Span::styled(volume_bar, Style::default().fg(theme.primary).add_modifier(Modifier::BOLD)),
+            Span::styled(volume_empty, Style::default().fg(theme.muted)),
+            Span::raw(format!(" {}%", (volume * 100.0) as u32)),
+            Span::raw(" | "),
+            Span::styled(repeat_symbol, Style::default().fg(theme.accent)),
+            Span::raw(" "),
+            Span::styled(shuffle_symbol, Style::default().fg(shuffle_color)),
+        ];
+        for badge in badges {
+            status_spans.push(Span::raw(" | "));
+            status_spans.push(Span::styled(badge.as_str(), Style::default().fg(theme.accent)));
+        }
         
         let controls_text = vec![
             Line::from(vec![
-                Span::styled(current_track_info, Style::default().fg(Color::White).add_modifier(Modifier::BOLD)),
-            ]),
-            Line::from(vec![
-                Span::styled(status_symbol, Style::default().fg(status_color).add_modifier(Modifier::BOLD)),
-                Span::raw(" "),
-                Span::styled(status_text, Style::default().fg(status_color)),
-                Span::raw(" | "),
-                Span::styled("Vol: ", Style::default().fg(Color::Gray)),
-                Span::styled(volume_bar, Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD)),
-                Span::styled(volume_empty, Style::default().fg(Color::DarkGray)),
-                Span::raw(format!(" {}%", (volume * 100.0) as u32)),
-                Span::raw(" | "),
-                Span::styled(repeat_symbol, Style::default().fg(Color::Magenta)),
-                Span::raw(" "),
-                Span::styled(shuffle_symbol, Style::default().fg(Color::Cyan)),
+                Span::styled(current_track_info, Style::default().fg(theme.text).add_modifier(Modifier::BOLD)),
             ]),
+            Line::from(status_spans),
             Line::from(vec![
-                Span::styled("Controls: ", Style::default().fg(Color::Gray)),
-                Span::styled("Space", Style::default().fg(Color::Yellow)),
+                Span::styled("Controls: ", Style::default().fg(theme.muted)),
+                Span::styled("Space", Style::default().fg(theme.accent)),
                 Span::raw("=Play/Pause "),
-                Span::styled("n", Style::default().fg(Color::Yellow)),
+                Span::styled("n", Style::default().fg(theme.accent)),
                 Span::raw("=Next "),
-                Span::styled("p", Style::default().fg(Color::Yellow)),
+                Span::styled("p", Style::default().fg(theme.accent)),
                 Span::raw("=Prev "),
-                Span::styled("q", Style::default().fg(Color::Yellow)),
+                Span::styled("q", Style::default().fg(theme.accent)),
                 Span::raw("=Quit"),
             ]),
         ];
@@ -2125,85 +6808,296 @@ impl InteractiveApp {
             .wrap(Wrap { trim: true });
         
         f.render_widget(controls, chunks[1]);
+        chunks[0] // the gauge, for click-to-seek
     }
     
-    fn render_settings(f: &mut Frame, area: Rect) {
-        let settings_content = vec![
-            Line::from(vec![Span::styled("⚙️ Settings", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))]),
+    #[allow(clippy::too_many_arguments)]
+    #[allow(clippy::too_many_arguments)]
+    fn render_settings(f: &mut Frame, area: Rect, theme: &Theme, scan_report: &ScanReport, audio: &AudioSettings, visualizer: bool, eq_band: usize, excluded_tracks: usize, keymap: &Keymap) {
+        let equalizer = &audio.equalizer;
+        let gains = equalizer.gains();
+        let mut band_spans = vec![Span::raw("  ")];
+        for (band, (freq, gain)) in BAND_FREQUENCIES.iter().zip(gains).enumerate() {
+            let style = if band == eq_band {
+                Style::default().fg(theme.accent).add_modifier(Modifier::BOLD | Modifier::REVERSED)
+            } else {
+                Style::default().fg(theme.text)
+            };
+            band_spans.push(Span::styled(format!("{} {:+.0}", band_label(*freq), gain), style));
+            band_spans.push(Span::raw("  "));
+        }
+        
+        let mut settings_content = vec![
+            Line::from(vec![Span::styled("⚙️ Settings", Style::default().fg(theme.accent).add_modifier(Modifier::BOLD))]),
+            Line::from(""),
+            Line::from(vec![Span::styled("🎨 Theme:", Style::default().fg(theme.primary).add_modifier(Modifier::BOLD))]),
+            Line::from(vec![
+                Span::raw("  Current: "),
+                Span::styled(theme.name, Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)),
+                Span::raw(format!(" (press {} to cycle: {})", keymap.keys_label(Action::Theme), Theme::NAMES.join(" / "))),
+            ]),
             Line::from(""),
-            Line::from(vec![Span::styled("⌨️ Keyboard Controls:", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))]),
-            Line::from("  Space         Toggle play/pause"),
-            Line::from("  p             Play current track"),
-            Line::from("  s             Stop playback"),
-            Line::from("  n / →         Next track"),
-            Line::from("  b / ←         Previous track"),
-            Line::from("  ↑ / ↓         Navigate track list"),
-            Line::from("  Enter         Select/play highlighted track"),
-            Line::from("  + / =         Volume up"),
-            Line::from("  -             Volume down"),
-            Line::from("  z             Toggle shuffle mode"),
-            Line::from("  r             Toggle repeat mode"),
-            Line::from("  F5            Refresh library"),
-            Line::from("  q / Esc       Quit player"),
+            Line::from(vec![Span::styled("🔈 Output Device:", Style::default().fg(theme.primary).add_modifier(Modifier::BOLD))]),
+            Line::from(vec![
+                Span::raw("  Current: "),
+                Span::styled(audio.output_device.as_deref().unwrap_or("System default"), Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)),
+                Span::raw(format!(" (press {} to choose)", keymap.keys_label(Action::OutputDevice))),
+            ]),
+            Line::from(""),
+            Line::from(vec![Span::styled("🎚️ Equalizer:", Style::default().fg(theme.primary).add_modifier(Modifier::BOLD))]),
+            Line::from(vec![
+                Span::raw("  Preset: "),
+                Span::styled(equalizer.preset.name(), Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)),
+                Span::raw(format!(
+                    " (press {} to cycle, {} / {} pick a band, {} / {} adjust it in dB)",
+                    keymap.keys_label(Action::EqPreset),
+                    keymap.keys_label(Action::EqBandPrevious),
+                    keymap.keys_label(Action::EqBandNext),
+                    keymap.keys_label(Action::EqGainDown),
+                    keymap.keys_label(Action::EqGainUp),
+                )),
+            ]),
+            Line::from(band_spans),
+            Line::from(""),
+            Line::from(vec![Span::styled("🎧 Channels:", Style::default().fg(theme.primary).add_modifier(Modifier::BOLD))]),
+            Line::from(vec![
+                Span::raw("  Mode: "),
+                Span::styled(audio.channel_mode.name(), Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)),
+                Span::raw(format!(" (press {} to cycle: stereo / mono / crossfeed for headphones)", keymap.keys_label(Action::ChannelMode))),
+            ]),
             Line::from(""),
-            Line::from(vec![Span::styled("🎵 Audio Configuration:", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))]),
-            Line::from("  Volume: Controlled via +/- keys"),
-            Line::from("  Repeat Mode: Controlled via 'r' key"),
-            Line::from("  Shuffle: Controlled via 'z' key"),
+            Line::from(vec![Span::styled("📊 Visualizer:", Style::default().fg(theme.primary).add_modifier(Modifier::BOLD))]),
+            Line::from(vec![
+                Span::raw("  "),
+                Span::styled(if visualizer { "On" } else { "Off" }, Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)),
+                Span::raw(format!(" (press {} to toggle)", keymap.keys_label(Action::Visualizer))),
+            ]),
+            Line::from(""),
+            Line::from(vec![Span::styled("🧹 Library Report:", Style::default().fg(theme.primary).add_modifier(Modifier::BOLD))]),
+            Line::from(format!(
+                "  {} duplicate groups ({} redundant files), {} unreadable files - press {} for details",
+                scan_report.duplicate_groups.len(),
+                scan_report.redundant_file_count(),
+                scan_report.unreadable.len(),
+                keymap.keys_label(Action::ScanReport),
+            )),
+            Line::from(format!("  Press {} to review duplicates and keep one copy of each", keymap.keys_label(Action::FindDuplicates))),
+            Line::from(format!("  Press {} to export every playlist as M3U", keymap.keys_label(Action::ExportPlaylists))),
+            Line::from(""),
+            Line::from(vec![Span::styled("🎵 Audio Configuration:", Style::default().fg(theme.primary).add_modifier(Modifier::BOLD))]),
+            Line::from(format!("  Volume: Controlled via {} / {}", keymap.keys_label(Action::VolumeUp), keymap.keys_label(Action::VolumeDown))),
+            Line::from(format!("  Repeat Mode: Controlled via {}", keymap.keys_label(Action::Repeat))),
+            Line::from(format!("  Shuffle: Controlled via {}", keymap.keys_label(Action::Shuffle))),
+            Line::from(format!(
+                "  Excluded tracks: {} kept out of shuffle - press {} to manage ({} in the Library excludes one)",
+                excluded_tracks,
+                keymap.keys_label(Action::ExcludedTracks),
+                keymap.keys_label(Action::ExcludeFromShuffle),
+            )),
+            Line::from(format!(
+                "  Sleep Timer: {} cycles durations (then end of track), {} cancels",
+                keymap.keys_label(Action::SleepTimer),
+                keymap.keys_label(Action::SleepTimerCancel),
+            )),
             Line::from(""),
-            Line::from(vec![Span::styled("📁 Library Management:", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))]),
+            Line::from(vec![Span::styled("📁 Library Management:", Style::default().fg(theme.primary).add_modifier(Modifier::BOLD))]),
             Line::from("  Music Directory: Scanned on startup"),
             Line::from("  Metadata Editor: Available in tab 2"),
             Line::from(""),
-            Line::from(vec![Span::styled("🔮 Future Features:", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))]),
+            Line::from(vec![Span::styled("🔮 Future Features:", Style::default().fg(theme.primary).add_modifier(Modifier::BOLD))]),
             Line::from("  ⭐ Favorites System - Coming Soon"),
             Line::from("  📋 Custom Playlists - Coming Soon"),
             Line::from("  🎯 Queue Management - Coming Soon"),
             Line::from("  💾 Persistent Settings - Coming Soon"),
             Line::from(""),
-            Line::from(vec![Span::styled("🔧 Configuration:", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))]),
+            Line::from(vec![Span::styled("🔧 Configuration:", Style::default().fg(theme.primary).add_modifier(Modifier::BOLD))]),
             Line::from("  Audio Buffer: 65KB (optimized for stability)"),
             Line::from("  Sample Rate: 44.1kHz"),
             Line::from("  Channels: Stereo"),
             Line::from(""),
-            Line::from(vec![Span::styled("💡 Tips:", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD))]),
-            Line::from("  • Press ? for help overlay with all keybindings"),
-            Line::from("  • Use 1/2/3 to switch between tabs"),
+            Line::from(vec![Span::styled("💡 Tips:", Style::default().fg(theme.playing).add_modifier(Modifier::BOLD))]),
+            Line::from(format!("  • Press {} for the keys on the current tab", keymap.keys_label(Action::ShowHelp))),
+            Line::from("  • Use the tab keys listed below to switch between tabs"),
             Line::from("  • Lower system volume to ~75% for best audio quality"),
+            Line::from(""),
+            Line::from(vec![Span::styled("⌨️ Keybindings:", Style::default().fg(theme.primary).add_modifier(Modifier::BOLD))]),
+            Line::from("  Rebind any action under [keybindings] in config.toml, e.g. play_pause = [\"space\", \"ctrl+p\"]"),
+        ];
+        for info in ACTIONS {
+            settings_content.push(Line::from(vec![
+                Span::styled(format!("  {:<24}", info.name), Style::default().fg(theme.accent)),
+                Span::raw(format!("{:<18}", keymap.keys_label(info.action))),
+                Span::styled(info.description, Style::default().fg(theme.muted)),
+            ]));
+        }
+        
+        let settings_paragraph = Paragraph::new(settings_content)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Settings & Configuration")
+                    .border_style(Style::default().fg(theme.accent))
+            )
+            .style(Style::default().fg(theme.text))
+            .wrap(Wrap { trim: true });
+        
+        f.render_widget(settings_paragraph, area);
+    }
+    
+    fn render_stats(f: &mut Frame, area: Rect, theme: &Theme, stats: &ListeningStats, library: &LibraryStats, tracks: &[panpipe::Track], scroll: u16) {
+        let heading = |text: String| Line::from(vec![Span::styled(text, Style::default().fg(theme.primary).add_modifier(Modifier::BOLD))]);
+        let track_name = |id: &uuid::Uuid| {
+            tracks.iter()
+                .find(|t| t.id == *id)
+                .map(|t| format!("{} - {}", t.display_title(), t.display_artist()))
+                .unwrap_or_else(|| "(no longer in library)".to_string())
+        };
+        
+        let hours = stats.total_listening_secs / 3600;
+        let minutes = (stats.total_listening_secs % 3600) / 60;
+        
+        let mut lines = vec![
+            heading("📈 Overview".to_string()),
+            Line::from(format!("  Total plays:     {}", stats.total_plays)),
+            Line::from(format!("  Total skips:     {}", stats.total_skips)),
+            Line::from(format!("  Listening time:  {}h {:02}m", hours, minutes)),
+            Line::from(format!("  Tracks with history: {}", stats.tracks_with_history)),
+            Line::from(""),
         ];
         
-        let settings_paragraph = Paragraph::new(settings_content)
+        if stats.is_empty() {
+            lines.push(Line::from(vec![Span::styled(
+                "  Nothing recorded yet - play some music and check back",
+                Style::default().fg(theme.muted),
+            )]));
+        }
+        
+        lines.push(heading("📚 Library".to_string()));
+        let library_hours = library.total_duration.as_secs() / 3600;
+        lines.push(Line::from(format!(
+            "  Tracks:          {} ({:.1} GB)",
+            library.track_count,
+            library.total_size as f64 / 1_073_741_824.0,
+        )));
+        lines.push(Line::from(format!(
+            "  Total length:    {}h {:02}m, {} on average",
+            library_hours,
+            (library.total_duration.as_secs() % 3600) / 60,
+            library.average_duration().map(Self::format_duration).unwrap_or_else(|| "-".to_string()),
+        )));
+        let formats: Vec<String> = library.formats.iter()
+            .map(|(format, count)| format!("{} {}", format.name(), count))
+            .collect();
+        lines.push(Line::from(format!("  Formats:         {}", formats.join(", "))));
+        for (label, missing) in [("Missing tags:    ", library.missing_metadata), ("Unknown length:  ", library.missing_duration)] {
+            let style = if missing > 0 { Style::default().fg(theme.warning) } else { Style::default().fg(theme.text) };
+            lines.push(Line::from(vec![
+                Span::raw(format!("  {}", label)),
+                Span::styled(format!("{}", missing), style),
+                Span::styled(" tracks", Style::default().fg(theme.muted)),
+            ]));
+        }
+        lines.push(Line::from(""));
+        
+        lines.push(heading(format!("🔥 Most played (top {})", panpipe::behavior::stats::TOP_TRACKS)));
+        for (rank, (id, plays)) in stats.most_played.iter().enumerate() {
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {:>2}. {:>4} plays  ", rank + 1, plays), Style::default().fg(theme.accent)),
+                Span::raw(track_name(id)),
+            ]));
+        }
+        lines.push(Line::from(""));
+        
+        lines.push(heading(format!("⏭️ Most skipped (top {})", panpipe::behavior::stats::TOP_TRACKS)));
+        for (rank, (id, skips)) in stats.most_skipped.iter().enumerate() {
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {:>2}. {:>4} skips  ", rank + 1, skips), Style::default().fg(theme.warning)),
+                Span::raw(track_name(id)),
+            ]));
+        }
+        lines.push(Line::from(""));
+        
+        lines.push(heading("🏷️ Behavior tags".to_string()));
+        if stats.tag_counts.is_empty() {
+            lines.push(Line::from(vec![Span::styled("  No tags yet", Style::default().fg(theme.muted))]));
+        }
+        for (tag, count) in &stats.tag_counts {
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {:<20}", tag), Style::default().fg(theme.text)),
+                Span::styled(format!("{:>5} tracks", count), Style::default().fg(theme.accent)),
+            ]));
+        }
+        
+        let stats_paragraph = Paragraph::new(lines)
             .block(
                 Block::default()
                     .borders(Borders::ALL)
-                    .title("Settings & Configuration")
-                    .border_style(Style::default().fg(Color::Yellow))
+                    .title("Listening Stats - ↑↓ scroll, e export CSV")
+                    .border_style(Style::default().fg(theme.accent))
             )
-            .style(Style::default().fg(Color::White))
-            .wrap(Wrap { trim: true });
+            .style(Style::default().fg(theme.text))
+            .scroll((scroll, 0));
         
-        f.render_widget(settings_paragraph, area);
+        f.render_widget(stats_paragraph, area);
     }
     
-    fn render_status_bar(f: &mut Frame, area: Rect, status_message: Option<(String, Instant)>) {
-        let status_text = if let Some((message, timestamp)) = status_message {
-            // Show status message for 3 seconds
-            if timestamp.elapsed() < Duration::from_secs(3) {
-                message
-            } else {
-                "Ready".to_string()
-            }
-        } else {
-            "Ready".to_string()
+    fn render_status_bar(f: &mut Frame, area: Rect, theme: &Theme, status_message: Option<(String, NotificationLevel, Instant)>) {
+        let (status_text, level) = match status_message {
+            Some((message, level, timestamp)) if timestamp.elapsed() < STATUS_DURATION => (message, level),
+            _ => ("Ready".to_string(), NotificationLevel::Info),
         };
         
+        // A single row can't fit the border, so the compact layout goes without
+        let borders = if area.height < 3 { Borders::NONE } else { Borders::ALL };
         let status = Paragraph::new(status_text)
-            .style(Style::default().fg(Color::Green))
-            .block(Block::default().borders(Borders::ALL));
+            .style(Style::default().fg(Self::level_color(theme, level)))
+            .block(Block::default().borders(borders));
         f.render_widget(status, area);
     }
     
-    fn render_search_input(f: &mut Frame, area: Rect, search_query: &str, results_count: usize) {
+    fn level_color(theme: &Theme, level: NotificationLevel) -> Color {
+        match level {
+            NotificationLevel::Info => theme.primary,
+            NotificationLevel::Success => theme.playing,
+            NotificationLevel::Warning => theme.paused,
+            NotificationLevel::Error => theme.warning,
+        }
+    }
+    
+    fn render_notifications(f: &mut Frame, area: Rect, theme: &Theme, notifications: &NotificationCenter) {
+        // Stack toasts down from the top-right corner, below the header
+        let width = (area.width / 2).clamp(20, 50).min(area.width);
+        let mut y = area.y + 3;
+        
+        for notification in notifications.visible() {
+            if y + 3 > area.y + area.height {
+                break;
+            }
+            
+            let toast_area = Rect {
+                x: area.x + area.width - width,
+                y,
+                width,
+                height: 3,
+            };
+            
+            let color = Self::level_color(theme, notification.level);
+            
+            let toast = Paragraph::new(notification.text.as_str())
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(color))
+                )
+                .style(Style::default().fg(theme.text).bg(theme.background));
+            
+            f.render_widget(Clear, toast_area);
+            f.render_widget(toast, toast_area);
+            y += 3;
+        }
+    }
+    
+    fn render_search_input(f: &mut Frame, area: Rect, theme: &Theme, search_query: &str, scope: &str, results_count: usize) {
         // Create a centered popup for search input
         let popup_area = Layout::default()
             .direction(Direction::Vertical)
@@ -2224,17 +7118,17 @@ impl InteractiveApp {
             .block(
                 Block::default()
                     .borders(Borders::ALL)
-                    .title("Search Mode - Esc to exit")
-                    .border_style(Style::default().fg(Color::Green))
+                    .title(format!("Search Mode [{}] - Tab: scope, Esc to exit", scope))
+                    .border_style(Style::default().fg(theme.playing))
             )
-            .style(Style::default().fg(Color::White).bg(Color::Black));
+            .style(Style::default().fg(theme.text).bg(theme.background));
         
         // Clear the area and render the search input
         f.render_widget(Clear, popup_area);
         f.render_widget(search_input, popup_area);
     }
     
-    fn render_playlist_input(f: &mut Frame, area: Rect, playlist_name: &str) {
+    fn render_playlist_input(f: &mut Frame, area: Rect, theme: &Theme, playlist_name: &str) {
         // Create a centered popup for playlist name input
         let popup_area = Layout::default()
             .direction(Direction::Vertical)
@@ -2252,19 +7146,50 @@ impl InteractiveApp {
                 Block::default()
                     .borders(Borders::ALL)
                     .title("Create Playlist - Enter to confirm, Esc to cancel")
-                    .border_style(Style::default().fg(Color::Blue))
+                    .border_style(Style::default().fg(theme.primary))
             )
-            .style(Style::default().fg(Color::White).bg(Color::Black));
+            .style(Style::default().fg(theme.text).bg(theme.background));
         
         // Clear the area and render the playlist input
         f.render_widget(Clear, popup_area);
         f.render_widget(playlist_input, popup_area);
     }
     
+    fn render_confirm_overlay(f: &mut Frame, area: Rect, theme: &Theme, prompt: &str, hint: &str) {
+        let width = (prompt.chars().count().max(hint.chars().count()) as u16 + 6).max(30).min(area.width); // clamp() panics below 30 columns
+        let popup_area = Rect {
+            x: area.x + area.width.saturating_sub(width) / 2,
+            y: area.y + area.height.saturating_sub(5) / 2,
+            width,
+            height: 5.min(area.height),
+        };
+        
+        let text = vec![
+            Line::from(Span::styled(prompt.to_string(), Style::default().fg(theme.text).add_modifier(Modifier::BOLD))),
+            Line::from(""),
+            Line::from(Span::styled(hint.to_string(), Style::default().fg(theme.muted))),
+        ];
+        let dialog = Paragraph::new(text)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(" Confirm ")
+                    .border_style(Style::default().fg(theme.warning))
+            )
+            .style(Style::default().bg(theme.background))
+            .alignment(Alignment::Center);
+        
+        f.render_widget(Clear, popup_area);
+        f.render_widget(dialog, popup_area);
+    }
+    
+    #[allow(clippy::too_many_arguments)]
     fn render_playlists_tree_view(
         f: &mut Frame,
         area: Rect,
+        theme: &Theme,
         playlist_manager: &PlaylistManager,
+        search_matches: Option<&HashSet<String>>,
         playlist_list_state: &mut ListState,
         expanded_playlists: &std::collections::HashSet<String>,
         tracks: &[panpipe::Track],
@@ -2272,27 +7197,33 @@ impl InteractiveApp {
         current_track_index: Option<usize>,
         is_playing: bool,
     ) {
-        let playlists = playlist_manager.list_playlists();
+        let mut playlists = playlist_manager.list_playlists();
+        if let Some(matches) = search_matches {
+            playlists.retain(|playlist| matches.contains(&playlist.id));
+        }
         
         // Build tree-view items: playlists + their expanded tracks
         let mut tree_items: Vec<ListItem> = Vec::new();
         
-        for (_playlist_idx, playlist) in playlists.iter().enumerate() {
+        for playlist in playlists.iter() {
             let stats = playlist_manager.get_playlist_stats(&playlist.id, tracks).unwrap_or_default();
             let is_expanded = expanded_playlists.contains(&playlist.id);
             
             // Playlist header with expand/collapse indicator
             let expand_icon = if is_expanded { "▼" } else { "▶" };
-            let playlist_content = format!(
+            let mut playlist_content = format!(
                 "{} {} ({} tracks, {})",
                 expand_icon,
                 playlist.name,
                 stats.track_count,
                 Self::format_duration(std::time::Duration::from_millis(stats.total_duration))
             );
+            if let Some(modes) = Self::playlist_modes_label(playlist) {
+                playlist_content.push_str(&format!(" · {}", modes));
+            }
             
             let playlist_style = Style::default()
-                .fg(Color::Cyan)
+                .fg(theme.primary)
                 .add_modifier(Modifier::BOLD);
             
             tree_items.push(ListItem::new(playlist_content).style(playlist_style));
@@ -2314,9 +7245,9 @@ impl InteractiveApp {
                         }
                         
                         let track_style = if is_current {
-                            Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)
+                            Style::default().fg(theme.playing).add_modifier(Modifier::BOLD)
                         } else {
-                            Style::default().fg(Color::Gray)
+                            Style::default().fg(theme.muted)
                         };
                         
                         tree_items.push(ListItem::new(track_content).style(track_style));
@@ -2330,13 +7261,13 @@ impl InteractiveApp {
             .block(
                 Block::default()
                     .borders(Borders::ALL)
-                    .title("🎵 Playlists (Tree View)")
-                    .title_style(Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD))
+                    .title(if search_matches.is_some() { "🎵 Playlists (Tree View) · filtered" } else { "🎵 Playlists (Tree View)" })
+                    .title_style(Style::default().fg(theme.primary).add_modifier(Modifier::BOLD))
             )
             .highlight_style(
                 Style::default()
-                    .bg(Color::Blue)
-                    .fg(Color::White)
+                    .bg(theme.highlight_bg)
+                    .fg(theme.text)
                     .add_modifier(Modifier::BOLD)
             )
             .highlight_symbol("→ ");
@@ -2348,12 +7279,12 @@ impl InteractiveApp {
             x: area.x,
             y: area.y + area.height.saturating_sub(3),
             width: area.width,
-            height: 3,
+            height: area.height.min(3),
         };
         
-        let instructions = Paragraph::new("Del: Delete | Enter: Expand/Collapse | Space: Play/Pause")
+        let instructions = Paragraph::new("Del: Delete | Enter: Expand/Collapse | Shift+↑/↓: Reorder | Space: Play/Pause")
             .block(Block::default().borders(Borders::TOP))
-            .style(Style::default().fg(Color::Yellow))
+            .style(Style::default().fg(theme.accent))
             .wrap(Wrap { trim: true });
         
         f.render_widget(instructions, instructions_area);
@@ -2372,7 +7303,7 @@ impl InteractiveApp {
         }
     }
     
-    fn render_playlist_selector_overlay(f: &mut Frame, area: Rect, playlist_manager: &PlaylistManager, list_state: &mut ListState, track_title: &str) {
+    fn render_playlist_selector_overlay(f: &mut Frame, area: Rect, theme: &Theme, playlist_manager: &PlaylistManager, list_state: &mut ListState, target: &str) {
         // Create centered popup area
         let popup_area = Self::centered_rect(60, 70, area);
         
@@ -2381,10 +7312,10 @@ impl InteractiveApp {
         
         // Create the popup block
         let block = Block::default()
-            .title(format!(" Select Playlist for '{}' ", track_title))
+            .title(format!(" Select Playlist for {} ", target))
             .borders(Borders::ALL)
-            .border_style(Style::default().fg(Color::Cyan))
-            .style(Style::default().bg(Color::Black));
+            .border_style(Style::default().fg(theme.primary))
+            .style(Style::default().bg(theme.background));
         
         f.render_widget(block, popup_area);
         
@@ -2395,80 +7326,621 @@ impl InteractiveApp {
         let playlists = playlist_manager.list_playlists();
         let mut items = Vec::new();
         
-        // Add existing playlists
-        for playlist in &playlists {
-            // Simple playlist display without duration for now (to avoid complexity)
-            items.push(ListItem::new(format!("📋 {}", playlist.name)));
+        // Add existing playlists
+        for playlist in &playlists {
+            // Simple playlist display without duration for now (to avoid complexity)
+            items.push(ListItem::new(format!("📋 {}", playlist.name)));
+        }
+        
+        // Add "Create New Playlist" option
+        items.push(ListItem::new("➕ Create New Playlist"));
+        
+        // Create the list widget
+        let list = List::new(items)
+            .block(Block::default())
+            .style(Style::default().fg(theme.text))
+            .highlight_style(Style::default().bg(theme.highlight_bg).fg(theme.text))
+            .highlight_symbol("▶ ");
+        
+        // Render the list
+        f.render_stateful_widget(list, inner_area, list_state);
+        
+        // Add instructions at the bottom
+        let instructions_area = Self::popup_footer(popup_area);
+        
+        let instructions = Paragraph::new("↑↓: Navigate | Enter: Select | Esc: Cancel")
+            .style(Style::default().fg(theme.muted))
+            .alignment(Alignment::Center);
+        
+        f.render_widget(instructions, instructions_area);
+    }
+    
+    fn render_device_picker_overlay(f: &mut Frame, area: Rect, theme: &Theme, devices: &[String], list_state: &mut ListState) {
+        let popup_area = Self::centered_rect(60, 50, area);
+        f.render_widget(Clear, popup_area);
+        
+        let block = Block::default()
+            .title(" Audio Output Device ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.primary))
+            .style(Style::default().bg(theme.background));
+        f.render_widget(block, popup_area);
+        
+        let inner_area = popup_area.inner(Margin { horizontal: 1, vertical: 1 });
+        let items: Vec<ListItem> = std::iter::once(ListItem::new("🔈 System default"))
+            .chain(devices.iter().map(|name| ListItem::new(format!("🎧 {}", name))))
+            .collect();
+        
+        let list = List::new(items)
+            .style(Style::default().fg(theme.text))
+            .highlight_style(Style::default().bg(theme.highlight_bg).fg(theme.text))
+            .highlight_symbol("▶ ");
+        f.render_stateful_widget(list, inner_area, list_state);
+        
+        let instructions_area = Self::popup_footer(popup_area);
+        let instructions = Paragraph::new("↑↓: Navigate | Enter: Use device | Esc: Cancel")
+            .style(Style::default().fg(theme.muted))
+            .alignment(Alignment::Center);
+        f.render_widget(instructions, instructions_area);
+    }
+    
+    fn render_bookmark_picker_overlay(f: &mut Frame, area: Rect, theme: &Theme, bookmarks: &[Bookmark], list_state: &mut ListState) {
+        let popup_area = Self::centered_rect(50, 50, area);
+        f.render_widget(Clear, popup_area);
+        
+        let block = Block::default()
+            .title(" Bookmarks ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.primary))
+            .style(Style::default().bg(theme.background));
+        f.render_widget(block, popup_area);
+        
+        let inner_area = popup_area.inner(Margin { horizontal: 1, vertical: 1 });
+        let items: Vec<ListItem> = bookmarks.iter()
+            .map(|b| ListItem::new(format!("🔖 {:>7}  {}", format_position(b.position()), b.label)))
+            .collect();
+        
+        let list = List::new(items)
+            .style(Style::default().fg(theme.text))
+            .highlight_style(Style::default().bg(theme.highlight_bg).fg(theme.text))
+            .highlight_symbol("▶ ");
+        f.render_stateful_widget(list, inner_area, list_state);
+        
+        let instructions_area = Self::popup_footer(popup_area);
+        let instructions = Paragraph::new("↑↓: Navigate | Enter: Jump | x: Delete | Esc: Close")
+            .style(Style::default().fg(theme.muted))
+            .alignment(Alignment::Center);
+        f.render_widget(instructions, instructions_area);
+    }
+    
+    fn render_chapter_list_overlay(f: &mut Frame, area: Rect, theme: &Theme, chapters: &[Chapter], list_state: &mut ListState) {
+        let popup_area = Self::centered_rect(60, 60, area);
+        f.render_widget(Clear, popup_area);
+        
+        let block = Block::default()
+            .title(" Chapters ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.primary))
+            .style(Style::default().bg(theme.background));
+        f.render_widget(block, popup_area);
+        
+        let inner_area = popup_area.inner(Margin { horizontal: 1, vertical: 1 });
+        let items: Vec<ListItem> = chapters.iter()
+            .map(|chapter| ListItem::new(format!("📖 {:>8}  {}", format_position(chapter.start), chapter.title)))
+            .collect();
+        
+        let list = List::new(items)
+            .style(Style::default().fg(theme.text))
+            .highlight_style(Style::default().bg(theme.highlight_bg).fg(theme.text))
+            .highlight_symbol("▶ ");
+        f.render_stateful_widget(list, inner_area, list_state);
+        
+        let instructions_area = Self::popup_footer(popup_area);
+        let instructions = Paragraph::new("↑↓: Navigate | Enter: Jump | Esc: Close")
+            .style(Style::default().fg(theme.muted))
+            .alignment(Alignment::Center);
+        f.render_widget(instructions, instructions_area);
+    }
+    
+    fn render_history_overlay(f: &mut Frame, area: Rect, theme: &Theme, history: &[RecentPlay], list_state: &mut ListState) {
+        let popup_area = Self::centered_rect(60, 70, area);
+        f.render_widget(Clear, popup_area);
+        
+        let block = Block::default()
+            .title(" Recently Played ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.primary))
+            .style(Style::default().bg(theme.background));
+        f.render_widget(block, popup_area);
+        
+        // Leave the last inner row to the key hints
+        let inner_area = popup_area.inner(Margin { horizontal: 1, vertical: 1 });
+        let list_area = Rect { height: inner_area.height.saturating_sub(1), ..inner_area };
+        let items: Vec<ListItem> = history.iter()
+            .map(|play| {
+                let when = play.started_at.with_timezone(&chrono::Local).format("%b %d %H:%M").to_string();
+                let name = match (&play.artist, &play.title, &play.file_path) {
+                    (Some(artist), Some(title), _) => format!("{} - {}", artist, title),
+                    (None, Some(title), _) => title.clone(),
+                    (_, None, Some(path)) => path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default(),
+                    _ => "Unknown track".to_string(),
+                };
+                let mut spans = vec![
+                    Span::styled(format!("{}  ", when), Style::default().fg(theme.muted)),
+                    Span::raw(name),
+                ];
+                if play.plays > 1 {
+                    spans.push(Span::styled(format!("  ×{}", play.plays), Style::default().fg(theme.accent)));
+                }
+                ListItem::new(Line::from(spans))
+            })
+            .collect();
+        
+        let list = List::new(items)
+            .style(Style::default().fg(theme.text))
+            .highlight_style(Style::default().bg(theme.highlight_bg).fg(theme.text))
+            .highlight_symbol("▶ ");
+        f.render_stateful_widget(list, list_area, list_state);
+        
+        let instructions_area = Self::popup_footer(popup_area);
+        let instructions = Paragraph::new("↑↓: Navigate | Enter: Play again | Esc: Close")
+            .style(Style::default().fg(theme.muted))
+            .alignment(Alignment::Center);
+        f.render_widget(instructions, instructions_area);
+    }
+    
+    fn render_more_like_this_overlay(f: &mut Frame, area: Rect, theme: &Theme, view: &MoreLikeThis, tracks: &[panpipe::Track], list_state: &mut ListState) {
+        let popup_area = Self::centered_rect(60, 70, area);
+        f.render_widget(Clear, popup_area);
+        
+        let block = Block::default()
+            .title(format!(" More like '{}' ", view.title))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.primary))
+            .style(Style::default().bg(theme.background));
+        f.render_widget(block, popup_area);
+        
+        // Leave the last inner row to the key hints
+        let inner_area = popup_area.inner(Margin { horizontal: 1, vertical: 1 });
+        let list_area = Rect { height: inner_area.height.saturating_sub(1), ..inner_area };
+        let items: Vec<ListItem> = view.similar.iter()
+            .filter_map(|suggestion| {
+                let track = tracks.get(suggestion.index)?;
+                Some(ListItem::new(Line::from(vec![
+                    Span::raw(format!("{} - {}", track.display_artist(), track.display_title())),
+                    Span::styled(format!("  {}", suggestion.reasons.join(", ")), Style::default().fg(theme.muted)),
+                ])))
+            })
+            .collect();
+        
+        let list = List::new(items)
+            .style(Style::default().fg(theme.text))
+            .highlight_style(Style::default().bg(theme.highlight_bg).fg(theme.text))
+            .highlight_symbol("▶ ");
+        f.render_stateful_widget(list, list_area, list_state);
+        
+        let instructions_area = Self::popup_footer(popup_area);
+        let instructions = Paragraph::new("↑↓: Navigate | Enter: Play from here | a: Queue all | Esc: Close")
+            .style(Style::default().fg(theme.muted))
+            .alignment(Alignment::Center);
+        f.render_widget(instructions, instructions_area);
+    }
+    
+    fn render_error_log_overlay(f: &mut Frame, area: Rect, theme: &Theme, log: &ErrorLog, list_state: &mut ListState) {
+        let popup_area = Self::centered_rect(70, 70, area);
+        f.render_widget(Clear, popup_area);
+        
+        let block = Block::default()
+            .title(format!(" Error Log ({}) ", log.len()))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.primary))
+            .style(Style::default().bg(theme.background));
+        f.render_widget(block, popup_area);
+        
+        // Leave the last inner row to the key hints
+        let inner_area = popup_area.inner(Margin { horizontal: 1, vertical: 1 });
+        let list_area = Rect { height: inner_area.height.saturating_sub(1), ..inner_area };
+        let items: Vec<ListItem> = log.entries()
+            .map(|entry| {
+                let when = chrono::DateTime::<chrono::Local>::from(entry.last_seen).format("%H:%M:%S").to_string();
+                let mut spans = vec![
+                    Span::styled(format!("{}  ", when), Style::default().fg(theme.muted)),
+                    Span::styled(entry.text.clone(), Style::default().fg(Self::level_color(theme, entry.level))),
+                ];
+                if entry.count > 1 {
+                    spans.push(Span::styled(format!("  ×{}", entry.count), Style::default().fg(theme.accent)));
+                }
+                ListItem::new(Line::from(spans))
+            })
+            .collect();
+        
+        let list = List::new(items)
+            .style(Style::default().fg(theme.text))
+            .highlight_style(Style::default().bg(theme.highlight_bg).fg(theme.text))
+            .highlight_symbol("▶ ");
+        f.render_stateful_widget(list, list_area, list_state);
+        
+        let instructions_area = Self::popup_footer(popup_area);
+        let instructions = Paragraph::new("↑↓: Navigate | Esc: Close")
+            .style(Style::default().fg(theme.muted))
+            .alignment(Alignment::Center);
+        f.render_widget(instructions, instructions_area);
+    }
+    
+    fn render_excluded_tracks_overlay(f: &mut Frame, area: Rect, theme: &Theme, tracks: &[panpipe::Track], excluded: &[usize], list_state: &mut ListState) {
+        let popup_area = Self::centered_rect(60, 70, area);
+        f.render_widget(Clear, popup_area);
+        
+        let block = Block::default()
+            .title(format!(" Excluded from Shuffle ({}) ", excluded.len()))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.primary))
+            .style(Style::default().bg(theme.background));
+        f.render_widget(block, popup_area);
+        
+        // Leave the last inner row to the key hints
+        let inner_area = popup_area.inner(Margin { horizontal: 1, vertical: 1 });
+        let list_area = Rect { height: inner_area.height.saturating_sub(1), ..inner_area };
+        let items: Vec<ListItem> = excluded.iter()
+            .map(|&idx| {
+                let track = &tracks[idx];
+                ListItem::new(Line::from(vec![
+                    Span::raw(format!("{} - {}", track.display_artist(), track.display_title())),
+                    Span::styled(format!("  {}", track.display_album()), Style::default().fg(theme.muted)),
+                ]))
+            })
+            .collect();
+        
+        let list = List::new(items)
+            .style(Style::default().fg(theme.text))
+            .highlight_style(Style::default().bg(theme.highlight_bg).fg(theme.text))
+            .highlight_symbol("▶ ");
+        f.render_stateful_widget(list, list_area, list_state);
+        
+        let instructions_area = Self::popup_footer(popup_area);
+        let instructions = Paragraph::new("↑↓: Navigate | Enter/x: Back into shuffle | Esc: Close")
+            .style(Style::default().fg(theme.muted))
+            .alignment(Alignment::Center);
+        f.render_widget(instructions, instructions_area);
+    }
+    
+    fn render_behavior_detail_overlay(f: &mut Frame, area: Rect, theme: &Theme, detail: &BehaviorDetail) {
+        let popup_area = Self::centered_rect(50, 50, area);
+        f.render_widget(Clear, popup_area);
+        
+        let label = |name: &str| Span::styled(format!("  {:<16}", name), Style::default().fg(theme.muted));
+        let mut lines = vec![
+            Line::from(vec![Span::styled(detail.title.clone(), Style::default().fg(theme.primary).add_modifier(Modifier::BOLD))]),
+            Line::from(""),
+        ];
+        
+        match (&detail.behavior, detail.weight) {
+            (Some(behavior), Some(weight)) => {
+                let skip_rate = if behavior.total_plays > 0 {
+                    behavior.total_skips as f64 / behavior.total_plays as f64 * 100.0
+                } else {
+                    0.0
+                };
+                let last_played = behavior.last_played
+                    .map(|at| {
+                        let days = (chrono::Utc::now() - at).num_days();
+                        format!("{} ({} days ago)", at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M"), days)
+                    })
+                    .unwrap_or_else(|| "never".to_string());
+                let tags = if behavior.tags.is_empty() {
+                    "none".to_string()
+                } else {
+                    behavior.tags.iter().map(|tag| tag.replace('_', " ")).collect::<Vec<_>>().join(", ")
+                };
+                
+                lines.extend([
+                    Line::from(vec![label("Plays"), Span::raw(behavior.total_plays.to_string())]),
+                    Line::from(vec![label("Skips"), Span::raw(format!("{} ({:.0}%), {} early", behavior.total_skips, skip_rate, behavior.early_skips))]),
+                    Line::from(vec![label("Completion"), Span::raw(format!("{:.0}%", behavior.completion_rate))]),
+                    Line::from(vec![label("Listened"), Span::raw(Self::format_duration(Duration::from_secs(behavior.total_play_time)))]),
+                    Line::from(vec![label("Last played"), Span::raw(last_played)]),
+                    Line::from(vec![label("Tags"), Span::styled(tags, Style::default().fg(theme.accent))]),
+                    Line::from(""),
+                    Line::from(vec![
+                        label("Shuffle weight"),
+                        Span::styled(format!("{:.2}", weight), Style::default().fg(theme.playing).add_modifier(Modifier::BOLD)),
+                        Span::styled("  (1.00 is neutral)", Style::default().fg(theme.muted)),
+                    ]),
+                ]);
+            }
+            _ => {
+                lines.push(Line::from("  No plays recorded yet."));
+                lines.push(Line::from("  Shuffle gives tracks without history a small boost."));
+            }
+        }
+        
+        let paragraph = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .title(" Listening behavior ")
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(theme.primary))
+                    .style(Style::default().bg(theme.background))
+            )
+            .style(Style::default().fg(theme.text))
+            .wrap(Wrap { trim: false });
+        f.render_widget(paragraph, popup_area);
+        
+        let instructions_area = Self::popup_footer(popup_area);
+        let instructions = Paragraph::new("Esc / i: Close")
+            .style(Style::default().fg(theme.muted))
+            .alignment(Alignment::Center);
+        f.render_widget(instructions, instructions_area);
+    }
+    
+    fn render_scan_report_overlay(f: &mut Frame, area: Rect, theme: &Theme, report: &ScanReport, scroll: u16) {
+        let popup_area = Self::centered_rect(80, 70, area);
+        
+        let mut lines = vec![
+            Line::from(vec![Span::styled(
+                format!("🔁 Likely duplicates ({} groups)", report.duplicate_groups.len()),
+                Style::default().fg(theme.primary).add_modifier(Modifier::BOLD),
+            )]),
+        ];
+        if report.duplicate_groups.is_empty() {
+            lines.push(Line::from("  ✨ No duplicate content found"));
+        }
+        for group in &report.duplicate_groups {
+            lines.push(Line::from(vec![Span::styled(
+                format!("  Hash {:016x} ({} files)", group.content_hash, group.paths.len()),
+                Style::default().fg(theme.accent),
+            )]));
+            for path in &group.paths {
+                lines.push(Line::from(format!("    📄 {}", path.display())));
+            }
+        }
+        
+        lines.push(Line::from(""));
+        lines.push(Line::from(vec![Span::styled(
+            format!("⚠️ Unreadable files ({})", report.unreadable.len()),
+            Style::default().fg(theme.warning).add_modifier(Modifier::BOLD),
+        )]));
+        if report.unreadable.is_empty() {
+            lines.push(Line::from("  ✨ Every file was read cleanly"));
+        }
+        for file in &report.unreadable {
+            lines.push(Line::from(format!("  📄 {}", file.path.display())));
+            lines.push(Line::from(vec![Span::styled(format!("     {}", file.error), Style::default().fg(theme.muted))]));
+        }
+        
+        let report_paragraph = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Scan Report - ↑↓ scroll, Esc/d to close")
+                    .border_style(Style::default().fg(theme.primary))
+            )
+            .style(Style::default().bg(theme.background).fg(theme.text))
+            .scroll((scroll, 0));
+        
+        f.render_widget(Clear, popup_area);
+        f.render_widget(report_paragraph, popup_area);
+    }
+    
+    fn render_duplicates_overlay(f: &mut Frame, area: Rect, theme: &Theme, review: &DuplicateReview, tracks: &[panpipe::Track]) {
+        let popup_area = Self::centered_rect(80, 60, area);
+        let group = &review.groups[review.selected];
+        
+        let mut lines = vec![
+            Line::from(vec![Span::styled(
+                format!("🔁 Group {} of {} - hash {:016x}", review.selected + 1, review.groups.len(), group.content_hash),
+                Style::default().fg(theme.primary).add_modifier(Modifier::BOLD),
+            )]),
+            Line::from(""),
+        ];
+        for (idx, path) in group.paths.iter().enumerate() {
+            let track = tracks.iter().find(|t| &t.file_path == path);
+            let details = track
+                .map(|t| format!("{} - {}, {:.1} MB", t.display_artist(), t.display_title(), t.file_size as f64 / 1_048_576.0))
+                .unwrap_or_default();
+            let (marker, style) = if idx == review.keep {
+                ("✅ keep  ", Style::default().fg(theme.accent).add_modifier(Modifier::BOLD))
+            } else {
+                ("🗑️ extra ", Style::default().fg(theme.text))
+            };
+            lines.push(Line::from(vec![Span::styled(format!("  {} {}", marker, path.display()), style)]));
+            lines.push(Line::from(vec![Span::styled(format!("           {}", details), Style::default().fg(theme.muted))]));
+        }
+        
+        lines.push(Line::from(""));
+        let mode = if review.dry_run {
+            Span::styled("🔍 Dry run - nothing is hidden or deleted (r to act for real)", Style::default().fg(theme.playing))
+        } else {
+            Span::styled("⚠️ Hide and delete act for real (r for a dry run)", Style::default().fg(theme.warning).add_modifier(Modifier::BOLD))
+        };
+        lines.push(Line::from(mode));
+        lines.push(Line::from(vec![Span::styled(
+            "↑↓ group · ←→ copy to keep · h hide the others · x delete the others · Esc close",
+            Style::default().fg(theme.muted),
+        )]));
+        
+        let paragraph = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Find Duplicates")
+                    .border_style(Style::default().fg(theme.primary))
+            )
+            .style(Style::default().bg(theme.background).fg(theme.text))
+            .wrap(Wrap { trim: false });
+        
+        f.render_widget(Clear, popup_area);
+        f.render_widget(paragraph, popup_area);
+    }
+    
+    fn render_rename_preview_overlay(f: &mut Frame, area: Rect, theme: &Theme, preview: &RenamePreview, pattern: &str) {
+        let popup_area = Self::centered_rect(80, 70, area);
+        let name = |path: &PathBuf| path.file_name().unwrap_or(path.as_os_str()).to_string_lossy().into_owned();
+        
+        let mut lines = vec![
+            Line::from(vec![Span::styled(
+                format!("✏️ {} - pattern {}", preview.plan.describe(), pattern),
+                Style::default().fg(theme.primary).add_modifier(Modifier::BOLD),
+            )]),
+            Line::from(""),
+        ];
+        let mode = if preview.dry_run {
+            Span::styled("🔍 Dry run - Enter only writes the plan to the log (r to rename for real)", Style::default().fg(theme.playing))
+        } else {
+            Span::styled("⚠️ Enter renames for real, after a confirmation (r for a dry run)", Style::default().fg(theme.warning).add_modifier(Modifier::BOLD))
+        };
+        lines.push(Line::from(mode));
+        lines.push(Line::from(vec![Span::styled("↑↓ scroll · r dry run · Enter rename · Esc close", Style::default().fg(theme.muted))]));
+        lines.push(Line::from(""));
+        for rename in preview.plan.renames.iter().skip(preview.scroll as usize) {
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {}", name(&rename.from)), Style::default().fg(theme.muted)),
+                Span::raw(" → "),
+                Span::styled(name(&rename.to), Style::default().fg(theme.accent)),
+            ]));
         }
         
-        // Add "Create New Playlist" option
-        items.push(ListItem::new("➕ Create New Playlist"));
+        let paragraph = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Rename Files")
+                    .border_style(Style::default().fg(theme.primary))
+            )
+            .style(Style::default().bg(theme.background).fg(theme.text));
         
-        // Create the list widget
-        let list = List::new(items)
-            .block(Block::default())
-            .style(Style::default().fg(Color::White))
-            .highlight_style(Style::default().bg(Color::Blue).fg(Color::White))
-            .highlight_symbol("▶ ");
+        f.render_widget(Clear, popup_area);
+        f.render_widget(paragraph, popup_area);
+    }
+    
+    #[cfg(feature = "musicbrainz")]
+    fn render_metadata_matches_overlay(f: &mut Frame, area: Rect, theme: &Theme, matches: &MetadataMatches) {
+        let popup_area = Self::centered_rect(70, 60, area);
+        let name = matches.path.file_name().unwrap_or(matches.path.as_os_str()).to_string_lossy().into_owned();
         
-        // Render the list
-        f.render_stateful_widget(list, inner_area, list_state);
+        let mut lines = vec![
+            Line::from(vec![Span::styled(
+                format!("🔎 {} MusicBrainz matches for {}", matches.candidates.len(), name),
+                Style::default().fg(theme.primary).add_modifier(Modifier::BOLD),
+            )]),
+            Line::from(vec![Span::styled("↑↓ choose · Enter apply · Esc close", Style::default().fg(theme.muted))]),
+            Line::from(""),
+        ];
+        for (idx, candidate) in matches.candidates.iter().enumerate() {
+            let selected = idx == matches.selected;
+            let style = if selected {
+                Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(theme.text)
+            };
+            let mut release = candidate.album.clone().unwrap_or_else(|| "no album".to_string());
+            if let Some(year) = candidate.year {
+                release.push_str(&format!(" ({})", year));
+            }
+            if let Some(number) = candidate.track_number {
+                release.push_str(&format!(" · track {}", number));
+            }
+            lines.push(Line::from(vec![
+                Span::styled(format!("{} {:>3}%  ", if selected { "▶" } else { " " }, candidate.score), Style::default().fg(theme.muted)),
+                Span::styled(candidate.display_name(), style),
+            ]));
+            lines.push(Line::from(vec![Span::styled(format!("        {}", release), Style::default().fg(theme.muted))]));
+        }
         
-        // Add instructions at the bottom
-        let instructions_area = Rect {
-            x: popup_area.x + 1,
-            y: popup_area.y + popup_area.height - 2,
-            width: popup_area.width - 2,
-            height: 1,
+        let paragraph = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("MusicBrainz Lookup")
+                    .border_style(Style::default().fg(theme.primary))
+            )
+            .style(Style::default().bg(theme.background).fg(theme.text));
+        
+        f.render_widget(Clear, popup_area);
+        f.render_widget(paragraph, popup_area);
+    }
+    
+    fn render_task_overlay(f: &mut Frame, area: Rect, theme: &Theme, task: &BackgroundTask) {
+        let width = 50.min(area.width);
+        let popup_area = Rect {
+            x: area.x + area.width.saturating_sub(width) / 2,
+            y: area.y + area.height.saturating_sub(7) / 2,
+            width,
+            height: 7.min(area.height),
         };
+        let cancelling = task.cancel.load(Ordering::Relaxed);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(format!(" {} ", task.label))
+            .border_style(Style::default().fg(if cancelling { theme.warning } else { theme.primary }))
+            .style(Style::default().bg(theme.background));
+        let inner = block.inner(popup_area);
+        f.render_widget(Clear, popup_area);
+        f.render_widget(block, popup_area);
         
-        let instructions = Paragraph::new("↑↓: Navigate | Enter: Select | Esc: Cancel")
-            .style(Style::default().fg(Color::Gray))
-            .alignment(Alignment::Center);
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Length(1), Constraint::Length(1), Constraint::Min(0)])
+            .split(inner);
+        let ratio = if task.total == 0 { 0.0 } else { (task.done as f64 / task.total as f64).min(1.0) };
+        let gauge = Gauge::default()
+            .gauge_style(Style::default().fg(theme.accent))
+            .ratio(ratio)
+            .label(format!("{} / {}", task.done, task.total));
+        f.render_widget(gauge, rows[0]);
         
-        f.render_widget(instructions, instructions_area);
+        let detail = if cancelling {
+            "Cancelling…".to_string()
+        } else if let Some(label) = task.results_label {
+            format!("{} {} / {}", label, task.results, task.total)
+        } else {
+            String::new()
+        };
+        f.render_widget(
+            Paragraph::new(detail).style(Style::default().fg(theme.text)).alignment(Alignment::Center),
+            rows[2],
+        );
+        f.render_widget(
+            Paragraph::new("Esc: Cancel · q: Quit").style(Style::default().fg(theme.muted)).alignment(Alignment::Center),
+            Self::popup_footer(popup_area),
+        );
     }
     
-    fn render_help_overlay(f: &mut Frame, area: Rect) {
+    /// Built from the active keymap, so it lists exactly what each key does on the current tab
+    fn render_help_overlay(f: &mut Frame, area: Rect, theme: &Theme, keymap: &Keymap, tab: &AppTab) {
         // Create centered popup area
         let popup_area = Self::centered_rect(80, 70, area);
+        let heading = |text: &str| Line::from(vec![Span::styled(text.to_string(), Style::default().fg(theme.primary).add_modifier(Modifier::BOLD))]);
+        let entry = |keys: &str, description: &str| Line::from(vec![
+            Span::styled(format!("  {:<16}", keys), Style::default().fg(theme.accent)),
+            Span::raw(description.to_string()),
+        ]);
         
-        let help_text = vec![
-            Line::from(vec![Span::styled("🎵 BangTunes Help", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))]),
+        let mut help_text = vec![
+            Line::from(vec![Span::styled("🎵 BangTunes Help", Style::default().fg(theme.accent).add_modifier(Modifier::BOLD))]),
             Line::from(""),
-            Line::from(vec![Span::styled("Navigation:", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))]),
-            Line::from("  ↑/↓           Navigate tracks (no auto-play)"),
-            Line::from("  1/2/3         Switch tabs (Library/Metadata Editor/Settings)"),
-            Line::from("  /             Enter search mode (fuzzy search)"),
-            Line::from("  ?             Toggle this help"),
-            Line::from("  q             Quit"),
-            Line::from(""),
-            Line::from(vec![Span::styled("Playback:", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))]),
-            Line::from("  Space         Play/Pause"),
-            Line::from("  n             Next track"),
-            Line::from("  p             Previous track"),
-            Line::from("  s             Toggle shuffle"),
-            Line::from("  r             Cycle repeat mode"),
-            Line::from("  +/-           Volume up/down"),
-            Line::from(""),
-            Line::from(vec![Span::styled("Playlists:", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))]),
-            Line::from("  c             Create playlist"),
-            Line::from("  Del           Delete playlist"),
-            Line::from("  l/Enter       Load playlist"),
-            Line::from("  a             Add track to playlist (from Library)"),
-            Line::from(""),
-            Line::from(vec![Span::styled("Metadata Editor:", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))]),
-            Line::from("  Enter         Edit selected track"),
-            Line::from("  Ctrl+S        Save changes"),
-            Line::from("  Esc           Cancel edit"),
-            Line::from("  Ctrl+R        Reset to original"),
-            Line::from("  Ctrl+A        Apply suggestions"),
-            Line::from(""),
-            Line::from(vec![Span::styled("Press ? again to close", Style::default().fg(Color::Yellow))]),
         ];
+        for (category, entries) in keymap.help(tab.key_scope()) {
+            help_text.push(heading(&format!("{}:", category.name())));
+            help_text.extend(entries.iter().map(|(keys, description)| entry(keys, description)));
+            help_text.push(Line::from(""));
+        }
+        
+        // Keys handled outside the keymap
+        help_text.push(heading("Other:"));
+        help_text.push(entry("esc", "Close a popup, leave search or an edit, then clear the filter, then the marks"));
+        help_text.push(entry("tab", "While searching: match All / Title / Artist / Album"));
+        if *tab == AppTab::Library {
+            help_text.push(entry("letters", "Jump to the artist/title starting with what you type"));
+        }
+        help_text.push(Line::from(""));
+        help_text.push(Line::from(vec![Span::styled(
+            format!("Other tabs have their own keys - rebind any under [keybindings]. Press {} again to close", keymap.keys_label(Action::ShowHelp)),
+            Style::default().fg(theme.accent),
+        )]));
         
         // Clear the entire screen background first
-        let clear_all = Block::default().style(Style::default().bg(Color::Black));
+        let clear_all = Block::default().style(Style::default().bg(theme.background));
         f.render_widget(clear_all, area);
         
         // Create a completely solid background that fills the entire popup area
@@ -2477,7 +7949,7 @@ impl InteractiveApp {
         
         // Render a solid background block to ensure complete opacity
         let solid_background = Block::default()
-            .style(Style::default().bg(Color::Black));
+            .style(Style::default().bg(theme.background));
         f.render_widget(solid_background, popup_area);
         
         let help_paragraph = Paragraph::new(help_text)
@@ -2485,14 +7957,38 @@ impl InteractiveApp {
                 Block::default()
                     .borders(Borders::ALL)
                     .title("Help")
-                    .border_style(Style::default().fg(Color::Yellow))
+                    .border_style(Style::default().fg(theme.accent))
             )
-            .style(Style::default().bg(Color::Black).fg(Color::White))
+            .style(Style::default().bg(theme.background).fg(theme.text))
             .wrap(Wrap { trim: true });
         
         f.render_widget(help_paragraph, popup_area);
     }
     
+    /// The last row inside a bordered popup, where its key hints go
+    fn popup_footer(popup_area: Rect) -> Rect {
+        Rect {
+            x: popup_area.x + 1,
+            y: (popup_area.y + popup_area.height).saturating_sub(2).max(popup_area.y),
+            width: popup_area.width.saturating_sub(2),
+            height: popup_area.height.min(1),
+        }
+    }
+    
+    fn render_too_small(f: &mut Frame, area: Rect, theme: &Theme, (min_width, min_height): (u16, u16)) {
+        let message = format!(
+            "Window too small (need ≥ {}x{}, have {}x{})",
+            min_width, min_height, area.width, area.height,
+        );
+        let notice = Paragraph::new(message)
+            .style(Style::default().fg(theme.warning).bg(theme.background))
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true });
+        let top = area.height.saturating_sub(2) / 2;
+        f.render_widget(notice, Rect { y: area.y + top, height: area.height - top, ..area });
+    }
+    
+    /// A `percent_x` by `percent_y` rect in the middle of `r`, grown to the popup minimum where `r` allows
     fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
         let popup_layout = Layout::default()
             .direction(Direction::Vertical)
@@ -2503,29 +7999,53 @@ impl InteractiveApp {
             ])
             .split(r);
 
-        Layout::default()
+        let popup = Layout::default()
             .direction(Direction::Horizontal)
             .constraints([
                 Constraint::Percentage((100 - percent_x) / 2),
                 Constraint::Percentage(percent_x),
                 Constraint::Percentage((100 - percent_x) / 2),
             ])
-            .split(popup_layout[1])[1]
+            .split(popup_layout[1])[1];
+        
+        let width = popup.width.max(MIN_POPUP_WIDTH.min(r.width));
+        let height = popup.height.max(MIN_POPUP_HEIGHT.min(r.height));
+        Rect {
+            x: r.x + (r.width - width) / 2,
+            y: r.y + (r.height - height) / 2,
+            width,
+            height,
+        }
     }
 
     /// Handle audio events from the player (duration learning, track finished, etc.)
     async fn handle_audio_event(&mut self, event: PlayerEvent) -> Result<()> {
         // PlayerEvent already imported at top
+        self.needs_redraw = true;
         
         match event {
             PlayerEvent::TrackStarted(track) => {
-                self.set_status(&format!("▶️ Playing: {}", self.format_track_title(&track)));
+                if let Some((position, track_idx)) = self.gapless_next {
+                    if self.tracks.get(track_idx).is_some_and(|t| t.id == track.id) {
+                        self.adopt_gapless_track(position, track_idx).await;
+                    }
+                }
+                // A gapless follow-up can start just before a stop catches up with it
+                let is_current = self.current_track_index
+                    .is_some_and(|idx| self.tracks.get(idx).is_some_and(|t| t.id == track.id));
+                if !is_current {
+                    return Ok(());
+                }
+                self.position_reports_stale = false;
+                self.push_notification(NotificationLevel::Info, &format!("▶️ Playing: {}", self.format_track_title(&track)));
             }
             PlayerEvent::TrackFinished(track) => {
-                self.set_status(&format!("🔧 DEBUG: TrackFinished set is_playing=false for {}", self.format_track_title(&track)));
-                // Just stop playing - don't auto-advance or reset track index
-                // This preserves the current track display and progress bar state
-                self.is_playing = false;
+                // Ignore stragglers from a track we've already moved away from
+                let is_current = self.current_track_index
+                    .is_some_and(|idx| self.tracks.get(idx).is_some_and(|t| t.id == track.id));
+                if is_current {
+                    self.finish_track(track).await?;
+                }
             }
             PlayerEvent::DurationLearned(learned_track, actual_duration) => {
                 // Find the track in our library and update its duration
@@ -2549,91 +8069,384 @@ impl InteractiveApp {
                 }
             }
             PlayerEvent::TrackPaused => {
-                // Ignore premature pause events - only pause if explicitly requested by user
-                // The audio player sends false pause events due to sink.empty() immediately after start
-                // NOTE: Don't set is_playing = false here - let explicit user pause actions handle that
+                // Only sent when playback really went from playing to paused
+                self.is_playing = false;
             }
             PlayerEvent::TrackResumed => {
                 self.is_playing = true;
                 self.set_status("▶️ Resumed");
             }
             PlayerEvent::TrackStopped => {
-                // Implement autoplay logic with false positive protection
-                // Only autoplay if we're currently playing and have been for a reasonable duration
-                if self.is_playing && self.current_track_index.is_some() {
-                    let elapsed = self.last_position_update.elapsed();
-                    
-                    // Only autoplay if track has been playing for more than 2 seconds
-                    // This prevents false positives from sink.empty() immediately after start
-                    if elapsed.as_secs() >= 2 {
-                        debug!("🎵 Track completed after {}s, triggering autoplay", elapsed.as_secs());
-                        
-                        // Record track completion
-                        if let Some(current_idx) = self.current_track_index {
-                            let track = &self.tracks[current_idx];
-                            let _ = self.behavior_tracker.handle_event(PlaybackEvent::TrackCompleted {
-                                track_id: track.id,
-                                timestamp: chrono::Utc::now(),
-                            }).await;
-                        }
-                        
-                        // Autoplay next track with strict playlist isolation
-                        if self.current_tab == AppTab::Playlists && !self.expanded_playlists.is_empty() {
-                            // Autoplay within the expanded playlist only
-                            match self.next_track().await {
-                                Ok(()) => {
-                                    debug!("🎵 Autoplay: Successfully started next track in playlist");
-                                }
-                                Err(e) => {
-                                    debug!("❌ Autoplay failed in playlist: {}", e);
-                                    self.is_playing = false;
-                                    self.current_track_index = None;
-                                    self.set_status("⏹️ Playback stopped - end of playlist");
-                                }
-                            }
-                        } else {
-                            // Autoplay in library context
-                            match self.next_track().await {
-                                Ok(()) => {
-                                    debug!("🎵 Autoplay: Successfully started next track in library");
-                                }
-                                Err(e) => {
-                                    debug!("❌ Autoplay failed in library: {}", e);
-                                    self.is_playing = false;
-                                    self.current_track_index = None;
-                                    self.set_status("⏹️ Playback stopped - end of library");
-                                }
-                            }
-                        }
-                    } else {
-                        debug!("🔍 Ignoring premature TrackStopped event ({}ms elapsed)", elapsed.as_millis());
-                    }
-                } else {
-                    debug!("🔍 Ignoring TrackStopped - not currently playing");
-                }
+                // Explicit stop or a track change - completion arrives as TrackFinished
             }
             PlayerEvent::VolumeChanged(volume) => {
-                self.volume = volume;
-                self.set_status(&format!("🔊 Volume: {}%", (volume * 100.0) as u32));
+                // The player already owns the volume - nothing to sync here
+                debug!("Volume changed to {:.2}", volume);
             }
             PlayerEvent::Error(error) => {
                 // Filter out known ALSA underrun errors to avoid UI spam
                 let error_str = error.to_string();
                 if error_str.contains("underrun occurred") || error_str.contains("snd_pcm_recover") {
-                    // Log ALSA underruns but don't show in UI (these are common and non-critical)
+                    // Too common to toast, but a run of them explains stutters, so the error log keeps them
                     debug!("🔊 ALSA underrun occurred (audio buffer issue, non-critical)");
+                    self.error_log.record(NotificationLevel::Warning, format!("🔊 ALSA underrun: {}", error_str.trim()));
                 } else {
                     // Show other audio errors in UI
-                    self.set_status(&format!("❌ Audio Error: {}", error));
+                    self.push_notification(NotificationLevel::Error, &format!("❌ Audio Error: {}", error));
+                }
+            }
+            PlayerEvent::PositionChanged(position) => {
+                // The sink's real position, so the progress bar doesn't drift through underruns
+                if !self.position_reports_stale {
+                    self.current_position = position;
+                }
+            }
+        }
+        
+        Ok(())
+    }
+
+    /// A left click on the progress bar seeks to that share of the track; other mouse input is ignored
+    fn mouse_to_app_event(&self, mouse: MouseEvent) -> Option<InteractiveEvent> {
+        if mouse.kind != MouseEventKind::Down(MouseButton::Left) {
+            return None;
+        }
+        let bar = self.progress_bar_area?;
+        let inside = mouse.row == bar.y && (bar.x..bar.x + bar.width).contains(&mouse.column);
+        inside.then(|| {
+            let span = bar.width.saturating_sub(1).max(1) as f64;
+            InteractiveEvent::SeekToFraction((mouse.column - bar.x) as f64 / span)
+        })
+    }
+    
+    /// Jump within the current track
+    fn seek_to(&mut self, position: Duration) {
+        let position = match self.total_duration {
+            Some(total) => position.min(total),
+            None => position,
+        };
+        
+        match self.audio_player.seek(position) {
+            Ok(()) => self.current_position = position,
+            Err(e) => self.push_notification(NotificationLevel::Warning, &format!("⚠️ {}", e)),
+        }
+    }
+    
+    /// Run commands from the desktop, then push any state changes back out
+    #[cfg(feature = "mpris")]
+    async fn process_mpris(&mut self) -> Result<()> {
+        let commands: Vec<MprisCommand> = match self.mpris.as_mut() {
+            Some(bridge) => std::iter::from_fn(|| bridge.commands.try_recv().ok()).collect(),
+            None => return Ok(()),
+        };
+        
+        for command in commands {
+            match command {
+                MprisCommand::PlayPause => self.handle_event(InteractiveEvent::TogglePlayPause).await?,
+                MprisCommand::Play if !self.is_playing => self.handle_event(InteractiveEvent::TogglePlayPause).await?,
+                MprisCommand::Pause if self.is_playing => self.handle_event(InteractiveEvent::TogglePlayPause).await?,
+                MprisCommand::Play | MprisCommand::Pause => {}
+                MprisCommand::Stop => self.handle_event(InteractiveEvent::Stop).await?,
+                MprisCommand::Next => self.handle_event(InteractiveEvent::NextTrack).await?,
+                MprisCommand::Previous => self.handle_event(InteractiveEvent::PreviousTrack).await?,
+                MprisCommand::Seek(offset) => {
+                    let current = self.current_position.as_micros() as i64;
+                    let target = (current + offset).max(0) as u64;
+                    self.seek_to(Duration::from_micros(target));
+                }
+                MprisCommand::SetPosition(position) => self.seek_to(position),
+                MprisCommand::SetVolume(volume) => self.set_volume(volume as f32)?,
+            }
+        }
+        
+        let track = self.current_track_index.and_then(|idx| self.tracks.get(idx));
+        let state = self.audio_player.get_state();
+        let volume = self.audio_player.get_volume();
+        if let Some(bridge) = self.mpris.as_mut() {
+            if let Err(e) = bridge.sync(track, state, volume, self.current_position).await {
+                debug!("MPRIS update failed: {}", e);
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Run commands from the control socket, then refresh what its `status` reports
+    async fn process_control(&mut self) -> Result<()> {
+        let commands: Vec<ControlCommand> = match self.control.as_mut() {
+            Some((_, commands)) => std::iter::from_fn(|| commands.try_recv().ok()).collect(),
+            None => return Ok(()),
+        };
+        
+        for command in commands {
+            match command {
+                ControlCommand::PlayPause => self.handle_event(InteractiveEvent::TogglePlayPause).await?,
+                ControlCommand::Play if !self.is_playing => self.handle_event(InteractiveEvent::TogglePlayPause).await?,
+                ControlCommand::Pause if self.is_playing => self.handle_event(InteractiveEvent::TogglePlayPause).await?,
+                ControlCommand::Play | ControlCommand::Pause | ControlCommand::Status => {}
+                ControlCommand::Stop => self.handle_event(InteractiveEvent::Stop).await?,
+                ControlCommand::Next => self.handle_event(InteractiveEvent::NextTrack).await?,
+                ControlCommand::Prev => self.handle_event(InteractiveEvent::PreviousTrack).await?,
+                ControlCommand::SetVolume { volume } => self.set_volume(volume)?,
+            }
+        }
+        
+        if let Some((server, _)) = &self.control {
+            let track = self.current_track_index.and_then(|idx| self.tracks.get(idx));
+            let state = self.audio_player.get_state();
+            server.set_status(ControlStatus::new(track, &state, self.current_position, self.audio_player.get_volume()));
+        }
+        Ok(())
+    }
+    
+    /// Re-read everything cached from the behavior data after it was reset
+    async fn reload_behavior_views(&mut self) {
+        self.refresh_stats().await;
+        if self.sort_field == SortField::Plays {
+            self.load_play_counts().await;
+        }
+        if self.tag_filter.is_some() {
+            match self.behavior_tracker.get_all_behaviors().await {
+                Ok(behaviors) => self.behavior_tags = behaviors.into_iter().map(|b| (b.track_id, b.tags)).collect(),
+                Err(e) => warn!("Failed to reload behavior tags: {}", e),
+            }
+        }
+        if self.sort_field == SortField::Plays || self.tag_filter.is_some() {
+            self.resort_library();
+        }
+    }
+    
+    /// Reload listening stats and shuffle exclusions from the behavior database and recount the library
+    async fn refresh_stats(&mut self) {
+        self.library_stats = LibraryStats::from_tracks(&self.tracks);
+        match self.behavior_tracker.get_all_behaviors().await {
+            Ok(behaviors) => {
+                self.listening_stats = ListeningStats::from_behaviors(&behaviors);
+                self.shuffle_excluded = behaviors.iter().filter(|b| b.shuffle_excluded).map(|b| b.track_id).collect();
+            }
+            Err(e) => warn!("Failed to load listening stats: {}", e),
+        }
+    }
+    
+    /// Dump play sessions and per-track behavior to CSV files next to the database
+    fn export_listening_history(&mut self) -> Result<()> {
+        let export_dir = self.config.database_path.with_file_name("exports");
+        std::fs::create_dir_all(&export_dir)?;
+        
+        let stamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
+        let sessions_path = export_dir.join(format!("play_sessions_{}.csv", stamp));
+        let behaviors_path = export_dir.join(format!("track_behaviors_{}.csv", stamp));
+        
+        let sessions = self.behavior_tracker.export_sessions_csv(&sessions_path)?;
+        let behaviors = self.behavior_tracker.export_behaviors_csv(&behaviors_path)?;
+        
+        info!("Exported listening history to {}", export_dir.display());
+        self.push_notification(
+            NotificationLevel::Success,
+            &format!("📤 Exported {} sessions and {} tracks to {}", sessions, behaviors, export_dir.display()),
+        );
+        Ok(())
+    }
+    
+    /// Write every playlist as M3U into a timestamped folder next to the database, in the background
+    fn export_playlists(&mut self) {
+        let total = self.playlist_manager.list_playlists().len();
+        let Some(cancel) = self.start_task("Exporting playlists", total, None) else {
+            return;
+        };
+        let stamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
+        let export_dir = self.config.database_path.with_file_name("exports").join(format!("playlists_{}", stamp));
+        // Copies, so edits made meanwhile don't race the export
+        let manager = self.playlist_manager.clone();
+        let tracks = self.tracks.clone();
+        let events = self._event_tx.clone();
+        tokio::spawn(async move {
+            let progress = |done, total| {
+                let _ = events.send(InteractiveEvent::TaskProgress { done, total });
+            };
+            let report = manager.export_all_with_progress(&export_dir, ExportFormat::M3u, &tracks, progress, &cancel).await
+                .map_err(|e| e.to_string());
+            let _ = events.send(InteractiveEvent::TaskFinished(TaskResult::PlaylistsExported { dir: export_dir, report }));
+        });
+    }
+    
+    /// Build and save a playlist weighted by listening behavior, then open it
+    ///
+    /// Favorites and long-unplayed tracks come up more often, frequently skipped ones rarely.
+    async fn generate_smart_mix(&mut self) -> Result<()> {
+        if self.tracks.is_empty() {
+            self.set_status("🎲 Library is empty - nothing to mix");
+            return Ok(());
+        }
+        
+        let behaviors: HashMap<_, _> = self.behavior_tracker.get_all_behaviors().await?
+            .into_iter()
+            .map(|behavior| (behavior.track_id, behavior))
+            .collect();
+        
+        // Audiobooks play in order, so they never turn up in a mix
+        let track_ids: Vec<_> = self.tracks.iter()
+            .filter(|t| !self.config.library.is_spoken_word(&t.file_path))
+            .map(|t| t.id)
+            .collect();
+        let mut weighting = ShuffleWeighting::new(self.config.behavior.weight_decay_days);
+        if self.config.behavior.time_of_day_weighting {
+            weighting = weighting.with_play_hours(self.behavior_tracker.get_play_hours().await?);
+        }
+        if self.config.behavior.session_coherence > 0.0 {
+            let metadata = self.tracks.iter().map(|t| (t.id, t.metadata.clone())).collect();
+            weighting = weighting.with_coherence(self.config.behavior.session_coherence, metadata);
+        }
+        let mix = weighting.generate_shuffled_playlist(&track_ids, &behaviors, self.config.behavior.smart_mix_size);
+        
+        // One mix per day keeps the date name; later ones that day get the time too
+        let now = chrono::Local::now();
+        let mut name = format!("Smart Mix {}", now.format("%Y-%m-%d"));
+        if self.playlist_manager.list_playlists().iter().any(|p| p.name == name) {
+            name = format!("Smart Mix {}", now.format("%Y-%m-%d %H:%M:%S"));
+        }
+        
+        let playlist_id = self.playlist_manager
+            .create_playlist(name.clone(), Some(format!("{} tracks weighted by listening behavior", mix.len())))
+            .map_err(|e| anyhow::anyhow!("{}", e))?;
+        
+        if let Some(playlist) = self.playlist_manager.get_playlist_mut(&playlist_id) {
+            for track_id in &mix {
+                if let Some(track) = self.tracks.iter().find(|t| t.id == *track_id) {
+                    playlist.add_track(track.file_path.clone(), track.duration);
                 }
             }
-            PlayerEvent::PositionChanged(_position) => {
-                // Position updates are handled by update_playback_status
+        }
+        if let Some(playlist) = self.playlist_manager.get_playlist(&playlist_id) {
+            self.playlist_manager.save_playlist(playlist)?;
+        }
+        
+        // Switch to the new mix with it expanded and ready to play
+        self.current_tab = AppTab::Playlists;
+        let playlists = self.playlist_manager.list_playlists();
+        if let Some(index) = playlists.iter().position(|p| p.id == playlist_id) {
+            self.playlist_list_state.select(Some(index));
+            
+            self.expanded_playlists.clear();
+            self.playlist_track_states.clear();
+            self.expanded_playlists.insert(playlist_id.clone());
+            
+            let mut track_state = ListState::default();
+            if !playlists[index].get_valid_tracks(&self.tracks).is_empty() {
+                track_state.select(Some(0));
             }
+            self.playlist_track_states.insert(playlist_id, track_state);
+        }
+        self.save_session();
+        
+        self.push_notification(NotificationLevel::Success, &format!("🎲 Created {} ({} tracks)", name, mix.len()));
+        Ok(())
+    }
+    
+    /// Feed a playback event to the behavior tracker, and to last.fm when scrobbling
+    async fn record_playback(&mut self, event: PlaybackEvent) {
+        #[cfg(feature = "scrobble")]
+        let started = match &event {
+            PlaybackEvent::TrackStarted { track_id, .. } => Some(*track_id),
+            _ => None,
+        };
+        
+        if let Err(e) = self.behavior_tracker.handle_event(event).await {
+            debug!("Behavior tracking failed: {}", e);
         }
         
+        #[cfg(feature = "scrobble")]
+        {
+            // Starting a track ends the one before, so its scrobble goes out first
+            self.scrobble_finished_session();
+            let track = started.and_then(|id| self.tracks.iter().find(|t| t.id == id));
+            if let (Some(scrobbler), Some(track)) = (&self.scrobbler, track) {
+                scrobbler.now_playing(track);
+            }
+        }
+    }
+    
+    /// Hand the session the tracker just closed to the scrobbler, which decides if it counts
+    #[cfg(feature = "scrobble")]
+    fn scrobble_finished_session(&mut self) {
+        let Some(session) = self.behavior_tracker.take_finished_session() else {
+            return;
+        };
+        let track = self.tracks.iter().find(|t| t.id == session.track_id);
+        if let (Some(scrobbler), Some(track)) = (&self.scrobbler, track) {
+            scrobbler.session_finished(track, &session);
+        }
+    }
+    
+    /// The playing track is over: stop if asked to, otherwise move on
+    async fn finish_track(&mut self, track: panpipe::Track) -> Result<()> {
+        // Heard to the end: next time it starts from the beginning
+        if let Some(spoken) = self.spoken_word.take() {
+            if let Err(e) = self.behavior_tracker.clear_resume_position(spoken.content_hash) {
+                warn!("Failed to clear the resume position: {}", e);
+            }
+            self.show_chapter_list = false;
+        }
+        // Checked before any autoplay, so it also wins over repeat
+        let sleep_ends_here = self.sleep_timer == Some(SleepTimer::EndOfTrack);
+        if self.stop_after_current || sleep_ends_here {
+            self.record_playback(PlaybackEvent::TrackCompleted {
+                track_id: track.id,
+                timestamp: chrono::Utc::now(),
+            }).await;
+            if sleep_ends_here {
+                self.sleep_stop()?;
+            } else {
+                self.stop_after_track()?;
+            }
+        } else if self.gapless_next.is_some() {
+            // The queued track is already playing - just record this one as completed
+            self.record_playback(PlaybackEvent::TrackCompleted {
+                track_id: track.id,
+                timestamp: chrono::Utc::now(),
+            }).await;
+        } else {
+            self.handle_track_finished(track).await;
+        }
         Ok(())
     }
+    
+    /// The sink played the current track to its end: record it and autoplay the next one
+    async fn handle_track_finished(&mut self, track: panpipe::Track) {
+        debug!("🎵 Track finished: {}", self.format_track_title(&track));
+        
+        self.record_playback(PlaybackEvent::TrackCompleted {
+            track_id: track.id,
+            timestamp: chrono::Utc::now(),
+        }).await;
+        
+        let context = match self.playback_context {
+            PlaybackContext::Library { .. } => "library",
+            PlaybackContext::Playlist { .. } => "playlist",
+            PlaybackContext::Album { .. } => "album",
+            PlaybackContext::Similar { .. } => "suggestions",
+            PlaybackContext::Queue => "queue",
+        };
+        
+        let Some((position, track_idx)) = self.autoplay_track() else {
+            self.is_playing = false;
+            self.current_track_index = None;
+            self.set_status(&format!("⏹️ Playback stopped - end of {}", context));
+            return;
+        };
+        self.select_in_playing_list(position);
+        match self.play_track(track_idx).await {
+            Ok(()) => {
+                debug!("🎵 Autoplay: Successfully started next track in {}", context);
+            }
+            Err(e) => {
+                debug!("❌ Autoplay failed in {}: {}", context, e);
+                self.is_playing = false;
+                self.current_track_index = None;
+                self.set_status(&format!("⏹️ Playback stopped - end of {}", context));
+            }
+        }
+    }
 
     /// Format track title for display in status messages
     fn format_track_title(&self, track: &panpipe::Track) -> String {
@@ -2651,11 +8464,72 @@ impl InteractiveApp {
     }
 }
 
+/// Keeps the desktop's MPRIS view in step with the player, sending only what changed
+#[cfg(feature = "mpris")]
+struct MprisBridge {
+    handle: MprisHandle,
+    commands: mpsc::UnboundedReceiver<MprisCommand>,
+    track_id: Option<uuid::Uuid>,
+    state: Option<PlaybackState>,
+    volume: Option<f32>,
+    position: Duration,
+    synced_at: Instant,
+}
+
+#[cfg(feature = "mpris")]
+impl MprisBridge {
+    fn new(handle: MprisHandle, commands: mpsc::UnboundedReceiver<MprisCommand>) -> Self {
+        Self {
+            handle,
+            commands,
+            track_id: None,
+            state: None,
+            volume: None,
+            position: Duration::ZERO,
+            synced_at: Instant::now(),
+        }
+    }
+    
+    async fn sync(&mut self, track: Option<&panpipe::Track>, state: PlaybackState, volume: f32, position: Duration) -> Result<()> {
+        let track_id = track.map(|t| t.id);
+        let track_changed = track_id != self.track_id;
+        if track_changed {
+            self.handle.set_track(track).await?;
+            self.track_id = track_id;
+        }
+        
+        // Anything other than steady forward progress counts as a seek
+        let expected = if self.state == Some(PlaybackState::Playing) {
+            self.position + self.synced_at.elapsed()
+        } else {
+            self.position
+        };
+        let drift = position.abs_diff(expected);
+        let seeked = !track_changed && drift > Duration::from_secs(1);
+        self.handle.set_position(position, seeked).await?;
+        self.position = position;
+        self.synced_at = Instant::now();
+        
+        if self.state.as_ref() != Some(&state) {
+            self.handle.set_playback_state(&state).await?;
+            self.state = Some(state);
+        }
+        
+        if self.volume != Some(volume) {
+            self.handle.set_volume(volume).await?;
+            self.volume = Some(volume);
+        }
+        
+        Ok(())
+    }
+}
+
 // Define AppEvent enum for the interactive client
 #[derive(Debug, Clone)]
 enum InteractiveEvent {
     Quit,
     Tick,
+    LibraryChanged(Vec<PathBuf>), // Debounced batch of paths from the library watcher
     Play,
     TogglePlayPause,
     NextTrack,
@@ -2665,6 +8539,7 @@ enum InteractiveEvent {
     Down,
     VolumeUp,
     VolumeDown,
+    ToggleMute,       // 'm': silence playback, again to restore the previous volume
     ToggleRepeat,
     ToggleShuffle,
     // Tab navigation
@@ -2672,17 +8547,21 @@ enum InteractiveEvent {
     SwitchToPlaylists,
     SwitchToMetadataEditor,
     SwitchToSettings,
+    SwitchToStats,
     // Metadata editor events
     EditTitle,
     EditArtist,
     SaveMetadata,
-    CancelEdit,
+    Escape, // leaves the innermost ModeLayer
     ApplySuggestion,
     #[allow(dead_code)] // Used in metadata editor event handling (line 516)
     ResetToOriginal,
     BulkApplySuggestions,
     ClearMetadata,
-    // Visualizer events removed
+    ResetTrackBehavior, // Library tab: forget the selected track's plays/skips/tags
+    ResetAllBehavior,   // Settings tab: forget behavior for every track
+    PlayAlbum,          // Library tab: the selected track's album, from that track on
+    ReloadTrack,        // Library tab: re-read the selected file after an outside tag edit
     // UI events
     ShowHelp,
     Input(char),
@@ -2692,14 +8571,105 @@ enum InteractiveEvent {
     ExitSearch,
     SearchInput(char),
     SearchBackspace,
+    CycleSearchScope, // Tab: All → Title → Artist → Album (Playlists tab: names ↔ names + tracks)
     // Playlist events
 
     DeletePlaylist,
     RenamePlaylist,
+    GenerateSmartMix,
+    ExportListeningHistory,
     AddToPlaylist,
     RemoveFromPlaylist,
     LoadPlaylist,
     TogglePlaylistExpansion, // New: Toggle expand/collapse playlist in tree view
+    CycleTheme,    // Settings tab: switch to the next color theme
+    ToggleScanReport, // Settings tab: duplicates/unreadable files overlay
+    FindDuplicates,   // Settings tab: open the duplicate review overlay
+    ExportPlaylists,  // Settings tab: write every playlist out as M3U
+    CloseDuplicates,
+    StepDuplicateGroup(i32), // duplicate overlay: previous/next group
+    StepDuplicateKeep(i32),  // duplicate overlay: which copy stays
+    ToggleDuplicatesDryRun,
+    ResolveDuplicates(DuplicateAction), // duplicate overlay: hide or delete every copy but the kept one
+    PreviewRenames,            // Metadata tab: open the rename preview for every file
+    CloseRenamePreview,
+    ScrollRenamePreview(i32),
+    ToggleRenameDryRun,
+    ApplyRenames,              // rename preview: log the plan, or ask before renaming
+    LookupMetadata,            // Metadata tab: ask MusicBrainz about the selected track
+    #[cfg(feature = "musicbrainz")]
+    MetadataMatchesFound(PathBuf, Result<Vec<MetadataCandidate>, String>), // a lookup finished
+    CloseMetadataMatches,
+    #[cfg_attr(not(feature = "musicbrainz"), allow(dead_code))] // the overlay never opens without the feature
+    StepMetadataMatch(i32),    // MusicBrainz matches: previous/next candidate
+    ApplyMetadataMatch,
+    CycleChannelMode, // Settings tab: stereo, mono or crossfeed
+    ToggleVisualizer, // Settings tab: spectrum pane above the player controls
+    CycleEqPreset, // Settings tab: Flat/Bass Boost/Vocal/Treble/Custom
+    SelectEqBand(i32), // Settings tab: move the band cursor left/right
+    AdjustEqGain(f32), // Settings tab: dB change for the selected band
+    OpenDevicePicker, // Settings tab: choose the audio output device
+    GotoPrefix,       // 'g' - a second one jumps to the top of the list
+    JumpToBottom,     // 'G' / End
+    HalfPage(i32),    // Ctrl+D / Ctrl+U, sign gives the direction
+    Page(i32),        // PageDown / PageUp
+    JumpToTop,        // Home
+    TypeJump(char),   // Library tab: jump to the next artist/title starting with what's typed
+    CycleSort,        // Library tab: unsorted/artist/title/album/duration/plays
+    CycleSleepTimer,  // Shift+T: next duration, then end of track, then off
+    CancelSleepTimer, // '0'
+    ToggleStopAfterCurrent, // Shift+S
+    ResumeLastTrack,  // Shift+R while a resume is on offer
+    SetLoopStart,     // '[' outside Settings
+    SetLoopEnd,       // ']' outside Settings
+    ClearLoop,        // '\'
+    ChangeSpeed(f32), // '<' / '>': slower / faster by the given step
+    ResetSpeed,       // '|': back to normal speed
+    ToggleRemainingTime, // ctrl+t: elapsed / -remaining instead of elapsed / total
+    ToggleLyrics,        // lyrics pane beside the current tab
+    #[cfg(feature = "lyrics")]
+    LyricsLoaded(PathBuf, Option<Lyrics>), // a background fetch finished, `None` if nothing was found
+    #[cfg(feature = "silence")]
+    SilenceDetected(u64, Result<SilenceBounds, String>), // content hash and what decoding it found
+    SeekToFraction(f64), // click on the progress bar, 0.0 is the start of the track
+    AddBookmark,      // Shift+B: bookmark the current position
+    OpenBookmarkPicker, // apostrophe: pick a bookmark to jump to
+    JumpToBookmark,
+    DeleteBookmark,
+    CloseBookmarkPicker,
+    OpenChapterList,    // 'K': chapters of the spoken-word file playing
+    StepChapter(isize), // '.' / ',': next chapter, or back to the start of this one (or the one before)
+    JumpToChapter,      // Enter in the chapter list
+    CloseChapterList,
+    OpenHistory,     // 'H': recently played tracks
+    PlayFromHistory, // Enter in the history overlay
+    CloseHistory,
+    OpenErrorLog,    // '!': recent warnings and errors, ALSA underruns included
+    RevealFile,      // Ctrl+O: the selected or playing track's folder in the file manager
+    SaveAll,         // 'W': edited tags into their files, plus playlists that failed to save
+    QuitNow,         // SIGINT/SIGTERM: quit without asking about unsaved changes
+    TaskProgress { done: usize, total: usize },        // a background task got further
+    SuggestionsParsed(Vec<(PathBuf, ParsedMetadata)>), // bulk apply: the confident suggestions from one batch
+    TaskFinished(TaskResult),                          // a background task stopped, done or cancelled
+    CancelTask,                                        // Esc in the progress overlay
+    CloseErrorLog,
+    ToggleShuffleExclusion, // Library tab 'E': keep the selected track out of shuffle, or let it back in
+    OpenExcludedTracks,     // Settings tab 'E': tracks kept out of shuffle
+    IncludeInShuffle,       // Enter in the excluded tracks overlay
+    CloseExcludedTracks,
+    ShowBehaviorDetail,  // Library tab 'i': plays, skips and shuffle weight of the selected track
+    CloseBehaviorDetail,
+    OpenMoreLikeThis,    // Library tab 'L': tracks like the selected one
+    PlayMoreLikeThis,    // Enter in More like this: the highlighted one, then the rest below it
+    QueueMoreLikeThis,   // 'a' in More like this: all of them after the track playing
+    CloseMoreLikeThis,
+    ToggleSortDirection,
+    SelectOutputDevice,
+    CancelDevicePicker,
+    MoveTrackUp,   // Shift+Up: reorder within expanded playlist
+    MoveTrackDown, // Shift+Down: reorder within expanded playlist
+    CyclePlaylistShuffle, // Playlists tab 'Z': the playlist's own shuffle
+    CyclePlaylistRepeat,  // Playlists tab Ctrl+R: the playlist's own repeat
     PlaylistInput(char),
     PlaylistBackspace,
     ConfirmPlaylistCreation,
@@ -2707,15 +8677,28 @@ enum InteractiveEvent {
     // Playlist selector overlay events
     SelectPlaylistFromSelector,
     CancelPlaylistSelector,
+    CycleLibraryView, // Library tab: flat list, artist tree, album tree
+    CycleTagFilter,   // 'f' in Library: favorites, never played, then each behavior tag
+    ClearTagFilter,   // 'F' in Library
+    // Library multi-select
+    ToggleMark,      // 'v': mark the row and step down
+    MarkAllFiltered, // 'V': mark every row in the current view, or clear when all are marked
+    // Confirmation overlay events
+    AcceptConfirm,  // y / Enter
+    DeclineConfirm, // n
+    DismissConfirm, // Esc: same as n, except when quitting with unsaved changes
 }
 
 /// Redirect stderr to /dev/null to suppress ALSA error messages that interfere with TUI
+/// The real stderr, saved while it points at /dev/null so a panic can still be reported
+static ORIGINAL_STDERR: std::sync::OnceLock<libc::c_int> = std::sync::OnceLock::new();
+
 fn redirect_stderr_to_null() -> Result<()> {
     
     unsafe {
         // Open /dev/null for writing
         let null_fd = libc::open(
-            b"/dev/null\0".as_ptr() as *const libc::c_char,
+            c"/dev/null".as_ptr(),
             libc::O_WRONLY,
         );
         
@@ -2738,7 +8721,47 @@ fn redirect_stderr_to_null() -> Result<()> {
         }
         
         libc::close(null_fd);
+        let _ = ORIGINAL_STDERR.set(stderr_backup);
     }
     
     Ok(())
 }
+
+/// Restore the terminal (and stderr, if it was silenced) before the panic message prints
+fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        restore_terminal();
+        if let Some(&fd) = ORIGINAL_STDERR.get() {
+            unsafe {
+                libc::dup2(fd, libc::STDERR_FILENO);
+            }
+        }
+        error!("Panic: {}", info);
+        default_hook(info);
+    }));
+}
+
+/// Turn SIGINT/SIGTERM into a normal quit, so the session is saved and the terminal restored
+///
+/// Raw mode delivers Ctrl+C as a key, so SIGINT only arrives from `kill -INT` and the like.
+fn spawn_signal_listener(event_tx: mpsc::UnboundedSender<InteractiveEvent>) {
+    use tokio::signal::unix::{signal, SignalKind};
+    
+    tokio::spawn(async move {
+        let (mut interrupt, mut terminate) = match (signal(SignalKind::interrupt()), signal(SignalKind::terminate())) {
+            (Ok(interrupt), Ok(terminate)) => (interrupt, terminate),
+            (Err(e), _) | (_, Err(e)) => {
+                warn!("Could not install signal handlers: {}", e);
+                return;
+            }
+        };
+        
+        tokio::select! {
+            _ = interrupt.recv() => info!("SIGINT received, quitting"),
+            _ = terminate.recv() => info!("SIGTERM received, quitting"),
+        }
+        // No one is there to answer the unsaved-changes prompt
+        let _ = event_tx.send(InteractiveEvent::QuitNow);
+    });
+}
@@ -0,0 +1,219 @@
+// Dry-run scan: what the library scan makes of every file, and why some never show up or play
+// Reads files fresh (no scan cache) and never touches the library, the caches or the behavior database
+
+use clap::Parser;
+use panpipe::audio::scanner::{scan_roots, FileDiagnosis};
+use panpipe::audio::{metadata_parser::MetadataParser, AudioFormat, MusicScanner};
+use panpipe::config::Config;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+#[derive(Parser)]
+#[command(name = "scan_report")]
+#[command(about = "Show what a library scan makes of each file, without touching the library")]
+struct Args {
+    /// Directories to scan instead of the configured music directories
+    directories: Vec<PathBuf>,
+
+    /// Decode each file's headers for its duration, like a scan built with the probe feature
+    #[arg(long)]
+    probe: bool,
+
+    /// Only list files that were skipped or had problems
+    #[arg(long)]
+    problems_only: bool,
+
+    /// Config file to take the directories, extensions and patterns from
+    #[arg(long = "config", value_name = "PATH")]
+    config_path: Option<PathBuf>,
+}
+
+/// Running totals for the summary at the end
+#[derive(Default)]
+struct Summary {
+    files: usize,
+    scanned: usize,
+    tagged: usize,
+    untagged: usize,
+    with_problems: usize,
+    unreadable: usize,
+    without_hash: usize,
+    without_duration: usize,
+    skipped: BTreeMap<&'static str, usize>,
+    unsupported_extensions: BTreeMap<String, usize>,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    if let Some(path) = args.config_path {
+        Config::set_path_override(path)?;
+    }
+    let config = Config::load()?;
+
+    let directories = if args.directories.is_empty() { config.music_directories.clone() } else { args.directories };
+    let scanner = config.library.build_scanner()?;
+    // Same naming conventions as the app, so the report shows what it would read
+    let mut parser = MetadataParser::new();
+    let config_errors = parser.apply_config(&config.metadata);
+    for error in &config_errors {
+        println!("⚠️ Ignoring metadata config entry: {}", error);
+    }
+    if !config_errors.is_empty() {
+        println!();
+    }
+
+    if args.probe && cfg!(not(feature = "probe")) {
+        println!("⚠️ Built without the probe feature, so --probe has nothing to decode with");
+        println!("   (cargo run --features probe --bin scan_report)");
+        println!();
+    }
+
+    let mut summary = Summary::default();
+    for root in scan_roots(&directories) {
+        println!("📁 {}", root.display());
+        if !root.is_dir() {
+            println!("   ❌ Not a directory, or missing");
+            println!();
+            continue;
+        }
+
+        for diagnosis in scanner.diagnose_directory(&root) {
+            summary.count(&diagnosis);
+            let has_problem = diagnosis.skipped.is_some() || diagnosis.track.is_none() || !diagnosis.problems.is_empty();
+            if args.problems_only && !has_problem {
+                continue;
+            }
+            print_diagnosis(&root, &diagnosis, &parser, &scanner, args.probe);
+        }
+        println!();
+    }
+
+    summary.print();
+    Ok(())
+}
+
+fn print_diagnosis(root: &Path, diagnosis: &FileDiagnosis, parser: &MetadataParser, scanner: &MusicScanner, probe: bool) {
+    println!("📄 {}", diagnosis.path.strip_prefix(root).unwrap_or(&diagnosis.path).display());
+    let format = match diagnosis.format {
+        AudioFormat::Unknown => "unknown".to_string(),
+        ref format => format.name().to_string(),
+    };
+
+    if let Some(reason) = diagnosis.skipped {
+        println!("   format: {} | skipped: {}", format, reason);
+        return;
+    }
+    let Some(track) = &diagnosis.track else {
+        println!("   format: {} | ❌ unreadable: {}", format, diagnosis.problems.join("; "));
+        return;
+    };
+
+    let tags = if !diagnosis.problems.is_empty() {
+        "⚠️ problems"
+    } else if track.metadata.title.is_some() || track.metadata.artist.is_some() {
+        "read"
+    } else {
+        "none"
+    };
+    let hash = track.content_hash.map_or_else(|| "none".to_string(), |hash| format!("{:016x}", hash));
+    println!("   format: {} | tags: {} | hash: {}", format, tags, hash);
+    for problem in &diagnosis.problems {
+        println!("   ⚠️ {}", problem);
+    }
+
+    println!(
+        "   tagged: {} - {}",
+        track.metadata.title.as_deref().unwrap_or("(no title)"),
+        track.metadata.artist.as_deref().unwrap_or("(no artist)"),
+    );
+    let file_name = diagnosis.path.file_name().unwrap_or_default().to_string_lossy();
+    let parsed = parser.parse_filename(&file_name);
+    println!(
+        "   parsed: {} - {} ({:.0}%, {})",
+        parsed.suggested_title,
+        parsed.suggested_artist,
+        parsed.confidence * 100.0,
+        parsed.pattern_used,
+    );
+
+    let mut duration = format!("   duration: {}", track.duration.map_or_else(|| "unknown".to_string(), format_duration));
+    if probe {
+        duration.push_str(&format!(" | probed: {}", probe_duration(scanner, &diagnosis.path)));
+    }
+    println!("{}", duration);
+}
+
+#[cfg(feature = "probe")]
+fn probe_duration(scanner: &MusicScanner, path: &Path) -> String {
+    match scanner.probe_duration_with_symphonia(path) {
+        Ok(duration) => format_duration(duration),
+        Err(e) => format!("failed ({})", e),
+    }
+}
+
+#[cfg(not(feature = "probe"))]
+fn probe_duration(_scanner: &MusicScanner, _path: &Path) -> String {
+    "n/a".to_string()
+}
+
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+impl Summary {
+    fn count(&mut self, diagnosis: &FileDiagnosis) {
+        self.files += 1;
+        if let Some(reason) = diagnosis.skipped {
+            *self.skipped.entry(reason).or_default() += 1;
+            if !diagnosis.format.is_supported() {
+                let extension = diagnosis.path.extension()
+                    .map_or_else(|| "(none)".to_string(), |ext| ext.to_string_lossy().to_lowercase());
+                *self.unsupported_extensions.entry(extension).or_default() += 1;
+            }
+            return;
+        }
+        let Some(track) = &diagnosis.track else {
+            self.unreadable += 1;
+            return;
+        };
+
+        self.scanned += 1;
+        if !diagnosis.problems.is_empty() {
+            self.with_problems += 1;
+        } else if track.metadata.title.is_some() || track.metadata.artist.is_some() {
+            self.tagged += 1;
+        } else {
+            self.untagged += 1;
+        }
+        if track.content_hash.is_none() {
+            self.without_hash += 1;
+        }
+        if track.duration.is_none() {
+            self.without_duration += 1;
+        }
+    }
+
+    fn print(&self) {
+        println!("📈 Summary");
+        println!("  📄 Files seen: {}", self.files);
+        println!("  ✅ Would be in the library: {}", self.scanned);
+        println!("     🏷️ with tags: {}, without: {}, with tag problems: {}", self.tagged, self.untagged, self.with_problems);
+        println!("     ⏱️ without a duration: {}", self.without_duration);
+        println!("     🔢 without a content hash: {}", self.without_hash);
+        println!("  ❌ Unreadable: {}", self.unreadable);
+
+        let skipped: usize = self.skipped.values().sum();
+        println!("  ⏭️ Skipped: {}", skipped);
+        for (reason, count) in &self.skipped {
+            println!("     {}: {}", reason, count);
+        }
+        if !self.unsupported_extensions.is_empty() {
+            let extensions: Vec<String> = self.unsupported_extensions.iter()
+                .map(|(extension, count)| format!(".{} ({})", extension, count))
+                .collect();
+            println!("  🚫 Unsupported formats: {}", extensions.join(", "));
+        }
+    }
+}
@@ -44,7 +44,7 @@ fn main() -> anyhow::Result<()> {
         }
         
         hash_groups.entry(track.content_hash)
-            .or_insert_with(Vec::new)
+            .or_default()
             .push(track);
     }
     
@@ -88,13 +88,13 @@ fn main() -> anyhow::Result<()> {
     let original = tracks.iter().find(|t| {
         t.file_path.file_name()
             .and_then(|n| n.to_str())
-            .map_or(false, |n| n == original_name)
+            .is_some_and(|n| n == original_name)
     });
     
     let duplicate = tracks.iter().find(|t| {
         t.file_path.file_name()
             .and_then(|n| n.to_str())
-            .map_or(false, |n| n == duplicate_name)
+            .is_some_and(|n| n == duplicate_name)
     });
     
     match (original, duplicate) {
@@ -2,39 +2,297 @@
 // Keeps all the settings in one place so nothing gets lost
 // Handles loading/saving settings, with sensible defaults when config is missing
 
+use crate::audio::channel_mix::ChannelMode;
+use crate::audio::equalizer::{self, EqPreset, BAND_COUNT};
+use crate::audio::scanner::DEFAULT_EXTENSIONS;
+use crate::audio::player::clamp_speed;
+use crate::audio::MusicScanner;
+use crate::ui::keybindings::Keymap;
 use anyhow::Result;
 use dirs::config_dir;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
+pub mod session;
+
+pub use session::{ResumePoint, SessionState};
+
+/// Schema version written to config.toml; files without one are version 0
+pub const CONFIG_VERSION: u32 = 1;
+
+/// Environment variable naming the config file, used when no `--config` is given
+pub const CONFIG_ENV_VAR: &str = "BANGTUNES_CONFIG";
+
+static CONFIG_PATH_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+/// Missing sections and keys fall back to their defaults, so older files still load
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
+    #[serde(default)]
+    pub version: u32, // 0 when absent, unlike Config::default()
     pub music_directories: Vec<PathBuf>,
     pub database_path: PathBuf,
     pub spotify: SpotifyConfig,
     pub behavior: BehaviorConfig,
     pub ui: UiConfig,
+    pub audio: AudioSettings,
+    pub library: LibraryConfig,
+    pub metadata: MetadataConfig,
+    pub lastfm: LastfmConfig,
+    pub musicbrainz: MusicBrainzConfig,
+    pub control: ControlConfig,
+    pub keybindings: BTreeMap<String, Vec<String>>, // action name → keys, replacing that action's defaults
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct SpotifyConfig {
     pub client_id: Option<String>,
     pub redirect_uri: String,
 }
 
+impl Default for SpotifyConfig {
+    fn default() -> Self {
+        Self {
+            client_id: None,
+            redirect_uri: "http://localhost:8888/callback".to_string(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct BehaviorConfig {
-    pub skip_threshold_seconds: u64,
-    pub weight_decay_days: u64,
-    pub min_play_time_for_tracking: u64,
+    pub skip_threshold_seconds: u64, // skips sooner than this count against a track harder than late ones
+    pub weight_decay_days: u64,      // left unplayed longer than this, a track's shuffle weight climbs back
+    pub min_play_time_for_tracking: u64, // shorter listens, skips included, aren't recorded at all
+    pub smart_mix_size: usize, // tracks in a generated Smart Mix playlist
+    pub session_coherence: f64, // 0.0 (off) to 1.0: how strongly Smart Mix follows genre/year from track to track
+    pub time_of_day_weighting: bool, // favor tracks usually played around the current hour
+}
+
+impl Default for BehaviorConfig {
+    fn default() -> Self {
+        Self {
+            skip_threshold_seconds: 30,
+            weight_decay_days: 30,
+            min_play_time_for_tracking: 10,
+            smart_mix_size: 50,
+            session_coherence: 0.0,
+            time_of_day_weighting: false,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct UiConfig {
     pub show_notifications: bool,
     pub notification_duration_ms: u64,
     pub theme: String,
+    pub auto_resume: bool, // resume the last track on startup instead of offering to
+    pub autoplay_on_start: bool, // start playing on launch: the resumable track if any, else the first in the library
+    pub continue_last_playlist: bool, // start the playlist that was playing at the last quit, where it stopped if known
+    pub visualizer: bool, // spectrum bars above the player controls; off costs nothing
+    pub show_remaining_time: bool, // progress bar shows "-3:12" left instead of the track length
+    pub show_lyrics: bool, // lyrics pane beside the current tab, in builds with the `lyrics` feature
+    pub min_width: u16,  // below this many columns or rows the player shows
+    pub min_height: u16, // a "window too small" notice instead of its layout
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        Self {
+            show_notifications: true,
+            notification_duration_ms: 3000,
+            theme: "default".to_string(),
+            auto_resume: false,
+            autoplay_on_start: false,
+            continue_last_playlist: false,
+            visualizer: false,
+            show_remaining_time: false,
+            show_lyrics: false,
+            min_width: 40,
+            min_height: 16,
+        }
+    }
+}
+
+/// `[library]` section - how the music directories are scanned and kept up to date
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LibraryConfig {
+    pub supported_extensions: Vec<String>, // without the dot, e.g. "opus"
+    pub exclude_patterns: Vec<String>,     // globs relative to each music directory, e.g. "**/Podcasts/**"
+    pub include_patterns: Vec<String>,     // globs that win over exclude_patterns
+    pub watch_for_changes: bool, // off by default, watching huge trees isn't free
+    pub watch_debounce_ms: u64,
+    pub recently_added_days: u64, // cutoff for the "recently added" sort, 0 shows everything
+    pub hidden_paths: Vec<PathBuf>, // duplicate copies hidden from the library, still on disk
+    pub rename_pattern: String, // file names for "rename files", see Track::suggested_filename
+    pub audiobook_directories: Vec<PathBuf>, // spoken word: played in order, without fades, each file resumed where it was left
+}
+
+impl LibraryConfig {
+    /// Hidden from the library by the Find Duplicates overlay
+    pub fn is_hidden(&self, path: &Path) -> bool {
+        self.hidden_paths.iter().any(|hidden| hidden == path)
+    }
+    
+    /// Under one of the `audiobook_directories`
+    pub fn is_spoken_word(&self, path: &Path) -> bool {
+        self.audiobook_directories.iter().any(|dir| path.starts_with(dir))
+    }
+    
+    /// Scanner honoring the configured extensions and include/exclude patterns
+    pub fn build_scanner(&self) -> Result<MusicScanner> {
+        MusicScanner::with_extensions(self.supported_extensions.clone())
+            .with_patterns(&self.include_patterns, &self.exclude_patterns)
+    }
+}
+
+impl Default for LibraryConfig {
+    fn default() -> Self {
+        Self {
+            supported_extensions: DEFAULT_EXTENSIONS.iter().map(|ext| ext.to_string()).collect(),
+            exclude_patterns: Vec::new(),
+            include_patterns: Vec::new(),
+            watch_for_changes: false,
+            watch_debounce_ms: 1500,
+            recently_added_days: 30,
+            hidden_paths: Vec::new(),
+            rename_pattern: "{track:02} - {artist} - {title}.{ext}".to_string(),
+            audiobook_directories: Vec::new(),
+        }
+    }
+}
+
+/// `[lastfm]` section - scrobbling, for builds with the `scrobble` feature
+///
+/// The API key and secret come from an API account at last.fm/api; `lastfm-auth` fills in the rest.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LastfmConfig {
+    pub enabled: bool,
+    pub api_key: Option<String>,
+    pub api_secret: Option<String>,
+    pub session_key: Option<String>, // from the web auth flow, doesn't expire
+    pub username: Option<String>,
+}
+
+/// `[musicbrainz]` section - tag lookups in the Metadata Editor, for builds with the `musicbrainz` feature
+///
+/// Fingerprint matches need Chromaprint's `fpcalc` and an AcoustID application key from acoustid.org;
+/// without them lookups search MusicBrainz by title and artist.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MusicBrainzConfig {
+    pub acoustid_api_key: Option<String>,
+    pub fpcalc_path: Option<PathBuf>, // `fpcalc` on the PATH when unset
+}
+
+/// `[control]` section - a unix socket scripts can send JSON commands to, see `panpipe::control`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ControlConfig {
+    pub socket_path: Option<PathBuf>, // unset leaves the socket off
+}
+
+/// `[metadata]` section - teach the filename parser your library's naming conventions
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MetadataConfig {
+    pub custom_delimiters: Vec<CustomDelimiterConfig>,
+    pub custom_patterns: Vec<CustomPatternConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomDelimiterConfig {
+    pub delimiter: String, // e.g. " — " for "Artist — Title"
+    pub confidence: f32,   // 0.0 to 1.0
+    pub name: String,
+}
+
+/// A regex with a named `title` group and optional `artist` group
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomPatternConfig {
+    pub name: String,
+    pub regex: String,
+    pub confidence: f32, // 0.0 to 1.0
+}
+
+/// `[audio]` section - missing keys fall back to defaults, out-of-range values are clamped on load
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioSettings {
+    pub volume: f32,              // 0.0 to 1.0, saved whenever the user changes it
+    pub crossfade_duration: u64,  // milliseconds, 0 to 10000
+    pub fade_in_duration: u64,    // milliseconds, 0 to 5000 (0 disables the fade)
+    pub fade_out_duration: u64,   // milliseconds, 0 to 5000 (0 disables the fade)
+    pub fade_on_pause: bool,      // a quick fade out on pause and back in on resume, off to cut straight away
+    pub sample_rate: u32,         // Hz, 8000 to 192000
+    pub gapless: bool,            // queue the next track onto the same sink, no fades between tracks
+    pub playback_speed: f32,      // 0.5 to 2.0 at startup; the speed keys only change it until quit
+    pub equalizer: EqualizerConfig,
+    pub channel_mode: ChannelMode, // stereo, mono or crossfeed for headphones
+    pub output_device: Option<String>, // device name from the Settings picker, unset for the system default
+    pub sleep_timer_minutes: Vec<u64>, // durations Shift+T cycles through
+    pub sleep_timer_fade: bool,        // fade out over the last 30 seconds before the sleep timer stops
+    pub skip_silence: bool,            // start after a silent intro, end where a silent outro begins (`silence` feature)
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        Self {
+            volume: 0.7,
+            crossfade_duration: 500,
+            fade_in_duration: 300,
+            fade_out_duration: 200,
+            fade_on_pause: true,
+            sample_rate: 44100,
+            gapless: false,
+            playback_speed: 1.0,
+            equalizer: EqualizerConfig::default(),
+            channel_mode: ChannelMode::Stereo,
+            output_device: None,
+            sleep_timer_minutes: vec![15, 30, 45, 60, 90],
+            sleep_timer_fade: true,
+            skip_silence: false,
+        }
+    }
+}
+
+impl AudioSettings {
+    /// Pull hand-edited values back into their documented ranges
+    pub fn clamp_to_ranges(&mut self) {
+        self.volume = if self.volume.is_finite() { self.volume.clamp(0.0, 1.0) } else { 0.7 };
+        self.crossfade_duration = self.crossfade_duration.min(10_000);
+        self.fade_in_duration = self.fade_in_duration.min(5_000);
+        self.fade_out_duration = self.fade_out_duration.min(5_000);
+        self.sample_rate = self.sample_rate.clamp(8_000, 192_000);
+        self.playback_speed = clamp_speed(self.playback_speed);
+        self.equalizer.custom_gains = self.equalizer.custom_gains.map(equalizer::clamp_gain);
+        self.sleep_timer_minutes.retain(|minutes| *minutes > 0);
+    }
+}
+
+/// `[audio.equalizer]` section - a preset, or per-band gains when the preset is `custom`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EqualizerConfig {
+    pub preset: EqPreset,
+    pub custom_gains: [f32; BAND_COUNT], // dB, -12 to +12, lowest band first
+}
+
+impl EqualizerConfig {
+    /// Gains for the selected preset
+    pub fn gains(&self) -> [f32; BAND_COUNT] {
+        self.preset.gains().unwrap_or(self.custom_gains)
+    }
 }
 
 impl Default for Config {
@@ -44,24 +302,21 @@ impl Default for Config {
             .join("panpipe");
         
         Self {
+            version: CONFIG_VERSION,
             music_directories: vec![
                 dirs::audio_dir().unwrap_or_else(|| PathBuf::from("~/Music")),
             ],
             database_path: config_dir.join("panpipe.db"),
-            spotify: SpotifyConfig {
-                client_id: None,
-                redirect_uri: "http://localhost:8888/callback".to_string(),
-            },
-            behavior: BehaviorConfig {
-                skip_threshold_seconds: 30,
-                weight_decay_days: 30,
-                min_play_time_for_tracking: 10,
-            },
-            ui: UiConfig {
-                show_notifications: true,
-                notification_duration_ms: 3000,
-                theme: "default".to_string(),
-            },
+            spotify: SpotifyConfig::default(),
+            behavior: BehaviorConfig::default(),
+            ui: UiConfig::default(),
+            audio: AudioSettings::default(),
+            library: LibraryConfig::default(),
+            metadata: MetadataConfig::default(),
+            lastfm: LastfmConfig::default(),
+            musicbrainz: MusicBrainzConfig::default(),
+            control: ControlConfig::default(),
+            keybindings: BTreeMap::new(),
         }
     }
 }
@@ -72,7 +327,13 @@ impl Config {
         
         if config_path.exists() {
             let content = fs::read_to_string(&config_path)?;
-            let config: Config = toml::from_str(&content)?;
+            let mut config = Self::parse(&content)?;
+            let old_version = config.version;
+            if config.migrate() {
+                // Rewriting drops comments, so keep the original next to it
+                fs::copy(&config_path, config_path.with_extension(format!("toml.v{}.bak", old_version)))?;
+                config.save()?;
+            }
             Ok(config)
         } else {
             let config = Config::default();
@@ -81,6 +342,27 @@ impl Config {
         }
     }
     
+    /// Read a config.toml's contents, filling anything missing with defaults
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut config: Config = toml::from_str(content)?;
+        config.audio.clamp_to_ranges();
+        config.keymap()?; // a bad binding should stop startup, not silently do nothing
+        Ok(config)
+    }
+    
+    /// Bring a config from an older schema up to `CONFIG_VERSION`
+    ///
+    /// Returns true if anything changed and the file should be rewritten. Configs from a newer
+    /// version are left alone so downgrading doesn't clobber them.
+    pub fn migrate(&mut self) -> bool {
+        if self.version >= CONFIG_VERSION {
+            return false;
+        }
+        // 0 → 1: only adds the version; missing sections were already filled by serde defaults
+        self.version = CONFIG_VERSION;
+        true
+    }
+    
     pub fn save(&self) -> Result<()> {
         let config_path = Self::config_path()?;
         
@@ -94,7 +376,78 @@ impl Config {
         Ok(())
     }
     
-    fn config_path() -> Result<PathBuf> {
+    /// Add a music directory, stored as an absolute path
+    ///
+    /// Returns false if it was already there; fails if `path` isn't an existing directory.
+    pub fn add_music_directory(&mut self, path: &Path) -> Result<bool> {
+        if !path.is_dir() {
+            return Err(anyhow::anyhow!("'{}' is not a directory", path.display()));
+        }
+        let path = fs::canonicalize(path)?;
+        if self.music_directories.contains(&path) {
+            return Ok(false);
+        }
+        self.music_directories.push(path);
+        Ok(true)
+    }
+    
+    /// Remove a music directory, matching it as written or as the absolute path it resolves to
+    ///
+    /// Works for directories that no longer exist; returns false if it wasn't configured.
+    pub fn remove_music_directory(&mut self, path: &Path) -> bool {
+        let resolved = fs::canonicalize(path).ok();
+        let before = self.music_directories.len();
+        self.music_directories.retain(|dir| dir != path && Some(dir) != resolved.as_ref());
+        self.music_directories.len() != before
+    }
+    
+    /// The active keybindings: defaults with the `[keybindings]` overrides applied
+    pub fn keymap(&self) -> Result<Keymap> {
+        Keymap::new(&self.keybindings)
+    }
+    
+    /// Snapshot of the last scanned library, kept next to the behavior database
+    pub fn library_cache_path(&self) -> PathBuf {
+        self.database_path.with_file_name("library_cache.json")
+    }
+    
+    /// Use `path` instead of the default location for every later load and save
+    ///
+    /// Set once at startup (from `--config`); wins over `BANGTUNES_CONFIG`.
+    pub fn set_path_override(path: PathBuf) -> Result<()> {
+        CONFIG_PATH_OVERRIDE.set(path)
+            .map_err(|_| anyhow::anyhow!("The config path was already set"))
+    }
+    
+    /// `--config`, else `BANGTUNES_CONFIG`, else config.toml in the platform config directory
+    /// Where playlists are saved, one JSON file each
+    pub fn playlists_dir(&self) -> PathBuf {
+        PathBuf::from("playlists")
+    }
+    
+    /// Scrobbles waiting to reach last.fm, kept next to the behavior database
+    pub fn scrobble_queue_path(&self) -> PathBuf {
+        self.database_path.with_file_name("scrobble_queue.json")
+    }
+    
+    /// Answers from earlier MusicBrainz lookups, kept next to the behavior database
+    pub fn musicbrainz_cache_path(&self) -> PathBuf {
+        self.database_path.with_file_name("musicbrainz_cache.json")
+    }
+    
+    /// Lyrics downloaded so far, one file per track, kept next to the behavior database
+    pub fn lyrics_cache_dir(&self) -> PathBuf {
+        self.database_path.with_file_name("lyrics")
+    }
+    
+    pub fn config_path() -> Result<PathBuf> {
+        if let Some(path) = CONFIG_PATH_OVERRIDE.get() {
+            return Ok(path.clone());
+        }
+        if let Some(path) = std::env::var_os(CONFIG_ENV_VAR).filter(|path| !path.is_empty()) {
+            return Ok(PathBuf::from(path));
+        }
+        
         let config_dir = config_dir()
             .ok_or_else(|| anyhow::anyhow!("Could not find config directory"))?
             .join("panpipe");
@@ -102,3 +455,82 @@ impl Config {
         Ok(config_dir.join("config.toml"))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_and_remove_music_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config { music_directories: Vec::new(), ..Config::default() };
+
+        assert!(config.add_music_directory(dir.path()).unwrap());
+        assert!(!config.add_music_directory(&dir.path().join(".")).unwrap());
+        assert_eq!(config.music_directories, vec![fs::canonicalize(dir.path()).unwrap()]);
+        assert!(config.add_music_directory(&dir.path().join("missing")).is_err());
+
+        assert!(config.remove_music_directory(dir.path()));
+        assert!(!config.remove_music_directory(dir.path()));
+        assert!(config.music_directories.is_empty());
+    }
+
+    #[test]
+    fn test_v0_config_migrates() {
+        // What config.toml looked like before it had a version, [audio], [library] or [keybindings]
+        let v0 = r#"
+music_directories = ["/home/user/Music"]
+database_path = "/home/user/.config/panpipe/panpipe.db"
+
+[spotify]
+redirect_uri = "http://localhost:8888/callback"
+
+[behavior]
+skip_threshold_seconds = 20
+weight_decay_days = 30
+min_play_time_for_tracking = 10
+
+[ui]
+show_notifications = true
+notification_duration_ms = 3000
+theme = "nord"
+"#;
+        let mut config = Config::parse(v0).unwrap();
+        assert_eq!(config.version, 0);
+        assert_eq!(config.behavior.skip_threshold_seconds, 20);
+        assert_eq!(config.behavior.smart_mix_size, 50);
+        assert_eq!(config.ui.theme, "nord");
+        assert_eq!(config.audio.volume, AudioSettings::default().volume);
+        assert!(config.keybindings.is_empty());
+
+        assert!(config.migrate());
+        assert_eq!(config.version, CONFIG_VERSION);
+
+        // The rewritten file loads as current and doesn't migrate again
+        let mut reloaded = Config::parse(&toml::to_string_pretty(&config).unwrap()).unwrap();
+        assert_eq!(reloaded.version, CONFIG_VERSION);
+        assert_eq!(reloaded.music_directories, vec![PathBuf::from("/home/user/Music")]);
+        assert!(!reloaded.migrate());
+    }
+
+    #[test]
+    fn test_audiobook_directories() {
+        let config = Config::parse("[library]\naudiobook_directories = [\"/home/user/Audiobooks\"]\n").unwrap();
+        assert!(config.library.is_spoken_word(Path::new("/home/user/Audiobooks/Dune/01.m4b")));
+        assert!(!config.library.is_spoken_word(Path::new("/home/user/AudiobooksExtra/x.mp3")));
+        assert!(!config.library.is_spoken_word(Path::new("/home/user/Music/song.mp3")));
+    }
+
+    #[test]
+    fn test_missing_sections_use_defaults() {
+        let config = Config::parse("music_directories = []\n").unwrap();
+        assert_eq!(config.version, 0);
+        assert_eq!(config.behavior.skip_threshold_seconds, 30);
+        assert_eq!(config.ui.theme, "default");
+        assert_eq!(config.spotify.redirect_uri, SpotifyConfig::default().redirect_uri);
+
+        let mut newer = Config::parse(&format!("version = {}\n", CONFIG_VERSION + 1)).unwrap();
+        assert!(!newer.migrate());
+        assert_eq!(newer.version, CONFIG_VERSION + 1);
+    }
+}
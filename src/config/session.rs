@@ -0,0 +1,72 @@
+// Session state - remembers where you left off between launches
+// Lives next to config.toml as session.json, small and disposable
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+use super::Config;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionState {
+    pub last_tab: String,
+    pub selected_track_path: Option<PathBuf>,
+    pub shuffle: bool,
+    pub repeat_mode: String,
+    pub current_playlist_id: Option<String>,
+    pub resume: Option<ResumePoint>, // only written on a clean quit
+}
+
+/// Where playback was when the app last quit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResumePoint {
+    pub track_path: PathBuf,
+    pub position_ms: u64,
+}
+
+impl Default for SessionState {
+    fn default() -> Self {
+        Self {
+            last_tab: "library".to_string(),
+            selected_track_path: None,
+            shuffle: false,
+            repeat_mode: "off".to_string(),
+            current_playlist_id: None,
+            resume: None,
+        }
+    }
+}
+
+impl SessionState {
+    /// Load the saved session, or defaults if there isn't one yet
+    pub fn load() -> Result<Self> {
+        let session_path = Self::session_path()?;
+        
+        if session_path.exists() {
+            let content = fs::read_to_string(&session_path)?;
+            let session: SessionState = serde_json::from_str(&content)?;
+            Ok(session)
+        } else {
+            Ok(SessionState::default())
+        }
+    }
+    
+    pub fn save(&self) -> Result<()> {
+        let session_path = Self::session_path()?;
+        
+        if let Some(parent) = session_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(session_path, content)?;
+        
+        Ok(())
+    }
+    
+    fn session_path() -> Result<PathBuf> {
+        Ok(Config::config_path()?.with_file_name("session.json"))
+    }
+}
@@ -0,0 +1,218 @@
+// Control socket - scripts and status bars drive a running player over a unix domain socket
+// One JSON command per line in, one JSON reply per line out; unix only, elsewhere ControlServer::start fails
+
+use crate::audio::{PlaybackState, Track};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Requests from the socket, for the client to map onto its own actions
+///
+/// Sent as `{"command": "set_volume", "volume": 0.5}`; `status` is answered by the socket itself.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum ControlCommand {
+    Play,
+    Pause,
+    PlayPause,
+    Stop,
+    Next,
+    Prev,
+    SetVolume { volume: f32 }, // 0.0 to 1.0
+    Status,
+}
+
+/// What `status` reports, refreshed by the client as it plays
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ControlStatus {
+    pub state: &'static str, // "playing", "paused" or "stopped"
+    pub track: Option<ControlTrack>,
+    pub position_secs: f64,
+    pub volume: f32, // 0.0 to 1.0
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ControlTrack {
+    pub title: String,
+    pub artist: String,
+    pub album: Option<String>,
+    pub path: PathBuf,
+    pub duration_secs: Option<f64>,
+}
+
+impl ControlStatus {
+    pub fn new(track: Option<&Track>, state: &PlaybackState, position: Duration, volume: f32) -> Self {
+        Self {
+            state: match state {
+                PlaybackState::Playing => "playing",
+                PlaybackState::Paused => "paused",
+                PlaybackState::Stopped => "stopped",
+            },
+            track: track.map(|track| ControlTrack {
+                title: track.display_title(),
+                artist: track.display_artist(),
+                album: track.metadata.album.clone(),
+                path: track.file_path.clone(),
+                duration_secs: track.duration.map(|d| d.as_secs_f64()),
+            }),
+            position_secs: position.as_secs_f64(),
+            volume,
+        }
+    }
+}
+
+/// Answer one line from a client: `status` from the snapshot, anything else queued for the player
+pub fn handle_line(line: &str, status: &Mutex<ControlStatus>, commands: &mpsc::UnboundedSender<ControlCommand>) -> String {
+    let reply = match serde_json::from_str::<ControlCommand>(line) {
+        Ok(ControlCommand::Status) => {
+            let status = status.lock().map(|status| status.clone()).unwrap_or_default();
+            json!({ "ok": true, "status": status })
+        }
+        Ok(ControlCommand::SetVolume { volume }) if !(0.0..=1.0).contains(&volume) => {
+            json!({ "ok": false, "error": "volume must be between 0.0 and 1.0" })
+        }
+        Ok(command) => match commands.send(command) {
+            Ok(()) => json!({ "ok": true }),
+            Err(_) => json!({ "ok": false, "error": "the player is shutting down" }),
+        },
+        Err(e) => json!({ "ok": false, "error": format!("bad command: {}", e) }),
+    };
+    reply.to_string()
+}
+
+/// The listening socket; dropping it removes the socket file
+pub struct ControlServer {
+    path: PathBuf,
+    status: Arc<Mutex<ControlStatus>>,
+}
+
+impl ControlServer {
+    /// Replace the latest status, as `status` will report it
+    pub fn set_status(&self, status: ControlStatus) {
+        if let Ok(mut current) = self.status.lock() {
+            *current = status;
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[cfg(unix)]
+impl ControlServer {
+    /// Listen at `path`, taking over a socket left behind by a player that didn't shut down cleanly
+    ///
+    /// Each client gets its own thread; the accept loop lives until the process exits.
+    pub fn start(path: &Path) -> Result<(Self, mpsc::UnboundedReceiver<ControlCommand>)> {
+        use std::io::{BufRead, BufReader, Write};
+        use std::os::unix::net::{UnixListener, UnixStream};
+
+        if path.exists() {
+            if UnixStream::connect(path).is_ok() {
+                return Err(anyhow::anyhow!("Another player is already listening on {}", path.display()));
+            }
+            std::fs::remove_file(path)?;
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let listener = UnixListener::bind(path)?;
+
+        let (commands, command_rx) = mpsc::unbounded_channel();
+        let status = Arc::new(Mutex::new(ControlStatus::default()));
+        let shared = Arc::clone(&status);
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let status = Arc::clone(&shared);
+                let commands = commands.clone();
+                std::thread::spawn(move || {
+                    let Ok(mut writer) = stream.try_clone() else {
+                        return;
+                    };
+                    for line in BufReader::new(stream).lines().map_while(Result::ok) {
+                        if line.trim().is_empty() {
+                            continue;
+                        }
+                        let reply = handle_line(&line, &status, &commands);
+                        if writeln!(writer, "{}", reply).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+
+        Ok((Self { path: path.to_path_buf(), status }, command_rx))
+    }
+}
+
+#[cfg(not(unix))]
+impl ControlServer {
+    pub fn start(_path: &Path) -> Result<(Self, mpsc::UnboundedReceiver<ControlCommand>)> {
+        Err(anyhow::anyhow!("The control socket needs unix domain sockets"))
+    }
+}
+
+impl Drop for ControlServer {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_handle_line() {
+        let status = Mutex::new(ControlStatus { state: "paused", volume: 0.5, ..Default::default() });
+        let (commands, mut command_rx) = mpsc::unbounded_channel();
+        let reply = |line: &str| serde_json::from_str::<serde_json::Value>(&handle_line(line, &status, &commands)).unwrap();
+
+        assert_eq!(reply(r#"{"command": "next"}"#), json!({ "ok": true }));
+        assert_eq!(reply(r#"{"command": "set_volume", "volume": 0.25}"#), json!({ "ok": true }));
+        assert_eq!(command_rx.try_recv().unwrap(), ControlCommand::Next);
+        assert_eq!(command_rx.try_recv().unwrap(), ControlCommand::SetVolume { volume: 0.25 });
+
+        let status_reply = reply(r#"{"command": "status"}"#);
+        assert_eq!(status_reply["status"]["state"], "paused");
+        assert_eq!(status_reply["status"]["track"], serde_json::Value::Null);
+
+        // Bad input gets an error reply and never reaches the player
+        assert_eq!(reply(r#"{"command": "set_volume", "volume": 3}"#)["ok"], false);
+        assert_eq!(reply(r#"{"command": "rewind"}"#)["ok"], false);
+        assert_eq!(reply("play")["ok"], false);
+        assert!(command_rx.try_recv().is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_socket_round_trip() {
+        use std::io::{BufRead, BufReader, Write};
+        use std::os::unix::net::UnixStream;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("control.sock");
+        let (server, mut command_rx) = ControlServer::start(&path).unwrap();
+        server.set_status(ControlStatus { state: "playing", position_secs: 12.5, volume: 0.8, ..Default::default() });
+        assert!(ControlServer::start(&path).is_err()); // one player per socket
+
+        let mut stream = UnixStream::connect(&path).unwrap();
+        let mut replies = BufReader::new(stream.try_clone().unwrap()).lines();
+        writeln!(stream, r#"{{"command": "pause"}}"#).unwrap();
+        assert_eq!(replies.next().unwrap().unwrap(), r#"{"ok":true}"#);
+        assert_eq!(command_rx.blocking_recv(), Some(ControlCommand::Pause));
+
+        writeln!(stream, r#"{{"command": "status"}}"#).unwrap();
+        let status: serde_json::Value = serde_json::from_str(&replies.next().unwrap().unwrap()).unwrap();
+        assert_eq!(status["status"]["position_secs"], 12.5);
+
+        drop(server);
+        assert!(!path.exists());
+    }
+}
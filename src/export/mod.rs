@@ -1,17 +1,48 @@
-// Export module - placeholder for playlist export functionality
-// This will handle JSON, M3U, and Spotify playlist exports
+// Export module - playlist files for other players
+// M3U, PLS, XSPF and JSON are written out; the Spotify export is still a placeholder
 
 use anyhow::Result;
 use crate::audio::Track;
 use crate::behavior::TrackBehavior;
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
 use uuid::Uuid;
 
+/// How track locations are written into an exported playlist
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PathStyle {
+    #[default]
+    Absolute,
+    Relative, // to the playlist file's folder, so the folder can move together with the music
+}
+
+/// Formats a whole playlist collection can be exported in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExportFormat {
+    #[default]
+    M3u,
+    Json,
+    Pls,
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::M3u => "m3u",
+            ExportFormat::Json => "json",
+            ExportFormat::Pls => "pls",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlaylistExport {
     pub name: String,
     pub tracks: Vec<Uuid>,
+    #[serde(default)]
+    pub track_paths: Vec<PathBuf>, // same order as `tracks`, for players that don't know our ids
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub behavior_data: Option<Vec<TrackBehavior>>,
 }
@@ -23,22 +54,46 @@ impl ExportManager {
         Self
     }
     
+    /// Write the playlist as pretty-printed JSON
     pub async fn export_to_json<P: AsRef<Path>>(
         &self,
-        _playlist: &PlaylistExport,
-        _path: P,
+        playlist: &PlaylistExport,
+        path: P,
     ) -> Result<()> {
-        // TODO: Implement JSON export
-        Ok(())
+        write_playlist(path.as_ref(), &serde_json::to_string_pretty(playlist)?)
     }
     
+    /// Write an extended M3U playlist (`#EXTINF:` line before each path)
     pub async fn export_to_m3u<P: AsRef<Path>>(
         &self,
-        _tracks: &[Track],
-        _path: P,
+        tracks: &[Track],
+        path: P,
+        paths: PathStyle,
     ) -> Result<()> {
-        // TODO: Implement M3U export
-        Ok(())
+        let path = path.as_ref();
+        write_playlist(path, &render_m3u(tracks, &playlist_dir(path)?, paths))
+    }
+    
+    /// Write a PLS playlist (`[playlist]` with `FileN=` / `TitleN=` / `LengthN=` entries)
+    pub async fn export_to_pls<P: AsRef<Path>>(
+        &self,
+        tracks: &[Track],
+        path: P,
+        paths: PathStyle,
+    ) -> Result<()> {
+        let path = path.as_ref();
+        write_playlist(path, &render_pls(tracks, &playlist_dir(path)?, paths))
+    }
+    
+    /// Write an XSPF playlist (XML `<trackList>` with a `<location>` URI per track)
+    pub async fn export_to_xspf<P: AsRef<Path>>(
+        &self,
+        tracks: &[Track],
+        path: P,
+        paths: PathStyle,
+    ) -> Result<()> {
+        let path = path.as_ref();
+        write_playlist(path, &render_xspf(tracks, &playlist_dir(path)?, paths))
     }
     
     pub async fn export_to_spotify(
@@ -56,3 +111,214 @@ impl Default for ExportManager {
         Self::new()
     }
 }
+
+/// The absolute folder a playlist file at `path` lives in, where relative entries start from
+fn playlist_dir(path: &Path) -> Result<PathBuf> {
+    let dir = path.parent().unwrap_or(Path::new(""));
+    Ok(if dir.is_absolute() { dir.to_path_buf() } else { std::env::current_dir()?.join(dir) })
+}
+
+fn write_playlist(path: &Path, content: &str) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, content)?;
+    Ok(())
+}
+
+/// `track` as written in a playlist in `dir`
+///
+/// Relative paths fall back to absolute when there's no common root (another drive on Windows).
+fn entry_path(track: &Track, dir: &Path, paths: PathStyle) -> PathBuf {
+    match paths {
+        PathStyle::Absolute => track.file_path.clone(),
+        PathStyle::Relative => relative_path(&track.file_path, dir).unwrap_or_else(|| track.file_path.clone()),
+    }
+}
+
+/// `path` relative to the directory `base`, e.g. `../Music/a.mp3`; both must be absolute
+fn relative_path(path: &Path, base: &Path) -> Option<PathBuf> {
+    if !path.is_absolute() || !base.is_absolute() {
+        return None;
+    }
+    let path: Vec<Component> = path.components().collect();
+    let base: Vec<Component> = base.components().filter(|c| *c != Component::CurDir).collect();
+    if path.first() != base.first() {
+        return None;
+    }
+    
+    let common = path.iter().zip(&base).take_while(|(a, b)| a == b).count();
+    let mut relative = PathBuf::new();
+    for _ in common..base.len() {
+        relative.push("..");
+    }
+    relative.extend(&path[common..]);
+    Some(relative)
+}
+
+/// "Artist - Title", or just the title when there's no artist tag
+fn entry_title(track: &Track) -> String {
+    match &track.metadata.artist {
+        Some(artist) => format!("{} - {}", artist, track.display_title()),
+        None => track.display_title(),
+    }
+}
+
+fn render_m3u(tracks: &[Track], dir: &Path, paths: PathStyle) -> String {
+    let mut m3u = String::from("#EXTM3U\n");
+    for track in tracks {
+        let length = track.duration_seconds().map(|secs| secs as i64).unwrap_or(-1); // -1 is "unknown"
+        let _ = write!(m3u, "#EXTINF:{},{}\n{}\n", length, entry_title(track), entry_path(track, dir, paths).display());
+    }
+    m3u
+}
+
+fn render_pls(tracks: &[Track], dir: &Path, paths: PathStyle) -> String {
+    let mut pls = format!("[playlist]\nNumberOfEntries={}\n", tracks.len());
+    for (i, track) in tracks.iter().enumerate() {
+        let number = i + 1;
+        let length = track.duration_seconds().map(|secs| secs as i64).unwrap_or(-1); // -1 is "unknown"
+        let _ = write!(
+            pls,
+            "File{n}={}\nTitle{n}={}\nLength{n}={}\n",
+            entry_path(track, dir, paths).display(),
+            entry_title(track),
+            length,
+            n = number,
+        );
+    }
+    pls.push_str("Version=2\n");
+    pls
+}
+
+fn render_xspf(tracks: &[Track], dir: &Path, paths: PathStyle) -> String {
+    let mut xspf = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<playlist version=\"1\" xmlns=\"http://xspf.org/ns/0/\">\n  <trackList>\n");
+    for track in tracks {
+        xspf.push_str("    <track>\n");
+        let _ = writeln!(xspf, "      <location>{}</location>", xml_escape(&location_uri(&entry_path(track, dir, paths))));
+        let _ = writeln!(xspf, "      <title>{}</title>", xml_escape(&track.display_title()));
+        if let Some(artist) = &track.metadata.artist {
+            let _ = writeln!(xspf, "      <creator>{}</creator>", xml_escape(artist));
+        }
+        if let Some(album) = &track.metadata.album {
+            let _ = writeln!(xspf, "      <album>{}</album>", xml_escape(album));
+        }
+        if let Some(duration) = track.duration {
+            let _ = writeln!(xspf, "      <duration>{}</duration>", duration.as_millis()); // milliseconds
+        }
+        xspf.push_str("    </track>\n");
+    }
+    xspf.push_str("  </trackList>\n</playlist>\n");
+    xspf
+}
+
+/// A `file://` URI for absolute paths, a relative URI reference otherwise
+fn location_uri(path: &Path) -> String {
+    let mut uri = if path.is_absolute() { "file://".to_string() } else { String::new() };
+    let text = path.to_string_lossy().replace('\\', "/");
+    if path.is_absolute() && !text.starts_with('/') {
+        uri.push('/'); // C:/Music → file:///C:/Music
+    }
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => uri.push(byte as char),
+            _ => { let _ = write!(uri, "%{:02X}", byte); }
+        }
+    }
+    uri
+}
+
+fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn playlist() -> Vec<Track> {
+        vec![
+            Track::test_fixture("/music/Björk/Jóga.mp3").with_artist("Björk").with_title("Jóga").with_duration_secs(305),
+            Track::test_fixture("/music/Misc/Tom & Jerry.flac").with_title("Tom & Jerry <live>"),
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_pls_export() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("mix.pls");
+        ExportManager::new().export_to_pls(&playlist(), &path, PathStyle::Absolute).await.unwrap();
+
+        let pls = fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = pls.lines().collect();
+        assert_eq!(lines, [
+            "[playlist]",
+            "NumberOfEntries=2",
+            "File1=/music/Björk/Jóga.mp3",
+            "Title1=Björk - Jóga",
+            "Length1=305",
+            "File2=/music/Misc/Tom & Jerry.flac",
+            "Title2=Tom & Jerry <live>",
+            "Length2=-1",
+            "Version=2",
+        ]);
+
+        let relative = render_pls(&playlist(), Path::new("/music/Playlists"), PathStyle::Relative);
+        assert!(relative.contains("File1=../Björk/Jóga.mp3\n"), "{}", relative);
+    }
+
+    #[tokio::test]
+    async fn test_m3u_export() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("mix.m3u");
+        ExportManager::new().export_to_m3u(&playlist(), &path, PathStyle::Absolute).await.unwrap();
+
+        let m3u = fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = m3u.lines().collect();
+        assert_eq!(lines, [
+            "#EXTM3U",
+            "#EXTINF:305,Björk - Jóga",
+            "/music/Björk/Jóga.mp3",
+            "#EXTINF:-1,Tom & Jerry <live>",
+            "/music/Misc/Tom & Jerry.flac",
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_xspf_export() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("mix.xspf");
+        ExportManager::new().export_to_xspf(&playlist(), &path, PathStyle::Absolute).await.unwrap();
+
+        let xspf = fs::read_to_string(&path).unwrap();
+        assert!(xspf.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<playlist version=\"1\" xmlns=\"http://xspf.org/ns/0/\">"));
+        assert_eq!(xspf.matches("<track>").count(), 2);
+        assert_eq!(xspf.matches("</track>").count(), 2);
+        assert!(xspf.contains("<location>file:///music/Bj%C3%B6rk/J%C3%B3ga.mp3</location>"));
+        assert!(xspf.contains("<title>Jóga</title>\n      <creator>Björk</creator>\n      <duration>305000</duration>"));
+        assert!(xspf.contains("<location>file:///music/Misc/Tom%20%26%20Jerry.flac</location>"));
+        assert!(xspf.contains("<title>Tom &amp; Jerry &lt;live&gt;</title>"));
+        assert!(xspf.trim_end().ends_with("</trackList>\n</playlist>"));
+
+        let relative = render_xspf(&playlist(), Path::new("/music/Björk"), PathStyle::Relative);
+        assert!(relative.contains("<location>J%C3%B3ga.mp3</location>"), "{}", relative);
+    }
+
+    #[test]
+    fn test_relative_paths() {
+        assert_eq!(relative_path(Path::new("/a/b/c.mp3"), Path::new("/a/b")), Some(PathBuf::from("c.mp3")));
+        assert_eq!(relative_path(Path::new("/a/b/c.mp3"), Path::new("/a/x/y")), Some(PathBuf::from("../../b/c.mp3")));
+        assert_eq!(relative_path(Path::new("c.mp3"), Path::new("/a")), None);
+    }
+}
@@ -2,9 +2,21 @@
 // Modular design makes it easy to swap out components
 
 pub mod audio;     // handles playback, scanning, metadata
+pub mod backup;    // copies of the behavior database and playlists
 pub mod behavior;  // tracks what you like/skip
 pub mod config;    // settings and preferences
+pub mod control;   // JSON commands over a unix socket, for scripts
 pub mod export;    // playlist export features
+pub mod library;   // the whole engine behind one struct, for other frontends
+#[cfg(feature = "lyrics")]
+pub mod lyrics;    // synced or plain lyrics from lrclib
+#[cfg(feature = "mpris")]
+pub mod mpris;     // media keys + desktop widgets over D-Bus
+#[cfg(feature = "musicbrainz")]
+pub mod musicbrainz; // tag lookups by fingerprint or title/artist
+pub mod reveal;    // show a track's file in the system file manager
+#[cfg(feature = "scrobble")]
+pub mod scrobble;  // last.fm now playing + scrobbles
 pub mod spotify;   // spotify integration (when needed)
 pub mod ui;        // terminal interface
 
@@ -12,3 +24,4 @@ pub mod ui;        // terminal interface
 pub use audio::{AudioPlayer, MusicScanner, Track, TrackMetadata};
 pub use behavior::{BehaviorTracker, TrackBehavior, PlaybackEvent, SkipReason};
 pub use config::Config;
+pub use library::Library;
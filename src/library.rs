@@ -0,0 +1,348 @@
+// Library - the scanner, player, behavior tracker and playlists wired together for embedding
+// A GUI or web frontend drives this instead of redoing what the interactive client does by hand
+
+use crate::audio::player::PlayerEvent;
+use crate::audio::playlist::PlaylistManager;
+use crate::audio::scan_report::ScanReport;
+use crate::audio::scanner::ScanProgress;
+use crate::audio::search::{SearchIndex, SearchScope};
+use crate::audio::{AudioPlayer, Track};
+use crate::behavior::weighting::ShuffleWeighting;
+use crate::behavior::{BehaviorDatabase, BehaviorTracker, PlaybackEvent, SkipReason, TrackBehavior};
+use crate::config::Config;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tracing::warn;
+
+/// Tracks shuffle avoids repeating, counted back from the current one
+const SHUFFLE_MEMORY: usize = 20;
+
+/// The whole engine: a scanned library, playback with behavior tracking, playlists and search
+///
+/// Track indices refer to `tracks()` and stay valid until the next `scan` or `set_tracks`.
+pub struct Library {
+    config: Config,
+    tracks: Vec<Track>,
+    search_index: SearchIndex,
+    player: AudioPlayer,
+    tracker: BehaviorTracker,
+    playlists: PlaylistManager,
+    weighting: ShuffleWeighting,
+    shuffle: bool,
+    history: Vec<usize>, // played track indices, the current one last
+}
+
+impl Library {
+    /// Open the configured database, playlists and output device; the library starts empty until `scan`
+    pub fn open(config: Config) -> Result<Self> {
+        let player = match &config.audio.output_device {
+            Some(device) => AudioPlayer::with_device(device, config.clone().into()).or_else(|e| {
+                warn!("Output device '{}' unavailable, using the default: {}", device, e);
+                AudioPlayer::new(config.clone().into())
+            })?,
+            None => AudioPlayer::new(config.clone().into())?,
+        };
+        let playlists = PlaylistManager::new(config.playlists_dir()).map_err(|e| anyhow::anyhow!("{}", e))?;
+        Self::new(config, player, playlists)
+    }
+
+    /// Assemble from parts built elsewhere, e.g. a player on a `NullBackend`
+    pub fn new(config: Config, player: AudioPlayer, playlists: PlaylistManager) -> Result<Self> {
+        let tracker = BehaviorTracker::new(
+            BehaviorDatabase::new(&config.database_path)?,
+            config.behavior.min_play_time_for_tracking,
+        )
+        .with_skip_threshold(config.behavior.skip_threshold_seconds)
+        .with_decay_days(config.behavior.weight_decay_days);
+        let weighting = ShuffleWeighting::new(config.behavior.weight_decay_days);
+        Ok(Self {
+            config,
+            tracks: Vec::new(),
+            search_index: SearchIndex::build(&[]),
+            player,
+            tracker,
+            playlists,
+            weighting,
+            shuffle: false,
+            history: Vec::new(),
+        })
+    }
+
+    /// Scan the music directories; unchanged files come from the scan cache
+    pub async fn scan(&mut self) -> Result<ScanReport> {
+        let (progress_tx, _) = mpsc::channel(1); // nobody listening, progress is dropped
+        self.scan_with_progress(progress_tx).await
+    }
+
+    /// `scan`, reporting each directory and track to `progress` as it goes
+    pub async fn scan_with_progress(&mut self, progress: mpsc::Sender<ScanProgress>) -> Result<ScanReport> {
+        let cache_db = BehaviorDatabase::new(&self.config.database_path)?;
+        let scan_cache = cache_db.load_scan_cache().unwrap_or_else(|e| {
+            warn!("Could not load scan cache, doing a full scan: {}", e);
+            Default::default()
+        });
+        let scanner = self.config.library.build_scanner()?.with_cache(scan_cache);
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (tracks, mut report) = scanner
+            .scan_directories_incremental(&self.config.music_directories, progress, cancel)
+            .await?;
+        if let Err(e) = cache_db.save_scan_cache(&tracks) {
+            warn!("Failed to update scan cache: {}", e);
+        }
+
+        let before = tracks.len();
+        self.set_tracks(tracks);
+        if self.tracks.len() != before {
+            report.duplicate_groups = ScanReport::build(&self.tracks, Vec::new()).duplicate_groups;
+        }
+        Ok(report)
+    }
+
+    /// Replace the library, e.g. with tracks kept from an earlier run; hidden duplicates are left out
+    ///
+    /// Stops playback, since the old indices no longer mean anything.
+    pub fn set_tracks(&mut self, mut tracks: Vec<Track>) {
+        tracks.retain(|track| !self.config.library.is_hidden(&track.file_path));
+        tracks.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+        if let Err(e) = self.player.stop() {
+            warn!("Failed to stop playback: {}", e);
+        }
+        self.search_index = SearchIndex::build(&tracks);
+        self.tracks = tracks;
+        self.history.clear();
+    }
+
+    pub fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    pub fn current_index(&self) -> Option<usize> {
+        self.history.last().copied()
+    }
+
+    pub fn current(&self) -> Option<&Track> {
+        self.current_index().and_then(|idx| self.tracks.get(idx))
+    }
+
+    /// Start the track at `track_idx`, ending whatever played before as a skip
+    pub async fn play(&mut self, track_idx: usize) -> Result<()> {
+        self.skip_current(SkipReason::UserSkip).await;
+        self.start(track_idx).await
+    }
+
+    /// On to the next track in library order, or a behavior-weighted pick when shuffling
+    ///
+    /// `None` at the end of the library.
+    pub async fn next(&mut self) -> Result<Option<&Track>> {
+        let Some(next) = self.next_index().await? else {
+            return Ok(None);
+        };
+        self.skip_current(SkipReason::NextTrack).await;
+        self.start(next).await?;
+        Ok(self.current())
+    }
+
+    /// Back to the track played before this one, or the one before it in library order
+    pub async fn prev(&mut self) -> Result<Option<&Track>> {
+        let previous = match self.history.len() {
+            0 => return Ok(None),
+            1 => match self.history[0].checked_sub(1) {
+                Some(idx) => idx,
+                None => return Ok(None),
+            },
+            len => self.history[len - 2],
+        };
+        self.skip_current(SkipReason::UserSkip).await;
+        self.history.pop(); // the track being left
+        if self.history.last() == Some(&previous) {
+            self.history.pop(); // start() puts it back
+        }
+        self.start(previous).await?;
+        Ok(self.current())
+    }
+
+    pub async fn pause(&mut self) -> Result<()> {
+        self.player.pause()?;
+        if let Some(track) = self.current() {
+            let event = PlaybackEvent::TrackPaused { track_id: track.id, position: self.position_secs(), timestamp: chrono::Utc::now() };
+            self.record(event).await;
+        }
+        Ok(())
+    }
+
+    pub async fn resume(&mut self) -> Result<()> {
+        self.player.resume()?;
+        if let Some(track) = self.current() {
+            let event = PlaybackEvent::TrackResumed { track_id: track.id, position: self.position_secs(), timestamp: chrono::Utc::now() };
+            self.record(event).await;
+        }
+        Ok(())
+    }
+
+    /// Turn shuffle on or off; returns the new setting
+    pub fn toggle_shuffle(&mut self) -> bool {
+        self.shuffle = !self.shuffle;
+        self.shuffle
+    }
+
+    pub fn is_shuffled(&self) -> bool {
+        self.shuffle
+    }
+
+    /// Fuzzy search over title, artist, album and filename, best match first
+    pub fn search(&self, query: &str) -> Vec<&Track> {
+        self.search_index.search(query, SearchScope::All)
+            .into_iter()
+            .filter_map(|(idx, _)| self.tracks.get(idx))
+            .collect()
+    }
+
+    /// What's been learned about every track that has been played
+    pub async fn behaviors(&self) -> Result<Vec<TrackBehavior>> {
+        self.tracker.get_all_behaviors().await
+    }
+
+    /// Feed the player's events back in; a finished track counts as completed and the next one starts
+    ///
+    /// Give the player a sender with `player_mut().set_event_sender` and pass on what arrives.
+    pub async fn handle_player_event(&mut self, event: &PlayerEvent) -> Result<()> {
+        if let PlayerEvent::TrackFinished(finished) = event {
+            if self.current().is_some_and(|track| track.id == finished.id) {
+                let event = PlaybackEvent::TrackCompleted { track_id: finished.id, timestamp: chrono::Utc::now() };
+                self.record(event).await;
+                if let Some(next) = self.next_index().await? {
+                    self.start(next).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn player(&self) -> &AudioPlayer {
+        &self.player
+    }
+
+    pub fn player_mut(&mut self) -> &mut AudioPlayer {
+        &mut self.player
+    }
+
+    pub fn playlists(&self) -> &PlaylistManager {
+        &self.playlists
+    }
+
+    pub fn playlists_mut(&mut self) -> &mut PlaylistManager {
+        &mut self.playlists
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Stop playback and write out the session still open
+    pub async fn shutdown(mut self) -> Result<()> {
+        self.skip_current(SkipReason::UserSkip).await;
+        self.player.stop()?;
+        self.tracker.finalize().await
+    }
+
+    async fn next_index(&mut self) -> Result<Option<usize>> {
+        // Spoken word always goes on to the next file, and never turns up in a shuffle
+        let spoken_word = |track: &Track| self.config.library.is_spoken_word(&track.file_path);
+        if !self.shuffle || self.current_index().is_some_and(|idx| spoken_word(&self.tracks[idx])) {
+            return Ok(match self.current_index() {
+                Some(idx) => Some(idx + 1).filter(|&next| next < self.tracks.len()),
+                None => (!self.tracks.is_empty()).then_some(0),
+            });
+        }
+
+        let ids: Vec<_> = self.tracks.iter().filter(|track| !spoken_word(track)).map(|track| track.id).collect();
+        let behaviors: HashMap<_, _> = self.tracker.get_all_behaviors().await?
+            .into_iter()
+            .map(|behavior| (behavior.track_id, behavior))
+            .collect();
+        let recent: Vec<_> = self.history.iter().rev().take(SHUFFLE_MEMORY).rev()
+            .filter_map(|&idx| self.tracks.get(idx).map(|track| track.id))
+            .collect();
+        let pick = self.weighting.select_next_track(&ids, &behaviors, &recent);
+        Ok(pick.and_then(|id| self.tracks.iter().position(|track| track.id == id)))
+    }
+
+    async fn start(&mut self, track_idx: usize) -> Result<()> {
+        let track = self.tracks.get(track_idx)
+            .ok_or_else(|| anyhow::anyhow!("No track at index {}", track_idx))?
+            .clone();
+        self.player.set_fades(!self.config.library.is_spoken_word(&track.file_path));
+        self.player.play_track(track.clone())?;
+        self.history.push(track_idx);
+        self.record(PlaybackEvent::TrackStarted { track_id: track.id, timestamp: chrono::Utc::now() }).await;
+        Ok(())
+    }
+
+    /// End the playing track's session as a skip, if one is playing
+    async fn skip_current(&mut self, reason: SkipReason) {
+        let Some(track_id) = self.current().map(|track| track.id) else {
+            return;
+        };
+        if self.player.is_finished() {
+            return;
+        }
+        let event = PlaybackEvent::TrackSkipped { track_id, position: self.position_secs(), reason, timestamp: chrono::Utc::now() };
+        self.record(event).await;
+    }
+
+    fn position_secs(&self) -> u64 {
+        self.player.get_position().map(|p| p.as_secs()).unwrap_or(0)
+    }
+
+    async fn record(&mut self, event: PlaybackEvent) {
+        if let Err(e) = self.tracker.handle_event(event).await {
+            warn!("Behavior tracking failed: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::{NullBackend, PlaybackState};
+
+    fn library(dir: &std::path::Path) -> Library {
+        let mut config = Config { database_path: dir.join("panpipe.db"), ..Config::default() };
+        config.behavior.min_play_time_for_tracking = 0; // the null backend never gets past 0:00
+        let player = AudioPlayer::with_backend(Box::new(NullBackend::new()), &config.clone().into());
+        let playlists = PlaylistManager::new(dir.join("playlists")).unwrap();
+        Library::new(config, player, playlists).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_play_next_prev() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut library = library(dir.path());
+        library.set_tracks(vec![
+            Track::test_fixture("/music/c.mp3").with_title("Gamma"),
+            Track::test_fixture("/music/a.mp3").with_title("Alpha"),
+            Track::test_fixture("/music/bb.mp3").with_title("Beta"),
+        ]);
+        assert_eq!(library.search("beta")[0].display_title(), "Beta");
+
+        library.play(0).await.unwrap();
+        assert_eq!(library.current().unwrap().display_title(), "Alpha");
+        assert_eq!(library.player().get_state(), PlaybackState::Playing);
+        assert_eq!(library.next().await.unwrap().unwrap().display_title(), "Beta");
+        assert_eq!(library.next().await.unwrap().unwrap().display_title(), "Gamma");
+        assert!(library.next().await.unwrap().is_none()); // end of the library
+        assert_eq!(library.prev().await.unwrap().unwrap().display_title(), "Beta");
+        assert_eq!(library.prev().await.unwrap().unwrap().display_title(), "Alpha");
+
+        assert!(library.toggle_shuffle());
+        assert!(library.next().await.unwrap().is_some());
+
+        // Skips were recorded for the tracks left early
+        let behaviors = library.behaviors().await.unwrap();
+        assert!(behaviors.iter().any(|behavior| behavior.total_skips > 0));
+        library.shutdown().await.unwrap();
+    }
+}
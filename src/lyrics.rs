@@ -0,0 +1,273 @@
+// Lyrics - a .lrc next to the track wins, then our cache, then lrclib.net
+// Timed (LRC) lyrics follow playback line by line; anything else is shown as plain text
+
+use crate::audio::Track;
+use anyhow::Result;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{debug, warn};
+
+const GET_URL: &str = "https://lrclib.net/api/get";
+const SEARCH_URL: &str = "https://lrclib.net/api/search";
+
+/// lrclib asks clients to say who they are
+const USER_AGENT: &str = concat!("BangTunes/", env!("CARGO_PKG_VERSION"), " (https://github.com/Himo-kai/BangTunes)");
+
+/// How far a search result's length may be from the track's and still count as the same recording
+const DURATION_TOLERANCE_SECS: u64 = 3;
+
+/// What lrclib has for tracks without words
+const INSTRUMENTAL: &str = "♪ Instrumental ♪";
+
+/// One line of timed lyrics
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LyricLine {
+    pub time: Duration, // when the line starts
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Lyrics {
+    Synced(Vec<LyricLine>), // earliest first
+    Plain(Vec<String>),
+}
+
+impl Lyrics {
+    /// LRC when any line has a timestamp, plain text otherwise; `None` for blank text
+    pub fn parse(text: &str) -> Option<Self> {
+        let lines = parse_lrc(text);
+        if !lines.is_empty() {
+            return Some(Lyrics::Synced(lines));
+        }
+        let lines: Vec<String> = text.lines().map(|line| line.trim().to_string()).collect();
+        let start = lines.iter().position(|line| !line.is_empty())?;
+        let end = lines.iter().rposition(|line| !line.is_empty())? + 1;
+        Some(Lyrics::Plain(lines[start..end].to_vec()))
+    }
+
+    /// The line being sung at `position`, for timed lyrics that have started
+    pub fn current_line(&self, position: Duration) -> Option<usize> {
+        match self {
+            Lyrics::Synced(lines) => lines.partition_point(|line| line.time <= position).checked_sub(1),
+            Lyrics::Plain(_) => None,
+        }
+    }
+
+    pub fn is_synced(&self) -> bool {
+        matches!(self, Lyrics::Synced(_))
+    }
+}
+
+/// The timed lines of an LRC file, earliest first
+///
+/// A line may carry several timestamps (a repeated chorus), `[offset:ms]` shifts every line,
+/// and the per-word `<mm:ss.xx>` stamps of enhanced LRC are dropped.
+pub fn parse_lrc(text: &str) -> Vec<LyricLine> {
+    let mut offset_ms: i64 = 0;
+    let mut timed: Vec<(i64, String)> = Vec::new();
+    for line in text.lines() {
+        let mut rest = line.trim();
+        let mut times = Vec::new();
+        while let Some(tag) = rest.strip_prefix('[').and_then(|r| r.split_once(']')) {
+            let (tag, after) = tag;
+            if let Some(ms) = parse_timestamp(tag) {
+                times.push(ms);
+            } else if let Some(offset) = tag.strip_prefix("offset:") {
+                offset_ms = offset.trim().parse().unwrap_or(0);
+            }
+            rest = after;
+        }
+        let text = strip_word_stamps(rest.trim());
+        timed.extend(times.into_iter().map(|ms| (ms, text.clone())));
+    }
+
+    // A positive offset makes the lyrics come sooner
+    let mut lines: Vec<LyricLine> = timed.into_iter()
+        .map(|(ms, text)| LyricLine { time: Duration::from_millis((ms - offset_ms).max(0) as u64), text })
+        .collect();
+    lines.sort_by_key(|line| line.time);
+    lines
+}
+
+/// Milliseconds from "mm:ss", "mm:ss.xx" or "mm:ss.xxx"
+fn parse_timestamp(tag: &str) -> Option<i64> {
+    let (minutes, seconds) = tag.split_once(':')?;
+    let minutes: i64 = minutes.trim().parse().ok()?;
+    let (whole, fraction) = seconds.split_once(['.', ':']).unwrap_or((seconds, ""));
+    let whole: i64 = whole.parse().ok()?;
+    if !fraction.chars().all(|c| c.is_ascii_digit()) || fraction.len() > 3 {
+        return None;
+    }
+    let fraction_ms = format!("{:0<3}", fraction).parse::<i64>().ok()?;
+    Some(minutes * 60_000 + whole * 1000 + fraction_ms)
+}
+
+fn strip_word_stamps(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('<') {
+        match rest[start..].find('>') {
+            Some(end) if parse_timestamp(&rest[start + 1..start + end]).is_some() => {
+                out.push_str(&rest[..start]);
+                rest = &rest[start + end + 1..];
+            }
+            _ => {
+                out.push_str(&rest[..=start]);
+                rest = &rest[start + 1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Finds lyrics and keeps what lrclib sends, one file per track
+pub struct LyricsClient {
+    http: reqwest::Client,
+    cache_dir: PathBuf,
+}
+
+impl LyricsClient {
+    pub fn new(cache_dir: &Path) -> Result<Self> {
+        Ok(Self {
+            http: reqwest::Client::builder()
+                .timeout(Duration::from_secs(15))
+                .user_agent(USER_AGENT)
+                .build()?,
+            cache_dir: cache_dir.to_path_buf(),
+        })
+    }
+
+    /// Lyrics for `track`, `None` when there aren't any to be had
+    pub async fn fetch(&self, track: &Track) -> Result<Option<Lyrics>> {
+        let sidecar = track.file_path.with_extension("lrc");
+        let cached = self.cache_path(track);
+        for path in [&sidecar, &cached] {
+            if let Ok(text) = fs::read_to_string(path) {
+                debug!("🎤 Lyrics from {}", path.display());
+                return Ok(Lyrics::parse(&text));
+            }
+        }
+
+        let Some(text) = self.download(track).await? else {
+            return Ok(None);
+        };
+        if let Err(e) = fs::create_dir_all(&self.cache_dir).and_then(|_| fs::write(&cached, &text)) {
+            warn!("🎤 Couldn't cache lyrics for {}: {}", track.file_path.display(), e);
+        }
+        Ok(Lyrics::parse(&text))
+    }
+
+    /// Keyed by the content-derived id, so the cache follows a file that moves
+    fn cache_path(&self, track: &Track) -> PathBuf {
+        self.cache_dir.join(format!("{}.lrc", track.id))
+    }
+
+    /// lrclib's exact match when there's enough to ask for one, its search otherwise
+    async fn download(&self, track: &Track) -> Result<Option<String>> {
+        let tag = |value: &Option<String>| value.as_deref().map(str::trim).filter(|v| !v.is_empty()).map(str::to_string);
+        let (Some(artist), Some(title)) = (tag(&track.metadata.artist), tag(&track.metadata.title)) else {
+            return Ok(None);
+        };
+        let duration = track.duration.map(|d| d.as_secs());
+
+        if let (Some(album), Some(duration)) = (tag(&track.metadata.album), duration) {
+            let response = self.http.get(GET_URL)
+                .query(&[
+                    ("artist_name", artist.as_str()),
+                    ("track_name", title.as_str()),
+                    ("album_name", album.as_str()),
+                    ("duration", &duration.to_string()),
+                ])
+                .send().await?;
+            if response.status().is_success() {
+                if let Some(text) = lyrics_text(&response.json().await?) {
+                    return Ok(Some(text));
+                }
+            } else if response.status() != reqwest::StatusCode::NOT_FOUND {
+                return Err(anyhow::anyhow!("lrclib answered {}", response.status()));
+            }
+        }
+
+        let response = self.http.get(SEARCH_URL)
+            .query(&[("artist_name", artist.as_str()), ("track_name", title.as_str())])
+            .send().await?;
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!("lrclib answered {}", response.status()));
+        }
+        let results: Value = response.json().await?;
+        Ok(best_match(results.as_array().map(Vec::as_slice).unwrap_or_default(), duration).and_then(lyrics_text))
+    }
+}
+
+/// Timed lyrics if the record has them, else plain ones
+fn lyrics_text(record: &Value) -> Option<String> {
+    let field = |name: &str| record[name].as_str().filter(|text| !text.trim().is_empty()).map(str::to_string);
+    field("syncedLyrics")
+        .or_else(|| field("plainLyrics"))
+        .or_else(|| record["instrumental"].as_bool().unwrap_or(false).then(|| INSTRUMENTAL.to_string()))
+}
+
+/// The first search result with lyrics, preferring one about as long as the track
+fn best_match(results: &[Value], duration: Option<u64>) -> Option<&Value> {
+    let with_lyrics = || results.iter().filter(|record| lyrics_text(record).is_some());
+    let close = |record: &&Value| match (duration, record["duration"].as_f64()) {
+        (Some(duration), Some(length)) => (length.round() as u64).abs_diff(duration) <= DURATION_TOLERANCE_SECS,
+        _ => false,
+    };
+    with_lyrics().find(close).or_else(|| with_lyrics().next())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn line(ms: u64, text: &str) -> LyricLine {
+        LyricLine { time: Duration::from_millis(ms), text: text.to_string() }
+    }
+
+    #[test]
+    fn test_parse_lrc() {
+        let text = "[ar:Someone]\n[ti:Song]\n[offset:500]\n\
+                    [00:12.50]First line\n\
+                    [00:20.00][01:05.3]Chorus <00:21.00>with <00:21.50>words\n\
+                    [00:30.123]\n\
+                    not timed at all";
+        let lyrics = Lyrics::parse(text).unwrap();
+        assert_eq!(lyrics, Lyrics::Synced(vec![
+            line(12_000, "First line"),
+            line(19_500, "Chorus with words"),
+            line(29_623, ""),
+            line(64_800, "Chorus with words"),
+        ]));
+
+        assert_eq!(lyrics.current_line(Duration::from_secs(5)), None);
+        assert_eq!(lyrics.current_line(Duration::from_millis(19_500)), Some(1));
+        assert_eq!(lyrics.current_line(Duration::from_secs(600)), Some(3));
+    }
+
+    #[test]
+    fn test_plain_lyrics() {
+        let lyrics = Lyrics::parse("\n\n  Verse one\n\nVerse two  \n\n").unwrap();
+        assert_eq!(lyrics, Lyrics::Plain(vec!["Verse one".to_string(), String::new(), "Verse two".to_string()]));
+        assert!(!lyrics.is_synced());
+        assert_eq!(lyrics.current_line(Duration::from_secs(10)), None);
+        assert_eq!(Lyrics::parse(" \n "), None);
+    }
+
+    #[test]
+    fn test_best_match() {
+        let results = vec![
+            json!({ "duration": 100.0, "plainLyrics": "", "syncedLyrics": null }),
+            json!({ "duration": 250.0, "plainLyrics": "Radio edit" }),
+            json!({ "duration": 301.4, "plainLyrics": "Album version", "syncedLyrics": "[00:01.00]Album version" }),
+        ];
+        assert_eq!(best_match(&results, Some(300)).and_then(lyrics_text).as_deref(), Some("[00:01.00]Album version"));
+        assert_eq!(best_match(&results, Some(120)).and_then(lyrics_text).as_deref(), Some("Radio edit"));
+        assert_eq!(lyrics_text(&json!({ "instrumental": true })).as_deref(), Some(INSTRUMENTAL));
+        assert!(best_match(&results[..1], None).is_none());
+    }
+}
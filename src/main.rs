@@ -2,16 +2,9 @@
 // Started as a simple player, grew into something with smart features
 // Now integrated into BangTunes for music discovery -> playback
 
-mod audio;
-mod behavior;
-mod config;
-mod export;
-mod spotify;
-mod ui;
-
 use anyhow::Result;
-use config::Config;
-use ui::App;
+use panpipe::config::Config;
+use panpipe::ui::App;
 
 #[tokio::main]
 async fn main() -> Result<()> {
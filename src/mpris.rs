@@ -0,0 +1,356 @@
+// MPRIS integration - media keys and desktop widgets drive playback over D-Bus
+// Linux only; on other platforms MprisHandle quietly does nothing
+
+use crate::audio::{PlaybackState, Track};
+use anyhow::Result;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Requests coming in from the desktop, for the client to map onto its own actions
+#[derive(Debug, Clone, PartialEq)]
+pub enum MprisCommand {
+    Play,
+    Pause,
+    PlayPause,
+    Stop,
+    Next,
+    Previous,
+    Seek(i64),             // relative offset in microseconds, may be negative
+    SetPosition(Duration), // absolute position in the current track
+    SetVolume(f64),        // 0.0 to 1.0
+}
+
+/// Registered `org.mpris.MediaPlayer2` service; dropping it releases the bus name
+pub struct MprisHandle {
+    #[cfg(target_os = "linux")]
+    connection: zbus::Connection,
+}
+
+#[cfg(target_os = "linux")]
+impl MprisHandle {
+    /// Claim the bus name and start serving the MPRIS interfaces
+    pub async fn start() -> Result<(Self, mpsc::UnboundedReceiver<MprisCommand>)> {
+        let (commands, command_rx) = mpsc::unbounded_channel();
+
+        let connection = zbus::connection::Builder::session()?
+            .name(dbus::BUS_NAME)?
+            .serve_at(dbus::OBJECT_PATH, dbus::RootInterface)?
+            .serve_at(dbus::OBJECT_PATH, dbus::PlayerInterface::new(commands))?
+            .build()
+            .await?;
+
+        Ok((Self { connection }, command_rx))
+    }
+
+    pub async fn set_track(&self, track: Option<&Track>) -> Result<()> {
+        let player = self.player().await?;
+        player.get_mut().await.track = track.map(dbus::TrackInfo::from_track);
+        player.get().await.metadata_changed(player.signal_emitter()).await?;
+        Ok(())
+    }
+
+    pub async fn set_playback_state(&self, state: &PlaybackState) -> Result<()> {
+        let player = self.player().await?;
+        player.get_mut().await.status = match state {
+            PlaybackState::Playing => "Playing",
+            PlaybackState::Paused => "Paused",
+            PlaybackState::Stopped => "Stopped",
+        };
+        player.get().await.playback_status_changed(player.signal_emitter()).await?;
+        Ok(())
+    }
+
+    pub async fn set_volume(&self, volume: f32) -> Result<()> {
+        let player = self.player().await?;
+        player.get_mut().await.volume = volume as f64;
+        player.get().await.volume_changed(player.signal_emitter()).await?;
+        Ok(())
+    }
+
+    /// Record the playback position; `seeked` emits the Seeked signal for jumps
+    ///
+    /// MPRIS clients extrapolate Position themselves, so steady progress isn't signalled.
+    pub async fn set_position(&self, position: Duration, seeked: bool) -> Result<()> {
+        let player = self.player().await?;
+        let micros = position.as_micros() as i64;
+        player.get_mut().await.position = micros;
+        if seeked {
+            dbus::PlayerInterface::seeked(player.signal_emitter(), micros).await?;
+        }
+        Ok(())
+    }
+
+    async fn player(&self) -> Result<zbus::object_server::InterfaceRef<dbus::PlayerInterface>> {
+        Ok(self.connection
+            .object_server()
+            .interface::<_, dbus::PlayerInterface>(dbus::OBJECT_PATH)
+            .await?)
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+impl MprisHandle {
+    /// No D-Bus here - hand back a handle that ignores updates and a receiver that stays quiet
+    pub async fn start() -> Result<(Self, mpsc::UnboundedReceiver<MprisCommand>)> {
+        let (_commands, command_rx) = mpsc::unbounded_channel();
+        Ok((Self {}, command_rx))
+    }
+
+    pub async fn set_track(&self, _track: Option<&Track>) -> Result<()> {
+        Ok(())
+    }
+
+    pub async fn set_playback_state(&self, _state: &PlaybackState) -> Result<()> {
+        Ok(())
+    }
+
+    pub async fn set_volume(&self, _volume: f32) -> Result<()> {
+        Ok(())
+    }
+
+    pub async fn set_position(&self, _position: Duration, _seeked: bool) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod dbus {
+    use super::MprisCommand;
+    use crate::audio::Track;
+    use std::collections::HashMap;
+    use std::time::Duration;
+    use tokio::sync::mpsc;
+    use zbus::fdo;
+    use zbus::object_server::SignalEmitter;
+    use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value};
+
+    pub const BUS_NAME: &str = "org.mpris.MediaPlayer2.bangtunes";
+    pub const OBJECT_PATH: &str = "/org/mpris/MediaPlayer2";
+
+    /// What the Metadata property reports for the current track
+    pub struct TrackInfo {
+        id: OwnedObjectPath,
+        title: String,
+        artist: String,
+        album: Option<String>,
+        length: Option<Duration>,
+    }
+
+    impl TrackInfo {
+        pub fn from_track(track: &Track) -> Self {
+            // Object paths only allow [A-Za-z0-9_], so use the uuid's simple form
+            let path = format!("/org/bangtunes/track/t{}", track.id.simple());
+            let id = ObjectPath::try_from(path)
+                .map(OwnedObjectPath::from)
+                .unwrap_or_else(|_| OwnedObjectPath::from(ObjectPath::from_static_str_unchecked("/org/mpris/MediaPlayer2/TrackList/NoTrack")));
+
+            Self {
+                id,
+                title: track.display_title(),
+                artist: track.display_artist(),
+                album: track.metadata.album.clone(),
+                length: track.duration,
+            }
+        }
+    }
+
+    pub struct RootInterface;
+
+    #[zbus::interface(name = "org.mpris.MediaPlayer2")]
+    impl RootInterface {
+        fn raise(&self) {}
+
+        fn quit(&self) {}
+
+        #[zbus(property)]
+        fn can_quit(&self) -> bool {
+            false
+        }
+
+        #[zbus(property)]
+        fn can_raise(&self) -> bool {
+            false
+        }
+
+        #[zbus(property)]
+        fn has_track_list(&self) -> bool {
+            false
+        }
+
+        #[zbus(property)]
+        fn identity(&self) -> String {
+            "BangTunes".to_string()
+        }
+
+        #[zbus(property)]
+        fn supported_uri_schemes(&self) -> Vec<String> {
+            Vec::new()
+        }
+
+        #[zbus(property)]
+        fn supported_mime_types(&self) -> Vec<String> {
+            Vec::new()
+        }
+    }
+
+    pub struct PlayerInterface {
+        commands: mpsc::UnboundedSender<MprisCommand>,
+        pub status: &'static str,
+        pub track: Option<TrackInfo>,
+        pub volume: f64,
+        pub position: i64, // microseconds
+    }
+
+    impl PlayerInterface {
+        pub fn new(commands: mpsc::UnboundedSender<MprisCommand>) -> Self {
+            Self {
+                commands,
+                status: "Stopped",
+                track: None,
+                volume: 1.0,
+                position: 0,
+            }
+        }
+
+        fn send(&self, command: MprisCommand) {
+            let _ = self.commands.send(command);
+        }
+    }
+
+    #[zbus::interface(name = "org.mpris.MediaPlayer2.Player")]
+    impl PlayerInterface {
+        fn next(&self) {
+            self.send(MprisCommand::Next);
+        }
+
+        fn previous(&self) {
+            self.send(MprisCommand::Previous);
+        }
+
+        fn pause(&self) {
+            self.send(MprisCommand::Pause);
+        }
+
+        fn play_pause(&self) {
+            self.send(MprisCommand::PlayPause);
+        }
+
+        fn stop(&self) {
+            self.send(MprisCommand::Stop);
+        }
+
+        fn play(&self) {
+            self.send(MprisCommand::Play);
+        }
+
+        fn seek(&self, offset: i64) {
+            self.send(MprisCommand::Seek(offset));
+        }
+
+        fn set_position(&self, track_id: ObjectPath<'_>, position: i64) {
+            // Per spec, stale track ids and negative positions are ignored
+            let is_current = self.track.as_ref().is_some_and(|t| t.id.as_str() == track_id.as_str());
+            if is_current && position >= 0 {
+                self.send(MprisCommand::SetPosition(Duration::from_micros(position as u64)));
+            }
+        }
+
+        fn open_uri(&self, _uri: &str) -> fdo::Result<()> {
+            Err(fdo::Error::NotSupported("Opening URIs is not supported".to_string()))
+        }
+
+        #[zbus(signal)]
+        pub async fn seeked(emitter: &SignalEmitter<'_>, position: i64) -> zbus::Result<()>;
+
+        #[zbus(property)]
+        fn playback_status(&self) -> String {
+            self.status.to_string()
+        }
+
+        #[zbus(property)]
+        fn rate(&self) -> f64 {
+            1.0
+        }
+
+        #[zbus(property)]
+        fn minimum_rate(&self) -> f64 {
+            1.0
+        }
+
+        #[zbus(property)]
+        fn maximum_rate(&self) -> f64 {
+            1.0
+        }
+
+        #[zbus(property)]
+        fn metadata(&self) -> HashMap<String, OwnedValue> {
+            let mut metadata = HashMap::new();
+            let Some(track) = &self.track else {
+                return metadata;
+            };
+
+            let mut insert = |key: &str, value: Value<'_>| {
+                if let Ok(value) = OwnedValue::try_from(value) {
+                    metadata.insert(key.to_string(), value);
+                }
+            };
+            insert("mpris:trackid", Value::from(track.id.clone()));
+            insert("xesam:title", Value::from(track.title.as_str()));
+            insert("xesam:artist", Value::from(vec![track.artist.as_str()]));
+            if let Some(album) = &track.album {
+                insert("xesam:album", Value::from(album.as_str()));
+            }
+            if let Some(length) = track.length {
+                insert("mpris:length", Value::from(length.as_micros() as i64));
+            }
+
+            metadata
+        }
+
+        #[zbus(property)]
+        fn volume(&self) -> f64 {
+            self.volume
+        }
+
+        #[zbus(property)]
+        fn set_volume(&mut self, volume: f64) {
+            // The client applies it and reports back through MprisHandle::set_volume
+            self.send(MprisCommand::SetVolume(volume.clamp(0.0, 1.0)));
+        }
+
+        #[zbus(property(emits_changed_signal = "false"))]
+        fn position(&self) -> i64 {
+            self.position
+        }
+
+        #[zbus(property(emits_changed_signal = "const"))]
+        fn can_go_next(&self) -> bool {
+            true
+        }
+
+        #[zbus(property(emits_changed_signal = "const"))]
+        fn can_go_previous(&self) -> bool {
+            true
+        }
+
+        #[zbus(property(emits_changed_signal = "const"))]
+        fn can_play(&self) -> bool {
+            true
+        }
+
+        #[zbus(property(emits_changed_signal = "const"))]
+        fn can_pause(&self) -> bool {
+            true
+        }
+
+        #[zbus(property(emits_changed_signal = "const"))]
+        fn can_seek(&self) -> bool {
+            true
+        }
+
+        #[zbus(property(emits_changed_signal = "const"))]
+        fn can_control(&self) -> bool {
+            true
+        }
+    }
+}
@@ -0,0 +1,454 @@
+// MusicBrainz lookups - candidate tags for a track, from its AcoustID fingerprint or a title/artist guess
+// Every request waits its turn to stay within MusicBrainz's one-a-second policy, and answers are cached on disk
+
+use crate::audio::{Track, TrackMetadata};
+use crate::config::MusicBrainzConfig;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+use tracing::{debug, warn};
+
+const SEARCH_URL: &str = "https://musicbrainz.org/ws/2/recording";
+const ACOUSTID_URL: &str = "https://api.acoustid.org/v2/lookup";
+
+/// MusicBrainz turns away requests without a User-Agent that names the app and a way to reach it
+const USER_AGENT: &str = concat!("BangTunes/", env!("CARGO_PKG_VERSION"), " ( https://github.com/Himo-kai/BangTunes )");
+
+/// MusicBrainz asks clients to make at most one request a second
+pub const REQUEST_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Most candidates a lookup offers
+const MAX_CANDIDATES: usize = 8;
+
+/// Releases offered per recording, the same song is often on a dozen compilations
+const RELEASES_PER_RECORDING: usize = 3;
+
+/// Fingerprint matches below this AcoustID score are more likely wrong than right
+const MIN_ACOUSTID_SCORE: f64 = 0.5;
+
+/// Tags MusicBrainz has for one release of a recording
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MetadataCandidate {
+    pub recording_id: String,
+    pub title: String,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub year: Option<u32>,
+    pub track_number: Option<u32>,
+    pub score: u8, // 0-100, how sure the search or fingerprint is
+}
+
+impl MetadataCandidate {
+    /// Overwrite the tags this candidate has, leaving the rest of `metadata` alone
+    pub fn apply_to(&self, metadata: &mut TrackMetadata) {
+        metadata.title = Some(self.title.clone());
+        if let Some(artist) = &self.artist {
+            metadata.artist = Some(artist.clone());
+        }
+        if let Some(album) = &self.album {
+            metadata.album = Some(album.clone());
+        }
+        if self.year.is_some() {
+            metadata.year = self.year;
+        }
+        if self.track_number.is_some() {
+            metadata.track_number = self.track_number;
+        }
+    }
+
+    /// "Artist - Title", for status lines
+    pub fn display_name(&self) -> String {
+        match &self.artist {
+            Some(artist) => format!("{} - {}", artist, self.title),
+            None => self.title.clone(),
+        }
+    }
+}
+
+/// Spaces requests at least `interval` apart, however many tasks are asking
+#[derive(Debug)]
+pub struct RateLimiter {
+    interval: Duration,
+    next: Mutex<Option<Instant>>, // earliest the next request may go out
+}
+
+impl RateLimiter {
+    pub fn new(interval: Duration) -> Self {
+        Self { interval, next: Mutex::new(None) }
+    }
+
+    /// Returns once it's this caller's turn; callers queue up behind each other
+    pub async fn wait(&self) {
+        let mut next = self.next.lock().await;
+        if let Some(at) = *next {
+            tokio::time::sleep_until(at).await;
+        }
+        *next = Some(Instant::now() + self.interval);
+    }
+}
+
+/// Earlier lookups by query, saved after every new answer
+#[derive(Debug, Default)]
+pub struct LookupCache {
+    path: PathBuf,
+    entries: HashMap<String, Vec<MetadataCandidate>>,
+}
+
+impl LookupCache {
+    /// The cache saved at `path`, empty if there isn't one or it can't be read
+    pub fn load(path: &Path) -> Self {
+        let entries = fs::read_to_string(path).ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self { path: path.to_path_buf(), entries }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string(&self.entries)?)?;
+        Ok(())
+    }
+
+    pub fn get(&self, key: &str) -> Option<&[MetadataCandidate]> {
+        self.entries.get(key).map(Vec::as_slice)
+    }
+
+    pub fn insert(&mut self, key: String, candidates: Vec<MetadataCandidate>) {
+        self.entries.insert(key, candidates);
+    }
+}
+
+/// What `fpcalc` makes of an audio file
+#[derive(Debug, Clone, Deserialize)]
+struct Fingerprint {
+    duration: f64,
+    fingerprint: String,
+}
+
+/// MusicBrainz search client, with AcoustID fingerprint matching when a key is configured
+pub struct MusicBrainzClient {
+    http: reqwest::Client,
+    acoustid_api_key: Option<String>,
+    fpcalc: PathBuf,
+    limiter: RateLimiter,
+    cache: Mutex<LookupCache>,
+}
+
+impl MusicBrainzClient {
+    pub fn new(config: &MusicBrainzConfig, cache_path: &Path) -> Result<Self> {
+        Ok(Self {
+            http: reqwest::Client::builder()
+                .timeout(Duration::from_secs(15))
+                .user_agent(USER_AGENT)
+                .build()?,
+            acoustid_api_key: config.acoustid_api_key.clone().filter(|key| !key.trim().is_empty()),
+            fpcalc: config.fpcalc_path.clone().unwrap_or_else(|| PathBuf::from("fpcalc")),
+            limiter: RateLimiter::new(REQUEST_INTERVAL),
+            cache: Mutex::new(LookupCache::load(cache_path)),
+        })
+    }
+
+    /// Candidate tags for `track`, best first
+    ///
+    /// Tries the fingerprint when an AcoustID key is set, then falls back to searching for
+    /// `title` (and `artist`, if known) - usually the current tags or a guess from the filename.
+    pub async fn lookup(&self, track: &Track, artist: Option<&str>, title: &str) -> Result<Vec<MetadataCandidate>> {
+        if self.acoustid_api_key.is_some() {
+            match self.lookup_fingerprint(track).await {
+                Ok(candidates) if !candidates.is_empty() => return Ok(candidates),
+                Ok(_) => debug!("🔎 No fingerprint match for {}", track.file_path.display()),
+                Err(e) => debug!("🔎 Fingerprint lookup failed for {}: {}", track.file_path.display(), e),
+            }
+        }
+        if title.trim().is_empty() {
+            return Err(anyhow::anyhow!("Nothing to search for - the track has no title to go on"));
+        }
+        self.search(artist, title).await
+    }
+
+    /// Search MusicBrainz recordings by title and artist
+    pub async fn search(&self, artist: Option<&str>, title: &str) -> Result<Vec<MetadataCandidate>> {
+        let query = search_query(artist, title);
+        let key = format!("search:{}", query.to_lowercase());
+        self.cached(key, async {
+            let response = self.get_recordings(&query).await?;
+            Ok(candidates_from_search(&response, None))
+        }).await
+    }
+
+    /// Fingerprint `track` with fpcalc, find its recordings on AcoustID, then their tags on MusicBrainz
+    async fn lookup_fingerprint(&self, track: &Track) -> Result<Vec<MetadataCandidate>> {
+        let Some(api_key) = &self.acoustid_api_key else {
+            return Ok(Vec::new());
+        };
+        // The id follows the audio content, so a moved or renamed file still hits the cache
+        let key = format!("acoustid:{}", track.id);
+        self.cached(key, async {
+            let fpcalc = self.fpcalc.clone();
+            let path = track.file_path.clone();
+            let fingerprint = tokio::task::spawn_blocking(move || run_fpcalc(&fpcalc, &path)).await??;
+
+            self.limiter.wait().await;
+            let response: Value = self.http.get(ACOUSTID_URL)
+                .query(&[
+                    ("client", api_key.as_str()),
+                    ("meta", "recordingids"),
+                    ("duration", &(fingerprint.duration.round() as u64).to_string()),
+                    ("fingerprint", &fingerprint.fingerprint),
+                ])
+                .send().await?
+                .json().await?;
+            if response["status"] != "ok" {
+                let message = response["error"]["message"].as_str().unwrap_or("unknown error");
+                return Err(anyhow::anyhow!("AcoustID error: {}", message));
+            }
+
+            let scores = recording_scores(&response);
+            if scores.is_empty() {
+                return Ok(Vec::new());
+            }
+            // One search for every matched recording at once, rather than a request each
+            let ids: Vec<&str> = scores.keys().map(String::as_str).collect();
+            let query = format!("rid:({})", ids.join(" OR "));
+            let response = self.get_recordings(&query).await?;
+            Ok(candidates_from_search(&response, Some(&scores)))
+        }).await
+    }
+
+    /// The cached answer for `key`, or `fetch`'s, which is cached for next time
+    async fn cached(&self, key: String, fetch: impl std::future::Future<Output = Result<Vec<MetadataCandidate>>>) -> Result<Vec<MetadataCandidate>> {
+        if let Some(candidates) = self.cache.lock().await.get(&key) {
+            return Ok(candidates.to_vec());
+        }
+        let candidates = fetch.await?;
+        let mut cache = self.cache.lock().await;
+        cache.insert(key, candidates.clone());
+        if let Err(e) = cache.save() {
+            warn!("🔎 Couldn't save the MusicBrainz cache: {}", e);
+        }
+        Ok(candidates)
+    }
+
+    async fn get_recordings(&self, query: &str) -> Result<Value> {
+        self.limiter.wait().await;
+        let response = self.http.get(SEARCH_URL)
+            .query(&[("query", query), ("fmt", "json"), ("limit", "10")])
+            .send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(anyhow::anyhow!("MusicBrainz answered {}", status));
+        }
+        Ok(response.json().await?)
+    }
+}
+
+/// A Lucene query for `title` by `artist`, each matched as a phrase
+pub fn search_query(artist: Option<&str>, title: &str) -> String {
+    let phrase = |text: &str| format!("\"{}\"", text.trim().replace('\\', "\\\\").replace('"', "\\\""));
+    match artist.map(str::trim).filter(|artist| !artist.is_empty()) {
+        Some(artist) => format!("recording:{} AND artist:{}", phrase(title), phrase(artist)),
+        None => format!("recording:{}", phrase(title)),
+    }
+}
+
+fn run_fpcalc(fpcalc: &Path, path: &Path) -> Result<Fingerprint> {
+    let output = Command::new(fpcalc)
+        .arg("-json")
+        .arg(path)
+        .output()
+        .with_context(|| format!("Couldn't run {} - is Chromaprint installed?", fpcalc.display()))?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!("fpcalc failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(serde_json::from_slice(&output.stdout)?)
+}
+
+/// Best AcoustID score (0-100) for each MusicBrainz recording id in a lookup response
+fn recording_scores(response: &Value) -> HashMap<String, u8> {
+    let mut scores = HashMap::new();
+    for result in response["results"].as_array().into_iter().flatten() {
+        let score = result["score"].as_f64().unwrap_or(0.0);
+        if score < MIN_ACOUSTID_SCORE {
+            continue;
+        }
+        for recording in result["recordings"].as_array().into_iter().flatten() {
+            if let Some(id) = recording["id"].as_str() {
+                let best = scores.entry(id.to_string()).or_insert(0);
+                *best = (*best).max((score * 100.0).round() as u8);
+            }
+        }
+    }
+    scores
+}
+
+/// Candidates from a recording search, one per release, best first
+///
+/// `scores` replaces MusicBrainz's own search score, for recordings found by fingerprint.
+fn candidates_from_search(response: &Value, scores: Option<&HashMap<String, u8>>) -> Vec<MetadataCandidate> {
+    let mut candidates: Vec<MetadataCandidate> = Vec::new();
+    for recording in response["recordings"].as_array().into_iter().flatten() {
+        let (Some(recording_id), Some(title)) = (recording["id"].as_str(), recording["title"].as_str()) else {
+            continue;
+        };
+        let score = match scores {
+            Some(scores) => scores.get(recording_id).copied().unwrap_or(0),
+            None => recording["score"].as_u64().unwrap_or(0).min(100) as u8,
+        };
+        let artist = artist_credit(&recording["artist-credit"]);
+        let first_year = year_of(&recording["first-release-date"]);
+
+        let releases: Vec<&Value> = recording["releases"].as_array().into_iter().flatten().collect();
+        let mut offered = 0;
+        for release in releases.iter().map(Some).chain(releases.is_empty().then_some(None)) {
+            let candidate = MetadataCandidate {
+                recording_id: recording_id.to_string(),
+                title: title.to_string(),
+                artist: artist.clone(),
+                album: release.and_then(|release| release["title"].as_str()).map(str::to_string),
+                year: release.and_then(|release| year_of(&release["date"])).or(first_year),
+                track_number: release.and_then(|release| {
+                    release["media"][0]["track"][0]["number"].as_str().and_then(|number| number.parse().ok())
+                }),
+                score,
+            };
+            if candidates.iter().any(|known| known.title == candidate.title && known.artist == candidate.artist
+                && known.album == candidate.album && known.year == candidate.year) {
+                continue;
+            }
+            candidates.push(candidate);
+            offered += 1;
+            if offered == RELEASES_PER_RECORDING {
+                break;
+            }
+        }
+    }
+    candidates.sort_by_key(|candidate| std::cmp::Reverse(candidate.score)); // stable, so MusicBrainz's order breaks ties
+    candidates.truncate(MAX_CANDIDATES);
+    candidates
+}
+
+/// "Artist feat. Other", joined the way MusicBrainz credits it
+fn artist_credit(credit: &Value) -> Option<String> {
+    let names: String = credit.as_array()?.iter()
+        .map(|part| format!("{}{}", part["name"].as_str().unwrap_or(""), part["joinphrase"].as_str().unwrap_or("")))
+        .collect();
+    let names = names.trim();
+    (!names.is_empty()).then(|| names.to_string())
+}
+
+/// The year from a "YYYY", "YYYY-MM" or "YYYY-MM-DD" date
+fn year_of(date: &Value) -> Option<u32> {
+    date.as_str()?.get(..4)?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn search_response() -> Value {
+        json!({
+            "recordings": [
+                {
+                    "id": "rec-1",
+                    "score": 100,
+                    "title": "Jóga",
+                    "artist-credit": [{ "name": "Björk", "joinphrase": "" }],
+                    "first-release-date": "1997-09-15",
+                    "releases": [
+                        {
+                            "title": "Homogenic",
+                            "date": "1997-09-22",
+                            "media": [{ "position": 1, "track": [{ "number": "2", "title": "Jóga" }] }]
+                        },
+                        {
+                            "title": "Homogenic",
+                            "date": "1997-09-22",
+                            "media": [{ "position": 1, "track": [{ "number": "2", "title": "Jóga" }] }]
+                        },
+                        { "title": "Greatest Hits", "media": [{ "track": [{ "number": "A3" }] }] }
+                    ]
+                },
+                {
+                    "id": "rec-2",
+                    "score": 62,
+                    "title": "Jóga (live)",
+                    "artist-credit": [
+                        { "name": "Björk", "joinphrase": " & " },
+                        { "name": "Brodsky Quartet", "joinphrase": "" }
+                    ]
+                }
+            ]
+        })
+    }
+
+    #[test]
+    fn test_candidates_from_search() {
+        let candidates = candidates_from_search(&search_response(), None);
+        assert_eq!(candidates.len(), 3); // the repeated release is only offered once
+
+        assert_eq!(candidates[0], MetadataCandidate {
+            recording_id: "rec-1".to_string(),
+            title: "Jóga".to_string(),
+            artist: Some("Björk".to_string()),
+            album: Some("Homogenic".to_string()),
+            year: Some(1997),
+            track_number: Some(2),
+            score: 100,
+        });
+        // Vinyl-style track numbers don't parse, and the recording's first release fills in the year
+        assert_eq!(candidates[1].album.as_deref(), Some("Greatest Hits"));
+        assert_eq!((candidates[1].track_number, candidates[1].year), (None, Some(1997)));
+        // A recording without releases is still offered, credited to everyone on it
+        assert_eq!(candidates[2].artist.as_deref(), Some("Björk & Brodsky Quartet"));
+        assert_eq!((candidates[2].album.as_deref(), candidates[2].score), (None, 62));
+
+        // Fingerprint scores take over, and reorder
+        let scores = HashMap::from([("rec-1".to_string(), 40), ("rec-2".to_string(), 95)]);
+        let candidates = candidates_from_search(&search_response(), Some(&scores));
+        assert_eq!((candidates[0].recording_id.as_str(), candidates[0].score), ("rec-2", 95));
+
+        let mut metadata = TrackMetadata { genre: Some("Electronic".to_string()), ..Default::default() };
+        candidates[1].apply_to(&mut metadata);
+        assert_eq!(metadata.title.as_deref(), Some("Jóga"));
+        assert_eq!(metadata.track_number, Some(2));
+        assert_eq!(metadata.genre.as_deref(), Some("Electronic"));
+    }
+
+    #[test]
+    fn test_acoustid_scores_and_queries() {
+        let response = json!({
+            "status": "ok",
+            "results": [
+                { "score": 0.97, "recordings": [{ "id": "rec-1" }, { "id": "rec-2" }] },
+                { "score": 0.81, "recordings": [{ "id": "rec-1" }] },
+                { "score": 0.2, "recordings": [{ "id": "rec-3" }] }
+            ]
+        });
+        let scores = recording_scores(&response);
+        assert_eq!(scores.len(), 2);
+        assert_eq!(scores["rec-1"], 97);
+
+        assert_eq!(search_query(Some(" Björk "), "Jóga"), r#"recording:"Jóga" AND artist:"Björk""#);
+        assert_eq!(search_query(Some(""), r#"Say "Hi" \o/"#), r#"recording:"Say \"Hi\" \\o/""#);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limiter_spaces_requests() {
+        let limiter = RateLimiter::new(Duration::from_millis(40));
+        let start = Instant::now();
+        for _ in 0..3 {
+            limiter.wait().await;
+        }
+        assert!(start.elapsed() >= Duration::from_millis(80));
+    }
+}
@@ -0,0 +1,95 @@
+// Reveal a file in the system file manager - Finder, Explorer, or whatever the desktop has on Linux
+// The file is selected where the platform supports it, otherwise its folder just opens
+
+use anyhow::Result;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Open the folder holding `path`, with the file selected where possible
+///
+/// Returns once the file manager has been asked; it keeps running on its own.
+pub fn reveal(path: &Path) -> Result<()> {
+    if !path.exists() {
+        return Err(anyhow::anyhow!("{} no longer exists", path.display()));
+    }
+    let mut candidates = reveal_commands(path, std::env::consts::OS);
+    let last = candidates.pop().ok_or_else(|| anyhow::anyhow!("Don't know how to open a file manager here"))?;
+
+    // Earlier candidates select the file but may be missing, so only their success counts
+    for (program, args) in candidates {
+        let succeeded = quiet(Command::new(&program).args(&args)).status().is_ok_and(|status| status.success());
+        if succeeded {
+            return Ok(());
+        }
+    }
+    quiet(Command::new(&last.0).args(&last.1))
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("Could not run {}: {}", last.0, e))?;
+    Ok(())
+}
+
+/// The commands to try in order, as (program, args), for an `std::env::consts::OS` value
+pub fn reveal_commands(path: &Path, os: &str) -> Vec<(String, Vec<String>)> {
+    let file = path.to_string_lossy().into_owned();
+    let folder = path.parent().unwrap_or(path).to_string_lossy().into_owned();
+    match os {
+        "macos" => vec![("open".to_string(), vec!["-R".to_string(), file])],
+        // Explorer wants the path glued to the switch, and exits non-zero even when it worked
+        "windows" => vec![("explorer".to_string(), vec![format!("/select,{}", file)])],
+        _ => vec![
+            // The freedesktop FileManager1 interface selects the file in Nautilus, Dolphin, Nemo and friends
+            ("dbus-send".to_string(), vec![
+                "--session".to_string(),
+                "--print-reply".to_string(),
+                "--dest=org.freedesktop.FileManager1".to_string(),
+                "--type=method_call".to_string(),
+                "/org/freedesktop/FileManager1".to_string(),
+                "org.freedesktop.FileManager1.ShowItems".to_string(),
+                format!("array:string:{}", file_uri(path)),
+                "string:".to_string(),
+            ]),
+            ("xdg-open".to_string(), vec![folder]),
+        ],
+    }
+}
+
+/// `file://` URI with everything outside the unreserved set percent-encoded, slashes kept
+fn file_uri(path: &Path) -> String {
+    let mut uri = String::from("file://");
+    for byte in path.to_string_lossy().bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => uri.push(byte as char),
+            // dbus-send splits array elements on commas, so they're escaped too
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+/// Keep the child's output off the terminal the TUI is drawing on
+fn quiet(command: &mut Command) -> &mut Command {
+    command.stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reveal_commands_per_platform() {
+        let path = Path::new("/music/AC,DC/Back in Black.mp3");
+        let linux = reveal_commands(path, "linux");
+        assert_eq!(linux[0].0, "dbus-send");
+        assert!(linux[0].1.contains(&"array:string:file:///music/AC%2CDC/Back%20in%20Black.mp3".to_string()));
+        assert_eq!(linux[1], ("xdg-open".to_string(), vec!["/music/AC,DC".to_string()]));
+
+        assert_eq!(reveal_commands(path, "macos"), vec![("open".to_string(), vec!["-R".to_string(), path.display().to_string()])]);
+        assert_eq!(reveal_commands(path, "windows")[0].1, vec![format!("/select,{}", path.display())]);
+    }
+
+    #[test]
+    fn test_missing_file_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(reveal(&dir.path().join("gone.mp3")).is_err());
+    }
+}
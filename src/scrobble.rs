@@ -0,0 +1,508 @@
+// Last.fm scrobbling - "now playing" when a track starts, a scrobble when a listen counts
+// Scrobbles are queued on disk first, so plays made offline are sent once last.fm is reachable again
+
+use crate::audio::Track;
+use crate::behavior::PlaySession;
+use crate::config::LastfmConfig;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+const API_URL: &str = "https://ws.audioscrobbler.com/2.0/";
+const AUTH_URL: &str = "https://www.last.fm/api/auth/";
+
+/// How often queued scrobbles are retried while last.fm can't be reached
+pub const RETRY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The most scrobbles last.fm takes in one request
+const BATCH_SIZE: usize = 50;
+
+/// Error codes that mean "try again later" rather than "this scrobble is bad"
+///
+/// 9 (invalid session) is here too, so plays survive until the user re-authorizes.
+const RETRYABLE_CODES: [u32; 5] = [8, 9, 11, 16, 29];
+
+/// Whether a listen counts, per last.fm's rules: the track is longer than 30 seconds and
+/// was played for half its length or 4 minutes, whichever comes first
+pub fn should_scrobble(played_secs: u64, duration_secs: u64) -> bool {
+    duration_secs > 30 && (played_secs >= duration_secs / 2 || played_secs >= 240)
+}
+
+/// The tags last.fm needs to identify a track
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScrobbleTrack {
+    pub artist: String,
+    pub title: String,
+    pub album: Option<String>,
+    pub duration_secs: Option<u64>,
+}
+
+impl ScrobbleTrack {
+    /// `None` for tracks missing an artist or title tag, which last.fm can't match
+    pub fn from_track(track: &Track) -> Option<Self> {
+        let tag = |value: &Option<String>| value.as_deref().map(str::trim).filter(|v| !v.is_empty()).map(str::to_string);
+        Some(Self {
+            artist: tag(&track.metadata.artist)?,
+            title: tag(&track.metadata.title)?,
+            album: tag(&track.metadata.album),
+            duration_secs: track.duration.map(|d| d.as_secs()),
+        })
+    }
+}
+
+/// One listen waiting to be submitted
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Scrobble {
+    pub track: ScrobbleTrack,
+    pub timestamp: i64, // when playback started, seconds since the Unix epoch
+}
+
+impl Scrobble {
+    /// The scrobble for a finished play session, if it was listened to long enough
+    pub fn from_session(track: &Track, session: &PlaySession) -> Option<Self> {
+        let track_info = ScrobbleTrack::from_track(track)?;
+        let duration = track_info.duration_secs.unwrap_or(session.track_duration);
+        // Reaching the tracker's idea of the end means it played through, even if that guess was short
+        let played = if session.play_duration >= session.track_duration { duration } else { session.play_duration };
+        should_scrobble(played, duration).then(|| Self {
+            track: track_info,
+            timestamp: session.started_at.timestamp(),
+        })
+    }
+}
+
+/// Scrobbles not yet accepted by last.fm, saved after every change
+#[derive(Debug, Default)]
+pub struct ScrobbleQueue {
+    path: PathBuf,
+    pending: Vec<Scrobble>,
+}
+
+impl ScrobbleQueue {
+    /// The queue saved at `path`, empty if there isn't one or it can't be read
+    pub fn load(path: &Path) -> Self {
+        let pending = fs::read_to_string(path).ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self { path: path.to_path_buf(), pending }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if self.pending.is_empty() {
+            if self.path.exists() {
+                fs::remove_file(&self.path)?;
+            }
+            return Ok(());
+        }
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string(&self.pending)?)?;
+        Ok(())
+    }
+
+    pub fn push(&mut self, scrobble: Scrobble) {
+        self.pending.push(scrobble);
+    }
+
+    pub fn pending(&self) -> &[Scrobble] {
+        &self.pending
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Drop the first `count` scrobbles, once they've been sent
+    pub fn remove_front(&mut self, count: usize) {
+        self.pending.drain(..count.min(self.pending.len()));
+    }
+}
+
+/// An error last.fm reported in its response
+#[derive(Debug, Clone)]
+pub struct LastfmError {
+    pub code: u32,
+    pub message: String,
+}
+
+impl fmt::Display for LastfmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "last.fm error {}: {}", self.code, self.message)
+    }
+}
+
+impl std::error::Error for LastfmError {}
+
+/// Whether a failed request is worth repeating later; network trouble always is
+fn is_retryable(error: &anyhow::Error) -> bool {
+    match error.downcast_ref::<LastfmError>() {
+        Some(lastfm) => RETRYABLE_CODES.contains(&lastfm.code),
+        None => true,
+    }
+}
+
+/// Minimal last.fm API client - signed calls for auth, now playing and scrobbles
+pub struct LastfmClient {
+    http: reqwest::Client,
+    api_key: String,
+    api_secret: String,
+    session_key: Option<String>,
+}
+
+impl LastfmClient {
+    /// Fails if the config has no API key or secret
+    pub fn new(config: &LastfmConfig) -> Result<Self> {
+        let (Some(api_key), Some(api_secret)) = (config.api_key.clone(), config.api_secret.clone()) else {
+            return Err(anyhow::anyhow!("Set api_key and api_secret under [lastfm] in config.toml first"));
+        };
+        Ok(Self {
+            http: reqwest::Client::builder().timeout(Duration::from_secs(15)).build()?,
+            api_key,
+            api_secret,
+            session_key: config.session_key.clone(),
+        })
+    }
+
+    /// Start the web auth flow: a token for the user to approve at `auth_url`
+    pub async fn get_token(&self) -> Result<String> {
+        let response = self.call("auth.getToken", BTreeMap::new()).await?;
+        response["token"].as_str()
+            .map(str::to_string)
+            .ok_or_else(|| anyhow::anyhow!("last.fm sent no token"))
+    }
+
+    /// Where the user approves `token` for this app
+    pub fn auth_url(&self, token: &str) -> String {
+        format!("{}?api_key={}&token={}", AUTH_URL, self.api_key, token)
+    }
+
+    /// Finish the web auth flow once `token` is approved: the username and a session key that doesn't expire
+    pub async fn get_session(&self, token: &str) -> Result<(String, String)> {
+        let params = BTreeMap::from([("token", token.to_string())]);
+        let response = self.call("auth.getSession", params).await?;
+        let session = &response["session"];
+        match (session["name"].as_str(), session["key"].as_str()) {
+            (Some(name), Some(key)) => Ok((name.to_string(), key.to_string())),
+            _ => Err(anyhow::anyhow!("last.fm sent no session")),
+        }
+    }
+
+    pub async fn update_now_playing(&self, track: &ScrobbleTrack) -> Result<()> {
+        let mut params = BTreeMap::from([
+            ("artist", track.artist.clone()),
+            ("track", track.title.clone()),
+        ]);
+        if let Some(album) = &track.album {
+            params.insert("album", album.clone());
+        }
+        if let Some(duration) = track.duration_secs {
+            params.insert("duration", duration.to_string());
+        }
+        self.call_with_session("track.updateNowPlaying", params).await?;
+        Ok(())
+    }
+
+    /// Submit up to `BATCH_SIZE` scrobbles in one request
+    pub async fn scrobble(&self, scrobbles: &[Scrobble]) -> Result<()> {
+        let mut params = BTreeMap::new();
+        for (i, scrobble) in scrobbles.iter().take(BATCH_SIZE).enumerate() {
+            params.insert(format!("artist[{}]", i), scrobble.track.artist.clone());
+            params.insert(format!("track[{}]", i), scrobble.track.title.clone());
+            params.insert(format!("timestamp[{}]", i), scrobble.timestamp.to_string());
+            if let Some(album) = &scrobble.track.album {
+                params.insert(format!("album[{}]", i), album.clone());
+            }
+            if let Some(duration) = scrobble.track.duration_secs {
+                params.insert(format!("duration[{}]", i), duration.to_string());
+            }
+        }
+        let params = params.iter().map(|(name, value)| (name.as_str(), value.clone())).collect();
+        let response = self.call_with_session("track.scrobble", params).await?;
+        let ignored = response["scrobbles"]["@attr"]["ignored"].as_u64().unwrap_or(0);
+        if ignored > 0 {
+            debug!("📻 last.fm ignored {} of {} scrobbles", ignored, scrobbles.len());
+        }
+        Ok(())
+    }
+
+    async fn call_with_session(&self, method: &str, mut params: BTreeMap<&str, String>) -> Result<serde_json::Value> {
+        let session_key = self.session_key.clone()
+            .ok_or_else(|| anyhow::anyhow!("Not authorized with last.fm yet"))?;
+        params.insert("sk", session_key);
+        self.call(method, params).await
+    }
+
+    /// A signed POST to the API, with last.fm's own errors surfaced as `LastfmError`
+    async fn call(&self, method: &str, mut params: BTreeMap<&str, String>) -> Result<serde_json::Value> {
+        params.insert("method", method.to_string());
+        params.insert("api_key", self.api_key.clone());
+        let signature = api_signature(&params, &self.api_secret);
+        params.insert("api_sig", signature);
+        params.insert("format", "json".to_string());
+
+        let response = self.http.post(API_URL).form(&params).send().await?;
+        let status = response.status();
+        let body: serde_json::Value = match response.json().await {
+            Ok(body) => body,
+            Err(e) if status.is_success() => return Err(e.into()),
+            Err(_) => return Err(anyhow::anyhow!("last.fm returned HTTP {}", status)),
+        };
+        if let Some(code) = body["error"].as_u64() {
+            return Err(LastfmError {
+                code: code as u32,
+                message: body["message"].as_str().unwrap_or_default().to_string(),
+            }.into());
+        }
+        Ok(body)
+    }
+}
+
+/// `api_sig`: the MD5 of every parameter as name + value in name order, then the secret
+fn api_signature(params: &BTreeMap<&str, String>, secret: &str) -> String {
+    let mut text: String = params.iter()
+        .filter(|(name, _)| !matches!(**name, "format" | "callback"))
+        .map(|(name, value)| format!("{}{}", name, value))
+        .collect();
+    text.push_str(secret);
+    md5_hex(text.as_bytes())
+}
+
+/// MD5 (RFC 1321) as lowercase hex - last.fm signs requests with it
+fn md5_hex(data: &[u8]) -> String {
+    const SHIFTS: [u32; 64] = [
+        7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22,
+        5, 9, 14, 20, 5, 9, 14, 20, 5, 9, 14, 20, 5, 9, 14, 20,
+        4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23,
+        6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21,
+    ];
+    let constants: Vec<u32> = (1..=64)
+        .map(|i| ((i as f64).sin().abs() * 4_294_967_296.0) as u32)
+        .collect();
+
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&(data.len() as u64).wrapping_mul(8).to_le_bytes());
+
+    let mut state: [u32; 4] = [0x6745_2301, 0xefcd_ab89, 0x98ba_dcfe, 0x1032_5476];
+    for block in message.chunks(64) {
+        let words: Vec<u32> = block.chunks(4)
+            .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+            .collect();
+        let [mut a, mut b, mut c, mut d] = state;
+        for i in 0..64 {
+            let (f, g) = match i / 16 {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), (7 * i) % 16),
+            };
+            let f = f.wrapping_add(a).wrapping_add(constants[i]).wrapping_add(words[g]);
+            a = d;
+            d = c;
+            c = b;
+            b = b.wrapping_add(f.rotate_left(SHIFTS[i]));
+        }
+        for (total, value) in state.iter_mut().zip([a, b, c, d]) {
+            *total = total.wrapping_add(value);
+        }
+    }
+
+    state.iter()
+        .flat_map(|word| word.to_le_bytes())
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+enum ScrobbleMessage {
+    NowPlaying(ScrobbleTrack),
+    Scrobble(Scrobble),
+}
+
+/// Background sender; requests never hold up the caller
+pub struct Scrobbler {
+    messages: mpsc::UnboundedSender<ScrobbleMessage>,
+    task: JoinHandle<()>,
+}
+
+impl Scrobbler {
+    /// Start sending, if scrobbling is enabled and authorized; anything left in the queue goes first
+    pub fn start(config: &LastfmConfig, queue_path: &Path) -> Result<Option<Self>> {
+        if !config.enabled {
+            return Ok(None);
+        }
+        if config.session_key.is_none() {
+            return Err(anyhow::anyhow!("last.fm scrobbling is enabled but not authorized - run `lastfm-auth`"));
+        }
+        let client = LastfmClient::new(config)?;
+        let queue = ScrobbleQueue::load(queue_path);
+        let (messages, message_rx) = mpsc::unbounded_channel();
+        let task = tokio::spawn(run(client, queue, message_rx));
+        Ok(Some(Self { messages, task }))
+    }
+
+    /// Tell last.fm `track` just started; tracks without artist/title tags are skipped
+    pub fn now_playing(&self, track: &Track) {
+        if let Some(track) = ScrobbleTrack::from_track(track) {
+            let _ = self.messages.send(ScrobbleMessage::NowPlaying(track));
+        }
+    }
+
+    /// Queue a scrobble for a finished session if it passed the threshold
+    pub fn session_finished(&self, track: &Track, session: &PlaySession) {
+        if let Some(scrobble) = Scrobble::from_session(track, session) {
+            let _ = self.messages.send(ScrobbleMessage::Scrobble(scrobble));
+        }
+    }
+
+    /// Stop, giving a last flush a moment; whatever isn't sent stays queued on disk
+    pub async fn shutdown(self) {
+        drop(self.messages);
+        let _ = tokio::time::timeout(Duration::from_secs(3), self.task).await;
+    }
+}
+
+async fn run(client: LastfmClient, mut queue: ScrobbleQueue, mut messages: mpsc::UnboundedReceiver<ScrobbleMessage>) {
+    // Fires right away too, which sends whatever an earlier session couldn't
+    let mut retry = tokio::time::interval(RETRY_INTERVAL);
+    retry.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            message = messages.recv() => match message {
+                Some(ScrobbleMessage::NowPlaying(track)) => {
+                    if let Err(e) = client.update_now_playing(&track).await {
+                        debug!("📻 Now playing update failed: {}", e);
+                    }
+                }
+                Some(ScrobbleMessage::Scrobble(scrobble)) => {
+                    queue.push(scrobble);
+                    if let Err(e) = queue.save() {
+                        warn!("📻 Couldn't save the scrobble queue: {}", e);
+                    }
+                    flush(&client, &mut queue).await;
+                }
+                None => break,
+            },
+            _ = retry.tick(), if !queue.is_empty() => flush(&client, &mut queue).await,
+        }
+    }
+}
+
+/// Send queued scrobbles in batches until the queue is empty or last.fm can't take them right now
+async fn flush(client: &LastfmClient, queue: &mut ScrobbleQueue) {
+    while !queue.is_empty() {
+        let batch = &queue.pending()[..queue.pending().len().min(BATCH_SIZE)];
+        let sent = batch.len();
+        match client.scrobble(batch).await {
+            Ok(()) => queue.remove_front(sent),
+            Err(e) if is_retryable(&e) => {
+                debug!("📻 Scrobbling later, {} queued: {}", queue.pending().len(), e);
+                break;
+            }
+            Err(e) => {
+                warn!("📻 last.fm rejected {} scrobbles: {}", sent, e);
+                queue.remove_front(sent);
+            }
+        }
+    }
+    if let Err(e) = queue.save() {
+        warn!("📻 Couldn't save the scrobble queue: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_md5_and_signature() {
+        assert_eq!(md5_hex(b""), "d41d8cd98f00b204e9800998ecf8427e");
+        assert_eq!(md5_hex(b"The quick brown fox jumps over the lazy dog"), "9e107d9d372bb6826bd81d3542a419d6");
+        assert_eq!(md5_hex(&[b'a'; 100]), "36a92cc94a9e0fa21f625f8bfb007adf");
+
+        let params = BTreeMap::from([
+            ("method", "auth.getSession".to_string()),
+            ("api_key", "key".to_string()),
+            ("token", "tok".to_string()),
+            ("format", "json".to_string()),
+        ]);
+        assert_eq!(api_signature(&params, "secret"), md5_hex(b"api_keykeymethodauth.getSessiontokentoksecret"));
+    }
+
+    #[test]
+    fn test_scrobble_threshold() {
+        assert!(!should_scrobble(30, 30)); // too short to ever count
+        assert!(!should_scrobble(89, 180));
+        assert!(should_scrobble(90, 180));
+        assert!(should_scrobble(240, 1200)); // 4 minutes of a long track is enough
+        assert!(!should_scrobble(239, 1200));
+    }
+
+    #[test]
+    fn test_scrobbles_come_from_tagged_sessions() {
+        let mut track = Track::test_fixture("/music/song.mp3").with_artist("Artist").with_title("Song").with_duration_secs(200);
+        let started_at = Utc::now();
+        let session = |play_duration, track_duration| PlaySession {
+            session_id: Uuid::new_v4(),
+            track_id: track.id,
+            started_at,
+            ended_at: None,
+            play_duration,
+            track_duration,
+            skip_reason: None,
+            completion_percentage: 0.0,
+        };
+
+        let scrobble = Scrobble::from_session(&track, &session(120, 200)).unwrap();
+        assert_eq!(scrobble.timestamp, started_at.timestamp());
+        assert_eq!(scrobble.track.duration_secs, Some(200));
+        assert!(Scrobble::from_session(&track, &session(60, 200)).is_none());
+        // Completed against a too-short duration guess still counts as a full play
+        assert!(Scrobble::from_session(&track, &session(90, 90)).is_some());
+
+        track.metadata.artist = None;
+        assert!(Scrobble::from_session(&track, &session(200, 200)).is_none());
+    }
+
+    #[test]
+    fn test_queue_survives_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("scrobble_queue.json");
+        let scrobble = |timestamp| Scrobble {
+            track: ScrobbleTrack { artist: "A".into(), title: "T".into(), album: None, duration_secs: None },
+            timestamp,
+        };
+
+        let mut queue = ScrobbleQueue::load(&path);
+        queue.push(scrobble(1));
+        queue.push(scrobble(2));
+        queue.save().unwrap();
+
+        let mut reloaded = ScrobbleQueue::load(&path);
+        assert_eq!(reloaded.pending(), &[scrobble(1), scrobble(2)]);
+        reloaded.remove_front(2);
+        reloaded.save().unwrap();
+        assert!(!path.exists());
+        assert!(ScrobbleQueue::load(&path).is_empty());
+    }
+
+    #[test]
+    fn test_retryable_errors() {
+        assert!(is_retryable(&anyhow::anyhow!("connection refused")));
+        assert!(is_retryable(&LastfmError { code: 16, message: String::new() }.into()));
+        assert!(!is_retryable(&LastfmError { code: 6, message: String::new() }.into()));
+    }
+}
@@ -1,5 +1,5 @@
 use super::{AppEvent, EventHandler, TerminalManager};
-use crate::audio::{AudioPlayer, MusicScanner, PlaybackState, Track};
+use crate::audio::{AudioPlayer, PlaybackState, Track};
 use crate::behavior::{BehaviorDatabase, BehaviorTracker, PlaybackEvent, SkipReason};
 use crate::config::Config;
 use anyhow::Result;
@@ -57,14 +57,16 @@ impl App {
     pub async fn new(config: Config) -> Result<Self> {
         let terminal = TerminalManager::new()?;
         let event_handler = EventHandler::new();
-        let audio_player = AudioPlayer::new(Default::default())?;
+        let audio_player = AudioPlayer::new(config.clone().into())?;
         
         // Initialize behavior database
         let database = BehaviorDatabase::new(&config.database_path)?;
-        let behavior_tracker = BehaviorTracker::new(database, config.behavior.min_play_time_for_tracking);
+        let behavior_tracker = BehaviorTracker::new(database, config.behavior.min_play_time_for_tracking)
+            .with_skip_threshold(config.behavior.skip_threshold_seconds)
+            .with_decay_days(config.behavior.weight_decay_days);
         
         // Scan music library
-        let scanner = MusicScanner::new();
+        let scanner = config.library.build_scanner()?;
         let tracks = scanner.scan_directories(&config.music_directories)?;
         
         let mut list_state = ListState::default();
@@ -72,6 +74,8 @@ impl App {
             list_state.select(Some(0));
         }
         
+        let volume = audio_player.get_volume();
+        
         Ok(Self {
             config,
             terminal,
@@ -83,7 +87,7 @@ impl App {
             list_state,
             should_quit: false,
             current_tab: Tab::Library,
-            volume: 0.7,
+            volume,
             is_shuffled: false,
             repeat_mode: RepeatMode::Off,
         })
@@ -136,7 +140,7 @@ impl App {
                         if let Some(track) = self.get_current_track() {
                             let _ = self.behavior_tracker.handle_event(PlaybackEvent::TrackPaused {
                                 track_id: track.id,
-                                position: 0, // TODO: Get actual position
+                                position: self.audio_player.get_position().map(|p| p.as_secs()).unwrap_or(0),
                                 timestamp: chrono::Utc::now(),
                             }).await;
                         }
@@ -146,7 +150,7 @@ impl App {
                         if let Some(track) = self.get_current_track() {
                             let _ = self.behavior_tracker.handle_event(PlaybackEvent::TrackResumed {
                                 track_id: track.id,
-                                position: 0, // TODO: Get actual position
+                                position: self.audio_player.get_position().map(|p| p.as_secs()).unwrap_or(0),
                                 timestamp: chrono::Utc::now(),
                             }).await;
                         }
@@ -221,7 +225,7 @@ impl App {
             if let Some(track) = self.get_current_track() {
                 let _ = self.behavior_tracker.handle_event(PlaybackEvent::TrackSkipped {
                     track_id: track.id,
-                    position: 0, // TODO: Get actual position
+                    position: self.audio_player.get_position().map(|p| p.as_secs()).unwrap_or(0),
                     reason: SkipReason::NextTrack,
                     timestamp: chrono::Utc::now(),
                 }).await;
@@ -266,7 +270,7 @@ impl App {
     }
     
     async fn refresh_library(&mut self) -> Result<()> {
-        let scanner = MusicScanner::new();
+        let scanner = self.config.library.build_scanner()?;
         self.tracks = scanner.scan_directories(&self.config.music_directories)?;
         
         if !self.tracks.is_empty() && self.list_state.selected().is_none() {
@@ -62,11 +62,9 @@ impl EventHandler {
         loop {
             if event::poll(Duration::from_millis(50))? {
                 match event::read()? {
-                    Event::Key(key) => {
-                        if key.kind == KeyEventKind::Press {
-                            if let Some(app_event) = self.key_to_app_event(key) {
-                                let _ = self.event_sender.send(app_event);
-                            }
+                    Event::Key(key) if key.kind == KeyEventKind::Press => {
+                        if let Some(app_event) = self.key_to_app_event(key) {
+                            let _ = self.event_sender.send(app_event);
                         }
                     }
                     Event::Resize(_, _) => {
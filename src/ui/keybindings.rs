@@ -0,0 +1,577 @@
+// Keybindings - which key runs which command, overridable from the [keybindings] config section
+// Every command has a stable name so config files and the help text can refer to it
+
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// A key and the modifiers that matter for it
+///
+/// Shift is folded into characters ('G', '?'), terminals don't agree on whether they report it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyCombo {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyCombo {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let mut code = code;
+        let mut modifiers = modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        if let KeyCode::Char(c) = code {
+            if modifiers.contains(KeyModifiers::SHIFT) {
+                code = KeyCode::Char(c.to_ascii_uppercase());
+            }
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+        Self { code, modifiers }
+    }
+
+    pub fn from_event(key: &KeyEvent) -> Self {
+        Self::new(key.code, key.modifiers)
+    }
+
+    /// Parse "q", "G", "space", "ctrl+s", "shift+up", "+" and the like
+    pub fn parse(text: &str) -> Result<Self> {
+        let (modifier_part, key_part) = if text == "+" {
+            ("", "+")
+        } else if let Some(prefix) = text.strip_suffix("++") {
+            (prefix, "+")
+        } else {
+            text.rsplit_once('+').unwrap_or(("", text))
+        };
+
+        let mut modifiers = KeyModifiers::NONE;
+        for modifier in modifier_part.split('+').filter(|m| !m.is_empty()) {
+            modifiers |= match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(anyhow::anyhow!("Unknown modifier '{}' in key '{}'", modifier, text)),
+            };
+        }
+
+        let mut chars = key_part.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match key_part.to_lowercase().as_str() {
+                "space" => KeyCode::Char(' '),
+                "enter" | "return" => KeyCode::Enter,
+                "tab" => KeyCode::Tab,
+                "esc" | "escape" => KeyCode::Esc,
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" => KeyCode::Insert,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                name => match name.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                    Some(n @ 1..=12) => KeyCode::F(n),
+                    _ => return Err(anyhow::anyhow!("Unknown key '{}'", text)),
+                },
+            },
+        };
+
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+/// Written the way `parse` reads it, so it can be pasted into config.toml
+impl fmt::Display for KeyCombo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "ctrl+"),
+            (KeyModifiers::ALT, "alt+"),
+            (KeyModifiers::SHIFT, "shift+"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                f.write_str(name)?;
+            }
+        }
+
+        match self.code {
+            KeyCode::Char(' ') => f.write_str("space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::Enter => f.write_str("enter"),
+            KeyCode::Tab => f.write_str("tab"),
+            KeyCode::Esc => f.write_str("esc"),
+            KeyCode::Backspace => f.write_str("backspace"),
+            KeyCode::Delete => f.write_str("delete"),
+            KeyCode::Insert => f.write_str("insert"),
+            KeyCode::Home => f.write_str("home"),
+            KeyCode::End => f.write_str("end"),
+            KeyCode::PageUp => f.write_str("pageup"),
+            KeyCode::PageDown => f.write_str("pagedown"),
+            KeyCode::Up => f.write_str("up"),
+            KeyCode::Down => f.write_str("down"),
+            KeyCode::Left => f.write_str("left"),
+            KeyCode::Right => f.write_str("right"),
+            KeyCode::F(n) => write!(f, "f{}", n),
+            other => write!(f, "{:?}", other),
+        }
+    }
+}
+
+/// Where a binding applies; a tab's own bindings win over global ones
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyScope {
+    Global,
+    Library,
+    Playlists,
+    Metadata,
+    Settings,
+    Stats,
+}
+
+/// The help overlay's sections, in the order it shows them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyCategory {
+    Navigation,
+    Playback,
+    Library,
+    Playlists,
+    Metadata,
+    Settings,
+    Stats,
+}
+
+impl KeyCategory {
+    pub const ALL: [KeyCategory; 7] = [
+        KeyCategory::Navigation,
+        KeyCategory::Playback,
+        KeyCategory::Library,
+        KeyCategory::Playlists,
+        KeyCategory::Metadata,
+        KeyCategory::Settings,
+        KeyCategory::Stats,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            KeyCategory::Navigation => "Navigation",
+            KeyCategory::Playback => "Playback",
+            KeyCategory::Library => "Library",
+            KeyCategory::Playlists => "Playlists",
+            KeyCategory::Metadata => "Metadata Editor",
+            KeyCategory::Settings => "Settings",
+            KeyCategory::Stats => "Stats",
+        }
+    }
+}
+
+/// Every command a key can be bound to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Quit,
+    ShowHelp,
+    Search,
+    TabLibrary,
+    TabPlaylists,
+    TabMetadata,
+    TabSettings,
+    TabStats,
+    Up,
+    Down,
+    GotoTop, // first half of `gg`
+    JumpToTop,
+    JumpToBottom,
+    HalfPageDown,
+    HalfPageUp,
+    PageDown,
+    PageUp,
+    Play,
+    PlayPause,
+    NextTrack,
+    PreviousTrack,
+    Stop,
+    VolumeUp,
+    VolumeDown,
+    Mute,
+    Shuffle,
+    Repeat,
+    SpeedDown,
+    SpeedUp,
+    SpeedReset,
+    RemainingTime,
+    Lyrics,
+    LoopStart,
+    LoopEnd,
+    LoopClear,
+    SleepTimer,
+    SleepTimerCancel,
+    StopAfterCurrent,
+    AddBookmark,
+    Bookmarks,
+    Chapters,
+    NextChapter,
+    PreviousChapter,
+    History,
+    ErrorLog,
+    RevealFile,
+    SaveAll,
+    ResumeLastTrack,
+    AddToPlaylist,
+    ToggleMark,
+    MarkAll,
+    CycleSort,
+    SortDirection,
+    CycleTagFilter,
+    ClearTagFilter,
+    LibraryView,
+    BehaviorDetail,
+    ResetTrackBehavior,
+    PlayAlbum,
+    ExcludeFromShuffle,
+    MoreLikeThis,
+    ReloadTrack,
+    SmartMix,
+    TogglePlaylist,
+    LoadPlaylist,
+    RenamePlaylist,
+    RemoveFromPlaylist,
+    DeletePlaylist,
+    MoveTrackUp,
+    MoveTrackDown,
+    PlaylistShuffle,
+    PlaylistRepeat,
+    EditTitle,
+    EditArtist,
+    SaveMetadata,
+    ApplySuggestion,
+    BulkApplySuggestions,
+    ClearMetadata,
+    RenameFiles,
+    LookupMetadata,
+    EqBandPrevious,
+    EqBandNext,
+    EqGainDown,
+    EqGainUp,
+    EqPreset,
+    ChannelMode,
+    OutputDevice,
+    Theme,
+    ScanReport,
+    FindDuplicates,
+    ExportPlaylists,
+    Visualizer,
+    ResetAllBehavior,
+    ExcludedTracks,
+    ExportHistory,
+}
+
+/// An action's config name, help section, where it applies, what it does and its default keys
+pub struct ActionInfo {
+    pub action: Action,
+    pub name: &'static str,
+    pub category: KeyCategory,
+    pub scopes: &'static [KeyScope],
+    pub description: &'static str,
+    pub default_keys: &'static [&'static str],
+}
+
+const fn info(
+    action: Action,
+    name: &'static str,
+    category: KeyCategory,
+    scopes: &'static [KeyScope],
+    default_keys: &'static [&'static str],
+    description: &'static str,
+) -> ActionInfo {
+    ActionInfo { action, name, category, scopes, description, default_keys }
+}
+
+const GLOBAL: &[KeyScope] = &[KeyScope::Global];
+const LIBRARY: &[KeyScope] = &[KeyScope::Library];
+const PLAYLISTS: &[KeyScope] = &[KeyScope::Playlists];
+const METADATA: &[KeyScope] = &[KeyScope::Metadata];
+const SETTINGS: &[KeyScope] = &[KeyScope::Settings];
+const STATS: &[KeyScope] = &[KeyScope::Stats];
+
+/// The built-in layout, in the order the help lists it
+pub const ACTIONS: &[ActionInfo] = &[
+    info(Action::Quit, "quit", KeyCategory::Navigation, GLOBAL, &["q", "ctrl+c"], "Quit"),
+    info(Action::ShowHelp, "help", KeyCategory::Navigation, GLOBAL, &["?"], "Show or hide the help overlay"),
+    info(Action::Search, "search", KeyCategory::Navigation, GLOBAL, &["/"], "Search the library or playlists"),
+    info(Action::TabLibrary, "tab_library", KeyCategory::Navigation, GLOBAL, &["1"], "Library tab"),
+    info(Action::TabPlaylists, "tab_playlists", KeyCategory::Navigation, GLOBAL, &["2"], "Playlists tab"),
+    info(Action::TabMetadata, "tab_metadata", KeyCategory::Navigation, GLOBAL, &["3"], "Metadata editor tab"),
+    info(Action::TabSettings, "tab_settings", KeyCategory::Navigation, GLOBAL, &["4"], "Settings tab"),
+    info(Action::TabStats, "tab_stats", KeyCategory::Navigation, GLOBAL, &["5"], "Listening stats tab"),
+    info(Action::Up, "up", KeyCategory::Navigation, GLOBAL, &["up", "k"], "Move up"),
+    info(Action::Down, "down", KeyCategory::Navigation, GLOBAL, &["down", "j"], "Move down"),
+    info(Action::GotoTop, "goto_top", KeyCategory::Navigation, GLOBAL, &["g"], "Press twice to jump to the top"),
+    info(Action::JumpToTop, "jump_to_top", KeyCategory::Navigation, GLOBAL, &["home"], "Jump to the top"),
+    info(Action::JumpToBottom, "jump_to_bottom", KeyCategory::Navigation, GLOBAL, &["G", "end"], "Jump to the bottom"),
+    info(Action::HalfPageDown, "half_page_down", KeyCategory::Navigation, GLOBAL, &["ctrl+d"], "Half a page down"),
+    info(Action::HalfPageUp, "half_page_up", KeyCategory::Navigation, GLOBAL, &["ctrl+u"], "Half a page up"),
+    info(Action::PageDown, "page_down", KeyCategory::Navigation, GLOBAL, &["pagedown"], "A page down"),
+    info(Action::PageUp, "page_up", KeyCategory::Navigation, GLOBAL, &["pageup"], "A page up"),
+    info(Action::Play, "play", KeyCategory::Playback, GLOBAL, &["enter"], "Play the selected track"),
+    info(Action::PlayPause, "play_pause", KeyCategory::Playback, GLOBAL, &["space"], "Pause or resume"),
+    info(Action::NextTrack, "next_track", KeyCategory::Playback, GLOBAL, &["n"], "Next track"),
+    info(Action::PreviousTrack, "previous_track", KeyCategory::Playback, GLOBAL, &["p"], "Previous track"),
+    info(Action::Stop, "stop", KeyCategory::Playback, GLOBAL, &["s"], "Stop"),
+    info(Action::VolumeUp, "volume_up", KeyCategory::Playback, GLOBAL, &["+", "="], "Volume up"),
+    info(Action::VolumeDown, "volume_down", KeyCategory::Playback, GLOBAL, &["-"], "Volume down"),
+    info(Action::Mute, "mute", KeyCategory::Playback, GLOBAL, &["m"], "Mute, again to restore the volume"),
+    info(Action::Shuffle, "shuffle", KeyCategory::Playback, GLOBAL, &["z"], "Toggle shuffle"),
+    info(Action::Repeat, "repeat", KeyCategory::Playback, GLOBAL, &["r"], "Cycle repeat mode"),
+    info(Action::SpeedDown, "speed_down", KeyCategory::Playback, GLOBAL, &["<"], "Slower playback (pitch follows speed)"),
+    info(Action::SpeedUp, "speed_up", KeyCategory::Playback, GLOBAL, &[">"], "Faster playback (pitch follows speed)"),
+    info(Action::SpeedReset, "speed_reset", KeyCategory::Playback, GLOBAL, &["|"], "Back to normal speed"),
+    info(Action::RemainingTime, "remaining_time", KeyCategory::Playback, GLOBAL, &["ctrl+t"], "Show the time left instead of the length"),
+    info(Action::Lyrics, "lyrics", KeyCategory::Playback, GLOBAL, &["Y"], "Show or hide the lyrics pane"),
+    info(Action::LoopStart, "loop_start", KeyCategory::Playback, GLOBAL, &["["], "Set loop point A"),
+    info(Action::LoopEnd, "loop_end", KeyCategory::Playback, GLOBAL, &["]"], "Set loop point B"),
+    info(Action::LoopClear, "loop_clear", KeyCategory::Playback, GLOBAL, &["\\"], "Clear the A-B loop"),
+    info(Action::SleepTimer, "sleep_timer", KeyCategory::Playback, GLOBAL, &["T"], "Cycle the sleep timer"),
+    info(Action::SleepTimerCancel, "sleep_timer_cancel", KeyCategory::Playback, GLOBAL, &["0"], "Cancel the sleep timer"),
+    info(Action::StopAfterCurrent, "stop_after_current", KeyCategory::Playback, GLOBAL, &["S"], "Stop when the current track ends"),
+    info(Action::AddBookmark, "add_bookmark", KeyCategory::Playback, GLOBAL, &["B"], "Bookmark the current position"),
+    info(Action::Bookmarks, "bookmarks", KeyCategory::Playback, GLOBAL, &["'"], "Jump to a bookmark"),
+    info(Action::Chapters, "chapters", KeyCategory::Playback, GLOBAL, &["K"], "Chapters of the audiobook playing"),
+    info(Action::NextChapter, "next_chapter", KeyCategory::Playback, GLOBAL, &["."], "Next chapter"),
+    info(Action::PreviousChapter, "previous_chapter", KeyCategory::Playback, GLOBAL, &[","], "Start of the chapter, again for the one before"),
+    info(Action::ErrorLog, "error_log", KeyCategory::Playback, GLOBAL, &["!"], "Recent warnings and errors, audio ones included"),
+    info(Action::SaveAll, "save_all", KeyCategory::Navigation, GLOBAL, &["W"], "Write edited tags and unsaved playlists to disk"),
+    info(Action::RevealFile, "reveal_file", KeyCategory::Navigation, GLOBAL, &["ctrl+o"], "Show the selected (or playing) track's file in the file manager"),
+    info(Action::History, "history", KeyCategory::Playback, GLOBAL, &["H"], "Recently played tracks, to play one again"),
+    info(Action::ResumeLastTrack, "resume_last_track", KeyCategory::Playback, GLOBAL, &["R"], "Resume where the last session stopped"),
+    info(Action::AddToPlaylist, "add_to_playlist", KeyCategory::Library, LIBRARY, &["a"], "Add the track (or marked tracks) to a playlist"),
+    info(Action::ToggleMark, "toggle_mark", KeyCategory::Library, LIBRARY, &["v"], "Mark a track for a bulk add"),
+    info(Action::MarkAll, "mark_all", KeyCategory::Library, LIBRARY, &["V"], "Mark every shown track, again to clear"),
+    info(Action::CycleSort, "cycle_sort", KeyCategory::Library, LIBRARY, &["o"], "Cycle the sort order"),
+    info(Action::SortDirection, "sort_direction", KeyCategory::Library, LIBRARY, &["O"], "Reverse the sort"),
+    info(Action::CycleTagFilter, "cycle_tag_filter", KeyCategory::Library, LIBRARY, &["f"], "Cycle the tag filter"),
+    info(Action::ClearTagFilter, "clear_tag_filter", KeyCategory::Library, LIBRARY, &["F"], "Clear the tag filter"),
+    info(Action::LibraryView, "library_view", KeyCategory::Library, LIBRARY, &["tab"], "Flat list, by artist or by album"),
+    info(Action::BehaviorDetail, "behavior_detail", KeyCategory::Library, LIBRARY, &["i"], "Why shuffle weighs the track the way it does"),
+    info(Action::ResetTrackBehavior, "reset_track_behavior", KeyCategory::Library, LIBRARY, &["X"], "Forget the track's plays, skips and tags"),
+    info(Action::PlayAlbum, "play_album", KeyCategory::Library, LIBRARY, &["A"], "Play the track's album from it, in track order"),
+    info(Action::ExcludeFromShuffle, "exclude_from_shuffle", KeyCategory::Library, LIBRARY, &["E"], "Keep the track out of shuffle, again to let it back in"),
+    info(Action::MoreLikeThis, "more_like_this", KeyCategory::Library, LIBRARY, &["L"], "Tracks like this one, to play or queue"),
+    info(Action::ReloadTrack, "reload_track", KeyCategory::Library, LIBRARY, &["ctrl+r"], "Re-read the track's tags, length and hash from disk"),
+    info(Action::SmartMix, "smart_mix", KeyCategory::Playlists, &[KeyScope::Library, KeyScope::Playlists], &["M"], "Generate a Smart Mix"),
+    info(Action::TogglePlaylist, "toggle_playlist", KeyCategory::Playlists, PLAYLISTS, &["enter"], "Expand or collapse a playlist"),
+    info(Action::LoadPlaylist, "load_playlist", KeyCategory::Playlists, PLAYLISTS, &["l"], "Play the playlist from the start"),
+    info(Action::RenamePlaylist, "rename_playlist", KeyCategory::Playlists, PLAYLISTS, &["r"], "Rename the playlist"),
+    info(Action::RemoveFromPlaylist, "remove_from_playlist", KeyCategory::Playlists, PLAYLISTS, &["x"], "Remove the track from the playlist"),
+    info(Action::DeletePlaylist, "delete_playlist", KeyCategory::Playlists, PLAYLISTS, &["delete"], "Delete the playlist"),
+    info(Action::MoveTrackUp, "move_track_up", KeyCategory::Playlists, PLAYLISTS, &["shift+up"], "Move the track up"),
+    info(Action::MoveTrackDown, "move_track_down", KeyCategory::Playlists, PLAYLISTS, &["shift+down"], "Move the track down"),
+    info(Action::PlaylistShuffle, "playlist_shuffle", KeyCategory::Playlists, PLAYLISTS, &["Z"], "The playlist's own shuffle: as the app, always or never"),
+    info(Action::PlaylistRepeat, "playlist_repeat", KeyCategory::Playlists, PLAYLISTS, &["ctrl+r"], "The playlist's own repeat: as the app, off, all or one"),
+    info(Action::EditTitle, "edit_title", KeyCategory::Metadata, METADATA, &["t"], "Edit the title"),
+    info(Action::EditArtist, "edit_artist", KeyCategory::Metadata, METADATA, &["a"], "Edit the artist"),
+    info(Action::SaveMetadata, "save_metadata", KeyCategory::Metadata, METADATA, &["ctrl+s"], "Save the edit"),
+    info(Action::ApplySuggestion, "apply_suggestion", KeyCategory::Metadata, METADATA, &["tab"], "Apply the parsed suggestion"),
+    info(Action::BulkApplySuggestions, "bulk_apply_suggestions", KeyCategory::Metadata, METADATA, &["b"], "Apply suggestions to every track"),
+    info(Action::ClearMetadata, "clear_metadata", KeyCategory::Metadata, METADATA, &["c"], "Clear the title and artist"),
+    info(Action::RenameFiles, "rename_files", KeyCategory::Metadata, METADATA, &["N"], "Rename every file after its tags"),
+    info(Action::LookupMetadata, "lookup_metadata", KeyCategory::Metadata, METADATA, &["L"], "Look the track up on MusicBrainz"),
+    info(Action::EqBandPrevious, "eq_band_previous", KeyCategory::Settings, SETTINGS, &["left"], "Previous equalizer band"),
+    info(Action::EqBandNext, "eq_band_next", KeyCategory::Settings, SETTINGS, &["right"], "Next equalizer band"),
+    info(Action::EqGainDown, "eq_gain_down", KeyCategory::Settings, SETTINGS, &["["], "Lower the band's gain"),
+    info(Action::EqGainUp, "eq_gain_up", KeyCategory::Settings, SETTINGS, &["]"], "Raise the band's gain"),
+    info(Action::EqPreset, "eq_preset", KeyCategory::Settings, SETTINGS, &["e"], "Cycle equalizer presets"),
+    info(Action::ChannelMode, "channel_mode", KeyCategory::Settings, SETTINGS, &["C"], "Cycle stereo, mono and crossfeed"),
+    info(Action::OutputDevice, "output_device", KeyCategory::Settings, SETTINGS, &["o"], "Pick the output device"),
+    info(Action::Theme, "theme", KeyCategory::Settings, SETTINGS, &["t"], "Cycle the theme"),
+    info(Action::ScanReport, "scan_report", KeyCategory::Settings, SETTINGS, &["d"], "Show duplicates and unreadable files"),
+    info(Action::FindDuplicates, "find_duplicates", KeyCategory::Settings, SETTINGS, &["D"], "Review duplicate tracks, keep one copy of each"),
+    info(Action::ExportPlaylists, "export_playlists", KeyCategory::Settings, SETTINGS, &["P"], "Export every playlist as M3U"),
+    info(Action::Visualizer, "visualizer", KeyCategory::Settings, SETTINGS, &["v"], "Show or hide the spectrum visualizer"),
+    info(Action::ResetAllBehavior, "reset_all_behavior", KeyCategory::Settings, SETTINGS, &["X"], "Forget all listening behavior"),
+    info(Action::ExcludedTracks, "excluded_tracks", KeyCategory::Settings, SETTINGS, &["E"], "Tracks kept out of shuffle"),
+    info(Action::ExportHistory, "export_history", KeyCategory::Stats, STATS, &["e"], "Export the listening history"),
+];
+
+impl Action {
+    pub fn info(self) -> &'static ActionInfo {
+        ACTIONS.iter()
+            .find(|info| info.action == self)
+            .expect("every action is listed in ACTIONS")
+    }
+}
+
+/// The active bindings: the defaults with the `[keybindings]` overrides applied
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: HashMap<(KeyScope, KeyCombo), Action>,
+    keys: HashMap<Action, Vec<KeyCombo>>,
+}
+
+impl Keymap {
+    /// Build from `overrides`, action name → keys; an action listed there loses its default keys
+    ///
+    /// Fails on unknown action names, unreadable keys, and one key bound twice in the same scope.
+    pub fn new(overrides: &BTreeMap<String, Vec<String>>) -> Result<Self> {
+        if let Some(unknown) = overrides.keys().find(|name| !ACTIONS.iter().any(|info| info.name == name.as_str())) {
+            return Err(anyhow::anyhow!("Unknown action '{}' in [keybindings]", unknown));
+        }
+
+        let mut keymap = Self { bindings: HashMap::new(), keys: HashMap::new() };
+        for info in ACTIONS {
+            let keys = match overrides.get(info.name) {
+                Some(keys) => keys.iter()
+                    .map(|key| KeyCombo::parse(key))
+                    .collect::<Result<Vec<_>>>()
+                    .map_err(|e| anyhow::anyhow!("[keybindings] {}: {}", info.name, e))?,
+                None => info.default_keys.iter()
+                    .map(|key| KeyCombo::parse(key).expect("default keys parse"))
+                    .collect(),
+            };
+
+            for &combo in &keys {
+                for &scope in info.scopes {
+                    if let Some(other) = keymap.bindings.insert((scope, combo), info.action) {
+                        return Err(anyhow::anyhow!(
+                            "[keybindings] '{}' is bound to both {} and {}",
+                            combo, other.info().name, info.name
+                        ));
+                    }
+                }
+            }
+            keymap.keys.insert(info.action, keys);
+        }
+        Ok(keymap)
+    }
+
+    /// The action `combo` runs on a tab, preferring the tab's own bindings over global ones
+    pub fn lookup(&self, combo: KeyCombo, scope: KeyScope) -> Option<Action> {
+        self.bindings.get(&(scope, combo))
+            .or_else(|| self.bindings.get(&(KeyScope::Global, combo)))
+            .copied()
+    }
+
+    /// Keys currently bound to `action`, empty if it was unbound
+    pub fn keys_for(&self, action: Action) -> &[KeyCombo] {
+        self.keys.get(&action).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// `action`'s keys for display, e.g. "+ / ="
+    pub fn keys_label(&self, action: Action) -> String {
+        match self.keys_for(action) {
+            [] => "unbound".to_string(),
+            keys => keys.iter().map(ToString::to_string).collect::<Vec<_>>().join(" / "),
+        }
+    }
+
+    /// What each key does on a tab, grouped by help section
+    ///
+    /// Keys the tab takes over from a global action are left out, and so are actions with no key left.
+    pub fn help(&self, scope: KeyScope) -> Vec<(KeyCategory, Vec<(String, &'static str)>)> {
+        KeyCategory::ALL.iter()
+            .map(|&category| {
+                let entries = ACTIONS.iter()
+                    .filter(|info| info.category == category)
+                    .filter_map(|info| {
+                        let reachable = self.keys_for(info.action).iter()
+                            .filter(|&&combo| self.lookup(combo, scope) == Some(info.action))
+                            .map(ToString::to_string)
+                            .collect::<Vec<_>>();
+                        (!reachable.is_empty()).then(|| (reachable.join(" / "), info.description))
+                    })
+                    .collect::<Vec<_>>();
+                (category, entries)
+            })
+            .filter(|(_, entries)| !entries.is_empty())
+            .collect()
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::new(&BTreeMap::new()).expect("the default keybindings don't conflict")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overrides(pairs: &[(&str, &[&str])]) -> BTreeMap<String, Vec<String>> {
+        pairs.iter()
+            .map(|(name, keys)| (name.to_string(), keys.iter().map(|k| k.to_string()).collect()))
+            .collect()
+    }
+
+    fn combo(text: &str) -> KeyCombo {
+        KeyCombo::parse(text).unwrap()
+    }
+
+    #[test]
+    fn test_key_combo_parsing() {
+        assert_eq!(combo("ctrl+s"), KeyCombo::new(KeyCode::Char('s'), KeyModifiers::CONTROL));
+        assert_eq!(combo("Shift+Up"), KeyCombo::new(KeyCode::Up, KeyModifiers::SHIFT));
+        assert_eq!(combo("+"), KeyCombo::new(KeyCode::Char('+'), KeyModifiers::NONE));
+        assert_eq!(combo("ctrl++"), KeyCombo::new(KeyCode::Char('+'), KeyModifiers::CONTROL));
+        assert_eq!(combo("f5"), KeyCombo::new(KeyCode::F(5), KeyModifiers::NONE));
+
+        // Terminals may or may not report shift with a capital letter
+        assert_eq!(combo("G"), KeyCombo::new(KeyCode::Char('G'), KeyModifiers::SHIFT));
+        assert_eq!(combo("shift+g"), combo("G"));
+        assert_ne!(combo("G"), combo("g"));
+
+        for text in ["space", "ctrl+d", "shift+down", "pagedown", "?", "\\"] {
+            assert_eq!(combo(text).to_string(), text);
+        }
+        assert!(KeyCombo::parse("hyper+x").is_err());
+        assert!(KeyCombo::parse("f13").is_err());
+    }
+
+    #[test]
+    fn test_tab_bindings_win_over_global_ones() {
+        let keymap = Keymap::default();
+        assert_eq!(keymap.lookup(combo("r"), KeyScope::Library), Some(Action::Repeat));
+        assert_eq!(keymap.lookup(combo("r"), KeyScope::Playlists), Some(Action::RenamePlaylist));
+        assert_eq!(keymap.lookup(combo("M"), KeyScope::Playlists), Some(Action::SmartMix));
+        assert_eq!(keymap.lookup(combo("M"), KeyScope::Stats), None);
+    }
+
+    #[test]
+    fn test_overrides_replace_defaults() {
+        let keymap = Keymap::new(&overrides(&[("next_track", &["l", "ctrl+n"]), ("stop", &[])])).unwrap();
+        assert_eq!(keymap.lookup(combo("l"), KeyScope::Library), Some(Action::NextTrack));
+        assert_eq!(keymap.lookup(combo("l"), KeyScope::Playlists), Some(Action::LoadPlaylist));
+        assert_eq!(keymap.lookup(combo("n"), KeyScope::Library), None);
+        assert_eq!(keymap.lookup(combo("s"), KeyScope::Library), None);
+        assert!(keymap.keys_for(Action::Stop).is_empty());
+    }
+
+    #[test]
+    fn test_conflicts_are_rejected() {
+        let error = Keymap::new(&overrides(&[("stop", &["q"])])).unwrap_err().to_string();
+        assert!(error.contains("quit") && error.contains("stop"), "{}", error);
+
+        assert!(Keymap::new(&overrides(&[("mark_all", &["a"])])).is_err());
+        assert!(Keymap::new(&overrides(&[("no_such_action", &["x"])])).is_err());
+        assert!(Keymap::new(&overrides(&[("stop", &["meta+x"])])).is_err());
+    }
+
+    #[test]
+    fn test_help_follows_the_active_bindings() {
+        let keymap = Keymap::new(&overrides(&[("stop", &["x"])])).unwrap();
+        let describe = |scope, description| {
+            keymap.help(scope).into_iter()
+                .flat_map(|(_, entries)| entries)
+                .find(|(_, d)| *d == description)
+                .map(|(keys, _)| keys)
+        };
+
+        assert_eq!(describe(KeyScope::Library, "Stop"), Some("x".to_string()));
+        assert_eq!(describe(KeyScope::Library, "Volume up"), Some("+ / =".to_string()));
+        // On Playlists, x removes a track and r renames, so neither global action is reachable there
+        assert_eq!(describe(KeyScope::Playlists, "Stop"), None);
+        assert_eq!(describe(KeyScope::Playlists, "Cycle repeat mode"), None);
+
+        let categories = keymap.help(KeyScope::Metadata).into_iter().map(|(c, _)| c).collect::<Vec<_>>();
+        assert_eq!(categories, [KeyCategory::Navigation, KeyCategory::Playback, KeyCategory::Metadata]);
+    }
+}
@@ -4,9 +4,14 @@
 mod app;        // main application state and event loop
 mod components; // reusable UI widgets
 pub mod events; // keyboard/mouse event handling
+pub mod keybindings; // configurable key → action map
+pub mod notifications; // toast popups for confirmations and errors
+pub mod theme;  // color palettes selected by UiConfig.theme
 
 pub use app::App;
 pub use events::{AppEvent, EventHandler};
+pub use notifications::{ErrorLog, NotificationCenter, NotificationLevel};
+pub use theme::Theme;
 
 use anyhow::Result;
 use crossterm::{
@@ -31,17 +36,24 @@ struct CleanupGuard;
 
 impl Drop for CleanupGuard {
     fn drop(&mut self) {
-        // Force terminal cleanup - NO stdout usage to avoid stream conflicts!
-        let _ = disable_raw_mode();
-        
-        let mut stdout = io::stdout();
-        let _ = execute!(stdout, LeaveAlternateScreen, DisableMouseCapture);
-        
-        // Force cursor show
-        let _ = execute!(stdout, cursor::Show);
+        restore_terminal();
     }
 }
 
+/// Leave raw mode and the alternate screen; safe to call more than once
+///
+/// Also used from the panic hook, where Drop impls haven't run yet.
+pub fn restore_terminal() {
+    // Force terminal cleanup - NO stdout usage to avoid stream conflicts!
+    let _ = disable_raw_mode();
+    
+    let mut stdout = io::stdout();
+    let _ = execute!(stdout, LeaveAlternateScreen, DisableMouseCapture);
+    
+    // Force cursor show
+    let _ = execute!(stdout, cursor::Show);
+}
+
 impl TerminalManager {
     pub fn new() -> Result<Self> {
         // Ensure clean terminal state first
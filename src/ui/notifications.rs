@@ -0,0 +1,140 @@
+// Toast notifications - short-lived popups for things worth noticing
+// The status bar still handles quick hints, toasts are for confirmations and errors
+// Warnings and errors also land in an error log that outlives the toasts
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant, SystemTime};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NotificationLevel {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub level: NotificationLevel,
+    pub text: String,
+    pub created_at: Instant,
+}
+
+/// Keeps the active toasts, newest last, dropping them once they expire
+#[derive(Debug, Clone)]
+pub struct NotificationCenter {
+    notifications: VecDeque<Notification>,
+    duration: Duration,
+    max_visible: usize,
+}
+
+impl NotificationCenter {
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            notifications: VecDeque::new(),
+            duration,
+            max_visible: 4,
+        }
+    }
+    
+    pub fn push(&mut self, level: NotificationLevel, text: impl Into<String>) {
+        self.notifications.push_back(Notification {
+            level,
+            text: text.into(),
+            created_at: Instant::now(),
+        });
+        
+        // Oldest toasts make room for new ones
+        while self.notifications.len() > self.max_visible {
+            self.notifications.pop_front();
+        }
+    }
+    
+    /// Drop expired toasts - call once per frame
+    pub fn prune(&mut self) {
+        let duration = self.duration;
+        self.notifications.retain(|n| n.created_at.elapsed() < duration);
+    }
+    
+    pub fn visible(&self) -> impl Iterator<Item = &Notification> {
+        self.notifications.iter()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.notifications.is_empty()
+    }
+}
+
+/// One error log line; the same message arriving again in a row bumps its count instead
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub level: NotificationLevel,
+    pub text: String,
+    pub last_seen: SystemTime,
+    pub count: usize,
+}
+
+/// Recent warnings and errors, oldest dropped once `capacity` is reached
+#[derive(Debug, Clone)]
+pub struct ErrorLog {
+    entries: VecDeque<LogEntry>,
+    capacity: usize,
+}
+
+impl ErrorLog {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            capacity: capacity.max(1),
+        }
+    }
+    
+    pub fn record(&mut self, level: NotificationLevel, text: impl Into<String>) {
+        let text = text.into();
+        let now = SystemTime::now();
+        
+        // Underruns and the like come in bursts, one line with a count reads better
+        if let Some(last) = self.entries.back_mut().filter(|e| e.level == level && e.text == text) {
+            last.count += 1;
+            last.last_seen = now;
+            return;
+        }
+        
+        self.entries.push_back(LogEntry { level, text, last_seen: now, count: 1 });
+        while self.entries.len() > self.capacity {
+            self.entries.pop_front();
+        }
+    }
+    
+    /// Newest first
+    pub fn entries(&self) -> impl Iterator<Item = &LogEntry> {
+        self.entries.iter().rev()
+    }
+    
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_log_folds_repeats_and_caps() {
+        let mut log = ErrorLog::new(2);
+        log.record(NotificationLevel::Warning, "ALSA underrun");
+        log.record(NotificationLevel::Warning, "ALSA underrun");
+        assert_eq!(log.len(), 1);
+        assert_eq!(log.entries().next().unwrap().count, 2);
+        
+        log.record(NotificationLevel::Error, "Failed to open file");
+        log.record(NotificationLevel::Warning, "ALSA underrun");
+        let texts: Vec<_> = log.entries().map(|e| (e.text.as_str(), e.count)).collect();
+        assert_eq!(texts, [("ALSA underrun", 1), ("Failed to open file", 1)]);
+    }
+}
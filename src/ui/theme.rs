@@ -0,0 +1,102 @@
+// Color themes - picked by name from UiConfig.theme
+// Render code asks the theme for colors instead of hardcoding them
+
+use ratatui::style::Color;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub name: &'static str,
+    pub primary: Color,      // headings, borders, section titles
+    pub accent: Color,       // active tab, current track, key hints
+    pub highlight_bg: Color, // selected row background
+    pub playing: Color,      // playback indicators while playing
+    pub paused: Color,       // playback indicators while paused
+    pub warning: Color,      // destructive actions and errors
+    pub text: Color,         // regular foreground text
+    pub muted: Color,        // secondary text, inactive items
+    pub background: Color,   // overlay backgrounds
+}
+
+impl Theme {
+    /// Theme names in the order the Settings tab cycles through them
+    pub const NAMES: [&'static str; 4] = ["default", "dark", "light", "solarized"];
+    
+    /// Look up a theme by config name, falling back to "default" for unknown names
+    pub fn from_name(name: &str) -> Self {
+        match name.to_lowercase().as_str() {
+            "dark" => Self::dark(),
+            "light" => Self::light(),
+            "solarized" => Self::solarized(),
+            _ => Self::default(),
+        }
+    }
+    
+    /// The theme after this one in `NAMES`, wrapping around
+    pub fn next(&self) -> Self {
+        let index = Self::NAMES.iter().position(|&n| n == self.name).unwrap_or(0);
+        Self::from_name(Self::NAMES[(index + 1) % Self::NAMES.len()])
+    }
+    
+    pub fn dark() -> Self {
+        Self {
+            name: "dark",
+            primary: Color::LightBlue,
+            accent: Color::LightMagenta,
+            highlight_bg: Color::Rgb(48, 48, 64),
+            playing: Color::LightGreen,
+            paused: Color::LightYellow,
+            warning: Color::LightRed,
+            text: Color::Gray,
+            muted: Color::DarkGray,
+            background: Color::Black,
+        }
+    }
+    
+    pub fn light() -> Self {
+        Self {
+            name: "light",
+            primary: Color::Blue,
+            accent: Color::Magenta,
+            highlight_bg: Color::Rgb(210, 210, 210),
+            playing: Color::Green,
+            paused: Color::Rgb(180, 120, 0),
+            warning: Color::Red,
+            text: Color::Black,
+            muted: Color::DarkGray,
+            background: Color::White,
+        }
+    }
+    
+    pub fn solarized() -> Self {
+        Self {
+            name: "solarized",
+            primary: Color::Rgb(38, 139, 210),  // blue
+            accent: Color::Rgb(181, 137, 0),    // yellow
+            highlight_bg: Color::Rgb(7, 54, 66), // base02
+            playing: Color::Rgb(133, 153, 0),   // green
+            paused: Color::Rgb(203, 75, 22),    // orange
+            warning: Color::Rgb(220, 50, 47),   // red
+            text: Color::Rgb(147, 161, 161),    // base1
+            muted: Color::Rgb(88, 110, 117),    // base01
+            background: Color::Rgb(0, 43, 54),  // base03
+        }
+    }
+}
+
+impl Default for Theme {
+    // The original hardcoded palette
+    fn default() -> Self {
+        Self {
+            name: "default",
+            primary: Color::Cyan,
+            accent: Color::Yellow,
+            highlight_bg: Color::DarkGray,
+            playing: Color::Green,
+            paused: Color::Yellow,
+            warning: Color::Red,
+            text: Color::White,
+            muted: Color::Gray,
+            background: Color::Black,
+        }
+    }
+}